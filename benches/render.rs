@@ -0,0 +1,49 @@
+//! Render throughput of the standard benchmark scenes in rays per second, run by `cargo bench`. Scenes render at the
+//! default seed and a fixed sample count, so each version traces the same rays as long as it renders the same image.
+//! Outside of cargo, `eden benchmark --throughput [--baseline CSV]` measures the same renders and compares them
+//! against a baseline of an earlier version. The cover scene of random spheres also renders with packet intersection
+//! of BVH leaves disabled, comparing SIMD packets against testing objects one by one.
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use eden::{
+    benchmark::{self, standard_scene, standard_settings, SCENES},
+    camera::AspectPolicy,
+};
+
+/// Resolution of benchmark renders.
+const RESOLUTION: (u32, u32) = (320, 180);
+
+/// Samples per pixel of benchmark renders.
+const SAMPLES: u32 = 4;
+
+fn render(c: &mut Criterion) {
+    let settings = standard_settings(SAMPLES);
+    let mut group = c.benchmark_group("render");
+    group.sample_size(10);
+    for name in SCENES {
+        let (scene, mut camera) = standard_scene(name).unwrap();
+        camera.set_resolution_override(Some(RESOLUTION), AspectPolicy::default());
+        let rays = benchmark::Throughput::count_rays(&camera, &scene, &settings);
+        group.throughput(Throughput::Elements(rays));
+        group.bench_function(name, |b| b.iter(|| camera.render(&scene, &settings)));
+    }
+    group.finish();
+}
+
+fn packets(c: &mut Criterion) {
+    let settings = standard_settings(SAMPLES);
+    let mut group = c.benchmark_group("packets");
+    group.sample_size(10);
+    let (mut scene, mut camera) = standard_scene("cover").unwrap();
+    camera.set_resolution_override(Some(RESOLUTION), AspectPolicy::default());
+    let rays = benchmark::Throughput::count_rays(&camera, &scene, &settings);
+    group.throughput(Throughput::Elements(rays));
+    for (name, packets) in [("packet", true), ("scalar", false)] {
+        scene.set_packet_intersection(packets);
+        group.bench_function(name, |b| b.iter(|| camera.render(&scene, &settings)));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, render, packets);
+criterion_main!(benches);
@@ -0,0 +1,510 @@
+use crate::{
+    base::{point::Point3f, shape::Shape, vector::Vector3f},
+    camera::Camera,
+    checkpoint::{self, CheckpointError, SequenceManifest},
+    expression::FRAME_RATE,
+    film::Film,
+    output::{self, ImageSink},
+    scene::Scene,
+    settings::RenderSettings,
+    tonemap::ToneMapping,
+};
+use std::{
+    f32::consts::PI,
+    fs, io,
+    ops::{Add, Mul, Sub},
+    path::Path,
+};
+
+/// File name of progress manifest of resumable sequence renders, in their directory.
+const MANIFEST_NAME: &str = "manifest.tsv";
+
+/// Interpolation of values between keyframes.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Interpolation {
+    /// Straight lines between keyframes, changing speed abruptly at each.
+    Linear,
+
+    /// Cubic curves through keyframes with tangents given by their neighbors (Catmull-Rom), e.g. for smooth
+    /// flythroughs.
+    #[default]
+    Smooth,
+}
+
+/// Camera placement at a keyframe.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CameraKey {
+    /// Point camera is looking from.
+    pub look_from: Point3f,
+
+    /// Point camera is looking at.
+    pub look_at: Point3f,
+
+    /// Vertical field of view in degrees.
+    pub vfov: f32,
+}
+
+impl CameraKey {
+    /// Current placement of camera.
+    pub fn of(camera: &Camera) -> Self {
+        CameraKey {
+            look_from: camera.look_from(),
+            look_at: camera.look_at(),
+            vfov: camera.vfov(),
+        }
+    }
+
+    /// Places camera.
+    fn apply(&self, camera: &mut Camera) {
+        camera.set_look_from(self.look_from);
+        camera.set_look_at(self.look_at);
+        camera.set_vfov(self.vfov);
+    }
+}
+
+/// Keyframed animation of camera placement and object positions, rendered as numbered image sequence. Before the
+/// first and after the last keyframe values hold still.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Animation {
+    /// Number of frames.
+    frames: u32,
+
+    /// Frames per second.
+    frame_rate: f32,
+
+    /// Interpolation between keyframes.
+    interpolation: Interpolation,
+
+    /// Camera keyframes with their time in seconds, by time.
+    camera_keys: Vec<(f32, CameraKey)>,
+
+    /// Center keyframes with their time in seconds by object index, by time.
+    object_keys: Vec<(usize, Vec<(f32, Point3f)>)>,
+}
+
+impl Animation {
+    /// Creates animation of given number of frames at the default frame rate, without keyframes.
+    pub fn new(frames: u32) -> Self {
+        assert!(frames > 0, "animation must have frames");
+        Animation {
+            frames,
+            frame_rate: FRAME_RATE,
+            interpolation: Interpolation::default(),
+            camera_keys: Vec::new(),
+            object_keys: Vec::new(),
+        }
+    }
+
+    /// Creates turntable animation: camera orbits once counter-clockwise around the point it looks at, about its
+    /// 'up' direction, in given number of frames. The last frame stops short of the first, so the sequence loops.
+    pub fn turntable(camera: &Camera, frames: u32) -> Self {
+        let mut animation = Animation::new(frames);
+        let key = CameraKey::of(camera);
+        let axis = camera.view_up().normalize();
+        let offset = key.look_from - key.look_at;
+        for frame in 0..frames {
+            let (sin, cos) = (2.0 * PI * frame as f32 / frames as f32).sin_cos();
+            let rotated =
+                cos * offset + sin * axis.cross(&offset) + (1.0 - cos) * axis.dot(&offset) * axis;
+            let look_from = key.look_at + rotated;
+            animation.add_camera_key(animation.time(frame), CameraKey { look_from, ..key });
+        }
+        animation
+    }
+
+    /// Number of frames.
+    pub fn frames(&self) -> u32 {
+        self.frames
+    }
+
+    /// Frames per second.
+    pub fn frame_rate(&self) -> f32 {
+        self.frame_rate
+    }
+
+    /// Sets frames per second.
+    pub fn set_frame_rate(&mut self, frame_rate: f32) {
+        assert!(frame_rate > 0.0);
+        self.frame_rate = frame_rate;
+    }
+
+    /// Interpolation between keyframes.
+    pub fn interpolation(&self) -> Interpolation {
+        self.interpolation
+    }
+
+    /// Sets interpolation between keyframes.
+    pub fn set_interpolation(&mut self, interpolation: Interpolation) {
+        self.interpolation = interpolation;
+    }
+
+    /// Time of frame in seconds.
+    pub fn time(&self, frame: u32) -> f32 {
+        frame as f32 / self.frame_rate
+    }
+
+    /// Adds camera keyframe at time in seconds, replacing one at the same time.
+    pub fn add_camera_key(&mut self, time: f32, key: CameraKey) {
+        insert_key(&mut self.camera_keys, time, key);
+    }
+
+    /// Adds keyframe of center position of object with given index at time in seconds, replacing one at the same
+    /// time. Objects are moved by `Shape::set_center`, thus instances move with all their prototype objects.
+    pub fn add_object_key(&mut self, object: usize, time: f32, center: Point3f) {
+        let index = match self.object_keys.iter().position(|(o, _)| *o == object) {
+            Some(index) => index,
+            None => {
+                self.object_keys.push((object, Vec::new()));
+                self.object_keys.len() - 1
+            }
+        };
+        insert_key(&mut self.object_keys[index].1, time, center);
+    }
+
+    /// Camera placement at time in seconds, none without camera keyframes.
+    pub fn camera_at(&self, time: f32) -> Option<CameraKey> {
+        let keys = |f: fn(&CameraKey) -> Vector3f| -> Vec<(f32, Vector3f)> {
+            self.camera_keys.iter().map(|(t, k)| (*t, f(k))).collect()
+        };
+        let look_from = interpolate(
+            &keys(|k| k.look_from - Point3f::default()),
+            time,
+            self.interpolation,
+        )?;
+        let look_at = interpolate(
+            &keys(|k| k.look_at - Point3f::default()),
+            time,
+            self.interpolation,
+        )?;
+        let vfovs: Vec<(f32, f32)> = self.camera_keys.iter().map(|(t, k)| (*t, k.vfov)).collect();
+        Some(CameraKey {
+            look_from: Point3f::default() + look_from,
+            look_at: Point3f::default() + look_at,
+            vfov: interpolate(&vfovs, time, self.interpolation)?,
+        })
+    }
+
+    /// Places camera and moves objects of scene as animated at frame. Animated spheres also get their motion until
+    /// the next frame, blurring them while the camera shutter is open.
+    pub fn apply(&self, frame: u32, camera: &mut Camera, scene: &mut Scene) {
+        let time = self.time(frame);
+        if let Some(key) = self.camera_at(time) {
+            key.apply(camera);
+        }
+        for (object, keys) in &self.object_keys {
+            let centers: Vec<(f32, Vector3f)> = keys
+                .iter()
+                .map(|(t, c)| (*t, *c - Point3f::default()))
+                .collect();
+            if let Some(center) = interpolate(&centers, time, self.interpolation) {
+                let mut shape = scene.objects()[*object].clone();
+                shape.set_center(Point3f::default() + center);
+                if let Shape::Sphere(sphere) = &mut shape {
+                    let next = self.time(frame + 1);
+                    sphere.set_motion(
+                        interpolate(&centers, next, self.interpolation).unwrap() - center,
+                    );
+                }
+                scene.set_object(*object, shape);
+            }
+        }
+    }
+
+    /// Renders all frames with settings, handing each to sink with its frame number.
+    pub fn render<F>(
+        &self,
+        camera: &mut Camera,
+        scene: &mut Scene,
+        settings: &RenderSettings,
+        mut sink: F,
+    ) -> io::Result<()>
+    where
+        F: FnMut(u32, &Film) -> io::Result<()>,
+    {
+        for frame in 0..self.frames {
+            self.apply(frame, camera, scene);
+            sink(frame, &camera.render(scene, settings))?;
+        }
+        Ok(())
+    }
+
+    /// Renders all frames with settings into directory as numbered image files from `frame_0001` on, in given
+    /// format extension. Returns paths of written files.
+    pub fn render_sequence(
+        &self,
+        camera: &mut Camera,
+        scene: &mut Scene,
+        settings: &RenderSettings,
+        directory: &Path,
+        extension: &str,
+        tone_mapping: ToneMapping,
+    ) -> io::Result<Vec<String>> {
+        let mut paths = Vec::new();
+        self.render(camera, scene, settings, |frame, film| {
+            let name = format!("frame_{:04}.{}", frame + 1, extension);
+            let path = directory.join(name).to_string_lossy().into_owned();
+            output::file_pipeline(&path, tone_mapping)?.write_image(film)?;
+            paths.push(path);
+            Ok(())
+        })?;
+        Ok(paths)
+    }
+
+    /// Renders all frames like `render_sequence`, but resumable after an interruption. Progress is tracked in a
+    /// manifest in the directory, and each frame accumulates samples in passes of given samples per pixel in a
+    /// checkpoint file next to its image, removed once the image is written. Frames already written are skipped, an
+    /// unfinished frame continues from its checkpoint.
+    #[allow(clippy::too_many_arguments)]
+    pub fn render_sequence_resumable(
+        &self,
+        camera: &mut Camera,
+        scene: &mut Scene,
+        settings: &RenderSettings,
+        directory: &Path,
+        extension: &str,
+        tone_mapping: ToneMapping,
+        samples_per_pass: u32,
+    ) -> Result<Vec<String>, CheckpointError> {
+        let manifest_path = directory.join(MANIFEST_NAME);
+        let mut manifest = SequenceManifest::load(&manifest_path, self.frames)?;
+        let mut paths = Vec::new();
+        for frame in 0..self.frames {
+            let file = |extension: &str| {
+                let name = format!("frame_{:04}.{}", frame + 1, extension);
+                directory.join(name).to_string_lossy().into_owned()
+            };
+            let path = file(extension);
+            if !manifest.is_done(frame) {
+                let checkpoint = file("checkpoint");
+                manifest.start(frame, &checkpoint);
+                manifest.save(&manifest_path)?;
+                self.apply(frame, camera, scene);
+                let film = checkpoint::render_checkpointed(
+                    camera,
+                    scene,
+                    settings,
+                    Path::new(&checkpoint),
+                    samples_per_pass,
+                    |_| {},
+                )?;
+                output::file_pipeline(&path, tone_mapping)?.write_image(&film)?;
+                manifest.finish(frame, &path);
+                manifest.save(&manifest_path)?;
+                fs::remove_file(&checkpoint)?;
+            }
+            paths.push(path);
+        }
+        Ok(paths)
+    }
+}
+
+/// Inserts value into keyframes sorted by time, replacing one at the same time.
+fn insert_key<T>(keys: &mut Vec<(f32, T)>, time: f32, value: T) {
+    assert!(time.is_finite(), "keyframe time must be finite");
+    match keys.binary_search_by(|(t, _)| t.total_cmp(&time)) {
+        Ok(index) => keys[index].1 = value,
+        Err(index) => keys.insert(index, (time, value)),
+    }
+}
+
+/// Value of keyframes sorted by time at given time, none without keyframes.
+fn interpolate<T>(keys: &[(f32, T)], time: f32, interpolation: Interpolation) -> Option<T>
+where
+    T: Copy + Add<Output = T> + Sub<Output = T> + Mul<f32, Output = T>,
+{
+    let (first, last) = (keys.first()?, keys.last()?);
+    if time <= first.0 {
+        return Some(first.1);
+    }
+    if time >= last.0 {
+        return Some(last.1);
+    }
+
+    // Segment containing time, with parameter s in [0,1).
+    let i = keys.partition_point(|(t, _)| *t <= time) - 1;
+    let ((t0, p0), (t1, p1)) = (keys[i], keys[i + 1]);
+    let dt = t1 - t0;
+    let s = (time - t0) / dt;
+    match interpolation {
+        Interpolation::Linear => Some(p0 + (p1 - p0) * s),
+        Interpolation::Smooth => {
+            // Cubic Hermite curve with tangents by finite differences of neighbors, one-sided at the ends.
+            let tangent = |j: usize| {
+                let (a, b) = (keys[j.saturating_sub(1)], keys[(j + 1).min(keys.len() - 1)]);
+                (b.1 - a.1) * (dt / (b.0 - a.0))
+            };
+            let (m0, m1) = (tangent(i), tangent(i + 1));
+            let (s2, s3) = (s * s, s * s * s);
+            let h00 = 2.0 * s3 - 3.0 * s2 + 1.0;
+            let h10 = s3 - 2.0 * s2 + s;
+            let h01 = -2.0 * s3 + 3.0 * s2;
+            let h11 = s3 - s2;
+            Some(p0 * h00 + m0 * h10 + p1 * h01 + m1 * h11)
+        }
+    }
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        base::{color::Color3f, material::Material, shape::Shape},
+        checkpoint::Accumulation,
+        materials::lambert::Lambert,
+        shapes::sphere::Sphere,
+    };
+    use std::{env, fs};
+
+    #[test]
+    fn interpolate() {
+        let keys = [(0.0, 0.0), (1.0, 1.0), (3.0, 5.0)];
+        for interpolation in [Interpolation::Linear, Interpolation::Smooth] {
+            // Keyframes are hit, values hold outside.
+            for (t, value) in keys {
+                assert_eq!(super::interpolate(&keys, t, interpolation), Some(value));
+            }
+            assert_eq!(super::interpolate(&keys, -1.0, interpolation), Some(0.0));
+            assert_eq!(super::interpolate(&keys, 4.0, interpolation), Some(5.0));
+        }
+        assert_eq!(
+            super::interpolate(&keys, 2.0, Interpolation::Linear),
+            Some(3.0)
+        );
+
+        // Smooth curves keep the speed steady through keyframes.
+        let smooth = |t| super::interpolate(&keys, t, Interpolation::Smooth).unwrap();
+        let (before, after) = (smooth(1.0) - smooth(0.99), smooth(1.01) - smooth(1.0));
+        assert!((before - after).abs() < 1e-3);
+        assert_eq!(
+            super::interpolate::<f32>(&[], 0.0, Interpolation::Smooth),
+            None
+        );
+    }
+
+    #[test]
+    fn turntable() {
+        let mut camera = Camera::new(4, 4);
+        camera.set_look_from(Point3f::new(0.0, 1.0, 5.0));
+        let animation = Animation::turntable(&camera, 4);
+        let look_from = |frame| {
+            animation
+                .camera_at(animation.time(frame))
+                .unwrap()
+                .look_from
+        };
+        assert!((look_from(0) - Point3f::new(0.0, 1.0, 5.0)).length() < 1e-5);
+        assert!((look_from(1) - Point3f::new(5.0, 1.0, 0.0)).length() < 1e-5);
+        assert!((look_from(2) - Point3f::new(0.0, 1.0, -5.0)).length() < 1e-5);
+    }
+
+    #[test]
+    fn render_sequence() {
+        let mut scene = Scene::new();
+        scene.add(Shape::Sphere(Sphere::new(
+            Point3f::new(0.0, 0.0, -1.0),
+            0.5,
+            Material::Lambert(Lambert::new(Color3f::new(0.5, 0.5, 0.5))),
+        )));
+        let mut camera = Camera::new(4, 4);
+
+        // Sphere moves linearly from left to right while camera zooms in.
+        let mut animation = Animation::new(3);
+        animation.set_frame_rate(1.0);
+        animation.set_interpolation(Interpolation::Linear);
+        animation.add_object_key(0, 0.0, Point3f::new(-1.0, 0.0, -1.0));
+        animation.add_object_key(0, 2.0, Point3f::new(1.0, 0.0, -1.0));
+        let mut key = CameraKey::of(&camera);
+        animation.add_camera_key(0.0, key);
+        key.vfov = 40.0;
+        animation.add_camera_key(2.0, key);
+
+        let directory = env::temp_dir().join(format!("eden-animation-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let paths = animation
+            .render_sequence(
+                &mut camera,
+                &mut scene,
+                &RenderSettings::new(1),
+                &directory,
+                "pfm",
+                ToneMapping::default(),
+            )
+            .unwrap();
+        assert_eq!(paths.len(), 3);
+        assert!(paths[0].ends_with("frame_0001.pfm"));
+        assert!(paths.iter().all(|p| Path::new(p).exists()));
+        assert_eq!(scene.objects()[0].center(), Point3f::new(1.0, 0.0, -1.0));
+        assert_eq!(camera.vfov(), 40.0);
+        animation.apply(1, &mut camera, &mut scene);
+        assert_eq!(scene.objects()[0].center(), Point3f::new(0.0, 0.0, -1.0));
+        assert_eq!(camera.vfov(), 65.0);
+
+        // Spheres move until next frame, last frame holds still.
+        let Shape::Sphere(sphere) = &scene.objects()[0] else {
+            unreachable!()
+        };
+        assert_eq!(sphere.motion(), Vector3f::new(1.0, 0.0, 0.0));
+        animation.apply(2, &mut camera, &mut scene);
+        let Shape::Sphere(sphere) = &scene.objects()[0] else {
+            unreachable!()
+        };
+        assert!(!sphere.is_moving());
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn render_sequence_resumable() {
+        let mut scene = Scene::new();
+        scene.add(Shape::Sphere(Sphere::new(
+            Point3f::new(0.0, 0.0, -1.0),
+            0.5,
+            Material::Lambert(Lambert::new(Color3f::new(0.5, 0.5, 0.5))),
+        )));
+        let mut camera = Camera::new(4, 4);
+        let mut animation = Animation::new(3);
+        animation.set_frame_rate(1.0);
+        animation.add_object_key(0, 0.0, Point3f::new(-1.0, 0.0, -1.0));
+        animation.add_object_key(0, 2.0, Point3f::new(1.0, 0.0, -1.0));
+        let settings = RenderSettings::new(8);
+        let tone_mapping = ToneMapping::default();
+        let directory = env::temp_dir().join(format!("eden-resumable-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let render = |camera: &mut Camera, scene: &mut Scene| {
+            animation
+                .render_sequence_resumable(
+                    camera,
+                    scene,
+                    &settings,
+                    &directory,
+                    "pfm",
+                    tone_mapping,
+                    3,
+                )
+                .unwrap()
+        };
+        let paths = render(&mut camera, &mut scene);
+        let images: Vec<Vec<u8>> = paths.iter().map(|p| fs::read(p).unwrap()).collect();
+        assert!(!directory.join("frame_0001.checkpoint").exists());
+
+        // Interrupted while rendering second frame, with a pass accumulated.
+        let mut manifest = SequenceManifest::load(&directory.join(MANIFEST_NAME), 3).unwrap();
+        let checkpoint = directory.join("frame_0002.checkpoint");
+        manifest.start(1, &checkpoint.to_string_lossy());
+        manifest.save(&directory.join(MANIFEST_NAME)).unwrap();
+        fs::remove_file(&paths[1]).unwrap();
+        fs::remove_file(&paths[2]).unwrap();
+        animation.apply(1, &mut camera, &mut scene);
+        let mut accumulation = Accumulation::new(&camera, &scene, &settings);
+        camera.render_accumulated(&scene, &settings, &mut accumulation, 3);
+        accumulation.save(&checkpoint).unwrap();
+
+        // Finished frames are kept, the others render to the same images.
+        fs::write(&paths[0], b"kept").unwrap();
+        assert_eq!(render(&mut camera, &mut scene), paths);
+        assert_eq!(fs::read(&paths[0]).unwrap(), b"kept");
+        assert_eq!(fs::read(&paths[1]).unwrap(), images[1]);
+        assert_eq!(fs::read(&paths[2]).unwrap(), images[2]);
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
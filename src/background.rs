@@ -0,0 +1,66 @@
+use crate::{
+    base::{color::Color3f, ray::Ray},
+    sky::PhysicalSky,
+};
+
+/// Background radiance for rays escaping the scene.
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq)]
+pub enum Background {
+    /// Sky gradient based on y component of ray direction.
+    #[default]
+    Sky,
+
+    /// Uniform radiance in all directions.
+    Uniform(Color3f),
+
+    /// Vertical gradient from bottom color looking down to top color looking up.
+    Gradient { bottom: Color3f, top: Color3f },
+
+    /// Physically based sky with sun disk, sampled explicitly as a light.
+    PhysicalSky(PhysicalSky),
+}
+
+impl Background {
+    /// Background color seen along ray.
+    pub fn color(&self, ray: Ray) -> Color3f {
+        match self {
+            Background::Sky => {
+                let normalized_direction = ray.direction().normalize();
+                let a = 0.5 * (normalized_direction.y() + 1.0);
+                (1.0 - a) * Color3f::white() + a * Color3f::new(0.5, 0.7, 1.0)
+            }
+            Background::Uniform(c) => *c,
+            Background::Gradient { bottom, top } => {
+                let a = 0.5 * (ray.direction().normalize().y() + 1.0);
+                (1.0 - a) * *bottom + a * *top
+            }
+            Background::PhysicalSky(sky) => sky.color(ray.direction()),
+        }
+    }
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::{point::Point3f, vector::Vector3f};
+
+    #[test]
+    fn color() {
+        let up = Ray::new(Point3f::default(), Vector3f::new(0.0, 2.0, 0.0));
+        let down = Ray::new(Point3f::default(), Vector3f::new(0.0, -1.0, 0.0));
+        assert_eq!(Background::Sky.color(up), Color3f::new(0.5, 0.7, 1.0));
+        assert_eq!(Background::Sky.color(down), Color3f::white());
+
+        let c = Color3f::new(0.1, 0.2, 0.3);
+        assert_eq!(Background::Uniform(c).color(up), c);
+        assert_eq!(Background::Uniform(c).color(down), c);
+
+        let gradient = Background::Gradient {
+            bottom: Color3f::white(),
+            top: c,
+        };
+        assert_eq!(gradient.color(up), c);
+        assert_eq!(gradient.color(down), Color3f::white());
+    }
+}
@@ -1,7 +1,18 @@
+pub mod aabb;
 pub mod color;
+pub(crate) mod hash;
 pub mod interval;
+pub mod light;
 pub mod material;
+pub mod onb;
+pub mod orientation;
+pub mod packet;
 pub mod point;
+pub(crate) mod polynomial;
 pub mod ray;
+pub mod sampling;
 pub mod shape;
+pub mod transform;
+pub mod uv_transform;
 pub mod vector;
+pub mod winding;
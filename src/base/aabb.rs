@@ -0,0 +1,168 @@
+use crate::base::{interval::Interval, point::Point3f, ray::Ray, vector::Vector3f};
+
+/// Axis-aligned bounding box in 3-dim space.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    /// Corner with smallest coordinates.
+    min: Point3f,
+
+    /// Corner with largest coordinates.
+    max: Point3f,
+}
+
+impl Aabb {
+    /// Creates bounding box spanned by two arbitrary corner points.
+    pub fn new(a: Point3f, b: Point3f) -> Self {
+        Aabb {
+            min: Point3f::new(a.x().min(b.x()), a.y().min(b.y()), a.z().min(b.z())),
+            max: Point3f::new(a.x().max(b.x()), a.y().max(b.y()), a.z().max(b.z())),
+        }
+    }
+
+    /// Corner with smallest coordinates.
+    pub fn min(&self) -> Point3f {
+        self.min
+    }
+
+    /// Corner with largest coordinates.
+    pub fn max(&self) -> Point3f {
+        self.max
+    }
+
+    /// Extent along each axis.
+    pub fn size(&self) -> Vector3f {
+        self.max - self.min
+    }
+
+    /// Total area of all six faces.
+    pub fn surface_area(&self) -> f32 {
+        let s = self.size();
+        2.0 * (s.x() * s.y() + s.y() * s.z() + s.z() * s.x())
+    }
+
+    /// Smallest bounding box enclosing both boxes.
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        Aabb::new(
+            Aabb::new(self.min, other.min).min,
+            Aabb::new(self.max, other.max).max,
+        )
+    }
+
+    /// Largest bounding box enclosed by both boxes. Returns none if boxes are disjoint.
+    pub fn intersection(&self, other: &Aabb) -> Option<Aabb> {
+        let min = Aabb::new(self.min, other.min).max;
+        let max = Aabb::new(self.max, other.max).min;
+        if min.x() > max.x() || min.y() > max.y() || min.z() > max.z() {
+            return None;
+        }
+        Some(Aabb::new(min, max))
+    }
+
+    /// Part of t-interval where ray lies inside box, by slab test. Returns none if ray misses box within interval.
+    /// Flat boxes are hit too, e.g. bounds of planar shapes.
+    pub fn hit(&self, ray: Ray, ray_t: Interval) -> Option<Interval> {
+        let (origin, direction) = (ray.origin(), ray.direction());
+        let (mut start, mut end) = (ray_t.start(), ray_t.end());
+        for (o, d, min, max) in [
+            (origin.x(), direction.x(), self.min.x(), self.max.x()),
+            (origin.y(), direction.y(), self.min.y(), self.max.y()),
+            (origin.z(), direction.z(), self.min.z(), self.max.z()),
+        ] {
+            // Division by zero gives infinite slab distances, thus rays parallel to a slab hit it if inside.
+            let inverse = 1.0 / d;
+            let (mut t0, mut t1) = ((min - o) * inverse, (max - o) * inverse);
+            if inverse < 0.0 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            start = start.max(t0);
+            end = end.min(t1);
+            if end < start {
+                return None;
+            }
+        }
+        Some(Interval::new(start, end))
+    }
+
+    /// All eight corner points.
+    pub fn corners(&self) -> [Point3f; 8] {
+        let (a, b) = (self.min, self.max);
+        [
+            Point3f::new(a.x(), a.y(), a.z()),
+            Point3f::new(b.x(), a.y(), a.z()),
+            Point3f::new(a.x(), b.y(), a.z()),
+            Point3f::new(b.x(), b.y(), a.z()),
+            Point3f::new(a.x(), a.y(), b.z()),
+            Point3f::new(b.x(), a.y(), b.z()),
+            Point3f::new(a.x(), b.y(), b.z()),
+            Point3f::new(b.x(), b.y(), b.z()),
+        ]
+    }
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new() {
+        let a = Aabb::new(Point3f::new(1.0, -2.0, 3.0), Point3f::new(-1.0, 2.0, 0.0));
+        assert_eq!(a.min(), Point3f::new(-1.0, -2.0, 0.0));
+        assert_eq!(a.max(), Point3f::new(1.0, 2.0, 3.0));
+        assert_eq!(a.size(), Vector3f::new(2.0, 4.0, 3.0));
+        assert_eq!(a.surface_area(), 52.0);
+        assert!(a.corners().iter().all(|&c| Aabb::new(c, c).union(&a) == a));
+    }
+
+    #[test]
+    fn union() {
+        let a = Aabb::new(Point3f::default(), Point3f::new(1.0, 1.0, 1.0));
+        let b = Aabb::new(Point3f::new(2.0, -1.0, 0.5), Point3f::new(3.0, 0.0, 0.5));
+        let c = a.union(&b);
+        assert_eq!(c.min(), Point3f::new(0.0, -1.0, 0.0));
+        assert_eq!(c.max(), Point3f::new(3.0, 1.0, 1.0));
+        assert_eq!(c, b.union(&a));
+    }
+
+    #[test]
+    fn intersection() {
+        let a = Aabb::new(Point3f::default(), Point3f::new(2.0, 2.0, 2.0));
+        let b = Aabb::new(Point3f::new(1.0, -1.0, 0.5), Point3f::new(3.0, 1.0, 1.5));
+        let c = a.intersection(&b).unwrap();
+        assert_eq!(c.min(), Point3f::new(1.0, 0.0, 0.5));
+        assert_eq!(c.max(), Point3f::new(2.0, 1.0, 1.5));
+        assert_eq!(Some(c), b.intersection(&a));
+        let d = Aabb::new(Point3f::new(3.0, 0.0, 0.0), Point3f::new(4.0, 1.0, 1.0));
+        assert_eq!(a.intersection(&d), None);
+    }
+
+    #[test]
+    fn hit() {
+        let a = Aabb::new(Point3f::new(-1.0, -1.0, -1.0), Point3f::new(1.0, 1.0, 1.0));
+        let all = Interval::new(0.0, f32::INFINITY);
+
+        // Entry and exit along ray, clipped to interval.
+        let ray = Ray::new(Point3f::new(-3.0, 0.0, 0.0), Vector3f::new(1.0, 0.0, 0.0));
+        assert_eq!(a.hit(ray, all), Some(Interval::new(2.0, 4.0)));
+        assert_eq!(
+            a.hit(ray, Interval::new(3.0, 10.0)),
+            Some(Interval::new(3.0, 4.0))
+        );
+        assert_eq!(a.hit(ray, Interval::new(0.0, 1.0)), None);
+        let inside = Ray::new(Point3f::default(), Vector3f::new(0.0, -2.0, 0.0));
+        assert_eq!(a.hit(inside, all), Some(Interval::new(0.0, 0.5)));
+
+        // Misses beside and behind.
+        let beside = Ray::new(Point3f::new(-3.0, 1.5, 0.0), Vector3f::new(1.0, 0.0, 0.0));
+        assert_eq!(a.hit(beside, all), None);
+        let diagonal = Ray::new(Point3f::new(-3.0, 0.0, 0.0), Vector3f::new(1.0, 1.0, 0.0));
+        assert_eq!(a.hit(diagonal, all), None);
+        let behind = Ray::new(Point3f::new(3.0, 0.0, 0.0), Vector3f::new(1.0, 0.0, 0.0));
+        assert_eq!(a.hit(behind, all), None);
+
+        // Flat box.
+        let flat = Aabb::new(Point3f::new(-1.0, 0.0, -1.0), Point3f::new(1.0, 0.0, 1.0));
+        let down = Ray::new(Point3f::new(0.5, 2.0, 0.5), Vector3f::new(0.0, -1.0, 0.0));
+        assert_eq!(flat.hit(down, all), Some(Interval::new(2.0, 2.0)));
+    }
+}
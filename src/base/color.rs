@@ -1,5 +1,7 @@
-use crate::base::vector::Vector;
+use crate::{base::vector::Vector, tonemap::srgb_decode};
+use half::f16;
 use num_traits::Float;
+use std::hash::{Hash, Hasher};
 
 /// Abstract color with N components.
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -12,7 +14,7 @@ impl<T: Float> Color<T, 3> {
     /// Creates RGB color from values.
     pub fn new(r: T, g: T, b: T) -> Self {
         Color {
-            values: Vector::new(r, g, b),
+            values: Vector::<T, 3>::new(r, g, b),
         }
     }
 
@@ -40,6 +42,111 @@ impl<T: Float> Color<T, 3> {
     pub fn white() -> Self {
         Color::new(T::one(), T::one(), T::one())
     }
+
+    /// Relative luminance (Rec. 709 primaries).
+    pub fn luminance(&self) -> T {
+        let weight = |w: f64| T::from(w).unwrap();
+        weight(0.2126) * self.r() + weight(0.7152) * self.g() + weight(0.0722) * self.b()
+    }
+
+    /// Converts color to other float representation (out of range values become infinite).
+    pub fn cast<U: Float>(&self) -> Color<U, 3> {
+        let cast = |x: T| U::from(x).unwrap();
+        Color::new(cast(self.r()), cast(self.g()), cast(self.b()))
+    }
+
+    /// Color with components clamped to [min,max].
+    pub fn clamp(&self, min: T, max: T) -> Self {
+        let clamp = |x: T| x.max(min).min(max);
+        Color::new(clamp(self.r()), clamp(self.g()), clamp(self.b()))
+    }
+
+    /// Linear interpolation from color (t=0) to other (t=1).
+    pub fn lerp(&self, other: Self, t: T) -> Self {
+        *self + (other - *self) * t
+    }
+
+    /// Parses color from hex notation `#RRGGBB` or `#RGB` (leading `#` optional), e.g. as picked in image editors.
+    /// Components are sRGB encoded, thus they are decoded to linear values. None if malformed.
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        let digits = hex.strip_prefix('#').unwrap_or(hex);
+        if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        let (width, scale) = match digits.len() {
+            3 => (1, 15.0),
+            6 => (2, 255.0),
+            _ => return None,
+        };
+        let component = |i: usize| {
+            let value = u8::from_str_radix(&digits[i * width..(i + 1) * width], 16).unwrap();
+            T::from(srgb_decode(value as f32 / scale)).unwrap()
+        };
+        Some(Color::new(component(0), component(1), component(2)))
+    }
+
+    /// Creates color from hue in degrees (wrapping around, 0 for red, 120 for green, 240 for blue), saturation and
+    /// value in [0,1], e.g. to pick colors around the color wheel. Non-finite hues count as red.
+    pub fn from_hsv(hue: T, saturation: T, value: T) -> Self {
+        let constant = |x: f64| T::from(x).unwrap();
+        let sector = wrap_sector(hue / constant(60.0));
+        let chroma = value * saturation;
+        let x = chroma * (T::one() - ((sector % constant(2.0)) - T::one()).abs());
+        let (r, g, b) = match sector.to_u8().unwrap() {
+            0 => (chroma, x, T::zero()),
+            1 => (x, chroma, T::zero()),
+            2 => (T::zero(), chroma, x),
+            3 => (T::zero(), x, chroma),
+            4 => (x, T::zero(), chroma),
+            _ => (chroma, T::zero(), x),
+        };
+        let m = value - chroma;
+        Color::new(r + m, g + m, b + m)
+    }
+
+    /// Hue in degrees in [0,360), saturation and value of color, see `from_hsv`. Hue of grays is zero.
+    pub fn to_hsv(&self) -> (T, T, T) {
+        let constant = |x: f64| T::from(x).unwrap();
+        let (r, g, b) = (self.r(), self.g(), self.b());
+        let max = r.max(g).max(b);
+        let chroma = max - r.min(g).min(b);
+        let sector = if chroma <= T::zero() {
+            T::zero()
+        } else if max == r {
+            wrap_sector((g - b) / chroma)
+        } else if max == g {
+            (b - r) / chroma + constant(2.0)
+        } else {
+            (r - g) / chroma + constant(4.0)
+        };
+        let saturation = if max <= T::zero() {
+            T::zero()
+        } else {
+            chroma / max
+        };
+        (sector * constant(60.0), saturation, max)
+    }
+}
+
+/// Hue sector (hue over 60 degrees) wrapped into [0,6), zero if not finite.
+fn wrap_sector<T: Float>(sector: T) -> T {
+    if !sector.is_finite() {
+        return T::zero();
+    }
+    let six = T::from(6.0).unwrap();
+    let wrapped = sector % six;
+    if wrapped < T::zero() {
+        wrapped + six
+    } else {
+        wrapped
+    }
+}
+
+impl<T: Float, const N: usize> Hash for Color<T, N> {
+    /// Hashes values by value.
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.values.hash(state);
+    }
 }
 
 /// Helper macro for binary operator overloading.
@@ -140,6 +247,65 @@ impl_assign_op!(DivAssign div_assign /);
 /// RGB color represented by single precision floats.
 pub type Color3f = Color<f32, 3>;
 
+impl Color3f {
+    /// Color of blackbody radiator at temperature in kelvin, e.g. about 2700 for tungsten bulbs and 6500 for
+    /// overcast daylight, with luminance one, so it scales to the desired brightness. Planck's law is integrated
+    /// over the visible spectrum against the CIE 1931 color matching functions, in their multi-lobe Gaussian fit by
+    /// Wyman et al., and mapped to linear Rec. 709 RGB. Components outside of the gamut, like blue of candlelight,
+    /// are clipped to zero.
+    pub fn blackbody(temperature: f32) -> Self {
+        assert!(temperature > 0.0, "blackbody temperature must be positive");
+        let mut xyz = [0.0; 3];
+        for step in 0..=94 {
+            // Relative spectral radiance by Planck's law, at wavelength in nanometers (second radiation constant
+            // in nm·K).
+            let wavelength = 360.0 + 5.0 * step as f64;
+            let radiance = 1.0
+                / (wavelength.powi(5)
+                    * ((1.4388e7 / (wavelength * temperature as f64)).exp() - 1.0));
+            for (sum, matching) in xyz.iter_mut().zip(color_matching(wavelength)) {
+                *sum += radiance * matching;
+            }
+        }
+        let color = xyz_to_rgb(xyz);
+        color / color.luminance()
+    }
+
+    /// Color of monochromatic light at wavelength in nanometers, by the CIE 1931 color matching functions like
+    /// `blackbody`, in linear Rec. 709 RGB with components outside of the gamut clipped to zero. Luminance peaks
+    /// at one near 555 nm.
+    pub fn from_wavelength(wavelength: f32) -> Self {
+        xyz_to_rgb(color_matching(wavelength as f64))
+    }
+}
+
+/// CIE 1931 color matching functions x̄, ȳ, z̄ at wavelength in nanometers, by the multi-lobe Gaussian fit of Wyman
+/// et al.
+fn color_matching(wavelength: f64) -> [f64; 3] {
+    let lobe = |mean: f64, below: f64, above: f64| {
+        let sigma = if wavelength < mean { below } else { above };
+        (-0.5 * ((wavelength - mean) / sigma).powi(2)).exp()
+    };
+    [
+        1.056 * lobe(599.8, 37.9, 31.0) + 0.362 * lobe(442.0, 16.0, 26.7)
+            - 0.065 * lobe(501.1, 20.4, 26.2),
+        0.821 * lobe(568.8, 46.9, 40.5) + 0.286 * lobe(530.9, 16.3, 31.1),
+        1.217 * lobe(437.0, 11.8, 36.0) + 0.681 * lobe(459.0, 26.0, 13.8),
+    ]
+}
+
+/// Linear Rec. 709 RGB color of CIE XYZ tristimulus values, components outside of the gamut clipped to zero.
+fn xyz_to_rgb([x, y, z]: [f64; 3]) -> Color3f {
+    Color3f::new(
+        (3.2406 * x - 1.5372 * y - 0.4986 * z).max(0.0) as f32,
+        (-0.9689 * x + 1.8758 * y + 0.0415 * z).max(0.0) as f32,
+        (0.0557 * x - 0.2040 * y + 1.0570 * z).max(0.0) as f32,
+    )
+}
+
+/// RGB color represented by half precision floats.
+pub type Color3h = Color<f16, 3>;
+
 /// Unit tests.
 #[cfg(test)]
 mod tests {
@@ -182,4 +348,99 @@ mod tests {
         v /= b;
         assert_eq!(v, a);
     }
+
+    #[test]
+    fn components() {
+        let a = Color3f::new(-1.0, 0.5, 2.0);
+        assert_eq!(a.clamp(0.0, 1.0), Color3f::new(0.0, 0.5, 1.0));
+        assert_eq!(
+            a.lerp(Color3f::new(1.0, 1.5, 0.0), 0.25),
+            Color3f::new(-0.5, 0.75, 1.5)
+        );
+        assert_eq!(Color3f::white().luminance(), 1.0);
+
+        // Hex colors are sRGB encoded.
+        assert_eq!(
+            Color3f::from_hex("#ff0080"),
+            Some(Color3f::new(1.0, 0.0, srgb_decode(128.0 / 255.0)))
+        );
+        assert_eq!(Color3f::from_hex("FFF"), Some(Color3f::white()));
+        assert_eq!(Color3h::from_hex("#000"), Some(Color3h::black()));
+        for hex in ["", "#ff00", "#ff00zz", "#+f00ff", "#ß0"] {
+            assert_eq!(Color3f::from_hex(hex), None, "{}", hex);
+        }
+    }
+
+    #[test]
+    fn hsv() {
+        for (hsv, rgb) in [
+            ((0.0, 1.0, 1.0), (1.0, 0.0, 0.0)),
+            ((120.0, 1.0, 1.0), (0.0, 1.0, 0.0)),
+            ((240.0, 1.0, 0.5), (0.0, 0.0, 0.5)),
+            ((300.0, 0.5, 1.0), (1.0, 0.5, 1.0)),
+            ((30.0, 1.0, 1.0), (1.0, 0.5, 0.0)),
+            ((0.0, 0.0, 0.25), (0.25, 0.25, 0.25)),
+        ] {
+            let color = Color3f::new(rgb.0, rgb.1, rgb.2);
+            assert_eq!(Color3f::from_hsv(hsv.0, hsv.1, hsv.2), color);
+            assert_eq!(color.to_hsv(), hsv);
+        }
+        assert_eq!(
+            Color3f::from_hsv(-120.0, 1.0, 1.0),
+            Color3f::from_hsv(240.0, 1.0, 1.0)
+        );
+        for hue in [f32::NAN, f32::INFINITY, f32::NEG_INFINITY] {
+            assert_eq!(
+                Color3f::from_hsv(hue, 1.0, 1.0),
+                Color3f::new(1.0, 0.0, 0.0)
+            );
+        }
+        assert_eq!(Color3f::black().to_hsv(), (0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn blackbody() {
+        // Warm light is reddish, hot light bluish, daylight about white.
+        for temperature in [1000.0, 2700.0, 6500.0, 20000.0] {
+            let color = Color3f::blackbody(temperature);
+            assert!((color.luminance() - 1.0).abs() < 1e-5);
+        }
+        let tungsten = Color3f::blackbody(3200.0);
+        assert!(tungsten.r() > tungsten.g() && tungsten.g() > tungsten.b());
+        let daylight = Color3f::blackbody(6500.0);
+        for channel in [daylight.r(), daylight.g(), daylight.b()] {
+            assert!((channel - 1.0).abs() < 0.1, "{:?}", daylight);
+        }
+        let sky = Color3f::blackbody(15000.0);
+        assert!(sky.b() > sky.g() && sky.g() > sky.r());
+        assert_eq!(Color3f::blackbody(1000.0).b(), 0.0);
+    }
+
+    #[test]
+    fn from_wavelength() {
+        let blue = Color3f::from_wavelength(450.0);
+        assert!(blue.b() > blue.g() && blue.b() > blue.r());
+        let green = Color3f::from_wavelength(530.0);
+        assert!(green.g() > green.r() && green.g() > green.b());
+        let red = Color3f::from_wavelength(650.0);
+        assert!(red.r() > red.g() && red.r() > red.b());
+        assert!((Color3f::from_wavelength(555.0).luminance() - 1.0).abs() < 0.05);
+        assert!(Color3f::from_wavelength(900.0).luminance() < 1e-6);
+    }
+
+    #[test]
+    fn cast() {
+        let a = Color3f::new(0.5, 1.0, 2.0);
+        let b = a.cast::<f16>();
+        assert_eq!(
+            b,
+            Color3h::new(f16::from_f32(0.5), f16::ONE, f16::from_f32(2.0))
+        );
+        assert_eq!(b.cast::<f32>(), a);
+
+        let c = Color3f::new(0.1, 100000.0, -100000.0).cast::<f16>();
+        assert!((c.r().to_f32() - 0.1).abs() < 0.001);
+        assert_eq!(c.g(), f16::INFINITY);
+        assert_eq!(c.b(), f16::NEG_INFINITY);
+    }
 }
@@ -0,0 +1,159 @@
+use num_traits::Float;
+use std::hash::{Hash, Hasher};
+
+/// 64-bit FNV-1a hasher. Unlike the std default hasher, its output is stable across runs, and integers are written
+/// little endian with sizes widened to 64 bits, so it is stable across platforms too. Across compiler versions, hashes
+/// are only stable as far as the std and derived `Hash` impls feeding the hasher are, e.g. of strings, slices and enum
+/// discriminants, which Rust does not guarantee; see `fingerprint` to detect changes.
+#[derive(Clone, Copy, Debug)]
+pub struct StableHasher {
+    /// Current hash state.
+    state: u64,
+}
+
+impl Default for StableHasher {
+    /// Hasher initialized with FNV offset basis.
+    fn default() -> Self {
+        StableHasher {
+            state: 0xcbf2_9ce4_8422_2325,
+        }
+    }
+}
+
+impl Hasher for StableHasher {
+    fn finish(&self) -> u64 {
+        self.state
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.state ^= byte as u64;
+            self.state = self.state.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+
+    fn write_i16(&mut self, i: i16) {
+        self.write_u16(i as u16);
+    }
+
+    fn write_i32(&mut self, i: i32) {
+        self.write_u32(i as u32);
+    }
+
+    fn write_i64(&mut self, i: i64) {
+        self.write_u64(i as u64);
+    }
+
+    fn write_i128(&mut self, i: i128) {
+        self.write_u128(i as u128);
+    }
+
+    fn write_isize(&mut self, i: isize) {
+        self.write_i64(i as i64);
+    }
+}
+
+/// Probe of the derived and std `Hash` impls content hashes rely on.
+#[derive(Hash)]
+enum Probe {
+    /// Unit variant.
+    Unit,
+
+    /// Variant with fields.
+    Fields(u8, Option<usize>),
+}
+
+/// Stable hash of fixed probe values of strings, slices, tuples, options and derived enums, which changes if the
+/// encoding of those impls changes, e.g. with a compiler version. Stored along with content hashes that outlive the
+/// build, e.g. in checkpoints, so they are not compared across builds hashing differently.
+pub fn fingerprint() -> u64 {
+    let probe = [
+        Probe::Unit,
+        Probe::Fields(1, Some(2)),
+        Probe::Fields(3, None),
+    ];
+    stable_hash(&("eden", &probe[..], (1.5f32).to_bits(), Some(-1i32)))
+}
+
+/// Stable content hash of value.
+pub fn stable_hash<T: Hash + ?Sized>(value: &T) -> u64 {
+    let mut hasher = StableHasher::default();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Feeds float into hasher (zeros of either sign hash equally).
+pub fn hash_float<T: Float, H: Hasher>(x: T, state: &mut H) {
+    let (mantissa, exponent, sign) = if x.is_zero() {
+        (0, 0, 0)
+    } else {
+        x.integer_decode()
+    };
+    mantissa.hash(state);
+    exponent.hash(state);
+    sign.hash(state);
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fnv() {
+        let mut h = StableHasher::default();
+        assert_eq!(h.finish(), 0xcbf29ce484222325);
+        h.write(b"a");
+        assert_eq!(h.finish(), 0xaf63dc4c8601ec8c);
+    }
+
+    #[test]
+    fn integers() {
+        // Fixed width little endian, sizes as 64 bits.
+        let hash = |write: &dyn Fn(&mut StableHasher)| {
+            let mut h = StableHasher::default();
+            write(&mut h);
+            h.finish()
+        };
+        let bytes = hash(&|h| h.write(&[1, 0, 0, 0, 0, 0, 0, 0]));
+        assert_eq!(hash(&|h| h.write_u64(1)), bytes);
+        assert_eq!(hash(&|h| h.write_usize(1)), bytes);
+        assert_eq!(hash(&|h| h.write_isize(1)), bytes);
+        assert_eq!(hash(&|h| h.write_u32(1)), hash(&|h| h.write(&[1, 0, 0, 0])));
+        assert_eq!(fingerprint(), fingerprint());
+    }
+
+    #[test]
+    fn floats() {
+        let hash = |x: f32| {
+            let mut h = StableHasher::default();
+            hash_float(x, &mut h);
+            h.finish()
+        };
+        assert_eq!(hash(0.0), hash(-0.0));
+        assert_eq!(hash(1.5), hash(1.5));
+        assert_ne!(hash(1.5), hash(-1.5));
+        assert_ne!(hash(1.0), hash(1.0 + f32::EPSILON));
+        assert_eq!(stable_hash(&[1u8, 2, 3][..]), stable_hash(&[1u8, 2, 3][..]));
+    }
+}
@@ -21,6 +21,43 @@ impl<T: Float> Interval<T> {
         self.start < x && x < self.end
     }
 
+    /// Whether interval surrounds value (start inclusive, end inclusive).
+    pub fn surrounds(&self, x: T) -> bool {
+        self.start <= x && x <= self.end
+    }
+
+    /// Value clamped to interval. Empty intervals return the start.
+    pub fn clamp(&self, x: T) -> T {
+        x.min(self.end).max(self.start)
+    }
+
+    /// Length of interval, negative if empty.
+    pub fn size(&self) -> T {
+        self.end - self.start
+    }
+
+    /// Whether interval holds no value, i.e. start lies after end.
+    pub fn is_empty(&self) -> bool {
+        self.start > self.end
+    }
+
+    /// Interval widened by padding on both sides, e.g. to give flat bounding boxes thickness or to keep shadow rays
+    /// off surfaces. Negative padding shrinks it.
+    pub fn pad(&self, padding: T) -> Self {
+        Interval::new(self.start - padding, self.end + padding)
+    }
+
+    /// Smallest interval enclosing both intervals. Empty intervals are ignored.
+    pub fn union(&self, other: &Self) -> Self {
+        if self.is_empty() {
+            *other
+        } else if other.is_empty() {
+            *self
+        } else {
+            Interval::new(self.start.min(other.start), self.end.max(other.end))
+        }
+    }
+
     /// Lower bound.
     pub fn start(&self) -> T {
         self.start
@@ -32,12 +69,28 @@ impl<T: Float> Interval<T> {
     }
 }
 
+/// Constants of default single precision, so `Interval::EMPTY` needs no type annotation.
+impl Interval<f32> {
+    /// Interval holding no value.
+    pub const EMPTY: Self = Interval {
+        start: f32::INFINITY,
+        end: f32::NEG_INFINITY,
+    };
+
+    /// Interval holding all values.
+    pub const UNIVERSE: Self = Interval {
+        start: f32::NEG_INFINITY,
+        end: f32::INFINITY,
+    };
+}
+
 /// Unit tests.
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn contains() {
         let a = Interval::new(2.0, 5.0);
         assert_eq!(a.contains(0.0), false);
@@ -49,6 +102,30 @@ mod tests {
     }
 
     #[test]
+    fn surrounds_and_clamp() {
+        let a = Interval::new(2.0, 5.0);
+        assert!(a.surrounds(2.0) && a.surrounds(5.0) && !a.surrounds(5.1));
+        assert_eq!(a.clamp(1.0), 2.0);
+        assert_eq!(a.clamp(3.0), 3.0);
+        assert_eq!(a.clamp(7.0), 5.0);
+        assert_eq!(a.size(), 3.0);
+        assert_eq!(a.pad(0.5), Interval::new(1.5, 5.5));
+    }
+
+    #[test]
+    fn union() {
+        let a = Interval::new(2.0, 5.0);
+        let b = Interval::new(4.0, 8.0);
+        assert_eq!(a.union(&b), Interval::new(2.0, 8.0));
+        assert_eq!(a.union(&Interval::EMPTY), a);
+        assert_eq!(Interval::EMPTY.union(&b), b);
+        assert!(Interval::EMPTY.is_empty() && !a.is_empty());
+        assert_eq!(a.union(&Interval::UNIVERSE), Interval::UNIVERSE);
+        assert!(!Interval::EMPTY.surrounds(0.0) && Interval::UNIVERSE.surrounds(0.0));
+    }
+
+    #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn infinity() {
         let a = Interval::new(f64::NEG_INFINITY, f64::INFINITY);
         assert_eq!(a.contains(f64::NEG_INFINITY), false);
@@ -0,0 +1,42 @@
+use crate::{
+    base::{color::Color3f, point::Point3f, vector::Vector3f},
+    lights::{directional::DirectionalLight, point::PointLight, spot::SpotLight},
+};
+
+/// An analytic light without surface, lighting scenes like in DCC tools. Such lights cannot be hit by rays, so only
+/// integrators sampling lights explicitly see their light.
+#[derive(Clone, Copy, Debug, Hash, PartialEq)]
+pub enum Light {
+    Directional(DirectionalLight),
+    Point(PointLight),
+    Spot(SpotLight),
+}
+
+impl Illuminating for Light {
+    fn illuminate(&self, reference: Point3f) -> Option<Illumination> {
+        match self {
+            Light::Directional(d) => d.illuminate(reference),
+            Light::Point(p) => p.illuminate(reference),
+            Light::Spot(s) => s.illuminate(reference),
+        }
+    }
+}
+
+/// An illuminating object lights points from a single direction.
+pub trait Illuminating {
+    /// Light arriving at reference point, ignoring occluders. Returns none if the point is not lit.
+    fn illuminate(&self, reference: Point3f) -> Option<Illumination>;
+}
+
+/// Struct holding light arriving at a point from an analytic light.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Illumination {
+    /// Unit direction from point towards light.
+    pub direction: Vector3f,
+
+    /// Distance to light, infinite for directional lights.
+    pub distance: f32,
+
+    /// Irradiance on a surface facing the light.
+    pub irradiance: Color3f,
+}
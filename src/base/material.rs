@@ -1,25 +1,150 @@
 use crate::{
-    base::{color::Color3f, ray::Ray, shape::Intersection},
-    materials::{dielectric::Dielectric, lambert::Lambert, metal::Metal},
+    base::{color::Color3f, ray::Ray, shape::Intersection, vector::Vector3f},
+    materials::{
+        custom::Custom,
+        dielectric::{Dielectric, Dispersion},
+        diffuse_light::DiffuseLight,
+        lambert::Lambert,
+        metal::Metal,
+        microfacet::Microfacet,
+        mix::Mix,
+        normal_map::NormalMapped,
+        principled::Principled,
+        shadow_catcher::ShadowCatcher,
+    },
 };
+use rand::Rng;
 
-/// A material defines how an object interacts with light rays.
-#[derive(Clone, Copy, Debug, PartialEq)]
+/// A material defines how an object interacts with light rays. Mixes and normal maps nest other materials, so
+/// materials are cloned rather than copied.
+#[derive(Clone, Debug, Hash, PartialEq)]
 pub enum Material {
+    Custom(Custom),
     Dielectric(Dielectric),
+    DiffuseLight(DiffuseLight),
     Lambert(Lambert),
     Metal(Metal),
+    Microfacet(Microfacet),
+    Mix(Mix),
+    NormalMapped(NormalMapped),
+    Principled(Principled),
+    ShadowCatcher(ShadowCatcher),
 
     #[cfg(test)]
     None,
 }
 
+impl Material {
+    /// Names of material presets.
+    pub const PRESETS: [&'static str; 11] = [
+        "glass", "frosted", "water", "diamond", "gold", "chrome", "plastic", "ceramic", "lacquer",
+        "rubber", "jade",
+    ];
+
+    /// Material preset with given name, see `PRESETS`. Returns none if unknown.
+    pub fn preset(name: &str) -> Option<Material> {
+        let material = match name {
+            "glass" => Material::Dielectric(Dielectric::new(1.5)),
+            "frosted" => Material::Microfacet(Microfacet::dielectric(1.5, 0.3)),
+            "water" => {
+                let mut water = Dielectric::new(1.33);
+                water.set_absorption(Color3f::new(0.45, 0.09, 0.06));
+                Material::Dielectric(water)
+            }
+            "diamond" => Material::Dielectric(Dielectric::dispersive(Dispersion::Sellmeier {
+                b: [4.3356, 0.3306, 0.0],
+                c: [0.011_236, 0.030_625, 0.0],
+            })),
+            "gold" => Material::Microfacet(Microfacet::metal(Color3f::new(1.0, 0.78, 0.34), 0.2)),
+            "chrome" => Material::Metal(Metal::new(Color3f::new(0.55, 0.56, 0.55), 0.0)),
+            "plastic" => Material::Lambert(Lambert::new(Color3f::new(0.8, 0.8, 0.8))),
+            "ceramic" => {
+                let mut ceramic = Principled::new(Color3f::new(0.9, 0.88, 0.82));
+                ceramic.set_roughness(0.15);
+                Material::Principled(ceramic)
+            }
+            "lacquer" => {
+                let base = Material::Lambert(Lambert::new(Color3f::new(0.5, 0.02, 0.02)));
+                let coating = Material::Metal(Metal::new(Color3f::white(), 0.0));
+                Material::Mix(Mix::layer(base, coating, 1.5))
+            }
+            "rubber" => Material::Lambert(Lambert::new(Color3f::new(0.05, 0.05, 0.05))),
+            "jade" => Material::Lambert(Lambert::new(Color3f::new(0.33, 0.62, 0.42))),
+            _ => return None,
+        };
+        Some(material)
+    }
+}
+
 impl Interactable for Material {
-    fn interact(&self, incident_ray: Ray, intersection: Intersection) -> Option<Interaction> {
+    fn interact(
+        &self,
+        incident_ray: Ray,
+        intersection: Intersection,
+        rng: &mut impl Rng,
+    ) -> Option<Interaction> {
+        let interaction = match self {
+            Material::Custom(c) => c.interact(incident_ray, intersection, rng),
+            Material::Dielectric(d) => d.interact(incident_ray, intersection, rng),
+            Material::DiffuseLight(l) => l.interact(incident_ray, intersection, rng),
+            Material::Lambert(l) => l.interact(incident_ray, intersection, rng),
+            Material::Metal(m) => m.interact(incident_ray, intersection, rng),
+            Material::Microfacet(m) => m.interact(incident_ray, intersection, rng),
+            Material::Mix(m) => m.interact(incident_ray, intersection, rng),
+            Material::NormalMapped(n) => n.interact(incident_ray, intersection, rng),
+            Material::Principled(p) => p.interact(incident_ray, intersection, rng),
+            Material::ShadowCatcher(s) => s.interact(incident_ray, intersection, rng),
+
+            #[cfg(test)]
+            Material::None => None,
+        };
+
+        // Scattered rays leave the surface offset along the geometric normal, rather than any shading normal of
+        // nested materials. They continue at the time of the incident ray, seeing moving objects at the same place,
+        // and with its wavelength unless scattering split the ray by wavelength.
+        interaction.map(|mut iact| {
+            let scattered = iact.scattered_ray;
+            iact.scattered_ray = Ray::with_time(
+                intersection.spawn_ray(scattered.direction()).origin(),
+                scattered.direction(),
+                incident_ray.time(),
+            );
+            iact.scattered_ray
+                .set_wavelength(scattered.wavelength().or(incident_ray.wavelength()));
+            iact
+        })
+    }
+
+    fn emitted(&self) -> Color3f {
         match self {
-            Material::Dielectric(d) => d.interact(incident_ray, intersection),
-            Material::Lambert(l) => l.interact(incident_ray, intersection),
-            Material::Metal(m) => m.interact(incident_ray, intersection),
+            Material::Custom(c) => c.emitted(),
+            Material::Dielectric(d) => d.emitted(),
+            Material::DiffuseLight(l) => l.emitted(),
+            Material::Lambert(l) => l.emitted(),
+            Material::Metal(m) => m.emitted(),
+            Material::Microfacet(m) => m.emitted(),
+            Material::Mix(m) => m.emitted(),
+            Material::NormalMapped(n) => n.emitted(),
+            Material::Principled(p) => p.emitted(),
+            Material::ShadowCatcher(s) => s.emitted(),
+
+            #[cfg(test)]
+            Material::None => Color3f::black(),
+        }
+    }
+
+    fn scattering_pdf(&self, intersection: Intersection, direction: Vector3f) -> Option<f32> {
+        match self {
+            Material::Custom(c) => c.scattering_pdf(intersection, direction),
+            Material::Dielectric(d) => d.scattering_pdf(intersection, direction),
+            Material::DiffuseLight(l) => l.scattering_pdf(intersection, direction),
+            Material::Lambert(l) => l.scattering_pdf(intersection, direction),
+            Material::Metal(m) => m.scattering_pdf(intersection, direction),
+            Material::Microfacet(m) => m.scattering_pdf(intersection, direction),
+            Material::Mix(m) => m.scattering_pdf(intersection, direction),
+            Material::NormalMapped(n) => n.scattering_pdf(intersection, direction),
+            Material::Principled(p) => p.scattering_pdf(intersection, direction),
+            Material::ShadowCatcher(s) => s.scattering_pdf(intersection, direction),
 
             #[cfg(test)]
             Material::None => None,
@@ -29,8 +154,31 @@ impl Interactable for Material {
 
 /// An interactable object can interact with light rays.
 pub trait Interactable {
-    /// Evaluates interactable at a given intersection point. Returns interaction struct if not absorbed.
-    fn interact(&self, incident_ray: Ray, intersection: Intersection) -> Option<Interaction>;
+    /// Evaluates interactable at a given intersection point, drawing random numbers from rng. Returns interaction
+    /// struct if not absorbed.
+    ///
+    /// During rendering, rng is the `PixelSampler` of the current path: each drawn value is the next dimension of the
+    /// pixel sample, stratified by quasi-random samplers, and renders with the same seed repeat. Implementations
+    /// should draw all random numbers from rng, never from other sources like `rand::thread_rng`, and draw the
+    /// same count of values on every call where possible, so later bounces keep their dimensions.
+    fn interact(
+        &self,
+        incident_ray: Ray,
+        intersection: Intersection,
+        rng: &mut impl Rng,
+    ) -> Option<Interaction>;
+
+    /// Radiance emitted by interactable.
+    fn emitted(&self) -> Color3f {
+        Color3f::black()
+    }
+
+    /// Probability density w.r.t. solid angle of scattering into direction, where the attenuation of `interact` is
+    /// independent of direction. Scattered radiance then equals attenuation times density times incident radiance.
+    /// Returns none for specular interactables, whose scattering cannot be evaluated for arbitrary directions.
+    fn scattering_pdf(&self, _intersection: Intersection, _direction: Vector3f) -> Option<f32> {
+        None
+    }
 }
 
 /// Struct holding interaction properties.
@@ -42,3 +190,21 @@ pub struct Interaction {
     /// Scattered ray.
     pub scattered_ray: Ray,
 }
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn preset() {
+        for name in Material::PRESETS {
+            assert!(Material::preset(name).is_some());
+        }
+        assert_eq!(
+            Material::preset("glass"),
+            Some(Material::Dielectric(Dielectric::new(1.5)))
+        );
+        assert_eq!(Material::preset("unobtainium"), None);
+    }
+}
@@ -0,0 +1,73 @@
+use crate::base::vector::Vector3f;
+
+/// Orthonormal basis u, v, w (tangent frame) around a normal w. Directions sampled in local coordinates, with the
+/// z-axis along the normal, are converted to world space by the basis.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Onb {
+    /// First tangent.
+    u: Vector3f,
+
+    /// Second tangent.
+    v: Vector3f,
+
+    /// Normal.
+    w: Vector3f,
+}
+
+impl Onb {
+    /// Creates right-handed basis around normal, which need not be unit length.
+    pub fn new(normal: Vector3f) -> Self {
+        let w = normal.normalize();
+        let (u, v) = w.orthonormal_basis();
+        Onb { u, v, w }
+    }
+
+    /// First tangent.
+    pub fn u(&self) -> Vector3f {
+        self.u
+    }
+
+    /// Second tangent.
+    pub fn v(&self) -> Vector3f {
+        self.v
+    }
+
+    /// Normal.
+    pub fn w(&self) -> Vector3f {
+        self.w
+    }
+
+    /// Converts local coordinates along u, v, w to world space.
+    pub fn to_world(&self, local: Vector3f) -> Vector3f {
+        local.x() * self.u + local.y() * self.v + local.z() * self.w
+    }
+
+    /// Converts world space vector to local coordinates along u, v, w.
+    pub fn to_local(&self, world: Vector3f) -> Vector3f {
+        Vector3f::new(world.dot(&self.u), world.dot(&self.v), world.dot(&self.w))
+    }
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new() {
+        let onb = Onb::new(Vector3f::new(0.0, 2.0, 0.0));
+        assert_eq!(onb.w(), Vector3f::new(0.0, 1.0, 0.0));
+        assert!(onb.u().dot(&onb.v()).abs() < 1e-6 && onb.u().dot(&onb.w()).abs() < 1e-6);
+        assert!((onb.u().cross(&onb.v()) - onb.w()).length() < 1e-6);
+    }
+
+    #[test]
+    fn transform() {
+        let onb = Onb::new(Vector3f::new(1.0, -2.0, 3.0));
+        assert!((onb.to_world(Vector3f::new(0.0, 0.0, 1.0)) - onb.w()).length() < 1e-6);
+        let local = Vector3f::new(0.3, -0.4, 0.5);
+        assert!((onb.to_local(onb.to_world(local)) - local).length() < 1e-6);
+        let world = Vector3f::new(-2.0, 1.0, 0.5);
+        assert!((onb.to_world(onb.to_local(world)) - world).length() < 1e-5);
+    }
+}
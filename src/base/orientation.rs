@@ -0,0 +1,144 @@
+use crate::base::{point::Point3f, vector::Vector3f};
+
+/// Coordinate axis pointing up.
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq)]
+pub enum UpAxis {
+    /// Y-axis up, as in most DCC tools and USD.
+    #[default]
+    Y,
+
+    /// Z-axis up, as in most CAD tools and MagicaVoxel.
+    Z,
+}
+
+/// Handedness of coordinate system, i.e. whether x cross y is z or -z.
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq)]
+pub enum Handedness {
+    #[default]
+    Right,
+    Left,
+}
+
+/// Convention of coordinate axes, by up axis and handedness. Conversions keep the x-axis and up direction, and turn
+/// the y-axis of z-up orientations, pointing away from the viewer, into the -z axis of y-up ones. Left-handed
+/// orientations mirror this depth axis. Scenes default to y-up and right-handed.
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq)]
+pub struct Orientation {
+    /// Axis pointing up.
+    up_axis: UpAxis,
+
+    /// Handedness of coordinate system.
+    handedness: Handedness,
+}
+
+impl Orientation {
+    /// Y-up right-handed orientation, default of scenes.
+    pub const Y_UP: Orientation = Orientation::new(UpAxis::Y, Handedness::Right);
+
+    /// Z-up right-handed orientation.
+    pub const Z_UP: Orientation = Orientation::new(UpAxis::Z, Handedness::Right);
+
+    /// Creates orientation with up axis and handedness.
+    pub const fn new(up_axis: UpAxis, handedness: Handedness) -> Self {
+        Orientation {
+            up_axis,
+            handedness,
+        }
+    }
+
+    /// Axis pointing up.
+    pub fn up_axis(&self) -> UpAxis {
+        self.up_axis
+    }
+
+    /// Handedness of coordinate system.
+    pub fn handedness(&self) -> Handedness {
+        self.handedness
+    }
+
+    /// Unit vector pointing up.
+    pub fn up(&self) -> Vector3f {
+        match self.up_axis {
+            UpAxis::Y => Vector3f::new(0.0, 1.0, 0.0),
+            UpAxis::Z => Vector3f::new(0.0, 0.0, 1.0),
+        }
+    }
+
+    /// Conversion of coordinates in this orientation to target orientation.
+    pub fn conversion(&self, target: Orientation) -> AxisConversion {
+        let (from, to) = (self.axes(), target.axes());
+        AxisConversion {
+            axes: from.map(|a| Vector3f::new(a.dot(&to[0]), a.dot(&to[1]), a.dot(&to[2]))),
+        }
+    }
+
+    /// Images of x, y and z axes in y-up right-handed coordinates.
+    fn axes(&self) -> [Vector3f; 3] {
+        let depth = match self.handedness {
+            Handedness::Right => 1.0,
+            Handedness::Left => -1.0,
+        };
+        match self.up_axis {
+            UpAxis::Y => [
+                Vector3f::new(1.0, 0.0, 0.0),
+                Vector3f::new(0.0, 1.0, 0.0),
+                Vector3f::new(0.0, 0.0, depth),
+            ],
+            UpAxis::Z => [
+                Vector3f::new(1.0, 0.0, 0.0),
+                Vector3f::new(0.0, 0.0, -depth),
+                Vector3f::new(0.0, 1.0, 0.0),
+            ],
+        }
+    }
+}
+
+/// Conversion of coordinates between orientations, permuting and flipping axes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AxisConversion {
+    /// Images of x, y and z axes.
+    axes: [Vector3f; 3],
+}
+
+impl AxisConversion {
+    /// Images of x, y and z axes.
+    pub fn axes(&self) -> [Vector3f; 3] {
+        self.axes
+    }
+
+    /// Converts vector.
+    pub fn vector(&self, v: Vector3f) -> Vector3f {
+        v.x() * self.axes[0] + v.y() * self.axes[1] + v.z() * self.axes[2]
+    }
+
+    /// Converts point.
+    pub fn point(&self, p: Point3f) -> Point3f {
+        Point3f::default() + self.vector(p - Point3f::default())
+    }
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conversion() {
+        let p = Point3f::new(1.0, 2.0, 3.0);
+        let to_y_up = |o: Orientation| o.conversion(Orientation::Y_UP).point(p);
+        assert_eq!(to_y_up(Orientation::Y_UP), p);
+        assert_eq!(to_y_up(Orientation::Z_UP), Point3f::new(1.0, 3.0, -2.0));
+        let y_left = Orientation::new(UpAxis::Y, Handedness::Left);
+        assert_eq!(to_y_up(y_left), Point3f::new(1.0, 2.0, -3.0));
+        let z_left = Orientation::new(UpAxis::Z, Handedness::Left);
+        assert_eq!(to_y_up(z_left), Point3f::new(1.0, 3.0, 2.0));
+
+        // Up maps to up, round trips are identities.
+        for from in [Orientation::Y_UP, Orientation::Z_UP, y_left, z_left] {
+            for to in [Orientation::Y_UP, Orientation::Z_UP, y_left, z_left] {
+                assert_eq!(from.conversion(to).vector(from.up()), to.up());
+                assert_eq!(to.conversion(from).point(from.conversion(to).point(p)), p);
+            }
+        }
+    }
+}
@@ -0,0 +1,182 @@
+use crate::base::{aabb::Aabb, interval::Interval, point::Point3f, ray::Ray};
+use wide::{f32x4, CmpGt, CmpLe, CmpLt};
+
+/// Count of objects intersected at once by a packet.
+pub const LANES: usize = 4;
+
+/// Up to four spheres in structure of arrays layout, intersected with a ray at once. Lanes are SIMD vectors, selected
+/// by comparison masks without branches.
+#[derive(Clone, Copy, Debug)]
+pub struct SpherePacket {
+    /// Center coordinates by axis, NaN in unused lanes.
+    center: [f32x4; 3],
+
+    /// Radius.
+    radius: f32x4,
+}
+
+impl SpherePacket {
+    /// Creates packet from centers and radii of at most four spheres.
+    pub fn new(spheres: &[(Point3f, f32)]) -> Self {
+        assert!(spheres.len() <= LANES);
+        let mut center = [[f32::NAN; LANES]; 3];
+        let mut radius = [0.0; LANES];
+        for (lane, &(c, r)) in spheres.iter().enumerate() {
+            [center[0][lane], center[1][lane], center[2][lane]] = [c.x(), c.y(), c.z()];
+            radius[lane] = r;
+        }
+        SpherePacket {
+            center: center.map(f32x4::new),
+            radius: f32x4::new(radius),
+        }
+    }
+
+    /// Nearest ray parameter within interval where ray hits each sphere, infinity if missed. Same as intersecting each
+    /// sphere on its own.
+    pub fn hit(&self, ray: Ray, ray_t: Interval) -> [f32; LANES] {
+        let (o, d) = (ray.origin(), ray.direction());
+        let (start, end) = (f32x4::splat(ray_t.start()), f32x4::splat(ray_t.end()));
+        let a = f32x4::splat(d.length_squared());
+        let oc = [
+            f32x4::splat(o.x()) - self.center[0],
+            f32x4::splat(o.y()) - self.center[1],
+            f32x4::splat(o.z()) - self.center[2],
+        ];
+        let half_b =
+            f32x4::splat(d.x()) * oc[0] + f32x4::splat(d.y()) * oc[1] + f32x4::splat(d.z()) * oc[2];
+        let c = oc[0] * oc[0] + oc[1] * oc[1] + oc[2] * oc[2] - self.radius * self.radius;
+        let discriminant_sqrt = (half_b * half_b - a * c).sqrt();
+
+        // NaN roots of negative discriminants and unused lanes are never within interval.
+        let near = (-half_b - discriminant_sqrt) / a;
+        let far = (-half_b + discriminant_sqrt) / a;
+        let within = |t: f32x4| t.cmp_gt(start) & t.cmp_lt(end);
+        let far = within(far).blend(far, f32x4::splat(f32::INFINITY));
+        within(near).blend(near, far).to_array()
+    }
+}
+
+/// Up to four bounding boxes in structure of arrays layout, intersected with a ray at once by slab test.
+#[derive(Clone, Copy, Debug)]
+pub struct AabbPacket {
+    /// Smallest coordinates by axis, infinity in unused lanes.
+    min: [f32x4; 3],
+
+    /// Largest coordinates by axis, negative infinity in unused lanes.
+    max: [f32x4; 3],
+}
+
+impl AabbPacket {
+    /// Creates packet from at most four boxes.
+    pub fn new(boxes: &[Aabb]) -> Self {
+        assert!(boxes.len() <= LANES);
+        let mut min = [[f32::INFINITY; LANES]; 3];
+        let mut max = [[f32::NEG_INFINITY; LANES]; 3];
+        for (lane, b) in boxes.iter().enumerate() {
+            let (lo, hi) = (b.min(), b.max());
+            [min[0][lane], min[1][lane], min[2][lane]] = [lo.x(), lo.y(), lo.z()];
+            [max[0][lane], max[1][lane], max[2][lane]] = [hi.x(), hi.y(), hi.z()];
+        }
+        AabbPacket {
+            min: min.map(f32x4::new),
+            max: max.map(f32x4::new),
+        }
+    }
+
+    /// Ray parameter within interval where ray enters each box, infinity if missed. Same as `Aabb::hit` of each box.
+    pub fn hit(&self, ray: Ray, ray_t: Interval) -> [f32; LANES] {
+        let (o, d) = (ray.origin(), ray.direction());
+        let origin = [o.x(), o.y(), o.z()];
+        let inverse = [1.0 / d.x(), 1.0 / d.y(), 1.0 / d.z()];
+        let mut start = f32x4::splat(ray_t.start());
+        let mut end = f32x4::splat(ray_t.end());
+        for axis in 0..3 {
+            let (origin, inverse) = (f32x4::splat(origin[axis]), f32x4::splat(inverse[axis]));
+            let t0 = (self.min[axis] - origin) * inverse;
+            let t1 = (self.max[axis] - origin) * inverse;
+            let backwards = inverse.cmp_lt(f32x4::ZERO);
+            let (t0, t1) = (backwards.blend(t1, t0), backwards.blend(t0, t1));
+
+            // Selects instead of max and min, as NaN slab distances leave interval unchanged anyway.
+            start = t0.cmp_gt(start).blend(t0, start);
+            end = t1.cmp_lt(end).blend(t1, end);
+        }
+        start
+            .cmp_le(end)
+            .blend(start, f32x4::splat(f32::INFINITY))
+            .to_array()
+    }
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        base::{material::Material, shape::Intersectable, vector::Vector3f},
+        shapes::sphere::Sphere,
+    };
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    #[test]
+    fn sphere_packet() {
+        // Matches scalar intersection of each sphere, unused lane misses.
+        let mut rng = StdRng::seed_from_u64(42);
+        let spheres: Vec<(Point3f, f32)> = (0..3)
+            .map(|_| {
+                let center = Point3f::new(
+                    rng.gen_range(-2.0..2.0),
+                    rng.gen_range(-2.0..2.0),
+                    rng.gen_range(-2.0..2.0),
+                );
+                (center, rng.gen_range(0.5..1.5))
+            })
+            .collect();
+        let packet = SpherePacket::new(&spheres);
+        let ray_t = Interval::new(0.001, 10.0);
+        for _ in 0..1000 {
+            let origin = Point3f::new(
+                rng.gen_range(-4.0..4.0),
+                rng.gen_range(-4.0..4.0),
+                rng.gen_range(-4.0..4.0),
+            );
+            let direction = Vector3f::new(
+                rng.gen_range(-1.0..1.0),
+                rng.gen_range(-1.0..1.0),
+                rng.gen_range(-1.0..1.0),
+            );
+            let ray = Ray::new(origin, direction);
+            let t = packet.hit(ray, ray_t);
+            for (lane, &(center, radius)) in spheres.iter().enumerate() {
+                let sphere = Sphere::new(center, radius, Material::None);
+                let expected = sphere.intersect(ray, ray_t).map_or(f32::INFINITY, |i| i.t);
+                assert_eq!(t[lane], expected);
+            }
+            assert_eq!(t[3], f32::INFINITY);
+        }
+    }
+
+    #[test]
+    fn aabb_packet() {
+        let boxes = [
+            Aabb::new(Point3f::new(-1.0, -1.0, -1.0), Point3f::new(1.0, 1.0, 1.0)),
+            Aabb::new(Point3f::new(2.0, -1.0, -1.0), Point3f::new(3.0, 1.0, 1.0)),
+            Aabb::new(Point3f::new(2.0, 2.0, -1.0), Point3f::new(3.0, 3.0, 1.0)),
+        ];
+        let packet = AabbPacket::new(&boxes);
+        let ray = Ray::new(Point3f::new(-3.0, 0.0, 0.0), Vector3f::new(1.0, 0.0, 0.0));
+        let all = Interval::new(0.0, f32::INFINITY);
+        assert_eq!(
+            packet.hit(ray, all),
+            [2.0, 5.0, f32::INFINITY, f32::INFINITY]
+        );
+        assert_eq!(
+            packet.hit(ray, Interval::new(3.0, 4.5)),
+            [3.0, f32::INFINITY, f32::INFINITY, f32::INFINITY]
+        );
+        for (lane, b) in boxes.iter().enumerate() {
+            let expected = b.hit(ray, all).map_or(f32::INFINITY, |t| t.start());
+            assert_eq!(packet.hit(ray, all)[lane], expected);
+        }
+    }
+}
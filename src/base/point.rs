@@ -1,5 +1,6 @@
 use crate::base::vector::Vector;
 use num_traits::Float;
+use std::hash::{Hash, Hasher};
 
 /// Abstract point in N-dimensional space.
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
@@ -8,15 +9,87 @@ pub struct Point<T: Float, const N: usize> {
     position: Vector<T, N>,
 }
 
+impl<T: Float> Point<T, 2> {
+    /// Creates 2-dim point from coordinates.
+    pub fn new(x: T, y: T) -> Self {
+        Point {
+            position: Vector::<T, 2>::new(x, y),
+        }
+    }
+
+    /// X coordinate.
+    pub fn x(&self) -> T {
+        self.position.x()
+    }
+
+    /// Y coordinate.
+    pub fn y(&self) -> T {
+        self.position.y()
+    }
+
+    /// U texture coordinate, alias of x.
+    pub fn u(&self) -> T {
+        self.x()
+    }
+
+    /// V texture coordinate, alias of y.
+    pub fn v(&self) -> T {
+        self.y()
+    }
+}
+
+impl<T: Float> From<(T, T)> for Point<T, 2> {
+    /// Point of coordinate pair, e.g. texture coordinates of an intersection.
+    fn from((x, y): (T, T)) -> Self {
+        Self::new(x, y)
+    }
+}
+
+impl<T: Float> From<Point<T, 2>> for (T, T) {
+    /// Coordinate pair of point.
+    fn from(p: Point<T, 2>) -> Self {
+        (p.x(), p.y())
+    }
+}
+
 impl<T: Float> Point<T, 3> {
     /// Creates 3-dim point from coordinates.
     pub fn new(x: T, y: T, z: T) -> Self {
         Point {
-            position: Vector::new(x, y, z),
+            position: Vector::<T, 3>::new(x, y, z),
+        }
+    }
+
+    /// X coordinate.
+    pub fn x(&self) -> T {
+        self.position.x()
+    }
+
+    /// Y coordinate.
+    pub fn y(&self) -> T {
+        self.position.y()
+    }
+
+    /// Z coordinate.
+    pub fn z(&self) -> T {
+        self.position.z()
+    }
+
+    /// Point with coordinates converted to other float type, e.g. to double precision.
+    pub fn cast<U: Float>(&self) -> Point<U, 3> {
+        Point {
+            position: self.position.cast(),
         }
     }
 }
 
+impl<T: Float, const N: usize> Hash for Point<T, N> {
+    /// Hashes position by value.
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.position.hash(state);
+    }
+}
+
 impl<T: Float, const N: usize> std::ops::Add<Vector<T, N>> for Point<T, N> {
     type Output = Point<T, N>;
 
@@ -48,6 +121,12 @@ impl<T: Float, const N: usize> std::ops::Sub<Vector<T, N>> for Point<T, N> {
     }
 }
 
+/// 2-dim point represented by single precision floats, e.g. sample points or screen positions.
+pub type Point2f = Point<f32, 2>;
+
+/// Texture coordinates (u, v), usually in [0,1]².
+pub type Uv = Point2f;
+
 /// 3-dim point represented by single precision floats.
 pub type Point3f = Point<f32, 3>;
 
@@ -55,7 +134,7 @@ pub type Point3f = Point<f32, 3>;
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::base::vector::Vector3f;
+    use crate::base::vector::{Vector2f, Vector3f};
 
     #[test]
     fn operators() {
@@ -65,5 +144,19 @@ mod tests {
         assert_eq!(ab, Vector3f::new(3.0, 0.0, -2.0));
         assert_eq!(a + ab, b);
         assert_eq!(b - ab, a);
+        assert_eq!(a.cast::<f64>(), Point::<f64, 3>::new(1.0, 2.0, 3.0));
+    }
+
+    #[test]
+    fn two_dimensional() {
+        let a = Point2f::new(0.25, 0.5);
+        let b = Point2f::new(0.75, 1.0);
+        assert_eq!((a.x(), a.y()), (0.25, 0.5));
+        assert_eq!(b - a, Vector2f::new(0.5, 0.5));
+        assert_eq!(a + Vector2f::new(0.5, 0.5), b);
+
+        let uv = Uv::from((0.1, 0.9));
+        assert_eq!((uv.u(), uv.v()), (0.1, 0.9));
+        assert_eq!(<(f32, f32)>::from(uv), (0.1, 0.9));
     }
 }
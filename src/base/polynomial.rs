@@ -0,0 +1,230 @@
+use std::ops::Deref;
+
+/// Relative magnitude below which the odd coefficient of a depressed quartic is treated as zero.
+const EPSILON: f64 = 1e-12;
+
+/// Real roots of polynomial in ascending order, at most four.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Roots {
+    /// Root values, first `count` are valid.
+    values: [f64; 4],
+
+    /// Number of roots.
+    count: usize,
+}
+
+impl Roots {
+    /// Adds root, keeping order.
+    fn push(&mut self, root: f64) {
+        let mut i = self.count;
+        while i > 0 && self.values[i - 1] > root {
+            self.values[i] = self.values[i - 1];
+            i -= 1;
+        }
+        self.values[i] = root;
+        self.count += 1;
+    }
+}
+
+impl Deref for Roots {
+    type Target = [f64];
+
+    /// Roots as slice.
+    fn deref(&self) -> &[f64] {
+        &self.values[..self.count]
+    }
+}
+
+/// Real roots of a·x² + b·x + c, of linear equation if a is zero. Double roots are reported twice.
+pub fn solve_quadratic(a: f64, b: f64, c: f64) -> Roots {
+    let mut roots = Roots::default();
+    if a == 0.0 {
+        if b != 0.0 {
+            roots.push(-c / b);
+        }
+        return roots;
+    }
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return roots;
+    }
+
+    // Numerically stable form, avoiding cancellation of b and square root.
+    let q = -0.5 * (b + b.signum() * discriminant.sqrt());
+    if q == 0.0 {
+        roots.push(0.0);
+        roots.push(0.0);
+    } else {
+        roots.push(q / a);
+        roots.push(c / q);
+    }
+    roots
+}
+
+/// Real roots of a·x³ + b·x² + c·x + d, of quadratic equation if a is zero.
+pub fn solve_cubic(a: f64, b: f64, c: f64, d: f64) -> Roots {
+    if a == 0.0 {
+        return solve_quadratic(b, c, d);
+    }
+
+    // Depressed cubic y³ + p·y + q with x = y - b/3.
+    let (b, c, d) = (b / a, c / a, d / a);
+    let shift = -b / 3.0;
+    let p = c - b * b / 3.0;
+    let q = 2.0 * b * b * b / 27.0 - b * c / 3.0 + d;
+
+    let mut roots = Roots::default();
+    let discriminant = q * q / 4.0 + p * p * p / 27.0;
+    if discriminant > 0.0 {
+        // One real root (Cardano).
+        let s = discriminant.sqrt();
+        roots.push((-q / 2.0 + s).cbrt() + (-q / 2.0 - s).cbrt() + shift);
+    } else if p == 0.0 {
+        // Triple root.
+        roots.push(shift);
+    } else {
+        // Three real roots (trigonometric method).
+        let r = 2.0 * (-p / 3.0).sqrt();
+        let phi = (3.0 * q / (p * r)).clamp(-1.0, 1.0).acos() / 3.0;
+        for k in 0..3 {
+            roots.push(r * (phi - 2.0 * std::f64::consts::PI * k as f64 / 3.0).cos() + shift);
+        }
+    }
+    polish(&mut roots, &[0.0, 1.0, b, c, d]);
+    roots
+}
+
+/// Real roots of a·x⁴ + b·x³ + c·x² + d·x + e, of cubic equation if a is zero. Roots are refined by Newton
+/// iterations, so they are accurate even where the closed form suffers from cancellation.
+pub fn solve_quartic(a: f64, b: f64, c: f64, d: f64, e: f64) -> Roots {
+    if a == 0.0 {
+        return solve_cubic(b, c, d, e);
+    }
+
+    // Depressed quartic y⁴ + p·y² + q·y + r with x = y - b/4.
+    let (b, c, d, e) = (b / a, c / a, d / a, e / a);
+    let shift = -b / 4.0;
+    let b2 = b * b;
+    let p = c - 3.0 * b2 / 8.0;
+    let q = d - b * c / 2.0 + b2 * b / 8.0;
+    let r = e - b * d / 4.0 + b2 * c / 16.0 - 3.0 * b2 * b2 / 256.0;
+
+    let mut roots = Roots::default();
+    if q.abs() <= EPSILON * (p.abs() + r.abs() + 1.0) {
+        // Biquadratic, quadratic in y².
+        for z in solve_quadratic(1.0, p, r).iter() {
+            if *z >= 0.0 {
+                let y = z.sqrt();
+                roots.push(y + shift);
+                roots.push(-y + shift);
+            }
+        }
+    } else {
+        // Ferrari: positive root m of resolvent cubic splits quartic into two quadratics.
+        let resolvent = solve_cubic(1.0, p, p * p / 4.0 - r, -q * q / 8.0);
+        let m = resolvent.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        if m <= 0.0 {
+            return roots;
+        }
+        let s = (2.0 * m).sqrt();
+        for sign in [-1.0, 1.0] {
+            let quadratic = solve_quadratic(1.0, sign * s, p / 2.0 + m - sign * q / (2.0 * s));
+            for y in quadratic.iter() {
+                roots.push(y + shift);
+            }
+        }
+    }
+    polish(&mut roots, &[1.0, b, c, d, e]);
+    roots
+}
+
+/// Refines roots of polynomial with coefficients from highest to lowest degree by Newton iterations.
+fn polish(roots: &mut Roots, coefficients: &[f64]) {
+    for i in 0..roots.count {
+        let mut x = roots.values[i];
+        for _ in 0..2 {
+            // Horner scheme for value and derivative.
+            let (mut value, mut derivative) = (0.0, 0.0);
+            for &coefficient in coefficients {
+                derivative = derivative * x + value;
+                value = value * x + coefficient;
+            }
+            if derivative == 0.0 {
+                break;
+            }
+            x -= value / derivative;
+        }
+        if x.is_finite() {
+            roots.values[i] = x;
+        }
+    }
+    roots.values[..roots.count].sort_by(f64::total_cmp);
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Asserts roots match expected values.
+    fn assert_roots(roots: Roots, expected: &[f64]) {
+        assert_eq!(roots.len(), expected.len(), "{:?}", roots);
+        for (r, e) in roots.iter().zip(expected) {
+            assert!((r - e).abs() < 1e-9, "{:?} != {:?}", &roots[..], expected);
+        }
+    }
+
+    #[test]
+    fn quadratic() {
+        assert_roots(solve_quadratic(1.0, -3.0, 2.0), &[1.0, 2.0]);
+        assert_roots(solve_quadratic(1.0, 0.0, 1.0), &[]);
+        assert_roots(solve_quadratic(0.0, 2.0, -1.0), &[0.5]);
+        assert_roots(solve_quadratic(1.0, 0.0, 0.0), &[0.0, 0.0]);
+
+        // Tiny root next to huge one.
+        assert_roots(solve_quadratic(1.0, -1e8, 1.0), &[1e-8, 1e8]);
+    }
+
+    #[test]
+    fn cubic() {
+        // (x-1)(x-2)(x-3), x³ - 1, (x-2)³.
+        assert_roots(solve_cubic(1.0, -6.0, 11.0, -6.0), &[1.0, 2.0, 3.0]);
+        assert_roots(solve_cubic(2.0, 0.0, 0.0, -2.0), &[1.0]);
+        assert_roots(solve_cubic(1.0, -6.0, 12.0, -8.0), &[2.0]);
+        assert_roots(solve_cubic(0.0, 1.0, -3.0, 2.0), &[1.0, 2.0]);
+    }
+
+    #[test]
+    fn quartic() {
+        // (x-1)(x-2)(x-3)(x-4).
+        assert_roots(
+            solve_quartic(1.0, -10.0, 35.0, -50.0, 24.0),
+            &[1.0, 2.0, 3.0, 4.0],
+        );
+
+        // Biquadratic (x²-1)(x²-4), no real roots x⁴ + 1, two real roots (x²-1)(x²+1).
+        assert_roots(
+            solve_quartic(1.0, 0.0, -5.0, 0.0, 4.0),
+            &[-2.0, -1.0, 1.0, 2.0],
+        );
+        assert_roots(solve_quartic(1.0, 0.0, 0.0, 0.0, 1.0), &[]);
+        assert_roots(solve_quartic(3.0, 0.0, 0.0, 0.0, -3.0), &[-1.0, 1.0]);
+
+        // (x+1)(x-0.5)(x²+x+1), general case with complex pair.
+        assert_roots(solve_quartic(1.0, 1.5, 1.0, 0.0, -0.5), &[-1.0, 0.5]);
+
+        // Roots spread over orders of magnitude, (x-0.001)(x-1)(x-10)(x-1000).
+        let (a, b, c, d) = (0.001, 1.0, 10.0, 1000.0);
+        let roots = solve_quartic(
+            1.0,
+            -(a + b + c + d),
+            a * b + a * c + a * d + b * c + b * d + c * d,
+            -(a * b * c + a * b * d + a * c * d + b * c * d),
+            a * b * c * d,
+        );
+        assert_roots(roots, &[a, b, c, d]);
+
+        // Degenerate leading coefficient.
+        assert_roots(solve_quartic(0.0, 1.0, -6.0, 11.0, -6.0), &[1.0, 2.0, 3.0]);
+    }
+}
@@ -1,6 +1,13 @@
 use crate::base::{point::Point3f, vector::Vector3f};
 
-/// Ray in 3-dim space defined by origin and direction.
+/// Bound of relative rounding error accumulated by n single precision operations. Scaled by magnitudes of the
+/// operands, it bounds absolute errors of computed points.
+pub fn gamma(n: u32) -> f32 {
+    let n = n as f32 * 0.5 * f32::EPSILON;
+    n / (1.0 - n)
+}
+
+/// Ray in 3-dim space defined by origin and direction, at a time within the frame.
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Ray {
     /// Initial point.
@@ -8,12 +15,57 @@ pub struct Ray {
 
     /// Direction vector.
     direction: Vector3f,
+
+    /// Time in frame durations since start of frame, seeing moving objects where they are at that time.
+    time: f32,
+
+    /// Wavelength in nanometers the ray carries after dispersion split its path by wavelength, if any.
+    wavelength: Option<f32>,
 }
 
 impl Ray {
-    /// Creates ray defined by origin and direction.
+    /// Creates ray defined by origin and direction at start of frame.
     pub fn new(origin: Point3f, direction: Vector3f) -> Self {
-        Ray { origin, direction }
+        Ray::with_time(origin, direction, 0.0)
+    }
+
+    /// Creates ray defined by origin and direction at time in frame durations since start of frame.
+    pub fn with_time(origin: Point3f, direction: Vector3f, time: f32) -> Self {
+        Ray {
+            origin,
+            direction,
+            time,
+            wavelength: None,
+        }
+    }
+
+    /// Creates ray leaving surface point along direction at start of frame. Its origin is offset along the surface
+    /// normal to the side the direction points to, just beyond the bound of the point's rounding error per
+    /// coordinate, so the ray cannot hit the surface it leaves again at any ray parameter above zero.
+    pub fn spawn(point: Point3f, error: Vector3f, normal: Vector3f, direction: Vector3f) -> Self {
+        let normal = if direction.dot(&normal) < 0.0 {
+            -normal
+        } else {
+            normal
+        };
+        let origin = point + normal.abs().dot(&error) * normal;
+
+        // Round away from point, as adding the offset may round back towards the surface.
+        let away = |x: f32, n: f32| {
+            if n > 0.0 {
+                x.next_up()
+            } else if n < 0.0 {
+                x.next_down()
+            } else {
+                x
+            }
+        };
+        let origin = Point3f::new(
+            away(origin.x(), normal.x()),
+            away(origin.y(), normal.y()),
+            away(origin.z(), normal.z()),
+        );
+        Ray::new(origin, direction)
     }
 
     /// Position on ray for parameter t.
@@ -21,6 +73,13 @@ impl Ray {
         self.origin + t * self.direction
     }
 
+    /// Bound of rounding error per coordinate of position `at(t)`, for parameter t found by an intersection test
+    /// and accurate up to the rounding of the few operations computing it.
+    pub fn error_at(&self, t: f32) -> Vector3f {
+        let origin = self.origin - Point3f::default();
+        gamma(7) * (origin.abs() + (t * self.direction).abs())
+    }
+
     /// Ray origin.
     pub fn origin(&self) -> Point3f {
         self.origin
@@ -30,6 +89,21 @@ impl Ray {
     pub fn direction(&self) -> Vector3f {
         self.direction
     }
+
+    /// Time in frame durations since start of frame.
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    /// Wavelength in nanometers the ray carries, if any. Rays without one carry all visible light.
+    pub fn wavelength(&self) -> Option<f32> {
+        self.wavelength
+    }
+
+    /// Sets wavelength in nanometers the ray carries.
+    pub fn set_wavelength(&mut self, wavelength: Option<f32>) {
+        self.wavelength = wavelength;
+    }
 }
 
 /// Unit tests.
@@ -46,4 +120,21 @@ mod tests {
         assert_eq!(r.at(1.0), o + d);
         assert_eq!(r.at(-2.0), Point3f::new(-3.0, -4.0, -5.0));
     }
+
+    #[test]
+    fn spawn() {
+        // Origin leaves surface on side of direction, beyond error bound.
+        let point = Point3f::new(1000.0, 0.0, 0.0);
+        let normal = Vector3f::new(1.0, 0.0, 0.0);
+        let error = Ray::new(Point3f::default(), normal).error_at(1000.0);
+        let out = Ray::spawn(point, error, normal, Vector3f::new(1.0, 1.0, 0.0));
+        assert!(out.origin().x() > 1000.0 + error.x());
+        let back = Ray::spawn(point, error, normal, Vector3f::new(-1.0, 1.0, 0.0));
+        assert!(back.origin().x() < 1000.0 - error.x());
+        assert_eq!(back.origin().y(), 0.0);
+
+        // Errors vanish at origin, yet offset stays nonzero.
+        let exact = Ray::spawn(Point3f::default(), Vector3f::default(), normal, normal);
+        assert!(exact.origin().x() > 0.0);
+    }
 }
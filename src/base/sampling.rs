@@ -0,0 +1,199 @@
+use crate::base::vector::Vector3f;
+use rand::Rng;
+use std::f32::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+
+/// Random unit direction in local coordinates, distributed by cosine around the z-axis over the upper hemisphere.
+/// Projects uniform disk samples up onto the hemisphere (Malley's method).
+pub fn cosine_hemisphere(rng: &mut impl Rng) -> Vector3f {
+    let (x, y) = uniform_disk(rng);
+    Vector3f::new(x, y, (1.0 - x * x - y * y).max(0.0).sqrt())
+}
+
+/// Density w.r.t. solid angle of `cosine_hemisphere` for direction with cosine to the z-axis (or normal).
+pub fn cosine_hemisphere_pdf(cos_theta: f32) -> f32 {
+    cos_theta.max(0.0) / PI
+}
+
+/// Random unit direction in local coordinates, uniformly distributed over the upper hemisphere around the z-axis.
+pub fn uniform_hemisphere(rng: &mut impl Rng) -> Vector3f {
+    let z = rng.gen::<f32>();
+    let r = (1.0 - z * z).max(0.0).sqrt();
+    let phi = 2.0 * PI * rng.gen::<f32>();
+    Vector3f::new(r * phi.cos(), r * phi.sin(), z)
+}
+
+/// Density w.r.t. solid angle of `uniform_hemisphere`.
+pub fn uniform_hemisphere_pdf() -> f32 {
+    1.0 / (2.0 * PI)
+}
+
+/// Random unit direction, uniformly distributed over the sphere.
+pub fn uniform_sphere(rng: &mut impl Rng) -> Vector3f {
+    let z = 1.0 - 2.0 * rng.gen::<f32>();
+    let r = (1.0 - z * z).max(0.0).sqrt();
+    let phi = 2.0 * PI * rng.gen::<f32>();
+    Vector3f::new(r * phi.cos(), r * phi.sin(), z)
+}
+
+/// Density w.r.t. solid angle of `uniform_sphere`.
+pub fn uniform_sphere_pdf() -> f32 {
+    1.0 / (4.0 * PI)
+}
+
+/// Random unit direction in local coordinates, uniformly distributed in cone around the z-axis with cosine of half
+/// opening angle.
+pub fn uniform_cone(cos_theta_max: f32, rng: &mut impl Rng) -> Vector3f {
+    let cos_theta = 1.0 - rng.gen::<f32>() * (1.0 - cos_theta_max);
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    let phi = 2.0 * PI * rng.gen::<f32>();
+    Vector3f::new(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta)
+}
+
+/// Density w.r.t. solid angle of `uniform_cone` with cosine of half opening angle.
+pub fn uniform_cone_pdf(cos_theta_max: f32) -> f32 {
+    1.0 / (2.0 * PI * (1.0 - cos_theta_max))
+}
+
+/// Random point uniformly distributed on unit disk.
+pub fn uniform_disk(rng: &mut impl Rng) -> (f32, f32) {
+    let r = rng.gen::<f32>().sqrt();
+    let phi = 2.0 * PI * rng.gen::<f32>();
+    (r * phi.cos(), r * phi.sin())
+}
+
+/// Density w.r.t. area of `uniform_disk`.
+pub fn uniform_disk_pdf() -> f32 {
+    1.0 / PI
+}
+
+/// Random point uniformly distributed on unit disk, mapping concentric squares of the unit square to concentric
+/// circles (Shirley and Chiu), so stratified inputs stay stratified with little distortion.
+pub fn concentric_disk(rng: &mut impl Rng) -> (f32, f32) {
+    let (a, b) = (2.0 * rng.gen::<f32>() - 1.0, 2.0 * rng.gen::<f32>() - 1.0);
+    if a == 0.0 && b == 0.0 {
+        return (0.0, 0.0);
+    }
+    let (r, phi) = if a.abs() > b.abs() {
+        (a, FRAC_PI_4 * (b / a))
+    } else {
+        (b, FRAC_PI_2 - FRAC_PI_4 * (a / b))
+    };
+    (r * phi.cos(), r * phi.sin())
+}
+
+/// Random point uniformly distributed on triangle, as barycentric coordinates (b0, b1) of its first two corners,
+/// b2 = 1 - b0 - b1 of the third. Density w.r.t. area is one over the area of the triangle.
+pub fn uniform_triangle(rng: &mut impl Rng) -> (f32, f32) {
+    let s = rng.gen::<f32>().sqrt();
+    (1.0 - s, rng.gen::<f32>() * s)
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    /// Count of samples per test.
+    const N: u32 = 100000;
+
+    #[test]
+    fn cosine_hemisphere() {
+        // Mean cosine of cosine-weighted directions is 2/3, density integrates to one over hemisphere.
+        let mut rng = StdRng::seed_from_u64(42);
+        let mut sum = 0.0;
+        for _ in 0..N {
+            let d = super::cosine_hemisphere(&mut rng);
+            assert!((d.length() - 1.0).abs() < 1e-5 && d.z() >= 0.0);
+            sum += d.z();
+        }
+        assert!((sum / N as f32 - 2.0 / 3.0).abs() < 0.01);
+        let integral: f32 = (0..N)
+            .map(|_| cosine_hemisphere_pdf(super::uniform_sphere(&mut rng).z()))
+            .sum::<f32>()
+            / (N as f32 * uniform_sphere_pdf());
+        assert!((integral - 1.0).abs() < 0.02);
+        assert_eq!(cosine_hemisphere_pdf(-0.5), 0.0);
+    }
+
+    #[test]
+    fn uniform_hemisphere() {
+        // Unit directions above the plane with mean cosine 1/2.
+        let mut rng = StdRng::seed_from_u64(42);
+        let mut sum = 0.0;
+        for _ in 0..N {
+            let d = super::uniform_hemisphere(&mut rng);
+            assert!((d.length() - 1.0).abs() < 1e-5 && d.z() >= 0.0);
+            sum += d.z();
+        }
+        assert!((sum / N as f32 - 0.5).abs() < 0.01);
+        assert_eq!(uniform_hemisphere_pdf(), 2.0 * uniform_sphere_pdf());
+    }
+
+    #[test]
+    fn uniform_sphere() {
+        // Unit directions with zero mean and mean squared component 1/3.
+        let mut rng = StdRng::seed_from_u64(42);
+        let (mut mean, mut squared) = (Vector3f::default(), 0.0);
+        for _ in 0..N {
+            let d = super::uniform_sphere(&mut rng);
+            assert!((d.length() - 1.0).abs() < 1e-5);
+            mean += d / N as f32;
+            squared += d.x() * d.x() / N as f32;
+        }
+        assert!(mean.length() < 0.01);
+        assert!((squared - 1.0 / 3.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn uniform_disk() {
+        // Points inside unit disk, half of them inside radius sqrt(1/2).
+        let mut rng = StdRng::seed_from_u64(42);
+        let mut inner = 0;
+        for _ in 0..N {
+            let (x, y) = super::uniform_disk(&mut rng);
+            let r2 = x * x + y * y;
+            assert!(r2 <= 1.0 + 1e-6);
+            inner += u32::from(r2 < 0.5);
+        }
+        assert!((inner as f32 / N as f32 - 0.5).abs() < 0.01);
+        assert_eq!(uniform_disk_pdf() * PI, 1.0);
+    }
+
+    #[test]
+    fn concentric_disk() {
+        // Points inside unit disk, half of them inside radius sqrt(1/2), balanced over quadrants.
+        let mut rng = StdRng::seed_from_u64(42);
+        let (mut inner, mut right, mut upper) = (0, 0, 0);
+        for _ in 0..N {
+            let (x, y) = super::concentric_disk(&mut rng);
+            let r2 = x * x + y * y;
+            assert!(r2 <= 1.0 + 1e-6);
+            inner += u32::from(r2 < 0.5);
+            right += u32::from(x > 0.0);
+            upper += u32::from(y > 0.0);
+        }
+        for count in [inner, right, upper] {
+            assert!((count as f32 / N as f32 - 0.5).abs() < 0.01);
+        }
+    }
+
+    #[test]
+    fn uniform_triangle() {
+        // Barycentric coordinates inside triangle, each with mean 1/3, a quarter of points in the corner triangle
+        // of half size.
+        let mut rng = StdRng::seed_from_u64(42);
+        let (mut mean, mut corner) = ([0.0; 3], 0);
+        for _ in 0..N {
+            let (b0, b1) = super::uniform_triangle(&mut rng);
+            let b2 = 1.0 - b0 - b1;
+            assert!(b0 >= 0.0 && b1 >= 0.0 && b2 >= -1e-6);
+            for (m, b) in mean.iter_mut().zip([b0, b1, b2]) {
+                *m += b / N as f32;
+            }
+            corner += u32::from(b0 > 0.5);
+        }
+        assert!(mean.iter().all(|m| (m - 1.0 / 3.0).abs() < 0.01));
+        assert!((corner as f32 / N as f32 - 0.25).abs() < 0.01);
+    }
+}
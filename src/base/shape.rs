@@ -1,18 +1,246 @@
 use crate::{
-    base::{interval::Interval, material::Material, point::Point3f, ray::Ray, vector::Vector3f},
-    shapes::sphere::Sphere,
+    base::{
+        aabb::Aabb,
+        interval::Interval,
+        material::Material,
+        point::{Point, Point3f},
+        ray::Ray,
+        transform::Transform,
+        vector::{Vector, Vector3f},
+    },
+    shapes::{
+        capsule::Capsule, cone::Cone, csg::Csg, custom::Custom, cylinder::Cylinder,
+        instance::Instance, mesh::Mesh, sphere::Sphere, torus::Torus, voxels::Voxels, water::Water,
+    },
 };
+use rand::Rng;
 
 /// An intersectable shape in 3-dim space.
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Debug, Hash, PartialEq)]
 pub enum Shape {
+    Capsule(Capsule),
+    Cone(Cone),
+    Csg(Csg),
+    Custom(Custom),
+    Cylinder(Cylinder),
+    Instance(Instance),
+    Mesh(Mesh),
     Sphere(Sphere),
+    Torus(Torus),
+    Voxels(Voxels),
+    Water(Water),
+}
+
+impl Shape {
+    /// Names of shape presets.
+    pub const PRESETS: [&'static str; 3] = ["ground", "ball", "pebble"];
+
+    /// Shape preset with given name and material, see `PRESETS`. Presets rest on the y=0 plane, except the ground
+    /// itself which is a huge sphere touching it from below. Returns none if unknown.
+    pub fn preset(name: &str, material: Material) -> Option<Shape> {
+        let (center, radius) = match name {
+            "ground" => (Point3f::new(0.0, -1000.0, 0.0), 1000.0),
+            "ball" => (Point3f::new(0.0, 1.0, 0.0), 1.0),
+            "pebble" => (Point3f::new(0.0, 0.2, 0.0), 0.2),
+            _ => return None,
+        };
+        Some(Shape::Sphere(Sphere::new(center, radius, material)))
+    }
+
+    /// Surface material.
+    pub fn material(&self) -> &Material {
+        match self {
+            Shape::Capsule(c) => c.material(),
+            Shape::Cone(c) => c.material(),
+            Shape::Csg(c) => c.material(),
+            Shape::Custom(c) => c.material(),
+            Shape::Cylinder(c) => c.material(),
+            Shape::Instance(i) => i.material(),
+            Shape::Mesh(m) => m.material(),
+            Shape::Sphere(s) => s.material(),
+            Shape::Torus(t) => t.material(),
+            Shape::Voxels(v) => v.material(),
+            Shape::Water(w) => w.material(),
+        }
+    }
+
+    /// Sets surface material.
+    pub fn set_material(&mut self, material: Material) {
+        match self {
+            Shape::Capsule(c) => c.set_material(material),
+            Shape::Cone(c) => c.set_material(material),
+            Shape::Csg(c) => c.set_material(material),
+            Shape::Custom(c) => c.set_material(material),
+            Shape::Cylinder(c) => c.set_material(material),
+            Shape::Instance(i) => i.set_material(material),
+            Shape::Mesh(m) => m.set_material(material),
+            Shape::Sphere(s) => s.set_material(material),
+            Shape::Torus(t) => t.set_material(material),
+            Shape::Voxels(v) => v.set_material(material),
+            Shape::Water(w) => w.set_material(material),
+        }
+    }
+
+    /// Center position of shape.
+    pub fn center(&self) -> Point3f {
+        match self {
+            Shape::Capsule(c) => c.center(),
+            Shape::Cone(c) => c.center(),
+            Shape::Csg(c) => c.center(),
+            Shape::Custom(c) => c.center(),
+            Shape::Cylinder(c) => c.center(),
+            Shape::Instance(i) => i.center(),
+            Shape::Mesh(m) => m.center(),
+            Shape::Sphere(s) => s.center(),
+            Shape::Torus(t) => t.center(),
+            Shape::Voxels(v) => v.center(),
+            Shape::Water(w) => w.center(),
+        }
+    }
+
+    /// Moves shape to center position.
+    pub fn set_center(&mut self, center: Point3f) {
+        match self {
+            Shape::Capsule(c) => c.set_center(center),
+            Shape::Cone(c) => c.set_center(center),
+            Shape::Csg(c) => c.set_center(center),
+            Shape::Custom(c) => c.set_center(center),
+            Shape::Cylinder(c) => c.set_center(center),
+            Shape::Instance(i) => i.set_center(center),
+            Shape::Mesh(m) => m.set_center(center),
+            Shape::Sphere(s) => s.set_center(center),
+            Shape::Torus(t) => t.set_center(center),
+            Shape::Voxels(v) => v.set_center(center),
+            Shape::Water(w) => w.set_center(center),
+        }
+    }
+
+    /// Transform from local space of shape to world space, e.g. to attach points to the shape: that of instances, a
+    /// translation to the center for other shapes. Local points follow the shape as it moves.
+    pub fn frame(&self) -> Transform {
+        match self {
+            Shape::Instance(i) => i.transform(),
+            _ => Transform::translation(self.center() - Point3f::default()),
+        }
+    }
+
+    /// Ray parameter of nearest intersection computed in double precision where supported (spheres), for checking
+    /// single precision intersections. Other shapes fall back to single precision.
+    pub fn intersect_precise(
+        &self,
+        origin: Point<f64, 3>,
+        direction: Vector<f64, 3>,
+        ray_t: Interval<f64>,
+    ) -> Option<f64> {
+        match self {
+            Shape::Sphere(s) => s.intersect_precise(origin, direction, ray_t),
+            _ => {
+                let ray = Ray::new(origin.cast(), direction.cast());
+                let ray_t = Interval::new(ray_t.start() as f32, ray_t.end() as f32);
+                self.intersect(ray, ray_t).map(|isect| isect.t as f64)
+            }
+        }
+    }
 }
 
 impl Intersectable for Shape {
-    fn intersect(&self, ray: Ray, ray_t: Interval) -> Option<Intersection> {
+    fn intersect(&self, ray: Ray, ray_t: Interval) -> Option<Intersection<'_>> {
         match self {
+            Shape::Capsule(c) => c.intersect(ray, ray_t),
+            Shape::Cone(c) => c.intersect(ray, ray_t),
+            Shape::Csg(c) => c.intersect(ray, ray_t),
+            Shape::Custom(c) => c.intersect(ray, ray_t),
+            Shape::Cylinder(c) => c.intersect(ray, ray_t),
+            Shape::Instance(i) => i.intersect(ray, ray_t),
+            Shape::Mesh(m) => m.intersect(ray, ray_t),
             Shape::Sphere(s) => s.intersect(ray, ray_t),
+            Shape::Torus(t) => t.intersect(ray, ray_t),
+            Shape::Voxels(v) => v.intersect(ray, ray_t),
+            Shape::Water(w) => w.intersect(ray, ray_t),
+        }
+    }
+}
+
+impl Bounded for Shape {
+    fn bounding_box(&self) -> Aabb {
+        match self {
+            Shape::Capsule(c) => c.bounding_box(),
+            Shape::Cone(c) => c.bounding_box(),
+            Shape::Csg(c) => c.bounding_box(),
+            Shape::Custom(c) => c.bounding_box(),
+            Shape::Cylinder(c) => c.bounding_box(),
+            Shape::Instance(i) => i.bounding_box(),
+            Shape::Mesh(m) => m.bounding_box(),
+            Shape::Sphere(s) => s.bounding_box(),
+            Shape::Torus(t) => t.bounding_box(),
+            Shape::Voxels(v) => v.bounding_box(),
+            Shape::Water(w) => w.bounding_box(),
+        }
+    }
+}
+
+impl Sampleable for Shape {
+    fn area(&self) -> f32 {
+        match self {
+            Shape::Capsule(c) => c.area(),
+            Shape::Cone(c) => c.area(),
+            Shape::Csg(c) => c.area(),
+            Shape::Custom(c) => c.area(),
+            Shape::Cylinder(c) => c.area(),
+            Shape::Instance(i) => i.area(),
+            Shape::Mesh(m) => m.area(),
+            Shape::Sphere(s) => s.area(),
+            Shape::Torus(t) => t.area(),
+            Shape::Voxels(v) => v.area(),
+            Shape::Water(w) => w.area(),
+        }
+    }
+
+    fn sample_point(&self, rng: &mut impl Rng) -> SurfaceSample {
+        match self {
+            Shape::Capsule(c) => c.sample_point(rng),
+            Shape::Cone(c) => c.sample_point(rng),
+            Shape::Csg(c) => c.sample_point(rng),
+            Shape::Custom(c) => c.sample_point(rng),
+            Shape::Cylinder(c) => c.sample_point(rng),
+            Shape::Instance(i) => i.sample_point(rng),
+            Shape::Mesh(m) => m.sample_point(rng),
+            Shape::Sphere(s) => s.sample_point(rng),
+            Shape::Torus(t) => t.sample_point(rng),
+            Shape::Voxels(v) => v.sample_point(rng),
+            Shape::Water(w) => w.sample_point(rng),
+        }
+    }
+
+    fn sample_from(&self, reference: Point3f, rng: &mut impl Rng) -> Option<SurfaceSample> {
+        match self {
+            Shape::Capsule(c) => c.sample_from(reference, rng),
+            Shape::Cone(c) => c.sample_from(reference, rng),
+            Shape::Csg(c) => c.sample_from(reference, rng),
+            Shape::Custom(c) => c.sample_from(reference, rng),
+            Shape::Cylinder(c) => c.sample_from(reference, rng),
+            Shape::Instance(i) => i.sample_from(reference, rng),
+            Shape::Mesh(m) => m.sample_from(reference, rng),
+            Shape::Sphere(s) => s.sample_from(reference, rng),
+            Shape::Torus(t) => t.sample_from(reference, rng),
+            Shape::Voxels(v) => v.sample_from(reference, rng),
+            Shape::Water(w) => w.sample_from(reference, rng),
+        }
+    }
+
+    fn pdf_from(&self, reference: Point3f, point: Point3f, normal: Vector3f) -> f32 {
+        match self {
+            Shape::Capsule(c) => c.pdf_from(reference, point, normal),
+            Shape::Cone(c) => c.pdf_from(reference, point, normal),
+            Shape::Csg(c) => c.pdf_from(reference, point, normal),
+            Shape::Custom(c) => c.pdf_from(reference, point, normal),
+            Shape::Cylinder(c) => c.pdf_from(reference, point, normal),
+            Shape::Instance(i) => i.pdf_from(reference, point, normal),
+            Shape::Mesh(m) => m.pdf_from(reference, point, normal),
+            Shape::Sphere(s) => s.pdf_from(reference, point, normal),
+            Shape::Torus(t) => t.pdf_from(reference, point, normal),
+            Shape::Voxels(v) => v.pdf_from(reference, point, normal),
+            Shape::Water(w) => w.pdf_from(reference, point, normal),
         }
     }
 }
@@ -20,7 +248,7 @@ impl Intersectable for Shape {
 /// An intersectable object can be intersected by rays.
 pub trait Intersectable {
     /// Tests for ray intersection in given t-interval. Returns intersection struct if exists.
-    fn intersect(&self, ray: Ray, ray_t: Interval) -> Option<Intersection>;
+    fn intersect(&self, ray: Ray, ray_t: Interval) -> Option<Intersection<'_>>;
 }
 
 /// Struct holding intersection properties.
@@ -32,9 +260,102 @@ pub struct Intersection<'a> {
     /// Normal at intersection.
     pub normal: Vector3f,
 
+    /// Surface coordinates at intersection, in [0,1] each.
+    pub uv: (f32, f32),
+
     /// Material at intersection.
     pub material: &'a Material,
 
     /// Value of ray parameter t.
     pub t: f32,
+
+    /// Bound of rounding error of intersection point per coordinate.
+    pub error: Vector3f,
+}
+
+impl Intersection<'_> {
+    /// Ray leaving intersection along direction, with origin offset off the surface beyond the error bound of the
+    /// point, so it needs no minimum ray parameter to avoid hitting the surface again.
+    pub fn spawn_ray(&self, direction: Vector3f) -> Ray {
+        Ray::spawn(self.point, self.error, self.normal, direction)
+    }
+}
+
+/// A bounded object occupies finite space.
+pub trait Bounded {
+    /// Axis-aligned box enclosing object.
+    fn bounding_box(&self) -> Aabb;
+}
+
+/// A sampleable object can generate random points on its surface.
+pub trait Sampleable {
+    /// Surface area.
+    fn area(&self) -> f32;
+
+    /// Samples point uniformly by area. Returns surface sample with pdf w.r.t. surface area.
+    fn sample_point(&self, rng: &mut impl Rng) -> SurfaceSample;
+
+    /// Samples point on surface as seen from reference point. Returns surface sample with pdf w.r.t. solid angle
+    /// at reference point, or none if sample cannot be seen.
+    fn sample_from(&self, reference: Point3f, rng: &mut impl Rng) -> Option<SurfaceSample> {
+        self.sample_point(rng).to_solid_angle(reference)
+    }
+
+    /// Probability density w.r.t. solid angle at reference point of `sample_from` returning given surface point.
+    fn pdf_from(&self, reference: Point3f, point: Point3f, normal: Vector3f) -> f32 {
+        let sample = SurfaceSample {
+            point,
+            normal,
+            pdf: 1.0 / self.area(),
+        };
+        sample.to_solid_angle(reference).map_or(0.0, |s| s.pdf)
+    }
+}
+
+/// Struct holding surface sample properties.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SurfaceSample {
+    /// Sampled point.
+    pub point: Point3f,
+
+    /// Surface normal at sampled point.
+    pub normal: Vector3f,
+
+    /// Probability density of sample.
+    pub pdf: f32,
+}
+
+impl SurfaceSample {
+    /// Converts pdf w.r.t. surface area to pdf w.r.t. solid angle at reference point. Returns none if degenerate.
+    pub fn to_solid_angle(mut self, reference: Point3f) -> Option<Self> {
+        let to_sample = self.point - reference;
+        let distance_squared = to_sample.length_squared();
+        if distance_squared <= 0.0 {
+            return None;
+        }
+        let cos_theta = to_sample.normalize().dot(&self.normal).abs();
+        if cos_theta <= 0.0 {
+            return None;
+        }
+        self.pdf *= distance_squared / cos_theta;
+        Some(self)
+    }
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn preset() {
+        for name in Shape::PRESETS {
+            let shape = Shape::preset(name, Material::None).unwrap();
+            assert_eq!(
+                shape.bounding_box().min().y(),
+                if name == "ground" { -2000.0 } else { 0.0 }
+            );
+        }
+        assert_eq!(Shape::preset("teapot", Material::None), None);
+    }
 }
@@ -0,0 +1,163 @@
+use crate::base::{point::Point3f, ray::gamma, vector::Vector3f};
+
+/// Affine transform mapping local to parent space.
+#[derive(Clone, Copy, Debug, Hash, PartialEq)]
+pub struct Transform {
+    /// Images of local x, y and z axes.
+    axes: [Vector3f; 3],
+
+    /// Image of local origin.
+    translation: Vector3f,
+}
+
+impl Transform {
+    /// Creates transform from images of local axes and origin.
+    pub fn new(axes: [Vector3f; 3], translation: Vector3f) -> Self {
+        Transform { axes, translation }
+    }
+
+    /// Identity transform.
+    pub fn identity() -> Self {
+        Transform::linear([
+            Vector3f::new(1.0, 0.0, 0.0),
+            Vector3f::new(0.0, 1.0, 0.0),
+            Vector3f::new(0.0, 0.0, 1.0),
+        ])
+    }
+
+    /// Linear transform with given axis images.
+    pub fn linear(axes: [Vector3f; 3]) -> Self {
+        Transform::new(axes, Vector3f::default())
+    }
+
+    /// Translation by offset.
+    pub fn translation(offset: Vector3f) -> Self {
+        Transform::new(Transform::identity().axes, offset)
+    }
+
+    /// Scaling by factor per axis.
+    pub fn scaling(x: f32, y: f32, z: f32) -> Self {
+        let [ax, ay, az] = Transform::identity().axes;
+        Transform::linear([x * ax, y * ay, z * az])
+    }
+
+    /// Counter-clockwise rotation by degrees around coordinate axis (0 = x, 1 = y, 2 = z).
+    pub fn rotation(axis: usize, degrees: f32) -> Self {
+        let (s, c) = degrees.to_radians().sin_cos();
+        let mut axes = Transform::identity().axes;
+        let (i, j) = ((axis + 1) % 3, (axis + 2) % 3);
+        axes[i] = c * axes[i] + s * Transform::identity().axes[j];
+        axes[j] = c * axes[j] - s * Transform::identity().axes[i];
+        Transform::linear(axes)
+    }
+
+    /// Images of local x, y and z axes.
+    pub fn axes(&self) -> [Vector3f; 3] {
+        self.axes
+    }
+
+    /// Image of local origin.
+    pub fn offset(&self) -> Vector3f {
+        self.translation
+    }
+
+    /// Transforms vector.
+    pub fn vector(&self, v: Vector3f) -> Vector3f {
+        v.x() * self.axes[0] + v.y() * self.axes[1] + v.z() * self.axes[2]
+    }
+
+    /// Transforms point.
+    pub fn point(&self, p: Point3f) -> Point3f {
+        Point3f::default() + self.translation + self.vector(p - Point3f::default())
+    }
+
+    /// Bound of rounding error per coordinate of transformed point, for point with given bound of error.
+    pub fn point_error(&self, p: Point3f, error: Vector3f) -> Vector3f {
+        let abs = Transform::new(self.axes.map(|a| a.abs()), self.translation.abs());
+        let p = (p - Point3f::default()).abs();
+        gamma(3) * (abs.vector(p) + abs.translation) + (1.0 + gamma(3)) * abs.vector(error)
+    }
+
+    /// Transforms surface normal by the inverse transpose, keeping it perpendicular to transformed surfaces. Result
+    /// is unit length.
+    pub fn normal(&self, n: Vector3f) -> Vector3f {
+        let [a, b, c] = self.axes;
+        let sign = self.determinant().signum();
+        (sign * (n.x() * b.cross(&c) + n.y() * c.cross(&a) + n.z() * a.cross(&b))).normalize()
+    }
+
+    /// Transform applying other transform first, then this one.
+    pub fn compose(&self, other: &Transform) -> Self {
+        Transform {
+            axes: other.axes.map(|a| self.vector(a)),
+            translation: self.vector(other.translation) + self.translation,
+        }
+    }
+
+    /// Inverse transform. Returns none if singular.
+    pub fn inverse(&self) -> Option<Self> {
+        let determinant = self.determinant();
+        if determinant == 0.0 || !determinant.is_finite() {
+            return None;
+        }
+
+        // Rows of inverse are cross products of axes over determinant.
+        let [a, b, c] = self.axes;
+        let rows = [b.cross(&c), c.cross(&a), a.cross(&b)].map(|r| r / determinant);
+        let axes = [0, 1, 2].map(|i| {
+            let column = |r: Vector3f| [r.x(), r.y(), r.z()][i];
+            Vector3f::new(column(rows[0]), column(rows[1]), column(rows[2]))
+        });
+        let linear = Transform::linear(axes);
+        Some(Transform::new(axes, -linear.vector(self.translation)))
+    }
+
+    /// Determinant of linear part, the factor on volumes.
+    pub fn determinant(&self) -> f32 {
+        let [a, b, c] = self.axes;
+        a.dot(&b.cross(&c))
+    }
+
+    /// Mean scale factor of axes.
+    pub fn scale(&self) -> f32 {
+        self.axes.iter().map(|a| a.length()).sum::<f32>() / 3.0
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Transform::identity()
+    }
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inverse() {
+        let t = Transform::translation(Vector3f::new(1.0, 2.0, 3.0))
+            .compose(&Transform::rotation(1, 30.0))
+            .compose(&Transform::scaling(2.0, 1.0, 0.5));
+        let p = Point3f::new(0.3, -1.0, 4.0);
+        let q = t.inverse().unwrap().point(t.point(p));
+        assert!((q - p).length() < 1e-5);
+        assert!((t.determinant() - 1.0).abs() < 1e-5);
+        assert_eq!(Transform::scaling(1.0, 0.0, 1.0).inverse(), None);
+    }
+
+    #[test]
+    fn normal() {
+        // Normal of plane x+y=0 stays perpendicular to it when stretched along x.
+        let t = Transform::scaling(2.0, 1.0, 1.0);
+        let n = t.normal(Vector3f::new(1.0, 1.0, 0.0));
+        let tangent = t.vector(Vector3f::new(1.0, -1.0, 0.0));
+        assert!(n.dot(&tangent).abs() < 1e-6);
+        assert!((n.length() - 1.0).abs() < 1e-6);
+
+        // Mirroring keeps normals outward.
+        let n = Transform::scaling(-1.0, 1.0, 1.0).normal(Vector3f::new(1.0, 0.0, 0.0));
+        assert_eq!(n, Vector3f::new(-1.0, 0.0, 0.0));
+    }
+}
@@ -0,0 +1,111 @@
+use crate::base::hash::hash_float;
+use std::hash::{Hash, Hasher};
+
+/// Transform of surface texture coordinates, e.g. to fix tiling of imported assets. Coordinates are scaled and
+/// rotated about the center (0.5,0.5), then offset, and wrapped back into [0,1], so scales above one tile the
+/// texture.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct UvTransform {
+    /// Scale along u and v.
+    scale: (f32, f32),
+
+    /// Offset along u and v, applied after scale and rotation.
+    offset: (f32, f32),
+
+    /// Counterclockwise rotation in degrees.
+    rotation: f32,
+}
+
+impl UvTransform {
+    /// Identity transform.
+    pub const IDENTITY: UvTransform = UvTransform {
+        scale: (1.0, 1.0),
+        offset: (0.0, 0.0),
+        rotation: 0.0,
+    };
+
+    /// Creates transform with scale and offset along u and v and counterclockwise rotation in degrees.
+    pub fn new(scale: (f32, f32), offset: (f32, f32), rotation: f32) -> Self {
+        assert!(scale.0.is_finite() && scale.1.is_finite());
+        assert!(offset.0.is_finite() && offset.1.is_finite() && rotation.is_finite());
+        UvTransform {
+            scale,
+            offset,
+            rotation,
+        }
+    }
+
+    /// Scale along u and v.
+    pub fn scale(&self) -> (f32, f32) {
+        self.scale
+    }
+
+    /// Offset along u and v.
+    pub fn offset(&self) -> (f32, f32) {
+        self.offset
+    }
+
+    /// Counterclockwise rotation in degrees.
+    pub fn rotation(&self) -> f32 {
+        self.rotation
+    }
+
+    /// Transforms texture coordinates. The identity returns them unchanged, others wrap into [0,1].
+    pub fn apply(&self, (u, v): (f32, f32)) -> (f32, f32) {
+        if *self == UvTransform::IDENTITY {
+            return (u, v);
+        }
+        let (x, y) = ((u - 0.5) * self.scale.0, (v - 0.5) * self.scale.1);
+        let (sin, cos) = self.rotation.to_radians().sin_cos();
+        let (u, v) = (
+            cos * x - sin * y + 0.5 + self.offset.0,
+            sin * x + cos * y + 0.5 + self.offset.1,
+        );
+        (u.rem_euclid(1.0), v.rem_euclid(1.0))
+    }
+}
+
+impl Default for UvTransform {
+    /// Identity transform.
+    fn default() -> Self {
+        UvTransform::IDENTITY
+    }
+}
+
+impl Hash for UvTransform {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for x in [
+            self.scale.0,
+            self.scale.1,
+            self.offset.0,
+            self.offset.1,
+            self.rotation,
+        ] {
+            hash_float(x, state);
+        }
+    }
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn apply() {
+        let close =
+            |(a, b): (f32, f32), (c, d): (f32, f32)| (a - c).abs() < 1e-5 && (b - d).abs() < 1e-5;
+        assert_eq!(UvTransform::default().apply((1.0, 0.25)), (1.0, 0.25));
+
+        // Scale tiles around center, offset shifts and wraps.
+        let tiled = UvTransform::new((2.0, 1.0), (0.0, 0.0), 0.0);
+        assert!(close(tiled.apply((0.75, 0.25)), (0.0, 0.25)));
+        assert!(close(tiled.apply((0.1, 0.25)), (0.7, 0.25)));
+        let shifted = UvTransform::new((1.0, 1.0), (0.25, -0.5), 0.0);
+        assert!(close(shifted.apply((0.875, 0.75)), (0.125, 0.25)));
+
+        // Quarter turn counterclockwise about center.
+        let rotated = UvTransform::new((1.0, 1.0), (0.0, 0.0), 90.0);
+        assert!(close(rotated.apply((0.75, 0.5)), (0.5, 0.75)));
+    }
+}
@@ -1,5 +1,7 @@
+use crate::base::hash::hash_float;
 use num_traits::Float;
 use rand::{distributions::uniform::SampleUniform, Rng};
+use std::hash::{Hash, Hasher};
 
 /// Abstract vector in N-dimensional space.
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -55,20 +57,111 @@ impl<T: Float, const N: usize> Vector<T, N> {
         Some(t)
     }
 
+    /// Vector of absolute values of components.
+    pub fn abs(&self) -> Self {
+        Vector {
+            components: self.components.map(|x| x.abs()),
+        }
+    }
+
+    /// Componentwise minimum with other vector.
+    pub fn min(&self, v: &Self) -> Self {
+        self.zip_with(v, T::min)
+    }
+
+    /// Componentwise maximum with other vector.
+    pub fn max(&self, v: &Self) -> Self {
+        self.zip_with(v, T::max)
+    }
+
+    /// Vector with components clamped componentwise between min and max.
+    pub fn clamp(&self, min: &Self, max: &Self) -> Self {
+        self.max(min).min(max)
+    }
+
+    /// Linear interpolation from vector (t = 0) to other vector (t = 1), extrapolating beyond.
+    pub fn lerp(&self, v: &Self, t: T) -> Self {
+        *self + (*v - *self) * t
+    }
+
+    /// Smallest component.
+    pub fn min_component(&self) -> T {
+        self.components
+            .iter()
+            .fold(T::infinity(), |acc, &x| acc.min(x))
+    }
+
+    /// Largest component.
+    pub fn max_component(&self) -> T {
+        self.components
+            .iter()
+            .fold(T::neg_infinity(), |acc, &x| acc.max(x))
+    }
+
+    /// Index of largest component, the first one of ties, e.g. the longest axis of an extent.
+    pub fn max_dimension(&self) -> usize {
+        (0..N).fold(0, |a, i| {
+            if self.components[i] > self.components[a] {
+                i
+            } else {
+                a
+            }
+        })
+    }
+
     /// Whether vector is close to zero in all components.
     pub fn near_zero(&self) -> bool {
         self.components.iter().all(|x| x.abs() < T::epsilon())
     }
+
+    /// Vector with components converted to other float type, e.g. to double precision.
+    pub fn cast<U: Float>(&self) -> Vector<U, N> {
+        Vector {
+            components: self.components.map(|x| U::from(x).unwrap()),
+        }
+    }
+
+    /// Vector of function applied to pairs of components of vector and other vector.
+    fn zip_with(&self, v: &Self, f: impl Fn(T, T) -> T) -> Self {
+        let mut result = *self;
+        for i in 0..N {
+            result.components[i] = f(self.components[i], v.components[i]);
+        }
+        result
+    }
 }
 
 impl<T: Float + SampleUniform, const N: usize> Vector<T, N> {
-    /// Generates random vector of unit length.
+    /// Generates random vector of unit length, uniformly distributed over directions.
     pub fn random_unit_vector(rng: &mut impl Rng) -> Self {
-        let mut result = Vector::default();
-        for i in 0..N {
-            result.components[i] = rng.gen_range(-T::one()..T::one());
+        // Rejection sampling in unit ball, since normalized cube samples favor diagonals.
+        loop {
+            let mut result = Vector::default();
+            for i in 0..N {
+                result.components[i] = rng.gen_range(-T::one()..T::one());
+            }
+            let length_squared = result.length_squared();
+            if length_squared > T::min_positive_value() && length_squared <= T::one() {
+                return result / length_squared.sqrt();
+            }
         }
-        result.normalize()
+    }
+}
+
+impl<T: Float> Vector<T, 2> {
+    /// Creates 2-dim vector from components.
+    pub fn new(x: T, y: T) -> Self {
+        Vector { components: [x, y] }
+    }
+
+    /// X component.
+    pub fn x(&self) -> T {
+        self.components[0]
+    }
+
+    /// Y component.
+    pub fn y(&self) -> T {
+        self.components[1]
     }
 }
 
@@ -107,6 +200,25 @@ impl<T: Float> Vector<T, 3> {
             ],
         }
     }
+
+    /// Vector of components of this vector at given indices, e.g. `swizzle(2, 0, 1)` for (z, x, y).
+    pub fn swizzle(&self, x: usize, y: usize, z: usize) -> Self {
+        let c = self.components;
+        Self::new(c[x], c[y], c[z])
+    }
+
+    /// Two unit vectors u, v completing unit vector w to right-handed orthonormal basis u, v, w.
+    pub fn orthonormal_basis(&self) -> (Self, Self) {
+        let (zero, one) = (T::zero(), T::one());
+        let a = if self.x().abs() > T::from(0.9).unwrap() {
+            Self::new(zero, one, zero)
+        } else {
+            Self::new(one, zero, zero)
+        };
+        let v = self.cross(&a).normalize();
+        let u = v.cross(self);
+        (u, v)
+    }
 }
 
 impl<T: Float, const N: usize> Default for Vector<T, N> {
@@ -118,6 +230,31 @@ impl<T: Float, const N: usize> Default for Vector<T, N> {
     }
 }
 
+impl<T: Float, const N: usize> Hash for Vector<T, N> {
+    /// Hashes components by value.
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for x in self.components {
+            hash_float(x, state);
+        }
+    }
+}
+
+impl<T: Float, const N: usize> std::ops::Index<usize> for Vector<T, N> {
+    type Output = T;
+
+    /// Component at index, panics beyond dimension.
+    fn index(&self, index: usize) -> &T {
+        &self.components[index]
+    }
+}
+
+impl<T: Float, const N: usize> std::ops::IndexMut<usize> for Vector<T, N> {
+    /// Mutable component at index, panics beyond dimension.
+    fn index_mut(&mut self, index: usize) -> &mut T {
+        &mut self.components[index]
+    }
+}
+
 /// Helper macro for binary operator overloading.
 macro_rules! impl_binary_op {
     // $op_trait is something like 'Add'.
@@ -223,6 +360,9 @@ impl_assign_op!(MulAssign mul_assign *);
 impl_binary_op!(Div div /);
 impl_assign_op!(DivAssign div_assign /);
 
+/// 2-dim vector represented by single precision floats.
+pub type Vector2f = Vector<f32, 2>;
+
 /// 3-dim vector represented by single precision floats.
 pub type Vector3f = Vector<f32, 3>;
 
@@ -241,6 +381,7 @@ mod tests {
                 components: [0.0, 0.0]
             }
         );
+        assert_eq!(Vector2f::default(), Vector2f::new(0.0, 0.0));
     }
 
     #[test]
@@ -267,6 +408,11 @@ mod tests {
         assert_eq!(3.0 * b, f);
         assert_eq!(f / 3.0, b);
         assert_eq!(6.0 / g, a);
+        assert_eq!(a.cast::<f64>(), Vector::<f64, 3>::new(1.0, 2.0, 3.0));
+        assert_eq!(
+            Vector2f::new(1.0, 2.0) * Vector2f::new(3.0, 4.0),
+            Vector2f::new(3.0, 8.0)
+        );
 
         assert_eq!(-g, h);
 
@@ -300,6 +446,38 @@ mod tests {
         assert_eq!(d.cross(&e), f);
         assert_eq!(e.cross(&d), -f);
         assert_eq!(d.cross(&d), Vector3f::default());
+
+        for w in [Vector3f::new(1.0, 0.0, 0.0), a.normalize()] {
+            let (u, v) = w.orthonormal_basis();
+            assert!((u.length() - 1.0).abs() < 1e-6 && (v.length() - 1.0).abs() < 1e-6);
+            assert!(u.dot(&v).abs() < 1e-6 && u.dot(&w).abs() < 1e-6 && v.dot(&w).abs() < 1e-6);
+            assert!((u.cross(&v) - w).length() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn componentwise() {
+        let a = Vector3f::new(1.0, -2.0, 3.0);
+        let b = Vector3f::new(2.0, -3.0, 0.0);
+        assert_eq!(a.min(&b), Vector3f::new(1.0, -3.0, 0.0));
+        assert_eq!(a.max(&b), Vector3f::new(2.0, -2.0, 3.0));
+        assert_eq!(a.abs(), Vector3f::new(1.0, 2.0, 3.0));
+        let (lo, hi) = (Vector3f::new(0.0, 0.0, 0.0), Vector3f::new(1.0, 1.0, 1.0));
+        assert_eq!(a.clamp(&lo, &hi), Vector3f::new(1.0, 0.0, 1.0));
+        assert_eq!(a.lerp(&b, 0.0), a);
+        assert_eq!(a.lerp(&b, 1.0), b);
+        assert_eq!(a.lerp(&b, 0.5), Vector3f::new(1.5, -2.5, 1.5));
+        assert_eq!(a.min_component(), -2.0);
+        assert_eq!(a.max_component(), 3.0);
+        assert_eq!(a.max_dimension(), 2);
+        assert_eq!(Vector3f::new(1.0, 1.0, 0.0).max_dimension(), 0);
+        assert_eq!(a.swizzle(2, 0, 1), Vector3f::new(3.0, 1.0, -2.0));
+        assert_eq!(a.swizzle(0, 0, 0), Vector3f::new(1.0, 1.0, 1.0));
+
+        let mut c = a;
+        assert_eq!((c[0], c[1], c[2]), (1.0, -2.0, 3.0));
+        c[1] = 5.0;
+        assert_eq!(c, Vector3f::new(1.0, 5.0, 3.0));
     }
 
     #[test]
@@ -325,6 +503,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn near_zero() {
         let a = Vector3f::default();
         assert_eq!(a.near_zero(), true);
@@ -341,5 +520,14 @@ mod tests {
         let b = Vector::<f64, 4>::random_unit_vector(&mut rng);
         assert_eq!(a.length(), 1.0);
         assert_eq!(b.length(), 1.0);
+
+        // Cap around axis receives its share of uniformly distributed directions.
+        let n = 200000;
+        let cos_cap = 0.3f32.cos();
+        let hits = (0..n)
+            .filter(|_| Vector3f::random_unit_vector(&mut rng).y() > cos_cap)
+            .count();
+        let expected = n as f32 * (1.0 - cos_cap) / 2.0;
+        assert!((hits as f32 - expected).abs() / expected < 0.05);
     }
 }
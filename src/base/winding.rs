@@ -0,0 +1,159 @@
+use crate::base::point::Point3f;
+use std::collections::HashMap;
+
+/// Orients triangles of closed parts of a triangle mesh consistently with faces pointing outwards, counterclockwise
+/// seen from outside, repairing e.g. meshes of mixed or inverted winding, which render black or wrong inside solids.
+/// Parts are found by flood fill over edges shared by two triangles, points at equal positions being welded, so flat
+/// shaded meshes with split points connect. Open parts, with an edge not shared by exactly two triangles, and
+/// non-orientable ones are left as they are. Returns indices of flipped triangles, e.g. to flip attributes indexed
+/// per corner along.
+pub fn orient(points: &[Point3f], triangles: &mut [[usize; 3]]) -> Vec<usize> {
+    // Weld points by position, zero of either sign being equal.
+    let mut positions = HashMap::new();
+    let ids: Vec<usize> = points
+        .iter()
+        .enumerate()
+        .map(|(i, p)| {
+            let key = [p.x(), p.y(), p.z()].map(|x| (x + 0.0).to_bits());
+            *positions.entry(key).or_insert(i)
+        })
+        .collect();
+    let corners: Vec<[usize; 3]> = triangles.iter().map(|t| t.map(|i| ids[i])).collect();
+    let edges = |t: usize| {
+        let [a, b, c] = corners[t];
+        [(a, b), (b, c), (c, a)].into_iter().filter(|(a, b)| a != b)
+    };
+
+    // Triangles by undirected edge.
+    let mut shared: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+    for t in 0..corners.len() {
+        for (a, b) in edges(t) {
+            shared.entry((a.min(b), a.max(b))).or_default().push(t);
+        }
+    }
+
+    // Flood fill parts, flipping neighbors which run along shared edges the same way.
+    let mut visited = vec![false; corners.len()];
+    let mut flip = vec![false; corners.len()];
+    let mut flipped = Vec::new();
+    for seed in 0..corners.len() {
+        if visited[seed] {
+            continue;
+        }
+        visited[seed] = true;
+        let mut part = vec![seed];
+        let mut stack = vec![seed];
+        let (mut closed, mut orientable) = (true, true);
+        while let Some(t) = stack.pop() {
+            for (a, b) in edges(t) {
+                let [first, second] = shared[&(a.min(b), a.max(b))][..] else {
+                    closed = false;
+                    continue;
+                };
+                let neighbor = if first == t { second } else { first };
+                let same_way = edges(neighbor).any(|edge| edge == (a, b));
+                if !visited[neighbor] {
+                    visited[neighbor] = true;
+                    flip[neighbor] = flip[t] != same_way;
+                    part.push(neighbor);
+                    stack.push(neighbor);
+                } else if flip[neighbor] != (flip[t] != same_way) {
+                    orientable = false;
+                }
+            }
+        }
+        if !(closed && orientable) {
+            continue;
+        }
+
+        // Outwards if enclosed volume, summed over tetrahedra spanned with any point of the part, is positive.
+        let origin = points[corners[seed][0]];
+        let volume: f32 = part
+            .iter()
+            .map(|&t| {
+                let [a, b, c] = triangles[t].map(|i| points[i] - origin);
+                let volume = a.dot(&b.cross(&c));
+                if flip[t] {
+                    -volume
+                } else {
+                    volume
+                }
+            })
+            .sum();
+        for &t in &part {
+            if flip[t] != (volume < 0.0) {
+                triangles[t].swap(1, 2);
+                flipped.push(t);
+            }
+        }
+    }
+    flipped.sort_unstable();
+    flipped
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::vector::Vector3f;
+
+    /// Unit cube with outwards triangles.
+    fn cube() -> (Vec<Point3f>, Vec<[usize; 3]>) {
+        let points = (0..8)
+            .map(|i| Point3f::new((i & 1) as f32, (i >> 1 & 1) as f32, (i >> 2 & 1) as f32))
+            .collect();
+        let quads = [
+            [0, 2, 3, 1],
+            [4, 5, 7, 6],
+            [0, 1, 5, 4],
+            [2, 6, 7, 3],
+            [0, 4, 6, 2],
+            [1, 3, 7, 5],
+        ];
+        let triangles = quads
+            .iter()
+            .flat_map(|&[a, b, c, d]| [[a, b, c], [a, c, d]])
+            .collect();
+        (points, triangles)
+    }
+
+    #[test]
+    fn orient() {
+        // Consistent outwards already.
+        let (points, triangles) = cube();
+        let mut t = triangles.clone();
+        assert!(super::orient(&points, &mut t).is_empty());
+
+        // Mixed and inverted winding.
+        for flipped in [vec![0, 3, 7], (0..12).collect()] {
+            let mut t = triangles.clone();
+            flipped.iter().for_each(|&i| t[i].swap(1, 2));
+            assert_eq!(super::orient(&points, &mut t), flipped);
+            assert_eq!(t, triangles);
+        }
+
+        // Split points of flat shaded cube are welded.
+        let split: Vec<Point3f> = triangles
+            .iter()
+            .flat_map(|t| t.map(|i| points[i]))
+            .collect();
+        let fan: Vec<[usize; 3]> = (0..12).map(|t| [3 * t, 3 * t + 1, 3 * t + 2]).collect();
+        let mut t = fan.clone();
+        t[5].swap(1, 2);
+        assert_eq!(super::orient(&split, &mut t), [5]);
+        assert_eq!(t, fan);
+
+        // Open square and separate cube with one part oriented.
+        let mut open = vec![[0, 1, 3], [0, 2, 3]];
+        assert!(super::orient(&points, &mut open).is_empty());
+        let mut both: Vec<Point3f> = points
+            .iter()
+            .map(|&p| p + Vector3f::new(2.0, 0.0, 0.0))
+            .collect();
+        both.extend(&points);
+        let mut t: Vec<[usize; 3]> = triangles.iter().map(|t| t.map(|i| i + 8)).collect();
+        t.extend([[0, 1, 3], [0, 3, 2]]);
+        t[4].swap(1, 2);
+        assert_eq!(super::orient(&both, &mut t), [4]);
+    }
+}
@@ -0,0 +1,527 @@
+use crate::{
+    base::{color::Color3f, material::Material, point::Point3f, shape::Shape, vector::Vector3f},
+    camera::{AspectPolicy, Camera},
+    film::Film,
+    integrator::Integrator,
+    integrators::path_tracer::PathTracer,
+    materials::{lambert::Lambert, metal::Metal, microfacet::Microfacet},
+    scene::Scene,
+    settings::RenderSettings,
+    shapes::sphere::Sphere,
+    studio,
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::time::Instant;
+
+/// Names of standard benchmark scenes.
+pub const SCENES: [&str; 2] = ["cover", "studio"];
+
+/// Max depth of paths in standard benchmark scenes, enough for the glass spheres to converge.
+const MAX_DEPTH: u32 = 50;
+
+/// Render time and image error at one sample count.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Run {
+    /// Samples per pixel.
+    pub samples: u32,
+
+    /// Wall clock render time in seconds.
+    pub seconds: f64,
+
+    /// Root mean square error of pixel luminance vs. reference.
+    pub rmse: f64,
+
+    /// Root mean square error relative to mean pixel luminance of reference.
+    pub relative_rmse: f64,
+}
+
+/// Render times and errors vs. a high sample count reference image, by sample count. Reports of different eden
+/// versions on the same scene and camera compare their performance and convergence.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BenchmarkReport {
+    /// Samples per pixel of reference image.
+    reference_samples: u32,
+
+    /// Render time of reference image in seconds.
+    reference_seconds: f64,
+
+    /// Runs by sample count, in order given.
+    runs: Vec<Run>,
+}
+
+impl BenchmarkReport {
+    /// Renders scene with camera and settings at each sample count, and at reference sample count with another
+    /// seed, so errors are independent of the reference noise.
+    pub fn measure(
+        camera: &Camera,
+        scene: &Scene,
+        settings: &RenderSettings,
+        sample_counts: &[u32],
+        reference_samples: u32,
+    ) -> Self {
+        assert!(reference_samples > 0 && sample_counts.iter().all(|&s| s > 0));
+        let mut reference_settings = settings.clone();
+        reference_settings.set_seed(settings.seed().wrapping_add(1));
+        let (reference, reference_seconds) =
+            timed_render(camera.clone(), scene, reference_settings, reference_samples);
+        let runs = sample_counts
+            .iter()
+            .map(|&samples| {
+                let (image, seconds) =
+                    timed_render(camera.clone(), scene, settings.clone(), samples);
+                let (rmse, relative_rmse) = image_error(&image, &reference);
+                Run {
+                    samples,
+                    seconds,
+                    rmse,
+                    relative_rmse,
+                }
+            })
+            .collect();
+        BenchmarkReport {
+            reference_samples,
+            reference_seconds,
+            runs,
+        }
+    }
+
+    /// Samples per pixel of reference image.
+    pub fn reference_samples(&self) -> u32 {
+        self.reference_samples
+    }
+
+    /// Render time of reference image in seconds.
+    pub fn reference_seconds(&self) -> f64 {
+        self.reference_seconds
+    }
+
+    /// Runs by sample count.
+    pub fn runs(&self) -> &[Run] {
+        &self.runs
+    }
+
+    /// Report as CSV table with header.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("samples,seconds,rmse,relative_rmse\n");
+        for r in self.runs.iter() {
+            csv += &format!(
+                "{},{},{},{}\n",
+                r.samples, r.seconds, r.rmse, r.relative_rmse
+            );
+        }
+        csv
+    }
+
+    /// Report as JSON object, including eden version.
+    pub fn to_json(&self) -> String {
+        let runs: Vec<String> = self
+            .runs
+            .iter()
+            .map(|r| {
+                format!(
+                    "{{\"samples\":{},\"seconds\":{},\"rmse\":{},\"relative_rmse\":{}}}",
+                    r.samples, r.seconds, r.rmse, r.relative_rmse
+                )
+            })
+            .collect();
+        format!(
+            "{{\"version\":\"{}\",\"reference\":{{\"samples\":{},\"seconds\":{}}},\"runs\":[{}]}}",
+            env!("CARGO_PKG_VERSION"),
+            self.reference_samples,
+            self.reference_seconds,
+            runs.join(",")
+        )
+    }
+
+    /// Report as aligned text table for terminals.
+    pub fn to_text(&self) -> String {
+        let mut text = format!(
+            "eden {}, reference {} spp in {:.2} s\n{:>8} {:>10} {:>12} {:>10}\n",
+            env!("CARGO_PKG_VERSION"),
+            self.reference_samples,
+            self.reference_seconds,
+            "spp",
+            "seconds",
+            "rmse",
+            "relative"
+        );
+        for r in self.runs.iter() {
+            text += &format!(
+                "{:>8} {:>10.3} {:>12.4e} {:>9.2}%\n",
+                r.samples,
+                r.seconds,
+                r.rmse,
+                100.0 * r.relative_rmse
+            );
+        }
+        text
+    }
+}
+
+/// Render throughput of one scene at one sample count.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Throughput {
+    /// Name of scene.
+    pub scene: String,
+
+    /// Samples per pixel.
+    pub samples: u32,
+
+    /// Wall clock render time in seconds.
+    pub seconds: f64,
+
+    /// Count of traced camera and scattered rays, excluding shadow rays.
+    pub rays: u64,
+}
+
+impl Throughput {
+    /// Renders scene with camera and settings, timing the render. Rays are counted by rendering again, see
+    /// `count_rays`, as path statistics would slow the timed render down.
+    pub fn measure(name: &str, camera: &Camera, scene: &Scene, settings: &RenderSettings) -> Self {
+        let (_, seconds) = timed_render(
+            camera.clone(),
+            scene,
+            settings.clone(),
+            settings.samples_per_pixel(),
+        );
+        Throughput {
+            scene: name.to_string(),
+            samples: settings.samples_per_pixel(),
+            seconds,
+            rays: Throughput::count_rays(camera, scene, settings),
+        }
+    }
+
+    /// Count of camera and scattered rays a render of scene with camera and settings traces, excluding shadow rays.
+    /// Renders with path statistics, which trace the same paths at the same seed.
+    pub fn count_rays(camera: &Camera, scene: &Scene, settings: &RenderSettings) -> u64 {
+        let (_, stats) = camera.clone().render_with_stats(scene, settings);
+        let bounces: u64 = stats.materials().iter().map(|m| m.bounces).sum();
+        stats.paths() + bounces
+    }
+
+    /// Traced rays per second.
+    pub fn rays_per_second(&self) -> f64 {
+        self.rays as f64 / self.seconds
+    }
+}
+
+/// Render throughput of standard scenes, compared across versions to catch performance regressions, e.g. of the
+/// bounding volume hierarchy, samplers or materials. Scenes render at fixed seeds, so each version traces the same
+/// count of rays as long as it renders the same image.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ThroughputReport {
+    /// Throughput by scene, in order measured.
+    runs: Vec<Throughput>,
+}
+
+impl ThroughputReport {
+    /// Renders standard scenes with given names at resolution with settings of `standard_settings`, see `SCENES`.
+    pub fn measure(scenes: &[&str], resolution: (u32, u32), samples: u32) -> Self {
+        let settings = standard_settings(samples);
+        let runs = scenes
+            .iter()
+            .map(|&name| {
+                let (scene, mut camera) = standard_scene(name).expect("unknown benchmark scene");
+                camera.set_resolution_override(Some(resolution), AspectPolicy::default());
+                Throughput::measure(name, &camera, &scene, &settings)
+            })
+            .collect();
+        ThroughputReport { runs }
+    }
+
+    /// Throughput by scene.
+    pub fn runs(&self) -> &[Throughput] {
+        &self.runs
+    }
+
+    /// Regressions vs. baseline report: scenes whose rays per second dropped by more than tolerance, a fraction of
+    /// the baseline, with description. Scenes missing in either report are skipped.
+    pub fn regressions(&self, baseline: &ThroughputReport, tolerance: f64) -> Vec<String> {
+        let mut regressions = Vec::new();
+        for run in self.runs.iter() {
+            let Some(base) = baseline.runs.iter().find(|b| b.scene == run.scene) else {
+                continue;
+            };
+            let change = run.rays_per_second() / base.rays_per_second() - 1.0;
+            if change < -tolerance {
+                regressions.push(format!(
+                    "{}: {:.0} rays/s, {:.1}% below baseline {:.0} rays/s",
+                    run.scene,
+                    run.rays_per_second(),
+                    -100.0 * change,
+                    base.rays_per_second()
+                ));
+            }
+        }
+        regressions
+    }
+
+    /// Report as CSV table with header.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("scene,samples,seconds,rays,rays_per_second\n");
+        for r in self.runs.iter() {
+            csv += &format!(
+                "{},{},{},{},{}\n",
+                r.scene,
+                r.samples,
+                r.seconds,
+                r.rays,
+                r.rays_per_second()
+            );
+        }
+        csv
+    }
+
+    /// Parses report from CSV table written by `to_csv`, e.g. a baseline of an earlier version. Returns none if
+    /// invalid.
+    pub fn from_csv(csv: &str) -> Option<Self> {
+        let mut lines = csv.lines();
+        lines.next()?.starts_with("scene,").then_some(())?;
+        let runs = lines
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                let fields: Vec<&str> = line.split(',').collect();
+                Some(Throughput {
+                    scene: fields.first()?.to_string(),
+                    samples: fields.get(1)?.parse().ok()?,
+                    seconds: fields.get(2)?.parse().ok()?,
+                    rays: fields.get(3)?.parse().ok()?,
+                })
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some(ThroughputReport { runs })
+    }
+
+    /// Report as aligned text table for terminals.
+    pub fn to_text(&self) -> String {
+        let mut text = format!(
+            "eden {}\n{:<10} {:>8} {:>10} {:>14} {:>12}\n",
+            env!("CARGO_PKG_VERSION"),
+            "scene",
+            "spp",
+            "seconds",
+            "rays",
+            "Mrays/s"
+        );
+        for r in self.runs.iter() {
+            text += &format!(
+                "{:<10} {:>8} {:>10.3} {:>14} {:>12.3}\n",
+                r.scene,
+                r.samples,
+                r.seconds,
+                r.rays,
+                r.rays_per_second() / 1e6
+            );
+        }
+        text
+    }
+}
+
+/// Standard benchmark scene with given name and its camera, see `SCENES`: the cover scene of random spheres around
+/// three large ones on a gray ground, or the same spheres in the studio. Returns none if unknown.
+pub fn standard_scene(name: &str) -> Option<(Scene, Camera)> {
+    let mut camera = cover_camera();
+    let mut scene = Scene::new();
+    match name {
+        "cover" => {
+            let ground = Material::Lambert(Lambert::new(Color3f::new(0.5, 0.5, 0.5)));
+            scene.add(Shape::preset("ground", ground).unwrap());
+            add_cover_spheres(&mut scene);
+        }
+        "studio" => {
+            add_cover_spheres(&mut scene);
+            studio::setup(&mut scene, &mut camera);
+        }
+        _ => return None,
+    }
+    Some((scene, camera))
+}
+
+/// Render settings of standard benchmark scenes at sample count: path tracer with max depth 50, default seed.
+pub fn standard_settings(samples: u32) -> RenderSettings {
+    let mut settings = RenderSettings::new(samples);
+    settings.set_integrator(Integrator::PathTracer(PathTracer::new(MAX_DEPTH)));
+    settings
+}
+
+/// Camera framing the cover scene of random spheres.
+pub fn cover_camera() -> Camera {
+    let mut camera = Camera::new(1200, 675);
+    camera.set_vfov(20.0);
+    camera.set_look_from(Point3f::new(13.0, 2.0, 3.0));
+    camera.set_look_at(Point3f::new(0.0, 0.0, 0.0));
+    camera.set_view_up(Vector3f::new(0.0, 1.0, 0.0));
+
+    camera.set_defocus_angle(0.6);
+    camera.set_focus_distance(10.0);
+    camera
+}
+
+/// Adds field of small random spheres around three large ones to scene, the same on every call.
+pub fn add_cover_spheres(scene: &mut Scene) {
+    let mut rng = StdRng::seed_from_u64(0);
+    for a in -11..11 {
+        for b in -11..11 {
+            let choose_mat = rng.gen::<f32>();
+            let center = Point3f::new(
+                a as f32 + 0.9 * rng.gen::<f32>(),
+                0.2,
+                b as f32 + 0.9 * rng.gen::<f32>(),
+            );
+
+            if (center - Point3f::new(4.0, 0.2, 0.0)).length() > 0.9 {
+                if choose_mat < 0.8 {
+                    // Diffuse.
+                    let albedo = random_color(&mut rng) * random_color(&mut rng);
+                    let l = Lambert::new(albedo);
+                    scene.add(Shape::Sphere(Sphere::new(
+                        center,
+                        0.2,
+                        Material::Lambert(l),
+                    )));
+                } else if choose_mat < 0.95 {
+                    // Metal.
+                    let albedo = (random_color(&mut rng) / 2.0) + 0.5;
+                    let roughness = rng.gen::<f32>() * 0.5;
+                    let m = Microfacet::metal(albedo, roughness);
+                    scene.add(Shape::Sphere(Sphere::new(
+                        center,
+                        0.2,
+                        Material::Microfacet(m),
+                    )));
+                } else {
+                    // Glass.
+                    let glass = Material::preset("glass").unwrap();
+                    scene.add(Shape::Sphere(Sphere::new(center, 0.2, glass)));
+                }
+            }
+        }
+    }
+
+    let material1 = Material::preset("glass").unwrap();
+    scene.add(Shape::preset("ball", material1).unwrap());
+
+    let material2 = Lambert::new(Color3f::new(0.4, 0.2, 0.1));
+    scene.add(Shape::Sphere(Sphere::new(
+        Point3f::new(-4.0, 1.0, 0.0),
+        1.0,
+        Material::Lambert(material2),
+    )));
+
+    let material3 = Metal::new(Color3f::new(0.7, 0.6, 0.5), 0.0);
+    scene.add(Shape::Sphere(Sphere::new(
+        Point3f::new(4.0, 1.0, 0.0),
+        1.0,
+        Material::Metal(material3),
+    )));
+}
+
+/// Random color with components in [0,1).
+fn random_color(rng: &mut impl Rng) -> Color3f {
+    Color3f::new(
+        rng.gen_range(0.0..1.0),
+        rng.gen_range(0.0..1.0),
+        rng.gen_range(0.0..1.0),
+    )
+}
+
+/// Renders scene at sample count, returning image and render time in seconds.
+fn timed_render(
+    mut camera: Camera,
+    scene: &Scene,
+    mut settings: RenderSettings,
+    samples: u32,
+) -> (Film, f64) {
+    settings.set_samples_per_pixel(samples);
+    let start = Instant::now();
+    let image = camera.render(scene, &settings);
+    (image, start.elapsed().as_secs_f64())
+}
+
+/// Root mean square error of pixel luminance vs. reference image of same size, absolute and relative to mean
+/// luminance of reference.
+pub fn image_error(image: &Film, reference: &Film) -> (f64, f64) {
+    assert_eq!(
+        (image.width(), image.height()),
+        (reference.width(), reference.height())
+    );
+    let count = (image.width() * image.height()) as f64;
+    let (mut squared, mut mean) = (0.0, 0.0);
+    for (a, b) in image.pixels().zip(reference.pixels()) {
+        let (a, b) = (a.luminance() as f64, b.luminance() as f64);
+        squared += (a - b) * (a - b) / count;
+        mean += b / count;
+    }
+    let rmse = squared.sqrt();
+    let relative = if mean > 0.0 { rmse / mean } else { 0.0 };
+    (rmse, relative)
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        base::{color::Color3f, material::Material, point::Point3f, shape::Shape},
+        film::FilmPrecision,
+        materials::lambert::Lambert,
+        shapes::sphere::Sphere,
+    };
+
+    #[test]
+    fn image_error() {
+        let mut image = Film::new(2, 1, FilmPrecision::Full);
+        let mut reference = image.clone();
+        reference.set_pixel(0, 0, Color3f::white());
+        reference.set_pixel(1, 0, Color3f::white());
+        image.set_pixel(0, 0, Color3f::white());
+        assert_eq!(super::image_error(&image, &image), (0.0, 0.0));
+        let (rmse, relative) = super::image_error(&image, &reference);
+        assert!((rmse - 0.5f64.sqrt()).abs() < 1e-6);
+        assert!((relative - rmse).abs() < 1e-6);
+    }
+
+    #[test]
+    fn measure() {
+        let mut scene = Scene::new();
+        let gray = Material::Lambert(Lambert::new(Color3f::new(0.5, 0.5, 0.5)));
+        scene.add(Shape::Sphere(Sphere::new(Point3f::default(), 0.5, gray)));
+        let mut camera = Camera::new(8, 8);
+        camera.set_look_from(Point3f::new(0.0, 0.0, 2.0));
+        let settings = RenderSettings::default();
+        let report = BenchmarkReport::measure(&camera, &scene, &settings, &[1, 16], 256);
+
+        // Error shrinks with sample count.
+        let runs = report.runs();
+        assert_eq!(runs.len(), 2);
+        assert!(runs[1].rmse < runs[0].rmse && runs[1].rmse > 0.0);
+        assert_eq!(report.reference_samples(), 256);
+        assert_eq!(report.to_csv().lines().count(), 3);
+        assert!(report.to_json().contains("\"runs\":[{\"samples\":1,"));
+        assert_eq!(report.to_text().lines().count(), 4);
+    }
+
+    #[test]
+    fn throughput() {
+        let report = ThroughputReport::measure(&SCENES, (8, 4), 2);
+        let runs = report.runs();
+        assert_eq!(runs.len(), SCENES.len());
+        assert!(runs
+            .iter()
+            .all(|r| r.rays >= 8 * 4 * 2 && r.rays_per_second() > 0.0));
+        assert!(standard_scene("teapot").is_none());
+
+        // Reports survive CSV, and compare to baselines by rays per second.
+        let parsed = ThroughputReport::from_csv(&report.to_csv()).unwrap();
+        assert_eq!(parsed.runs().len(), runs.len());
+        assert_eq!(parsed.runs()[1].rays, runs[1].rays);
+        assert!(report.regressions(&parsed, 0.01).is_empty());
+        let mut faster = parsed.clone();
+        faster.runs[0].seconds = runs[0].seconds / 2.0;
+        let regressions = report.regressions(&faster, 0.1);
+        assert_eq!(regressions.len(), 1);
+        assert!(regressions[0].starts_with("cover: "));
+        assert!(ThroughputReport::from_csv("samples,seconds\n").is_none());
+    }
+}
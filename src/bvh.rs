@@ -0,0 +1,653 @@
+use crate::base::{
+    aabb::Aabb,
+    interval::Interval,
+    packet::{AabbPacket, SpherePacket, LANES},
+    point::Point3f,
+    ray::Ray,
+    shape::{Bounded, Intersectable, Intersection, Shape},
+};
+
+/// Number of bins along split axis evaluated by surface area heuristic.
+const BINS: usize = 12;
+
+/// Cost of traversing a node relative to intersecting an object.
+const TRAVERSAL_COST: f32 = 1.0;
+
+/// Deepest node level, nodes there become leaves regardless of object count.
+const MAX_DEPTH: usize = 32;
+
+/// Bounding volume hierarchy over shapes, built by binned surface area heuristic (SAH): each node splits its objects
+/// along the longest axis of their centroids where the expected cost of intersecting both children is least. With
+/// packets enabled, traversal tests four node bounds or spheres at once, see `base::packet`.
+#[derive(Clone, Debug, Default)]
+pub struct Bvh {
+    /// Nodes in depth-first order, root first.
+    nodes: Vec<Node>,
+
+    /// Object indices, grouped by leaf.
+    indices: Vec<usize>,
+
+    /// Packets of consecutive objects of each leaf, if enabled.
+    packets: Vec<Packet>,
+
+    /// Descendants of interior nodes tested at once, if packets are enabled.
+    wide: Vec<Wide>,
+
+    /// Build statistics, cost updated by refits.
+    stats: BvhStats,
+
+    /// Expected cost of intersecting a ray at build, see `BvhStats::sah_cost`.
+    built_cost: f32,
+}
+
+/// Statistics of a built hierarchy, e.g. for benchmarking builders.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct BvhStats {
+    /// Count of nodes including leaves.
+    pub nodes: usize,
+
+    /// Count of leaves.
+    pub leaves: usize,
+
+    /// Count of node levels, zero for an empty hierarchy.
+    pub depth: usize,
+
+    /// Expected cost of intersecting a ray by surface area heuristic, in units of object intersections. Linear search
+    /// costs the object count.
+    pub sah_cost: f32,
+}
+
+/// Node of hierarchy.
+#[derive(Clone, Copy, Debug)]
+struct Node {
+    /// Bounds of all objects below node.
+    bounds: Aabb,
+
+    /// Leaf or interior node.
+    kind: NodeKind,
+}
+
+/// Content of node.
+#[derive(Clone, Copy, Debug)]
+enum NodeKind {
+    /// Range of object indices, and start of their packets if enabled.
+    Leaf {
+        start: usize,
+        count: usize,
+        packets: usize,
+    },
+
+    /// Two children, the first one directly following the node, and gathered descendants if packets are enabled.
+    Interior { second: usize, wide: usize },
+}
+
+/// Objects of a leaf tested at once.
+#[derive(Clone, Copy, Debug)]
+enum Packet {
+    /// Only spheres, tested exactly.
+    Spheres(SpherePacket),
+
+    /// Any shapes, tested by bounds.
+    Boxes(AabbPacket),
+}
+
+impl Packet {
+    /// Packet of at most four objects, exact if all are spheres at rest.
+    fn new(objects: &[Shape], indices: &[usize]) -> Self {
+        let spheres: Option<Vec<_>> = indices
+            .iter()
+            .map(|&i| match &objects[i] {
+                Shape::Sphere(s) if !s.is_moving() => Some((s.center(), s.radius())),
+                _ => None,
+            })
+            .collect();
+        match spheres {
+            Some(spheres) => Packet::Spheres(SpherePacket::new(&spheres)),
+            None => {
+                let boxes: Vec<Aabb> = indices.iter().map(|&i| objects[i].bounding_box()).collect();
+                Packet::Boxes(AabbPacket::new(&boxes))
+            }
+        }
+    }
+}
+
+/// Up to four descendants of an interior node, gathered by replacing the child of largest surface area by its
+/// children while it is an interior node. Traversal skips the levels in between.
+#[derive(Clone, Copy, Debug)]
+struct Wide {
+    /// Indices of descendant nodes.
+    children: [usize; LANES],
+
+    /// Count of descendants.
+    count: usize,
+
+    /// Bounds of descendants.
+    bounds: AabbPacket,
+}
+
+impl Wide {
+    /// Gathers descendants of interior node at index.
+    fn new(nodes: &[Node], index: usize) -> Self {
+        let NodeKind::Interior { second, .. } = nodes[index].kind else {
+            unreachable!()
+        };
+        let mut children = vec![index + 1, second];
+        while children.len() < LANES {
+            let interior = (0..children.len())
+                .filter(|&c| matches!(nodes[children[c]].kind, NodeKind::Interior { .. }))
+                .max_by(|&a, &b| {
+                    let area = |c: usize| nodes[children[c]].bounds.surface_area();
+                    area(a).total_cmp(&area(b))
+                });
+            let Some(position) = interior else {
+                break;
+            };
+            let child = children.remove(position);
+            let NodeKind::Interior { second, .. } = nodes[child].kind else {
+                unreachable!()
+            };
+            children.extend([child + 1, second]);
+        }
+        let bounds: Vec<Aabb> = children.iter().map(|&c| nodes[c].bounds).collect();
+        let mut wide = Wide {
+            children: [0; LANES],
+            count: children.len(),
+            bounds: AabbPacket::new(&bounds),
+        };
+        wide.children[..children.len()].copy_from_slice(&children);
+        wide
+    }
+}
+
+/// Nearest hit found so far during traversal.
+enum Nearest<'a> {
+    /// Intersection with object at index.
+    Object(usize, Intersection<'a>),
+
+    /// Hit of sphere at index by a packet, at the closest ray parameter.
+    Sphere(usize),
+}
+
+/// Object during build.
+#[derive(Clone, Copy, Debug)]
+struct Primitive {
+    /// Index of object.
+    index: usize,
+
+    /// Bounds of object.
+    bounds: Aabb,
+
+    /// Center of bounds.
+    centroid: Point3f,
+}
+
+impl Bvh {
+    /// Builds hierarchy over objects with at most given count of objects per leaf, unless objects cannot be split,
+    /// e.g. if their centroids coincide. Smaller leaves trade build time and memory for faster intersection. Packets
+    /// test descendants of interior nodes and objects of leaves four at a time, e.g. disabled to benchmark their
+    /// speedup.
+    pub fn build(objects: &[Shape], max_leaf_size: usize, packets: bool) -> Self {
+        assert!(max_leaf_size > 0);
+        let mut primitives: Vec<Primitive> = objects
+            .iter()
+            .enumerate()
+            .map(|(index, object)| {
+                let bounds = object.bounding_box();
+                Primitive {
+                    index,
+                    bounds,
+                    centroid: bounds.min() + bounds.size() * 0.5,
+                }
+            })
+            .collect();
+        let mut bvh = Bvh::default();
+        if !primitives.is_empty() {
+            bvh.build_node(&mut primitives, 0, max_leaf_size, 1);
+        }
+        bvh.indices = primitives.iter().map(|p| p.index).collect();
+        if packets {
+            for index in 0..bvh.nodes.len() {
+                match bvh.nodes[index].kind {
+                    NodeKind::Leaf { start, count, .. } => {
+                        let chunks = bvh.indices[start..start + count].chunks(LANES);
+                        bvh.nodes[index].kind = NodeKind::Leaf {
+                            start,
+                            count,
+                            packets: bvh.packets.len(),
+                        };
+                        bvh.packets
+                            .extend(chunks.map(|chunk| Packet::new(objects, chunk)));
+                    }
+                    NodeKind::Interior { second, .. } => {
+                        let wide = Wide::new(&bvh.nodes, index);
+                        bvh.nodes[index].kind = NodeKind::Interior {
+                            second,
+                            wide: bvh.wide.len(),
+                        };
+                        bvh.wide.push(wide);
+                    }
+                }
+            }
+        }
+        bvh.stats.sah_cost = bvh.sah_cost();
+        bvh.built_cost = bvh.stats.sah_cost;
+        bvh
+    }
+
+    /// Refits hierarchy to objects after they moved or changed shape, keeping its structure: bounds of nodes and
+    /// packets are updated bottom-up. Much faster than building, but objects moved far from where they were at
+    /// build leave nodes overlapping, see `degradation`. Objects must be as many as at build.
+    pub fn refit(&mut self, objects: &[Shape]) {
+        assert_eq!(objects.len(), self.indices.len());
+
+        // Descendants follow their ancestors, so are refitted first.
+        for index in (0..self.nodes.len()).rev() {
+            match self.nodes[index].kind {
+                NodeKind::Leaf {
+                    start,
+                    count,
+                    packets,
+                } => {
+                    let indices = &self.indices[start..start + count];
+                    self.nodes[index].bounds = indices
+                        .iter()
+                        .skip(1)
+                        .fold(objects[indices[0]].bounding_box(), |b, &i| {
+                            b.union(&objects[i].bounding_box())
+                        });
+                    if packets != usize::MAX {
+                        for (chunk, indices) in indices.chunks(LANES).enumerate() {
+                            self.packets[packets + chunk] = Packet::new(objects, indices);
+                        }
+                    }
+                }
+                NodeKind::Interior { second, wide } => {
+                    self.nodes[index].bounds = self.nodes[index + 1]
+                        .bounds
+                        .union(&self.nodes[second].bounds);
+                    if wide != usize::MAX {
+                        self.wide[wide] = Wide::new(&self.nodes, index);
+                    }
+                }
+            }
+        }
+        self.stats.sah_cost = self.sah_cost();
+    }
+
+    /// Factor by which refits raised the expected cost of intersecting a ray since build, one if not refitted.
+    pub fn degradation(&self) -> f32 {
+        if self.built_cost > 0.0 {
+            self.stats.sah_cost / self.built_cost
+        } else {
+            1.0
+        }
+    }
+
+    /// Build statistics, with cost of intersecting a ray updated by refits.
+    pub fn stats(&self) -> BvhStats {
+        self.stats
+    }
+
+    /// Nodes in depth-first order for traversal outside of Rust, e.g. on the GPU: bounds, and range of `indices` for
+    /// leaves or index of second child with count zero for interior nodes.
+    #[cfg(feature = "gpu")]
+    pub(crate) fn flat_nodes(&self) -> impl Iterator<Item = (Aabb, usize, usize)> + '_ {
+        self.nodes.iter().map(|node| match node.kind {
+            NodeKind::Leaf { start, count, .. } => (node.bounds, start, count),
+            NodeKind::Interior { second, .. } => (node.bounds, second, 0),
+        })
+    }
+
+    /// Object indices, grouped by leaf.
+    #[cfg(feature = "gpu")]
+    pub(crate) fn indices(&self) -> &[usize] {
+        &self.indices
+    }
+
+    /// Closest intersection of ray with objects the hierarchy was built from. Returns index of object and
+    /// intersection, or none if ray misses all objects within interval.
+    pub fn intersect<'a>(
+        &self,
+        objects: &'a [Shape],
+        ray: Ray,
+        ray_t: Interval,
+    ) -> Option<(usize, Intersection<'a>)> {
+        if self.nodes.is_empty() {
+            return None;
+        }
+        let root = self.nodes[0].bounds.hit(ray, ray_t)?;
+        let mut nearest = None;
+        let mut closest_t = ray_t.end();
+
+        // Nodes to visit with ray parameter where ray enters them, culled once a closer hit is found. Each level of
+        // traversal leaves at most three nodes behind.
+        let mut stack = [(0u32, root.start()); (LANES - 1) * MAX_DEPTH + 1];
+        let mut size = 1;
+        while size > 0 {
+            size -= 1;
+            let (index, entry) = stack[size];
+            let index = index as usize;
+            if entry > closest_t {
+                continue;
+            }
+            match self.nodes[index].kind {
+                NodeKind::Leaf {
+                    start,
+                    count,
+                    packets,
+                } => {
+                    for (chunk, indices) in
+                        self.indices[start..start + count].chunks(LANES).enumerate()
+                    {
+                        let t = Interval::new(ray_t.start(), closest_t);
+                        let entries = match self.packets.get(packets + chunk) {
+                            // Sphere hits are exact, thus only the nearest one is kept, without surface details
+                            // until it is known to be the closest hit.
+                            Some(Packet::Spheres(packet)) => {
+                                let hits = packet.hit(ray, t);
+                                let lane =
+                                    (1..LANES)
+                                        .fold(0, |a, l| if hits[l] < hits[a] { l } else { a });
+                                if hits[lane] < closest_t {
+                                    nearest = Some(Nearest::Sphere(indices[lane]));
+                                    closest_t = hits[lane];
+                                }
+                                continue;
+                            }
+                            Some(Packet::Boxes(packet)) => packet.hit(ray, t),
+                            None => [ray_t.start(); LANES],
+                        };
+
+                        // Objects not culled by packet are intersected one by one.
+                        for (&index, &entry) in indices.iter().zip(&entries) {
+                            if entry >= closest_t {
+                                continue;
+                            }
+                            let t = Interval::new(ray_t.start(), closest_t);
+                            if let Some(i) = objects[index].intersect(ray, t) {
+                                closest_t = i.t;
+                                nearest = Some(Nearest::Object(index, i));
+                            }
+                        }
+                    }
+                }
+                NodeKind::Interior { second, wide } => {
+                    // Test up to four descendants at once if gathered, else both children.
+                    let t = Interval::new(ray_t.start(), closest_t);
+                    let mut children = [(0, f32::INFINITY); LANES];
+                    let count = match self.wide.get(wide) {
+                        Some(wide) => {
+                            let entries = wide.bounds.hit(ray, t);
+                            for lane in 0..wide.count {
+                                children[lane] = (wide.children[lane], entries[lane]);
+                            }
+                            wide.count
+                        }
+                        None => {
+                            for (lane, child) in [index + 1, second].into_iter().enumerate() {
+                                let hit = self.nodes[child].bounds.hit(ray, t);
+                                children[lane] = (child, hit.map_or(f32::INFINITY, |i| i.start()));
+                            }
+                            2
+                        }
+                    };
+
+                    // Push hit children farthest first by insertion, so nearest is visited first.
+                    let bottom = size;
+                    for &(child, entry) in &children[..count] {
+                        if entry == f32::INFINITY {
+                            continue;
+                        }
+                        let mut position = size;
+                        while position > bottom && stack[position - 1].1 < entry {
+                            stack[position] = stack[position - 1];
+                            position -= 1;
+                        }
+                        stack[position] = (child as u32, entry);
+                        size += 1;
+                    }
+                }
+            }
+        }
+        nearest.map(|nearest| match nearest {
+            Nearest::Object(index, i) => (index, i),
+            Nearest::Sphere(index) => match &objects[index] {
+                Shape::Sphere(sphere) => (index, sphere.intersection_at(ray, closest_t)),
+                _ => unreachable!(),
+            },
+        })
+    }
+
+    /// Builds node over primitives, which start at offset into all primitives. Returns index of node.
+    fn build_node(
+        &mut self,
+        primitives: &mut [Primitive],
+        offset: usize,
+        max_leaf_size: usize,
+        depth: usize,
+    ) -> usize {
+        let bounds = primitives
+            .iter()
+            .skip(1)
+            .fold(primitives[0].bounds, |b, p| b.union(&p.bounds));
+        let index = self.nodes.len();
+        self.nodes.push(Node {
+            bounds,
+            kind: NodeKind::Leaf {
+                start: offset,
+                count: primitives.len(),
+                packets: usize::MAX,
+            },
+        });
+        self.stats.nodes += 1;
+        self.stats.depth = self.stats.depth.max(depth);
+
+        // Split along longest axis of centroid bounds, unless centroids coincide.
+        let centroids = primitives.iter().fold(
+            Aabb::new(primitives[0].centroid, primitives[0].centroid),
+            |b, p| b.union(&Aabb::new(p.centroid, p.centroid)),
+        );
+        let extent = centroids.size();
+        let axis = extent.max_dimension();
+        if primitives.len() <= max_leaf_size || extent[axis] == 0.0 || depth == MAX_DEPTH {
+            self.stats.leaves += 1;
+            return index;
+        }
+
+        // Bin centroids, then pick split between bins of least cost.
+        let start = coordinate(centroids.min(), axis);
+        let bin = |p: &Primitive| {
+            let b = (coordinate(p.centroid, axis) - start) / extent[axis] * BINS as f32;
+            (b as usize).min(BINS - 1)
+        };
+        let mut bins: [(usize, Option<Aabb>); BINS] = [(0, None); BINS];
+        for p in primitives.iter() {
+            let (count, bounds) = &mut bins[bin(p)];
+            *count += 1;
+            *bounds = Some(bounds.map_or(p.bounds, |b| b.union(&p.bounds)));
+        }
+        let cost = |side: &[(usize, Option<Aabb>)]| {
+            let (count, bounds) = side.iter().fold((0, None), |(c, b), (count, bounds)| {
+                let b = match (b, bounds) {
+                    (Some(b), Some(bounds)) => Some(bounds.union(&b)),
+                    (b, bounds) => b.or(*bounds),
+                };
+                (c + count, b)
+            });
+            count as f32 * bounds.map_or(0.0, |b: Aabb| b.surface_area())
+        };
+        let split = (1..BINS)
+            .map(|i| (i, cost(&bins[..i]) + cost(&bins[i..])))
+            .fold(
+                (1, f32::INFINITY),
+                |best, c| if c.1 < best.1 { c } else { best },
+            )
+            .0;
+
+        primitives.sort_unstable_by_key(bin);
+        let middle = primitives.partition_point(|p| bin(p) < split);
+        if middle == 0 || middle == primitives.len() {
+            // Only non-finite centroids, e.g. of shapes with NaN coordinates, leave a child empty.
+            self.stats.leaves += 1;
+            return index;
+        }
+        let (left, right) = primitives.split_at_mut(middle);
+        self.build_node(left, offset, max_leaf_size, depth + 1);
+        let second = self.build_node(right, offset + middle, max_leaf_size, depth + 1);
+        self.nodes[index].kind = NodeKind::Interior {
+            second,
+            wide: usize::MAX,
+        };
+        index
+    }
+
+    /// Expected cost of intersecting ray with hierarchy, given it hits the root: nodes are hit with probability of
+    /// their surface area relative to the root.
+    fn sah_cost(&self) -> f32 {
+        let Some(root) = self.nodes.first() else {
+            return 0.0;
+        };
+        let root_area = root.bounds.surface_area();
+        self.nodes
+            .iter()
+            .map(|node| {
+                let probability = if root_area > 0.0 {
+                    node.bounds.surface_area() / root_area
+                } else {
+                    1.0
+                };
+                match node.kind {
+                    NodeKind::Leaf { count, .. } => probability * count as f32,
+                    NodeKind::Interior { .. } => probability * TRAVERSAL_COST,
+                }
+            })
+            .sum()
+    }
+}
+
+/// Coordinate of point along axis.
+fn coordinate(p: Point3f, axis: usize) -> f32 {
+    [p.x(), p.y(), p.z()][axis]
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        base::{material::Material, vector::Vector3f},
+        shapes::{sphere::Sphere, torus::Torus},
+    };
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    /// Random small spheres in a cube.
+    fn spheres(count: usize) -> Vec<Shape> {
+        let mut rng = StdRng::seed_from_u64(42);
+        (0..count)
+            .map(|_| {
+                let center = Point3f::new(
+                    rng.gen_range(-10.0..10.0),
+                    rng.gen_range(-10.0..10.0),
+                    rng.gen_range(-10.0..10.0),
+                );
+                Shape::Sphere(Sphere::new(center, rng.gen_range(0.1..0.5), Material::None))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn intersect() {
+        // Same hits as linear search.
+        let mut objects = spheres(500);
+        objects.push(Shape::Torus(Torus::new(
+            Point3f::default(),
+            Vector3f::new(0.0, 1.0, 0.0),
+            3.0,
+            0.5,
+            Material::None,
+        )));
+        let packed = Bvh::build(&objects, 4, true);
+        let scalar = Bvh::build(&objects, 4, false);
+        let mut rng = StdRng::seed_from_u64(7);
+        let all = Interval::new(0.001, f32::INFINITY);
+        let mut hits = 0;
+        for _ in 0..2000 {
+            let origin = Point3f::new(
+                rng.gen_range(-12.0..12.0),
+                rng.gen_range(-12.0..12.0),
+                rng.gen_range(-12.0..12.0),
+            );
+            let direction = Vector3f::new(
+                rng.gen_range(-1.0..1.0),
+                rng.gen_range(-1.0..1.0),
+                rng.gen_range(-1.0..1.0),
+            );
+            let ray = Ray::new(origin, direction.normalize());
+            let linear = objects
+                .iter()
+                .enumerate()
+                .filter_map(|(index, o)| o.intersect(ray, all).map(|i| (index, i.t)))
+                .min_by(|a, b| a.1.total_cmp(&b.1));
+            let found = packed
+                .intersect(&objects, ray, all)
+                .map(|(index, i)| (index, i.t));
+            assert_eq!(found, linear);
+            let unpacked = scalar.intersect(&objects, ray, all);
+            assert_eq!(unpacked.map(|(index, i)| (index, i.t)), linear);
+            hits += found.is_some() as usize;
+        }
+        assert!(hits > 100);
+        let ray = Ray::new(Point3f::default(), Vector3f::new(1.0, 0.0, 0.0));
+        assert!(Bvh::build(&[], 4, true).intersect(&[], ray, all).is_none());
+    }
+
+    #[test]
+    fn refit() {
+        // Refitted hierarchy finds same hits as one built over moved objects, at higher cost.
+        let mut objects = spheres(500);
+        let mut bvh = Bvh::build(&objects, 4, true);
+        let mut scalar = Bvh::build(&objects, 4, false);
+        let mut rng = StdRng::seed_from_u64(3);
+        for object in objects.iter_mut().step_by(5) {
+            let offset = Vector3f::new(rng.gen_range(-8.0..8.0), 0.0, rng.gen_range(-8.0..8.0));
+            object.set_center(object.center() + offset);
+        }
+        bvh.refit(&objects);
+        scalar.refit(&objects);
+        let built = Bvh::build(&objects, 4, true);
+        assert!(bvh.degradation() > 1.0);
+        assert_eq!(built.degradation(), 1.0);
+        let all = Interval::new(0.001, f32::INFINITY);
+        for _ in 0..2000 {
+            let origin = Point3f::new(rng.gen_range(-12.0..12.0), 12.0, rng.gen_range(-12.0..12.0));
+            let direction = Vector3f::new(rng.gen_range(-1.0..1.0), -1.0, rng.gen_range(-1.0..1.0));
+            let ray = Ray::new(origin, direction.normalize());
+            let expected = built.intersect(&objects, ray, all).map(|(i, h)| (i, h.t));
+            assert_eq!(
+                bvh.intersect(&objects, ray, all).map(|(i, h)| (i, h.t)),
+                expected
+            );
+            assert_eq!(
+                scalar.intersect(&objects, ray, all).map(|(i, h)| (i, h.t)),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn stats() {
+        let objects = spheres(1000);
+        let stats = Bvh::build(&objects, 4, true).stats();
+        assert_eq!(stats.nodes, 2 * stats.leaves - 1);
+        assert!(stats.leaves >= 250 && stats.depth < 30);
+        assert!(stats.sah_cost > 1.0 && stats.sah_cost < 100.0);
+
+        // Larger leaves make fewer nodes at higher cost, one leaf is linear search.
+        let coarse = Bvh::build(&objects, 16, true).stats();
+        assert!(coarse.nodes < stats.nodes && coarse.sah_cost > stats.sah_cost);
+        let single = Bvh::build(&objects, 1000, true).stats();
+        assert_eq!((single.nodes, single.leaves, single.depth), (1, 1, 1));
+        assert_eq!(single.sah_cost, 1000.0);
+        assert_eq!(Bvh::build(&[], 4, true).stats(), BvhStats::default());
+    }
+}
@@ -1,21 +1,101 @@
 use crate::{
     base::{
-        color::Color3f, interval::Interval, material::Interactable, point::Point3f, ray::Ray,
-        shape::Intersectable, vector::Vector3f,
+        aabb::Aabb,
+        color::Color3f,
+        point::Point3f,
+        ray::Ray,
+        sampling::{concentric_disk, uniform_triangle},
+        shape::Bounded,
+        vector::Vector3f,
     },
+    checkpoint::Accumulation,
+    film::Film,
+    integrator::Estimator,
+    noise::{NoiseAccumulator, NoiseReport, PixelMoments},
     scene::Scene,
+    settings::RenderSettings,
+    stats::RenderStats,
+    stream::{RenderHandle, RenderStream},
+    tile::Tile,
 };
-use rand::{thread_rng, Rng};
-use rayon::{
-    iter::{IndexedParallelIterator, ParallelIterator},
-    slice::ParallelSliceMut,
-};
-use std::{
-    io::Write,
-    sync::atomic::{AtomicU32, Ordering},
+use rand::Rng;
+use rayon::prelude::*;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, Mutex,
 };
 
-/// Perspective camera in 3-dim space.
+/// Callback receiving finished tiles with their pixels, returns whether rendering continues.
+type TileObserver<'a> = dyn Fn(&Tile, &[Color3f]) -> bool + Sync + 'a;
+
+/// Policy for framing images whose aspect ratio differs from the camera's.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum AspectPolicy {
+    /// Whole camera frame stays visible, field of view widens along the longer image side.
+    #[default]
+    Fit,
+
+    /// Camera frame covers whole image, parts of it are cropped along the shorter image side.
+    Fill,
+}
+
+/// Projection of scene onto image plane.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Projection {
+    /// Rays diverge from lens through pixels, distant objects appear smaller.
+    #[default]
+    Perspective,
+
+    /// Rays are parallel along view direction, objects keep their size regardless of distance, e.g. for technical
+    /// drawings or checking geometry scale. Field of view and defocus blur do not apply.
+    Orthographic {
+        /// Height of view in scene units.
+        view_height: f32,
+    },
+}
+
+/// Shape of lens aperture, which out-of-focus highlights (bokeh) take on.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Aperture {
+    /// Ideal round aperture.
+    #[default]
+    Circular,
+
+    /// Regular polygon formed by diaphragm blades, as in real lenses stopped down.
+    Polygonal {
+        /// Count of blades, at least three.
+        blades: u32,
+
+        /// Rotation in degrees counter-clockwise, a corner points up at zero.
+        rotation: f32,
+    },
+}
+
+/// Pixel grid and lens of camera in space, for generating camera rays outside of `Camera`, e.g. on the GPU.
+#[cfg(feature = "gpu")]
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct RayFrame {
+    /// Location of top left pixel (0,0).
+    pub pixel00_location: Point3f,
+
+    /// Offset to pixel to the right.
+    pub pixel_delta_u: Vector3f,
+
+    /// Offset to pixel below.
+    pub pixel_delta_v: Vector3f,
+
+    /// Center of lens.
+    pub look_from: Point3f,
+
+    /// Defocus disk horizontal basis, zero without defocus blur.
+    pub defocus_disk_u: Vector3f,
+
+    /// Defocus disk vertical basis, zero without defocus blur.
+    pub defocus_disk_v: Vector3f,
+}
+
+/// Perspective or orthographic camera in 3-dim space.
+#[derive(Clone)]
 pub struct Camera {
     /// Image width in pixels.
     image_width: u32,
@@ -23,11 +103,14 @@ pub struct Camera {
     /// Image height in pixels.
     image_height: u32,
 
-    /// Count of random samples per pixel.
-    samples_per_pixel: u32,
+    /// Resolution rendered instead of image resolution, keeping the framing.
+    resolution_override: Option<(u32, u32)>,
 
-    /// Max number of recursive ray bounces into scene.
-    max_depth: u32,
+    /// Framing policy if resolution override has different aspect ratio.
+    aspect_policy: AspectPolicy,
+
+    /// Projection onto image plane.
+    projection: Projection,
 
     /// Vertical view angle.
     vfov: f32,
@@ -47,6 +130,15 @@ pub struct Camera {
     /// Distance from 'look from' point to plane of perfect focus.
     focus_distance: f32,
 
+    /// Shape of lens aperture.
+    aperture: Aperture,
+
+    /// Horizontal squeeze of aperture, stretching bokeh vertically as anamorphic lenses do. One is no squeeze.
+    anamorphic_squeeze: f32,
+
+    /// Times shutter opens and closes, in frame durations since start of frame.
+    shutter: (f32, f32),
+
     /// Location of top left pixel (0,0).
     pixel00_location: Point3f,
 
@@ -56,6 +148,9 @@ pub struct Camera {
     /// Offset to pixel below.
     pixel_delta_v: Vector3f,
 
+    /// Offset from plane of perfect focus back to lens.
+    focus_offset: Vector3f,
+
     /// Defocus disk horizontal basis.
     defocus_disk_u: Vector3f,
 
@@ -70,64 +165,269 @@ impl Camera {
         Camera {
             image_width,
             image_height,
-            samples_per_pixel: 10,
-            max_depth: 10,
+            resolution_override: None,
+            aspect_policy: AspectPolicy::Fit,
+            projection: Projection::Perspective,
             vfov: 90.0,
             look_from: Point3f::new(0.0, 0.0, -1.0),
             look_at: Point3f::default(),
             view_up: Vector3f::new(0.0, 1.0, 0.0),
             defocus_angle: 0.0,
             focus_distance: 1.0,
+            aperture: Aperture::Circular,
+            anamorphic_squeeze: 1.0,
+            shutter: (0.0, 0.0),
             pixel00_location: Point3f::default(),
             pixel_delta_u: Vector3f::default(),
             pixel_delta_v: Vector3f::default(),
+            focus_offset: Vector3f::default(),
             defocus_disk_u: Vector3f::default(),
             defocus_disk_v: Vector3f::default(),
         }
     }
 
-    /// Renders scene.
-    pub fn render(&mut self, scene: &Scene) -> Vec<Color3f> {
+    /// Renders scene with settings.
+    pub fn render(&mut self, scene: &Scene, settings: &RenderSettings) -> Film {
         self.initialize();
-        let mut pixels = vec![Color3f::black(); (self.image_width * self.image_height) as usize];
+        let (width, height) = self.resolution();
+        let mut film = Film::new(width, height, settings.film_precision());
+        let tiles = settings.tiles(width, height);
+        self.render_tiles(scene, settings, &mut film, &tiles, None, None, None);
+        film
+    }
+
+    /// Renders scene with settings, handing each finished tile with its pixels (row-major) to observer, e.g. to show
+    /// rendering progress. Rendering stops early once observer returns false, tiles not yet rendered stay black.
+    pub fn render_observed<F>(
+        &mut self,
+        scene: &Scene,
+        settings: &RenderSettings,
+        observer: F,
+    ) -> Film
+    where
+        F: Fn(&Tile, &[Color3f]) -> bool + Sync,
+    {
+        self.initialize();
+        let (width, height) = self.resolution();
+        let mut film = Film::new(width, height, settings.film_precision());
+        let tiles = settings.tiles(width, height);
+        self.render_tiles(
+            scene,
+            settings,
+            &mut film,
+            &tiles,
+            None,
+            None,
+            Some(&observer),
+        );
+        film
+    }
+
+    /// Renders only given tiles of scene, leaving all other pixels black, e.g. a share of a render distributed across
+    /// machines. Pixels equal those of a full render, as their samples depend on pixel position and seed only.
+    pub fn render_region(
+        &mut self,
+        scene: &Scene,
+        settings: &RenderSettings,
+        tiles: &[Tile],
+    ) -> Film {
+        self.initialize();
+        let (width, height) = self.resolution();
+        let mut film = Film::new(width, height, settings.film_precision());
+        self.render_tiles(scene, settings, &mut film, tiles, None, None, None);
+        film
+    }
 
-        // Render loop.
-        let progress = AtomicU32::new(0);
-        pixels
-            .par_chunks_mut(self.image_width as usize)
+    /// Adds up to given number of samples per pixel to accumulation of scene, stopping at the sample count of each
+    /// pixel. Returns whether all pixels have reached their sample count. Accumulation must match camera resolution.
+    pub fn render_accumulated(
+        &mut self,
+        scene: &Scene,
+        settings: &RenderSettings,
+        accumulation: &mut Accumulation,
+        samples: u32,
+    ) -> bool {
+        assert!((accumulation.width(), accumulation.height()) == self.resolution());
+        self.initialize();
+        let width = accumulation.width() as usize;
+        let this = &*self;
+        let integrator = settings.integrator();
+        accumulation
+            .pixels_mut()
+            .par_chunks_mut(width)
             .enumerate()
-            .for_each(|(y, line)| {
-                line.iter_mut().enumerate().for_each(|(x, pixel)| {
-                    // Multi sample rendering.
-                    for _ in 0..self.samples_per_pixel {
-                        let ray = self.get_ray(x as u32, y as u32);
-                        *pixel += self.ray_color(ray, self.max_depth, &scene);
+            .map(|(y, row)| {
+                let y = y as u32;
+                let mut done = true;
+                for (x, pixel) in row.iter_mut().enumerate() {
+                    let x = x as u32;
+                    let target = settings.samples_at(x, y);
+                    let first = pixel.samples.min(target);
+                    let end = target.min(first.saturating_add(samples));
+                    let mut rng = settings.pixel_sampler(x, y, first);
+                    for s in first..end {
+                        rng.start_sample(s);
+                        let ray = this.get_ray(x, y, &mut rng);
+                        pixel.sum +=
+                            settings.clamp_sample(integrator.radiance(ray, scene, &mut rng));
                     }
+                    pixel.samples = pixel.samples.max(end);
+                    done &= end == target;
+                }
+                done
+            })
+            .reduce(|| true, |a, b| a && b)
+    }
 
-                    // Average samples.
-                    *pixel = *pixel / self.samples_per_pixel as f32;
-                });
+    /// Renders scene with settings on a background thread, returning a stream of finished tiles and the final image
+    /// to await, e.g. from async web services or GUIs without blocking their runtime.
+    pub fn render_async(&self, scene: Arc<Scene>, settings: &RenderSettings) -> RenderStream {
+        RenderStream::spawn(self.clone(), scene, settings.clone())
+    }
 
-                // Progress stdout.
-                let progress = progress.fetch_add(1, Ordering::Relaxed);
-                print!(
-                    "\r{:.2}%",
-                    progress as f32 / (self.image_height - 1) as f32 * 100.0
-                );
-                std::io::stdout().flush().unwrap();
-            });
+    /// Starts rendering scene with settings on a background thread, returning a handle to manage the render as a
+    /// job: pause, resume or cancel it, and query its progress, path statistics and partial image.
+    pub fn start_render(&self, scene: Arc<Scene>, settings: &RenderSettings) -> RenderHandle {
+        RenderHandle::spawn(self.clone(), scene, settings.clone())
+    }
+
+    /// Renders scene, counting path events into stats and handing finished tiles to observer, see
+    /// `render_observed`.
+    pub(crate) fn render_observed_with_stats<F>(
+        &mut self,
+        scene: &Scene,
+        settings: &RenderSettings,
+        stats: &Mutex<RenderStats>,
+        observer: F,
+    ) -> Film
+    where
+        F: Fn(&Tile, &[Color3f]) -> bool + Sync,
+    {
+        self.initialize();
+        let (width, height) = self.resolution();
+        let mut film = Film::new(width, height, settings.film_precision());
+        let tiles = settings.tiles(width, height);
+        self.render_tiles(
+            scene,
+            settings,
+            &mut film,
+            &tiles,
+            None,
+            Some(stats),
+            Some(&observer),
+        );
+        film
+    }
+
+    /// Renders scene with settings, estimating image error at sample count checkpoints during rendering.
+    pub fn render_with_noise_report(
+        &mut self,
+        scene: &Scene,
+        settings: &RenderSettings,
+    ) -> (Film, NoiseReport) {
+        self.initialize();
+        let (width, height) = self.resolution();
+        let mut film = Film::new(width, height, settings.film_precision());
+        let accumulator = NoiseAccumulator::new(settings.samples_per_pixel());
+        let tiles = settings.tiles(width, height);
+        self.render_tiles(
+            scene,
+            settings,
+            &mut film,
+            &tiles,
+            Some(&accumulator),
+            None,
+            None,
+        );
+        (film, accumulator.report())
+    }
+
+    /// Renders scene with settings, counting path events per material (see `RenderStats`), e.g. to find out why
+    /// glass renders dark.
+    pub fn render_with_stats(
+        &mut self,
+        scene: &Scene,
+        settings: &RenderSettings,
+    ) -> (Film, RenderStats) {
+        self.initialize();
+        let (width, height) = self.resolution();
+        let mut film = Film::new(width, height, settings.film_precision());
+        let stats = Mutex::new(RenderStats::default());
+        let tiles = settings.tiles(width, height);
+        self.render_tiles(scene, settings, &mut film, &tiles, None, Some(&stats), None);
+        (film, stats.into_inner().unwrap())
+    }
+
+    /// Re-renders regions of film affected by changes from old to new scene, keeping all other pixels. Regions are
+    /// the screen projections of added and removed objects, so indirect effects of an edit elsewhere in the image
+    /// (shadows, reflections) are not updated. Returns re-rendered tiles.
+    pub fn rerender(
+        &mut self,
+        old_scene: &Scene,
+        scene: &Scene,
+        settings: &RenderSettings,
+        film: &mut Film,
+    ) -> Vec<Tile> {
+        assert!((film.width(), film.height()) == self.resolution());
+        self.initialize();
 
-        pixels
+        // Find tiles affected by scene changes.
+        let diff = old_scene.diff(scene);
+        let tiles = settings.tiles(film.width(), film.height());
+        let dirty: Vec<Tile> =
+            if diff.background_changed || diff.decals_changed || diff.lights_changed {
+                tiles
+            } else {
+                let regions: Vec<Tile> = diff
+                    .removed
+                    .iter()
+                    .map(|&i| &old_scene.objects()[i])
+                    .chain(diff.added.iter().map(|&i| &scene.objects()[i]))
+                    .filter_map(|object| self.screen_region(&object.bounding_box()))
+                    .collect();
+                tiles
+                    .into_iter()
+                    .filter(|tile| regions.iter().any(|region| region.intersects(tile)))
+                    .collect()
+            };
+
+        self.render_tiles(scene, settings, film, &dirty, None, None, None);
+        dirty
     }
 
-    /// Sets samples per pixel.
-    pub fn set_samples_per_pixel(&mut self, samples_per_pixel: u32) {
-        self.samples_per_pixel = samples_per_pixel;
+    /// Resolution of rendered images.
+    pub fn resolution(&self) -> (u32, u32) {
+        self.resolution_override
+            .unwrap_or((self.image_width, self.image_height))
     }
 
-    /// Sets max depth.
-    pub fn set_max_depth(&mut self, max_depth: u32) {
-        self.max_depth = max_depth;
+    /// Overrides resolution of rendered images without changing camera framing, e.g. for thumbnails. Aspect policy
+    /// decides framing if aspect ratio differs. None restores image resolution.
+    pub fn set_resolution_override(
+        &mut self,
+        resolution: Option<(u32, u32)>,
+        policy: AspectPolicy,
+    ) {
+        if let Some((width, height)) = resolution {
+            assert!(width > 0 && height > 0);
+        }
+        self.resolution_override = resolution;
+        self.aspect_policy = policy;
+    }
+
+    /// Projection onto image plane.
+    pub fn projection(&self) -> Projection {
+        self.projection
+    }
+
+    /// Sets projection onto image plane.
+    pub fn set_projection(&mut self, projection: Projection) {
+        self.projection = projection;
+    }
+
+    /// Vertical field of view in degrees.
+    pub fn vfov(&self) -> f32 {
+        self.vfov
     }
 
     /// Sets vertical field of view.
@@ -135,16 +435,31 @@ impl Camera {
         self.vfov = vfov;
     }
 
+    /// Point camera is looking from.
+    pub fn look_from(&self) -> Point3f {
+        self.look_from
+    }
+
     /// Sets point camera is looking from.
     pub fn set_look_from(&mut self, look_from: Point3f) {
         self.look_from = look_from;
     }
 
+    /// Point camera is looking at.
+    pub fn look_at(&self) -> Point3f {
+        self.look_at
+    }
+
     /// Sets point camera is looking at.
     pub fn set_look_at(&mut self, look_at: Point3f) {
         self.look_at = look_at;
     }
 
+    /// Camera-relative 'up' direction.
+    pub fn view_up(&self) -> Vector3f {
+        self.view_up
+    }
+
     /// Sets camera-relative 'up' direction.
     pub fn set_view_up(&mut self, view_up: Vector3f) {
         self.view_up = view_up;
@@ -160,12 +475,104 @@ impl Camera {
         self.focus_distance = focus_distance;
     }
 
+    /// Shape of lens aperture.
+    pub fn aperture(&self) -> Aperture {
+        self.aperture
+    }
+
+    /// Sets shape of lens aperture, changing the shape of defocus blur but not its size.
+    pub fn set_aperture(&mut self, aperture: Aperture) {
+        if let Aperture::Polygonal { blades, .. } = aperture {
+            assert!(
+                blades >= 3,
+                "polygonal aperture needs at least three blades"
+            );
+        }
+        self.aperture = aperture;
+    }
+
+    /// Horizontal squeeze of aperture.
+    pub fn anamorphic_squeeze(&self) -> f32 {
+        self.anamorphic_squeeze
+    }
+
+    /// Sets horizontal squeeze of aperture, e.g. 2 for oval bokeh twice as tall as wide. Defocus angle sets the
+    /// vertical extent of blur.
+    pub fn set_anamorphic_squeeze(&mut self, anamorphic_squeeze: f32) {
+        assert!(anamorphic_squeeze > 0.0);
+        self.anamorphic_squeeze = anamorphic_squeeze;
+    }
+
+    /// Times shutter opens and closes, in frame durations since start of frame.
+    pub fn shutter(&self) -> (f32, f32) {
+        self.shutter
+    }
+
+    /// Sets times shutter opens and closes, in frame durations since start of frame, e.g. (0, 0.5) for a 180°
+    /// shutter. Moving objects blur along their motion while the shutter is open, an instantaneous shutter (the
+    /// default) freezes them. Pixels are the mean radiance while the shutter is open, so exposure does not change
+    /// with the interval, see `ToneMapping::from_exposure_value` for matching that of a real camera.
+    pub fn set_shutter(&mut self, open: f32, close: f32) {
+        assert!(
+            (0.0..=close).contains(&open) && close <= 1.0,
+            "shutter must open and close within frame"
+        );
+        self.shutter = (open, close);
+    }
+
+    /// Ray from center of lens through point of image in pixel coordinates of rendered resolution, pixel x,y
+    /// covering [x,x+1)x[y,y+1), at the time the shutter opens. Picks objects clicked in a viewport showing the image,
+    /// see `Scene::pick`.
+    pub fn pick_ray(&mut self, x: f32, y: f32) -> Ray {
+        self.initialize();
+        let point = self.pixel00_location
+            + ((x - 0.5) * self.pixel_delta_u)
+            + ((y - 0.5) * self.pixel_delta_v);
+        let time = self.shutter.0;
+        if let Projection::Orthographic { .. } = self.projection {
+            return Ray::with_time(point + self.focus_offset, -self.focus_offset, time);
+        }
+        Ray::with_time(self.look_from, point - self.look_from, time)
+    }
+
+    /// Initializes rendering vars, returning pixel grid and lens. Fails if settings have priority regions,
+    /// projection is orthographic or aperture is polygonal, as they are not supported outside of `Camera`.
+    #[cfg(feature = "gpu")]
+    pub(crate) fn ray_frame(&mut self, settings: &RenderSettings) -> Option<RayFrame> {
+        if settings.has_priority_regions()
+            || self.projection != Projection::Perspective
+            || self.aperture != Aperture::Circular
+        {
+            return None;
+        }
+        self.initialize();
+        let defocus = self.defocus_angle > 0.0;
+        let disk = |v: Vector3f| if defocus { v } else { Vector3f::default() };
+        Some(RayFrame {
+            pixel00_location: self.pixel00_location,
+            pixel_delta_u: self.pixel_delta_u,
+            pixel_delta_v: self.pixel_delta_v,
+            look_from: self.look_from,
+            defocus_disk_u: disk(self.defocus_disk_u),
+            defocus_disk_v: disk(self.defocus_disk_v),
+        })
+    }
+
     /// Initializes rendering vars.
     fn initialize(&mut self) {
-        // Viewport dimensions.
-        let aspect_ratio = (self.image_width as f32) / (self.image_height as f32);
-        let h = (self.vfov.to_radians() / 2.0).tan();
-        let viewport_height = 2.0 * h * self.focus_distance;
+        // Viewport dimensions, vertical extent adapted to framing policy.
+        let (width, height) = self.resolution();
+        let frame_aspect_ratio = (self.image_width as f32) / (self.image_height as f32);
+        let aspect_ratio = (width as f32) / (height as f32);
+        let (mut h, distance) = match self.projection {
+            Projection::Perspective => ((self.vfov.to_radians() / 2.0).tan(), self.focus_distance),
+            Projection::Orthographic { view_height } => (view_height / 2.0, 1.0),
+        };
+        let narrower = aspect_ratio < frame_aspect_ratio;
+        if narrower == (self.aspect_policy == AspectPolicy::Fit) {
+            h *= frame_aspect_ratio / aspect_ratio;
+        }
+        let viewport_height = 2.0 * h * distance;
         let viewport_width = viewport_height * aspect_ratio;
 
         // Orthonormal basis u,v,w for camera coordinate system.
@@ -178,77 +585,209 @@ impl Camera {
         let viewport_v = viewport_height * -v;
 
         // Pixel deltas in space.
-        self.pixel_delta_u = viewport_u / self.image_width as f32;
-        self.pixel_delta_v = viewport_v / self.image_height as f32;
+        self.pixel_delta_u = viewport_u / width as f32;
+        self.pixel_delta_v = viewport_v / height as f32;
 
         // Pixel positions in space.
         let viewport_top_left =
             self.look_from - (self.focus_distance * w) - (viewport_u / 2.0) - (viewport_v / 2.0);
         self.pixel00_location = viewport_top_left + 0.5 * (self.pixel_delta_u + self.pixel_delta_v);
+        self.focus_offset = self.focus_distance * w;
 
         // Defocus disk basis vectors.
         let defocus_radius = self.focus_distance * (self.defocus_angle / 2.0).to_radians().tan();
-        self.defocus_disk_u = defocus_radius * u;
+        self.defocus_disk_u = (defocus_radius / self.anamorphic_squeeze) * u;
         self.defocus_disk_v = defocus_radius * v;
     }
 
+    /// Renders tiles of film, replacing previous pixel values. Sample statistics are collected if noise accumulator
+    /// is given, path statistics if render stats are given. Finished tiles are handed to observer if given, remaining
+    /// tiles are skipped once it returns false.
+    #[allow(clippy::too_many_arguments)]
+    fn render_tiles(
+        &self,
+        scene: &Scene,
+        settings: &RenderSettings,
+        film: &mut Film,
+        tiles: &[Tile],
+        noise: Option<&NoiseAccumulator>,
+        stats: Option<&Mutex<RenderStats>>,
+        observer: Option<&TileObserver>,
+    ) {
+        // Render loop (accumulation is single precision regardless of film precision).
+        let integrator = settings.integrator();
+        let stopped = AtomicBool::new(false);
+        film.par_process_tiles(tiles, |tile, buffer| {
+            if stopped.load(Ordering::Relaxed) {
+                return;
+            }
+            let mut batch = noise.map(|n| n.batch());
+            let mut tile_stats = stats.map(|_| RenderStats::default());
+            let mut samples = Vec::new();
+            for (i, pixel) in buffer.iter_mut().enumerate() {
+                let x = tile.x + i as u32 % tile.width;
+                let y = tile.y + i as u32 / tile.width;
+
+                // Multi sample rendering.
+                let mut rng = settings.pixel_sampler(x, y, 0);
+                let mut moments = PixelMoments::default();
+                samples.clear();
+                for s in 0..settings.samples_at(x, y) {
+                    rng.start_sample(s);
+                    let ray = self.get_ray(x, y, &mut rng);
+                    let sample = match tile_stats.as_mut() {
+                        Some(tile_stats) => {
+                            tile_stats.record_path();
+                            integrator.radiance_with_stats(ray, scene, &mut rng, tile_stats)
+                        }
+                        None => integrator.radiance(ray, scene, &mut rng),
+                    };
+                    let sample = settings.clamp_sample(sample);
+                    if let (Some(noise), Some(batch)) = (noise, batch.as_mut()) {
+                        noise.add_sample(batch, &mut moments, sample);
+                    }
+                    samples.push(sample);
+                }
+
+                // Average samples, rejecting outliers.
+                *pixel = settings.pixel_mean(&samples);
+            }
+
+            if let (Some(noise), Some(batch)) = (noise, batch) {
+                noise.merge(&batch, tile.pixel_count() as u64);
+            }
+            if let (Some(stats), Some(tile_stats)) = (stats, tile_stats) {
+                stats.lock().unwrap().merge(&tile_stats);
+            }
+            if let Some(observer) = observer {
+                if !observer(tile, buffer) {
+                    stopped.store(true, Ordering::Relaxed);
+                }
+            }
+        });
+    }
+
+    /// Conservative pixel region covered by bounding box on screen, including defocus blur of perspective
+    /// projection. Returns none if off screen.
+    fn screen_region(&self, bounds: &Aabb) -> Option<Tile> {
+        let w = (self.look_from - self.look_at).normalize();
+        let pixel_size = self.pixel_delta_u.length();
+        let defocus_radius = self
+            .defocus_disk_u
+            .length()
+            .max(self.defocus_disk_v.length());
+        let (image_width, image_height) = self.resolution();
+        let full_image = Tile {
+            x: 0,
+            y: 0,
+            width: image_width,
+            height: image_height,
+        };
+
+        // Project corners onto focus plane.
+        let (mut min_x, mut min_y) = (f32::INFINITY, f32::INFINITY);
+        let (mut max_x, mut max_y) = (f32::NEG_INFINITY, f32::NEG_INFINITY);
+        for corner in bounds.corners() {
+            let d = corner - self.look_from;
+            let depth = -d.dot(&w);
+            let (projected, blur) = match self.projection {
+                Projection::Perspective => {
+                    if depth <= 0.0 {
+                        return Some(full_image); // Behind camera.
+                    }
+                    let blur =
+                        defocus_radius * (1.0 - self.focus_distance / depth).abs() / pixel_size;
+                    (self.look_from + (self.focus_distance / depth) * d, blur)
+                }
+                Projection::Orthographic { .. } => {
+                    (corner + (depth - self.focus_distance) * w, 0.0)
+                }
+            };
+            let offset = projected - self.pixel00_location;
+            let x = offset.dot(&self.pixel_delta_u) / (pixel_size * pixel_size);
+            let y = offset.dot(&self.pixel_delta_v) / (pixel_size * pixel_size);
+            min_x = min_x.min(x - blur);
+            min_y = min_y.min(y - blur);
+            max_x = max_x.max(x + blur);
+            max_y = max_y.max(y + blur);
+        }
+
+        // Pixel centers are at integer coordinates, samples jitter by half a pixel.
+        let (width, height) = (image_width as f32, image_height as f32);
+        if max_x < -1.0 || max_y < -1.0 || min_x > width || min_y > height {
+            return None;
+        }
+        let x0 = (min_x - 1.0).floor().clamp(0.0, width) as u32;
+        let y0 = (min_y - 1.0).floor().clamp(0.0, height) as u32;
+        let x1 = (max_x + 1.0).ceil().clamp(0.0, width) as u32;
+        let y1 = (max_y + 1.0).ceil().clamp(0.0, height) as u32;
+        Some(Tile {
+            x: x0,
+            y: y0,
+            width: x1 - x0,
+            height: y1 - y0,
+        })
+    }
+
     /// Generates ray for pixel x,y.
-    fn get_ray(&self, x: u32, y: u32) -> Ray {
+    fn get_ray(&self, x: u32, y: u32, rng: &mut impl Rng) -> Ray {
         let pixel_center = self.pixel00_location
             + (x as f32 * self.pixel_delta_u)
             + (y as f32 * self.pixel_delta_v);
-        let pixel_sample = pixel_center + self.sample_pixel_square();
+        let pixel_sample = pixel_center + self.sample_pixel_square(rng);
+
+        // Parallel rays start in plane of lens.
+        if let Projection::Orthographic { .. } = self.projection {
+            let time = self.sample_shutter(rng);
+            return Ray::with_time(pixel_sample + self.focus_offset, -self.focus_offset, time);
+        }
 
         let ray_origin = if self.defocus_angle <= 0.0 {
             self.look_from
         } else {
-            self.sample_defocus_disk()
+            self.sample_defocus_disk(rng)
         };
         let ray_direction = pixel_sample - ray_origin;
 
-        Ray::new(ray_origin, ray_direction)
+        Ray::with_time(ray_origin, ray_direction, self.sample_shutter(rng))
     }
 
-    /// Calculate color shading for ray into scene.
-    fn ray_color(&self, ray: Ray, depth: u32, scene: &Scene) -> Color3f {
-        // Recursion limit.
-        if depth <= 0 {
-            return Color3f::black();
-        }
-
-        // Intersect with scene.
-        if let Some(isect) = scene.intersect(ray, Interval::new(0.001, f32::INFINITY)) {
-            // Interact with material.
-            if let Some(iact) = isect.material.interact(ray, isect) {
-                // Recurse and attenuate.
-                return iact.attenuation * self.ray_color(iact.scattered_ray, depth - 1, scene);
-            } else {
-                // Fully absorbed.
-                return Color3f::black();
-            }
+    /// Samples random time while shutter is open.
+    fn sample_shutter(&self, rng: &mut impl Rng) -> f32 {
+        let (open, close) = self.shutter;
+        if close > open {
+            open + rng.gen::<f32>() * (close - open)
+        } else {
+            open
         }
-
-        // Background based on y component of ray direction.
-        let normalized_direction = ray.direction().normalize();
-        let a = 0.5 * (normalized_direction.y() + 1.0);
-        (1.0 - a) * Color3f::white() + a * Color3f::new(0.5, 0.7, 1.0)
     }
 
     /// Samples random offset in pixel square.
-    fn sample_pixel_square(&self) -> Vector3f {
-        let mut rng = thread_rng();
+    fn sample_pixel_square(&self, rng: &mut impl Rng) -> Vector3f {
         let dx = -0.5 + rng.gen::<f32>();
         let dy = -0.5 + rng.gen::<f32>();
         (dx * self.pixel_delta_u) + (dy * self.pixel_delta_v)
     }
 
-    /// Samples random point in camera defocus disk.
-    fn sample_defocus_disk(&self) -> Point3f {
-        let mut rng = thread_rng();
-        let mut dv = Vector3f::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0), 0.0);
-        if dv.length_squared() > 1.0 {
-            dv = dv.normalize();
-        }
+    /// Samples random point in camera defocus disk, shaped by aperture.
+    fn sample_defocus_disk(&self, rng: &mut impl Rng) -> Point3f {
+        let dv = match self.aperture {
+            Aperture::Circular => {
+                let (x, y) = concentric_disk(rng);
+                Vector3f::new(x, y, 0.0)
+            }
+            Aperture::Polygonal { blades, rotation } => {
+                // Uniform point in triangle of center and two adjacent corners, all triangles equally likely.
+                let blade = rng.gen_range(0..blades);
+                let corner = |i: u32| {
+                    let angle = (90.0 + rotation).to_radians()
+                        + std::f32::consts::TAU * i as f32 / blades as f32;
+                    Vector3f::new(angle.cos(), angle.sin(), 0.0)
+                };
+                let (a, b) = uniform_triangle(rng);
+                a * corner(blade) + b * corner(blade + 1)
+            }
+        };
         self.look_from + (dv.x() * self.defocus_disk_u) + (dv.y() * self.defocus_disk_v)
     }
 }
@@ -257,6 +796,50 @@ impl Camera {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{
+        background::Background,
+        base::{material::Material, shape::Shape},
+        integrator::Integrator,
+        integrators::path_tracer::PathTracer,
+        materials::{
+            dielectric::Dielectric, diffuse_light::DiffuseLight, lambert::Lambert, metal::Metal,
+        },
+        sampler::Sampler,
+        shapes::{sphere::Sphere, voxels::Voxels},
+        tile::TileOrder,
+    };
+    use rand::{rngs::StdRng, SeedableRng};
+    use std::sync::{atomic::AtomicU32, Mutex};
+
+    /// Camera looking at origin from distance with narrow field of view.
+    fn narrow_camera(distance: f32) -> Camera {
+        let mut c = Camera::new(16, 16);
+        c.set_vfov(10.0);
+        c.set_look_from(Point3f::new(0.0, 0.0, distance));
+        c
+    }
+
+    /// Settings of deep paths at moderate sample count.
+    fn deep_settings() -> RenderSettings {
+        let mut s = RenderSettings::new(16);
+        s.set_integrator(Integrator::PathTracer(PathTracer::new(50)));
+        s
+    }
+
+    /// Asserts that mean film color matches expected color within relative tolerance.
+    fn assert_mean(film: &Film, expected: Color3f, tolerance: f32) {
+        let sum = film.pixels().fold(Color3f::black(), |acc, c| acc + c);
+        let mean = sum / (film.width() * film.height()) as f32;
+        let error = (mean - expected) / expected;
+        assert!(
+            error.r().abs() < tolerance
+                && error.g().abs() < tolerance
+                && error.b().abs() < tolerance,
+            "mean {:?} differs from expected {:?}",
+            mean,
+            expected
+        );
+    }
 
     #[test]
     fn initialize() {
@@ -275,15 +858,477 @@ mod tests {
         assert_eq!(c.defocus_disk_v, Vector3f::new(0.0, 10.0, 0.0));
     }
 
+    #[test]
+    fn resolution_override() {
+        let mut c = Camera::new(200, 100);
+        c.set_vfov(90.0);
+        c.initialize();
+        let corner = |c: &Camera| c.pixel00_location - (c.pixel_delta_u + c.pixel_delta_v) / 2.0;
+        let (delta, top_left) = (c.pixel_delta_u, corner(&c));
+        let pixel_size = |c: &Camera| (c.pixel_delta_u.length(), c.pixel_delta_v.length());
+
+        // Same aspect ratio keeps framing at finer pixels.
+        c.set_resolution_override(Some((400, 200)), AspectPolicy::Fit);
+        assert_eq!(c.resolution(), (400, 200));
+        c.initialize();
+        assert_eq!(c.pixel_delta_u, delta / 2.0);
+        assert!((corner(&c) - top_left).length() < 1e-6);
+
+        // Square image fits whole frame (4 x 2 viewport) or fills with its center.
+        c.set_resolution_override(Some((100, 100)), AspectPolicy::Fit);
+        c.initialize();
+        assert_eq!(pixel_size(&c), (0.04, 0.04));
+        c.set_resolution_override(Some((100, 100)), AspectPolicy::Fill);
+        c.initialize();
+        assert_eq!(pixel_size(&c), (0.02, 0.02));
+
+        // Wide image keeps horizontal extent when filling.
+        c.set_resolution_override(Some((400, 100)), AspectPolicy::Fill);
+        c.initialize();
+        assert_eq!(pixel_size(&c), (0.01, 0.01));
+        let film = c.render(&Scene::new(), &RenderSettings::new(1));
+        assert_eq!((film.width(), film.height()), (400, 100));
+
+        c.set_resolution_override(None, AspectPolicy::Fit);
+        assert_eq!(c.resolution(), (200, 100));
+    }
+
     #[test]
     fn get_ray() {
         let mut c = Camera::new(2000, 1000);
         c.set_look_from(Point3f::new(1.0, 0.0, 0.0));
         c.initialize();
 
-        let r = c.get_ray(10, 10);
+        let r = c.get_ray(10, 10, &mut StdRng::seed_from_u64(42));
         let pixel_center = c.pixel00_location + 10.0 * (c.pixel_delta_u + c.pixel_delta_v);
         assert_eq!(r.at(0.0), c.look_from);
         assert!((r.at(1.0) - pixel_center).length() <= c.pixel_delta_u.length());
     }
+
+    #[test]
+    fn pick_ray() {
+        // Image center lies on the line of sight, corners on the viewport edges.
+        let mut c = Camera::new(200, 100);
+        c.set_look_from(Point3f::new(0.0, 0.0, 5.0));
+        c.set_look_at(Point3f::default());
+        let r = c.pick_ray(100.0, 50.0);
+        assert_eq!(r.origin(), c.look_from);
+        assert!(
+            r.direction()
+                .normalize()
+                .dot(&Vector3f::new(0.0, 0.0, -1.0))
+                > 1.0 - 1e-6
+        );
+        let corner = c.pick_ray(0.0, 0.0).direction().normalize();
+        let expected = (c.vfov / 2.0).to_radians().tan();
+        assert!((corner.y() / -corner.z() - expected).abs() < 1e-4);
+        assert!((corner.x() / corner.z() - 2.0 * expected).abs() < 1e-4);
+
+        // Orthographic rays are parallel.
+        c.set_projection(Projection::Orthographic { view_height: 2.0 });
+        let (a, b) = (c.pick_ray(0.0, 0.0), c.pick_ray(200.0, 100.0));
+        assert_eq!(a.direction(), b.direction());
+        assert!((a.origin() - b.origin()).length() > 4.0);
+    }
+
+    #[test]
+    fn aperture() {
+        // Unit defocus disk around origin, in plane z=0.
+        let mut c = Camera::new(16, 16);
+        c.set_look_from(Point3f::default());
+        c.set_look_at(Point3f::new(0.0, 0.0, -1.0));
+        c.set_defocus_angle(90.0);
+        c.initialize();
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut samples = |c: &Camera| -> Vec<Point3f> {
+            (0..1000).map(|_| c.sample_defocus_disk(&mut rng)).collect()
+        };
+
+        // Four blades form a diamond with corner pointing up, rotated by 45 degrees a square.
+        c.set_aperture(Aperture::Polygonal {
+            blades: 4,
+            rotation: 0.0,
+        });
+        let diamond = samples(&c);
+        assert!(diamond
+            .iter()
+            .all(|p| p.x().abs() + p.y().abs() <= 1.0 + 1e-5));
+        assert!(diamond.iter().any(|p| p.y() > 0.9));
+        c.set_aperture(Aperture::Polygonal {
+            blades: 4,
+            rotation: 45.0,
+        });
+        let square = samples(&c);
+        let half = std::f32::consts::FRAC_1_SQRT_2 + 1e-5;
+        assert!(square
+            .iter()
+            .all(|p| p.x().abs() <= half && p.y().abs() <= half));
+        assert!(square.iter().any(|p| p.x() > 0.6 && p.y() > 0.6));
+
+        // Anamorphic squeeze narrows aperture horizontally only.
+        c.set_aperture(Aperture::Circular);
+        c.set_anamorphic_squeeze(2.0);
+        c.initialize();
+        let oval = samples(&c);
+        assert!(oval
+            .iter()
+            .all(|p| p.x().abs() <= 0.5 + 1e-5 && p.z() == 0.0));
+        assert!(oval.iter().any(|p| p.y().abs() > 0.9));
+    }
+
+    #[test]
+    fn shutter() {
+        // Glowing sphere moving right through the view center over the frame.
+        let mut scene = Scene::new();
+        scene.set_background(Background::Uniform(Color3f::black()));
+        let light = Material::DiffuseLight(DiffuseLight::new(Color3f::white()));
+        let mut sphere = Sphere::new(Point3f::new(-1.0, 0.0, -3.0), 0.5, light);
+        sphere.set_motion(Vector3f::new(2.0, 0.0, 0.0));
+        scene.add(Shape::Sphere(sphere));
+        let mut c = Camera::new(16, 16);
+        c.set_look_from(Point3f::default());
+        c.set_look_at(Point3f::new(0.0, 0.0, -1.0));
+        let mut s = RenderSettings::new(256);
+        s.set_integrator(Integrator::PathTracer(PathTracer::new(2)));
+
+        // Instantaneous shutter freezes sphere at start of frame.
+        let frozen = c.render(&scene, &s);
+        assert_eq!(frozen.pixel(8, 8), Color3f::black());
+        assert_eq!(frozen.pixel(5, 8), Color3f::white());
+
+        // Open shutter smears it over its path, partially covering pixels.
+        c.set_shutter(0.0, 1.0);
+        let blurred = c.render(&scene, &s);
+        let center = blurred.pixel(8, 8).g();
+        assert!(center > 0.3 && center < 0.6);
+        assert!(blurred.pixel(5, 8).g() < 1.0);
+        c.set_shutter(0.5, 0.5);
+        assert!(c.render(&scene, &s).pixel(8, 8).g() > 0.9);
+    }
+
+    #[test]
+    fn orthographic() {
+        let mut c = Camera::new(200, 100);
+        c.set_look_from(Point3f::new(0.0, 0.0, 5.0));
+        c.set_focus_distance(3.0);
+        c.set_defocus_angle(10.0);
+        c.set_projection(Projection::Orthographic { view_height: 2.0 });
+        c.initialize();
+        assert_eq!(c.pixel_delta_u.length(), 0.02);
+
+        // Parallel rays start in plane of lens, ignoring defocus blur.
+        let mut rng = StdRng::seed_from_u64(42);
+        for (x, y) in [(0, 0), (100, 50), (199, 99)] {
+            let r = c.get_ray(x, y, &mut rng);
+            let pixel_center =
+                c.pixel00_location + x as f32 * c.pixel_delta_u + y as f32 * c.pixel_delta_v;
+            assert_eq!(r.direction(), Vector3f::new(0.0, 0.0, -3.0));
+            assert!((r.origin().z() - 5.0).abs() < 1e-6);
+            assert!((r.at(1.0) - pixel_center).length() <= c.pixel_delta_u.length());
+        }
+
+        // Objects cover the same screen region at any distance.
+        let region = |z: f32| {
+            let bounds = Aabb::new(Point3f::new(-0.5, -0.5, z), Point3f::new(0.5, 0.5, z + 1.0));
+            c.screen_region(&bounds).unwrap()
+        };
+        assert_eq!(region(0.0), region(-100.0));
+        assert_eq!(region(0.0).width, 53);
+    }
+
+    #[test]
+    fn furnace() {
+        // Energy conserving materials in uniform white environment must be invisible.
+        let mut scene = Scene::new();
+        scene.set_background(Background::Uniform(Color3f::white()));
+        let white = Lambert::new(Color3f::white());
+        let mirror = Metal::new(Color3f::white(), 0.0);
+        let glass = Dielectric::new(1.5);
+        let spheres = [
+            (Point3f::new(0.0, 0.0, 0.0), Material::Lambert(white)),
+            (Point3f::new(0.3, 0.0, 0.5), Material::Metal(mirror)),
+            (Point3f::new(-0.3, 0.0, 0.5), Material::Dielectric(glass)),
+        ];
+        for (center, material) in spheres {
+            scene.add(Shape::Sphere(Sphere::new(center, 0.5, material)));
+        }
+
+        let film = narrow_camera(5.0).render(&scene, &deep_settings());
+        assert_mean(&film, Color3f::white(), 0.01);
+    }
+
+    #[test]
+    fn lambertian_plane() {
+        // Single bounce off (locally) flat diffuse ground under constant sky yields albedo * sky radiance.
+        let mut scene = Scene::new();
+        let sky = Color3f::new(1.0, 0.5, 0.25);
+        scene.set_background(Background::Uniform(sky));
+        let albedo = Color3f::new(0.5, 0.8, 0.2);
+        scene.add(Shape::Sphere(Sphere::new(
+            Point3f::new(0.0, -1000.0, 0.0),
+            1000.0,
+            Material::Lambert(Lambert::new(albedo)),
+        )));
+
+        let mut c = narrow_camera(5.0);
+        c.set_look_from(Point3f::new(0.0, 5.0, 0.0));
+        c.set_view_up(Vector3f::new(0.0, 0.0, -1.0));
+        let film = c.render(&scene, &deep_settings());
+        assert_mean(&film, albedo * sky, 0.01);
+    }
+
+    #[test]
+    fn seed() {
+        let mut scene = Scene::new();
+        let material = Material::Lambert(Lambert::new(Color3f::new(0.5, 0.5, 0.5)));
+        scene.add(Shape::Sphere(Sphere::new(
+            Point3f::default(),
+            0.5,
+            material,
+        )));
+
+        let mut c = Camera::new(16, 16);
+        c.set_look_from(Point3f::new(0.0, 0.0, 2.0));
+        c.set_defocus_angle(2.0);
+        let mut s = RenderSettings::default();
+        s.set_tile_size(4);
+        s.set_seed(7);
+        let a = c.render(&scene, &s);
+        s.set_tile_order(TileOrder::Hilbert);
+        let b = c.render(&scene, &s);
+        s.set_seed(8);
+        let d = c.render(&scene, &s);
+        assert_eq!(a, b);
+        assert_ne!(a, d);
+    }
+
+    #[test]
+    fn render_observed() {
+        let mut scene = Scene::new();
+        let material = Material::Lambert(Lambert::new(Color3f::new(0.5, 0.5, 0.5)));
+        scene.add(Shape::Sphere(Sphere::new(
+            Point3f::default(),
+            0.5,
+            material,
+        )));
+        let mut c = Camera::new(32, 32);
+        c.set_look_from(Point3f::new(0.0, 0.0, 2.0));
+        let mut s = RenderSettings::default();
+        s.set_tile_size(4);
+        let full = c.render(&scene, &s);
+
+        // Observed tiles hold final pixels.
+        let observed = Mutex::new(Vec::new());
+        let film = c.render_observed(&scene, &s, |tile, buffer| {
+            for (i, &color) in buffer.iter().enumerate() {
+                let (x, y) = (
+                    tile.x + i as u32 % tile.width,
+                    tile.y + i as u32 / tile.width,
+                );
+                assert_eq!(color, full.pixel(x, y));
+            }
+            observed.lock().unwrap().push(*tile);
+            true
+        });
+        assert_eq!(film, full);
+        assert_eq!(observed.into_inner().unwrap().len(), 64);
+
+        // Stopping leaves at most the tiles in flight rendered.
+        let count = AtomicU32::new(0);
+        let film = c.render_observed(&scene, &s, |_, _| {
+            count.fetch_add(1, Ordering::Relaxed);
+            false
+        });
+        let count = count.into_inner();
+        assert!(count >= 1 && count as usize <= rayon::current_num_threads().min(64));
+        assert_eq!(film == full, count == 64);
+    }
+
+    #[test]
+    fn render_with_stats() {
+        // Shallow paths through glass cube seen from corner, partly trapped inside by total internal reflection.
+        let mut scene = Scene::new();
+        scene.set_background(Background::Uniform(Color3f::white()));
+        let glass = Material::Dielectric(Dielectric::new(1.5));
+        let origin = Point3f::new(-0.5, -0.5, -0.5);
+        let cube = Voxels::new(origin, 1.0, vec![glass.clone()], &[([0, 0, 0], 0)]);
+        scene.add(Shape::Voxels(cube));
+        let mut c = narrow_camera(5.0);
+        c.set_look_from(Point3f::new(3.0, 3.0, 3.0));
+        c.set_vfov(30.0);
+        let mut s = RenderSettings::new(16);
+        s.set_integrator(Integrator::PathTracer(PathTracer::new(3)));
+        let film = c.render(&scene, &s);
+        let (stats_film, stats) = c.render_with_stats(&scene, &s);
+
+        // Counting does not change image.
+        assert_eq!(film, stats_film);
+        assert_eq!(stats.paths(), 16 * 16 * 16);
+        assert_eq!(stats.materials().len(), 1);
+        let glass_stats = stats.material(&glass).unwrap();
+        assert!(glass_stats.tir_bounces > 0 && glass_stats.tir_bounces < glass_stats.bounces);
+        assert!(glass_stats.max_depth_paths > 0 && glass_stats.max_depth_paths < stats.paths());
+    }
+
+    #[test]
+    fn priority_regions() {
+        let mut scene = Scene::new();
+        let material = Material::Lambert(Lambert::new(Color3f::new(0.5, 0.5, 0.5)));
+        scene.add(Shape::Sphere(Sphere::new(
+            Point3f::default(),
+            0.5,
+            material,
+        )));
+        let mut c = Camera::new(32, 32);
+        c.set_look_from(Point3f::new(0.0, 0.0, 2.0));
+        let mut s = RenderSettings::new(2);
+        s.set_tile_size(8);
+        let low = c.render(&scene, &s);
+        s.set_samples_per_pixel(16);
+        let high = c.render(&scene, &s);
+
+        // Pixels in region match render with region sample count, others keep base count.
+        s.set_samples_per_pixel(2);
+        let region = Tile {
+            x: 20,
+            y: 10,
+            width: 6,
+            height: 4,
+        };
+        s.add_priority_region(region, 16);
+        let film = c.render(&scene, &s);
+        for y in 0..32 {
+            for x in 0..32 {
+                let expected = if region.contains(x, y) { &high } else { &low };
+                assert_eq!(film.pixel(x, y), expected.pixel(x, y));
+            }
+        }
+
+        // Priority tiles are scheduled first.
+        let tiles = s.tiles(32, 32);
+        assert!(tiles[..2].iter().all(|t| t.intersects(&region)));
+        assert!(tiles[2..].iter().all(|t| !t.intersects(&region)));
+
+        s.clear_priority_regions();
+        assert_eq!(c.render(&scene, &s), low);
+    }
+
+    #[test]
+    fn rerender() {
+        let material = Material::Lambert(Lambert::new(Color3f::new(0.5, 0.5, 0.5)));
+        let mut old_scene = Scene::new();
+        old_scene.add(Shape::Sphere(Sphere::new(
+            Point3f::new(-0.5, 0.0, 0.0),
+            0.2,
+            material.clone(),
+        )));
+        let mut scene = Scene::new();
+        scene.add(Shape::Sphere(Sphere::new(
+            Point3f::new(-0.4, 0.0, 0.0),
+            0.2,
+            material.clone(),
+        )));
+
+        let mut c = Camera::new(32, 32);
+        c.set_look_from(Point3f::new(0.0, 0.0, 2.0));
+        let mut s = RenderSettings::default();
+        s.set_tile_size(4);
+        let mut film = c.render(&old_scene, &s);
+        let old_film = film.clone();
+        let new_film = c.render(&scene, &s);
+
+        // Only tiles on left half are affected.
+        let dirty = c.rerender(&old_scene, &scene, &s, &mut film);
+        assert!(!dirty.is_empty());
+        assert!(dirty.iter().all(|tile| tile.x < 16));
+        for y in 0..32 {
+            for x in 0..32 {
+                let is_dirty = dirty.iter().any(|t| {
+                    t.intersects(&Tile {
+                        x,
+                        y,
+                        width: 1,
+                        height: 1,
+                    })
+                });
+                let expected = if is_dirty { &new_film } else { &old_film };
+                assert_eq!(film.pixel(x, y), expected.pixel(x, y));
+            }
+        }
+
+        // Identical scene re-renders nothing, changed background everything.
+        assert!(c.rerender(&scene, &scene, &s, &mut film).is_empty());
+        let mut sky_scene = Scene::new();
+        sky_scene.add(Shape::Sphere(Sphere::new(
+            Point3f::new(-0.4, 0.0, 0.0),
+            0.2,
+            material,
+        )));
+        sky_scene.set_background(Background::Uniform(Color3f::white()));
+        assert_eq!(c.rerender(&scene, &sky_scene, &s, &mut film).len(), 64);
+    }
+
+    #[test]
+    fn halton() {
+        let mut scene = Scene::new();
+        scene.set_background(Background::Uniform(Color3f::white()));
+        let material = Material::Lambert(Lambert::new(Color3f::new(0.5, 0.5, 0.5)));
+        scene.add(Shape::Sphere(Sphere::new(
+            Point3f::default(),
+            1.0,
+            material,
+        )));
+
+        let mut c = narrow_camera(5.0);
+        let mut s = deep_settings();
+        s.set_sampler(Sampler::Halton);
+        let film = c.render(&scene, &s);
+        assert_mean(&film, Color3f::new(0.5, 0.5, 0.5), 0.01);
+        assert_eq!(film, c.render(&scene, &s));
+    }
+
+    #[test]
+    fn light_sampling() {
+        // Small light above diffuse ground, seen at an angle without the light in view.
+        let mut scene = Scene::new();
+        scene.set_background(Background::Uniform(Color3f::black()));
+        let ground = Material::Lambert(Lambert::new(Color3f::new(0.5, 0.5, 0.5)));
+        scene.add(Shape::preset("ground", ground).unwrap());
+        let light = Material::DiffuseLight(DiffuseLight::new(Color3f::new(20.0, 20.0, 20.0)));
+        scene.add(Shape::Sphere(Sphere::new(
+            Point3f::new(0.0, 2.0, 0.0),
+            0.25,
+            light,
+        )));
+
+        let mut c = narrow_camera(5.0);
+        c.set_look_from(Point3f::new(3.0, 3.0, 0.0));
+        let mut s = deep_settings();
+        let mut render = |light_sampling, samples_per_pixel| {
+            let mut path_tracer = PathTracer::new(50);
+            path_tracer.set_light_sampling(light_sampling);
+            s.set_integrator(Integrator::PathTracer(path_tracer));
+            s.set_samples_per_pixel(samples_per_pixel);
+            let film = c.render(&scene, &s);
+            let pixels: Vec<f32> = film.pixels().map(|p| p.g()).collect();
+            let mean = pixels.iter().sum::<f32>() / pixels.len() as f32;
+            let variance =
+                pixels.iter().map(|p| (p - mean).powi(2)).sum::<f32>() / pixels.len() as f32;
+            (mean, variance)
+        };
+
+        // Both estimators converge to same image, explicit light samples with far less noise.
+        let (mean, variance) = render(true, 16);
+        let (reference, reference_variance) = render(false, 1024);
+        assert!((mean - reference).abs() / reference < 0.05);
+        assert!(variance < reference_variance);
+        assert!(render(false, 16).1 > 20.0 * variance);
+
+        // Direct view of light.
+        c.set_look_from(Point3f::new(0.0, 2.0, 5.0));
+        c.set_look_at(Point3f::new(0.0, 2.0, 0.0));
+        c.set_vfov(1.0);
+        let film = c.render(&scene, &s);
+        assert_mean(&film, Color3f::new(20.0, 20.0, 20.0), 1e-6);
+    }
 }
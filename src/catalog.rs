@@ -0,0 +1,103 @@
+use crate::{
+    base::{material::Material, point::Point3f, shape::Shape},
+    camera::Camera,
+    comparison::contact_sheet,
+    film::Film,
+    scene::Scene,
+    settings::RenderSettings,
+    shapes::sphere::Sphere,
+    studio,
+};
+
+/// Distinct materials of scene objects, each with the index of the first object using it, in order of first use.
+pub fn used_materials(scene: &Scene) -> Vec<(usize, Material)> {
+    let mut materials: Vec<(usize, Material)> = Vec::new();
+    for index in 0..scene.objects().len() {
+        let material = scene.material(index);
+        if !materials.iter().any(|(_, m)| m == material) {
+            materials.push((index, material.clone()));
+        }
+    }
+    materials
+}
+
+/// Scene and camera of the standard shader ball: a unit sphere of material in the studio, framed square with given
+/// edge length in pixels. All materials are shown at the same scale and under the same lights, so thumbnails
+/// compare side by side.
+pub fn shader_ball(material: Material, size: u32) -> (Scene, Camera) {
+    let mut scene = Scene::new();
+    scene.add(Shape::Sphere(Sphere::new(
+        Point3f::default(),
+        1.0,
+        material,
+    )));
+    let mut camera = Camera::new(size, size);
+    studio::setup(&mut scene, &mut camera);
+    (scene, camera)
+}
+
+/// Renders labeled materials on shader balls of given size with settings and composes the thumbnails row by row
+/// into a catalog with given count of columns, e.g. to review a material library.
+pub fn render_catalog(
+    materials: &[(String, Material)],
+    size: u32,
+    settings: &RenderSettings,
+    columns: u32,
+) -> Film {
+    let cells: Vec<(String, Film)> = materials
+        .iter()
+        .map(|(label, material)| {
+            let (scene, mut camera) = shader_ball(material.clone(), size);
+            (label.clone(), camera.render(&scene, settings))
+        })
+        .collect();
+    contact_sheet(&cells, columns)
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{base::color::Color3f, materials::lambert::Lambert};
+
+    #[test]
+    fn used_materials() {
+        let red = Material::Lambert(Lambert::new(Color3f::new(1.0, 0.0, 0.0)));
+        let blue = Material::Lambert(Lambert::new(Color3f::new(0.0, 0.0, 1.0)));
+        let mut scene = Scene::new();
+        for (x, material) in [(0.0, &red), (2.0, &blue), (4.0, &red)] {
+            scene.add(Shape::Sphere(Sphere::new(
+                Point3f::new(x, 0.0, 0.0),
+                1.0,
+                material.clone(),
+            )));
+        }
+        assert_eq!(super::used_materials(&scene), [(0, red), (1, blue)]);
+        assert!(super::used_materials(&Scene::new()).is_empty());
+    }
+
+    #[test]
+    fn render_catalog() {
+        let red = Material::Lambert(Lambert::new(Color3f::new(0.8, 0.0, 0.0)));
+        let materials = [
+            ("red".to_string(), red.clone()),
+            ("chrome".to_string(), Material::preset("chrome").unwrap()),
+            ("glass".to_string(), Material::preset("glass").unwrap()),
+        ];
+        let settings = RenderSettings::new(2);
+        let catalog = super::render_catalog(&materials, 16, &settings, 2);
+        assert_eq!(catalog.width(), 32);
+
+        // First thumbnail is the red shader ball below its label, two rows of cells.
+        let (scene, mut camera) = shader_ball(red, 16);
+        let thumbnail = camera.render(&scene, &settings);
+        let top = catalog.height() / 2 - 16;
+        for y in 0..16 {
+            for x in 0..16 {
+                assert_eq!(catalog.pixel(x, top + y), thumbnail.pixel(x, y));
+            }
+        }
+        let center = thumbnail.pixel(8, 8);
+        assert!(center.r() > center.b());
+    }
+}
@@ -0,0 +1,520 @@
+use crate::{
+    base::{color::Color3f, hash},
+    camera::Camera,
+    film::{Film, FilmPrecision},
+    scene::Scene,
+    settings::RenderSettings,
+};
+use std::{error::Error, fmt, fs, io, path::Path};
+
+/// Leading bytes of checkpoint files, including format version.
+const MAGIC: &[u8; 8] = b"EDENACC2";
+
+/// Bytes of checkpoint header after magic: width, height, hash fingerprint, scene hash and seed.
+const HEADER_SIZE: usize = 32;
+
+/// Bytes per pixel in checkpoint files: radiance sum and sample count.
+const PIXEL_SIZE: usize = 16;
+
+/// Error of checkpoint operation.
+#[derive(Debug)]
+pub enum CheckpointError {
+    /// Checkpoint file could not be read or written.
+    Io(io::Error),
+
+    /// Checkpoint file is malformed.
+    Format(String),
+
+    /// Checkpoint belongs to a different scene, seed or resolution than the render, or was written by a build hashing
+    /// scenes differently.
+    Mismatch,
+}
+
+impl fmt::Display for CheckpointError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CheckpointError::Io(e) => write!(f, "{}", e),
+            CheckpointError::Format(message) => write!(f, "invalid checkpoint: {}", message),
+            CheckpointError::Mismatch => write!(f, "checkpoint belongs to a different render"),
+        }
+    }
+}
+
+impl Error for CheckpointError {}
+
+impl From<io::Error> for CheckpointError {
+    fn from(e: io::Error) -> Self {
+        CheckpointError::Io(e)
+    }
+}
+
+/// Accumulated samples of a pixel.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AccumulatedPixel {
+    /// Sum of sampled radiance.
+    pub sum: Color3f,
+
+    /// Number of samples taken.
+    pub samples: u32,
+}
+
+/// Raw sample accumulation of a render, which more samples can be added to at any time, e.g. to continue a long
+/// render from a checkpoint after a crash. Records scene hash and seed of the render so it is only continued with
+/// the same ones. Scene hashes are only comparable between builds hashing alike, so the fingerprint of the hash
+/// encoding is recorded too, see `hash::fingerprint`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Accumulation {
+    /// Image width in pixels.
+    width: u32,
+
+    /// Image height in pixels.
+    height: u32,
+
+    /// Fingerprint of content hash encoding of the build that hashed the scene.
+    fingerprint: u64,
+
+    /// Content hash of rendered scene.
+    scene_hash: u64,
+
+    /// Seed of rendering camera.
+    seed: u64,
+
+    /// Pixels row by row.
+    pixels: Vec<AccumulatedPixel>,
+}
+
+impl Accumulation {
+    /// Creates empty accumulation for render of scene by camera with settings.
+    pub fn new(camera: &Camera, scene: &Scene, settings: &RenderSettings) -> Self {
+        let (width, height) = camera.resolution();
+        Accumulation {
+            width,
+            height,
+            fingerprint: hash::fingerprint(),
+            scene_hash: scene.content_hash(),
+            seed: settings.seed(),
+            pixels: vec![
+                AccumulatedPixel {
+                    sum: Color3f::black(),
+                    samples: 0,
+                };
+                (width * height) as usize
+            ],
+        }
+    }
+
+    /// Image width in pixels.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Image height in pixels.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Whether accumulation belongs to render of scene by camera with settings.
+    pub fn matches(&self, camera: &Camera, scene: &Scene, settings: &RenderSettings) -> bool {
+        (self.width, self.height) == camera.resolution()
+            && self.fingerprint == hash::fingerprint()
+            && self.scene_hash == scene.content_hash()
+            && self.seed == settings.seed()
+    }
+
+    /// Accumulated pixel x,y.
+    pub fn pixel(&self, x: u32, y: u32) -> AccumulatedPixel {
+        self.pixels[(y * self.width + x) as usize]
+    }
+
+    /// Pixels row by row.
+    pub fn pixels(&self) -> &[AccumulatedPixel] {
+        &self.pixels
+    }
+
+    /// Mutable pixels row by row.
+    pub(crate) fn pixels_mut(&mut self) -> &mut [AccumulatedPixel] {
+        &mut self.pixels
+    }
+
+    /// Image of mean radiance per pixel, black where no samples were taken yet.
+    pub fn to_film(&self, precision: FilmPrecision) -> Film {
+        let mut film = Film::new(self.width, self.height, precision);
+        for (i, pixel) in self.pixels.iter().enumerate() {
+            if pixel.samples > 0 {
+                let (x, y) = (i as u32 % self.width, i as u32 / self.width);
+                film.set_pixel(x, y, pixel.sum / pixel.samples as f32);
+            }
+        }
+        film
+    }
+
+    /// Loads accumulation from checkpoint file.
+    pub fn load(path: &Path) -> Result<Accumulation, CheckpointError> {
+        let bytes = fs::read(path)?;
+        let format = |message: &str| CheckpointError::Format(message.to_string());
+        let header = bytes
+            .get(..MAGIC.len() + HEADER_SIZE)
+            .ok_or_else(|| format("truncated header"))?;
+        if &header[..MAGIC.len()] != MAGIC {
+            return Err(format("unknown file type or version"));
+        }
+        let word = |i: usize| &header[MAGIC.len() + i..];
+        let width = u32::from_le_bytes(word(0)[..4].try_into().unwrap());
+        let height = u32::from_le_bytes(word(4)[..4].try_into().unwrap());
+        let fingerprint = u64::from_le_bytes(word(8)[..8].try_into().unwrap());
+        let scene_hash = u64::from_le_bytes(word(16)[..8].try_into().unwrap());
+        let seed = u64::from_le_bytes(word(24)[..8].try_into().unwrap());
+        let data = &bytes[header.len()..];
+        if data.len() as u64 != width as u64 * height as u64 * PIXEL_SIZE as u64 {
+            return Err(format("pixel data does not match resolution"));
+        }
+        let pixels = data
+            .chunks_exact(PIXEL_SIZE)
+            .map(|chunk| {
+                let value = |i: usize| chunk[4 * i..4 * i + 4].try_into().unwrap();
+                AccumulatedPixel {
+                    sum: Color3f::new(
+                        f32::from_le_bytes(value(0)),
+                        f32::from_le_bytes(value(1)),
+                        f32::from_le_bytes(value(2)),
+                    ),
+                    samples: u32::from_le_bytes(value(3)),
+                }
+            })
+            .collect();
+        Ok(Accumulation {
+            width,
+            height,
+            fingerprint,
+            scene_hash,
+            seed,
+            pixels,
+        })
+    }
+
+    /// Saves accumulation to checkpoint file, replacing it atomically so a crash while saving keeps the previous
+    /// checkpoint. Binary little endian: magic, width, height, hash fingerprint, scene hash, seed, then per pixel
+    /// radiance sum and sample count.
+    pub fn save(&self, path: &Path) -> Result<(), CheckpointError> {
+        let mut bytes =
+            Vec::with_capacity(MAGIC.len() + HEADER_SIZE + self.pixels.len() * PIXEL_SIZE);
+        bytes.extend(MAGIC);
+        bytes.extend(self.width.to_le_bytes());
+        bytes.extend(self.height.to_le_bytes());
+        bytes.extend(self.fingerprint.to_le_bytes());
+        bytes.extend(self.scene_hash.to_le_bytes());
+        bytes.extend(self.seed.to_le_bytes());
+        for pixel in &self.pixels {
+            for channel in [pixel.sum.r(), pixel.sum.g(), pixel.sum.b()] {
+                bytes.extend(channel.to_le_bytes());
+            }
+            bytes.extend(pixel.samples.to_le_bytes());
+        }
+        let temporary = path.with_extension("tmp");
+        fs::write(&temporary, bytes)?;
+        fs::rename(&temporary, path)?;
+        Ok(())
+    }
+}
+
+/// Renders scene with settings in passes of given samples per pixel, saving the accumulation to checkpoint file after
+/// each pass. Continues from the checkpoint if it exists, failing if it belongs to a different render. Passes are
+/// handed to observer with the accumulation so far, e.g. to report progress.
+pub fn render_checkpointed<F>(
+    camera: &mut Camera,
+    scene: &Scene,
+    settings: &RenderSettings,
+    path: &Path,
+    samples_per_pass: u32,
+    observer: F,
+) -> Result<Film, CheckpointError>
+where
+    F: Fn(&Accumulation),
+{
+    assert!(samples_per_pass > 0, "passes must take samples");
+    let mut accumulation = match Accumulation::load(path) {
+        Ok(accumulation) if accumulation.matches(camera, scene, settings) => accumulation,
+        Ok(_) => return Err(CheckpointError::Mismatch),
+        Err(CheckpointError::Io(e)) if e.kind() == io::ErrorKind::NotFound => {
+            Accumulation::new(camera, scene, settings)
+        }
+        Err(e) => return Err(e),
+    };
+    loop {
+        let done = camera.render_accumulated(scene, settings, &mut accumulation, samples_per_pass);
+        accumulation.save(path)?;
+        observer(&accumulation);
+        if done {
+            break;
+        }
+    }
+    Ok(accumulation.to_film(settings.film_precision()))
+}
+
+/// Progress of frame of sequence render.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FrameState {
+    /// Not started yet.
+    Pending,
+
+    /// Started, samples are accumulated in checkpoint file at path.
+    Rendering(String),
+
+    /// Finished, image written to path.
+    Done(String),
+}
+
+/// Progress manifest of a sequence render, e.g. of an animation, saved whenever a frame starts or finishes so an
+/// interrupted render resumes at the first unfinished frame and continues its checkpoint instead of restarting from
+/// the first frame. Persisted as text: a header line `frames` and the count of frames, then one line per started
+/// frame with frame index, state (`rendering` or `done`) and path of checkpoint or image, separated by tabs.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SequenceManifest {
+    /// States by frame index.
+    states: Vec<FrameState>,
+}
+
+impl SequenceManifest {
+    /// Creates manifest of given count of frames, none started.
+    pub fn new(frames: u32) -> Self {
+        SequenceManifest {
+            states: vec![FrameState::Pending; frames as usize],
+        }
+    }
+
+    /// Loads manifest of sequence with given count of frames from file. A missing file is a sequence not started
+    /// yet, one with another count of frames belongs to a different render.
+    pub fn load(path: &Path, frames: u32) -> Result<SequenceManifest, CheckpointError> {
+        let manifest = match fs::read_to_string(path) {
+            Ok(text) => SequenceManifest::parse(&text)?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                return Ok(SequenceManifest::new(frames))
+            }
+            Err(e) => return Err(e.into()),
+        };
+        if manifest.frames() != frames {
+            return Err(CheckpointError::Mismatch);
+        }
+        Ok(manifest)
+    }
+
+    /// Saves manifest to file, replacing it atomically so a crash while saving keeps the previous manifest.
+    pub fn save(&self, path: &Path) -> Result<(), CheckpointError> {
+        let temporary = path.with_extension("tmp");
+        fs::write(&temporary, self.to_text())?;
+        fs::rename(&temporary, path)?;
+        Ok(())
+    }
+
+    /// Parses manifest from text.
+    pub fn parse(text: &str) -> Result<SequenceManifest, CheckpointError> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.is_empty());
+        let format = |line: usize, message: &str| {
+            CheckpointError::Format(format!("line {}: {}", line + 1, message))
+        };
+        let frames = match lines.next().map(|(i, line)| (i, line.split_once('\t'))) {
+            Some((_, Some(("frames", count)))) => count
+                .parse()
+                .map_err(|_| format(0, "invalid frame count"))?,
+            Some((i, _)) => return Err(format(i, "missing frame count")),
+            None => return Err(format(0, "empty manifest")),
+        };
+        let mut manifest = SequenceManifest::new(frames);
+        for (i, line) in lines {
+            let mut fields = line.splitn(3, '\t');
+            let frame: usize = fields
+                .next()
+                .and_then(|frame| frame.parse().ok())
+                .filter(|&frame| frame < manifest.states.len())
+                .ok_or_else(|| format(i, "invalid frame"))?;
+            let state = fields.next();
+            let path = fields
+                .next()
+                .ok_or_else(|| format(i, "missing path"))?
+                .to_string();
+            manifest.states[frame] = match state {
+                Some("rendering") => FrameState::Rendering(path),
+                Some("done") => FrameState::Done(path),
+                _ => return Err(format(i, "invalid frame state")),
+            };
+        }
+        Ok(manifest)
+    }
+
+    /// Manifest as text, see `parse`.
+    pub fn to_text(&self) -> String {
+        let mut text = format!("frames\t{}\n", self.frames());
+        for (frame, state) in self.states.iter().enumerate() {
+            let (name, path) = match state {
+                FrameState::Pending => continue,
+                FrameState::Rendering(path) => ("rendering", path),
+                FrameState::Done(path) => ("done", path),
+            };
+            text += &format!("{}\t{}\t{}\n", frame, name, path);
+        }
+        text
+    }
+
+    /// Count of frames.
+    pub fn frames(&self) -> u32 {
+        self.states.len() as u32
+    }
+
+    /// Progress of frame.
+    pub fn state(&self, frame: u32) -> &FrameState {
+        &self.states[frame as usize]
+    }
+
+    /// Whether frame is finished and its image still exists, so it need not be rendered again.
+    pub fn is_done(&self, frame: u32) -> bool {
+        matches!(self.state(frame), FrameState::Done(image) if Path::new(image).exists())
+    }
+
+    /// Marks frame as started, accumulating samples in checkpoint file at path, which must not contain tabs or
+    /// line breaks.
+    pub fn start(&mut self, frame: u32, checkpoint: &str) {
+        assert!(!checkpoint.contains(['\t', '\n', '\r']));
+        self.states[frame as usize] = FrameState::Rendering(checkpoint.to_string());
+    }
+
+    /// Marks frame as finished with image written to path, which must not contain tabs or line breaks.
+    pub fn finish(&mut self, frame: u32, image: &str) {
+        assert!(!image.contains(['\t', '\n', '\r']));
+        self.states[frame as usize] = FrameState::Done(image.to_string());
+    }
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        base::{material::Material, point::Point3f, shape::Shape},
+        materials::lambert::Lambert,
+        shapes::sphere::Sphere,
+    };
+    use std::{cell::Cell, env};
+
+    fn scene() -> Scene {
+        let mut scene = Scene::new();
+        scene.add(Shape::Sphere(Sphere::new(
+            Point3f::new(0.0, 0.0, -1.0),
+            0.5,
+            Material::Lambert(Lambert::new(Color3f::new(0.5, 0.2, 0.1))),
+        )));
+        scene
+    }
+
+    fn camera() -> Camera {
+        Camera::new(12, 8)
+    }
+
+    #[test]
+    fn accumulation() {
+        let path = env::temp_dir().join(format!("eden-checkpoint-{}.bin", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        // Interrupted render continues from checkpoint.
+        let settings = RenderSettings::new(6);
+        let passes = Cell::new(0);
+        let first = render_checkpointed(&mut camera(), &scene(), &settings, &path, 4, |a| {
+            passes.set(passes.get() + 1);
+            assert_eq!(a.pixel(3, 2).samples, [4, 6][passes.get() - 1]);
+        })
+        .unwrap();
+        assert_eq!(passes.get(), 2);
+        let mut accumulation = Accumulation::load(&path).unwrap();
+        assert!(accumulation.pixels().iter().all(|p| p.samples == 6));
+        assert_eq!(
+            accumulation.to_film(FilmPrecision::Full).pixels().count(),
+            96
+        );
+        assert!(first
+            .pixels()
+            .eq(accumulation.to_film(FilmPrecision::Full).pixels()));
+
+        // More samples are added when asked for.
+        let more = RenderSettings::new(8);
+        assert!(camera().render_accumulated(&scene(), &more, &mut accumulation, 4));
+        assert!(accumulation.pixels().iter().all(|p| p.samples == 8));
+
+        // Single pass equals normal render, other renders do not continue checkpoint.
+        let film = render_checkpointed(&mut camera(), &scene(), &more, &path, 16, |_| {}).unwrap();
+        assert!(film.pixels().ne(first.pixels()));
+        fs::remove_file(&path).unwrap();
+        let film =
+            render_checkpointed(&mut camera(), &scene(), &settings, &path, 6, |_| {}).unwrap();
+        assert!(film
+            .pixels()
+            .eq(camera().render(&scene(), &settings).pixels()));
+        assert!(matches!(
+            render_checkpointed(&mut camera(), &Scene::new(), &settings, &path, 6, |_| {}),
+            Err(CheckpointError::Mismatch)
+        ));
+
+        // Checkpoints of builds hashing differently do not continue.
+        let mut foreign = Accumulation::new(&camera(), &scene(), &settings);
+        foreign.fingerprint ^= 1;
+        foreign.save(&path).unwrap();
+        assert!(matches!(
+            render_checkpointed(&mut camera(), &scene(), &settings, &path, 6, |_| {}),
+            Err(CheckpointError::Mismatch)
+        ));
+
+        // Corrupt files fail.
+        fs::write(&path, b"EDENACC2").unwrap();
+        assert!(matches!(
+            Accumulation::load(&path),
+            Err(CheckpointError::Format(_))
+        ));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn sequence_manifest() {
+        let mut manifest = SequenceManifest::new(3);
+        manifest.start(0, "frame_0001.checkpoint");
+        manifest.finish(0, "frame_0001.ppm");
+        manifest.start(1, "frame_0002.checkpoint");
+        let text = manifest.to_text();
+        assert_eq!(
+            text,
+            "frames\t3\n0\tdone\tframe_0001.ppm\n1\trendering\tframe_0002.checkpoint\n"
+        );
+        assert_eq!(SequenceManifest::parse(&text).unwrap(), manifest);
+        assert_eq!(manifest.state(2), &FrameState::Pending);
+
+        // Frames are only done while their image exists.
+        assert!(!manifest.is_done(0));
+        let path = env::temp_dir().join(format!("eden-manifest-{}.tsv", std::process::id()));
+        manifest.finish(2, &path.to_string_lossy());
+        manifest.save(&path).unwrap();
+        assert!(manifest.is_done(2));
+
+        // Manifests of other sequences and malformed ones fail, missing ones start over.
+        assert_eq!(SequenceManifest::load(&path, 3).unwrap(), manifest);
+        assert!(matches!(
+            SequenceManifest::load(&path, 4),
+            Err(CheckpointError::Mismatch)
+        ));
+        fs::remove_file(&path).unwrap();
+        assert_eq!(
+            SequenceManifest::load(&path, 4).unwrap(),
+            SequenceManifest::new(4)
+        );
+        for text in [
+            "",
+            "0\tdone\ta.ppm\n",
+            "frames\t2\n2\tdone\ta.ppm\n",
+            "frames\t2\n0\tlost\ta\n",
+        ] {
+            assert!(matches!(
+                SequenceManifest::parse(text),
+                Err(CheckpointError::Format(_))
+            ));
+        }
+    }
+}
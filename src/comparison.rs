@@ -0,0 +1,268 @@
+use crate::{
+    base::color::Color3f,
+    camera::Camera,
+    film::{Film, FilmPrecision},
+    font::{draw_text, GLYPH_HEIGHT},
+    integrator::Integrator,
+    integrators::{
+        light_visibility::LightVisibility, path_tracer::PathTracer, photon_mapper::PhotonMapper,
+        precision_debug::PrecisionDebug,
+    },
+    sampler::Sampler,
+    scene::Scene,
+    settings::RenderSettings,
+};
+
+/// Height of label strip above each image in pixels.
+const LABEL_HEIGHT: u32 = GLYPH_HEIGHT + 4;
+
+/// Render configuration of comparison grid cell. Unset settings are taken from the given render settings.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Configuration {
+    /// Label shown above image.
+    label: String,
+
+    /// Light transport algorithm.
+    integrator: Option<Integrator>,
+
+    /// Strategy for generating pixel samples.
+    sampler: Option<Sampler>,
+
+    /// Count of random samples per pixel.
+    samples_per_pixel: Option<u32>,
+}
+
+impl Configuration {
+    /// Names of configuration presets.
+    pub const PRESETS: [&'static str; 4] = ["caustics", "lights", "photons", "precision"];
+
+    /// Configuration preset with given name, see `PRESETS`, labeled by its name. Returns none if unknown.
+    ///
+    /// `caustics` targets glass under small lights: deep paths let light pass glass with several internal
+    /// reflections, clamping at diffuse bounces removes fireflies of caustics hit by chance, and many stratified
+    /// samples resolve the remaining caustic light.
+    ///
+    /// `lights` previews only emitters and their direct light on white clay surfaces, fast enough to position and
+    /// balance lights interactively before rendering global illumination.
+    ///
+    /// `photons` renders caustics from a photon map instead of finding them by path tracing, converging in a
+    /// fraction of the samples of `caustics` at the cost of slightly blurred caustics.
+    ///
+    /// `precision` flags pixels where single precision intersections disagree with double precision ones, to tell
+    /// acne and leaks caused by rounding from other artifacts. Slow, few samples suffice to see the fraction flagged.
+    pub fn preset(name: &str) -> Option<Configuration> {
+        let mut configuration = Configuration::new(name);
+        match name {
+            "caustics" => {
+                let mut path_tracer = PathTracer::new(24);
+                path_tracer.set_max_radiance(8.0);
+                configuration.set_integrator(Integrator::PathTracer(path_tracer));
+                configuration.set_sampler(Sampler::Halton);
+                configuration.set_samples_per_pixel(256);
+            }
+            "lights" => {
+                configuration.set_integrator(Integrator::LightVisibility(LightVisibility));
+                configuration.set_samples_per_pixel(4);
+            }
+            "photons" => {
+                configuration.set_integrator(Integrator::PhotonMapper(PhotonMapper::new(24)));
+                configuration.set_sampler(Sampler::Halton);
+                configuration.set_samples_per_pixel(64);
+            }
+            "precision" => {
+                configuration.set_integrator(Integrator::PrecisionDebug(PrecisionDebug::default()));
+                configuration.set_samples_per_pixel(16);
+            }
+            _ => return None,
+        }
+        Some(configuration)
+    }
+
+    /// Creates configuration with label, using given render settings.
+    pub fn new(label: &str) -> Self {
+        Configuration {
+            label: label.to_string(),
+            integrator: None,
+            sampler: None,
+            samples_per_pixel: None,
+        }
+    }
+
+    /// Label shown above image.
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    /// Sets light transport algorithm.
+    pub fn set_integrator(&mut self, integrator: Integrator) {
+        self.integrator = Some(integrator);
+    }
+
+    /// Sets strategy for generating pixel samples.
+    pub fn set_sampler(&mut self, sampler: Sampler) {
+        self.sampler = Some(sampler);
+    }
+
+    /// Sets samples per pixel.
+    pub fn set_samples_per_pixel(&mut self, samples_per_pixel: u32) {
+        self.samples_per_pixel = Some(samples_per_pixel);
+    }
+
+    /// Copy of render settings with configuration applied.
+    pub fn apply(&self, settings: &RenderSettings) -> RenderSettings {
+        let mut settings = settings.clone();
+        if let Some(integrator) = self.integrator {
+            settings.set_integrator(integrator);
+        }
+        if let Some(sampler) = self.sampler {
+            settings.set_sampler(sampler);
+        }
+        if let Some(samples_per_pixel) = self.samples_per_pixel {
+            settings.set_samples_per_pixel(samples_per_pixel);
+        }
+        settings
+    }
+}
+
+/// Renders scene once per configuration of settings and composes images row by row into a labeled contact sheet
+/// with given count of columns.
+pub fn render_comparison(
+    camera: &Camera,
+    scene: &Scene,
+    settings: &RenderSettings,
+    configurations: &[Configuration],
+    columns: u32,
+) -> Film {
+    let cells: Vec<(String, Film)> = configurations
+        .iter()
+        .map(|c| {
+            let image = camera.clone().render(scene, &c.apply(settings));
+            (c.label.clone(), image)
+        })
+        .collect();
+    contact_sheet(&cells, columns)
+}
+
+/// Composes labeled images row by row into a contact sheet with given count of columns. Cells are sized to fit the
+/// largest image, with images at their top left below a label strip. Labels are clipped to cells.
+pub fn contact_sheet(cells: &[(String, Film)], columns: u32) -> Film {
+    assert!(columns > 0);
+    let width = cells
+        .iter()
+        .map(|(_, image)| image.width())
+        .max()
+        .unwrap_or(0);
+    let height = cells
+        .iter()
+        .map(|(_, image)| image.height())
+        .max()
+        .unwrap_or(0);
+    let cell_height = LABEL_HEIGHT + height;
+    let rows = (cells.len() as u32).div_ceil(columns);
+    let mut sheet = Film::new(columns * width, rows * cell_height, FilmPrecision::Full);
+
+    for (i, (text, image)) in cells.iter().enumerate() {
+        let (left, top) = (
+            (i as u32 % columns) * width,
+            (i as u32 / columns) * cell_height,
+        );
+
+        // Image below label strip.
+        for y in 0..image.height() {
+            for x in 0..image.width() {
+                sheet.set_pixel(left + x, top + LABEL_HEIGHT + y, image.pixel(x, y));
+            }
+        }
+
+        // Label, clipped to cell.
+        let mut label = Film::new(width, LABEL_HEIGHT, FilmPrecision::Full);
+        draw_text(&mut label, 2, 2, text, Color3f::white());
+        for y in 0..LABEL_HEIGHT {
+            for x in 0..width {
+                sheet.set_pixel(left + x, top + y, label.pixel(x, y));
+            }
+        }
+    }
+    sheet
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        base::{material::Material, point::Point3f, shape::Shape},
+        integrators::normal_debug::NormalDebug,
+        materials::lambert::Lambert,
+        shapes::sphere::Sphere,
+    };
+
+    #[test]
+    fn render_comparison() {
+        let mut scene = Scene::new();
+        let material = Material::Lambert(Lambert::new(Color3f::new(0.5, 0.5, 0.5)));
+        scene.add(Shape::Sphere(Sphere::new(
+            Point3f::default(),
+            0.5,
+            material,
+        )));
+        let mut camera = Camera::new(12, 8);
+        camera.set_look_from(Point3f::new(0.0, 0.0, 2.0));
+        let settings = RenderSettings::new(2);
+
+        let mut normals = Configuration::new("normals");
+        normals.set_integrator(Integrator::NormalDebug(NormalDebug));
+        let mut halton = Configuration::new("halton 4");
+        halton.set_sampler(Sampler::Halton);
+        halton.set_samples_per_pixel(4);
+        let configurations = [Configuration::new("default"), normals, halton.clone()];
+        let sheet = super::render_comparison(&camera, &scene, &settings, &configurations, 2);
+        assert_eq!(
+            (sheet.width(), sheet.height()),
+            (24, 2 * (LABEL_HEIGHT + 8))
+        );
+
+        // Third cell starts second row, below its label.
+        let image = camera.render(&scene, &halton.apply(&settings));
+        let top = LABEL_HEIGHT + 8 + LABEL_HEIGHT;
+        for y in 0..8 {
+            for x in 0..12 {
+                assert_eq!(sheet.pixel(x, top + y), image.pixel(x, y));
+            }
+        }
+
+        // Labels are drawn, empty cell stays black.
+        for (x, y) in [(0, 2), (12, 2), (0, top - LABEL_HEIGHT + 2)] {
+            assert!((x..x + 12).any(|x| sheet.pixel(x, y) == Color3f::white()));
+        }
+        assert_eq!(sheet.pixel(20, top + 4), Color3f::black());
+    }
+
+    #[test]
+    fn contact_sheet() {
+        // Cells fit largest image, smaller images sit top left.
+        let mut small = Film::new(2, 2, FilmPrecision::Full);
+        small.set_pixel(1, 1, Color3f::white());
+        let large = Film::new(6, 3, FilmPrecision::Half);
+        let cells = [("a".to_string(), small), ("b".to_string(), large)];
+        let sheet = super::contact_sheet(&cells, 1);
+        assert_eq!((sheet.width(), sheet.height()), (6, 2 * (LABEL_HEIGHT + 3)));
+        assert_eq!(sheet.pixel(1, LABEL_HEIGHT + 1), Color3f::white());
+        assert_eq!(sheet.pixel(1, LABEL_HEIGHT + 2), Color3f::black());
+    }
+
+    #[test]
+    fn preset() {
+        for name in Configuration::PRESETS {
+            assert_eq!(Configuration::preset(name).unwrap().label(), name);
+        }
+        assert_eq!(Configuration::preset("fast"), None);
+
+        let caustics = Configuration::preset("caustics").unwrap();
+        let Some(Integrator::PathTracer(path_tracer)) = caustics.integrator else {
+            panic!("caustics preset uses path tracer");
+        };
+        assert!(path_tracer.max_radiance().is_finite());
+        assert_eq!(caustics.samples_per_pixel, Some(256));
+    }
+}
@@ -0,0 +1,129 @@
+use crate::{
+    base::{hash::hash_float, material::Material, point::Point3f, vector::Vector3f},
+    image::RgbaImage,
+    materials::lambert::Lambert,
+};
+use rand::Rng;
+use std::{
+    hash::{Hash, Hasher},
+    sync::Arc,
+};
+
+/// Image projected onto surfaces within a box, layered over the base material. Decals are attached to an object, see
+/// `Scene::add_decal`, with the box placed in the object's local space, so they follow it as it moves. Decal pixels
+/// behave like a lambertian surface with the image color as albedo, blended by alpha coverage.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Decal {
+    /// Center of projection box.
+    center: Point3f,
+
+    /// Half extent of projection box along image horizontal axis.
+    half_u: Vector3f,
+
+    /// Half extent of projection box along image vertical axis.
+    half_v: Vector3f,
+
+    /// Half extent of projection box along projection direction.
+    half_depth: f32,
+
+    /// Projected image.
+    image: Arc<RgbaImage>,
+}
+
+impl Decal {
+    /// Creates decal projecting image along -(u x v) onto surfaces within box spanned by center, full image width
+    /// and height vectors u,v and depth.
+    pub fn new(
+        center: Point3f,
+        u: Vector3f,
+        v: Vector3f,
+        depth: f32,
+        image: Arc<RgbaImage>,
+    ) -> Self {
+        Decal {
+            center,
+            half_u: u / 2.0,
+            half_v: v / 2.0,
+            half_depth: depth / 2.0,
+            image,
+        }
+    }
+
+    /// Texture coordinates of point in local space of object, if inside projection box.
+    pub fn project(&self, point: Point3f) -> Option<(f32, f32)> {
+        let offset = point - self.center;
+        let u = offset.dot(&self.half_u) / self.half_u.length_squared();
+        let v = offset.dot(&self.half_v) / self.half_v.length_squared();
+        let normal = self.half_u.cross(&self.half_v).normalize();
+        let depth = offset.dot(&normal);
+        if u.abs() > 1.0 || v.abs() > 1.0 || depth.abs() > self.half_depth {
+            return None;
+        }
+        Some(((u + 1.0) / 2.0, (v + 1.0) / 2.0))
+    }
+
+    /// Stochastically selects decal material at point in local space of object with probability of its alpha
+    /// coverage. Returns none if base material shows through.
+    pub fn material_at(&self, point: Point3f, rng: &mut impl Rng) -> Option<Material> {
+        let (u, v) = self.project(point)?;
+        let (color, alpha) = self.image.sample(u, v);
+        if alpha <= 0.0 || rng.gen::<f32>() >= alpha {
+            return None;
+        }
+        Some(Material::Lambert(Lambert::new(color)))
+    }
+}
+
+impl Hash for Decal {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.center.hash(state);
+        self.half_u.hash(state);
+        self.half_v.hash(state);
+        hash_float(self.half_depth, state);
+        self.image.hash(state);
+    }
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::color::Color3f;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    fn decal(alpha: f32) -> Decal {
+        let image = RgbaImage::new(1, 1, vec![Color3f::new(1.0, 0.0, 0.0)], vec![alpha]);
+        Decal::new(
+            Point3f::new(0.0, 1.0, 0.0),
+            Vector3f::new(2.0, 0.0, 0.0),
+            Vector3f::new(0.0, 0.0, -4.0),
+            0.5,
+            Arc::new(image),
+        )
+    }
+
+    #[test]
+    fn project() {
+        let d = decal(1.0);
+        assert_eq!(d.project(Point3f::new(0.0, 1.0, 0.0)), Some((0.5, 0.5)));
+        assert_eq!(d.project(Point3f::new(1.0, 1.2, -2.0)), Some((1.0, 1.0)));
+        assert_eq!(d.project(Point3f::new(-0.5, 0.8, 1.0)), Some((0.25, 0.25)));
+        assert_eq!(d.project(Point3f::new(1.1, 1.0, 0.0)), None);
+        assert_eq!(d.project(Point3f::new(0.0, 1.3, 0.0)), None);
+    }
+
+    #[test]
+    fn material_at() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let p = Point3f::new(0.0, 1.0, 0.0);
+        let red = Material::Lambert(Lambert::new(Color3f::new(1.0, 0.0, 0.0)));
+        assert_eq!(decal(1.0).material_at(p, &mut rng), Some(red));
+        assert_eq!(decal(0.0).material_at(p, &mut rng), None);
+        assert_eq!(decal(1.0).material_at(Point3f::default(), &mut rng), None);
+
+        let hits = (0..1000)
+            .filter(|_| decal(0.25).material_at(p, &mut rng).is_some())
+            .count();
+        assert!((200..300).contains(&hits));
+    }
+}
@@ -0,0 +1,221 @@
+use crate::{
+    base::color::Color3f,
+    camera::Camera,
+    film::Film,
+    integrator::Integrator,
+    integrators::{albedo_debug::AlbedoDebug, normal_debug::NormalDebug},
+    scene::Scene,
+    settings::RenderSettings,
+};
+use rayon::prelude::*;
+
+/// Samples per pixel of guide buffers, antialiasing edges about as well as the image.
+const GUIDE_SAMPLES: u32 = 16;
+
+/// Weights of B3 spline kernel per axis, spread further apart each iteration.
+const KERNEL: [f32; 5] = [1.0 / 16.0, 1.0 / 4.0, 3.0 / 8.0, 1.0 / 4.0, 1.0 / 16.0];
+
+/// Added to albedo before dividing it out of colors, keeping black surfaces finite.
+const ALBEDO_EPSILON: f32 = 1e-3;
+
+/// Edge stopping scale of normal guide differences (normals mapped to [0,1]).
+const NORMAL_SIGMA: f32 = 0.1;
+
+/// Edge stopping scale of albedo guide differences.
+const ALBEDO_SIGMA: f32 = 0.1;
+
+/// Feature buffers guiding the denoiser along edges of geometry and texture: albedo and normals at first hits, as
+/// rendered by `AlbedoDebug` and `NormalDebug`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Guides {
+    /// Albedo at first hits.
+    pub albedo: Film,
+
+    /// Normals at first hits, components mapped to [0,1].
+    pub normal: Film,
+}
+
+impl Guides {
+    /// Renders guides of scene as seen by camera with settings, replacing their integrator, sample counts and
+    /// firefly controls.
+    pub fn render(camera: &mut Camera, scene: &Scene, settings: &RenderSettings) -> Self {
+        let mut settings = settings.clone();
+        settings.set_samples_per_pixel(GUIDE_SAMPLES);
+        settings.clear_priority_regions();
+        settings.set_max_sample_luminance(f32::INFINITY);
+        settings.set_outlier_rejection(None);
+        settings.set_integrator(Integrator::AlbedoDebug(AlbedoDebug));
+        let albedo = camera.render(scene, &settings);
+        settings.set_integrator(Integrator::NormalDebug(NormalDebug));
+        let normal = camera.render(scene, &settings);
+        Guides { albedo, normal }
+    }
+}
+
+/// Edge-avoiding à-trous wavelet denoiser (Dammertz et al. 2010), cleaning up images rendered with few samples per
+/// pixel. Each iteration blurs with a 5x5 kernel whose taps are spread twice as far as in the previous one, weighting
+/// neighbors down where color, normal or albedo differ. Albedo is divided out before filtering and multiplied back
+/// afterwards, so textures stay sharp while the lighting on them is smoothed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Denoiser {
+    /// Count of filter iterations, the filter reaching 2^(iterations+1) pixels far.
+    iterations: u32,
+
+    /// Edge stopping scale of color differences (of colors compressed to [0,1)) in the first iteration, halved in
+    /// each following one.
+    color_sigma: f32,
+}
+
+impl Denoiser {
+    /// Creates denoiser with count of iterations and edge stopping scale of color differences.
+    pub fn new(iterations: u32, color_sigma: f32) -> Self {
+        assert!(color_sigma > 0.0);
+        Denoiser {
+            iterations,
+            color_sigma,
+        }
+    }
+
+    /// Count of filter iterations.
+    pub fn iterations(&self) -> u32 {
+        self.iterations
+    }
+
+    /// Edge stopping scale of color differences in the first iteration.
+    pub fn color_sigma(&self) -> f32 {
+        self.color_sigma
+    }
+
+    /// Denoised copy of image, guided by guides of the same resolution.
+    pub fn denoise(&self, image: &Film, guides: &Guides) -> Film {
+        let (width, height) = (image.width(), image.height());
+        assert!(
+            (guides.albedo.width(), guides.albedo.height()) == (width, height)
+                && (guides.normal.width(), guides.normal.height()) == (width, height)
+        );
+        let albedo: Vec<Color3f> = guides.albedo.pixels().map(|a| a + ALBEDO_EPSILON).collect();
+        let normal: Vec<Color3f> = guides.normal.pixels().collect();
+        let mut irradiance: Vec<Color3f> =
+            image.pixels().zip(&albedo).map(|(c, &a)| c / a).collect();
+
+        // Filter iterations.
+        let mut sigma = self.color_sigma;
+        for iteration in 0..self.iterations {
+            let step = 1i64 << iteration;
+            let source = &irradiance;
+            let mut filtered = vec![Color3f::black(); source.len()];
+            filtered
+                .par_chunks_mut(width as usize)
+                .enumerate()
+                .for_each(|(y, row)| {
+                    for (x, pixel) in row.iter_mut().enumerate() {
+                        let p = y * width as usize + x;
+                        let center = compress(source[p]);
+                        let (mut sum, mut weights) = (Color3f::black(), 0.0);
+                        for (j, ky) in KERNEL.iter().enumerate() {
+                            let qy = y as i64 + (j as i64 - 2) * step;
+                            if qy < 0 || qy >= height as i64 {
+                                continue;
+                            }
+                            for (i, kx) in KERNEL.iter().enumerate() {
+                                let qx = x as i64 + (i as i64 - 2) * step;
+                                if qx < 0 || qx >= width as i64 {
+                                    continue;
+                                }
+                                let q = qy as usize * width as usize + qx as usize;
+                                let exponent = distance_squared(center, compress(source[q]))
+                                    / (sigma * sigma)
+                                    + distance_squared(normal[p], normal[q])
+                                        / (NORMAL_SIGMA * NORMAL_SIGMA)
+                                    + distance_squared(albedo[p], albedo[q])
+                                        / (ALBEDO_SIGMA * ALBEDO_SIGMA);
+                                let weight = kx * ky * (-exponent).exp();
+                                sum += source[q] * weight;
+                                weights += weight;
+                            }
+                        }
+                        *pixel = sum / weights;
+                    }
+                });
+            irradiance = filtered;
+            sigma *= 0.5;
+        }
+
+        // Multiply albedo back.
+        let mut denoised = Film::new(width, height, image.precision());
+        for (i, (&e, &a)) in irradiance.iter().zip(&albedo).enumerate() {
+            let (x, y) = (i as u32 % width, i as u32 / width);
+            denoised.set_pixel(x, y, e * a);
+        }
+        denoised
+    }
+}
+
+impl Default for Denoiser {
+    /// Default denoiser with 5 iterations and color scale 0.5.
+    fn default() -> Self {
+        Denoiser::new(5, 0.5)
+    }
+}
+
+/// Color compressed to [0,1) per component, so color differences weigh alike in dark and bright regions.
+fn compress(color: Color3f) -> Color3f {
+    color / (color + 1.0)
+}
+
+/// Squared Euclidean distance of colors.
+fn distance_squared(a: Color3f, b: Color3f) -> f32 {
+    let d = a - b;
+    d.r() * d.r() + d.g() * d.g() + d.b() * d.b()
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::film::FilmPrecision;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    /// Film of size with pixel colors given by function of position.
+    fn film(width: u32, height: u32, mut color: impl FnMut(u32, u32) -> Color3f) -> Film {
+        let mut film = Film::new(width, height, FilmPrecision::Full);
+        for y in 0..height {
+            for x in 0..width {
+                film.set_pixel(x, y, color(x, y));
+            }
+        }
+        film
+    }
+
+    #[test]
+    fn denoise() {
+        // Noisy gray image over two halves of different albedo, lit alike.
+        let (width, height) = (64, 32);
+        let albedo = |x: u32| if x < width / 2 { 0.2 } else { 0.8 };
+        let guides = Guides {
+            albedo: film(width, height, |x, _| Color3f::white() * albedo(x)),
+            normal: film(width, height, |_, _| Color3f::new(0.5, 1.0, 0.5)),
+        };
+        let mut rng = StdRng::seed_from_u64(3);
+        let noisy = film(width, height, |x, _| {
+            Color3f::white() * (albedo(x) * rng.gen_range(0.5..1.5))
+        });
+        let denoised = Denoiser::default().denoise(&noisy, &guides);
+
+        // Error drops by far, halves stay apart at the edge.
+        let error = |image: &Film| {
+            let squared = (0..height)
+                .flat_map(|y| (0..width).map(move |x| (x, y)))
+                .map(|(x, y)| (image.pixel(x, y).g() - albedo(x)).powi(2))
+                .sum::<f32>();
+            (squared / (width * height) as f32).sqrt()
+        };
+        assert!(error(&denoised) < 0.2 * error(&noisy));
+        assert!((denoised.pixel(width / 2 - 1, 10).g() - 0.2).abs() < 0.05);
+        assert!((denoised.pixel(width / 2, 10).g() - 0.8).abs() < 0.1);
+
+        // No iterations keep image.
+        let kept = Denoiser::new(0, 0.2).denoise(&noisy, &guides);
+        assert!((kept.pixel(3, 3).g() - noisy.pixel(3, 3).g()).abs() < 1e-5);
+    }
+}
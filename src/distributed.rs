@@ -0,0 +1,545 @@
+use crate::{
+    base::color::Color3f, camera::Camera, film::Film, scene::Scene, settings::RenderSettings,
+    tile::Tile,
+};
+use std::{
+    collections::VecDeque,
+    error::Error,
+    fmt,
+    io::{self, Read, Write},
+    net::TcpStream,
+    sync::Mutex,
+    thread,
+};
+
+/// Largest accepted message in bytes, guarding against allocations for corrupt length prefixes.
+const MAX_MESSAGE_SIZE: u32 = 1 << 30;
+
+/// Message tags.
+const HELLO: u8 = 0;
+const READY: u8 = 1;
+const REJECT: u8 = 2;
+const RENDER: u8 = 3;
+const PIXELS: u8 = 4;
+
+/// Error of distributed rendering.
+#[derive(Debug)]
+pub enum DistributedError {
+    /// Connection failed or broke off.
+    Io(io::Error),
+
+    /// Peer sent a malformed or unexpected message.
+    Protocol(String),
+
+    /// Worker refused the render, e.g. as it has loaded a different scene.
+    Rejected(String),
+}
+
+impl fmt::Display for DistributedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DistributedError::Io(e) => write!(f, "{}", e),
+            DistributedError::Protocol(message) => write!(f, "protocol error: {}", message),
+            DistributedError::Rejected(reason) => write!(f, "rejected: {}", reason),
+        }
+    }
+}
+
+impl Error for DistributedError {}
+
+impl From<io::Error> for DistributedError {
+    fn from(e: io::Error) -> Self {
+        DistributedError::Io(e)
+    }
+}
+
+/// Message between coordinator and worker. Sent as little endian length in bytes followed by a tag byte and the
+/// fields in little endian order.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Message {
+    /// Coordinator opens session, describing the render so the worker can check it has loaded the same one.
+    Hello {
+        scene_hash: u64,
+        width: u32,
+        height: u32,
+        samples_per_pixel: u32,
+        seed: u64,
+    },
+
+    /// Worker accepts session.
+    Ready,
+
+    /// Worker refuses session, giving the reason.
+    Reject(String),
+
+    /// Coordinator requests tile range.
+    Render(Vec<Tile>),
+
+    /// Worker returns rendered tiles with their pixels (row-major).
+    Pixels(Vec<(Tile, Vec<Color3f>)>),
+}
+
+impl Message {
+    /// Writes length-prefixed message to stream.
+    pub fn write(&self, stream: &mut impl Write) -> io::Result<()> {
+        let mut payload = Vec::new();
+        match self {
+            Message::Hello {
+                scene_hash,
+                width,
+                height,
+                samples_per_pixel,
+                seed,
+            } => {
+                payload.push(HELLO);
+                payload.extend(scene_hash.to_le_bytes());
+                payload.extend(width.to_le_bytes());
+                payload.extend(height.to_le_bytes());
+                payload.extend(samples_per_pixel.to_le_bytes());
+                payload.extend(seed.to_le_bytes());
+            }
+            Message::Ready => payload.push(READY),
+            Message::Reject(reason) => {
+                payload.push(REJECT);
+                payload.extend(reason.as_bytes());
+            }
+            Message::Render(tiles) => {
+                payload.push(RENDER);
+                payload.extend((tiles.len() as u32).to_le_bytes());
+                for tile in tiles {
+                    push_tile(&mut payload, tile);
+                }
+            }
+            Message::Pixels(tiles) => {
+                payload.push(PIXELS);
+                payload.extend((tiles.len() as u32).to_le_bytes());
+                for (tile, pixels) in tiles {
+                    push_tile(&mut payload, tile);
+                    for pixel in pixels {
+                        for channel in [pixel.r(), pixel.g(), pixel.b()] {
+                            payload.extend(channel.to_le_bytes());
+                        }
+                    }
+                }
+            }
+        }
+        stream.write_all(&(payload.len() as u32).to_le_bytes())?;
+        stream.write_all(&payload)?;
+        stream.flush()
+    }
+
+    /// Reads length-prefixed message from stream. Returns none if stream ended before a message began.
+    pub fn read(stream: &mut impl Read) -> Result<Option<Message>, DistributedError> {
+        let mut length = [0; 4];
+        match stream.read_exact(&mut length) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e.into()),
+        }
+        let length = u32::from_le_bytes(length);
+        if length == 0 || length > MAX_MESSAGE_SIZE {
+            return Err(DistributedError::Protocol(format!(
+                "invalid message length {}",
+                length
+            )));
+        }
+        let mut payload = vec![0; length as usize];
+        stream.read_exact(&mut payload)?;
+
+        let mut reader = Reader {
+            bytes: &payload[1..],
+        };
+        let message = match payload[0] {
+            HELLO => Message::Hello {
+                scene_hash: reader.u64()?,
+                width: reader.u32()?,
+                height: reader.u32()?,
+                samples_per_pixel: reader.u32()?,
+                seed: reader.u64()?,
+            },
+            READY => Message::Ready,
+            REJECT => Message::Reject(String::from_utf8_lossy(reader.rest()).into_owned()),
+            RENDER => {
+                let count = reader.u32()?;
+                let tiles = (0..count)
+                    .map(|_| reader.tile())
+                    .collect::<Result<_, _>>()?;
+                Message::Render(tiles)
+            }
+            PIXELS => {
+                let count = reader.u32()?;
+                let mut tiles = Vec::new();
+                for _ in 0..count {
+                    let tile = reader.tile()?;
+                    let pixels = (0..tile.pixel_count())
+                        .map(|_| Ok(Color3f::new(reader.f32()?, reader.f32()?, reader.f32()?)))
+                        .collect::<Result<_, DistributedError>>()?;
+                    tiles.push((tile, pixels));
+                }
+                Message::Pixels(tiles)
+            }
+            tag => {
+                return Err(DistributedError::Protocol(format!(
+                    "unknown message tag {}",
+                    tag
+                )))
+            }
+        };
+        if !reader.bytes.is_empty() {
+            return Err(DistributedError::Protocol(
+                "trailing message bytes".to_string(),
+            ));
+        }
+        Ok(Some(message))
+    }
+}
+
+/// Appends tile fields to payload.
+fn push_tile(payload: &mut Vec<u8>, tile: &Tile) {
+    for value in [tile.x, tile.y, tile.width, tile.height] {
+        payload.extend(value.to_le_bytes());
+    }
+}
+
+/// Cursor over message payload.
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl Reader<'_> {
+    /// Takes next bytes of payload.
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], DistributedError> {
+        if self.bytes.len() < N {
+            return Err(DistributedError::Protocol("truncated message".to_string()));
+        }
+        let (head, rest) = self.bytes.split_at(N);
+        self.bytes = rest;
+        Ok(head.try_into().unwrap())
+    }
+
+    fn u32(&mut self) -> Result<u32, DistributedError> {
+        self.take().map(u32::from_le_bytes)
+    }
+
+    fn u64(&mut self) -> Result<u64, DistributedError> {
+        self.take().map(u64::from_le_bytes)
+    }
+
+    fn f32(&mut self) -> Result<f32, DistributedError> {
+        self.take().map(f32::from_le_bytes)
+    }
+
+    fn tile(&mut self) -> Result<Tile, DistributedError> {
+        Ok(Tile {
+            x: self.u32()?,
+            y: self.u32()?,
+            width: self.u32()?,
+            height: self.u32()?,
+        })
+    }
+
+    /// Takes remaining bytes of payload.
+    fn rest(&mut self) -> &[u8] {
+        std::mem::take(&mut self.bytes)
+    }
+}
+
+/// Session description of render, which coordinator and worker must agree on.
+fn hello(camera: &Camera, scene: &Scene, settings: &RenderSettings) -> Message {
+    let (width, height) = camera.resolution();
+    Message::Hello {
+        scene_hash: scene.content_hash(),
+        width,
+        height,
+        samples_per_pixel: settings.samples_per_pixel(),
+        seed: settings.seed(),
+    }
+}
+
+/// Serves a coordinator connected by stream as worker: checks that both render the same scene with the same camera
+/// and settings, then renders requested tile ranges until the coordinator closes the connection.
+pub fn serve_connection(
+    stream: &mut TcpStream,
+    camera: &mut Camera,
+    scene: &Scene,
+    settings: &RenderSettings,
+) -> Result<(), DistributedError> {
+    let expected = hello(camera, scene, settings);
+    match Message::read(stream)? {
+        Some(message) if message == expected => Message::Ready.write(stream)?,
+        Some(Message::Hello { .. }) => {
+            let reason = "scene, camera or settings differ from coordinator".to_string();
+            Message::Reject(reason.clone()).write(stream)?;
+            return Err(DistributedError::Rejected(reason));
+        }
+        Some(message) => return Err(unexpected(&message)),
+        None => return Ok(()),
+    }
+    let (width, height) = camera.resolution();
+    while let Some(message) = Message::read(stream)? {
+        let Message::Render(tiles) = message else {
+            return Err(unexpected(&message));
+        };
+        if tiles
+            .iter()
+            .any(|t| t.x + t.width > width || t.y + t.height > height)
+        {
+            return Err(DistributedError::Protocol(
+                "tile outside of image".to_string(),
+            ));
+        }
+        let film = camera.render_region(scene, settings, &tiles);
+        let pixels = tiles
+            .into_iter()
+            .map(|tile| (tile, read_tile(&film, &tile)))
+            .collect();
+        Message::Pixels(pixels).write(stream)?;
+    }
+    Ok(())
+}
+
+/// Renders scene with settings by splitting the image into ranges of given number of tiles, dispatched to workers
+/// listening at given addresses. Each worker must have loaded the same scene, camera and settings. Ranges of workers
+/// which fail are handed to the remaining ones; an error is returned only once no worker is left. Returns image with
+/// addresses and errors of workers which failed, in order of failure.
+pub fn render_distributed(
+    camera: &mut Camera,
+    scene: &Scene,
+    settings: &RenderSettings,
+    workers: &[String],
+    tiles_per_range: usize,
+) -> Result<(Film, Vec<(String, DistributedError)>), DistributedError> {
+    assert!(tiles_per_range > 0, "tile ranges must not be empty");
+    let (width, height) = camera.resolution();
+    let hello = hello(camera, scene, settings);
+    let queue: Mutex<VecDeque<Vec<Tile>>> = Mutex::new(
+        settings
+            .tiles(width, height)
+            .chunks(tiles_per_range)
+            .map(|range| range.to_vec())
+            .collect(),
+    );
+    let film = Mutex::new(Film::new(width, height, settings.film_precision()));
+
+    let mut workers: Vec<&String> = workers.iter().collect();
+    let mut failed = Vec::new();
+    while !queue.lock().unwrap().is_empty() {
+        if workers.is_empty() {
+            return Err(failed.pop().map_or_else(
+                || DistributedError::Protocol("no workers given".to_string()),
+                |(_, e)| e,
+            ));
+        }
+
+        // Each worker takes ranges until the queue is drained, failed workers drop out.
+        let results: Vec<Result<(), DistributedError>> = thread::scope(|s| {
+            let handles: Vec<_> = workers
+                .iter()
+                .map(|address| s.spawn(|| work(address, &hello, &queue, &film)))
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+        let mut surviving = Vec::new();
+        for (address, result) in workers.into_iter().zip(results) {
+            match result {
+                Ok(()) => surviving.push(address),
+                Err(e) => failed.push((address.clone(), e)),
+            }
+        }
+        workers = surviving;
+    }
+    Ok((film.into_inner().unwrap(), failed))
+}
+
+/// Connects to worker at address and renders ranges of queue into film. A range being rendered when the
+/// connection fails is put back into the queue.
+fn work(
+    address: &str,
+    hello: &Message,
+    queue: &Mutex<VecDeque<Vec<Tile>>>,
+    film: &Mutex<Film>,
+) -> Result<(), DistributedError> {
+    let mut stream = TcpStream::connect(address)?;
+    hello.write(&mut stream)?;
+    match Message::read(&mut stream)? {
+        Some(Message::Ready) => {}
+        Some(Message::Reject(reason)) => return Err(DistributedError::Rejected(reason)),
+        Some(message) => return Err(unexpected(&message)),
+        None => return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
+    }
+    loop {
+        let Some(range) = queue.lock().unwrap().pop_front() else {
+            return Ok(());
+        };
+        match render_range(&mut stream, &range) {
+            Ok(pixels) => {
+                let mut film = film.lock().unwrap();
+                for (tile, pixels) in pixels {
+                    write_tile(&mut film, &tile, &pixels);
+                }
+            }
+            Err(e) => {
+                queue.lock().unwrap().push_back(range);
+                return Err(e);
+            }
+        }
+    }
+}
+
+/// Requests range from worker, checking that the reply holds exactly the requested tiles.
+fn render_range(
+    stream: &mut TcpStream,
+    range: &[Tile],
+) -> Result<Vec<(Tile, Vec<Color3f>)>, DistributedError> {
+    Message::Render(range.to_vec()).write(stream)?;
+    match Message::read(stream)? {
+        Some(Message::Pixels(pixels))
+            if pixels.len() == range.len()
+                && pixels.iter().zip(range).all(|((t, _), r)| t == r) =>
+        {
+            Ok(pixels)
+        }
+        Some(message) => Err(unexpected(&message)),
+        None => Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
+    }
+}
+
+/// Error for message not expected at this point of the session.
+fn unexpected(message: &Message) -> DistributedError {
+    let name = match message {
+        Message::Hello { .. } => "hello",
+        Message::Ready => "ready",
+        Message::Reject(_) => "reject",
+        Message::Render(_) => "render",
+        Message::Pixels(_) => "pixels",
+    };
+    DistributedError::Protocol(format!("unexpected {} message", name))
+}
+
+/// Pixels of tile region of film (row-major).
+fn read_tile(film: &Film, tile: &Tile) -> Vec<Color3f> {
+    (tile.y..tile.y + tile.height)
+        .flat_map(|y| (tile.x..tile.x + tile.width).map(move |x| film.pixel(x, y)))
+        .collect()
+}
+
+/// Writes pixels (row-major) into tile region of film.
+fn write_tile(film: &mut Film, tile: &Tile, pixels: &[Color3f]) {
+    for (i, &pixel) in pixels.iter().enumerate() {
+        let x = tile.x + i as u32 % tile.width;
+        let y = tile.y + i as u32 / tile.width;
+        film.set_pixel(x, y, pixel);
+    }
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        base::{material::Material, point::Point3f, shape::Shape},
+        materials::lambert::Lambert,
+        shapes::sphere::Sphere,
+    };
+    use std::net::TcpListener;
+
+    fn scene() -> Scene {
+        let mut scene = Scene::new();
+        scene.add(Shape::Sphere(Sphere::new(
+            Point3f::new(0.0, 0.0, -1.0),
+            0.5,
+            Material::Lambert(Lambert::new(Color3f::new(0.5, 0.2, 0.1))),
+        )));
+        scene
+    }
+
+    fn camera() -> Camera {
+        Camera::new(20, 12)
+    }
+
+    fn settings() -> RenderSettings {
+        RenderSettings::new(4)
+    }
+
+    /// Spawns worker serving connections with given scene, returning its address.
+    fn spawn_worker(scene: Scene, connections: usize) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        thread::spawn(move || {
+            for stream in listener.incoming().take(connections) {
+                let _ = serve_connection(&mut stream.unwrap(), &mut camera(), &scene, &settings());
+            }
+        });
+        address
+    }
+
+    #[test]
+    fn message() {
+        let tile = Tile {
+            x: 1,
+            y: 2,
+            width: 2,
+            height: 1,
+        };
+        let messages = [
+            hello(&camera(), &scene(), &settings()),
+            Message::Ready,
+            Message::Reject("reason".to_string()),
+            Message::Render(vec![tile, tile]),
+            Message::Pixels(vec![(
+                tile,
+                vec![Color3f::new(0.5, 1.0, 2.0), Color3f::white()],
+            )]),
+        ];
+        let mut bytes = Vec::new();
+        for message in &messages {
+            message.write(&mut bytes).unwrap();
+        }
+        let mut stream = bytes.as_slice();
+        for message in &messages {
+            assert_eq!(Message::read(&mut stream).unwrap().as_ref(), Some(message));
+        }
+        assert!(Message::read(&mut stream).unwrap().is_none());
+
+        // Corrupt messages fail.
+        let mut truncated: &[u8] = &[2, 0, 0, 0, HELLO, 0];
+        assert!(matches!(
+            Message::read(&mut truncated),
+            Err(DistributedError::Protocol(_))
+        ));
+        let mut unknown: &[u8] = &[1, 0, 0, 0, 9];
+        assert!(matches!(
+            Message::read(&mut unknown),
+            Err(DistributedError::Protocol(_))
+        ));
+    }
+
+    #[test]
+    fn render_distributed() {
+        let expected = camera().render(&scene(), &settings());
+        let workers = [spawn_worker(scene(), 1), spawn_worker(scene(), 1)];
+        let (film, failed) =
+            super::render_distributed(&mut camera(), &scene(), &settings(), &workers, 3).unwrap();
+        assert!(film.pixels().eq(expected.pixels()));
+        assert!(failed.is_empty());
+
+        // Workers with a different scene refuse, ranges go to the remaining worker.
+        let mut other = scene();
+        other.set_environment_intensity(2.0);
+        let workers = [spawn_worker(other, 1), spawn_worker(scene(), 1)];
+        let (film, failed) =
+            super::render_distributed(&mut camera(), &scene(), &settings(), &workers, 3).unwrap();
+        assert!(film.pixels().eq(expected.pixels()));
+        assert!(matches!(
+            failed.as_slice(),
+            [(address, DistributedError::Rejected(_))] if *address == workers[0]
+        ));
+
+        // Fails once no worker is left.
+        let workers = [spawn_worker(Scene::new(), 1)];
+        assert!(matches!(
+            super::render_distributed(&mut camera(), &scene(), &settings(), &workers, 3),
+            Err(DistributedError::Rejected(_))
+        ));
+    }
+}
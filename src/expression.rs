@@ -0,0 +1,504 @@
+use crate::base::color::Color3f;
+use std::{error::Error, f32::consts::PI, fmt};
+
+/// Frames per second of animations, relating frame numbers to time.
+pub const FRAME_RATE: f32 = 24.0;
+
+/// Named colors usable in expressions.
+const COLORS: [(&str, [f32; 3]); 9] = [
+    ("black", [0.0, 0.0, 0.0]),
+    ("white", [1.0, 1.0, 1.0]),
+    ("gray", [0.5, 0.5, 0.5]),
+    ("red", [1.0, 0.0, 0.0]),
+    ("green", [0.0, 1.0, 0.0]),
+    ("blue", [0.0, 0.0, 1.0]),
+    ("yellow", [1.0, 1.0, 0.0]),
+    ("cyan", [0.0, 1.0, 1.0]),
+    ("magenta", [1.0, 0.0, 1.0]),
+];
+
+/// Functions usable in expressions, with their argument count.
+const FUNCTIONS: [(&str, usize); 13] = [
+    ("sin", 1),
+    ("cos", 1),
+    ("tan", 1),
+    ("abs", 1),
+    ("sqrt", 1),
+    ("floor", 1),
+    ("fract", 1),
+    ("min", 2),
+    ("max", 2),
+    ("clamp", 3),
+    ("lerp", 3),
+    ("rgb", 3),
+    ("blackbody", 1),
+];
+
+/// Error of parsing or evaluating expression.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ExpressionError {
+    /// Expression is malformed, at character position.
+    Syntax { position: usize, message: String },
+
+    /// Operation is not defined for values, e.g. sine of color.
+    Type(String),
+}
+
+impl fmt::Display for ExpressionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExpressionError::Syntax { position, message } => {
+                write!(f, "column {}: {}", position + 1, message)
+            }
+            ExpressionError::Type(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl Error for ExpressionError {}
+
+/// Value of expression.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Value {
+    Number(f32),
+    Color(Color3f),
+}
+
+/// Arithmetic expression over numbers and colors, e.g. `0.2 + 0.1*sin(frame/24)` or `lerp(red, blue, t)`.
+///
+/// Supports `+ - * / ^`, parentheses, the functions in `FUNCTIONS`, the constant `pi`, the named colors in `COLORS`,
+/// the frame number `frame` and the time `t` in seconds. Colors combine componentwise with colors and numbers.
+/// `blackbody` is the color of luminance one at a temperature in kelvin, which numbers may mark by unit `K`, e.g.
+/// `blackbody(3200K) * 10` for a tungsten light.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Expression {
+    /// Root of syntax tree.
+    root: Node,
+}
+
+impl Expression {
+    /// Parses expression from text.
+    pub fn parse(text: &str) -> Result<Expression, ExpressionError> {
+        let mut parser = Parser {
+            chars: text.chars().collect(),
+            position: 0,
+        };
+        let root = parser.sum()?;
+        parser.skip_whitespace();
+        if parser.position < parser.chars.len() {
+            return Err(parser.error("unexpected character"));
+        }
+        Ok(Expression { root })
+    }
+
+    /// Evaluates expression at frame number.
+    pub fn eval(&self, frame: u32) -> Result<Value, ExpressionError> {
+        self.root.eval(frame as f32)
+    }
+
+    /// Evaluates expression at frame number, expecting number.
+    pub fn number(&self, frame: u32) -> Result<f32, ExpressionError> {
+        match self.eval(frame)? {
+            Value::Number(x) => Ok(x),
+            Value::Color(_) => Err(ExpressionError::Type("expected number, found color".into())),
+        }
+    }
+
+    /// Evaluates expression at frame number, expecting color. Numbers are gray levels.
+    pub fn color(&self, frame: u32) -> Result<Color3f, ExpressionError> {
+        match self.eval(frame)? {
+            Value::Number(x) => Ok(Color3f::new(x, x, x)),
+            Value::Color(c) => Ok(c),
+        }
+    }
+
+    /// Whether expression depends on frame number or time, i.e. is animated.
+    pub fn is_animated(&self) -> bool {
+        self.root.is_animated()
+    }
+}
+
+/// Node of expression syntax tree.
+#[derive(Clone, Debug, PartialEq)]
+enum Node {
+    /// Literal or constant value.
+    Constant(Value),
+
+    /// Frame number.
+    Frame,
+
+    /// Time in seconds.
+    Time,
+
+    /// Negated operand.
+    Negate(Box<Node>),
+
+    /// Binary operator with operands.
+    Binary(char, Box<Node>, Box<Node>),
+
+    /// Function call with arguments.
+    Call(&'static str, Vec<Node>),
+}
+
+impl Node {
+    /// Evaluates node with frame number.
+    fn eval(&self, frame: f32) -> Result<Value, ExpressionError> {
+        match self {
+            Node::Constant(value) => Ok(*value),
+            Node::Frame => Ok(Value::Number(frame)),
+            Node::Time => Ok(Value::Number(frame / FRAME_RATE)),
+            Node::Negate(operand) => match operand.eval(frame)? {
+                Value::Number(x) => Ok(Value::Number(-x)),
+                Value::Color(c) => Ok(Value::Color(-c)),
+            },
+            Node::Binary(op, lhs, rhs) => Ok(binary(*op, lhs.eval(frame)?, rhs.eval(frame)?)),
+            Node::Call(name, args) => {
+                let args = args
+                    .iter()
+                    .map(|a| a.eval(frame))
+                    .collect::<Result<Vec<_>, _>>()?;
+                call(name, &args)
+            }
+        }
+    }
+
+    /// Whether node depends on frame number.
+    fn is_animated(&self) -> bool {
+        match self {
+            Node::Constant(_) => false,
+            Node::Frame | Node::Time => true,
+            Node::Negate(operand) => operand.is_animated(),
+            Node::Binary(_, lhs, rhs) => lhs.is_animated() || rhs.is_animated(),
+            Node::Call(_, args) => args.iter().any(Node::is_animated),
+        }
+    }
+}
+
+/// Applies binary operator, componentwise for colors.
+fn binary(op: char, lhs: Value, rhs: Value) -> Value {
+    let apply = |a: f32, b: f32| match op {
+        '+' => a + b,
+        '-' => a - b,
+        '*' => a * b,
+        '/' => a / b,
+        _ => a.powf(b),
+    };
+    let componentwise = |a: Color3f, b: Color3f| {
+        Value::Color(Color3f::new(
+            apply(a.r(), b.r()),
+            apply(a.g(), b.g()),
+            apply(a.b(), b.b()),
+        ))
+    };
+    match (lhs, rhs) {
+        (Value::Number(a), Value::Number(b)) => Value::Number(apply(a, b)),
+        (Value::Color(a), Value::Color(b)) => componentwise(a, b),
+        (Value::Color(a), Value::Number(b)) => componentwise(a, Color3f::new(b, b, b)),
+        (Value::Number(a), Value::Color(b)) => componentwise(Color3f::new(a, a, a), b),
+    }
+}
+
+/// Calls function with arguments of correct count.
+fn call(name: &str, args: &[Value]) -> Result<Value, ExpressionError> {
+    // Interpolation and color construction mix value kinds.
+    match (name, args) {
+        ("lerp", [a, b, Value::Number(t)]) => {
+            let a = binary('*', *a, Value::Number(1.0 - t));
+            let b = binary('*', *b, Value::Number(*t));
+            return Ok(binary('+', a, b));
+        }
+        ("rgb", [Value::Number(r), Value::Number(g), Value::Number(b)]) => {
+            return Ok(Value::Color(Color3f::new(*r, *g, *b)))
+        }
+        ("blackbody", [Value::Number(temperature)]) if *temperature <= 0.0 => {
+            return Err(ExpressionError::Type(
+                "blackbody expects positive temperature".into(),
+            ))
+        }
+        ("blackbody", [Value::Number(temperature)]) => {
+            return Ok(Value::Color(Color3f::blackbody(*temperature)))
+        }
+        _ => {}
+    }
+
+    // Remaining functions take numbers only.
+    let numbers = args
+        .iter()
+        .map(|a| match a {
+            Value::Number(x) => Ok(*x),
+            Value::Color(_) => Err(ExpressionError::Type(format!(
+                "{} expects numbers, found color",
+                name
+            ))),
+        })
+        .collect::<Result<Vec<f32>, _>>()?;
+    let x = numbers[0];
+    Ok(Value::Number(match name {
+        "sin" => x.sin(),
+        "cos" => x.cos(),
+        "tan" => x.tan(),
+        "abs" => x.abs(),
+        "sqrt" => x.sqrt(),
+        "floor" => x.floor(),
+        "fract" => x - x.floor(),
+        "min" => x.min(numbers[1]),
+        "max" => x.max(numbers[1]),
+        "clamp" => x.max(numbers[1]).min(numbers[2]),
+        _ => return Err(ExpressionError::Type(format!("{} expects numbers", name))),
+    }))
+}
+
+/// Recursive descent parser over expression characters.
+struct Parser {
+    /// Characters of expression.
+    chars: Vec<char>,
+
+    /// Index of next character.
+    position: usize,
+}
+
+impl Parser {
+    /// Parses sum of products.
+    fn sum(&mut self) -> Result<Node, ExpressionError> {
+        let mut node = self.product()?;
+        while let Some(op) = self.operator(&['+', '-']) {
+            node = Node::Binary(op, Box::new(node), Box::new(self.product()?));
+        }
+        Ok(node)
+    }
+
+    /// Parses product of factors.
+    fn product(&mut self) -> Result<Node, ExpressionError> {
+        let mut node = self.factor()?;
+        while let Some(op) = self.operator(&['*', '/']) {
+            node = Node::Binary(op, Box::new(node), Box::new(self.factor()?));
+        }
+        Ok(node)
+    }
+
+    /// Parses negated factor or power, which binds tighter than negation and is right associative.
+    fn factor(&mut self) -> Result<Node, ExpressionError> {
+        if self.operator(&['-']).is_some() {
+            return Ok(Node::Negate(Box::new(self.factor()?)));
+        }
+        let base = self.atom()?;
+        if self.operator(&['^']).is_some() {
+            return Ok(Node::Binary('^', Box::new(base), Box::new(self.factor()?)));
+        }
+        Ok(base)
+    }
+
+    /// Parses number, name, function call or parenthesized expression.
+    fn atom(&mut self) -> Result<Node, ExpressionError> {
+        self.skip_whitespace();
+        let start = self.position;
+        match self.chars.get(start) {
+            Some('(') => {
+                self.position += 1;
+                let node = self.sum()?;
+                self.expect(')')?;
+                Ok(node)
+            }
+            Some(c) if c.is_ascii_digit() || *c == '.' => {
+                while self
+                    .chars
+                    .get(self.position)
+                    .is_some_and(|c| c.is_ascii_digit() || *c == '.')
+                {
+                    self.position += 1;
+                }
+                let text: String = self.chars[start..self.position].iter().collect();
+                let Ok(x) = text.parse() else {
+                    self.position = start;
+                    return Err(self.error("invalid number"));
+                };
+
+                // Unit of temperatures, unless start of a name.
+                if self.chars.get(self.position) == Some(&'K')
+                    && !self
+                        .chars
+                        .get(self.position + 1)
+                        .is_some_and(|c| c.is_alphanumeric() || *c == '_')
+                {
+                    self.position += 1;
+                }
+                Ok(Node::Constant(Value::Number(x)))
+            }
+            Some(c) if c.is_alphabetic() => {
+                while self
+                    .chars
+                    .get(self.position)
+                    .is_some_and(|c| c.is_alphanumeric() || *c == '_')
+                {
+                    self.position += 1;
+                }
+                let name: String = self.chars[start..self.position].iter().collect();
+                self.name(&name, start)
+            }
+            Some(_) => Err(self.error("unexpected character")),
+            None => Err(self.error("unexpected end of expression")),
+        }
+    }
+
+    /// Resolves variable, constant or function call with given name starting at position.
+    fn name(&mut self, name: &str, start: usize) -> Result<Node, ExpressionError> {
+        match name {
+            "frame" => return Ok(Node::Frame),
+            "t" => return Ok(Node::Time),
+            "pi" => return Ok(Node::Constant(Value::Number(PI))),
+            _ => {}
+        }
+        if let Some((_, [r, g, b])) = COLORS.iter().find(|(n, _)| *n == name) {
+            return Ok(Node::Constant(Value::Color(Color3f::new(*r, *g, *b))));
+        }
+        let Some(&(function, arity)) = FUNCTIONS.iter().find(|(n, _)| *n == name) else {
+            self.position = start;
+            return Err(self.error(&format!("unknown name {}", name)));
+        };
+
+        // Arguments.
+        self.expect('(')?;
+        let mut args = vec![self.sum()?];
+        while self.operator(&[',']).is_some() {
+            args.push(self.sum()?);
+        }
+        self.expect(')')?;
+        if args.len() != arity {
+            self.position = start;
+            return Err(self.error(&format!("{} takes {} arguments", name, arity)));
+        }
+        Ok(Node::Call(function, args))
+    }
+
+    /// Consumes one of given operator characters.
+    fn operator(&mut self, ops: &[char]) -> Option<char> {
+        self.skip_whitespace();
+        let c = *self.chars.get(self.position).filter(|c| ops.contains(c))?;
+        self.position += 1;
+        Some(c)
+    }
+
+    /// Consumes given character.
+    fn expect(&mut self, c: char) -> Result<(), ExpressionError> {
+        self.operator(&[c])
+            .map(|_| ())
+            .ok_or_else(|| self.error(&format!("expected '{}'", c)))
+    }
+
+    /// Skips whitespace characters.
+    fn skip_whitespace(&mut self) {
+        while self
+            .chars
+            .get(self.position)
+            .is_some_and(|c| c.is_whitespace())
+        {
+            self.position += 1;
+        }
+    }
+
+    /// Syntax error at current position.
+    fn error(&self, message: &str) -> ExpressionError {
+        ExpressionError::Syntax {
+            position: self.position,
+            message: message.to_string(),
+        }
+    }
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Evaluates expression text as number at frame.
+    fn number(text: &str, frame: u32) -> f32 {
+        Expression::parse(text).unwrap().number(frame).unwrap()
+    }
+
+    #[test]
+    fn numbers() {
+        assert_eq!(number("1 + 2 * 3", 0), 7.0);
+        assert_eq!(number("(1 + 2) * 3", 0), 9.0);
+        assert_eq!(number("2 - 3 - 4", 0), -5.0);
+        assert_eq!(number("-2^2 + 2^3^2", 0), 508.0);
+        assert_eq!(
+            number("clamp(frame / 4, 0, 1) + max(1, fract(2.5))", 2),
+            1.5
+        );
+        assert_eq!(number("t", 48), 2.0);
+        assert!((number("0.2 + 0.1*sin(frame/24)", 12) - (0.2 + 0.1 * 0.5f32.sin())).abs() < 1e-6);
+        assert!((number("cos(pi)", 0) + 1.0).abs() < 1e-6);
+        assert!(!Expression::parse("sin(pi / 2)").unwrap().is_animated());
+        assert!(Expression::parse("1 + sin(t)").unwrap().is_animated());
+    }
+
+    #[test]
+    fn colors() {
+        let e = Expression::parse("lerp(red, blue, t)").unwrap();
+        assert_eq!(e.color(0).unwrap(), Color3f::new(1.0, 0.0, 0.0));
+        assert_eq!(e.color(12).unwrap(), Color3f::new(0.5, 0.0, 0.5));
+        assert_eq!(
+            Expression::parse("white * 0.5 + rgb(0, 0.25, 0)")
+                .unwrap()
+                .color(0)
+                .unwrap(),
+            Color3f::new(0.5, 0.75, 0.5)
+        );
+        assert_eq!(
+            Expression::parse("0.25").unwrap().color(0).unwrap(),
+            Color3f::new(0.25, 0.25, 0.25)
+        );
+        assert!(matches!(
+            Expression::parse("green").unwrap().number(0),
+            Err(ExpressionError::Type(_))
+        ));
+        assert!(matches!(
+            Expression::parse("sin(red)").unwrap().eval(0),
+            Err(ExpressionError::Type(_))
+        ));
+
+        // Blackbody colors by temperature with optional unit.
+        assert_eq!(
+            Expression::parse("blackbody(3200K) * 10")
+                .unwrap()
+                .color(0)
+                .unwrap(),
+            Color3f::blackbody(3200.0) * 10.0
+        );
+        assert_eq!(
+            Expression::parse("blackbody(2000 + 100*frame)")
+                .unwrap()
+                .color(12)
+                .unwrap(),
+            Color3f::blackbody(3200.0)
+        );
+        assert!(matches!(
+            Expression::parse("blackbody(-1)").unwrap().eval(0),
+            Err(ExpressionError::Type(_))
+        ));
+    }
+
+    #[test]
+    fn syntax_errors() {
+        for (text, position) in [
+            ("1 +", 3),
+            ("2 * (3 + 4", 10),
+            ("1 2", 2),
+            ("purple + 1", 0),
+            ("lerp(1, 2)", 0),
+            ("sin 1", 4),
+            ("1.2.3", 0),
+            ("3200Kelvin", 4),
+        ] {
+            assert_eq!(
+                Expression::parse(text).map_err(|e| match e {
+                    ExpressionError::Syntax { position, .. } => position,
+                    _ => usize::MAX,
+                }),
+                Err(position),
+                "{}",
+                text
+            );
+        }
+    }
+}
@@ -0,0 +1,200 @@
+use crate::{
+    base::color::{Color3f, Color3h},
+    tile::Tile,
+};
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Mutex,
+};
+
+/// Storage precision of film pixels.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum FilmPrecision {
+    /// Single precision floats (12 bytes per pixel).
+    #[default]
+    Full,
+
+    /// Half precision floats (6 bytes per pixel).
+    Half,
+}
+
+/// Pixel storage of film.
+#[derive(Clone, Debug, PartialEq)]
+enum Pixels {
+    Full(Vec<Color3f>),
+    Half(Vec<Color3h>),
+}
+
+/// Rectangular image film holding rendered pixel colors.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Film {
+    /// Image width in pixels.
+    width: u32,
+
+    /// Image height in pixels.
+    height: u32,
+
+    /// Pixel values in row-major order.
+    pixels: Pixels,
+}
+
+impl Film {
+    /// Creates black film with resolution and storage precision.
+    pub fn new(width: u32, height: u32, precision: FilmPrecision) -> Self {
+        let size = (width * height) as usize;
+        let pixels = match precision {
+            FilmPrecision::Full => Pixels::Full(vec![Color3f::black(); size]),
+            FilmPrecision::Half => Pixels::Half(vec![Color3h::black(); size]),
+        };
+        Film {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    /// Image width in pixels.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Image height in pixels.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Storage precision.
+    pub fn precision(&self) -> FilmPrecision {
+        match self.pixels {
+            Pixels::Full(_) => FilmPrecision::Full,
+            Pixels::Half(_) => FilmPrecision::Half,
+        }
+    }
+
+    /// Color of pixel x,y.
+    pub fn pixel(&self, x: u32, y: u32) -> Color3f {
+        self.get(self.index(x, y))
+    }
+
+    /// Sets color of pixel x,y.
+    pub fn set_pixel(&mut self, x: u32, y: u32, color: Color3f) {
+        let i = self.index(x, y);
+        match &mut self.pixels {
+            Pixels::Full(p) => p[i] = color,
+            Pixels::Half(p) => p[i] = color.cast(),
+        }
+    }
+
+    /// Iterates pixel colors in row-major order.
+    pub fn pixels(&self) -> impl Iterator<Item = Color3f> + '_ {
+        (0..(self.width * self.height) as usize).map(|i| self.get(i))
+    }
+
+    /// Processes tiles of film in parallel. Tiles are handed out in given order to worker threads as they become
+    /// idle. Closure receives tile and single precision tile buffer (row-major), which is converted back to storage
+    /// precision afterwards.
+    pub fn par_process_tiles<F>(&mut self, tiles: &[Tile], f: F)
+    where
+        F: Fn(&Tile, &mut [Color3f]) + Sync,
+    {
+        let next = AtomicUsize::new(0);
+        let film = Mutex::new(self);
+        rayon::scope(|s| {
+            for _ in 0..rayon::current_num_threads() {
+                s.spawn(|_| {
+                    while let Some(tile) = tiles.get(next.fetch_add(1, Ordering::Relaxed)) {
+                        let mut buffer = film.lock().unwrap().read_tile(tile);
+                        f(tile, &mut buffer);
+                        film.lock().unwrap().write_tile(tile, &buffer);
+                    }
+                });
+            }
+        });
+    }
+
+    /// Copies tile region into single precision buffer.
+    fn read_tile(&self, tile: &Tile) -> Vec<Color3f> {
+        let mut buffer = Vec::with_capacity(tile.pixel_count());
+        for y in tile.y..tile.y + tile.height {
+            for x in tile.x..tile.x + tile.width {
+                buffer.push(self.pixel(x, y));
+            }
+        }
+        buffer
+    }
+
+    /// Copies single precision buffer into tile region.
+    fn write_tile(&mut self, tile: &Tile, buffer: &[Color3f]) {
+        let mut colors = buffer.iter();
+        for y in tile.y..tile.y + tile.height {
+            for x in tile.x..tile.x + tile.width {
+                self.set_pixel(x, y, *colors.next().unwrap());
+            }
+        }
+    }
+
+    /// Index of pixel x,y in storage.
+    fn index(&self, x: u32, y: u32) -> usize {
+        assert!(x < self.width && y < self.height);
+        (y * self.width + x) as usize
+    }
+
+    /// Color at storage index.
+    fn get(&self, i: usize) -> Color3f {
+        match &self.pixels {
+            Pixels::Full(p) => p[i],
+            Pixels::Half(p) => p[i].cast(),
+        }
+    }
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tile::{tiles, TileOrder};
+
+    #[test]
+    fn pixels() {
+        for precision in [FilmPrecision::Full, FilmPrecision::Half] {
+            let mut f = Film::new(3, 2, precision);
+            assert_eq!(f.precision(), precision);
+            assert_eq!(f.pixel(2, 1), Color3f::black());
+
+            let c = Color3f::new(0.25, 0.5, 1.0);
+            f.set_pixel(2, 1, c);
+            assert_eq!(f.pixel(2, 1), c);
+            assert_eq!(f.pixels().count(), 6);
+            assert_eq!(f.pixels().last(), Some(c));
+        }
+    }
+
+    #[test]
+    fn par_process_tiles() {
+        for precision in [FilmPrecision::Full, FilmPrecision::Half] {
+            let mut f = Film::new(5, 3, precision);
+            f.set_pixel(1, 2, Color3f::white());
+            let tiles = tiles(5, 3, 2, TileOrder::Spiral);
+            f.par_process_tiles(&tiles, |tile, buffer| {
+                assert_eq!(buffer.len(), tile.pixel_count());
+                for pixel in buffer.iter_mut() {
+                    *pixel += Color3f::new(tile.y as f32, 0.0, 0.0);
+                }
+            });
+            assert_eq!(f.pixel(0, 0), Color3f::black());
+            assert_eq!(f.pixel(4, 1), Color3f::black());
+            assert_eq!(f.pixel(4, 2), Color3f::new(2.0, 0.0, 0.0));
+            assert_eq!(f.pixel(1, 2), Color3f::new(3.0, 1.0, 1.0));
+        }
+    }
+
+    #[test]
+    fn half_precision() {
+        let mut f = Film::new(1, 1, FilmPrecision::Half);
+        f.set_pixel(0, 0, Color3f::new(0.1, 0.2, 0.3));
+        let c = f.pixel(0, 0);
+        assert!((c.r() - 0.1).abs() < 0.001);
+        assert!((c.g() - 0.2).abs() < 0.001);
+        assert!((c.b() - 0.3).abs() < 0.001);
+    }
+}
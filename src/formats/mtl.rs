@@ -0,0 +1,419 @@
+use crate::{
+    base::{color::Color3f, material::Material},
+    film::Film,
+    image::RgbaImage,
+    materials::{
+        dielectric::Dielectric,
+        diffuse_light::DiffuseLight,
+        lambert::Lambert,
+        metal::Metal,
+        normal_map::{NormalMap, NormalMapped},
+        principled::Principled,
+    },
+    output::read_image,
+};
+use std::{error::Error, fmt, fs, io, path::Path, sync::Arc};
+
+/// Error while importing MTL material library.
+#[derive(Debug)]
+pub enum ImportError {
+    /// File could not be read.
+    Io(io::Error),
+
+    /// Source is not a valid material library.
+    Syntax { line: usize, message: String },
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::Io(e) => write!(f, "{}", e),
+            ImportError::Syntax { line, message } => write!(f, "line {}: {}", line, message),
+        }
+    }
+}
+
+impl Error for ImportError {}
+
+impl From<io::Error> for ImportError {
+    fn from(e: io::Error) -> Self {
+        ImportError::Io(e)
+    }
+}
+
+/// Materials of an imported MTL material library, e.g. to define them in a scene's library by name, see
+/// `Scene::define_material`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MaterialFile {
+    /// Materials with their names, in order of definition.
+    materials: Vec<(String, Material)>,
+
+    /// Paths of texture maps that could not be read, thus were left out.
+    skipped_maps: Vec<String>,
+}
+
+impl MaterialFile {
+    /// Materials with their names, in order of definition.
+    pub fn materials(&self) -> &[(String, Material)] {
+        &self.materials
+    }
+
+    /// Material with name, or none if undefined.
+    pub fn material(&self, name: &str) -> Option<&Material> {
+        self.materials
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, m)| m)
+    }
+
+    /// Paths of texture maps that could not be read, thus were left out.
+    pub fn skipped_maps(&self) -> &[String] {
+        &self.skipped_maps
+    }
+}
+
+/// Imports MTL material library at path, with texture maps relative to its directory, see `parse`.
+pub fn import(path: &Path) -> Result<MaterialFile, ImportError> {
+    let directory = path.parent().unwrap_or(Path::new(""));
+    parse(&fs::read_to_string(path)?, directory)
+}
+
+/// Parses MTL material library, reading texture maps relative to directory. Each material becomes the eden material
+/// closest to its parameters:
+///
+/// - emitting materials (`Ke`) become diffuse lights,
+/// - reflective ones (`illum 3` and `5`) metals of specular color `Ks`, fuzzed by `Pr` or low exponents `Ns`,
+/// - transparent ones (`illum 4`, `6`, `7` and `9`, or dissolve `d` below 1) dielectrics of index `Ni`, absorbing
+///   what transmission filter `Tf` lets not pass through unit distance,
+/// - those with PBR extension parameters (`Pm`, `Pr`) principled materials, as do glossy ones (`illum 2` with
+///   specular color `Ks`) with roughness from `Ns`,
+/// - all others lambert materials of diffuse color `Kd`.
+///
+/// Diffuse color maps (`map_Kd`) are averaged into `Kd`, as materials take no color textures. Bump maps (`bump`,
+/// `map_Bump`, with strength `-bm`) and normal maps (`norm`) perturb normals, see `NormalMapped`. Other statements
+/// and texture options are ignored, as are maps in formats `read_image` does not support.
+pub fn parse(source: &str, directory: &Path) -> Result<MaterialFile, ImportError> {
+    let mut definitions: Vec<Definition> = Vec::new();
+    let mut skipped_maps = Vec::new();
+    for (i, line) in source.lines().enumerate() {
+        let syntax = |message: &str| ImportError::Syntax {
+            line: i + 1,
+            message: message.to_string(),
+        };
+        let line = line.split('#').next().unwrap_or_default();
+        let mut tokens = line.split_whitespace();
+        let Some(keyword) = tokens.next() else {
+            continue;
+        };
+        let arguments: Vec<&str> = tokens.collect();
+        if keyword == "newmtl" {
+            if arguments.is_empty() {
+                return Err(syntax("missing material name"));
+            }
+            definitions.push(Definition::new(arguments.join(" ")));
+            continue;
+        }
+        let Some(definition) = definitions.last_mut() else {
+            return Err(syntax(&format!("{} before newmtl", keyword)));
+        };
+
+        // Numbers and colors, the latter given by one or three components.
+        let number = |index: usize| -> Result<f32, ImportError> {
+            let token = arguments
+                .get(index)
+                .ok_or_else(|| syntax("missing value"))?;
+            token
+                .parse()
+                .map_err(|_| syntax(&format!("invalid number {}", token)))
+        };
+        let color = || -> Result<Color3f, ImportError> {
+            match arguments.len() {
+                1 => Ok(Color3f::white() * number(0)?),
+                3 => Ok(Color3f::new(number(0)?, number(1)?, number(2)?)),
+                _ => Err(syntax("expected one or three color components")),
+            }
+        };
+        match keyword {
+            "Kd" => definition.kd = color()?,
+            "Ks" => definition.ks = color()?,
+            "Ke" => definition.ke = color()?,
+            "Tf" => definition.tf = color()?,
+            "Ns" => definition.ns = number(0)?,
+            "d" => definition.d = number(0)?,
+            "Tr" => definition.tr = number(0)?,
+            "Ni" => definition.ni = Some(number(0)?),
+            "Pm" => definition.pm = Some(number(0)?),
+            "Pr" => definition.pr = Some(number(0)?),
+            "illum" => definition.illum = number(0)? as u32,
+            "map_Kd" | "map_Bump" | "map_bump" | "bump" | "norm" => {
+                let file = *arguments.last().ok_or_else(|| syntax("missing map file"))?;
+                let Ok(image) = read_image(&directory.join(file).to_string_lossy()) else {
+                    skipped_maps.push(file.to_string());
+                    continue;
+                };
+                match keyword {
+                    "map_Kd" => definition.kd_mean = Some(mean(&image)),
+                    "norm" => definition.normal_map = Some(NormalMap::Normal(rgba(&image))),
+                    _ => {
+                        let strength = match arguments.iter().position(|&a| a == "-bm") {
+                            Some(index) => number(index + 1)?,
+                            None => 1.0,
+                        };
+                        definition.normal_map =
+                            Some(NormalMap::Bump(rgba(&image), strength.max(0.0)));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(MaterialFile {
+        materials: definitions
+            .into_iter()
+            .map(|d| (d.name.clone(), d.material()))
+            .collect(),
+        skipped_maps,
+    })
+}
+
+/// Parameters of material definition, defaulting to those of the MTL format.
+struct Definition {
+    /// Material name.
+    name: String,
+
+    /// Diffuse color.
+    kd: Color3f,
+
+    /// Mean color of diffuse color map, if any.
+    kd_mean: Option<Color3f>,
+
+    /// Specular color.
+    ks: Color3f,
+
+    /// Emitted radiance.
+    ke: Color3f,
+
+    /// Transmission filter.
+    tf: Color3f,
+
+    /// Specular exponent.
+    ns: f32,
+
+    /// Dissolve, 1 for opaque.
+    d: f32,
+
+    /// Transparency, or transmission of PBR extension.
+    tr: f32,
+
+    /// Index of refraction.
+    ni: Option<f32>,
+
+    /// Metallic of PBR extension.
+    pm: Option<f32>,
+
+    /// Roughness of PBR extension.
+    pr: Option<f32>,
+
+    /// Illumination model.
+    illum: u32,
+
+    /// Bump or normal map.
+    normal_map: Option<NormalMap>,
+}
+
+impl Definition {
+    /// Creates definition of material with name and default parameters.
+    fn new(name: String) -> Self {
+        Definition {
+            name,
+            kd: Color3f::white() * 0.8,
+            kd_mean: None,
+            ks: Color3f::black(),
+            ke: Color3f::black(),
+            tf: Color3f::white(),
+            ns: 0.0,
+            d: 1.0,
+            tr: 0.0,
+            ni: None,
+            pm: None,
+            pr: None,
+            illum: 1,
+            normal_map: None,
+        }
+    }
+
+    /// Eden material closest to parameters, see `parse`.
+    fn material(&self) -> Material {
+        let kd = self.kd * self.kd_mean.unwrap_or(Color3f::white());
+        let material = if self.ke != Color3f::black() {
+            Material::DiffuseLight(DiffuseLight::new(self.ke))
+        } else if matches!(self.illum, 3 | 5) {
+            let fuzz = self.pr.unwrap_or(1.0 - self.ns / 1000.0);
+            Material::Metal(Metal::new(self.ks, fuzz))
+        } else if matches!(self.illum, 4 | 6 | 7 | 9) || self.d < 1.0 {
+            let mut dielectric = Dielectric::new(self.ni.unwrap_or(1.5));
+            let absorb = |t: f32| -t.clamp(1e-6, 1.0).ln();
+            let tf = self.tf;
+            dielectric.set_absorption(Color3f::new(absorb(tf.r()), absorb(tf.g()), absorb(tf.b())));
+            Material::Dielectric(dielectric)
+        } else if self.pm.is_some() || self.pr.is_some() {
+            let mut principled = Principled::new(kd);
+            principled.set_metallic(self.pm.unwrap_or(0.0));
+            principled.set_roughness(self.pr.unwrap_or(0.5));
+            principled.set_transmission(self.tr);
+            principled.set_ior(self.ni.unwrap_or(1.5));
+            Material::Principled(principled)
+        } else if self.illum == 2 && self.ks != Color3f::black() {
+            // Phong exponent to roughness, matching highlight widths of the Beckmann distribution.
+            let mut principled = Principled::new(kd);
+            principled.set_roughness((2.0 / (self.ns + 2.0)).sqrt());
+            Material::Principled(principled)
+        } else {
+            Material::Lambert(Lambert::new(kd))
+        };
+        match &self.normal_map {
+            Some(map) => Material::NormalMapped(NormalMapped::new(material, map.clone())),
+            None => material,
+        }
+    }
+}
+
+/// Mean color of image.
+fn mean(image: &Film) -> Color3f {
+    let count = image.width() * image.height();
+    image.pixels().fold(Color3f::black(), |sum, c| sum + c) / count as f32
+}
+
+/// Image as texture, fully opaque.
+fn rgba(image: &Film) -> Arc<RgbaImage> {
+    let count = (image.width() * image.height()) as usize;
+    Arc::new(RgbaImage::new(
+        image.width(),
+        image.height(),
+        image.pixels().collect(),
+        vec![1.0; count],
+    ))
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        base::{point::Point3f, shape::Shape},
+        formats::obj,
+        materials::microfacet::Microfacet,
+        scene::Scene,
+        shapes::sphere::Sphere,
+    };
+    use std::env;
+
+    #[test]
+    fn roundtrip() {
+        // Materials exported alongside OBJ files come back alike.
+        let mut glass = Dielectric::new(1.33);
+        glass.set_absorption(Color3f::new(0.5, 0.1, 0.0));
+        let mut principled = Principled::new(Color3f::new(0.2, 0.4, 0.6));
+        principled.set_metallic(0.5);
+        principled.set_roughness(0.3);
+        let materials = [
+            Material::Lambert(Lambert::new(Color3f::new(0.5, 0.25, 0.125))),
+            Material::DiffuseLight(DiffuseLight::new(Color3f::new(4.0, 2.0, 1.0))),
+            Material::Metal(Metal::new(Color3f::new(0.9, 0.8, 0.7), 0.25)),
+            Material::Dielectric(glass),
+            Material::Principled(principled),
+        ];
+        let mut scene = Scene::new();
+        for material in materials.iter() {
+            scene.add(Shape::Sphere(Sphere::new(
+                Point3f::default(),
+                1.0,
+                material.clone(),
+            )));
+        }
+        let (mut obj, mut mtl) = (Vec::new(), Vec::new());
+        obj::write(&scene, &mut obj, &mut mtl, "scene.mtl", 8).unwrap();
+        let file = super::parse(&String::from_utf8(mtl).unwrap(), Path::new("")).unwrap();
+        assert_eq!(file.materials().len(), materials.len());
+        for ((name, imported), material) in file.materials().iter().zip(&materials) {
+            match (imported, material) {
+                (Material::Dielectric(a), Material::Dielectric(b)) => {
+                    assert_eq!(a.ior(), b.ior());
+                    let d = a.absorption() - b.absorption();
+                    assert!(d.r().abs().max(d.g().abs()).max(d.b().abs()) < 1e-4);
+                }
+                _ => assert_eq!(imported, material, "{}", name),
+            }
+        }
+
+        // Rough metals export like fuzzed ones.
+        let gold = Material::Microfacet(Microfacet::metal(Color3f::new(1.0, 0.8, 0.3), 0.2));
+        scene.set_object(0, Shape::Sphere(Sphere::new(Point3f::default(), 1.0, gold)));
+        let (mut obj, mut mtl) = (Vec::new(), Vec::new());
+        obj::write(&scene, &mut obj, &mut mtl, "scene.mtl", 8).unwrap();
+        let file = super::parse(&String::from_utf8(mtl).unwrap(), Path::new("")).unwrap();
+        assert_eq!(
+            file.material("material_0"),
+            Some(&Material::Metal(Metal::new(
+                Color3f::new(1.0, 0.8, 0.3),
+                0.2
+            )))
+        );
+    }
+
+    #[test]
+    fn parse() {
+        // Texture maps next to library, one missing.
+        let directory = env::temp_dir().join(format!("eden_mtl_{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(
+            directory.join("checker.ppm"),
+            "P3 2 1 255 255 255 255 0 0 0",
+        )
+        .unwrap();
+        let source = "# exported by a modeler\n\
+                      newmtl Plastic Red\n\
+                      Kd 1 0 0\n\
+                      Ks 1 1 1\n\
+                      Ns 48\n\
+                      illum 2\n\
+                      newmtl Checker\n\
+                      Kd 1\n\
+                      map_Kd checker.ppm\n\
+                      map_Bump -bm 0.5 checker.ppm\n\
+                      newmtl Missing\n\
+                      map_Kd wood.png # unsupported format\n";
+        let file = super::parse(source, &directory).unwrap();
+        fs::remove_dir_all(&directory).unwrap();
+
+        let Some(Material::Principled(plastic)) = file.material("Plastic Red") else {
+            panic!("glossy material imported as principled material");
+        };
+        assert_eq!(plastic.base_color(), Color3f::new(1.0, 0.0, 0.0));
+        assert!((plastic.roughness() - 0.2).abs() < 1e-6);
+        let Some(Material::NormalMapped(checker)) = file.material("Checker") else {
+            panic!("bump mapped material imported as normal mapped material");
+        };
+        assert!(matches!(checker.map(), NormalMap::Bump(_, strength) if *strength == 0.5));
+        assert_eq!(
+            *checker.material(),
+            Material::Lambert(Lambert::new(Color3f::white() * 0.5))
+        );
+        assert_eq!(
+            file.material("Missing"),
+            Some(&Material::Lambert(Lambert::new(Color3f::white() * 0.8)))
+        );
+        assert_eq!(file.skipped_maps(), ["wood.png"]);
+
+        // Statements outside of materials and bad numbers fail.
+        assert!(matches!(
+            super::parse("Kd 1 1 1", Path::new("")),
+            Err(ImportError::Syntax { line: 1, .. })
+        ));
+        assert!(matches!(
+            super::parse("newmtl a\nNs high", Path::new("")),
+            Err(ImportError::Syntax { line: 2, .. })
+        ));
+    }
+}
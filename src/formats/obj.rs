@@ -0,0 +1,944 @@
+use crate::{
+    base::{hash::stable_hash, material::Material, point::Point3f, shape::Shape, vector::Vector3f},
+    scene::Scene,
+    shapes::{
+        capsule::Capsule, cone::Cone, csg::Csg, cylinder::Cylinder, instance::Instance, mesh::Mesh,
+        sphere::Sphere, torus::Torus, voxels::Voxels, water::Water,
+    },
+};
+use std::{
+    f32::consts::PI,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+/// Exports scene to Wavefront OBJ file at path with material library next to it (same name, .mtl extension).
+/// Round shapes are tessellated with given count of segments around their axis, water surfaces into grids with given
+/// count of segments per side. CSG nodes are written as the meshes of their children.
+pub fn export(scene: &Scene, path: &Path, segments: u32) -> io::Result<()> {
+    let mtl_path = path.with_extension("mtl");
+    let mtl_name = mtl_path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let mut obj = BufWriter::new(File::create(path)?);
+    let mut mtl = BufWriter::new(File::create(&mtl_path)?);
+    write(scene, &mut obj, &mut mtl, &mtl_name, segments)?;
+    obj.flush()?;
+    mtl.flush()
+}
+
+/// Writes scene as OBJ geometry and MTL material library. Every object becomes its own named object, identical
+/// materials are shared. Custom shapes become empty objects, as their geometry cannot be meshed.
+pub fn write(
+    scene: &Scene,
+    obj: &mut impl Write,
+    mtl: &mut impl Write,
+    mtl_name: &str,
+    segments: u32,
+) -> io::Result<()> {
+    assert!(segments >= 3);
+    writeln!(obj, "# eden scene export")?;
+    writeln!(obj, "mtllib {}", mtl_name)?;
+    writeln!(mtl, "# eden material library")?;
+
+    let mut materials: Vec<u64> = Vec::new();
+    let mut vertex_offset = 1;
+    for (i, object) in scene.objects().iter().enumerate() {
+        match object {
+            Shape::Capsule(_) => writeln!(obj, "o capsule_{}", i)?,
+            Shape::Cone(_) => writeln!(obj, "o cone_{}", i)?,
+            Shape::Csg(_) => writeln!(obj, "o csg_{}", i)?,
+            Shape::Custom(_) => writeln!(obj, "o custom_{}", i)?,
+            Shape::Cylinder(_) => writeln!(obj, "o cylinder_{}", i)?,
+            Shape::Instance(_) => writeln!(obj, "o instance_{}", i)?,
+            Shape::Mesh(_) => writeln!(obj, "o mesh_{}", i)?,
+            Shape::Sphere(_) => writeln!(obj, "o sphere_{}", i)?,
+            Shape::Torus(_) => writeln!(obj, "o torus_{}", i)?,
+            Shape::Voxels(_) => writeln!(obj, "o voxels_{}", i)?,
+            Shape::Water(_) => writeln!(obj, "o water_{}", i)?,
+        }
+
+        // Material, written on first use.
+        let hash = stable_hash(scene.material(i));
+        let index = match materials.iter().position(|&h| h == hash) {
+            Some(index) => index,
+            None => {
+                materials.push(hash);
+                write_material(mtl, materials.len() - 1, scene.material(i))?;
+                materials.len() - 1
+            }
+        };
+        writeln!(obj, "usemtl material_{}", index)?;
+
+        vertex_offset += write_shape(obj, object, segments, vertex_offset)?;
+    }
+    Ok(())
+}
+
+/// Writes mesh of shape. Returns count of written vertices.
+fn write_shape(
+    obj: &mut impl Write,
+    shape: &Shape,
+    segments: u32,
+    vertex_offset: u32,
+) -> io::Result<u32> {
+    match shape {
+        Shape::Capsule(c) => write_capsule(obj, c, segments, vertex_offset),
+        Shape::Cone(c) => write_cone(obj, c, segments, vertex_offset),
+        Shape::Csg(c) => write_csg(obj, c, segments, vertex_offset),
+        Shape::Custom(_) => Ok(0),
+        Shape::Cylinder(c) => write_cylinder(obj, c, segments, vertex_offset),
+        Shape::Instance(i) => write_instance(obj, i, segments, vertex_offset),
+        Shape::Mesh(m) => write_mesh(obj, m, vertex_offset),
+        Shape::Sphere(s) => write_sphere(obj, s, segments, vertex_offset),
+        Shape::Torus(t) => write_torus(obj, t, segments, vertex_offset),
+        Shape::Voxels(v) => write_voxels(obj, v, vertex_offset),
+        Shape::Water(w) => write_water(obj, w, segments, vertex_offset),
+    }
+}
+
+/// Writes sphere as UV sphere mesh with vertex normals. Returns count of written vertices.
+fn write_sphere(
+    obj: &mut impl Write,
+    sphere: &Sphere,
+    segments: u32,
+    vertex_offset: u32,
+) -> io::Result<u32> {
+    let rings = (segments / 2).max(2);
+
+    // Vertices and normals, ring by ring from north to south pole.
+    for ring in 0..=rings {
+        let theta = PI * ring as f32 / rings as f32;
+        for segment in 0..segments {
+            let phi = 2.0 * PI * segment as f32 / segments as f32;
+            let n = Vector3f::new(
+                theta.sin() * phi.cos(),
+                theta.cos(),
+                theta.sin() * phi.sin(),
+            );
+            let p = sphere.center() + sphere.radius() * n;
+            writeln!(obj, "v {} {} {}", p.x(), p.y(), p.z())?;
+            writeln!(obj, "vn {} {} {}", n.x(), n.y(), n.z())?;
+        }
+    }
+
+    // Quads between rings, counter-clockwise seen from outside.
+    let index = |ring: u32, segment: u32| vertex_offset + ring * segments + segment % segments;
+    for ring in 0..rings {
+        for segment in 0..segments {
+            let a = index(ring, segment);
+            let b = index(ring + 1, segment);
+            let c = index(ring + 1, segment + 1);
+            let d = index(ring, segment + 1);
+            writeln!(obj, "f {a}//{a} {d}//{d} {c}//{c} {b}//{b}")?;
+        }
+    }
+    Ok((rings + 1) * segments)
+}
+
+/// Writes cylinder as prism mesh with vertex normals, caps as triangle fans. Returns count of written vertices.
+fn write_cylinder(
+    obj: &mut impl Write,
+    cylinder: &Cylinder,
+    segments: u32,
+    vertex_offset: u32,
+) -> io::Result<u32> {
+    let (u, v, w) = cylinder.basis();
+    let half_axis = 0.5 * cylinder.height() * w;
+    let radial = |segment: u32| {
+        let phi = 2.0 * PI * segment as f32 / segments as f32;
+        phi.cos() * u + phi.sin() * v
+    };
+
+    // Side vertices, bottom and top ring, with radial normals.
+    for end in [-1.0, 1.0] {
+        for segment in 0..segments {
+            let n = radial(segment);
+            let p = cylinder.center() + end * half_axis + cylinder.radius() * n;
+            writeln!(obj, "v {} {} {}", p.x(), p.y(), p.z())?;
+            writeln!(obj, "vn {} {} {}", n.x(), n.y(), n.z())?;
+        }
+    }
+
+    // Side quads, counter-clockwise seen from outside.
+    let index = |ring: u32, segment: u32| vertex_offset + ring * segments + segment % segments;
+    for segment in 0..segments {
+        let a = index(0, segment);
+        let b = index(0, segment + 1);
+        let c = index(1, segment + 1);
+        let d = index(1, segment);
+        writeln!(obj, "f {a}//{a} {b}//{b} {c}//{c} {d}//{d}")?;
+    }
+    if !cylinder.caps() {
+        return Ok(2 * segments);
+    }
+
+    // Caps, center followed by ring with axial normals.
+    for (cap, end) in [(0, -1.0), (1, 1.0)] {
+        let n = end * w;
+        let center = cylinder.center() + end * half_axis;
+        for p in std::iter::once(center)
+            .chain((0..segments).map(|s| center + cylinder.radius() * radial(s)))
+        {
+            writeln!(obj, "v {} {} {}", p.x(), p.y(), p.z())?;
+            writeln!(obj, "vn {} {} {}", n.x(), n.y(), n.z())?;
+        }
+        let center = vertex_offset + 2 * segments + cap * (segments + 1);
+        for segment in 0..segments {
+            let a = center + 1 + segment;
+            let b = center + 1 + (segment + 1) % segments;
+            let (a, b) = if cap == 0 { (b, a) } else { (a, b) };
+            writeln!(obj, "f {center}//{center} {a}//{a} {b}//{b}")?;
+        }
+    }
+    Ok(4 * segments + 2)
+}
+
+/// Writes cone as pyramid mesh with vertex normals, apex duplicated per segment for smooth side normals and base as
+/// triangle fan. Returns count of written vertices.
+fn write_cone(
+    obj: &mut impl Write,
+    cone: &Cone,
+    segments: u32,
+    vertex_offset: u32,
+) -> io::Result<u32> {
+    let (u, v, w) = cone.basis();
+    let base = cone.center() - 0.5 * cone.height() * w;
+    let radial = |segment: u32| {
+        let phi = 2.0 * PI * segment as f32 / segments as f32;
+        phi.cos() * u + phi.sin() * v
+    };
+
+    // Side vertices, base ring and apex ring, with slanted normals.
+    for ring in 0..2 {
+        for segment in 0..segments {
+            let n = (cone.height() * radial(segment) + cone.radius() * w).normalize();
+            let p = if ring == 0 {
+                base + cone.radius() * radial(segment)
+            } else {
+                cone.apex()
+            };
+            writeln!(obj, "v {} {} {}", p.x(), p.y(), p.z())?;
+            writeln!(obj, "vn {} {} {}", n.x(), n.y(), n.z())?;
+        }
+    }
+
+    // Side triangles, counter-clockwise seen from outside.
+    let index = |ring: u32, segment: u32| vertex_offset + ring * segments + segment % segments;
+    for segment in 0..segments {
+        let a = index(0, segment);
+        let b = index(0, segment + 1);
+        let c = index(1, segment);
+        writeln!(obj, "f {a}//{a} {b}//{b} {c}//{c}")?;
+    }
+    if !cone.base() {
+        return Ok(2 * segments);
+    }
+
+    // Base, center followed by ring with axial normals.
+    let n = -w;
+    for p in std::iter::once(base).chain((0..segments).map(|s| base + cone.radius() * radial(s))) {
+        writeln!(obj, "v {} {} {}", p.x(), p.y(), p.z())?;
+        writeln!(obj, "vn {} {} {}", n.x(), n.y(), n.z())?;
+    }
+    let center = vertex_offset + 2 * segments;
+    for segment in 0..segments {
+        let a = center + 1 + segment;
+        let b = center + 1 + (segment + 1) % segments;
+        writeln!(obj, "f {center}//{center} {b}//{b} {a}//{a}")?;
+    }
+    Ok(3 * segments + 1)
+}
+
+/// Writes capsule as UV sphere mesh split at its equator, with the halves moved apart to the segment ends. Returns
+/// count of written vertices.
+fn write_capsule(
+    obj: &mut impl Write,
+    capsule: &Capsule,
+    segments: u32,
+    vertex_offset: u32,
+) -> io::Result<u32> {
+    let (u, v, w) = capsule.basis();
+    let half_axis = 0.5 * capsule.height() * w;
+    let rings = (segments / 4).max(1);
+
+    // Vertices and normals, ring by ring from top to bottom pole. Equator ring appears on both halves.
+    for ring in 0..=2 * rings + 1 {
+        let (theta, end) = if ring <= rings {
+            (0.5 * PI * ring as f32 / rings as f32, half_axis)
+        } else {
+            (0.5 * PI * (ring - 1) as f32 / rings as f32, -half_axis)
+        };
+        for segment in 0..segments {
+            let phi = 2.0 * PI * segment as f32 / segments as f32;
+            let n = theta.sin() * (phi.cos() * u + phi.sin() * v) + theta.cos() * w;
+            let p = capsule.center() + end + capsule.radius() * n;
+            writeln!(obj, "v {} {} {}", p.x(), p.y(), p.z())?;
+            writeln!(obj, "vn {} {} {}", n.x(), n.y(), n.z())?;
+        }
+    }
+
+    // Quads between rings, counter-clockwise seen from outside.
+    let index = |ring: u32, segment: u32| vertex_offset + ring * segments + segment % segments;
+    for ring in 0..=2 * rings {
+        for segment in 0..segments {
+            let a = index(ring, segment);
+            let b = index(ring + 1, segment);
+            let c = index(ring + 1, segment + 1);
+            let d = index(ring, segment + 1);
+            writeln!(obj, "f {a}//{a} {b}//{b} {c}//{c} {d}//{d}")?;
+        }
+    }
+    Ok((2 * rings + 2) * segments)
+}
+
+/// Writes torus as grid mesh with vertex normals, closed around axis and around tube. Returns count of written
+/// vertices.
+fn write_torus(
+    obj: &mut impl Write,
+    torus: &Torus,
+    segments: u32,
+    vertex_offset: u32,
+) -> io::Result<u32> {
+    let (u, v, w) = torus.basis();
+    let rings = (segments / 2).max(3);
+
+    // Vertices and normals, ring by ring around tube starting at outer equator.
+    for ring in 0..rings {
+        let theta = 2.0 * PI * ring as f32 / rings as f32;
+        for segment in 0..segments {
+            let phi = 2.0 * PI * segment as f32 / segments as f32;
+            let radial = phi.cos() * u + phi.sin() * v;
+            let n = theta.cos() * radial + theta.sin() * w;
+            let p = torus.center() + torus.major_radius() * radial + torus.minor_radius() * n;
+            writeln!(obj, "v {} {} {}", p.x(), p.y(), p.z())?;
+            writeln!(obj, "vn {} {} {}", n.x(), n.y(), n.z())?;
+        }
+    }
+
+    // Quads between rings, counter-clockwise seen from outside.
+    let index =
+        |ring: u32, segment: u32| vertex_offset + ring % rings * segments + segment % segments;
+    for ring in 0..rings {
+        for segment in 0..segments {
+            let a = index(ring, segment);
+            let b = index(ring + 1, segment);
+            let c = index(ring + 1, segment + 1);
+            let d = index(ring, segment + 1);
+            writeln!(obj, "f {a}//{a} {d}//{d} {c}//{c} {b}//{b}")?;
+        }
+    }
+    Ok(rings * segments)
+}
+
+/// Writes CSG node as meshes of both children, without applying its operation. Returns count of written vertices.
+fn write_csg(
+    obj: &mut impl Write,
+    csg: &Csg,
+    segments: u32,
+    vertex_offset: u32,
+) -> io::Result<u32> {
+    let left = write_shape(obj, csg.left(), segments, vertex_offset)?;
+    let right = write_shape(obj, csg.right(), segments, vertex_offset + left)?;
+    Ok(left + right)
+}
+
+/// Writes instance as meshes of its prototype objects, transformed into world space. Returns count of written
+/// vertices.
+fn write_instance(
+    obj: &mut impl Write,
+    instance: &Instance,
+    segments: u32,
+    vertex_offset: u32,
+) -> io::Result<u32> {
+    let mut local = Vec::new();
+    let mut count = 0;
+    for object in instance.prototype().objects() {
+        count += write_shape(&mut local, object, segments, vertex_offset + count)?;
+    }
+
+    // Transform positions and normals, pass faces through.
+    let transform = instance.transform();
+    for line in String::from_utf8_lossy(&local).lines() {
+        let mut fields = line.split(' ');
+        let kind = fields.next().unwrap_or_default();
+        let [x, y, z] = [(); 3].map(|_| fields.next().and_then(|f| f.parse().ok()));
+        match (kind, x, y, z) {
+            ("v", Some(x), Some(y), Some(z)) => {
+                let p = transform.point(Point3f::new(x, y, z));
+                writeln!(obj, "v {} {} {}", p.x(), p.y(), p.z())?;
+            }
+            ("vn", Some(x), Some(y), Some(z)) => {
+                let n = transform.normal(Vector3f::new(x, y, z));
+                writeln!(obj, "vn {} {} {}", n.x(), n.y(), n.z())?;
+            }
+            _ => writeln!(obj, "{}", line)?,
+        }
+    }
+    Ok(count)
+}
+
+/// Writes voxel grid as quads on faces between filled and empty voxels, with face normals. Returns count of written
+/// vertices.
+fn write_voxels(obj: &mut impl Write, voxels: &Voxels, vertex_offset: u32) -> io::Result<u32> {
+    let mut count = 0;
+    for &position in voxels.positions() {
+        for (axis, side) in (0..3).flat_map(|axis| [(axis, -1), (axis, 1)]) {
+            // Skip faces hidden by neighbor.
+            let mut neighbor = position;
+            neighbor[axis] = position[axis].checked_add_signed(side).unwrap_or(u32::MAX);
+            if voxels.voxel(neighbor).is_some() {
+                continue;
+            }
+
+            // Corners counter-clockwise seen from outside, on face at near or far end of axis.
+            let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+            let mut corners = [(0, 0), (1, 0), (1, 1), (0, 1)];
+            if side < 0 {
+                corners.reverse();
+            }
+            let mut n = [0.0; 3];
+            n[axis] = side as f32;
+            for (du, dv) in corners {
+                let mut c = position;
+                c[axis] += u32::from(side > 0);
+                c[u] += du;
+                c[v] += dv;
+                let p = voxels.origin()
+                    + voxels.voxel_size() * Vector3f::new(c[0] as f32, c[1] as f32, c[2] as f32);
+                writeln!(obj, "v {} {} {}", p.x(), p.y(), p.z())?;
+                writeln!(obj, "vn {} {} {}", n[0], n[1], n[2])?;
+            }
+            let [a, b, c, d] = [0, 1, 2, 3].map(|i| vertex_offset + count + i);
+            writeln!(obj, "f {a}//{a} {b}//{b} {c}//{c} {d}//{d}")?;
+            count += 4;
+        }
+    }
+    Ok(count)
+}
+
+/// Writes triangle mesh with vertex normals per triangle corner, shading normals if any. Returns count of written
+/// vertices.
+fn write_mesh(obj: &mut impl Write, mesh: &Mesh, vertex_offset: u32) -> io::Result<u32> {
+    for (i, triangle) in mesh.triangles().iter().enumerate() {
+        let [a, b, c] = mesh.corners(i);
+        let geometric = (b - a).cross(&(c - a)).normalize();
+        for (corner, &point) in triangle.iter().enumerate() {
+            let p = mesh.points()[point];
+            let n = match mesh.normals() {
+                Some((normals, indices)) => normals[indices[i][corner]],
+                None => geometric,
+            };
+            writeln!(obj, "v {} {} {}", p.x(), p.y(), p.z())?;
+            writeln!(obj, "vn {} {} {}", n.x(), n.y(), n.z())?;
+        }
+        let [a, b, c] = [0, 1, 2].map(|j| vertex_offset + 3 * i as u32 + j);
+        writeln!(obj, "f {a}//{a} {b}//{b} {c}//{c}")?;
+    }
+    Ok(3 * mesh.triangles().len() as u32)
+}
+
+/// Writes water surface as heightfield grid mesh with vertex normals. Returns count of written vertices.
+fn write_water(
+    obj: &mut impl Write,
+    water: &Water,
+    segments: u32,
+    vertex_offset: u32,
+) -> io::Result<u32> {
+    // Vertices and normals, row by row along z.
+    let (center, size) = (water.center(), water.size());
+    for row in 0..=segments {
+        let z = center.z() + size * (row as f32 / segments as f32 - 0.5);
+        for column in 0..=segments {
+            let x = center.x() + size * (column as f32 / segments as f32 - 0.5);
+            let (y, _, _) = water.height_at(x, z);
+            let n = water.normal_at(x, z);
+            writeln!(obj, "v {} {} {}", x, y, z)?;
+            writeln!(obj, "vn {} {} {}", n.x(), n.y(), n.z())?;
+        }
+    }
+
+    // Quads between rows, counter-clockwise seen from above.
+    let index = |row: u32, column: u32| vertex_offset + row * (segments + 1) + column;
+    for row in 0..segments {
+        for column in 0..segments {
+            let a = index(row, column);
+            let b = index(row + 1, column);
+            let c = index(row + 1, column + 1);
+            let d = index(row, column + 1);
+            writeln!(obj, "f {a}//{a} {b}//{b} {c}//{c} {d}//{d}")?;
+        }
+    }
+    Ok((segments + 1) * (segments + 1))
+}
+
+/// Writes material definition approximating eden material with MTL parameters.
+fn write_material(mtl: &mut impl Write, index: usize, material: &Material) -> io::Result<()> {
+    writeln!(mtl, "newmtl material_{}", index)?;
+    write_parameters(mtl, material)?;
+    writeln!(mtl)
+}
+
+/// Writes MTL parameters approximating eden material.
+fn write_parameters(mtl: &mut impl Write, material: &Material) -> io::Result<()> {
+    match material {
+        Material::Lambert(l) => {
+            let a = l.albedo();
+            writeln!(mtl, "Kd {} {} {}", a.r(), a.g(), a.b())?;
+            writeln!(mtl, "Ks 0 0 0")?;
+            writeln!(mtl, "illum 1")?;
+        }
+        Material::DiffuseLight(l) => {
+            let e = l.emission();
+            writeln!(mtl, "Kd 0 0 0")?;
+            writeln!(mtl, "Ke {} {} {}", e.r(), e.g(), e.b())?;
+            writeln!(mtl, "illum 0")?;
+        }
+        Material::Metal(m) => {
+            let a = m.albedo();
+            writeln!(mtl, "Kd 0 0 0")?;
+            writeln!(mtl, "Ks {} {} {}", a.r(), a.g(), a.b())?;
+            writeln!(mtl, "Ns {}", (1.0 - m.fuzz()) * 1000.0)?;
+            writeln!(mtl, "Pm 1")?;
+            writeln!(mtl, "Pr {}", m.fuzz())?;
+            writeln!(mtl, "illum 3")?;
+        }
+        Material::Microfacet(m) => {
+            let a = m.albedo();
+            writeln!(mtl, "Kd 0 0 0")?;
+            writeln!(mtl, "Ks {} {} {}", a.r(), a.g(), a.b())?;
+            writeln!(mtl, "Ns {}", (1.0 - m.roughness()) * 1000.0)?;
+            writeln!(mtl, "Pr {}", m.roughness())?;
+            match m.ior() {
+                Some(ior) => {
+                    writeln!(mtl, "Tf 1 1 1")?;
+                    writeln!(mtl, "Ni {}", ior)?;
+                    writeln!(mtl, "illum 7")?;
+                }
+                None => {
+                    writeln!(mtl, "Pm 1")?;
+                    writeln!(mtl, "illum 3")?;
+                }
+            }
+        }
+        Material::Principled(p) => {
+            // PBR extension of MTL.
+            let a = p.base_color();
+            writeln!(mtl, "Kd {} {} {}", a.r(), a.g(), a.b())?;
+            writeln!(mtl, "Pm {}", p.metallic())?;
+            writeln!(mtl, "Pr {}", p.roughness())?;
+            writeln!(mtl, "Ni {}", p.ior())?;
+            writeln!(mtl, "Tr {}", p.transmission())?;
+            writeln!(mtl, "illum 2")?;
+        }
+        Material::Dielectric(d) => {
+            writeln!(mtl, "Kd 0 0 0")?;
+            writeln!(mtl, "Ks 1 1 1")?;
+            // Transmission filter as transmittance through unit distance of medium.
+            let t = d.absorption();
+            let (r, g, b) = ((-t.r()).exp(), (-t.g()).exp(), (-t.b()).exp());
+            writeln!(mtl, "Tf {} {} {}", r, g, b)?;
+            writeln!(mtl, "Ni {}", d.ior())?;
+            writeln!(mtl, "illum 7")?;
+        }
+        Material::ShadowCatcher(_) => {
+            // Fully transparent, shadows are not representable.
+            writeln!(mtl, "Kd 0 0 0")?;
+            writeln!(mtl, "d 0")?;
+            writeln!(mtl, "illum 0")?;
+        }
+
+        Material::Custom(_) => {
+            // Not representable, approximated by neutral gray.
+            writeln!(mtl, "Kd 0.5 0.5 0.5")?;
+            writeln!(mtl, "illum 1")?;
+        }
+
+        Material::Mix(m) => {
+            // Not representable, approximated by first (base) material.
+            write_parameters(mtl, m.first())?;
+        }
+
+        Material::NormalMapped(n) => {
+            // Maps are not exported, only inner material.
+            write_parameters(mtl, n.material())?;
+        }
+
+        #[cfg(test)]
+        Material::None => {}
+    }
+    Ok(())
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        base::{color::Color3f, transform::Transform},
+        materials::{dielectric::Dielectric, lambert::Lambert},
+        shapes::{csg::Operation, instance::Prototype},
+    };
+    use std::sync::Arc;
+
+    #[test]
+    fn write() {
+        let mut scene = Scene::new();
+        let red = Material::Lambert(Lambert::new(Color3f::new(1.0, 0.0, 0.0)));
+        let glass = Material::Dielectric(Dielectric::new(1.5));
+        scene.add(Shape::Sphere(Sphere::new(
+            Point3f::default(),
+            1.0,
+            red.clone(),
+        )));
+        scene.add(Shape::Sphere(Sphere::new(
+            Point3f::new(3.0, 0.0, 0.0),
+            1.0,
+            glass,
+        )));
+        scene.add(Shape::Sphere(Sphere::new(
+            Point3f::new(6.0, 0.0, 0.0),
+            2.0,
+            red,
+        )));
+
+        let (mut obj, mut mtl) = (Vec::new(), Vec::new());
+        super::write(&scene, &mut obj, &mut mtl, "scene.mtl", 8).unwrap();
+        let obj = String::from_utf8(obj).unwrap();
+        let mtl = String::from_utf8(mtl).unwrap();
+
+        // 3 spheres with 5 rings of 8 vertices, 4 bands of 8 quads.
+        let count = |prefix: &str| obj.lines().filter(|l| l.starts_with(prefix)).count();
+        assert_eq!(count("o "), 3);
+        assert_eq!(count("v "), 3 * 40);
+        assert_eq!(count("vn "), 3 * 40);
+        assert_eq!(count("f "), 3 * 32);
+        assert!(obj.contains("mtllib scene.mtl"));
+        assert!(obj.lines().last().unwrap().starts_with("f 112//112 "));
+
+        // Shared materials.
+        let usemtl: Vec<&str> = obj.lines().filter(|l| l.starts_with("usemtl")).collect();
+        assert_eq!(
+            usemtl,
+            [
+                "usemtl material_0",
+                "usemtl material_1",
+                "usemtl material_0"
+            ]
+        );
+        assert_eq!(mtl.matches("newmtl").count(), 2);
+        assert!(mtl.contains("Kd 1 0 0"));
+        assert!(mtl.contains("Ni 1.5"));
+    }
+
+    #[test]
+    fn vertices_on_sphere() {
+        let mut scene = Scene::new();
+        let center = Point3f::new(1.0, 2.0, 3.0);
+        let red = Material::Lambert(Lambert::new(Color3f::new(1.0, 0.0, 0.0)));
+        scene.add(Shape::Sphere(Sphere::new(center, 2.0, red)));
+
+        let (mut obj, mut mtl) = (Vec::new(), Vec::new());
+        super::write(&scene, &mut obj, &mut mtl, "scene.mtl", 12).unwrap();
+        for line in String::from_utf8(obj).unwrap().lines() {
+            if let Some(v) = line.strip_prefix("v ") {
+                let c: Vec<f32> = v.split(' ').map(|x| x.parse().unwrap()).collect();
+                let p = Point3f::new(c[0], c[1], c[2]);
+                assert!(((p - center).length() - 2.0).abs() < 1e-5);
+            }
+        }
+    }
+
+    #[test]
+    fn instance() {
+        // Unit sphere prototype placed twice, scaled by two and moved.
+        let red = Material::Lambert(Lambert::new(Color3f::new(1.0, 0.0, 0.0)));
+        let sphere = Shape::Sphere(Sphere::new(Point3f::default(), 1.0, red));
+        let prototype = Arc::new(Prototype::new(vec![sphere]));
+        let mut scene = Scene::new();
+        let centers = [Point3f::new(5.0, 0.0, 0.0), Point3f::new(0.0, 0.0, 5.0)];
+        for center in centers {
+            let transform = Transform::translation(center - Point3f::default())
+                .compose(&Transform::scaling(2.0, 2.0, 2.0));
+            scene.add(Shape::Instance(Instance::new(prototype.clone(), transform)));
+        }
+
+        let (mut obj, mut mtl) = (Vec::new(), Vec::new());
+        super::write(&scene, &mut obj, &mut mtl, "scene.mtl", 12).unwrap();
+        let obj = String::from_utf8(obj).unwrap();
+        for line in obj.lines() {
+            if let Some(v) = line.strip_prefix("v ") {
+                let c: Vec<f32> = v.split(' ').map(|x| x.parse().unwrap()).collect();
+                let p = Point3f::new(c[0], c[1], c[2]);
+                let distance = centers.map(|center| (p - center).length());
+                assert!(distance.iter().any(|d| (d - 2.0).abs() < 1e-4));
+            }
+        }
+        assert_eq!(
+            obj.lines().filter(|l| l.starts_with("o instance_")).count(),
+            2
+        );
+        // Faces of second instance refer to its own vertices.
+        assert!(obj.contains("f 85//85"));
+    }
+
+    #[test]
+    fn cylinder() {
+        let mut scene = Scene::new();
+        let red = Material::Lambert(Lambert::new(Color3f::new(1.0, 0.0, 0.0)));
+        let axis = Vector3f::new(0.0, 1.0, 1.0);
+        let mut tube = Cylinder::new(Point3f::new(1.0, 2.0, 3.0), axis, 0.5, 2.0, red);
+        scene.add(Shape::Cylinder(tube.clone()));
+        tube.set_caps(false);
+        scene.add(Shape::Cylinder(tube.clone()));
+
+        // Closed cylinder with 2 rings and 2 caps of 8 vertices, open one with rings only.
+        let (mut obj, mut mtl) = (Vec::new(), Vec::new());
+        super::write(&scene, &mut obj, &mut mtl, "scene.mtl", 8).unwrap();
+        let obj = String::from_utf8(obj).unwrap();
+        let count = |prefix: &str| obj.lines().filter(|l| l.starts_with(prefix)).count();
+        assert_eq!(count("v "), 34 + 16);
+        assert_eq!(count("f "), 24 + 8);
+        assert!(obj
+            .lines()
+            .last()
+            .unwrap()
+            .ends_with("f 42//42 35//35 43//43 50//50"));
+        let (vertices, faces) = mesh(&obj);
+        assert!(outward(&vertices, &faces[..24], tube.center()));
+
+        // Vertices lie on surface.
+        let axis = axis.normalize();
+        for line in obj.lines() {
+            if let Some(v) = line.strip_prefix("v ") {
+                let c: Vec<f32> = v.split(' ').map(|x| x.parse().unwrap()).collect();
+                let local = Point3f::new(c[0], c[1], c[2]) - tube.center();
+                let along = local.dot(&axis);
+                let distance = (local - along * axis).length();
+                assert!(along.abs() < 1.0 + 1e-5 && distance < 0.5 + 1e-5);
+                assert!((along.abs() - 1.0).abs() < 1e-5 || (distance - 0.5).abs() < 1e-5);
+            }
+        }
+    }
+
+    #[test]
+    fn water() {
+        let mut scene = Scene::new();
+        let water = Water::new(
+            Point3f::new(1.0, 0.0, 0.0),
+            4.0,
+            0.1,
+            1.0,
+            Material::preset("water").unwrap(),
+        );
+        scene.add(Shape::Water(water.clone()));
+
+        // Grid of 5x5 vertices on surface, 4x4 quads.
+        let (mut obj, mut mtl) = (Vec::new(), Vec::new());
+        super::write(&scene, &mut obj, &mut mtl, "scene.mtl", 4).unwrap();
+        let obj = String::from_utf8(obj).unwrap();
+        assert!(obj.contains("o water_0"));
+        assert_eq!(obj.lines().filter(|l| l.starts_with("f ")).count(), 16);
+        let vertices: Vec<Point3f> = obj
+            .lines()
+            .filter_map(|l| l.strip_prefix("v "))
+            .map(|v| {
+                let c: Vec<f32> = v.split(' ').map(|x| x.parse().unwrap()).collect();
+                Point3f::new(c[0], c[1], c[2])
+            })
+            .collect();
+        assert_eq!(vertices.len(), 25);
+        assert_eq!((vertices[0].x(), vertices[0].z()), (-1.0, -2.0));
+        for p in vertices {
+            assert!((p.y() - water.height_at(p.x(), p.z()).0).abs() < 1e-6);
+        }
+        assert!(String::from_utf8(mtl).unwrap().contains("Tf 0.63"));
+    }
+
+    /// Vertices of OBJ text, and faces as vertex indices.
+    fn mesh(obj: &str) -> (Vec<Point3f>, Vec<Vec<usize>>) {
+        let mut vertices = Vec::new();
+        let mut faces = Vec::new();
+        for line in obj.lines() {
+            if let Some(v) = line.strip_prefix("v ") {
+                let c: Vec<f32> = v.split(' ').map(|x| x.parse().unwrap()).collect();
+                vertices.push(Point3f::new(c[0], c[1], c[2]));
+            } else if let Some(f) = line.strip_prefix("f ") {
+                let indices = f
+                    .split(' ')
+                    .map(|i| i.split('/').next().unwrap().parse().unwrap());
+                faces.push(indices.collect());
+            }
+        }
+        (vertices, faces)
+    }
+
+    /// Whether all faces of convex mesh are counter-clockwise seen from outside, i.e. face away from interior point.
+    fn outward(vertices: &[Point3f], faces: &[Vec<usize>], interior: Point3f) -> bool {
+        faces.iter().all(|f| {
+            let p: Vec<Point3f> = f.iter().map(|&i| vertices[i - 1]).collect();
+            // Skip degenerate first corner at cone apex.
+            let (a, b, c) = if (p[1] - p[0]).near_zero() {
+                (p[1], p[2], p[0])
+            } else {
+                (p[0], p[1], p[2])
+            };
+            (b - a).cross(&(c - a)).dot(&(a - interior)) > 0.0
+        })
+    }
+
+    #[test]
+    fn cone() {
+        let mut scene = Scene::new();
+        let red = Material::Lambert(Lambert::new(Color3f::new(1.0, 0.0, 0.0)));
+        let axis = Vector3f::new(1.0, 1.0, 0.0);
+        let mut cone = Cone::new(Point3f::new(1.0, 2.0, 3.0), axis, 0.5, 2.0, red);
+        scene.add(Shape::Cone(cone.clone()));
+        cone.set_base(false);
+        scene.add(Shape::Cone(cone.clone()));
+
+        // Closed cone with base and apex ring and base fan of 8 vertices, open one without fan.
+        let (mut obj, mut mtl) = (Vec::new(), Vec::new());
+        super::write(&scene, &mut obj, &mut mtl, "scene.mtl", 8).unwrap();
+        let obj = String::from_utf8(obj).unwrap();
+        assert!(obj.contains("o cone_0") && obj.contains("o cone_1"));
+        let (vertices, faces) = mesh(&obj);
+        assert_eq!(vertices.len(), 25 + 16);
+        assert_eq!(faces.len(), 16 + 8);
+        assert!(outward(&vertices, &faces[..16], cone.center()));
+
+        // Vertices lie on surface.
+        let axis = axis.normalize();
+        for p in vertices {
+            let local = p - cone.center();
+            let along = local.dot(&axis);
+            let distance = (local - along * axis).length();
+            assert!(distance < 0.25 * (1.0 - along) + 1e-5);
+            assert!((along + 1.0).abs() < 1e-5 || (distance - 0.25 * (1.0 - along)).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn capsule() {
+        let mut scene = Scene::new();
+        let red = Material::Lambert(Lambert::new(Color3f::new(1.0, 0.0, 0.0)));
+        let axis = Vector3f::new(0.0, 1.0, 1.0);
+        let capsule = Capsule::new(Point3f::new(1.0, 2.0, 3.0), axis, 0.5, 2.0, red);
+        scene.add(Shape::Capsule(capsule.clone()));
+
+        // 6 rings of 8 vertices, 2 per hemisphere plus poles, 5 bands of 8 quads.
+        let (mut obj, mut mtl) = (Vec::new(), Vec::new());
+        super::write(&scene, &mut obj, &mut mtl, "scene.mtl", 8).unwrap();
+        let obj = String::from_utf8(obj).unwrap();
+        assert!(obj.contains("o capsule_0"));
+        let (vertices, faces) = mesh(&obj);
+        assert_eq!(vertices.len(), 48);
+        assert_eq!(faces.len(), 40);
+        assert!(outward(&vertices, &faces[8..32], capsule.center()));
+
+        // Vertices lie on surface.
+        let axis = axis.normalize();
+        for p in vertices {
+            let local = p - capsule.center();
+            let along = local.dot(&axis).clamp(-1.0, 1.0);
+            assert!(((local - along * axis).length() - 0.5).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn torus() {
+        let mut scene = Scene::new();
+        let red = Material::Lambert(Lambert::new(Color3f::new(1.0, 0.0, 0.0)));
+        let axis = Vector3f::new(0.0, 1.0, 1.0);
+        let torus = Torus::new(Point3f::new(1.0, 2.0, 3.0), axis, 2.0, 0.5, red);
+        scene.add(Shape::Torus(torus.clone()));
+
+        // 4 rings of 8 vertices around tube, 4 bands of 8 quads closing the tube.
+        let (mut obj, mut mtl) = (Vec::new(), Vec::new());
+        super::write(&scene, &mut obj, &mut mtl, "scene.mtl", 8).unwrap();
+        let obj = String::from_utf8(obj).unwrap();
+        assert!(obj.contains("o torus_0"));
+        let (vertices, faces) = mesh(&obj);
+        assert_eq!(vertices.len(), 32);
+        assert_eq!(faces.len(), 32);
+        assert!(obj
+            .lines()
+            .last()
+            .unwrap()
+            .ends_with("f 32//32 25//25 1//1 8//8"));
+
+        // Vertices lie on surface, faces point away from tube center circle.
+        let axis = axis.normalize();
+        let tube_center = |p: Point3f| {
+            let local = p - torus.center();
+            let radial = (local - local.dot(&axis) * axis).normalize();
+            torus.center() + 2.0 * radial
+        };
+        for &p in &vertices {
+            assert!(((p - tube_center(p)).length() - 0.5).abs() < 1e-5);
+        }
+        for f in &faces {
+            let p: Vec<Point3f> = f.iter().map(|&i| vertices[i - 1]).collect();
+            let normal = (p[1] - p[0]).cross(&(p[2] - p[0]));
+            assert!(normal.dot(&(p[0] - tube_center(p[0]))) > 0.0);
+        }
+    }
+
+    #[test]
+    fn csg() {
+        let mut scene = Scene::new();
+        let red = Material::Lambert(Lambert::new(Color3f::new(1.0, 0.0, 0.0)));
+        let sphere =
+            |x: f32| Shape::Sphere(Sphere::new(Point3f::new(x, 0.0, 0.0), 1.0, red.clone()));
+        let csg = Csg::new(Operation::Difference, sphere(0.0), sphere(1.0));
+        scene.add(Shape::Csg(csg));
+
+        // Both spheres with 5 rings of 8 vertices as one object, second indexed after first.
+        let (mut obj, mut mtl) = (Vec::new(), Vec::new());
+        super::write(&scene, &mut obj, &mut mtl, "scene.mtl", 8).unwrap();
+        let obj = String::from_utf8(obj).unwrap();
+        assert!(obj.contains("o csg_0"));
+        let (vertices, faces) = mesh(&obj);
+        assert_eq!(vertices.len(), 80);
+        assert_eq!(faces.len(), 64);
+        assert!(obj.lines().last().unwrap().starts_with("f 72//72 "));
+    }
+
+    #[test]
+    fn voxels() {
+        let mut scene = Scene::new();
+        let red = Material::Lambert(Lambert::new(Color3f::new(1.0, 0.0, 0.0)));
+        let positions = [([0, 0, 0], 0), ([1, 0, 0], 0), ([0, 1, 0], 0)];
+        let origin = Point3f::new(1.0, 2.0, 3.0);
+        scene.add(Shape::Voxels(Voxels::new(
+            origin,
+            0.5,
+            vec![red],
+            &positions,
+        )));
+
+        // 14 exposed faces of L-shape as quads, winding agrees with face normals.
+        let (mut obj, mut mtl) = (Vec::new(), Vec::new());
+        super::write(&scene, &mut obj, &mut mtl, "scene.mtl", 8).unwrap();
+        let obj = String::from_utf8(obj).unwrap();
+        assert!(obj.contains("o voxels_0"));
+        let (vertices, faces) = mesh(&obj);
+        assert_eq!(vertices.len(), 56);
+        assert_eq!(faces.len(), 14);
+        let normals: Vec<Vector3f> = obj
+            .lines()
+            .filter_map(|l| l.strip_prefix("vn "))
+            .map(|n| {
+                let c: Vec<f32> = n.split(' ').map(|x| x.parse().unwrap()).collect();
+                Vector3f::new(c[0], c[1], c[2])
+            })
+            .collect();
+        for f in faces {
+            let p: Vec<Point3f> = f.iter().map(|&i| vertices[i - 1]).collect();
+            let normal = (p[1] - p[0]).cross(&(p[2] - p[0]));
+            assert!(normal.dot(&normals[f[0] - 1]) > 0.0);
+        }
+    }
+}
@@ -0,0 +1,1483 @@
+use crate::{
+    base::{
+        color::Color3f,
+        material::Material,
+        orientation::Orientation,
+        point::{Point3f, Uv},
+        shape::Shape,
+        transform::Transform,
+        vector::Vector3f,
+    },
+    camera::Camera,
+    expression::{self, Expression},
+    material_library::MaterialHandle,
+    materials::{diffuse_light::DiffuseLight, lambert::Lambert, principled::Principled},
+    scene::Scene,
+    shapes::{mesh::Mesh, sphere::Sphere},
+};
+use std::{collections::HashMap, error::Error, fmt, fs, io, path::Path};
+
+/// Error while importing USD stage.
+#[derive(Debug)]
+pub enum ImportError {
+    /// File could not be read.
+    Io(io::Error),
+
+    /// Source is not valid usda text.
+    Syntax { line: usize, message: String },
+
+    /// Prim holds values that cannot be interpreted.
+    Invalid { path: String, message: String },
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::Io(e) => write!(f, "{}", e),
+            ImportError::Syntax { line, message } => write!(f, "line {}: {}", line, message),
+            ImportError::Invalid { path, message } => write!(f, "{}: {}", path, message),
+        }
+    }
+}
+
+impl Error for ImportError {}
+
+impl From<io::Error> for ImportError {
+    fn from(e: io::Error) -> Self {
+        ImportError::Io(e)
+    }
+}
+
+/// Camera placement read from USD stage.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StageCamera {
+    /// Point camera is looking from.
+    look_from: Point3f,
+
+    /// Point camera is looking at.
+    look_at: Point3f,
+
+    /// Camera-relative 'up' direction.
+    view_up: Vector3f,
+
+    /// Vertical field of view in degrees.
+    vfov: f32,
+
+    /// Defocus angle in degrees.
+    defocus_angle: f32,
+
+    /// Focus distance.
+    focus_distance: f32,
+}
+
+impl StageCamera {
+    /// Applies placement and lens to camera.
+    pub fn apply(&self, camera: &mut Camera) {
+        camera.set_look_from(self.look_from);
+        camera.set_look_at(self.look_at);
+        camera.set_view_up(self.view_up);
+        camera.set_vfov(self.vfov);
+        camera.set_defocus_angle(self.defocus_angle);
+        camera.set_focus_distance(self.focus_distance);
+    }
+}
+
+/// Stage imported from USD file.
+pub struct Stage {
+    /// Scene built from supported prims.
+    scene: Scene,
+
+    /// First camera of stage.
+    camera: Option<StageCamera>,
+
+    /// Paths of prims skipped because their type is not supported.
+    skipped: Vec<String>,
+
+    /// Paths of closed mesh prims whose triangles were reoriented consistently outwards.
+    reoriented: Vec<String>,
+
+    /// Paths of prims scene objects were built from, by object index.
+    object_paths: Vec<String>,
+
+    /// Materials defined in stage with their prim paths, sorted by path.
+    materials: Vec<(String, Material)>,
+
+    /// Whether any attribute expression depends on frame number or time.
+    animated: bool,
+
+    /// Orientation authored in stage, converted from.
+    orientation: Orientation,
+}
+
+impl Stage {
+    /// Scene built from supported prims.
+    pub fn scene(&self) -> &Scene {
+        &self.scene
+    }
+
+    /// Takes scene out of stage.
+    pub fn into_scene(self) -> Scene {
+        self.scene
+    }
+
+    /// First camera of stage.
+    pub fn camera(&self) -> Option<StageCamera> {
+        self.camera
+    }
+
+    /// Paths of prims skipped because their type is not supported.
+    pub fn skipped(&self) -> &[String] {
+        &self.skipped
+    }
+
+    /// Paths of closed mesh prims whose triangles were reoriented consistently outwards, as their winding was mixed
+    /// or inverted.
+    pub fn reoriented(&self) -> &[String] {
+        &self.reoriented
+    }
+
+    /// Path of prim scene object with given index was built from, e.g. to name it in reports.
+    pub fn object_path(&self, index: usize) -> &str {
+        &self.object_paths[index]
+    }
+
+    /// Materials defined in stage with their prim paths, sorted by path, whether bound to any prim or not. They are
+    /// also defined by path in the scene's material library, with objects bound to them.
+    pub fn materials(&self) -> &[(String, Material)] {
+        &self.materials
+    }
+
+    /// Whether any attribute expression depends on frame number or time, so other frames differ.
+    pub fn is_animated(&self) -> bool {
+        self.animated
+    }
+
+    /// Orientation authored in stage, converted from.
+    pub fn orientation(&self) -> Orientation {
+        self.orientation
+    }
+}
+
+/// Imports USD stage from usda text file at path, at first frame, converted to orientation.
+pub fn import(path: &Path, orientation: Orientation) -> Result<Stage, ImportError> {
+    parse(&fs::read_to_string(path)?, orientation)
+}
+
+/// Imports USD stage from usda text file at path, at frame number, converted to orientation.
+pub fn import_frame(
+    path: &Path,
+    frame: u32,
+    orientation: Orientation,
+) -> Result<Stage, ImportError> {
+    parse_frame(&fs::read_to_string(path)?, frame, orientation)
+}
+
+/// Parses USD stage from usda text, at first frame. Supports spheres, polygon meshes (triangulated as fans, with
+/// normals and `st` surface coordinates of any interpolation, closed ones oriented outwards), transforms (xformOp
+/// translate, scale, rotate and transform), cameras and UsdPreviewSurface materials. Composition arcs are ignored,
+/// prims of other types are skipped.
+///
+/// The stage is converted from its authored up axis (stage metadata `upAxis`, y by default) to orientation, e.g. of
+/// the scene it is rendered in, which its scene takes on.
+pub fn parse(source: &str, orientation: Orientation) -> Result<Stage, ImportError> {
+    parse_frame(source, 0, orientation)
+}
+
+/// Parses USD stage from usda text, at frame number. Numeric and color attributes may be authored as expression
+/// strings instead of literals (e.g. `double radius = "0.2 + 0.1*sin(frame/24)"`), which are evaluated at the frame,
+/// see `Expression`.
+pub fn parse_frame(
+    source: &str,
+    frame: u32,
+    orientation: Orientation,
+) -> Result<Stage, ImportError> {
+    if !source.trim_start().starts_with("#usda") {
+        return Err(ImportError::Syntax {
+            line: 1,
+            message: "missing #usda header".to_string(),
+        });
+    }
+    let (metadata, mut prims) = Parser::new(tokenize(source)?).parse_stage()?;
+    let authored = match metadata.get("upAxis").map(String::as_str) {
+        None | Some("Y") => Orientation::Y_UP,
+        Some("Z") => Orientation::Z_UP,
+        Some(axis) => {
+            return Err(ImportError::Invalid {
+                path: "/".to_string(),
+                message: format!("unsupported upAxis {}", axis),
+            })
+        }
+    };
+
+    // Expressions evaluated before interpreting values.
+    let mut animated = false;
+    for prim in prims.iter_mut() {
+        evaluate(prim, "", frame, &mut animated)?;
+    }
+
+    // Materials first, bindings may point anywhere in stage.
+    let mut materials = HashMap::new();
+    for prim in prims.iter() {
+        collect_materials(prim, "", &mut materials)?;
+    }
+
+    let mut stage = Stage {
+        scene: Scene::new(),
+        camera: None,
+        skipped: Vec::new(),
+        reoriented: Vec::new(),
+        object_paths: Vec::new(),
+        materials: Vec::new(),
+        animated,
+        orientation: authored,
+    };
+    stage.scene.set_orientation(orientation);
+    stage.materials = materials.into_iter().collect();
+    stage.materials.sort_by(|a, b| a.0.cmp(&b.0));
+    for (path, material) in stage.materials.iter() {
+        stage.scene.define_material(path, material.clone());
+    }
+    let root = Transform::linear(authored.conversion(orientation).axes());
+    for prim in prims.iter() {
+        build(prim, "", root, None, &mut stage)?;
+    }
+    Ok(stage)
+}
+
+/// Attribute value.
+#[derive(Clone, Debug, PartialEq)]
+enum Value {
+    Number(f32),
+    Str(String),
+    Path(String),
+    Token(String),
+    List(Vec<Value>),
+    Dictionary,
+    Expression(Expression),
+}
+
+impl Value {
+    /// Scalar number.
+    fn number(&self) -> Option<f32> {
+        match self {
+            Value::Number(x) => Some(*x),
+            _ => None,
+        }
+    }
+
+    /// Text of string or token.
+    fn text(&self) -> Option<&str> {
+        match self {
+            Value::Str(s) | Value::Token(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Tuple of three numbers.
+    fn triple(&self) -> Option<[f32; 3]> {
+        match self {
+            Value::List(v) if v.len() == 3 => {
+                Some([v[0].number()?, v[1].number()?, v[2].number()?])
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Prim with its attributes and child prims.
+#[derive(Debug)]
+struct Prim {
+    /// Specifier (def, over or class).
+    specifier: String,
+
+    /// Schema type name, empty if typeless.
+    type_name: String,
+
+    /// Prim name.
+    name: String,
+
+    /// Attributes and relationships by name.
+    attributes: HashMap<String, Value>,
+
+    /// Interpolation metadata of attributes by name, e.g. faceVarying of primvars.
+    interpolations: HashMap<String, String>,
+
+    /// Child prims.
+    children: Vec<Prim>,
+}
+
+impl Prim {
+    /// Number attribute or default.
+    fn number(&self, name: &str, default: f32) -> f32 {
+        self.attributes
+            .get(name)
+            .and_then(Value::number)
+            .unwrap_or(default)
+    }
+
+    /// Color attribute or default.
+    fn color(&self, name: &str, default: Color3f) -> Color3f {
+        match self.attributes.get(name).and_then(Value::triple) {
+            Some([r, g, b]) => Color3f::new(r, g, b),
+            None => default,
+        }
+    }
+}
+
+/// Lexical token of usda text.
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    /// Single punctuation character.
+    Punct(char),
+
+    /// Quoted string or asset path.
+    Str(String),
+
+    /// Scene path in angle brackets.
+    Path(String),
+
+    /// Keyword, identifier, type name or number.
+    Word(String),
+}
+
+/// Splits usda text into tokens with their line numbers.
+fn tokenize(source: &str) -> Result<Vec<(Token, usize)>, ImportError> {
+    let mut tokens = Vec::new();
+    let mut line = 1;
+    let mut chars = source.chars().peekable();
+    let unterminated = |line| ImportError::Syntax {
+        line,
+        message: "unterminated literal".to_string(),
+    };
+    while let Some(c) = chars.next() {
+        let start = line;
+        match c {
+            '\n' => line += 1,
+            c if c.is_whitespace() => {}
+            '#' => while chars.next_if(|&c| c != '\n').is_some() {},
+            '(' | ')' | '[' | ']' | '{' | '}' | '=' | ',' | ';' => {
+                tokens.push((Token::Punct(c), line))
+            }
+            '"' | '\'' => {
+                // Triple quoted strings may span lines.
+                let triple = chars.next_if_eq(&c).is_some();
+                if triple && chars.next_if_eq(&c).is_none() {
+                    tokens.push((Token::Str(String::new()), line));
+                    continue;
+                }
+                let mut s = String::new();
+                loop {
+                    match chars.next().ok_or_else(|| unterminated(start))? {
+                        '\\' => s.push(chars.next().ok_or_else(|| unterminated(start))?),
+                        q if q == c && !triple => break,
+                        q if q == c && chars.peek() == Some(&c) => {
+                            chars.next();
+                            if chars.next_if_eq(&c).is_some() {
+                                break;
+                            }
+                            s.push(c);
+                            s.push(c);
+                        }
+                        ch => {
+                            if ch == '\n' {
+                                line += 1;
+                            }
+                            s.push(ch);
+                        }
+                    }
+                }
+                tokens.push((Token::Str(s), start));
+            }
+            '@' => {
+                let mut s = String::new();
+                while let Some(ch) = chars.next_if(|&ch| ch != '@' && ch != '\n') {
+                    s.push(ch);
+                }
+                chars.next_if_eq(&'@').ok_or_else(|| unterminated(start))?;
+                tokens.push((Token::Str(s), start));
+            }
+            '<' => {
+                let mut s = String::new();
+                while let Some(ch) = chars.next_if(|&ch| ch != '>' && ch != '\n') {
+                    s.push(ch);
+                }
+                chars.next_if_eq(&'>').ok_or_else(|| unterminated(start))?;
+                tokens.push((Token::Path(s), start));
+            }
+            c if is_word_char(c) => {
+                let mut s = c.to_string();
+                while let Some(ch) = chars.next_if(|&ch| is_word_char(ch)) {
+                    s.push(ch);
+                }
+                tokens.push((Token::Word(s), start));
+            }
+            c => {
+                return Err(ImportError::Syntax {
+                    line,
+                    message: format!("unexpected character '{}'", c),
+                })
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+/// Whether attribute type holds numbers (scalars, tuples, colors or matrices, also as arrays).
+fn is_numeric_type(type_name: &str) -> bool {
+    [
+        "half", "float", "double", "int", "uint", "color", "point", "vector", "normal", "matrix",
+        "quat", "texCoord",
+    ]
+    .iter()
+    .any(|prefix| type_name.starts_with(prefix))
+}
+
+/// Whether character can be part of word token.
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | ':' | '.' | '-' | '+' | '!')
+}
+
+/// Recursive descent parser over usda tokens.
+struct Parser {
+    /// Tokens with line numbers.
+    tokens: Vec<(Token, usize)>,
+
+    /// Index of next token.
+    position: usize,
+}
+
+impl Parser {
+    /// Creates parser over tokens.
+    fn new(tokens: Vec<(Token, usize)>) -> Self {
+        Parser {
+            tokens,
+            position: 0,
+        }
+    }
+
+    /// Parses string entries of stage metadata (e.g. upAxis) and root prims, skipping other metadata.
+    fn parse_stage(&mut self) -> Result<(HashMap<String, String>, Vec<Prim>), ImportError> {
+        let mut metadata = HashMap::new();
+        if self.peek() == Some(&Token::Punct('(')) {
+            let start = self.position;
+            self.skip_group()?;
+            let mut depth = 0;
+            let group = &self.tokens[start + 1..self.position - 1];
+            for (i, (token, _)) in group.iter().enumerate() {
+                match token {
+                    Token::Punct('(' | '[' | '{') => depth += 1,
+                    Token::Punct(')' | ']' | '}') => depth -= 1,
+                    Token::Word(key) if depth == 0 => {
+                        if let [_, (Token::Punct('='), _), (Token::Str(value), _), ..] = &group[i..]
+                        {
+                            metadata.insert(key.clone(), value.clone());
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        let mut prims = Vec::new();
+        while self.peek().is_some() {
+            prims.push(self.parse_prim()?);
+        }
+        Ok((metadata, prims))
+    }
+
+    /// Parses prim definition with its body.
+    fn parse_prim(&mut self) -> Result<Prim, ImportError> {
+        let specifier = self.word()?;
+        if !matches!(specifier.as_str(), "def" | "over" | "class") {
+            return Err(self.error(format!("expected prim, found '{}'", specifier)));
+        }
+        let type_name = match self.peek() {
+            Some(Token::Word(_)) => self.word()?,
+            _ => String::new(),
+        };
+        let name = match self.next()? {
+            Token::Str(name) => name,
+            _ => return Err(self.error("expected prim name".to_string())),
+        };
+        if self.peek() == Some(&Token::Punct('(')) {
+            self.skip_group()?;
+        }
+
+        // Body with properties and children.
+        let mut prim = Prim {
+            specifier,
+            type_name,
+            name,
+            attributes: HashMap::new(),
+            interpolations: HashMap::new(),
+            children: Vec::new(),
+        };
+        self.expect('{')?;
+        loop {
+            match self.peek() {
+                Some(Token::Punct('}')) => break,
+                Some(Token::Punct(';')) => {
+                    self.next()?;
+                }
+                Some(Token::Word(w)) if matches!(w.as_str(), "def" | "over" | "class") => {
+                    prim.children.push(self.parse_prim()?)
+                }
+                Some(Token::Word(w)) if w == "variantSet" => {
+                    return Err(self.error("variant sets are not supported".to_string()))
+                }
+                _ => {
+                    let (name, value, interpolation) = self.parse_property()?;
+                    if let Some(interpolation) = interpolation {
+                        prim.interpolations.insert(name.clone(), interpolation);
+                    }
+                    if let Some(value) = value {
+                        prim.attributes.insert(name, value);
+                    }
+                }
+            }
+        }
+        self.expect('}')?;
+        Ok(prim)
+    }
+
+    /// Parses attribute or relationship. Returns name, value and interpolation metadata if authored.
+    fn parse_property(&mut self) -> Result<(String, Option<Value>, Option<String>), ImportError> {
+        // Qualifiers, type and name.
+        let mut word = self.word()?;
+        while matches!(
+            word.as_str(),
+            "uniform" | "custom" | "varying" | "prepend" | "append" | "add" | "delete" | "reorder"
+        ) {
+            word = self.word()?;
+        }
+        if self.peek() == Some(&Token::Punct('[')) {
+            self.expect('[')?;
+            self.expect(']')?;
+        }
+        let name = self.word()?;
+
+        // Value and metadata. Strings of numeric types are expressions.
+        let mut value = None;
+        if self.peek() == Some(&Token::Punct('=')) {
+            self.next()?;
+            let literal = self.parse_value()?;
+            value = Some(if is_numeric_type(&word) {
+                self.expressions(literal)?
+            } else {
+                literal
+            });
+        }
+        let mut interpolation = None;
+        if self.peek() == Some(&Token::Punct('(')) {
+            let start = self.position;
+            self.skip_group()?;
+            if let Some([_, (Token::Punct('='), _), (Token::Str(value), _)]) = self.tokens
+                [start..self.position]
+                .windows(3)
+                .find(|w| w[0].0 == Token::Word("interpolation".to_string()))
+            {
+                interpolation = Some(value.clone());
+            }
+        }
+        Ok((name, value, interpolation))
+    }
+
+    /// Parses value literal.
+    fn parse_value(&mut self) -> Result<Value, ImportError> {
+        match self.next()? {
+            Token::Word(w) => Ok(match w.parse() {
+                Ok(x) => Value::Number(x),
+                Err(_) => Value::Token(w),
+            }),
+            Token::Str(s) => Ok(Value::Str(s)),
+            Token::Path(p) => Ok(Value::Path(p)),
+            Token::Punct('{') => {
+                self.position -= 1;
+                self.skip_group()?;
+                Ok(Value::Dictionary)
+            }
+            Token::Punct(open @ ('(' | '[')) => {
+                let close = if open == '(' { ')' } else { ']' };
+                let mut items = Vec::new();
+                loop {
+                    if self.peek() == Some(&Token::Punct(close)) {
+                        self.next()?;
+                        break;
+                    }
+                    items.push(self.parse_value()?);
+                    match self.next()? {
+                        Token::Punct(',') => {}
+                        Token::Punct(c) if c == close => break,
+                        _ => return Err(self.error(format!("expected ',' or '{}'", close))),
+                    }
+                }
+                Ok(Value::List(items))
+            }
+            Token::Punct(c) => Err(self.error(format!("unexpected '{}'", c))),
+        }
+    }
+
+    /// Parses strings within value as expressions.
+    fn expressions(&self, value: Value) -> Result<Value, ImportError> {
+        match value {
+            Value::Str(text) => Expression::parse(&text)
+                .map(Value::Expression)
+                .map_err(|e| self.error(format!("invalid expression \"{}\": {}", text, e))),
+            Value::List(items) => Ok(Value::List(
+                items
+                    .into_iter()
+                    .map(|item| self.expressions(item))
+                    .collect::<Result<_, _>>()?,
+            )),
+            value => Ok(value),
+        }
+    }
+
+    /// Skips parenthesized, bracketed or braced group including nested groups.
+    fn skip_group(&mut self) -> Result<(), ImportError> {
+        let mut depth = 0;
+        loop {
+            match self.next()? {
+                Token::Punct('(' | '[' | '{') => depth += 1,
+                Token::Punct(')' | ']' | '}') => depth -= 1,
+                _ => {}
+            }
+            if depth == 0 {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Next token without consuming it.
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(t, _)| t)
+    }
+
+    /// Consumes next token.
+    fn next(&mut self) -> Result<Token, ImportError> {
+        let token = self.peek().cloned();
+        self.position += 1;
+        token.ok_or_else(|| self.error("unexpected end of file".to_string()))
+    }
+
+    /// Consumes word token.
+    fn word(&mut self) -> Result<String, ImportError> {
+        match self.next()? {
+            Token::Word(w) => Ok(w),
+            t => Err(self.error(format!("expected word, found {:?}", t))),
+        }
+    }
+
+    /// Consumes given punctuation.
+    fn expect(&mut self, c: char) -> Result<(), ImportError> {
+        match self.next()? {
+            Token::Punct(p) if p == c => Ok(()),
+            t => Err(self.error(format!("expected '{}', found {:?}", c, t))),
+        }
+    }
+
+    /// Syntax error at line of last consumed token.
+    fn error(&self, message: String) -> ImportError {
+        let line = match self.tokens.get(self.position.saturating_sub(1)) {
+            Some((_, line)) => *line,
+            None => self.tokens.last().map_or(1, |(_, line)| *line),
+        };
+        ImportError::Syntax { line, message }
+    }
+}
+
+/// Replaces expressions in attributes of prim subtree by their values at frame number, colors becoming tuples.
+/// Records whether any expression is animated.
+fn evaluate(
+    prim: &mut Prim,
+    parent: &str,
+    frame: u32,
+    animated: &mut bool,
+) -> Result<(), ImportError> {
+    let path = format!("{}/{}", parent, prim.name);
+    for (name, value) in prim.attributes.iter_mut() {
+        evaluate_value(value, frame, animated).map_err(|e| ImportError::Invalid {
+            path: path.clone(),
+            message: format!("{}: {}", name, e),
+        })?;
+    }
+    for child in prim.children.iter_mut() {
+        evaluate(child, &path, frame, animated)?;
+    }
+    Ok(())
+}
+
+/// Replaces expressions within value by their values at frame number.
+fn evaluate_value(
+    value: &mut Value,
+    frame: u32,
+    animated: &mut bool,
+) -> Result<(), expression::ExpressionError> {
+    match value {
+        Value::Expression(e) => {
+            *animated |= e.is_animated();
+            *value = match e.eval(frame)? {
+                expression::Value::Number(x) => Value::Number(x),
+                expression::Value::Color(c) => {
+                    Value::List([c.r(), c.g(), c.b()].map(Value::Number).to_vec())
+                }
+            };
+        }
+        Value::List(items) => {
+            for item in items.iter_mut() {
+                evaluate_value(item, frame, animated)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Collects materials of prim subtree by path. Materials are either named eden presets (custom `eden:preset`
+/// attribute) or UsdPreviewSurface shaders.
+fn collect_materials(
+    prim: &Prim,
+    parent: &str,
+    materials: &mut HashMap<String, Material>,
+) -> Result<(), ImportError> {
+    let path = format!("{}/{}", parent, prim.name);
+    if let (true, Some(name)) = (
+        prim.type_name == "Material",
+        prim.attributes.get("eden:preset").and_then(Value::text),
+    ) {
+        let material = Material::preset(name).ok_or_else(|| ImportError::Invalid {
+            path: path.clone(),
+            message: format!("unknown preset {}", name),
+        })?;
+        materials.insert(path.clone(), material);
+    } else if prim.type_name == "Material" {
+        let shader = prim.children.iter().find(|c| {
+            c.type_name == "Shader"
+                && c.attributes.get("info:id").and_then(Value::text) == Some("UsdPreviewSurface")
+        });
+        if let Some(shader) = shader {
+            materials.insert(path.clone(), preview_surface(shader));
+        }
+    }
+    for child in prim.children.iter() {
+        collect_materials(child, &path, materials)?;
+    }
+    Ok(())
+}
+
+/// Approximates UsdPreviewSurface shader with eden material. Emissive surfaces become diffuse lights.
+fn preview_surface(shader: &Prim) -> Material {
+    let diffuse = shader.color("inputs:diffuseColor", Color3f::new(0.18, 0.18, 0.18));
+    let emissive = shader.color("inputs:emissiveColor", Color3f::black());
+    if emissive != Color3f::black() {
+        Material::DiffuseLight(DiffuseLight::new(emissive))
+    } else {
+        let mut principled = Principled::new(diffuse);
+        principled.set_metallic(shader.number("inputs:metallic", 0.0));
+        principled.set_roughness(shader.number("inputs:roughness", 0.5));
+        principled.set_transmission(1.0 - shader.number("inputs:opacity", 1.0));
+        principled.set_ior(shader.number("inputs:ior", 1.5));
+        Material::Principled(principled)
+    }
+}
+
+/// Adds supported prims of subtree to stage.
+fn build(
+    prim: &Prim,
+    parent: &str,
+    transform: Transform,
+    material: Option<MaterialHandle>,
+    stage: &mut Stage,
+) -> Result<(), ImportError> {
+    let path = format!("{}/{}", parent, prim.name);
+    let invalid = |message: &str| ImportError::Invalid {
+        path: path.clone(),
+        message: message.to_string(),
+    };
+
+    // Abstract prims and materials are no geometry.
+    if prim.specifier == "class" || prim.type_name == "Material" {
+        return Ok(());
+    }
+    let transform = local_transform(prim, transform).map_err(|m| invalid(&m))?;
+
+    // Bound material is inherited by descendants, and stays bound by its path in the scene's library.
+    let material = match prim.attributes.get("material:binding") {
+        Some(Value::Path(target)) => Some(
+            stage
+                .scene
+                .library()
+                .handle(target)
+                .ok_or_else(|| invalid(&format!("unknown material {}", target)))?,
+        ),
+        _ => material,
+    };
+
+    // Display color of geometry without bound material.
+    let display_color = match prim.attributes.get("primvars:displayColor") {
+        Some(Value::List(colors)) => colors.first().and_then(Value::triple),
+        _ => None,
+    };
+    let [r, g, b] = display_color.unwrap_or([0.18, 0.18, 0.18]);
+    let display = Material::Lambert(Lambert::new(Color3f::new(r, g, b)));
+
+    let shape = match prim.type_name.as_str() {
+        "Sphere" => Some(Shape::Sphere(Sphere::new(
+            transform.point(Point3f::default()),
+            prim.number("radius", 1.0) * transform.scale(),
+            display,
+        ))),
+        "Mesh" => {
+            let mut mesh = mesh(prim, &transform, display).map_err(|m| invalid(&m))?;
+            if mesh.orient() > 0 {
+                stage.reoriented.push(path.clone());
+            }
+            Some(Shape::Mesh(mesh))
+        }
+        _ => None,
+    };
+    if let Some(shape) = shape {
+        let id = stage.scene.add(shape);
+        if let Some(handle) = material {
+            stage.scene.bind_material(id, handle);
+        }
+        stage.object_paths.push(path.clone());
+    }
+
+    match prim.type_name.as_str() {
+        "Sphere" | "Mesh" => {}
+        "Camera" => {
+            if stage.camera.is_none() {
+                stage.camera = Some(camera(prim, &transform));
+            }
+        }
+        "" | "Xform" | "Scope" => {}
+        _ => stage.skipped.push(path.clone()),
+    }
+
+    for child in prim.children.iter() {
+        build(child, &path, transform, material, stage)?;
+    }
+    Ok(())
+}
+
+/// Composes parent transform with transform authored by xformOps of prim.
+fn local_transform(prim: &Prim, parent: Transform) -> Result<Transform, String> {
+    let order = match prim.attributes.get("xformOpOrder") {
+        Some(Value::List(order)) => order,
+        _ => return Ok(parent),
+    };
+
+    let mut transform = parent;
+    for op in order.iter() {
+        let op = op.text().ok_or("invalid xformOpOrder")?;
+        if op == "!resetXformStack!" {
+            transform = Transform::identity();
+            continue;
+        }
+        if op.starts_with("!invert!") {
+            return Err(format!("inverted op {} is not supported", op));
+        }
+        let value = prim
+            .attributes
+            .get(op)
+            .ok_or(format!("missing value of {}", op))?;
+        let invalid = || format!("invalid value of {}", op);
+        let kind = op.strip_prefix("xformOp:").unwrap_or(op);
+        let local = match kind.split(':').next().unwrap_or_default() {
+            "translate" => {
+                let [x, y, z] = value.triple().ok_or_else(invalid)?;
+                Transform::translation(Vector3f::new(x, y, z))
+            }
+            "scale" => {
+                let [x, y, z] = value.triple().ok_or_else(invalid)?;
+                Transform::scaling(x, y, z)
+            }
+            "rotateX" => Transform::rotation(0, value.number().ok_or_else(invalid)?),
+            "rotateY" => Transform::rotation(1, value.number().ok_or_else(invalid)?),
+            "rotateZ" => Transform::rotation(2, value.number().ok_or_else(invalid)?),
+            "rotateXYZ" => {
+                let [x, y, z] = value.triple().ok_or_else(invalid)?;
+                Transform::rotation(2, z)
+                    .compose(&Transform::rotation(1, y))
+                    .compose(&Transform::rotation(0, x))
+            }
+            "transform" => {
+                // Row-major matrix acting on row vectors, last row is translation.
+                let rows = match value {
+                    Value::List(rows) if rows.len() == 4 => rows,
+                    _ => return Err(invalid()),
+                };
+                let row = |i: usize| match &rows[i] {
+                    Value::List(r) if r.len() == 4 => Some(Vector3f::new(
+                        r[0].number()?,
+                        r[1].number()?,
+                        r[2].number()?,
+                    )),
+                    _ => None,
+                };
+                Transform::new(
+                    [
+                        row(0).ok_or_else(invalid)?,
+                        row(1).ok_or_else(invalid)?,
+                        row(2).ok_or_else(invalid)?,
+                    ],
+                    row(3).ok_or_else(invalid)?,
+                )
+            }
+            _ => return Err(format!("op {} is not supported", op)),
+        };
+        transform = transform.compose(&local);
+    }
+    Ok(transform)
+}
+
+/// Converts mesh prim, triangulating polygons as fans. Normals (`normals` or `primvars:normals`) and surface
+/// coordinates (`primvars:st`) may be indexed and of any interpolation, by metadata or else inferred from their count.
+fn mesh(prim: &Prim, transform: &Transform, material: Material) -> Result<Mesh, String> {
+    let points: Vec<Point3f> = list(prim, "points")?
+        .iter()
+        .map(|v| {
+            v.triple()
+                .map(|[x, y, z]| transform.point(Point3f::new(x, y, z)))
+        })
+        .collect::<Option<_>>()
+        .ok_or("invalid points")?;
+    let counts = indices(prim, "faceVertexCounts")?.ok_or("missing faceVertexCounts")?;
+    let vertices = indices(prim, "faceVertexIndices")?.ok_or("missing faceVertexIndices")?;
+    if counts.iter().sum::<usize>() != vertices.len() {
+        return Err("faceVertexCounts do not match faceVertexIndices".to_string());
+    }
+    if vertices.iter().any(|&i| i >= points.len()) {
+        return Err("face vertex index out of range".to_string());
+    }
+
+    // Fan triangles with their face index and face-vertex positions of their corners. Mirroring transforms and
+    // left-handed orientation flip the winding.
+    let flip = (transform.determinant() < 0.0)
+        != (prim.attributes.get("orientation").and_then(Value::text) == Some("leftHanded"));
+    let mut corners = Vec::new();
+    let mut start = 0;
+    for (face, &count) in counts.iter().enumerate() {
+        if count < 3 {
+            return Err(format!("face {} has fewer than three vertices", face));
+        }
+        for i in 1..count - 1 {
+            let mut corner = [start, start + i, start + i + 1];
+            if flip {
+                corner.swap(1, 2);
+            }
+            corners.push((face, corner));
+        }
+        start += count;
+    }
+    if corners.is_empty() {
+        return Err("mesh has no faces".to_string());
+    }
+    let triangles = corners
+        .iter()
+        .map(|(_, c)| c.map(|fv| vertices[fv]))
+        .collect();
+    let point_count = points.len();
+    let mut mesh = Mesh::new(points, triangles, material);
+
+    // Attribute indices per triangle corner by interpolation.
+    type Attribute = (Vec<Value>, Vec<[usize; 3]>);
+    let attribute = |name: &str| -> Result<Option<Attribute>, String> {
+        let values = match prim.attributes.get(name) {
+            Some(Value::List(values)) => values.clone(),
+            Some(_) => return Err(format!("invalid {}", name)),
+            None => return Ok(None),
+        };
+        let remap = indices(prim, &format!("{}:indices", name))?;
+        let elements = remap.as_ref().map_or(values.len(), Vec::len);
+        let interpolation = match prim.interpolations.get(name) {
+            Some(interpolation) => interpolation.as_str(),
+            None if elements == point_count => "vertex",
+            None if elements == vertices.len() => "faceVarying",
+            None if elements == counts.len() => "uniform",
+            None => "constant",
+        };
+        let element = |face: usize, fv: usize| match interpolation {
+            "vertex" | "varying" => Ok(vertices[fv]),
+            "faceVarying" => Ok(fv),
+            "uniform" => Ok(face),
+            "constant" => Ok(0),
+            other => Err(format!("unsupported interpolation {} of {}", other, name)),
+        };
+        let mut result = Vec::new();
+        for (face, corner) in corners.iter() {
+            let mut indices = [0; 3];
+            for (index, &fv) in indices.iter_mut().zip(corner) {
+                let e = element(*face, fv)?;
+                *index = match &remap {
+                    Some(remap) => *remap.get(e).ok_or(format!("too few {}", name))?,
+                    None => e,
+                };
+                if *index >= values.len() {
+                    return Err(format!("too few {}", name));
+                }
+            }
+            result.push(indices);
+        }
+        Ok(Some((values, result)))
+    };
+    let normals = match attribute("primvars:normals")? {
+        Some(normals) => Some(normals),
+        None => attribute("normals")?,
+    };
+    if let Some((values, indices)) = normals {
+        let normals = values
+            .iter()
+            .map(|v| {
+                v.triple()
+                    .map(|[x, y, z]| transform.normal(Vector3f::new(x, y, z)))
+            })
+            .collect::<Option<_>>()
+            .ok_or("invalid normals")?;
+        mesh.set_normals(normals, indices);
+    }
+    if let Some((values, indices)) = attribute("primvars:st")? {
+        let uvs = values
+            .iter()
+            .map(|v| match v {
+                Value::List(uv) if uv.len() == 2 => Some(Uv::new(uv[0].number()?, uv[1].number()?)),
+                _ => None,
+            })
+            .collect::<Option<_>>()
+            .ok_or("invalid primvars:st")?;
+        mesh.set_uvs(uvs, indices);
+    }
+    Ok(mesh)
+}
+
+/// List attribute of prim, empty if missing.
+fn list<'a>(prim: &'a Prim, name: &str) -> Result<&'a [Value], String> {
+    match prim.attributes.get(name) {
+        Some(Value::List(values)) => Ok(values),
+        Some(_) => Err(format!("invalid {}", name)),
+        None => Ok(&[]),
+    }
+}
+
+/// Integer array attribute of prim as indices, none if missing.
+fn indices(prim: &Prim, name: &str) -> Result<Option<Vec<usize>>, String> {
+    if !prim.attributes.contains_key(name) {
+        return Ok(None);
+    }
+    list(prim, name)?
+        .iter()
+        .map(|v| match v.number() {
+            Some(x) if x >= 0.0 && x.fract() == 0.0 => Ok(x as usize),
+            _ => Err(format!("invalid {}", name)),
+        })
+        .collect::<Result<_, _>>()
+        .map(Some)
+}
+
+/// Converts camera prim looking down its local -z axis. Lens values are in tenths of scene units.
+fn camera(prim: &Prim, transform: &Transform) -> StageCamera {
+    let focal_length = prim.number("focalLength", 50.0);
+    let aperture = prim.number("verticalAperture", 15.2908);
+    let f_stop = prim.number("fStop", 0.0);
+    let focus_distance = prim.number("focusDistance", 0.0);
+
+    let look_from = transform.point(Point3f::default());
+    let direction = transform.vector(Vector3f::new(0.0, 0.0, -1.0)).normalize();
+    let (defocus_angle, focus_distance) = if f_stop > 0.0 && focus_distance > 0.0 {
+        let radius = focal_length / f_stop / 2.0 / 10.0;
+        (
+            2.0 * (radius / focus_distance).atan().to_degrees(),
+            focus_distance,
+        )
+    } else {
+        (0.0, 1.0)
+    };
+    StageCamera {
+        look_from,
+        look_at: look_from + direction,
+        view_up: transform.vector(Vector3f::new(0.0, 1.0, 0.0)).normalize(),
+        vfov: 2.0 * (aperture / (2.0 * focal_length)).atan().to_degrees(),
+        defocus_angle,
+        focus_distance,
+    }
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::{interval::Interval, ray::Ray, shape::Intersectable};
+
+    const STAGE: &str = r#"#usda 1.0
+(
+    defaultPrim = "World"
+    upAxis = "Y"
+)
+
+def Xform "World"
+{
+    def Scope "Looks"
+    {
+        def Material "Gold"
+        {
+            token outputs:surface.connect = </World/Looks/Gold/Surface.outputs:surface>
+
+            def Shader "Surface"
+            {
+                uniform token info:id = "UsdPreviewSurface"
+                color3f inputs:diffuseColor = (1, 0.8, 0.2)
+                float inputs:metallic = 1
+                float inputs:roughness = 0.25
+                token outputs:surface
+            }
+        }
+
+        def Material "Rubber"
+        {
+            custom uniform token eden:preset = "rubber"
+        }
+
+        def Material "Glass"
+        {
+            def Shader "Surface"
+            {
+                uniform token info:id = "UsdPreviewSurface"
+                float inputs:opacity = 0.1
+                float inputs:ior = 1.33
+            }
+        }
+    }
+
+    def Xform "Group" (
+        kind = "group"
+    )
+    {
+        double3 xformOp:translate = (10, 0, 0)
+        float3 xformOp:scale = (2, 2, 2)
+        uniform token[] xformOpOrder = ["xformOp:translate", "xformOp:scale"]
+        rel material:binding = </World/Looks/Gold>
+
+        def Sphere "Ball"
+        {
+            double radius = 0.5
+            double3 xformOp:translate = (0, 1, 0)
+            uniform token[] xformOpOrder = ["xformOp:translate"]
+        }
+
+        def Sphere "Bubble"
+        {
+            prepend rel material:binding = </World/Looks/Glass>
+        }
+    }
+
+    def Sphere "Tire"
+    {
+        rel material:binding = </World/Looks/Rubber>
+    }
+
+    def Sphere "Plain"
+    {
+        color3f[] primvars:displayColor = [(0, 0, 1)] (
+            interpolation = "constant"
+        )
+    }
+
+    def Mesh "Floor"
+    {
+        int[] faceVertexCounts = [4]
+        int[] faceVertexIndices = [0, 3, 2, 1]
+        point3f[] points = [(-1, 0, -1), (1, 0, -1), (1, 0, 1), (-1, 0, 1)]
+        normal3f[] normals = [(0, 1, 0)] (
+            interpolation = "constant"
+        )
+        texCoord2f[] primvars:st = [(0, 0), (1, 0), (1, 1), (0, 1)] (
+            interpolation = "faceVarying"
+        )
+        int[] primvars:st:indices = [0, 3, 2, 1]
+    }
+
+    def Cube "Box"
+    {
+    }
+
+    def Camera "Camera"
+    {
+        float focalLength = 50
+        float verticalAperture = 50
+        double3 xformOp:translate = (0, 0, 5)
+        float xformOp:rotateY = 90
+        uniform token[] xformOpOrder = ["xformOp:translate", "xformOp:rotateY"]
+    }
+}
+"#;
+
+    #[test]
+    fn parse() {
+        let stage = super::parse(STAGE, Orientation::Y_UP).unwrap();
+        let spheres: Vec<&Sphere> = stage
+            .scene()
+            .objects()
+            .iter()
+            .filter_map(|o| match o {
+                Shape::Sphere(s) => Some(s),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(spheres.len(), 4);
+
+        // Transform hierarchy.
+        assert_eq!(spheres[0].center(), Point3f::new(10.0, 2.0, 0.0));
+        assert_eq!(spheres[0].radius(), 1.0);
+        assert_eq!(spheres[1].center(), Point3f::new(10.0, 0.0, 0.0));
+        assert_eq!(spheres[1].radius(), 2.0);
+        assert_eq!(spheres[3].center(), Point3f::default());
+
+        // Materials.
+        let Material::Principled(gold) = *stage.scene().material(0) else {
+            panic!("preview surface imported as principled material");
+        };
+        assert_eq!(gold.base_color(), Color3f::new(1.0, 0.8, 0.2));
+        assert_eq!((gold.metallic(), gold.roughness()), (1.0, 0.25));
+        let Material::Principled(glass) = *stage.scene().material(1) else {
+            panic!("preview surface imported as principled material");
+        };
+        assert!((glass.transmission() - 0.9).abs() < 1e-6);
+        assert_eq!(glass.ior(), 1.33);
+        assert_eq!(
+            *stage.scene().material(2),
+            Material::preset("rubber").unwrap()
+        );
+        assert_eq!(
+            *stage.scene().material(3),
+            Material::Lambert(Lambert::new(Color3f::new(0.0, 0.0, 1.0)))
+        );
+        let paths: Vec<&str> = stage.materials().iter().map(|(p, _)| p.as_str()).collect();
+        assert_eq!(
+            paths,
+            [
+                "/World/Looks/Glass",
+                "/World/Looks/Gold",
+                "/World/Looks/Rubber"
+            ]
+        );
+        assert_eq!(&stage.materials()[1].1, stage.scene().material(0));
+        let library = stage.scene().library();
+        let gold = library.handle("/World/Looks/Gold").unwrap();
+        assert_eq!(stage.scene().material_binding(0), Some(gold));
+        assert_eq!(stage.scene().material_binding(3), None);
+
+        assert_eq!(stage.skipped(), ["/World/Box"]);
+        assert_eq!(stage.object_path(0), "/World/Group/Ball");
+        assert_eq!(stage.object_path(4), "/World/Floor");
+    }
+
+    #[test]
+    fn mesh() {
+        let stage = super::parse(STAGE, Orientation::Y_UP).unwrap();
+        let Shape::Mesh(floor) = &stage.scene().objects()[4] else {
+            panic!("mesh imported as triangle mesh");
+        };
+        assert_eq!(floor.triangles(), [[0, 3, 2], [0, 2, 1]]);
+        assert_eq!(floor.uvs().unwrap().1, [[0, 3, 2], [0, 2, 1]]);
+
+        // Hit from above, on the side of the authored normals.
+        let ray = Ray::new(Point3f::new(0.5, 1.0, -0.25), Vector3f::new(0.0, -1.0, 0.0));
+        let hit = floor
+            .intersect(ray, Interval::new(0.0, f32::INFINITY))
+            .unwrap();
+        assert!((hit.point - Point3f::new(0.5, 0.0, -0.25)).length() < 1e-6);
+        assert_eq!(hit.normal, Vector3f::new(0.0, 1.0, 0.0));
+        assert!((hit.uv.0 - 0.75).abs() < 1e-6 && (hit.uv.1 - 0.375).abs() < 1e-6);
+        assert_eq!(
+            *floor.material(),
+            Material::Lambert(Lambert::new(Color3f::new(0.18, 0.18, 0.18)))
+        );
+
+        // Inverted closed tetrahedron.
+        let tetrahedron = r#"#usda 1.0
+def Mesh "Tetrahedron"
+{
+    int[] faceVertexCounts = [3, 3, 3, 3]
+    int[] faceVertexIndices = [0, 1, 2, 0, 3, 1, 0, 2, 3, 1, 3, 2]
+    point3f[] points = [(0, 0, 0), (1, 0, 0), (0, 1, 0), (0, 0, 1)]
+}
+"#;
+        let stage = super::parse(tetrahedron, Orientation::Y_UP).unwrap();
+        assert_eq!(stage.reoriented(), ["/Tetrahedron"]);
+        let Shape::Mesh(mesh) = &stage.scene().objects()[0] else {
+            panic!("mesh imported as triangle mesh");
+        };
+        assert_eq!(mesh.triangles()[0], [0, 2, 1]);
+        assert!(super::parse(STAGE, Orientation::Y_UP)
+            .unwrap()
+            .reoriented()
+            .is_empty());
+
+        // Out of range indices.
+        let broken = STAGE.replace(
+            "[0, 3, 2, 1]\n        point3f",
+            "[0, 3, 2, 7]\n        point3f",
+        );
+        assert!(matches!(
+            super::parse(&broken, Orientation::Y_UP),
+            Err(ImportError::Invalid { .. })
+        ));
+    }
+
+    #[test]
+    fn camera() {
+        let camera = super::parse(STAGE, Orientation::Y_UP)
+            .unwrap()
+            .camera()
+            .unwrap();
+        assert_eq!(camera.look_from, Point3f::new(0.0, 0.0, 5.0));
+        assert!((camera.look_at - Point3f::new(-1.0, 0.0, 5.0)).length() < 1e-6);
+        assert!((camera.view_up - Vector3f::new(0.0, 1.0, 0.0)).length() < 1e-6);
+        assert!((camera.vfov - 53.130104).abs() < 1e-4);
+        assert_eq!(camera.defocus_angle, 0.0);
+    }
+
+    #[test]
+    fn transform() {
+        // Ops apply from last to first.
+        let rotated = r#"#usda 1.0
+def Sphere "A" {
+    float3 xformOp:rotateXYZ = (0, 0, 90)
+    double3 xformOp:translate = (1, 0, 0)
+    uniform token[] xformOpOrder = ["xformOp:rotateXYZ", "xformOp:translate"]
+}
+def Sphere "B" {
+    matrix4d xformOp:transform = ((0, 1, 0, 0), (-1, 0, 0, 0), (0, 0, 1, 0), (0, 0, 3, 1))
+    uniform token[] xformOpOrder = ["xformOp:transform"]
+}
+"#;
+        let stage = super::parse(rotated, Orientation::Y_UP).unwrap();
+        let centers: Vec<Point3f> = stage
+            .scene()
+            .objects()
+            .iter()
+            .filter_map(|o| match o {
+                Shape::Sphere(s) => Some(s.center()),
+                _ => None,
+            })
+            .collect();
+        assert!((centers[0] - Point3f::new(0.0, 1.0, 0.0)).length() < 1e-6);
+        assert_eq!(centers[1], Point3f::new(0.0, 0.0, 3.0));
+    }
+
+    #[test]
+    fn orientation() {
+        // Z-up stage converted to y-up scene, kept in z-up scene.
+        let z_up = r#"#usda 1.0
+(
+    upAxis = "Z"
+    customLayerData = {
+        string upAxis = "Y"
+    }
+)
+def Sphere "A" {
+    double3 xformOp:translate = (1, 2, 3)
+    uniform token[] xformOpOrder = ["xformOp:translate"]
+}
+"#;
+        let center = |stage: &Stage| match &stage.scene().objects()[0] {
+            Shape::Sphere(s) => s.center(),
+            _ => unreachable!(),
+        };
+        let stage = super::parse(z_up, Orientation::Y_UP).unwrap();
+        assert_eq!(stage.orientation(), Orientation::Z_UP);
+        assert_eq!(stage.scene().orientation(), Orientation::Y_UP);
+        assert_eq!(center(&stage), Point3f::new(1.0, 3.0, -2.0));
+        let stage = super::parse(z_up, Orientation::Z_UP).unwrap();
+        assert_eq!(center(&stage), Point3f::new(1.0, 2.0, 3.0));
+
+        // Default y-up stage converted to z-up scene, unknown axes rejected.
+        let camera = super::parse(STAGE, Orientation::Z_UP)
+            .unwrap()
+            .camera()
+            .unwrap();
+        assert!((camera.view_up - Vector3f::new(0.0, 0.0, 1.0)).length() < 1e-6);
+        assert!(matches!(
+            super::parse("#usda 1.0\n(\n    upAxis = \"X\"\n)\n", Orientation::Y_UP),
+            Err(ImportError::Invalid { .. })
+        ));
+    }
+
+    #[test]
+    fn expressions() {
+        let animated = r#"#usda 1.0
+def Sphere "Ball" {
+    double radius = "0.2 + 0.1*sin(frame/24)"
+    double3 xformOp:translate = (0, "1 + t", 0)
+    uniform token[] xformOpOrder = ["xformOp:translate"]
+    color3f[] primvars:displayColor = ["lerp(red, blue, t)"]
+}
+"#;
+        let sphere = |frame| {
+            let stage = super::parse_frame(animated, frame, Orientation::Y_UP).unwrap();
+            assert!(stage.is_animated());
+            match &stage.scene().objects()[0] {
+                Shape::Sphere(s) => s.clone(),
+                _ => unreachable!(),
+            }
+        };
+        let first = sphere(0);
+        assert!((first.radius() - 0.2).abs() < 1e-6);
+        assert_eq!(first.center(), Point3f::new(0.0, 1.0, 0.0));
+        let later = sphere(12);
+        assert!((later.radius() - (0.2 + 0.1 * 0.5f32.sin())).abs() < 1e-6);
+        assert_eq!(later.center(), Point3f::new(0.0, 1.5, 0.0));
+        assert_eq!(
+            *later.material(),
+            Material::Lambert(Lambert::new(Color3f::new(0.5, 0.0, 0.5)))
+        );
+        assert!(!super::parse(STAGE, Orientation::Y_UP)
+            .unwrap()
+            .is_animated());
+
+        // Malformed and mistyped expressions.
+        assert!(matches!(
+            super::parse(
+                "#usda 1.0\ndef Sphere \"A\" {\n    double radius = \"1 +\"\n}",
+                Orientation::Y_UP
+            ),
+            Err(ImportError::Syntax { line: 3, .. })
+        ));
+        assert!(matches!(
+            super::parse(
+                "#usda 1.0\ndef Sphere \"A\" {\n    double radius = \"sin(red)\"\n}",
+                Orientation::Y_UP
+            ),
+            Err(ImportError::Invalid { .. })
+        ));
+    }
+
+    #[test]
+    fn errors() {
+        assert!(matches!(
+            super::parse("def Sphere \"A\" {}", Orientation::Y_UP),
+            Err(ImportError::Syntax { line: 1, .. })
+        ));
+        assert!(matches!(
+            super::parse(
+                "#usda 1.0\n\ndef Sphere \"A\" {\n    double radius = (1, 2\n}",
+                Orientation::Y_UP
+            ),
+            Err(ImportError::Syntax { line: 5, .. })
+        ));
+        assert!(matches!(
+            super::parse(
+                "#usda 1.0\ndef Sphere \"A\" {\n    rel material:binding = </Missing>\n}",
+                Orientation::Y_UP
+            ),
+            Err(ImportError::Invalid { .. })
+        ));
+        assert!(matches!(
+            super::parse(
+                "#usda 1.0\ndef Material \"M\" {\n    token eden:preset = \"cheese\"\n}",
+                Orientation::Y_UP
+            ),
+            Err(ImportError::Invalid { .. })
+        ));
+    }
+}
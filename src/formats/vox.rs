@@ -0,0 +1,340 @@
+use crate::{
+    base::{
+        color::Color3f, material::Material, orientation::Orientation, point::Point3f,
+        vector::Vector3f,
+    },
+    materials::{
+        dielectric::Dielectric, diffuse_light::DiffuseLight, lambert::Lambert,
+        microfacet::Microfacet,
+    },
+    shapes::voxels::Voxels,
+    tonemap::srgb_decode,
+};
+use std::{collections::HashMap, error::Error, fmt, fs, io, path::Path};
+
+/// Error while importing MagicaVoxel model.
+#[derive(Debug)]
+pub enum ImportError {
+    /// File could not be read.
+    Io(io::Error),
+
+    /// Data is no valid .vox model.
+    Invalid(String),
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::Io(e) => write!(f, "{}", e),
+            ImportError::Invalid(message) => write!(f, "invalid vox file: {}", message),
+        }
+    }
+}
+
+impl Error for ImportError {}
+
+impl From<io::Error> for ImportError {
+    fn from(e: io::Error) -> Self {
+        ImportError::Io(e)
+    }
+}
+
+/// Imports MagicaVoxel .vox file at path, see `parse`.
+pub fn import(
+    path: &Path,
+    voxel_size: f32,
+    orientation: Orientation,
+) -> Result<Voxels, ImportError> {
+    parse(&fs::read(path)?, voxel_size, orientation)
+}
+
+/// Parses MagicaVoxel .vox data into voxel grid with given voxel size, converted from MagicaVoxel's z-up axes to
+/// orientation, e.g. of the scene. The grid rests on the ground plane through the origin and is centered on the up
+/// axis. Only the first model of multi-model files is read.
+///
+/// Palette colors become lambert materials, unless material chunks turn them into metals (roughness as fuzz), glass
+/// (refractive index `_ri`) or lights (emission scaled by `_emit`). Files without palette use light gray.
+pub fn parse(
+    data: &[u8],
+    voxel_size: f32,
+    orientation: Orientation,
+) -> Result<Voxels, ImportError> {
+    let mut reader = Reader { data, position: 0 };
+    if reader.bytes(4)? != b"VOX " {
+        return Err(ImportError::Invalid("missing VOX header".to_string()));
+    }
+    reader.u32()?; // Version.
+    let main = reader.chunk()?;
+    if main.id != b"MAIN" {
+        return Err(ImportError::Invalid("missing MAIN chunk".to_string()));
+    }
+
+    // Child chunks of main chunk.
+    let mut reader = Reader {
+        data: main.children,
+        position: 0,
+    };
+    let mut size = None;
+    let mut voxels: Option<Vec<[u8; 4]>> = None;
+    let mut colors = [Color3f::new(0.75, 0.75, 0.75); 256];
+    let mut properties: HashMap<usize, HashMap<String, String>> = HashMap::new();
+    while reader.position < reader.data.len() {
+        let chunk = reader.chunk()?;
+        let mut content = Reader {
+            data: chunk.content,
+            position: 0,
+        };
+        match chunk.id {
+            b"SIZE" if size.is_none() => {
+                size = Some([content.u32()?, content.u32()?, content.u32()?]);
+            }
+            b"XYZI" if voxels.is_none() => {
+                let count = content.u32()? as usize;
+                let data = content.bytes(4 * count)?;
+                voxels = Some(data.chunks(4).map(|v| [v[0], v[1], v[2], v[3]]).collect());
+            }
+            b"RGBA" => {
+                // Entry i holds color of palette index i + 1.
+                for (i, rgba) in content.bytes(4 * 255)?.chunks(4).enumerate() {
+                    let c = |x: u8| srgb_decode(x as f32 / 255.0);
+                    colors[i + 1] = Color3f::new(c(rgba[0]), c(rgba[1]), c(rgba[2]));
+                }
+            }
+            b"MATL" => {
+                let index = content.u32()? as usize;
+                properties.insert(index, content.dictionary()?);
+            }
+            _ => {}
+        }
+    }
+    let (Some(size), Some(voxels)) = (size, voxels) else {
+        return Err(ImportError::Invalid("no voxel model".to_string()));
+    };
+    if voxels.is_empty() {
+        return Err(ImportError::Invalid("empty voxel model".to_string()));
+    }
+
+    // Materials per palette index.
+    let palette = (0..256)
+        .map(|i| {
+            let color = colors[i];
+            let Some(properties) = properties.get(&i) else {
+                return Material::Lambert(Lambert::new(color));
+            };
+            let number = |key: &str, default: f32| {
+                properties
+                    .get(key)
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(default)
+            };
+            match properties.get("_type").map(String::as_str) {
+                Some("_metal") => {
+                    Material::Microfacet(Microfacet::metal(color, number("_rough", 0.0)))
+                }
+                Some("_glass") => Material::Dielectric(Dielectric::new(number("_ri", 1.5))),
+                Some("_emit") => {
+                    Material::DiffuseLight(DiffuseLight::new(color * number("_emit", 1.0)))
+                }
+                _ => Material::Lambert(Lambert::new(color)),
+            }
+        })
+        .collect();
+
+    // Voxel centers converted to orientation, shifted to positive grid positions.
+    let conversion = Orientation::Z_UP.conversion(orientation);
+    let extent = conversion.vector(Vector3f::new(
+        size[0] as f32,
+        size[1] as f32,
+        size[2] as f32,
+    ));
+    let corner = Vector3f::new(
+        extent.x().min(0.0),
+        extent.y().min(0.0),
+        extent.z().min(0.0),
+    );
+    let mut grid = Vec::with_capacity(voxels.len());
+    for [x, y, z, index] in voxels {
+        let (x, y, z) = (x as u32, y as u32, z as u32);
+        if x >= size[0] || y >= size[1] || z >= size[2] {
+            return Err(ImportError::Invalid(format!(
+                "voxel {} {} {} outside model",
+                x, y, z
+            )));
+        }
+        let center = Vector3f::new(x as f32 + 0.5, y as f32 + 0.5, z as f32 + 0.5);
+        let position = conversion.vector(center) - corner;
+        grid.push((
+            [position.x(), position.y(), position.z()].map(|p| p as u32),
+            index,
+        ));
+    }
+
+    // Centered on up axis, resting on ground plane.
+    let extent = Vector3f::new(extent.x().abs(), extent.y().abs(), extent.z().abs());
+    let up = orientation.up();
+    let origin = Point3f::default() - 0.5 * voxel_size * (extent - extent.dot(&up) * up);
+    Ok(Voxels::new(origin, voxel_size, palette, &grid))
+}
+
+/// Chunk of .vox data.
+struct Chunk<'a> {
+    /// Four-character chunk id.
+    id: &'a [u8],
+
+    /// Content data.
+    content: &'a [u8],
+
+    /// Data of child chunks.
+    children: &'a [u8],
+}
+
+/// Cursor over little-endian binary data.
+struct Reader<'a> {
+    /// Data read.
+    data: &'a [u8],
+
+    /// Position of next byte.
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    /// Reads given number of bytes.
+    fn bytes(&mut self, count: usize) -> Result<&'a [u8], ImportError> {
+        let bytes = self
+            .data
+            .get(self.position..self.position.saturating_add(count))
+            .ok_or_else(|| ImportError::Invalid("unexpected end of data".to_string()))?;
+        self.position += count;
+        Ok(bytes)
+    }
+
+    /// Reads unsigned 32-bit integer.
+    fn u32(&mut self) -> Result<u32, ImportError> {
+        let bytes = self.bytes(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    /// Reads chunk with its content and children data.
+    fn chunk(&mut self) -> Result<Chunk<'a>, ImportError> {
+        let id = self.bytes(4)?;
+        let (content, children) = (self.u32()? as usize, self.u32()? as usize);
+        Ok(Chunk {
+            id,
+            content: self.bytes(content)?,
+            children: self.bytes(children)?,
+        })
+    }
+
+    /// Reads dictionary of length-prefixed key and value strings.
+    fn dictionary(&mut self) -> Result<HashMap<String, String>, ImportError> {
+        let count = self.u32()?;
+        let mut string = || -> Result<String, ImportError> {
+            let length = self.u32()? as usize;
+            Ok(String::from_utf8_lossy(self.bytes(length)?).into_owned())
+        };
+        (0..count).map(|_| Ok((string()?, string()?))).collect()
+    }
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Chunk with id, content and children.
+    fn chunk(id: &[u8], content: &[u8], children: &[u8]) -> Vec<u8> {
+        let mut chunk = id.to_vec();
+        chunk.extend((content.len() as u32).to_le_bytes());
+        chunk.extend((children.len() as u32).to_le_bytes());
+        chunk.extend(content);
+        chunk.extend(children);
+        chunk
+    }
+
+    /// File with main chunk holding given child chunks.
+    fn file(children: &[Vec<u8>]) -> Vec<u8> {
+        let mut file = b"VOX ".to_vec();
+        file.extend(150u32.to_le_bytes());
+        file.extend(chunk(b"MAIN", &[], &children.concat()));
+        file
+    }
+
+    /// Model of 2x3x4 voxels with voxels at (0,0,0) in palette index 1 and (1,2,3) in palette index 2.
+    fn model() -> Vec<Vec<u8>> {
+        let size: Vec<u8> = [2u32, 3, 4].iter().flat_map(|x| x.to_le_bytes()).collect();
+        let mut xyzi = 2u32.to_le_bytes().to_vec();
+        xyzi.extend([0, 0, 0, 1, 1, 2, 3, 2]);
+        vec![chunk(b"SIZE", &size, &[]), chunk(b"XYZI", &xyzi, &[])]
+    }
+
+    /// Material chunk for palette index with key-value pairs.
+    fn matl(index: u32, properties: &[(&str, &str)]) -> Vec<u8> {
+        let mut content = index.to_le_bytes().to_vec();
+        content.extend((properties.len() as u32).to_le_bytes());
+        for s in properties.iter().flat_map(|(k, v)| [k, v]) {
+            content.extend((s.len() as u32).to_le_bytes());
+            content.extend(s.as_bytes());
+        }
+        chunk(b"MATL", &content, &[])
+    }
+
+    #[test]
+    fn parse() {
+        // Palette with red at index 1 and white at index 2, index 2 metal.
+        let mut rgba = vec![0; 1024];
+        rgba[..4].copy_from_slice(&[255, 0, 0, 255]);
+        rgba[4..8].copy_from_slice(&[255, 255, 255, 255]);
+        let mut chunks = model();
+        chunks.push(chunk(b"RGBA", &rgba, &[]));
+        chunks.push(matl(2, &[("_type", "_metal"), ("_rough", "0.25")]));
+        chunks.push(chunk(b"nTRN", &[1, 2, 3], &[]));
+        let voxels = super::parse(&file(&chunks), 0.5, Orientation::Y_UP).unwrap();
+
+        // Z-up turned into y-up, centered on y-axis.
+        assert_eq!(voxels.count(), 2);
+        assert_eq!(voxels.size(), [2, 4, 3]);
+        assert_eq!(voxels.origin(), Point3f::new(-0.5, 0.0, -0.75));
+        let red = Color3f::new(1.0, 0.0, 0.0);
+        assert_eq!(
+            voxels.voxel([0, 0, 2]),
+            Some(&Material::Lambert(Lambert::new(red)))
+        );
+        assert_eq!(
+            voxels.voxel([1, 3, 0]),
+            Some(&Material::Microfacet(Microfacet::metal(
+                Color3f::white(),
+                0.25
+            )))
+        );
+
+        // Default palette.
+        let gray = super::parse(&file(&model()), 1.0, Orientation::Y_UP).unwrap();
+        assert!(matches!(gray.voxel([0, 0, 2]), Some(Material::Lambert(_))));
+
+        // Z-up keeps axes, centered on z-axis.
+        let z_up = super::parse(&file(&model()), 1.0, Orientation::Z_UP).unwrap();
+        assert_eq!(z_up.size(), [2, 3, 4]);
+        assert_eq!(z_up.origin(), Point3f::new(-1.0, -1.5, 0.0));
+        assert!(z_up.voxel([1, 2, 3]).is_some());
+    }
+
+    #[test]
+    fn errors() {
+        let invalid = |data: &[u8]| {
+            matches!(
+                super::parse(data, 1.0, Orientation::Y_UP),
+                Err(ImportError::Invalid(_))
+            )
+        };
+        assert!(invalid(b"RIFF"));
+        assert!(invalid(&file(&[])));
+        assert!(invalid(&file(&model()[..1])));
+        let mut truncated = file(&model());
+        truncated.pop();
+        assert!(invalid(&truncated));
+        assert!(matches!(
+            import(Path::new("missing.vox"), 1.0, Orientation::Y_UP),
+            Err(ImportError::Io(_))
+        ));
+    }
+}
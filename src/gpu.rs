@@ -0,0 +1,545 @@
+use crate::{
+    background::Background,
+    base::{color::Color3f, material::Material, orientation::Orientation, shape::Shape},
+    bvh::Bvh,
+    camera::{Camera, RayFrame},
+    film::Film,
+    integrator::Integrator,
+    materials::dielectric::Dispersion,
+    scene::Scene,
+    settings::RenderSettings,
+};
+use std::{
+    error::Error,
+    fmt,
+    future::Future,
+    sync::{mpsc, Arc},
+    task::{Context, Poll, Wake, Waker},
+    thread,
+};
+use wgpu::util::DeviceExt;
+
+/// Compute shader tracing paths.
+const SHADER: &str = include_str!("gpu.wgsl");
+
+/// Edge length of square workgroups of the shader, which runs one invocation per pixel.
+const WORKGROUP_SIZE: u32 = 8;
+
+/// Samples per pixel traced by one dispatch. Short dispatches keep the GPU responsive, and drivers may reset long
+/// running ones.
+const SAMPLES_PER_PASS: u32 = 4;
+
+/// Positions of first sample index and sample count of pass within shader parameters, in words.
+const FIRST_SAMPLE_WORD: usize = 11;
+const SAMPLES_WORD: usize = 15;
+
+/// Max count of spheres per leaf of hierarchy uploaded to the GPU.
+const MAX_LEAF_SIZE: usize = 4;
+
+/// Material kinds of shader.
+const LAMBERT: u32 = 0;
+const METAL: u32 = 1;
+const DIELECTRIC: u32 = 2;
+const DIFFUSE_LIGHT: u32 = 3;
+const MICROFACET: u32 = 4;
+
+/// Error of setting up GPU or rendering on it.
+#[derive(Debug)]
+pub enum GpuError {
+    /// No GPU adapter available, e.g. for missing drivers.
+    NoAdapter,
+
+    /// GPU device could not be created or failed.
+    Device(String),
+
+    /// Scene or camera use a feature the GPU renderer does not mirror.
+    Unsupported(String),
+}
+
+impl fmt::Display for GpuError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GpuError::NoAdapter => write!(f, "no GPU adapter available"),
+            GpuError::Device(message) => write!(f, "GPU device error: {}", message),
+            GpuError::Unsupported(feature) => write!(f, "not supported on GPU: {}", feature),
+        }
+    }
+}
+
+impl Error for GpuError {}
+
+/// Path tracer running as WGSL compute shader on the GPU via wgpu. Mirrors `PathTracer` for scenes of spheres with
+/// lambert, metal, dielectric, microfacet and diffuse light materials under sky, uniform or gradient backgrounds.
+/// Paths are traced without explicit light sampling, which converges to the same image, and random values are
+/// independent of the camera's sampler. The CPU renderer stays the reference, and renders all other scenes.
+pub struct GpuRenderer {
+    /// Logical device.
+    device: wgpu::Device,
+
+    /// Queue of device.
+    queue: wgpu::Queue,
+
+    /// Compiled shader.
+    pipeline: wgpu::ComputePipeline,
+
+    /// Name of adapter.
+    adapter_name: String,
+}
+
+impl GpuRenderer {
+    /// Creates renderer on the default adapter, preferring high performance GPUs.
+    pub fn new() -> Result<Self, GpuError> {
+        let instance = wgpu::Instance::default();
+        let options = wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            ..Default::default()
+        };
+        let adapter =
+            block_on(instance.request_adapter(&options)).map_err(|_| GpuError::NoAdapter)?;
+        let (device, queue) = block_on(adapter.request_device(&wgpu::DeviceDescriptor::default()))
+            .map_err(|e| GpuError::Device(e.to_string()))?;
+        let scope = device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("path tracer"),
+            source: wgpu::ShaderSource::Wgsl(SHADER.into()),
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("path tracer"),
+            layout: None,
+            module: &module,
+            entry_point: Some("main"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+        if let Some(e) = block_on(scope.pop()) {
+            return Err(GpuError::Device(e.to_string()));
+        }
+        Ok(GpuRenderer {
+            device,
+            queue,
+            pipeline,
+            adapter_name: adapter.get_info().name,
+        })
+    }
+
+    /// Name of GPU adapter.
+    pub fn adapter_name(&self) -> &str {
+        &self.adapter_name
+    }
+
+    /// Renders scene with camera and settings, like `Camera::render`. Scene and bounding volume hierarchy are
+    /// uploaded, samples are traced in passes adding to an accumulation buffer, which is read back into the film.
+    /// Fails if scene, camera or settings use features the GPU renderer does not mirror.
+    pub fn render(
+        &self,
+        camera: &mut Camera,
+        scene: &Scene,
+        settings: &RenderSettings,
+    ) -> Result<Film, GpuError> {
+        let buffers = Buffers::new(camera, scene, settings)?;
+        let (width, height) = camera.resolution();
+        let samples_per_pixel = settings.samples_per_pixel();
+
+        // Upload scene, zeroed accumulation buffer and its copy for reading back.
+        let storage = |label, words: &[u32], usage| {
+            self.device
+                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some(label),
+                    contents: &bytes(words),
+                    usage,
+                })
+        };
+        let params = storage(
+            "params",
+            &buffers.params,
+            wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        );
+        let nodes = storage("nodes", &buffers.nodes, wgpu::BufferUsages::STORAGE);
+        let spheres = storage("spheres", &buffers.spheres, wgpu::BufferUsages::STORAGE);
+        let size = (width * height) as u64 * 16;
+        let accumulation = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("accumulation"),
+            size,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let readback = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("readback"),
+            size,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("path tracer"),
+            layout: &self.pipeline.get_bind_group_layout(0),
+            entries: &[(0, &params), (1, &nodes), (2, &spheres), (3, &accumulation)].map(
+                |(binding, buffer)| wgpu::BindGroupEntry {
+                    binding,
+                    resource: buffer.as_entire_binding(),
+                },
+            ),
+        });
+
+        // Trace passes, each one after updating its sample range.
+        for first_sample in (0..samples_per_pixel).step_by(SAMPLES_PER_PASS as usize) {
+            let samples = SAMPLES_PER_PASS.min(samples_per_pixel - first_sample);
+            for (word, value) in [(FIRST_SAMPLE_WORD, first_sample), (SAMPLES_WORD, samples)] {
+                self.queue
+                    .write_buffer(&params, (word * 4) as u64, &bytes(&[value]));
+            }
+            let mut encoder = self.device.create_command_encoder(&Default::default());
+            {
+                let mut pass = encoder.begin_compute_pass(&Default::default());
+                pass.set_pipeline(&self.pipeline);
+                pass.set_bind_group(0, &bind_group, &[]);
+                pass.dispatch_workgroups(
+                    width.div_ceil(WORKGROUP_SIZE),
+                    height.div_ceil(WORKGROUP_SIZE),
+                    1,
+                );
+            }
+            self.queue.submit([encoder.finish()]);
+        }
+
+        // Read back accumulated sums.
+        let mut encoder = self.device.create_command_encoder(&Default::default());
+        encoder.copy_buffer_to_buffer(&accumulation, 0, &readback, 0, size);
+        self.queue.submit([encoder.finish()]);
+        let (sender, receiver) = mpsc::channel();
+        readback.map_async(wgpu::MapMode::Read, .., move |result| {
+            let _ = sender.send(result);
+        });
+        self.device
+            .poll(wgpu::PollType::wait_indefinitely())
+            .map_err(|e| GpuError::Device(e.to_string()))?;
+        receiver
+            .recv()
+            .map_err(|e| GpuError::Device(e.to_string()))?
+            .map_err(|e| GpuError::Device(e.to_string()))?;
+
+        // Average samples.
+        let mut film = Film::new(width, height, settings.film_precision());
+        {
+            let view = readback.get_mapped_range(..);
+            let sums = view
+                .chunks_exact(4)
+                .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]));
+            let sums: Vec<f32> = sums.collect();
+            for (i, sum) in sums.chunks_exact(4).enumerate() {
+                let (x, y) = (i as u32 % width, i as u32 / width);
+                let color = Color3f::new(sum[0], sum[1], sum[2]) / samples_per_pixel as f32;
+                film.set_pixel(x, y, color);
+            }
+        }
+        readback.unmap();
+        Ok(film)
+    }
+}
+
+/// Shader parameters and scene in the buffer layouts of the shader, as 32-bit words.
+#[derive(Clone, Debug, PartialEq)]
+struct Buffers {
+    /// Camera, background and render settings.
+    params: Vec<u32>,
+
+    /// Nodes of hierarchy, eight words each.
+    nodes: Vec<u32>,
+
+    /// Spheres with materials in order of leaves, twelve words each.
+    spheres: Vec<u32>,
+}
+
+impl Buffers {
+    /// Packs camera, render settings and scene, or fails if they use unsupported features.
+    fn new(
+        camera: &mut Camera,
+        scene: &Scene,
+        settings: &RenderSettings,
+    ) -> Result<Self, GpuError> {
+        let unsupported = |feature: &str| Err(GpuError::Unsupported(feature.to_string()));
+        let Integrator::PathTracer(path_tracer) = settings.integrator() else {
+            return unsupported("integrators other than path tracer");
+        };
+        if path_tracer.max_radiance().is_finite() {
+            return unsupported("max radiance of path tracer");
+        }
+        if settings.max_sample_luminance().is_finite() || settings.outlier_rejection().is_some() {
+            return unsupported("sample clamping and outlier rejection");
+        }
+        if !scene.analytic_lights().is_empty() {
+            return unsupported("analytic lights");
+        }
+        if !scene.decals().is_empty() {
+            return unsupported("decals");
+        }
+        let (bottom, top) = match scene.background() {
+            Background::Sky => (Color3f::white(), Color3f::new(0.5, 0.7, 1.0)),
+            Background::Uniform(c) => (c, c),
+            Background::Gradient { bottom, top } => (bottom, top),
+            Background::PhysicalSky(_) => return unsupported("physical sky"),
+        };
+        let intensity = scene.environment_intensity();
+        let (bottom, top) = (bottom * intensity, top * intensity);
+        let Some(frame) = camera.ray_frame(settings) else {
+            return unsupported("priority regions, orthographic projection or polygonal aperture");
+        };
+
+        // Spheres in order of leaves, so leaves refer to ranges of them.
+        let objects = scene.objects();
+        let bvh = Bvh::build(objects, MAX_LEAF_SIZE, false);
+        let mut spheres = Vec::with_capacity(objects.len() * 12);
+        for &index in bvh.indices() {
+            let Shape::Sphere(sphere) = &objects[index] else {
+                return unsupported(&format!("shape of object {}", index));
+            };
+            if sphere.is_moving() {
+                return unsupported(&format!("motion of object {}", index));
+            }
+            if let Material::Dielectric(d) = scene.material(index) {
+                if d.dispersion() != Dispersion::None {
+                    return unsupported(&format!("dispersion of object {}", index));
+                }
+            }
+            let center = sphere.center();
+            let (kind, color, absorption, parameter) = match scene.material(index) {
+                Material::Lambert(l) => (LAMBERT, l.albedo(), Color3f::black(), 0.0),
+                Material::Metal(m) => (METAL, m.albedo(), Color3f::black(), m.fuzz()),
+                Material::Dielectric(d) => (DIELECTRIC, Color3f::white(), d.absorption(), d.ior()),
+                Material::DiffuseLight(d) => (DIFFUSE_LIGHT, d.emission(), Color3f::black(), 0.0),
+                Material::Microfacet(m) => {
+                    let ior = Color3f::new(m.ior().unwrap_or(0.0), 0.0, 0.0);
+                    (MICROFACET, m.albedo(), ior, m.alpha())
+                }
+                _ => return unsupported(&format!("material of object {}", index)),
+            };
+            push(
+                &mut spheres,
+                [center.x(), center.y(), center.z()],
+                sphere.radius().to_bits(),
+            );
+            push(&mut spheres, [color.r(), color.g(), color.b()], kind);
+            push(
+                &mut spheres,
+                [absorption.r(), absorption.g(), absorption.b()],
+                parameter.to_bits(),
+            );
+        }
+        let mut nodes = Vec::new();
+        for (bounds, a, b) in bvh.flat_nodes() {
+            let (min, max) = (bounds.min(), bounds.max());
+            push(&mut nodes, [min.x(), min.y(), min.z()], a as u32);
+            push(&mut nodes, [max.x(), max.y(), max.z()], b as u32);
+        }
+        let node_count = (nodes.len() / 8) as u32;
+
+        // Storage buffers hold at least one element.
+        spheres.resize(spheres.len().max(12), 0);
+        nodes.resize(nodes.len().max(8), 0);
+
+        // Up direction of background, as the y axis of the scene in y-up coordinates.
+        let axes = scene.orientation().conversion(Orientation::Y_UP).axes();
+        let up = [axes[0].y(), axes[1].y(), axes[2].y()];
+        let seed = settings.seed() as u32 ^ (settings.seed() >> 32) as u32;
+        let max_depth = path_tracer.max_depth();
+        Ok(Buffers {
+            params: params(
+                &frame,
+                camera,
+                node_count,
+                max_depth,
+                seed,
+                (bottom, top),
+                up,
+            ),
+            nodes,
+            spheres,
+        })
+    }
+}
+
+/// Shader parameters, without sample range.
+fn params(
+    frame: &RayFrame,
+    camera: &Camera,
+    node_count: u32,
+    max_depth: u32,
+    seed: u32,
+    (bottom, top): (Color3f, Color3f),
+    up: [f32; 3],
+) -> Vec<u32> {
+    let (width, height) = camera.resolution();
+    let p = frame.pixel00_location;
+    let (u, v) = (frame.pixel_delta_u, frame.pixel_delta_v);
+    let (du, dv) = (frame.defocus_disk_u, frame.defocus_disk_v);
+    let o = frame.look_from;
+    let mut words = Vec::with_capacity(36);
+    push(&mut words, [p.x(), p.y(), p.z()], width);
+    push(&mut words, [u.x(), u.y(), u.z()], height);
+    push(&mut words, [v.x(), v.y(), v.z()], 0);
+    push(&mut words, [du.x(), du.y(), du.z()], 0);
+    push(&mut words, [dv.x(), dv.y(), dv.z()], max_depth);
+    push(&mut words, [o.x(), o.y(), o.z()], node_count);
+    push(&mut words, [bottom.r(), bottom.g(), bottom.b()], seed);
+    push(&mut words, [top.r(), top.g(), top.b()], 0);
+    push(&mut words, up, 0);
+    words
+}
+
+/// Appends vector and word, the layout of a `vec3<f32>` followed by a 32-bit scalar in the shader.
+fn push(words: &mut Vec<u32>, v: [f32; 3], w: u32) {
+    words.extend(v.map(f32::to_bits));
+    words.push(w);
+}
+
+/// Little endian bytes of words, the byte order of GPU buffers.
+fn bytes(words: &[u32]) -> Vec<u8> {
+    words.iter().flat_map(|w| w.to_le_bytes()).collect()
+}
+
+/// Waker unparking the thread blocked on a future.
+struct ThreadWaker(thread::Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// Minimal executor polling future on current thread until ready, for the requests of wgpu.
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = std::pin::pin!(future);
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        base::{light::Light, point::Point3f, vector::Vector3f},
+        integrators::path_tracer::PathTracer,
+        lights::point::PointLight,
+        materials::{
+            dielectric::Dielectric, diffuse_light::DiffuseLight, lambert::Lambert, metal::Metal,
+            microfacet::Microfacet,
+        },
+        shapes::{sphere::Sphere, torus::Torus},
+    };
+
+    /// Scene of grey ground, metal, rough metal and glass spheres lit by sky, camera looking at it and settings.
+    fn scene() -> (Scene, Camera, RenderSettings) {
+        let mut scene = Scene::new();
+        let grey = Material::Lambert(Lambert::new(Color3f::new(0.5, 0.5, 0.5)));
+        let metal = Material::Metal(Metal::new(Color3f::new(0.8, 0.6, 0.2), 0.1));
+        scene.add(Shape::Sphere(Sphere::new(
+            Point3f::new(0.0, -100.5, 0.0),
+            100.0,
+            grey,
+        )));
+        scene.add(Shape::Sphere(Sphere::new(Point3f::default(), 0.5, metal)));
+        let mut glass = Dielectric::new(1.5);
+        glass.set_absorption(Color3f::new(0.5, 0.1, 0.1));
+        scene.add(Shape::Sphere(Sphere::new(
+            Point3f::new(1.0, 0.0, 0.5),
+            0.4,
+            Material::Dielectric(glass),
+        )));
+        let rough = Microfacet::metal(Color3f::new(0.9, 0.9, 0.9), 0.4);
+        scene.add(Shape::Sphere(Sphere::new(
+            Point3f::new(-1.0, 0.0, 0.5),
+            0.4,
+            Material::Microfacet(rough),
+        )));
+        let mut camera = Camera::new(16, 8);
+        camera.set_look_from(Point3f::new(0.0, 0.5, 3.0));
+        let mut settings = RenderSettings::default();
+        settings.set_integrator(Integrator::PathTracer(PathTracer::new(8)));
+        (scene, camera, settings)
+    }
+
+    #[test]
+    fn buffers() {
+        let (scene, mut camera, settings) = scene();
+        let buffers = Buffers::new(&mut camera, &scene, &settings).unwrap();
+        assert_eq!(buffers.params.len(), 36);
+        assert_eq!(buffers.params[3], 16);
+        assert_eq!(buffers.params[7], 8);
+        assert_eq!(buffers.params[19], 8);
+
+        // Single leaf with all spheres, metal kind and fuzz in order of leaf.
+        assert_eq!(buffers.nodes.len(), 8);
+        assert_eq!((buffers.nodes[3], buffers.nodes[7]), (0, 4));
+        assert_eq!(buffers.spheres.len(), 48);
+        let metal = buffers.spheres.chunks(12).find(|s| s[7] == METAL).unwrap();
+        assert_eq!(f32::from_bits(metal[3]), 0.5);
+        assert_eq!(f32::from_bits(metal[11]), 0.1);
+
+        // Empty scene keeps one element per buffer, but no nodes to traverse.
+        let buffers = Buffers::new(&mut camera, &Scene::new(), &settings).unwrap();
+        assert_eq!((buffers.nodes.len(), buffers.spheres.len()), (8, 12));
+        assert_eq!(buffers.params[23], 0);
+    }
+
+    #[test]
+    fn unsupported() {
+        let unsupported = |scene: &Scene, camera: &mut Camera, settings: &RenderSettings| {
+            matches!(
+                Buffers::new(camera, scene, settings),
+                Err(GpuError::Unsupported(_))
+            )
+        };
+        let (mut scene, mut camera, settings) = scene();
+        assert!(!unsupported(&scene, &mut camera, &settings));
+        scene.add(Shape::Torus(Torus::new(
+            Point3f::default(),
+            Vector3f::new(0.0, 1.0, 0.0),
+            1.0,
+            0.2,
+            Material::DiffuseLight(DiffuseLight::new(Color3f::white())),
+        )));
+        assert!(unsupported(&scene, &mut camera, &settings));
+
+        let (mut scene, mut camera, settings) = super::tests::scene();
+        scene.add_light(Light::Point(PointLight::new(
+            Point3f::default(),
+            Color3f::white(),
+        )));
+        assert!(unsupported(&scene, &mut camera, &settings));
+
+        let (scene, mut camera, mut settings) = super::tests::scene();
+        let mut path_tracer = PathTracer::new(8);
+        path_tracer.set_max_radiance(10.0);
+        settings.set_integrator(Integrator::PathTracer(path_tracer));
+        assert!(unsupported(&scene, &mut camera, &settings));
+    }
+
+    #[test]
+    fn render() {
+        // Matches CPU renderer on average, if a GPU adapter is available.
+        let Ok(renderer) = GpuRenderer::new() else {
+            return;
+        };
+        let (scene, mut camera, mut settings) = scene();
+        settings.set_samples_per_pixel(64);
+        let mean = |film: &Film| {
+            let sum = film.pixels().fold(Color3f::black(), |acc, c| acc + c);
+            sum / (film.width() * film.height()) as f32
+        };
+        let gpu = mean(&renderer.render(&mut camera, &scene, &settings).unwrap());
+        let cpu = mean(&camera.render(&scene, &settings));
+        let error = (gpu - cpu) / cpu;
+        assert!(
+            error.r().abs() < 0.05 && error.g().abs() < 0.05 && error.b().abs() < 0.05,
+            "GPU mean {:?} differs from CPU mean {:?}",
+            gpu,
+            cpu
+        );
+    }
+}
@@ -0,0 +1,315 @@
+// Path tracer for scenes of spheres, mirroring `PathTracer` without explicit light sampling. Each invocation traces
+// the samples of one pixel in the current pass and adds their sum to the accumulation buffer.
+
+struct Params {
+    pixel00_location: vec3<f32>,
+    width: u32,
+    pixel_delta_u: vec3<f32>,
+    height: u32,
+    pixel_delta_v: vec3<f32>,
+    first_sample: u32,
+    defocus_disk_u: vec3<f32>,
+    samples: u32,
+    defocus_disk_v: vec3<f32>,
+    max_depth: u32,
+    look_from: vec3<f32>,
+    node_count: u32,
+    bottom: vec3<f32>,
+    seed: u32,
+    top: vec3<f32>,
+    _padding0: u32,
+    up: vec3<f32>,
+    _padding1: u32,
+}
+
+// Node of bounding volume hierarchy: for leaves a is the first sphere and b the count of spheres, for interior nodes
+// a is the second child and b zero.
+struct Node {
+    min: vec3<f32>,
+    a: u32,
+    max: vec3<f32>,
+    b: u32,
+}
+
+// Sphere with material: color is albedo or emission, parameter is fuzz of metals, index of refraction of dielectrics
+// or GGX width of microfacet materials. Absorption is that of dielectrics, its x holds the index of refraction of
+// microfacet dielectrics, zero for microfacet metals.
+struct Sphere {
+    center: vec3<f32>,
+    radius: f32,
+    color: vec3<f32>,
+    kind: u32,
+    absorption: vec3<f32>,
+    parameter: f32,
+}
+
+// Nearest hit of ray, index is NONE if missed.
+struct Hit {
+    t: f32,
+    index: u32,
+}
+
+const LAMBERT: u32 = 0u;
+const METAL: u32 = 1u;
+const DIELECTRIC: u32 = 2u;
+const MICROFACET: u32 = 4u;
+const NONE: u32 = 0xffffffffu;
+const T_MIN: f32 = 0.001;
+const T_MAX: f32 = 3.4e38;
+const EPSILON: f32 = 1.1920929e-7;
+const PI: f32 = 3.14159265;
+const STACK_SIZE: u32 = 64u;
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> nodes: array<Node>;
+@group(0) @binding(2) var<storage, read> spheres: array<Sphere>;
+@group(0) @binding(3) var<storage, read_write> accumulation: array<vec4<f32>>;
+
+var<private> rng_state: u32;
+
+// PCG hash of value.
+fn pcg(value: u32) -> u32 {
+    let state = value * 747796405u + 2891336453u;
+    let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+    return (word >> 22u) ^ word;
+}
+
+// Uniform random value in [0,1).
+fn random() -> f32 {
+    rng_state = pcg(rng_state);
+    return f32(rng_state >> 8u) / 16777216.0;
+}
+
+// Random unit vector, by rejection sampling in unit ball.
+fn random_unit_vector() -> vec3<f32> {
+    var p: vec3<f32>;
+    var length_squared: f32;
+    loop {
+        p = 2.0 * vec3(random(), random(), random()) - 1.0;
+        length_squared = dot(p, p);
+        if length_squared > 1e-30 && length_squared <= 1.0 {
+            break;
+        }
+    }
+    return p / sqrt(length_squared);
+}
+
+// Cosine-weighted random direction around normal.
+fn cosine_hemisphere(normal: vec3<f32>) -> vec3<f32> {
+    let r = sqrt(random());
+    let phi = 2.0 * PI * random();
+    let x = r * cos(phi);
+    let y = r * sin(phi);
+    return to_world(normal, vec3(x, y, sqrt(max(1.0 - x * x - y * y, 0.0))));
+}
+
+// Converts local vector around the z-axis to world space around normal.
+fn to_world(normal: vec3<f32>, local: vec3<f32>) -> vec3<f32> {
+    let w = normalize(normal);
+    var a = vec3(1.0, 0.0, 0.0);
+    if abs(w.x) > 0.9 {
+        a = vec3(0.0, 1.0, 0.0);
+    }
+    let v = normalize(cross(w, a));
+    let u = cross(v, w);
+    return local.x * u + local.y * v + local.z * w;
+}
+
+// Smith shadowing-masking of GGX with width alpha for direction with given cosine to the macro normal.
+fn smith_g1(alpha: f32, cos: f32) -> f32 {
+    let cos2 = max(cos * cos, 1.175494e-38);
+    let tan2 = (1.0 - cos2) / cos2;
+    return 2.0 / (1.0 + sqrt(1.0 + alpha * alpha * tan2));
+}
+
+// Whether ray hits node bounds before t_max.
+fn hit_node(node: Node, origin: vec3<f32>, inverse: vec3<f32>, t_max: f32) -> bool {
+    let t0 = (node.min - origin) * inverse;
+    let t1 = (node.max - origin) * inverse;
+    let near = min(t0, t1);
+    let far = max(t0, t1);
+    let start = max(max(near.x, near.y), max(near.z, T_MIN));
+    let end = min(min(far.x, far.y), min(far.z, t_max));
+    return start <= end;
+}
+
+// Nearest ray parameter within (T_MIN, t_max) where ray hits sphere, t_max if missed.
+fn hit_sphere(sphere: Sphere, origin: vec3<f32>, direction: vec3<f32>, t_max: f32) -> f32 {
+    let oc = origin - sphere.center;
+    let a = dot(direction, direction);
+    let half_b = dot(direction, oc);
+    let c = dot(oc, oc) - sphere.radius * sphere.radius;
+    let discriminant = half_b * half_b - a * c;
+    if discriminant < 0.0 {
+        return t_max;
+    }
+    let root = sqrt(discriminant);
+    let near = (-half_b - root) / a;
+    if T_MIN < near && near < t_max {
+        return near;
+    }
+    let far = (-half_b + root) / a;
+    if T_MIN < far && far < t_max {
+        return far;
+    }
+    return t_max;
+}
+
+// Nearest hit of ray with spheres, traversing the hierarchy depth-first.
+fn intersect(origin: vec3<f32>, direction: vec3<f32>) -> Hit {
+    var hit = Hit(T_MAX, NONE);
+    if params.node_count == 0u {
+        return hit;
+    }
+    let inverse = 1.0 / direction;
+    var stack: array<u32, STACK_SIZE>;
+    var size = 1u;
+    stack[0] = 0u;
+    while size > 0u {
+        size -= 1u;
+        let index = stack[size];
+        let node = nodes[index];
+        if !hit_node(node, origin, inverse, hit.t) {
+            continue;
+        }
+        if node.b > 0u {
+            for (var i = node.a; i < node.a + node.b; i++) {
+                let t = hit_sphere(spheres[i], origin, direction, hit.t);
+                if t < hit.t {
+                    hit = Hit(t, i);
+                }
+            }
+        } else if size + 2u <= STACK_SIZE {
+            stack[size] = node.a;
+            stack[size + 1u] = index + 1u;
+            size += 2u;
+        }
+    }
+    return hit;
+}
+
+// Radiance arriving along ray, with the same bounce limit and scattering as the CPU path tracer.
+fn radiance(ray_origin: vec3<f32>, ray_direction: vec3<f32>) -> vec3<f32> {
+    var origin = ray_origin;
+    var direction = ray_direction;
+    var throughput = vec3(1.0);
+    for (var depth = 0u; depth < params.max_depth; depth++) {
+        let hit = intersect(origin, direction);
+        if hit.index == NONE {
+            let a = 0.5 * (dot(normalize(direction), params.up) + 1.0);
+            return throughput * ((1.0 - a) * params.bottom + a * params.top);
+        }
+        let sphere = spheres[hit.index];
+        let point = origin + hit.t * direction;
+        let normal = (point - sphere.center) / sphere.radius;
+        switch sphere.kind {
+            case LAMBERT: {
+                throughput *= sphere.color;
+                direction = cosine_hemisphere(normal);
+            }
+            case METAL: {
+                // Offset reflection by fuzz, reflect scatters below surface about the normal.
+                let reflected = normalize(reflect(direction, normal));
+                var scattered = reflected + sphere.parameter * random_unit_vector();
+                if all(abs(scattered) < vec3(EPSILON)) {
+                    scattered = reflected;
+                }
+                if dot(scattered, normal) < 0.0 {
+                    scattered = reflect(scattered, normal);
+                }
+                throughput *= sphere.color;
+                direction = scattered;
+            }
+            case DIELECTRIC: {
+                // Refract or reflect by Schlick's approximation, absorb along path inside medium.
+                let front_face = dot(direction, normal) <= 0.0;
+                let n = select(-normal, normal, front_face);
+                let eta = select(sphere.parameter, 1.0 / sphere.parameter, front_face);
+                let incident = normalize(direction);
+                let cos_i = min(-dot(incident, n), 1.0);
+                let r0 = (1.0 - eta) / (1.0 + eta);
+                let reflectance = r0 * r0 + (1.0 - r0 * r0) * pow(1.0 - cos_i, 5.0);
+                let sin2_t = eta * eta * (1.0 - cos_i * cos_i);
+                if reflectance > random() || sin2_t > 1.0 {
+                    direction = reflect(incident, n);
+                } else {
+                    direction = incident * eta + n * (eta * cos_i - sqrt(1.0 - sin2_t));
+                }
+                if !front_face {
+                    throughput *= exp(-sphere.absorption * length(point - origin));
+                }
+            }
+            case MICROFACET: {
+                // Reflect or refract at microfacet normal sampled by GGX density times cosine.
+                let front_face = dot(direction, normal) <= 0.0;
+                let n = normalize(select(-normal, normal, front_face));
+                let incident = normalize(direction);
+                let alpha = sphere.parameter;
+                let u = random();
+                let tan2_theta = alpha * alpha * u / max(1.0 - u, 1.175494e-38);
+                let cos_theta = 1.0 / sqrt(1.0 + tan2_theta);
+                let sin_theta = sqrt(max(1.0 - cos_theta * cos_theta, 0.0));
+                let phi = 2.0 * PI * random();
+                let facet = to_world(n, vec3(sin_theta * cos(phi), sin_theta * sin(phi), cos_theta));
+                let cos_o = -dot(incident, n);
+                let cos_h = dot(facet, n);
+                let cos_oh = -dot(incident, facet);
+                if cos_o <= 0.0 || cos_oh <= 0.0 {
+                    return vec3(0.0);
+                }
+                var scattered = reflect(incident, facet);
+                var fresnel = vec3(1.0);
+                let ior = sphere.absorption.x;
+                if ior == 0.0 {
+                    fresnel = sphere.color + (1.0 - sphere.color) * pow(1.0 - cos_oh, 5.0);
+                } else {
+                    let eta = select(ior, 1.0 / ior, front_face);
+                    let r0 = (1.0 - eta) / (1.0 + eta);
+                    let reflectance = r0 * r0 + (1.0 - r0 * r0) * pow(1.0 - cos_oh, 5.0);
+                    let sin2_t = eta * eta * (1.0 - cos_oh * cos_oh);
+                    if sin2_t <= 1.0 && reflectance <= random() {
+                        scattered = incident * eta + facet * (eta * cos_oh - sqrt(1.0 - sin2_t));
+                    }
+                }
+
+                // Absorb reflections below and refractions above surface.
+                let cos_i = dot(scattered, n);
+                if (dot(scattered, facet) > 0.0) != (cos_i > 0.0) || cos_i == 0.0 {
+                    return vec3(0.0);
+                }
+                let shadowing = smith_g1(alpha, cos_o) * smith_g1(alpha, cos_i);
+                throughput *= fresnel * (shadowing * cos_oh / (cos_o * cos_h));
+                direction = scattered;
+            }
+            default: {
+                return throughput * sphere.color;
+            }
+        }
+        origin = point;
+    }
+    return vec3(0.0);
+}
+
+@compute @workgroup_size(8, 8)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x >= params.width || id.y >= params.height {
+        return;
+    }
+    let pixel = id.y * params.width + id.x;
+    var sum = vec3(0.0);
+    for (var s = params.first_sample; s < params.first_sample + params.samples; s++) {
+        rng_state = pcg(pixel ^ pcg(s ^ pcg(params.seed)));
+
+        // Ray from random point of defocus disk through random point of pixel square.
+        let pixel_sample = params.pixel00_location
+            + (f32(id.x) + random() - 0.5) * params.pixel_delta_u
+            + (f32(id.y) + random() - 0.5) * params.pixel_delta_v;
+        var disk = vec2(2.0 * random() - 1.0, 2.0 * random() - 1.0);
+        if dot(disk, disk) > 1.0 {
+            disk = normalize(disk);
+        }
+        let origin = params.look_from + disk.x * params.defocus_disk_u + disk.y * params.defocus_disk_v;
+        sum += radiance(origin, pixel_sample - origin);
+    }
+    accumulation[pixel] += vec4(sum, 0.0);
+}
@@ -0,0 +1,88 @@
+use crate::base::{color::Color3f, hash::hash_float};
+use std::hash::{Hash, Hasher};
+
+/// Image with RGB color and alpha coverage per pixel.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RgbaImage {
+    /// Width in pixels.
+    width: u32,
+
+    /// Height in pixels.
+    height: u32,
+
+    /// Pixel colors in row-major order, starting top left.
+    colors: Vec<Color3f>,
+
+    /// Pixel alpha values in row-major order, starting top left.
+    alphas: Vec<f32>,
+}
+
+impl RgbaImage {
+    /// Creates image from pixel colors and alpha values in row-major order.
+    pub fn new(width: u32, height: u32, colors: Vec<Color3f>, alphas: Vec<f32>) -> Self {
+        let size = (width * height) as usize;
+        assert!(size > 0 && colors.len() == size && alphas.len() == size);
+        RgbaImage {
+            width,
+            height,
+            colors,
+            alphas: alphas.into_iter().map(|a| a.clamp(0.0, 1.0)).collect(),
+        }
+    }
+
+    /// Width in pixels.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Height in pixels.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Color and alpha at texture coordinates u,v in [0,1] (nearest pixel, v pointing up).
+    pub fn sample(&self, u: f32, v: f32) -> (Color3f, f32) {
+        let x = ((u.clamp(0.0, 1.0) * self.width as f32) as u32).min(self.width - 1);
+        let y = (((1.0 - v.clamp(0.0, 1.0)) * self.height as f32) as u32).min(self.height - 1);
+        let i = (y * self.width + x) as usize;
+        (self.colors[i], self.alphas[i])
+    }
+}
+
+impl Hash for RgbaImage {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.width.hash(state);
+        self.height.hash(state);
+        self.colors.hash(state);
+        for &a in self.alphas.iter() {
+            hash_float(a, state);
+        }
+    }
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sample() {
+        let red = Color3f::new(1.0, 0.0, 0.0);
+        let blue = Color3f::new(0.0, 0.0, 1.0);
+        let image = RgbaImage::new(2, 1, vec![red, blue], vec![1.0, 0.5]);
+        assert_eq!(image.sample(0.0, 0.0), (red, 1.0));
+        assert_eq!(image.sample(0.49, 0.5), (red, 1.0));
+        assert_eq!(image.sample(0.51, 0.5), (blue, 0.5));
+        assert_eq!(image.sample(1.0, 1.0), (blue, 0.5));
+        assert_eq!(image.sample(-3.0, 7.0), (red, 1.0));
+    }
+
+    #[test]
+    fn orientation() {
+        let top = Color3f::white();
+        let bottom = Color3f::black();
+        let image = RgbaImage::new(1, 2, vec![top, bottom], vec![2.0, -1.0]);
+        assert_eq!(image.sample(0.5, 0.9), (top, 1.0));
+        assert_eq!(image.sample(0.5, 0.1), (bottom, 0.0));
+    }
+}
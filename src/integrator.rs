@@ -0,0 +1,436 @@
+use crate::{
+    base::{
+        color::Color3f,
+        interval::Interval,
+        material::{Interactable, Material},
+        onb::Onb,
+        ray::Ray,
+        sampling::cosine_hemisphere,
+        shape::{Intersectable, Intersection},
+    },
+    integrators::{
+        albedo_debug::AlbedoDebug, ambient_occlusion::AmbientOcclusion, depth_debug::DepthDebug,
+        direct_lighting::DirectLighting, light_visibility::LightVisibility,
+        material_debug::MaterialDebug, normal_debug::NormalDebug, path_tracer::PathTracer,
+        photon_mapper::PhotonMapper, polarized_path_tracer::PolarizedPathTracer,
+        precision_debug::PrecisionDebug, uv_debug::UvDebug,
+    },
+    materials::dielectric::Dielectric,
+    scene::Scene,
+    stats::RenderStats,
+};
+use rand::Rng;
+
+/// An integrator computes the light transported along camera rays.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Integrator {
+    AlbedoDebug(AlbedoDebug),
+    AmbientOcclusion(AmbientOcclusion),
+    DepthDebug(DepthDebug),
+    DirectLighting(DirectLighting),
+    LightVisibility(LightVisibility),
+    MaterialDebug(MaterialDebug),
+    NormalDebug(NormalDebug),
+    PathTracer(PathTracer),
+    PhotonMapper(PhotonMapper),
+    PolarizedPathTracer(PolarizedPathTracer),
+    PrecisionDebug(PrecisionDebug),
+    UvDebug(UvDebug),
+}
+
+impl Default for Integrator {
+    /// Default path tracer.
+    fn default() -> Self {
+        Integrator::PathTracer(PathTracer::default())
+    }
+}
+
+impl Estimator for Integrator {
+    fn radiance(&self, ray: Ray, scene: &Scene, rng: &mut impl Rng) -> Color3f {
+        match self {
+            Integrator::AlbedoDebug(a) => a.radiance(ray, scene, rng),
+            Integrator::AmbientOcclusion(a) => a.radiance(ray, scene, rng),
+            Integrator::DepthDebug(d) => d.radiance(ray, scene, rng),
+            Integrator::DirectLighting(d) => d.radiance(ray, scene, rng),
+            Integrator::LightVisibility(l) => l.radiance(ray, scene, rng),
+            Integrator::MaterialDebug(m) => m.radiance(ray, scene, rng),
+            Integrator::NormalDebug(n) => n.radiance(ray, scene, rng),
+            Integrator::PathTracer(p) => p.radiance(ray, scene, rng),
+            Integrator::PhotonMapper(p) => p.radiance(ray, scene, rng),
+            Integrator::PolarizedPathTracer(p) => p.radiance(ray, scene, rng),
+            Integrator::PrecisionDebug(p) => p.radiance(ray, scene, rng),
+            Integrator::UvDebug(u) => u.radiance(ray, scene, rng),
+        }
+    }
+
+    fn radiance_with_stats(
+        &self,
+        ray: Ray,
+        scene: &Scene,
+        rng: &mut impl Rng,
+        stats: &mut RenderStats,
+    ) -> Color3f {
+        match self {
+            Integrator::DirectLighting(d) => d.radiance_with_stats(ray, scene, rng, stats),
+            Integrator::PathTracer(p) => p.radiance_with_stats(ray, scene, rng, stats),
+            _ => self.radiance(ray, scene, rng),
+        }
+    }
+}
+
+/// An estimator computes radiance arriving along rays.
+pub trait Estimator {
+    /// Estimates radiance arriving at ray origin from ray direction, drawing random numbers from rng.
+    fn radiance(&self, ray: Ray, scene: &Scene, rng: &mut impl Rng) -> Color3f;
+
+    /// Estimates radiance like `radiance`, recording path events into stats. Estimators without paths through the
+    /// scene record none.
+    fn radiance_with_stats(
+        &self,
+        ray: Ray,
+        scene: &Scene,
+        rng: &mut impl Rng,
+        _stats: &mut RenderStats,
+    ) -> Color3f {
+        self.radiance(ray, scene, rng)
+    }
+}
+
+/// Estimates light arriving directly from randomly chosen light and scattered by material at intersection, weighted
+/// against scattering by power heuristic, plus light of all analytic lights. Result still has to be attenuated by
+/// material.
+pub fn sample_direct(
+    isect: &Intersection,
+    material: &Material,
+    scene: &Scene,
+    rng: &mut impl Rng,
+) -> Color3f {
+    // Analytic lights cannot be hit by scattered rays, so need no weighting.
+    let mut color = Color3f::black();
+    for illumination in scene.illumination(isect) {
+        let scattering_pdf = material
+            .scattering_pdf(*isect, illumination.direction)
+            .unwrap_or_default();
+        color += illumination.irradiance * scattering_pdf;
+    }
+
+    let Some(sample) = scene.sample_light(isect, rng) else {
+        return color;
+    };
+    let scattering_pdf = material
+        .scattering_pdf(*isect, sample.direction)
+        .unwrap_or_default();
+    if scattering_pdf <= 0.0 {
+        return color;
+    }
+    color
+        + sample.emission
+            * (scattering_pdf / sample.pdf * power_heuristic(sample.pdf, scattering_pdf))
+}
+
+/// Attenuation of ray passing through shadow catcher at intersection, darkened by occluders within the catcher's
+/// distance. Casts one cosine-weighted occlusion ray on the side of the ray origin, so the mean attenuation follows
+/// the ambient occlusion of the catcher. Returns one for other materials.
+pub fn catcher_shadow(
+    ray: Ray,
+    isect: &Intersection,
+    material: &Material,
+    scene: &Scene,
+    rng: &mut impl Rng,
+) -> f32 {
+    let Material::ShadowCatcher(catcher) = material else {
+        return 1.0;
+    };
+    let mut normal = isect.normal;
+    if normal.dot(&ray.direction()) > 0.0 {
+        normal = -normal;
+    }
+    let direction = Onb::new(normal).to_world(cosine_hemisphere(rng));
+
+    // Shadow catchers are invisible and do not occlude, including the catcher itself.
+    let mut occlusion_ray = isect.spawn_ray(direction);
+    let mut distance = catcher.distance();
+    while let Some(occluder) = scene.intersect(occlusion_ray, Interval::new(0.0, distance)) {
+        if !matches!(occluder.material, Material::ShadowCatcher(_)) {
+            return 1.0 - catcher.strength();
+        }
+        occlusion_ray = occluder.spawn_ray(direction);
+        distance -= occluder.t;
+    }
+    1.0
+}
+
+/// Records bounce of ray off material at intersection into stats, if given. Depth counts remaining bounces including
+/// this one, so the path is cut off afterwards at depth one.
+pub fn record_bounce(
+    stats: Option<&mut RenderStats>,
+    ray: Ray,
+    isect: &Intersection,
+    material: &Material,
+    depth: u32,
+) {
+    if let Some(stats) = stats {
+        let tir = match material {
+            Material::Dielectric(d) => d.total_internal_reflection(ray.direction(), isect.normal),
+            Material::Microfacet(m) => m.ior().is_some_and(|ior| {
+                Dielectric::new(ior).total_internal_reflection(ray.direction(), isect.normal)
+            }),
+            _ => false,
+        };
+        stats.record_bounce(material, tir, depth == 1);
+    }
+}
+
+/// Background radiance seen along ray escaping the scene. If the ray was scattered with given density at a bounce
+/// which also sampled lights explicitly, the sun disk of a physical sky is weighted against these samples.
+pub fn escaped_radiance(ray: Ray, scene: &Scene, scattering_pdf: Option<f32>) -> Color3f {
+    let color = scene.background_color(ray);
+    let (Some(sky), Some(scattering_pdf)) = (scene.sun(), scattering_pdf) else {
+        return color;
+    };
+    let sun_pdf = scene.sun_pdf(ray.direction());
+    if sun_pdf <= 0.0 {
+        return color;
+    }
+    color - sky.sun_radiance() * (1.0 - power_heuristic(scattering_pdf, sun_pdf))
+}
+
+/// Power heuristic weight (exponent two) of sampling technique with density pdf against other technique.
+pub fn power_heuristic(pdf: f32, other_pdf: f32) -> f32 {
+    let (a, b) = (pdf * pdf, other_pdf * other_pdf);
+    if a + b > 0.0 {
+        a / (a + b)
+    } else {
+        0.0
+    }
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        background::Background,
+        base::{light::Light, sampling::cosine_hemisphere},
+        base::{point::Point3f, shape::Shape, vector::Vector3f},
+        lights::{directional::DirectionalLight, point::PointLight, spot::SpotLight},
+        materials::{diffuse_light::DiffuseLight, lambert::Lambert, shadow_catcher::ShadowCatcher},
+        shapes::sphere::Sphere,
+        sky::PhysicalSky,
+    };
+    use rand::{rngs::StdRng, SeedableRng};
+    use std::f32::consts::PI;
+
+    /// Dark scene with diffuse ground lit by small light above origin.
+    fn lit_ground() -> Scene {
+        let mut scene = Scene::new();
+        scene.set_background(Background::Uniform(Color3f::black()));
+        let ground = Material::Lambert(Lambert::new(Color3f::new(0.5, 0.5, 0.5)));
+        scene.add(Shape::preset("ground", ground).unwrap());
+        let light = Material::DiffuseLight(DiffuseLight::new(Color3f::new(20.0, 20.0, 20.0)));
+        scene.add(Shape::Sphere(Sphere::new(
+            Point3f::new(0.0, 2.0, 0.0),
+            0.25,
+            light,
+        )));
+        scene
+    }
+
+    /// Mean radiance of integrator along ray.
+    fn mean_radiance(integrator: Integrator, ray: Ray, scene: &Scene, n: u32) -> Color3f {
+        let mut rng = StdRng::seed_from_u64(42);
+        let sum = (0..n).fold(Color3f::black(), |acc, _| {
+            acc + integrator.radiance(ray, scene, &mut rng)
+        });
+        sum / n as f32
+    }
+
+    #[test]
+    fn integrators() {
+        let scene = lit_ground();
+        let ray = Ray::new(Point3f::new(1.0, 1.0, 0.0), Vector3f::new(-1.0, -1.0, 0.0));
+
+        // Irradiance of small sphere light at distance 2, reflected by diffuse ground.
+        let expected = 0.5 * 20.0 * (0.25f32 / 2.0).powi(2);
+        for integrator in [
+            Integrator::PathTracer(PathTracer::new(10)),
+            Integrator::DirectLighting(DirectLighting::new(10)),
+        ] {
+            let radiance = mean_radiance(integrator, ray, &scene, 20000);
+            assert!((radiance.g() - expected).abs() / expected < 0.02);
+        }
+
+        // Light preview shades ground as white clay, shows light as seen.
+        let preview = Integrator::LightVisibility(LightVisibility);
+        let radiance = mean_radiance(preview, ray, &scene, 20000);
+        assert!((radiance.g() - 2.0 * expected).abs() / expected < 0.04);
+        let down = Ray::new(Point3f::new(0.0, 3.0, 0.0), Vector3f::new(0.0, -1.0, 0.0));
+        assert_eq!(mean_radiance(preview, down, &scene, 1).g(), 20.0);
+
+        // Ground normal points up, ground is fully unoccluded except towards small light.
+        let normal = mean_radiance(Integrator::NormalDebug(NormalDebug), ray, &scene, 1);
+        assert_eq!(normal, Color3f::new(0.5, 1.0, 0.5));
+        let albedo = mean_radiance(Integrator::AlbedoDebug(AlbedoDebug), ray, &scene, 1);
+        assert_eq!(albedo, Color3f::new(0.5, 0.5, 0.5));
+        let light = mean_radiance(Integrator::AlbedoDebug(AlbedoDebug), down, &scene, 1);
+        assert_eq!(light, Color3f::white());
+        let depth = mean_radiance(Integrator::DepthDebug(DepthDebug::new(2.0)), ray, &scene, 1);
+        assert!((depth.g() - (1.0 - 0.5f32.sqrt())).abs() < 1e-4);
+        let uv = mean_radiance(Integrator::UvDebug(UvDebug), ray, &scene, 1);
+        assert!(uv.b() == 0.0 && uv.r() > 0.0 && uv.g() > 0.0);
+        let ground = mean_radiance(Integrator::MaterialDebug(MaterialDebug), ray, &scene, 1);
+        let down = Ray::new(Point3f::new(0.0, 3.0, 0.0), Vector3f::new(0.0, -1.0, 0.0));
+        let light = mean_radiance(Integrator::MaterialDebug(MaterialDebug), down, &scene, 1);
+        assert!(ground.g() >= 0.25 && ground != light);
+        let occlusion = AmbientOcclusion::default();
+        let ambient = mean_radiance(Integrator::AmbientOcclusion(occlusion), ray, &scene, 1000);
+        assert!(ambient.g() > 0.95 && ambient.g() < 1.0);
+        let miss = Ray::new(Point3f::new(0.0, 1.0, 0.0), Vector3f::new(1.0, 1.0, 0.0));
+        let ambient = mean_radiance(Integrator::AmbientOcclusion(occlusion), miss, &scene, 1);
+        assert_eq!(ambient, Color3f::white());
+    }
+
+    #[test]
+    fn analytic_lights() {
+        // Unit irradiance from above, reflected by diffuse ground.
+        let mut scene = Scene::new();
+        scene.set_background(Background::Uniform(Color3f::black()));
+        let ground = Material::Lambert(Lambert::new(Color3f::new(0.5, 0.5, 0.5)));
+        scene.add(Shape::preset("ground", ground).unwrap());
+        let above = Point3f::new(0.0, 2.0, 0.0);
+        let down = Vector3f::new(0.0, -1.0, 0.0);
+        scene.add_light(Light::Point(PointLight::new(
+            above,
+            Color3f::new(4.0, 4.0, 4.0),
+        )));
+        scene.add_light(Light::Directional(DirectionalLight::new(
+            down,
+            Color3f::white(),
+        )));
+        scene.add_light(Light::Spot(SpotLight::new(
+            above,
+            -down,
+            Color3f::white(),
+            30.0,
+        )));
+        let ray = Ray::new(Point3f::new(1.0, 1.0, 0.0), Vector3f::new(-1.0, -1.0, 0.0));
+        let expected = 2.0 * 0.5 / PI;
+        for integrator in [
+            Integrator::PathTracer(PathTracer::new(10)),
+            Integrator::DirectLighting(DirectLighting::new(10)),
+        ] {
+            let radiance = mean_radiance(integrator, ray, &scene, 100);
+            assert!((radiance.g() - expected).abs() < 1e-4);
+        }
+
+        // Occluder above ground casts shadow of point light only.
+        let occluder = Material::Lambert(Lambert::new(Color3f::black()));
+        scene.add(Shape::Sphere(Sphere::new(
+            Point3f::new(0.0, 1.0, 0.0),
+            0.25,
+            occluder,
+        )));
+        let radiance = mean_radiance(
+            Integrator::DirectLighting(DirectLighting::new(10)),
+            ray,
+            &scene,
+            1,
+        );
+        assert!(radiance.g() == 0.0);
+        let side = Ray::new(Point3f::new(2.0, 1.0, 0.0), Vector3f::new(-1.0, -1.0, 0.0));
+        let radiance = mean_radiance(
+            Integrator::LightVisibility(LightVisibility),
+            side,
+            &scene,
+            1,
+        );
+        assert!(radiance.g() > 1.0 / PI);
+    }
+
+    #[test]
+    fn physical_sky() {
+        // Ground lit by sun and sky, irradiance integrated over hemisphere.
+        let mut scene = Scene::new();
+        let sky = PhysicalSky::new(Vector3f::new(1.0, 1.0, 0.0), 3.0);
+        scene.set_background(Background::PhysicalSky(sky));
+        let ground = Material::Lambert(Lambert::new(Color3f::new(0.5, 0.5, 0.5)));
+        scene.add(Shape::preset("ground", ground).unwrap());
+        let mut rng = StdRng::seed_from_u64(42);
+        let n = 100000;
+        let sky_irradiance = (0..n).fold(Color3f::black(), |acc, _| {
+            let d = cosine_hemisphere(&mut rng);
+            acc + sky.sky_color(Vector3f::new(d.x(), d.z(), d.y())) * (PI / n as f32)
+        });
+        let sun = sky.sun_direction();
+        let sun_irradiance = sky.sun_radiance() * (sun.y() / sky.sun_pdf(sun));
+        let expected = (sky_irradiance + sun_irradiance).g() * 0.5 / PI;
+
+        // Sun is sampled explicitly, with or without light sampling at surfaces of paths.
+        let ray = Ray::new(Point3f::new(1.0, 1.0, 0.0), Vector3f::new(-1.0, -1.0, 0.0));
+        for integrator in [
+            Integrator::PathTracer(PathTracer::new(10)),
+            Integrator::DirectLighting(DirectLighting::new(10)),
+        ] {
+            let radiance = mean_radiance(integrator, ray, &scene, 20000);
+            assert!((radiance.g() - expected).abs() / expected < 0.02);
+        }
+    }
+
+    #[test]
+    fn clamping() {
+        // Light reaches ground only via clamped bounce, diffuse albedo 0.5 bounds every sample.
+        let scene = lit_ground();
+        let ray = Ray::new(Point3f::new(1.0, 1.0, 0.0), Vector3f::new(-1.0, -1.0, 0.0));
+        let mut path_tracer = PathTracer::new(10);
+        path_tracer.set_light_sampling(false);
+        path_tracer.set_max_radiance(1.0);
+        let mut rng = StdRng::seed_from_u64(42);
+        let samples: Vec<Color3f> = (0..20000)
+            .map(|_| path_tracer.radiance(ray, &scene, &mut rng))
+            .collect();
+        assert!(samples.iter().all(|c| c.g() <= 0.5 + 1e-6));
+        assert!(samples.iter().any(|c| c.g() > 0.49));
+
+        // Light arriving at the first bounce is kept below clamp depth.
+        path_tracer.set_clamp_depth(1);
+        assert!((0..20000).any(|_| path_tracer.radiance(ray, &scene, &mut rng).g() > 1.0));
+    }
+
+    #[test]
+    fn shadow_catcher() {
+        // White background seen through catcher ground, darkened below unit sphere resting on it.
+        let mut scene = Scene::new();
+        scene.set_background(Background::Uniform(Color3f::white()));
+        let catcher = Material::ShadowCatcher(ShadowCatcher::new(1.0, 0.5));
+        scene.add(Shape::preset("ground", catcher).unwrap());
+        let sphere = Material::Lambert(Lambert::new(Color3f::white()));
+        scene.add(Shape::Sphere(Sphere::new(
+            Point3f::new(0.0, 1.0, 0.0),
+            1.0,
+            sphere,
+        )));
+        let direction = Vector3f::new(0.0, -1.0, 0.0);
+        let far = Ray::new(Point3f::new(5.0, 1.0, 0.0), direction);
+        let near = Ray::new(Point3f::new(1.05, 1.0, 0.0), direction);
+        for integrator in [
+            Integrator::PathTracer(PathTracer::new(10)),
+            Integrator::DirectLighting(DirectLighting::new(10)),
+        ] {
+            let unoccluded = mean_radiance(integrator, far, &scene, 100);
+            assert!((unoccluded.g() - 1.0).abs() < 1e-4);
+            let shadowed = mean_radiance(integrator, near, &scene, 1000);
+            assert!(shadowed.g() > 0.55 && shadowed.g() < 0.85);
+        }
+    }
+
+    #[test]
+    fn power_heuristic() {
+        assert_eq!(super::power_heuristic(1.0, 1.0), 0.5);
+        assert_eq!(super::power_heuristic(3.0, 1.0), 0.9);
+        assert_eq!(super::power_heuristic(0.0, 0.0), 0.0);
+        assert_eq!(
+            super::power_heuristic(2.0, 5.0) + super::power_heuristic(5.0, 2.0),
+            1.0
+        );
+    }
+}
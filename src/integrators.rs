@@ -0,0 +1,12 @@
+pub mod albedo_debug;
+pub mod ambient_occlusion;
+pub mod depth_debug;
+pub mod direct_lighting;
+pub mod light_visibility;
+pub mod material_debug;
+pub mod normal_debug;
+pub mod path_tracer;
+pub mod photon_mapper;
+pub mod polarized_path_tracer;
+pub mod precision_debug;
+pub mod uv_debug;
@@ -0,0 +1,26 @@
+use crate::{
+    base::{color::Color3f, interval::Interval, material::Interactable, ray::Ray},
+    integrator::Estimator,
+    scene::Scene,
+};
+use rand::Rng;
+
+/// Debug integrator showing albedo at first hits, the attenuation of one sampled interaction with the surface
+/// material, which averages to the fraction of light it scatters. Emitters show their emission clamped to one, misses
+/// are white. Guides the denoiser, see `denoise`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct AlbedoDebug;
+
+impl Estimator for AlbedoDebug {
+    fn radiance(&self, ray: Ray, scene: &Scene, rng: &mut impl Rng) -> Color3f {
+        let Some((object, isect)) = scene.intersect_object(ray, Interval::new(0.0, f32::INFINITY))
+        else {
+            return Color3f::white();
+        };
+        let material = scene.surface_material(object, &isect, rng);
+        match material.interact(ray, isect, rng) {
+            Some(iact) => iact.attenuation.clamp(0.0, 1.0),
+            None => material.emitted().clamp(0.0, 1.0),
+        }
+    }
+}
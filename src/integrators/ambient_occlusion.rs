@@ -0,0 +1,64 @@
+use crate::{
+    base::{
+        color::Color3f, interval::Interval, onb::Onb, ray::Ray, sampling::cosine_hemisphere,
+        shape::Intersectable,
+    },
+    integrator::Estimator,
+    scene::Scene,
+};
+use rand::Rng;
+
+/// Ambient occlusion integrator. Shades surfaces by fraction of cosine-weighted directions which are not occluded
+/// within a distance. Escaping camera rays count as unoccluded.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AmbientOcclusion {
+    /// Count of occlusion rays per camera ray.
+    samples: u32,
+
+    /// Max distance of occluders.
+    distance: f32,
+}
+
+impl AmbientOcclusion {
+    /// Creates ambient occlusion integrator with count of occlusion rays and max occluder distance.
+    pub fn new(samples: u32, distance: f32) -> Self {
+        assert!(samples > 0);
+        AmbientOcclusion { samples, distance }
+    }
+}
+
+impl Default for AmbientOcclusion {
+    /// Default ambient occlusion with 4 rays and infinite distance.
+    fn default() -> Self {
+        AmbientOcclusion::new(4, f32::INFINITY)
+    }
+}
+
+impl Estimator for AmbientOcclusion {
+    fn radiance(&self, ray: Ray, scene: &Scene, rng: &mut impl Rng) -> Color3f {
+        let Some(isect) = scene.intersect(ray, Interval::new(0.0, f32::INFINITY)) else {
+            return Color3f::white();
+        };
+
+        // Face normal towards ray origin.
+        let mut normal = isect.normal;
+        if normal.dot(&ray.direction()) > 0.0 {
+            normal = -normal;
+        }
+
+        // Cast cosine-weighted occlusion rays.
+        let basis = Onb::new(normal);
+        let unoccluded = (0..self.samples)
+            .filter(|_| {
+                let direction = basis.to_world(cosine_hemisphere(rng));
+                scene
+                    .intersect(
+                        isect.spawn_ray(direction),
+                        Interval::new(0.0, self.distance),
+                    )
+                    .is_none()
+            })
+            .count();
+        Color3f::white() * (unoccluded as f32 / self.samples as f32)
+    }
+}
@@ -0,0 +1,41 @@
+use crate::{
+    base::{color::Color3f, interval::Interval, ray::Ray, shape::Intersectable},
+    integrator::Estimator,
+    scene::Scene,
+};
+use rand::Rng;
+
+/// Debug integrator showing distance to first hit as gray level, fading linearly from white at ray origin to black
+/// at max distance. Misses are black.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DepthDebug {
+    /// Distance mapped to black.
+    max_distance: f32,
+}
+
+impl DepthDebug {
+    /// Creates depth debug integrator with distance mapped to black.
+    pub fn new(max_distance: f32) -> Self {
+        assert!(max_distance > 0.0);
+        DepthDebug { max_distance }
+    }
+}
+
+impl Default for DepthDebug {
+    /// Default depth debug with max distance 10.
+    fn default() -> Self {
+        DepthDebug::new(10.0)
+    }
+}
+
+impl Estimator for DepthDebug {
+    fn radiance(&self, ray: Ray, scene: &Scene, _rng: &mut impl Rng) -> Color3f {
+        match scene.intersect(ray, Interval::new(0.0, f32::INFINITY)) {
+            Some(isect) => {
+                let distance = (isect.point - ray.origin()).length();
+                Color3f::white() * (1.0 - distance / self.max_distance).max(0.0)
+            }
+            None => Color3f::black(),
+        }
+    }
+}
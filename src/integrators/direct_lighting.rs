@@ -0,0 +1,96 @@
+use crate::{
+    base::{color::Color3f, interval::Interval, material::Interactable, ray::Ray},
+    integrator::{
+        catcher_shadow, escaped_radiance, power_heuristic, record_bounce, sample_direct, Estimator,
+    },
+    scene::Scene,
+    stats::RenderStats,
+};
+use rand::Rng;
+
+/// Direct lighting integrator. Light is scattered once at diffuse surfaces, combining light and scattering samples.
+/// Specular surfaces are followed up to max depth.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DirectLighting {
+    /// Max number of specular bounces.
+    max_depth: u32,
+}
+
+impl DirectLighting {
+    /// Creates direct lighting integrator with max depth of specular chains.
+    pub fn new(max_depth: u32) -> Self {
+        DirectLighting { max_depth }
+    }
+
+    /// Calculate direct light reflected along ray. Specular bounces are recorded into stats if given.
+    fn ray_color(
+        &self,
+        ray: Ray,
+        depth: u32,
+        scene: &Scene,
+        rng: &mut impl Rng,
+        mut stats: Option<&mut RenderStats>,
+    ) -> Color3f {
+        // Recursion limit.
+        if depth == 0 {
+            return Color3f::black();
+        }
+
+        // Intersect with scene.
+        let Some((object, isect)) = scene.intersect_object(ray, Interval::new(0.0, f32::INFINITY))
+        else {
+            return scene.background_color(ray);
+        };
+        let material = scene.surface_material(object, &isect, rng);
+        let mut color = material.emitted();
+        let Some(iact) = material.interact(ray, isect, rng) else {
+            return color;
+        };
+
+        // Specular surfaces and shadow catchers continue path.
+        let direction = iact.scattered_ray.direction();
+        let Some(scattering_pdf) = material.scattering_pdf(isect, direction) else {
+            record_bounce(stats.as_deref_mut(), ray, &isect, &material, depth);
+            let attenuation = iact.attenuation * catcher_shadow(ray, &isect, &material, scene, rng);
+            let incident = self.ray_color(iact.scattered_ray, depth - 1, scene, rng, stats);
+            return color + attenuation * incident;
+        };
+
+        // Light sample.
+        color += iact.attenuation * sample_direct(&isect, &material, scene, rng);
+
+        // Scattering sample, weighted against light sample if it hits light.
+        let scattered = iact.scattered_ray;
+        let incident = match scene.intersect_object(scattered, Interval::new(0.0, f32::INFINITY)) {
+            Some((object, light)) => {
+                let light_pdf = scene.light_pdf(object, isect.point, light.point, light.normal);
+                light.material.emitted() * power_heuristic(scattering_pdf, light_pdf)
+            }
+            None => escaped_radiance(scattered, scene, Some(scattering_pdf)),
+        };
+        color + iact.attenuation * incident
+    }
+}
+
+impl Default for DirectLighting {
+    /// Default direct lighting integrator with max depth 10.
+    fn default() -> Self {
+        DirectLighting::new(10)
+    }
+}
+
+impl Estimator for DirectLighting {
+    fn radiance(&self, ray: Ray, scene: &Scene, rng: &mut impl Rng) -> Color3f {
+        self.ray_color(ray, self.max_depth, scene, rng, None)
+    }
+
+    fn radiance_with_stats(
+        &self,
+        ray: Ray,
+        scene: &Scene,
+        rng: &mut impl Rng,
+        stats: &mut RenderStats,
+    ) -> Color3f {
+        self.ray_color(ray, self.max_depth, scene, rng, Some(stats))
+    }
+}
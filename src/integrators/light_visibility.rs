@@ -0,0 +1,46 @@
+use crate::{
+    base::{
+        color::Color3f, interval::Interval, material::Interactable, ray::Ray, shape::Intersectable,
+    },
+    integrator::Estimator,
+    scene::Scene,
+};
+use rand::Rng;
+use std::f32::consts::PI;
+
+/// Fast light preview integrator for placing and balancing lights. Shows emissive surfaces and background as seen,
+/// and lights other surfaces by one light sample and all analytic lights as if they were white lambertian (clay
+/// render), ignoring their materials and indirect light.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LightVisibility;
+
+impl Estimator for LightVisibility {
+    fn radiance(&self, ray: Ray, scene: &Scene, rng: &mut impl Rng) -> Color3f {
+        let Some(isect) = scene.intersect(ray, Interval::new(0.0, f32::INFINITY)) else {
+            return scene.background_color(ray);
+        };
+        let emitted = isect.material.emitted();
+        if emitted != Color3f::black() {
+            return emitted;
+        }
+
+        // Direct light on side of camera, from one light sample.
+        let mut normal = isect.normal;
+        if normal.dot(&ray.direction()) > 0.0 {
+            normal = -normal;
+        }
+        let mut color = Color3f::black();
+        for illumination in scene.illumination(&isect) {
+            let cos = normal.dot(&illumination.direction).max(0.0);
+            color += illumination.irradiance * (cos / PI);
+        }
+        let Some(sample) = scene.sample_light(&isect, rng) else {
+            return color;
+        };
+        let cos = normal.dot(&sample.direction);
+        if cos <= 0.0 {
+            return color;
+        }
+        color + sample.emission * (cos / (PI * sample.pdf))
+    }
+}
@@ -0,0 +1,26 @@
+use crate::{
+    base::{color::Color3f, hash::stable_hash, interval::Interval, ray::Ray, shape::Intersectable},
+    integrator::Estimator,
+    scene::Scene,
+};
+use rand::Rng;
+
+/// Debug integrator showing materials as false colors derived from their content hash, so equal materials share a
+/// color across renders. Misses are black.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MaterialDebug;
+
+impl Estimator for MaterialDebug {
+    fn radiance(&self, ray: Ray, scene: &Scene, _rng: &mut impl Rng) -> Color3f {
+        match scene.intersect(ray, Interval::new(0.0, f32::INFINITY)) {
+            Some(isect) => false_color(stable_hash(isect.material)),
+            None => Color3f::black(),
+        }
+    }
+}
+
+/// Bright color from hash bits (components in [0.25,1]).
+fn false_color(hash: u64) -> Color3f {
+    let component = |shift: u32| 0.25 + 0.75 * ((hash >> shift) & 0xff) as f32 / 255.0;
+    Color3f::new(component(0), component(8), component(16))
+}
@@ -0,0 +1,22 @@
+use crate::{
+    base::{color::Color3f, interval::Interval, ray::Ray, shape::Intersectable},
+    integrator::Estimator,
+    scene::Scene,
+};
+use rand::Rng;
+
+/// Debug integrator showing surface normals, mapping components from [-1,1] to [0,1]. Misses are black.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct NormalDebug;
+
+impl Estimator for NormalDebug {
+    fn radiance(&self, ray: Ray, scene: &Scene, _rng: &mut impl Rng) -> Color3f {
+        match scene.intersect(ray, Interval::new(0.0, f32::INFINITY)) {
+            Some(isect) => {
+                let n = isect.normal;
+                0.5 * (Color3f::new(n.x(), n.y(), n.z()) + 1.0)
+            }
+            None => Color3f::black(),
+        }
+    }
+}
@@ -0,0 +1,169 @@
+use crate::{
+    base::{
+        color::Color3f,
+        interval::Interval,
+        material::{Interactable, Material},
+        point::Point3f,
+        ray::Ray,
+    },
+    integrator::{
+        catcher_shadow, escaped_radiance, power_heuristic, record_bounce, sample_direct, Estimator,
+    },
+    scene::Scene,
+    stats::RenderStats,
+};
+use rand::Rng;
+
+/// Unidirectional path tracer, optionally sampling lights explicitly at diffuse bounces (next-event estimation).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PathTracer {
+    /// Max number of recursive ray bounces into scene.
+    max_depth: u32,
+
+    /// Whether lights are sampled explicitly at diffuse bounces.
+    light_sampling: bool,
+
+    /// Max luminance of indirect light arriving at diffuse bounces.
+    max_radiance: f32,
+
+    /// Count of bounces from the camera whose incoming light is not clamped.
+    clamp_depth: u32,
+}
+
+impl PathTracer {
+    /// Creates path tracer with max depth, sampling lights explicitly.
+    pub fn new(max_depth: u32) -> Self {
+        PathTracer {
+            max_depth,
+            light_sampling: true,
+            max_radiance: f32::INFINITY,
+            clamp_depth: 0,
+        }
+    }
+
+    /// Max number of recursive ray bounces into scene.
+    pub fn max_depth(&self) -> u32 {
+        self.max_depth
+    }
+
+    /// Sets whether lights are sampled explicitly at diffuse bounces.
+    pub fn set_light_sampling(&mut self, light_sampling: bool) {
+        self.light_sampling = light_sampling;
+    }
+
+    /// Max luminance of indirect light arriving at diffuse bounces.
+    pub fn max_radiance(&self) -> f32 {
+        self.max_radiance
+    }
+
+    /// Sets max luminance of indirect light arriving at diffuse bounces. Clamping suppresses fireflies from rarely
+    /// sampled caustic paths (e.g. light focused by glass onto diffuse surfaces) at the cost of some energy loss.
+    pub fn set_max_radiance(&mut self, max_radiance: f32) {
+        assert!(max_radiance > 0.0);
+        self.max_radiance = max_radiance;
+    }
+
+    /// Count of bounces from the camera whose incoming light is not clamped.
+    pub fn clamp_depth(&self) -> u32 {
+        self.clamp_depth
+    }
+
+    /// Sets count of bounces from the camera whose incoming light is not clamped by max radiance, e.g. one keeps
+    /// caustics seen directly on surfaces hit by camera rays while still suppressing fireflies of longer paths.
+    pub fn set_clamp_depth(&mut self, clamp_depth: u32) {
+        self.clamp_depth = clamp_depth;
+    }
+
+    /// Calculate color shading for ray into scene. Previous holds origin and scattering density of ray if it was
+    /// scattered at a bounce which also sampled lights explicitly. Bounces are recorded into stats if given.
+    fn ray_color(
+        &self,
+        ray: Ray,
+        depth: u32,
+        scene: &Scene,
+        rng: &mut impl Rng,
+        previous: Option<(Point3f, f32)>,
+        mut stats: Option<&mut RenderStats>,
+    ) -> Color3f {
+        // Recursion limit.
+        if depth == 0 {
+            return Color3f::black();
+        }
+
+        // Intersect with scene.
+        if let Some((object, isect)) =
+            scene.intersect_object(ray, Interval::new(0.0, f32::INFINITY))
+        {
+            // Interact with decal or object material.
+            let material = scene.surface_material(object, &isect, rng);
+
+            // Emitted light, weighted against explicit light sample of previous bounce.
+            let mut color = material.emitted();
+            if let Some((origin, scattering_pdf)) = previous {
+                if color != Color3f::black() {
+                    let light_pdf = scene.light_pdf(object, origin, isect.point, isect.normal);
+                    color = color * power_heuristic(scattering_pdf, light_pdf);
+                }
+            }
+
+            if let Some(iact) = material.interact(ray, isect, rng) {
+                record_bounce(stats.as_deref_mut(), ray, &isect, &material, depth);
+
+                // Explicit light sample, if scattering can be evaluated (diffuse bounce) and path continues.
+                let direction = iact.scattered_ray.direction();
+                let scattering_pdf = material.scattering_pdf(isect, direction);
+                let mut next = None;
+                let mut attenuation = iact.attenuation;
+                if let Some(scattering_pdf) =
+                    scattering_pdf.filter(|_| self.light_sampling && depth > 1)
+                {
+                    color += iact.attenuation * sample_direct(&isect, &material, scene, rng);
+                    next = Some((isect.point, scattering_pdf));
+                } else if let Material::ShadowCatcher(_) = *material {
+                    // Ray passes through unchanged, keep light sample weighting of previous bounce.
+                    attenuation = attenuation * catcher_shadow(ray, &isect, &material, scene, rng);
+                    next = previous;
+                }
+
+                // Recurse, clamp at diffuse bounces beyond clamp depth and attenuate.
+                let mut incoming =
+                    self.ray_color(iact.scattered_ray, depth - 1, scene, rng, next, stats);
+                let luminance = incoming.luminance();
+                let clamped = self.max_depth - depth >= self.clamp_depth;
+                if scattering_pdf.is_some() && clamped && luminance > self.max_radiance {
+                    incoming = incoming * (self.max_radiance / luminance);
+                }
+                return color + attenuation * incoming;
+            } else {
+                // Fully absorbed.
+                return color;
+            }
+        }
+
+        // Ray escaped scene, sun weighted against explicit light sample of previous bounce.
+        escaped_radiance(ray, scene, previous.map(|(_, pdf)| pdf))
+    }
+}
+
+impl Default for PathTracer {
+    /// Default path tracer with max depth 10.
+    fn default() -> Self {
+        PathTracer::new(10)
+    }
+}
+
+impl Estimator for PathTracer {
+    fn radiance(&self, ray: Ray, scene: &Scene, rng: &mut impl Rng) -> Color3f {
+        self.ray_color(ray, self.max_depth, scene, rng, None, None)
+    }
+
+    fn radiance_with_stats(
+        &self,
+        ray: Ray,
+        scene: &Scene,
+        rng: &mut impl Rng,
+        stats: &mut RenderStats,
+    ) -> Color3f {
+        self.ray_color(ray, self.max_depth, scene, rng, None, Some(stats))
+    }
+}
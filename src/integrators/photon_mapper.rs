@@ -0,0 +1,231 @@
+use crate::{
+    base::{
+        color::Color3f,
+        interval::Interval,
+        material::{Interactable, Material},
+        ray::Ray,
+    },
+    integrator::{catcher_shadow, escaped_radiance, power_heuristic, sample_direct, Estimator},
+    scene::Scene,
+};
+use rand::Rng;
+use std::f32::consts::PI;
+
+/// Path tracer taking caustics from a photon map instead, so light focused by glass and mirrors onto diffuse
+/// surfaces converges rather than showing as sparse fireflies. At diffuse bounces, photons near the hit point add
+/// their light, and emitters reached through specular bounces from there are skipped, as their light is in the
+/// photon map already. Everything else is path traced with light sampling, like by the path tracer.
+///
+/// The photon map is built when first needed and kept with the scene, see `Scene::photon_map`. Caustics come out
+/// slightly blurred by the query radius, and only from lights that emit photons: emissive objects, point, spot and
+/// directional lights, and the sun. Caustics of other background light stay path traced.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PhotonMapper {
+    /// Max number of ray bounces into scene, also of photon paths.
+    max_depth: u32,
+
+    /// Count of emitted photons.
+    photons: u32,
+
+    /// Radius of photon queries.
+    radius: f32,
+}
+
+impl PhotonMapper {
+    /// Creates photon mapper with max depth, emitting 200000 photons queried within radius 0.05.
+    pub fn new(max_depth: u32) -> Self {
+        PhotonMapper {
+            max_depth,
+            photons: 200_000,
+            radius: 0.05,
+        }
+    }
+
+    /// Max number of ray bounces into scene, also of photon paths.
+    pub fn max_depth(&self) -> u32 {
+        self.max_depth
+    }
+
+    /// Count of emitted photons.
+    pub fn photons(&self) -> u32 {
+        self.photons
+    }
+
+    /// Sets count of emitted photons. More photons resolve finer caustics with less noise, at the cost of time
+    /// and memory building the map.
+    pub fn set_photons(&mut self, photons: u32) {
+        self.photons = photons;
+    }
+
+    /// Radius of photon queries.
+    pub fn radius(&self) -> f32 {
+        self.radius
+    }
+
+    /// Sets radius of photon queries. Larger radii gather more photons, trading noise for blur.
+    pub fn set_radius(&mut self, radius: f32) {
+        assert!(radius > 0.0);
+        self.radius = radius;
+    }
+}
+
+impl Default for PhotonMapper {
+    /// Default photon mapper with max depth 10.
+    fn default() -> Self {
+        PhotonMapper::new(10)
+    }
+}
+
+impl Estimator for PhotonMapper {
+    fn radiance(&self, mut ray: Ray, scene: &Scene, rng: &mut impl Rng) -> Color3f {
+        let photons = scene.photon_map(self.photons, self.max_depth, self.radius);
+        let area = PI * self.radius * self.radius;
+
+        let mut color = Color3f::black();
+        let mut throughput = Color3f::white();
+        let mut previous = None;
+        // Whether the path bounced diffusely, and only specularly since, thus continues a caustic path.
+        let (mut diffuse, mut caustic) = (false, false);
+        for depth in (1..=self.max_depth).rev() {
+            let Some((object, isect)) =
+                scene.intersect_object(ray, Interval::new(0.0, f32::INFINITY))
+            else {
+                // Ray escaped scene, sun weighted against explicit light sample of previous bounce, or left to
+                // photons if seen through specular bounces from a diffuse one.
+                let mut background = escaped_radiance(ray, scene, previous.map(|(_, pdf)| pdf));
+                if let Some(sky) = scene.sun().filter(|_| caustic) {
+                    if scene.sun_pdf(ray.direction()) > 0.0 {
+                        background -= sky.sun_radiance();
+                    }
+                }
+                return color + throughput * background;
+            };
+            let material = scene.surface_material(object, &isect, rng);
+
+            // Emitted light, weighted against explicit light sample of previous bounce.
+            let mut emitted = material.emitted();
+            if let Some((origin, scattering_pdf)) = previous {
+                if emitted != Color3f::black() {
+                    let light_pdf = scene.light_pdf(object, origin, isect.point, isect.normal);
+                    emitted = emitted * power_heuristic(scattering_pdf, light_pdf);
+                }
+            }
+            if !caustic {
+                color += throughput * emitted;
+            }
+            let Some(iact) = material.interact(ray, isect, rng) else {
+                break;
+            };
+
+            let direction = iact.scattered_ray.direction();
+            let mut attenuation = iact.attenuation;
+            if let Some(scattering_pdf) = material.scattering_pdf(isect, direction) {
+                // Diffuse bounce: direct light, and caustics from photons arriving within radius.
+                let mut caustics = Color3f::black();
+                for photon in photons.gather(isect.point) {
+                    let cos = isect.normal.dot(&photon.direction).abs();
+                    let pdf = material
+                        .scattering_pdf(isect, photon.direction)
+                        .unwrap_or_default();
+                    if cos > 1e-4 {
+                        caustics += photon.power * (pdf / cos);
+                    }
+                }
+                color += throughput * iact.attenuation * (caustics / area);
+                previous = None;
+                if depth > 1 {
+                    color += throughput
+                        * iact.attenuation
+                        * sample_direct(&isect, &material, scene, rng);
+                    previous = Some((isect.point, scattering_pdf));
+                }
+                diffuse = true;
+                caustic = false;
+            } else if let Material::ShadowCatcher(_) = *material {
+                // Ray passes through unchanged, keep light sample weighting of previous bounce.
+                attenuation = attenuation * catcher_shadow(ray, &isect, &material, scene, rng);
+            } else {
+                previous = None;
+                caustic = diffuse;
+            }
+            throughput *= attenuation;
+            ray = iact.scattered_ray;
+        }
+        color
+    }
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        background::Background,
+        base::{light::Light, point::Point3f, shape::Shape, vector::Vector3f},
+        integrator::Integrator,
+        integrators::path_tracer::PathTracer,
+        lights::directional::DirectionalLight,
+        materials::{diffuse_light::DiffuseLight, lambert::Lambert},
+        shapes::sphere::Sphere,
+    };
+    use rand::{rngs::StdRng, SeedableRng};
+
+    /// Mean radiance of estimator along ray.
+    fn mean_radiance(estimator: &impl Estimator, ray: Ray, scene: &Scene, n: u32) -> Color3f {
+        let mut rng = StdRng::seed_from_u64(7);
+        let sum = (0..n).fold(Color3f::black(), |acc, _| {
+            acc + estimator.radiance(ray, scene, &mut rng)
+        });
+        sum / n as f32
+    }
+
+    #[test]
+    fn diffuse() {
+        // Without specular objects, photon mapping is path tracing.
+        let mut scene = Scene::new();
+        let gray = Material::Lambert(Lambert::new(Color3f::new(0.5, 0.5, 0.5)));
+        scene.add(Shape::preset("ground", gray).unwrap());
+        let light = Material::DiffuseLight(DiffuseLight::new(Color3f::white() * 4.0));
+        scene.add(Shape::Sphere(Sphere::new(
+            Point3f::new(0.0, 2.0, 0.0),
+            0.5,
+            light,
+        )));
+        let ray = Ray::new(Point3f::new(-1.0, 1.0, 0.0), Vector3f::new(1.0, -1.0, 0.0));
+        let expected = mean_radiance(
+            &Integrator::PathTracer(PathTracer::new(4)),
+            ray,
+            &scene,
+            4000,
+        );
+        let radiance = mean_radiance(&PhotonMapper::new(4), ray, &scene, 4000);
+        assert!((radiance.g() - expected.g()).abs() / expected.g() < 0.05);
+    }
+
+    #[test]
+    fn caustic() {
+        // Glass ball focuses sunlight onto white ground below, which path tracing cannot find.
+        let mut scene = Scene::new();
+        let white = Material::Lambert(Lambert::new(Color3f::white()));
+        scene.add(Shape::preset("ground", white).unwrap());
+        let glass = Material::preset("glass").unwrap();
+        scene.add(Shape::Sphere(Sphere::new(
+            Point3f::new(0.0, 1.0, 0.0),
+            0.5,
+            glass,
+        )));
+        let sun = DirectionalLight::new(Vector3f::new(0.0, -1.0, 0.0), Color3f::white());
+        scene.add_light(Light::Directional(sun));
+        scene.set_background(Background::Uniform(Color3f::black()));
+
+        // Ground outside the shadow reflects the sun's irradiance over pi, plus little light reflected off the ball.
+        let mut mapper = PhotonMapper::new(8);
+        mapper.set_photons(20000);
+        let lit = Ray::new(Point3f::new(2.0, 1.0, 0.0), Vector3f::new(0.0, -1.0, 0.0));
+        assert!((mean_radiance(&mapper, lit, &scene, 100).g() * PI - 1.0).abs() < 0.1);
+        let focus = Ray::new(Point3f::new(0.0, 0.1, 0.0), Vector3f::new(0.0, -1.0, 0.0));
+        let path_tracer = Integrator::PathTracer(PathTracer::new(8));
+        assert!(mean_radiance(&path_tracer, focus, &scene, 100).g() < 0.05);
+        assert!(mean_radiance(&mapper, focus, &scene, 100).g() > 2.0 / PI);
+    }
+}
@@ -0,0 +1,212 @@
+use crate::{
+    base::{
+        color::Color3f,
+        interval::Interval,
+        material::{Interactable, Material},
+        ray::Ray,
+        vector::Vector3f,
+    },
+    integrator::{catcher_shadow, escaped_radiance, power_heuristic, sample_direct, Estimator},
+    polarization::{self, Mueller},
+    scene::Scene,
+};
+use rand::Rng;
+
+/// Path tracer tracking polarization of light, e.g. to simulate a polarizing filter in front of the camera cutting
+/// reflections off water and glass. Paths carry the product of Mueller matrices of their interactions: dielectrics
+/// and metals polarize by exact Fresnel equations, all other materials depolarize. Emitters and backgrounds are
+/// unpolarized. Lights are sampled explicitly at diffuse bounces like by the path tracer, where light is
+/// depolarized anyway.
+///
+/// Without filter, images differ from those of the path tracer only where exact Fresnel reflectance differs from
+/// Schlick's approximation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PolarizedPathTracer {
+    /// Max number of ray bounces into scene.
+    max_depth: u32,
+
+    /// Angle in degrees of transmission axis of linear polarizing filter in front of camera, if any.
+    filter: Option<f32>,
+}
+
+impl PolarizedPathTracer {
+    /// Creates polarized path tracer with max depth, without filter.
+    pub fn new(max_depth: u32) -> Self {
+        PolarizedPathTracer {
+            max_depth,
+            filter: None,
+        }
+    }
+
+    /// Max number of ray bounces into scene.
+    pub fn max_depth(&self) -> u32 {
+        self.max_depth
+    }
+
+    /// Angle in degrees of transmission axis of linear polarizing filter in front of camera, if any.
+    pub fn filter(&self) -> Option<f32> {
+        self.filter
+    }
+
+    /// Sets angle in degrees of transmission axis of linear polarizing filter in front of camera, counterclockwise
+    /// from horizontal (perpendicular to ray and scene 'up' direction, +y) as seen by camera. Filters at 0 degrees
+    /// cut light reflected off horizontal surfaces like water around Brewster's angle (about 53 degrees from
+    /// vertical) least, at 90 degrees most.
+    pub fn set_filter(&mut self, filter: Option<f32>) {
+        self.filter = filter;
+    }
+}
+
+impl Default for PolarizedPathTracer {
+    /// Default polarized path tracer with max depth 10, without filter.
+    fn default() -> Self {
+        PolarizedPathTracer::new(10)
+    }
+}
+
+impl Estimator for PolarizedPathTracer {
+    fn radiance(&self, mut ray: Ray, scene: &Scene, rng: &mut impl Rng) -> Color3f {
+        // Stokes vectors arriving along ray refer to horizontal direction, or any if ray is vertical.
+        let horizontal = ray.direction().cross(&Vector3f::new(0.0, 1.0, 0.0));
+        let mut reference = if horizontal.length_squared() > 1e-12 {
+            horizontal.normalize()
+        } else {
+            ray.direction().orthonormal_basis().0
+        };
+        let mut throughput = match self.filter {
+            Some(angle) => Mueller::linear_polarizer(angle.to_radians()),
+            None => Mueller::identity(),
+        };
+
+        let mut color = Color3f::black();
+        let mut previous = None;
+        for depth in (1..=self.max_depth).rev() {
+            let Some((object, isect)) =
+                scene.intersect_object(ray, Interval::new(0.0, f32::INFINITY))
+            else {
+                // Ray escaped scene, sun weighted against explicit light sample of previous bounce.
+                let background = escaped_radiance(ray, scene, previous.map(|(_, pdf)| pdf));
+                return color + throughput.apply_unpolarized(background);
+            };
+            let material = scene.surface_material(object, &isect, rng);
+
+            // Emitted light, weighted against explicit light sample of previous bounce.
+            let mut emitted = material.emitted();
+            if let Some((origin, scattering_pdf)) = previous {
+                if emitted != Color3f::black() {
+                    let light_pdf = scene.light_pdf(object, origin, isect.point, isect.normal);
+                    emitted = emitted * power_heuristic(scattering_pdf, light_pdf);
+                }
+            }
+            color += throughput.apply_unpolarized(emitted);
+            let Some(iact) = material.interact(ray, isect, rng) else {
+                break;
+            };
+
+            // Polarizing interaction in frame of plane of incidence, or depolarizing scattering.
+            let scattering_pdf = material.scattering_pdf(isect, iact.scattered_ray.direction());
+            if let Some((mueller, perpendicular)) = polarization::scattering(
+                &material,
+                ray,
+                &isect,
+                iact.scattered_ray,
+                iact.attenuation,
+            ) {
+                let angle =
+                    polarization::reference_angle(ray.direction(), reference, perpendicular);
+                throughput = throughput
+                    .compose(&Mueller::rotation(-angle))
+                    .compose(&mueller);
+                reference = perpendicular;
+                previous = None;
+            } else if let Material::ShadowCatcher(_) = *material {
+                // Ray passes through unchanged, keep light sample weighting of previous bounce.
+                let shadow = catcher_shadow(ray, &isect, &material, scene, rng);
+                throughput = throughput.attenuate(iact.attenuation * shadow);
+            } else {
+                throughput = throughput.compose(&Mueller::depolarizer(iact.attenuation));
+                previous = None;
+                if let Some(scattering_pdf) = scattering_pdf.filter(|_| depth > 1) {
+                    let direct = sample_direct(&isect, &material, scene, rng);
+                    color += throughput.apply_unpolarized(direct);
+                    previous = Some((isect.point, scattering_pdf));
+                }
+            }
+            ray = iact.scattered_ray;
+        }
+        color
+    }
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        background::Background,
+        base::{point::Point3f, shape::Shape},
+        integrator::Integrator,
+        integrators::path_tracer::PathTracer,
+        materials::{dielectric::Dielectric, lambert::Lambert},
+    };
+    use rand::{rngs::StdRng, SeedableRng};
+
+    /// Mean radiance of estimator along ray.
+    fn mean_radiance(estimator: &impl Estimator, ray: Ray, scene: &Scene, n: u32) -> Color3f {
+        let mut rng = StdRng::seed_from_u64(7);
+        let sum = (0..n).fold(Color3f::black(), |acc, _| {
+            acc + estimator.radiance(ray, scene, &mut rng)
+        });
+        sum / n as f32
+    }
+
+    #[test]
+    fn brewster() {
+        // Glass ground under white sky, seen at Brewster's angle. Refracted light is lost in the ground.
+        let mut scene = Scene::new();
+        scene.set_background(Background::Uniform(Color3f::white()));
+        let glass = Material::Dielectric(Dielectric::new(1.5));
+        scene.add(Shape::preset("ground", glass).unwrap());
+        let angle = 1.5f32.atan();
+        let direction = Vector3f::new(angle.sin(), -angle.cos(), 0.0);
+        let ray = Ray::new(Point3f::new(0.0, 1.0, 0.0) - direction, direction);
+
+        // Reflection is s-polarized, thus horizontal: passes horizontal filters fully, diagonal ones at half, vertical
+        // ones not at all.
+        let mut tracer = PolarizedPathTracer::new(2);
+        let reflectance =
+            Mueller::dielectric_reflection(angle.cos(), 1.5).apply_unpolarized(Color3f::white());
+        let unfiltered = mean_radiance(&tracer, ray, &scene, 20000);
+        assert!((unfiltered.g() - reflectance.g()).abs() / reflectance.g() < 0.05);
+        tracer.set_filter(Some(0.0));
+        let horizontal = mean_radiance(&tracer, ray, &scene, 20000);
+        assert!((horizontal.g() - reflectance.g()).abs() / reflectance.g() < 0.05);
+        tracer.set_filter(Some(90.0));
+        assert!(mean_radiance(&tracer, ray, &scene, 20000).g() < 1e-4);
+        tracer.set_filter(Some(45.0));
+        let diagonal = mean_radiance(&tracer, ray, &scene, 20000);
+        assert!((diagonal.g() - 0.5 * reflectance.g()).abs() / reflectance.g() < 0.05);
+    }
+
+    #[test]
+    fn diffuse() {
+        // Diffuse scenes match the path tracer, filters halve them.
+        let mut scene = Scene::new();
+        scene.set_background(Background::Uniform(Color3f::white()));
+        let gray = Material::Lambert(Lambert::new(Color3f::new(0.5, 0.5, 0.5)));
+        scene.add(Shape::preset("ground", gray).unwrap());
+        let ray = Ray::new(Point3f::new(0.0, 1.0, 0.0), Vector3f::new(1.0, -1.0, 0.0));
+        let expected = mean_radiance(
+            &Integrator::PathTracer(PathTracer::new(4)),
+            ray,
+            &scene,
+            4000,
+        );
+        let mut tracer = PolarizedPathTracer::new(4);
+        let radiance = mean_radiance(&tracer, ray, &scene, 4000);
+        assert!((radiance.g() - expected.g()).abs() / expected.g() < 0.05);
+        tracer.set_filter(Some(30.0));
+        let filtered = mean_radiance(&tracer, ray, &scene, 4000);
+        assert!((filtered.g() - 0.5 * expected.g()).abs() / expected.g() < 0.05);
+    }
+}
@@ -0,0 +1,147 @@
+use crate::{
+    base::{
+        color::Color3f,
+        interval::Interval,
+        onb::Onb,
+        point::Point,
+        ray::Ray,
+        sampling::cosine_hemisphere,
+        shape::{Intersectable, Intersection},
+        vector::Vector,
+    },
+    integrator::Estimator,
+    scene::Scene,
+};
+use rand::Rng;
+
+/// Min ray parameter of reference intersections relative to the distance of the ray origin from the world origin (at
+/// least one), well above double precision rounding, so reference rays leaving the exact hit point miss its surface.
+const REFERENCE_T_MIN: f64 = 1e-9;
+
+/// Debug integrator comparing fast single precision intersections with double precision reference intersections
+/// (see `Shape::intersect_precise`), to diagnose shadow acne and light leaks. Checks camera ray and a cosine-weighted
+/// ray leaving the first hit, which starts at the reference hit point for the reference. Slow, since the reference
+/// intersects all objects.
+///
+/// Consistent pixels are gray, shaded by incidence angle, misses are black. Pixels are red where the fast path hits
+/// surfaces earlier than the reference or hits nothing there (acne, e.g. rays leaving surfaces hitting them again),
+/// and blue where it hits later or misses (leaks). Averaged pixels show the fraction of discrepant samples.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PrecisionDebug {
+    /// Max difference of ray parameters counted as consistent, relative to the reference parameter (at least one).
+    tolerance: f32,
+}
+
+impl PrecisionDebug {
+    /// Creates precision debug integrator with max relative difference of consistent ray parameters.
+    pub fn new(tolerance: f32) -> Self {
+        assert!(tolerance > 0.0);
+        PrecisionDebug { tolerance }
+    }
+
+    /// Compares fast and reference intersection of ray, whose reference origin is given in double precision.
+    /// Returns discrepancy color, if any, fast intersection and reference ray parameter.
+    fn check<'a>(
+        &self,
+        ray: Ray,
+        origin: Point<f64, 3>,
+        scene: &'a Scene,
+    ) -> (Option<Color3f>, Option<Intersection<'a>>, Option<f64>) {
+        let fast = scene.intersect(ray, Interval::new(0.0, f32::INFINITY));
+        let direction: Vector<f64, 3> = ray.direction().cast();
+        let scale = (origin - Point::default()).length().max(1.0);
+        let ray_t = Interval::new(REFERENCE_T_MIN * scale, f64::INFINITY);
+        let reference = scene
+            .objects()
+            .iter()
+            .filter_map(|object| object.intersect_precise(origin, direction, ray_t))
+            .reduce(f64::min);
+        let (red, blue) = (Color3f::new(1.0, 0.0, 0.0), Color3f::new(0.0, 0.0, 1.0));
+        let discrepancy = match (fast.as_ref().map(|isect| isect.t), reference) {
+            (None, None) => None,
+            (Some(_), None) => Some(red),
+            (None, Some(_)) => Some(blue),
+            (Some(f), Some(r)) => {
+                let difference = f as f64 - r;
+                if difference.abs() <= self.tolerance as f64 * r.abs().max(1.0) {
+                    None
+                } else if difference < 0.0 {
+                    Some(red)
+                } else {
+                    Some(blue)
+                }
+            }
+        };
+        (discrepancy, fast, reference)
+    }
+}
+
+impl Default for PrecisionDebug {
+    /// Default precision debug with tolerance 1e-4.
+    fn default() -> Self {
+        PrecisionDebug::new(1e-4)
+    }
+}
+
+impl Estimator for PrecisionDebug {
+    fn radiance(&self, ray: Ray, scene: &Scene, rng: &mut impl Rng) -> Color3f {
+        // Camera ray.
+        let origin = ray.origin().cast();
+        let (isect, t) = match self.check(ray, origin, scene) {
+            (Some(color), _, _) => return color,
+            (None, Some(isect), Some(t)) => (isect, t),
+            _ => return Color3f::black(),
+        };
+
+        // Ray leaving surface on side of camera, spawned off the surface for the fast path but starting at the
+        // reference hit point for the reference.
+        let mut normal = isect.normal;
+        if normal.dot(&ray.direction()) > 0.0 {
+            normal = -normal;
+        }
+        let direction = Onb::new(normal).to_world(cosine_hemisphere(rng));
+        let reference_point = origin + ray.direction().cast::<f64>() * t;
+        let (discrepancy, _, _) = self.check(isect.spawn_ray(direction), reference_point, scene);
+        discrepancy.unwrap_or_else(|| {
+            let cos = normal.dot(&-ray.direction().normalize());
+            Color3f::white() * (0.25 + 0.5 * cos)
+        })
+    }
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        base::{material::Material, point::Point3f, shape::Shape, vector::Vector3f},
+        materials::lambert::Lambert,
+        shapes::sphere::Sphere,
+    };
+    use rand::{rngs::StdRng, SeedableRng};
+
+    /// Fraction of red samples of precision debug along ray down onto ground sphere of radius.
+    fn acne(radius: f32) -> f32 {
+        let mut scene = Scene::new();
+        let material = Material::Lambert(Lambert::new(Color3f::white()));
+        let center = Point3f::new(0.0, -radius, 0.0);
+        scene.add(Shape::Sphere(Sphere::new(center, radius, material)));
+        let ray = Ray::new(Point3f::new(0.3, 1.0, 0.1), Vector3f::new(0.1, -1.0, 0.2));
+        let mut rng = StdRng::seed_from_u64(42);
+        let n = 1000;
+        let red = (0..n)
+            .filter(|_| {
+                PrecisionDebug::default().radiance(ray, &scene, &mut rng)
+                    == Color3f::new(1.0, 0.0, 0.0)
+            })
+            .count();
+        red as f32 / n as f32
+    }
+
+    #[test]
+    fn radiance() {
+        // Ground spheres are consistent, even huge ones.
+        assert_eq!(acne(1000.0), 0.0);
+        assert_eq!(acne(1e6), 0.0);
+    }
+}
@@ -0,0 +1,28 @@
+use crate::{
+    base::{color::Color3f, interval::Interval, ray::Ray, shape::Intersectable},
+    integrator::Estimator,
+    scene::Scene,
+};
+use rand::Rng;
+
+/// Count of checker squares per unit of texture coordinates.
+const CHECKERS: f32 = 8.0;
+
+/// Debug integrator showing texture coordinates of surfaces, after UV transforms, as red (u) and green (v) over a
+/// checker pattern revealing tiling, stretching and rotation. Misses are black.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct UvDebug;
+
+impl Estimator for UvDebug {
+    fn radiance(&self, ray: Ray, scene: &Scene, _rng: &mut impl Rng) -> Color3f {
+        match scene.intersect(ray, Interval::new(0.0, f32::INFINITY)) {
+            Some(isect) => {
+                let (u, v) = isect.uv;
+                let checker = ((u * CHECKERS).floor() + (v * CHECKERS).floor()) as i32 % 2 == 0;
+                let brightness = if checker { 1.0 } else { 0.5 };
+                Color3f::new(u, v, 0.0) * brightness
+            }
+            None => Color3f::black(),
+        }
+    }
+}
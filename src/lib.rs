@@ -0,0 +1,53 @@
+//! Physically based raytracer: scenes of shapes with materials, rendered by a camera with a choice of integrators.
+//!
+//! # Stability
+//!
+//! Types re-exported by `prelude` form the stable API: while the version is 0.x, breaking changes to them only come
+//! with a minor version bump, and from 1.0 on with a major one. The remaining public modules are usable, but may
+//! change in any release. Helpers internal to the renderer are not public.
+
+pub mod animation;
+pub mod background;
+pub mod base;
+pub mod benchmark;
+pub mod bvh;
+pub mod camera;
+pub mod catalog;
+pub mod checkpoint;
+pub mod comparison;
+pub mod decal;
+pub mod denoise;
+pub mod distributed;
+pub mod expression;
+pub mod film;
+pub(crate) mod font;
+pub mod formats;
+#[cfg(feature = "gpu")]
+pub mod gpu;
+pub mod image;
+pub mod integrator;
+pub mod integrators;
+pub mod lights;
+pub mod lint;
+pub mod material_library;
+pub mod materials;
+pub mod noise;
+pub mod output;
+pub mod photon_map;
+pub mod polarization;
+pub mod prelude;
+#[cfg(feature = "preview")]
+pub mod preview;
+pub mod sampler;
+pub mod scene;
+#[cfg(feature = "scripting")]
+pub mod script;
+pub mod settings;
+pub mod shapes;
+pub mod sky;
+pub mod stats;
+pub mod stream;
+pub mod studio;
+pub mod tile;
+pub mod tonemap;
+pub mod visibility;
@@ -0,0 +1,3 @@
+pub mod directional;
+pub mod point;
+pub mod spot;
@@ -0,0 +1,46 @@
+use crate::base::{
+    color::Color3f,
+    light::{Illuminating, Illumination},
+    point::Point3f,
+    vector::Vector3f,
+};
+
+/// Light arriving from infinitely far away along a single direction, like sunlight.
+#[derive(Clone, Copy, Debug, Hash, PartialEq)]
+pub struct DirectionalLight {
+    /// Unit direction the light travels in.
+    direction: Vector3f,
+
+    /// Irradiance on surfaces facing the light.
+    irradiance: Color3f,
+}
+
+impl DirectionalLight {
+    /// Creates light travelling in direction, which need not be unit length, with irradiance on surfaces facing it.
+    pub fn new(direction: Vector3f, irradiance: Color3f) -> Self {
+        DirectionalLight {
+            direction: direction.normalize(),
+            irradiance,
+        }
+    }
+
+    /// Unit direction the light travels in.
+    pub fn direction(&self) -> Vector3f {
+        self.direction
+    }
+
+    /// Irradiance on surfaces facing the light.
+    pub fn irradiance(&self) -> Color3f {
+        self.irradiance
+    }
+}
+
+impl Illuminating for DirectionalLight {
+    fn illuminate(&self, _reference: Point3f) -> Option<Illumination> {
+        Some(Illumination {
+            direction: -self.direction,
+            distance: f32::INFINITY,
+            irradiance: self.irradiance,
+        })
+    }
+}
@@ -0,0 +1,67 @@
+use crate::base::{
+    color::Color3f,
+    light::{Illuminating, Illumination},
+    point::Point3f,
+};
+
+/// Light emitted uniformly in all directions from a point, falling off with squared distance.
+#[derive(Clone, Copy, Debug, Hash, PartialEq)]
+pub struct PointLight {
+    /// Position of light.
+    position: Point3f,
+
+    /// Radiant intensity, i.e. irradiance at unit distance.
+    intensity: Color3f,
+}
+
+impl PointLight {
+    /// Creates point light at position with radiant intensity.
+    pub fn new(position: Point3f, intensity: Color3f) -> Self {
+        PointLight {
+            position,
+            intensity,
+        }
+    }
+
+    /// Position of light.
+    pub fn position(&self) -> Point3f {
+        self.position
+    }
+
+    /// Radiant intensity, i.e. irradiance at unit distance.
+    pub fn intensity(&self) -> Color3f {
+        self.intensity
+    }
+}
+
+impl Illuminating for PointLight {
+    fn illuminate(&self, reference: Point3f) -> Option<Illumination> {
+        let to_light = self.position - reference;
+        let distance = to_light.length();
+        if distance <= 0.0 {
+            return None;
+        }
+        Some(Illumination {
+            direction: to_light / distance,
+            distance,
+            irradiance: self.intensity / (distance * distance),
+        })
+    }
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::vector::Vector3f;
+
+    #[test]
+    fn illuminate() {
+        let light = PointLight::new(Point3f::new(0.0, 2.0, 0.0), Color3f::new(4.0, 4.0, 4.0));
+        let i = light.illuminate(Point3f::default()).unwrap();
+        assert_eq!(i.direction, Vector3f::new(0.0, 1.0, 0.0));
+        assert_eq!(i.distance, 2.0);
+        assert_eq!(i.irradiance, Color3f::white());
+        assert_eq!(light.illuminate(light.position()), None);
+    }
+}
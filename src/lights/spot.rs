@@ -0,0 +1,143 @@
+use crate::base::{
+    color::Color3f,
+    hash::hash_float,
+    light::{Illuminating, Illumination},
+    point::Point3f,
+    vector::Vector3f,
+};
+use std::hash::{Hash, Hasher};
+
+/// Point light emitting into a cone, with a soft edge blending from full intensity to darkness.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SpotLight {
+    /// Position of light.
+    position: Point3f,
+
+    /// Unit direction of cone axis.
+    direction: Vector3f,
+
+    /// Radiant intensity along cone axis.
+    intensity: Color3f,
+
+    /// Full opening angle of cone in degrees, in (0,180].
+    angle: f32,
+
+    /// Fraction of cone angle over which the edge blends to darkness, in [0,1].
+    blend: f32,
+}
+
+impl SpotLight {
+    /// Creates spot light at position pointing in direction, which need not be unit length, with radiant intensity
+    /// and full opening angle of cone in degrees, clamped to (0,180]. The edge blends over 15 % of the cone.
+    pub fn new(position: Point3f, direction: Vector3f, intensity: Color3f, angle: f32) -> Self {
+        SpotLight {
+            position,
+            direction: direction.normalize(),
+            intensity,
+            angle: angle.clamp(1e-3, 180.0),
+            blend: 0.15,
+        }
+    }
+
+    /// Position of light.
+    pub fn position(&self) -> Point3f {
+        self.position
+    }
+
+    /// Unit direction of cone axis.
+    pub fn direction(&self) -> Vector3f {
+        self.direction
+    }
+
+    /// Radiant intensity along cone axis.
+    pub fn intensity(&self) -> Color3f {
+        self.intensity
+    }
+
+    /// Full opening angle of cone in degrees.
+    pub fn angle(&self) -> f32 {
+        self.angle
+    }
+
+    /// Fraction of cone angle over which the edge blends to darkness.
+    pub fn blend(&self) -> f32 {
+        self.blend
+    }
+
+    /// Sets fraction of cone angle over which the edge blends to darkness, clamped to [0,1]. Zero gives hard edges.
+    pub fn set_blend(&mut self, blend: f32) {
+        self.blend = blend.clamp(0.0, 1.0);
+    }
+
+    /// Intensity factor in [0,1] at angle to cone axis in degrees, smoothly falling off over the blended edge.
+    fn falloff(&self, angle: f32) -> f32 {
+        let outer = self.angle / 2.0;
+        let inner = outer * (1.0 - self.blend);
+        if angle >= outer {
+            0.0
+        } else if angle <= inner {
+            1.0
+        } else {
+            let x = (outer - angle) / (outer - inner);
+            x * x * (3.0 - 2.0 * x)
+        }
+    }
+}
+
+impl Hash for SpotLight {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.position.hash(state);
+        self.direction.hash(state);
+        self.intensity.hash(state);
+        hash_float(self.angle, state);
+        hash_float(self.blend, state);
+    }
+}
+
+impl Illuminating for SpotLight {
+    fn illuminate(&self, reference: Point3f) -> Option<Illumination> {
+        let to_light = self.position - reference;
+        let distance = to_light.length();
+        if distance <= 0.0 {
+            return None;
+        }
+        let direction = to_light / distance;
+        let cos = (-direction).dot(&self.direction).clamp(-1.0, 1.0);
+        let falloff = self.falloff(cos.acos().to_degrees());
+        if falloff <= 0.0 {
+            return None;
+        }
+        Some(Illumination {
+            direction,
+            distance,
+            irradiance: self.intensity * (falloff / (distance * distance)),
+        })
+    }
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn illuminate() {
+        let down = Vector3f::new(0.0, -1.0, 0.0);
+        let mut light = SpotLight::new(Point3f::new(0.0, 1.0, 0.0), down, Color3f::white(), 90.0);
+        let lit = |light: &SpotLight, x| light.illuminate(Point3f::new(x, 0.0, 0.0));
+
+        // Full intensity inside, soft edge around 45 degrees, dark outside.
+        let i = lit(&light, 0.0).unwrap();
+        assert_eq!(i.direction, Vector3f::new(0.0, 1.0, 0.0));
+        assert_eq!(i.irradiance, Color3f::white());
+        let edge = lit(&light, 0.95).unwrap().irradiance.r() * (1.0 + 0.95 * 0.95);
+        assert!(edge > 0.0 && edge < 1.0);
+        assert_eq!(lit(&light, 1.1), None);
+
+        // Hard edge.
+        light.set_blend(0.0);
+        assert!(
+            (lit(&light, 0.95).unwrap().irradiance.r() * (1.0 + 0.95 * 0.95) - 1.0).abs() < 1e-5
+        );
+    }
+}
@@ -0,0 +1,437 @@
+use crate::{
+    base::{
+        color::Color3f,
+        light::Light,
+        material::Material,
+        point::Point3f,
+        shape::{Bounded, Sampleable, Shape},
+    },
+    camera::Camera,
+    materials::{lambert::Lambert, metal::Metal},
+    scene::Scene,
+    shapes::csg::contains,
+};
+use rand::{rngs::StdRng, SeedableRng};
+use std::fmt;
+
+/// Surface points sampled per object to test whether it overlaps or lies inside another one.
+const SAMPLES: usize = 64;
+
+/// Albedo given to black diffuse and metal materials when fixed, about that of charcoal.
+const MIN_ALBEDO: f32 = 0.04;
+
+/// Common problem of a scene, which renders black or wrong without an error. Objects and lights are given by index.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Warning {
+    /// Dielectric objects overlap. Rays refract at both surfaces within the shared volume, as dielectrics have no
+    /// priorities deciding which medium fills it.
+    OverlappingDielectrics { first: usize, second: usize },
+
+    /// Emissive object lies inside opaque object, so its light never leaves it.
+    EmitterInsideObject { emitter: usize, object: usize },
+
+    /// Analytic light lies inside opaque object, so its light never leaves it.
+    LightInsideObject { light: usize, object: usize },
+
+    /// Diffuse, metal or opaque dielectric principled material of object is black, so it absorbs all light except
+    /// for specular highlights. Fixable.
+    ZeroAlbedo { object: usize },
+
+    /// Camera lies inside opaque object, so the image is black.
+    CameraInsideObject { object: usize },
+
+    /// Object has non-finite coordinates, e.g. by NaN transforms, so it is never hit.
+    InvalidGeometry { object: usize },
+
+    /// Object has no surface area, e.g. a sphere of zero radius, so it is never hit.
+    DegenerateGeometry { object: usize },
+
+    /// Surfaces of objects cross each other, so shading along the crossing depends on which one a ray hits first,
+    /// e.g. showing speckles or leaking shadows. Overlapping dielectrics are reported as such.
+    InterpenetratingObjects { first: usize, second: usize },
+}
+
+impl Warning {
+    /// Whether `fix` repairs the problem.
+    pub fn is_fixable(&self) -> bool {
+        matches!(self, Warning::ZeroAlbedo { .. })
+    }
+
+    /// Objects the problem concerns, e.g. to name them in reports.
+    pub fn objects(&self) -> Vec<usize> {
+        match *self {
+            Warning::OverlappingDielectrics { first, second }
+            | Warning::InterpenetratingObjects { first, second } => vec![first, second],
+            Warning::EmitterInsideObject { emitter, object } => vec![emitter, object],
+            Warning::LightInsideObject { object, .. }
+            | Warning::ZeroAlbedo { object }
+            | Warning::CameraInsideObject { object }
+            | Warning::InvalidGeometry { object }
+            | Warning::DegenerateGeometry { object } => vec![object],
+        }
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Warning::OverlappingDielectrics { first, second } => {
+                write!(f, "dielectric objects {} and {} overlap", first, second)
+            }
+            Warning::EmitterInsideObject { emitter, object } => {
+                write!(
+                    f,
+                    "emissive object {} lies inside object {}",
+                    emitter, object
+                )
+            }
+            Warning::LightInsideObject { light, object } => {
+                write!(f, "light {} lies inside object {}", light, object)
+            }
+            Warning::ZeroAlbedo { object } => write!(f, "object {} has zero albedo", object),
+            Warning::CameraInsideObject { object } => {
+                write!(f, "camera lies inside object {}", object)
+            }
+            Warning::InvalidGeometry { object } => {
+                write!(f, "object {} has non-finite coordinates", object)
+            }
+            Warning::DegenerateGeometry { object } => {
+                write!(f, "object {} has no surface area", object)
+            }
+            Warning::InterpenetratingObjects { first, second } => {
+                write!(f, "objects {} and {} interpenetrate", first, second)
+            }
+        }
+    }
+}
+
+/// Checks scene, and camera if given, for common problems. Containment is tested at surface points sampled from a
+/// fixed seed, so results are reproducible. Objects with invalid or degenerate geometry are excluded from other
+/// checks.
+pub fn lint(scene: &Scene, camera: Option<&Camera>) -> Vec<Warning> {
+    let mut warnings = Vec::new();
+    let objects = scene.objects();
+    let finite: Vec<bool> = objects.iter().map(is_finite).collect();
+    let valid: Vec<bool> = objects
+        .iter()
+        .zip(&finite)
+        .map(|(shape, &finite)| finite && shape.area() > 0.0)
+        .collect();
+    for object in 0..objects.len() {
+        if !finite[object] {
+            warnings.push(Warning::InvalidGeometry { object });
+        } else if !valid[object] {
+            warnings.push(Warning::DegenerateGeometry { object });
+        }
+        if albedo(scene.material(object)) == Some(Color3f::black()) {
+            warnings.push(Warning::ZeroAlbedo { object });
+        }
+    }
+
+    // Overlaps of dielectrics, either way round.
+    let dielectrics: Vec<usize> = (0..objects.len())
+        .filter(|&i| valid[i] && matches!(scene.material(i), Material::Dielectric(_)))
+        .collect();
+    for (k, &first) in dielectrics.iter().enumerate() {
+        for &second in &dielectrics[k + 1..] {
+            let (a, b) = (&objects[first], &objects[second]);
+            if a.bounding_box().intersection(&b.bounding_box()).is_some()
+                && (any_inside(a, b) || any_inside(b, a))
+            {
+                warnings.push(Warning::OverlappingDielectrics { first, second });
+            }
+        }
+    }
+
+    // Crossing surfaces of other pairs, where some but not all sampled points of either lie inside the other.
+    for first in (0..objects.len()).filter(|&i| valid[i]) {
+        for second in (first + 1..objects.len()).filter(|&i| valid[i]) {
+            let (a, b) = (&objects[first], &objects[second]);
+            if !(dielectrics.contains(&first) && dielectrics.contains(&second))
+                && a.bounding_box().intersection(&b.bounding_box()).is_some()
+                && (partly_inside(a, b) || partly_inside(b, a))
+            {
+                warnings.push(Warning::InterpenetratingObjects { first, second });
+            }
+        }
+    }
+
+    // Lights and camera enclosed by opaque objects.
+    let opaque = |i: usize| valid[i] && !matches!(scene.material(i), Material::Dielectric(_));
+    for &emitter in scene.lights() {
+        let enclosing = (0..objects.len()).find(|&object| {
+            object != emitter && opaque(object) && all_inside(&objects[emitter], &objects[object])
+        });
+        if let Some(object) = enclosing.filter(|_| valid[emitter]) {
+            warnings.push(Warning::EmitterInsideObject { emitter, object });
+        }
+    }
+    for (light, l) in scene.analytic_lights().iter().enumerate() {
+        let position = match l {
+            Light::Directional(_) => continue,
+            Light::Point(p) => p.position(),
+            Light::Spot(s) => s.position(),
+        };
+        if let Some(object) = enclosing_object(objects, position, &opaque) {
+            warnings.push(Warning::LightInsideObject { light, object });
+        }
+    }
+    if let Some(camera) = camera {
+        if let Some(object) = enclosing_object(objects, camera.look_from(), &opaque) {
+            warnings.push(Warning::CameraInsideObject { object });
+        }
+    }
+    warnings
+}
+
+/// Repairs fixable problems of scene: black diffuse, metal and principled materials become dark grey. Named
+/// materials are redefined, repairing all objects bound to them. Returns the warnings fixed.
+pub fn fix(scene: &mut Scene) -> Vec<Warning> {
+    let fixed: Vec<Warning> = lint(scene, None)
+        .into_iter()
+        .filter(Warning::is_fixable)
+        .collect();
+    let grey = Color3f::new(MIN_ALBEDO, MIN_ALBEDO, MIN_ALBEDO);
+    for warning in &fixed {
+        if let Warning::ZeroAlbedo { object } = *warning {
+            let material = match scene.material(object) {
+                Material::Metal(m) => Material::Metal(Metal::new(grey, m.fuzz())),
+                Material::Principled(mut p) => {
+                    p.set_base_color(grey);
+                    Material::Principled(p)
+                }
+                _ => Material::Lambert(Lambert::new(grey)),
+            };
+            match scene.material_binding(object) {
+                Some(handle) => {
+                    let name = scene.library().name(handle).to_string();
+                    scene.define_material(&name, material);
+                }
+                None => {
+                    scene.set_material(scene.object_id(object), material);
+                }
+            }
+        }
+    }
+    fixed
+}
+
+/// Albedo of diffuse, metal and opaque dielectric principled materials, the ones which turn black without it.
+/// Principled materials import e.g. usda preview surfaces.
+fn albedo(material: &Material) -> Option<Color3f> {
+    match material {
+        Material::Lambert(l) => Some(l.albedo()),
+        Material::Metal(m) => Some(m.albedo()),
+        Material::Principled(p) if p.metallic() == 0.0 && p.transmission() == 0.0 => {
+            Some(p.base_color())
+        }
+        _ => None,
+    }
+}
+
+/// Whether shape has finite bounds.
+fn is_finite(shape: &Shape) -> bool {
+    let b = shape.bounding_box();
+    [b.min(), b.max()]
+        .iter()
+        .all(|p| p.x().is_finite() && p.y().is_finite() && p.z().is_finite())
+}
+
+/// Whether any sampled surface point of shape lies inside other shape.
+fn any_inside(shape: &Shape, other: &Shape) -> bool {
+    let mut rng = StdRng::seed_from_u64(0);
+    (0..SAMPLES).any(|_| contains(other, shape.sample_point(&mut rng).point))
+}
+
+/// Whether all sampled surface points of shape lie inside other shape.
+fn all_inside(shape: &Shape, other: &Shape) -> bool {
+    let mut rng = StdRng::seed_from_u64(0);
+    (0..SAMPLES).all(|_| contains(other, shape.sample_point(&mut rng).point))
+}
+
+/// Whether some but not all sampled surface points of shape lie inside other shape.
+fn partly_inside(shape: &Shape, other: &Shape) -> bool {
+    let mut rng = StdRng::seed_from_u64(0);
+    let inside = (0..SAMPLES)
+        .filter(|_| contains(other, shape.sample_point(&mut rng).point))
+        .count();
+    inside > 0 && inside < SAMPLES
+}
+
+/// Index of first object passing filter which contains point.
+fn enclosing_object(
+    objects: &[Shape],
+    point: Point3f,
+    filter: &impl Fn(usize) -> bool,
+) -> Option<usize> {
+    (0..objects.len()).find(|&i| filter(i) && contains(&objects[i], point))
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        lights::point::PointLight,
+        materials::{dielectric::Dielectric, diffuse_light::DiffuseLight, principled::Principled},
+        shapes::sphere::Sphere,
+    };
+
+    #[test]
+    fn lint() {
+        let mut scene = Scene::new();
+        let sphere = |x: f32, r, material| {
+            Shape::Sphere(Sphere::new(Point3f::new(x, 0.0, 0.0), r, material))
+        };
+        let glass = || Material::Dielectric(Dielectric::new(1.5));
+        let grey = Material::Lambert(Lambert::new(Color3f::new(0.5, 0.5, 0.5)));
+        let light = Material::DiffuseLight(DiffuseLight::new(Color3f::new(4.0, 4.0, 4.0)));
+        scene.add(sphere(0.0, 1.0, glass()));
+        scene.add(sphere(1.5, 1.0, glass()));
+        scene.add(sphere(10.0, 2.0, grey.clone()));
+        scene.add(sphere(10.0, 0.5, light));
+        scene.add(sphere(
+            -10.0,
+            1.0,
+            Material::Lambert(Lambert::new(Color3f::black())),
+        ));
+        scene.add(sphere(f32::NAN, 1.0, grey));
+        scene.add_light(Light::Point(PointLight::new(
+            Point3f::new(10.0, 1.0, 0.0),
+            Color3f::new(1.0, 1.0, 1.0),
+        )));
+        let mut camera = Camera::new(16, 9);
+        camera.set_look_from(Point3f::new(-10.0, 0.5, 0.0));
+
+        let warnings = super::lint(&scene, Some(&camera));
+        assert_eq!(
+            warnings,
+            [
+                Warning::ZeroAlbedo { object: 4 },
+                Warning::InvalidGeometry { object: 5 },
+                Warning::OverlappingDielectrics {
+                    first: 0,
+                    second: 1
+                },
+                Warning::EmitterInsideObject {
+                    emitter: 3,
+                    object: 2
+                },
+                Warning::LightInsideObject {
+                    light: 0,
+                    object: 2
+                },
+                Warning::CameraInsideObject { object: 4 },
+            ]
+        );
+        assert_eq!(
+            warnings[2].to_string(),
+            "dielectric objects 0 and 1 overlap"
+        );
+
+        // Fixes repair albedo only.
+        assert_eq!(fix(&mut scene), [warnings[0]]);
+        assert_eq!(super::lint(&scene, Some(&camera)), warnings[1..]);
+        assert!(fix(&mut scene).is_empty());
+
+        // Separate dielectrics and empty scene pass.
+        let mut scene = Scene::new();
+        scene.add(sphere(0.0, 1.0, glass()));
+        scene.add(sphere(3.0, 1.0, glass()));
+        assert!(super::lint(&scene, None).is_empty());
+        assert!(super::lint(&Scene::new(), Some(&camera)).is_empty());
+    }
+
+    #[test]
+    fn fix_bound() {
+        let mut scene = Scene::new();
+        let sphere =
+            |x: f32| Shape::Sphere(Sphere::new(Point3f::new(x, 0.0, 0.0), 1.0, Material::None));
+        let (a, b) = (scene.add(sphere(0.0)), scene.add(sphere(3.0)));
+        scene.add(sphere(6.0));
+        let soot = Material::Metal(Metal::new(Color3f::black(), 0.2));
+        let handle = scene.define_material("soot", soot);
+        assert!(scene.bind_material(a, handle) && scene.bind_material(b, handle));
+        assert!(scene.set_material(
+            scene.object_id(2),
+            Material::Lambert(Lambert::new(Color3f::black()))
+        ));
+
+        // Shared named material is repaired for all bound objects, which stay bound.
+        let warnings = super::lint(&scene, None);
+        assert_eq!(warnings.len(), 3);
+        assert_eq!(super::fix(&mut scene), warnings);
+        assert!(super::lint(&scene, None).is_empty());
+        assert_eq!(scene.material_binding(1), Some(handle));
+        let grey = Color3f::new(MIN_ALBEDO, MIN_ALBEDO, MIN_ALBEDO);
+        assert_eq!(
+            *scene.library().get(handle),
+            Material::Metal(Metal::new(grey, 0.2))
+        );
+        assert_eq!(*scene.material(2), Material::Lambert(Lambert::new(grey)));
+    }
+
+    #[test]
+    fn principled() {
+        let mut scene = Scene::new();
+        let black = Principled::new(Color3f::black());
+        let mut metal = black;
+        metal.set_metallic(1.0);
+        let mut glass = black;
+        glass.set_transmission(1.0);
+        for (x, material) in [(0.0, black), (3.0, metal), (6.0, glass)] {
+            let center = Point3f::new(x, 0.0, 0.0);
+            scene.add(Shape::Sphere(Sphere::new(
+                center,
+                1.0,
+                Material::Principled(material),
+            )));
+        }
+
+        // Only opaque dielectric is flagged, fix keeps other parameters.
+        assert_eq!(
+            super::lint(&scene, None),
+            [Warning::ZeroAlbedo { object: 0 }]
+        );
+        super::fix(&mut scene);
+        let Material::Principled(fixed) = *scene.material(0) else {
+            panic!("principled material stays principled");
+        };
+        assert_eq!(
+            fixed.base_color(),
+            Color3f::new(MIN_ALBEDO, MIN_ALBEDO, MIN_ALBEDO)
+        );
+        assert_eq!(fixed.roughness(), black.roughness());
+        assert!(super::lint(&scene, None).is_empty());
+    }
+
+    #[test]
+    fn geometry() {
+        let mut scene = Scene::new();
+        let sphere = |x: f32, r, material| {
+            Shape::Sphere(Sphere::new(Point3f::new(x, 0.0, 0.0), r, material))
+        };
+        let grey = || Material::Lambert(Lambert::new(Color3f::new(0.5, 0.5, 0.5)));
+        let glass = Material::Dielectric(Dielectric::new(1.5));
+        scene.add(sphere(0.0, 1.0, grey()));
+        scene.add(sphere(1.5, 1.0, glass));
+        scene.add(sphere(0.0, 0.5, grey()));
+        scene.add(sphere(5.0, 0.0, grey()));
+        scene.add(sphere(5.0, 1.0, grey()));
+
+        // Nested spheres do not cross, degenerate sphere is skipped by other checks.
+        let warnings = super::lint(&scene, None);
+        assert_eq!(
+            warnings,
+            [
+                Warning::DegenerateGeometry { object: 3 },
+                Warning::InterpenetratingObjects {
+                    first: 0,
+                    second: 1
+                },
+            ]
+        );
+        assert_eq!(warnings[1].objects(), [0, 1]);
+        assert_eq!(warnings[1].to_string(), "objects 0 and 1 interpenetrate");
+    }
+}
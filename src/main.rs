@@ -1,134 +1,1040 @@
-mod base;
-mod camera;
-mod materials;
-mod scene;
-mod shapes;
-
-use crate::{
-    base::{color::Color3f, material::Material, point::Point3f, shape::Shape, vector::Vector3f},
-    camera::Camera,
-    materials::{dielectric::Dielectric, lambert::Lambert, metal::Metal},
-    scene::Scene,
-    shapes::sphere::Sphere,
+use eden::{
+    animation::Animation,
+    base::orientation::Orientation,
+    benchmark::{self, add_cover_spheres, cover_camera, BenchmarkReport, ThroughputReport},
+    camera::{Aperture, AspectPolicy, Projection},
+    catalog,
+    checkpoint::{self, SequenceManifest},
+    comparison::{contact_sheet, render_comparison, Configuration},
+    denoise::{Denoiser, Guides},
+    distributed::{self, DistributedError},
+    expression::FRAME_RATE,
+    formats::{usda, vox},
+    integrators::{
+        ambient_occlusion::AmbientOcclusion, depth_debug::DepthDebug,
+        direct_lighting::DirectLighting, light_visibility::LightVisibility,
+        material_debug::MaterialDebug, normal_debug::NormalDebug, path_tracer::PathTracer,
+        polarized_path_tracer::PolarizedPathTracer, uv_debug::UvDebug,
+    },
+    lint,
+    output::{self, ImageFormat, ImageSink, Tee, Terminal, Thumbnail, ToneMap},
+    prelude::*,
+    sampler::Sampler,
+    shapes::voxels::Voxels,
+    studio,
+    tile::Tile,
+    tonemap::{ToneMapper, ToneMapping},
 };
-use rand::{thread_rng, Rng};
+use queue::{JobState, Queue};
 use std::{
-    fs::File,
-    io::{BufWriter, Write},
+    io::{stdout, Write},
+    net::TcpListener,
+    path::Path,
+    process::Command,
+    sync::atomic::{AtomicUsize, Ordering},
 };
 
+mod queue;
+
+/// Path of render job queue file, in working directory.
+const QUEUE_PATH: &str = "eden-queue.tsv";
+
+/// Samples per pixel rendered between checkpoints.
+const CHECKPOINT_SAMPLES: u32 = 16;
+
+/// Tiles per range dispatched to render workers at once.
+const TILES_PER_RANGE: usize = 16;
+
 /// Entry point.
 fn main() {
-    // Camera.
-    let image_width = 1200;
-    let image_height = 675;
-    let mut camera = Camera::new(image_width, image_height);
-    camera.set_samples_per_pixel(500);
-    camera.set_max_depth(50);
+    // Job queue management, contact sheet, material catalog, lint or benchmark instead of rendering.
+    let mut command_line = std::env::args().skip(1).peekable();
+    match command_line.peek().map(String::as_str) {
+        Some("benchmark") => {
+            command_line.next();
+            benchmark_command(command_line.collect());
+            return;
+        }
+        Some("queue") => {
+            command_line.next();
+            queue_command(command_line.collect());
+            return;
+        }
+        Some("contactsheet") => {
+            command_line.next();
+            contact_sheet_command(command_line.collect());
+            return;
+        }
+        Some("lint") => {
+            command_line.next();
+            lint_command(command_line.collect());
+            return;
+        }
+        Some("materials") => {
+            command_line.next();
+            materials_command(command_line.collect());
+            return;
+        }
+        _ => {}
+    }
+    let args = parse_args(command_line);
 
-    camera.set_vfov(20.0);
-    camera.set_look_from(Point3f::new(13.0, 2.0, 3.0));
-    camera.set_look_at(Point3f::new(0.0, 0.0, 0.0));
-    camera.set_view_up(Vector3f::new(0.0, 1.0, 0.0));
+    // Camera and render settings.
+    let mut camera = cover_camera();
+    let mut settings = cover_settings();
 
-    camera.set_defocus_angle(0.6);
-    camera.set_focus_distance(10.0);
+    // Command line overrides.
+    camera.set_resolution_override(args.resolution, args.aspect);
+    if let Some(view_height) = args.orthographic {
+        camera.set_projection(Projection::Orthographic { view_height });
+    }
+    camera.set_aperture(args.aperture);
+    camera.set_anamorphic_squeeze(args.anamorphic_squeeze);
+    if let Some(preset) = &args.preset {
+        settings = preset.apply(&settings);
+    }
+    if let Some(max_sample_luminance) = args.clamp {
+        settings.set_max_sample_luminance(max_sample_luminance);
+    }
+    if let Some(factor) = args.reject_outliers {
+        settings.set_outlier_rejection(Some(factor));
+    }
+    if let Some(angle) = args.polarizer {
+        let max_depth = match settings.integrator() {
+            Integrator::PathTracer(path_tracer) => path_tracer.max_depth(),
+            _ => PolarizedPathTracer::default().max_depth(),
+        };
+        let mut polarized = PolarizedPathTracer::new(max_depth);
+        polarized.set_filter(Some(angle));
+        settings.set_integrator(Integrator::PolarizedPathTracer(polarized));
+    }
+    for &(region, samples_per_pixel) in args.priority_regions.iter() {
+        settings.add_priority_region(region, samples_per_pixel);
+    }
 
     // Scene.
     let mut scene = Scene::new();
 
-    let ground_material = Lambert::new(Color3f::new(0.5, 0.5, 0.5));
-    scene.add(Shape::Sphere(Sphere::new(
-        Point3f::new(0.0, -1000.0, 0.0),
-        1000.0,
-        Material::Lambert(ground_material),
-    )));
-
-    let rnd_f32 = || thread_rng().gen::<f32>();
-    let rnd_color = || {
-        let mut rng = thread_rng();
-        Color3f::new(
-            rng.gen_range(0.0..1.0),
-            rng.gen_range(0.0..1.0),
-            rng.gen_range(0.0..1.0),
-        )
+    if !args.studio {
+        let ground_material = Lambert::new(Color3f::new(0.5, 0.5, 0.5));
+        scene.add(Shape::preset("ground", Material::Lambert(ground_material)).unwrap());
+    }
+
+    match &args.vox {
+        Some(path) => {
+            scene.add(Shape::Voxels(load_vox(path)));
+        }
+        None => add_cover_spheres(&mut scene),
+    }
+
+    if args.studio {
+        studio::setup(&mut scene, &mut camera);
+    }
+
+    // Serve render workers instead of rendering.
+    if let Some(address) = &args.listen {
+        serve_workers(address, &mut camera, &scene, &settings);
+        return;
+    }
+
+    // Render frames, animated by turntable and script. Checkpointed sequences track finished frames in a manifest
+    // and skip them when resumed.
+    let script = args.script.as_deref().map(load_script);
+    let turntable = args
+        .turntable
+        .then(|| Animation::turntable(&camera, args.frames));
+    let manifest_path = args
+        .checkpoint
+        .as_ref()
+        .filter(|_| args.frames > 1)
+        .map(|path| Path::new(path).with_extension("manifest"));
+    let mut manifest = manifest_path.as_ref().map(|path| {
+        SequenceManifest::load(path, args.frames).unwrap_or_else(|e| {
+            eprintln!("{}: {}", path.display(), e);
+            std::process::exit(1);
+        })
+    });
+    for frame in 0..args.frames {
+        let output = frame_path(&args.output, frame, args.frames);
+        let checkpoint = args
+            .checkpoint
+            .as_ref()
+            .map(|path| frame_path(path, frame, args.frames));
+        if let (Some(manifest), Some(path), Some(checkpoint)) =
+            (&mut manifest, &manifest_path, &checkpoint)
+        {
+            if manifest.is_done(frame) {
+                continue;
+            }
+            manifest.start(frame, checkpoint);
+            manifest.save(path).unwrap();
+        }
+        if let Some(turntable) = &turntable {
+            turntable.apply(frame, &mut camera, &mut scene);
+        }
+        if let Some(script) = &script {
+            run_script(script, &mut scene, frame as f32 / FRAME_RATE);
+        }
+        let image = if args.compare {
+            let configurations = comparison_configurations();
+            render_comparison(&camera, &scene, &settings, &configurations, 3)
+        } else if args.explore {
+            render_explore(&mut camera, &scene, &settings, args.tone_mapping)
+        } else if args.preview {
+            render_preview(&mut camera, &scene, &settings, args.tone_mapping)
+        } else if args.gpu {
+            render_gpu(&mut camera, &scene, &settings)
+        } else if !args.workers.is_empty() {
+            render_distributed(&mut camera, &scene, &settings, &args.workers)
+        } else if let Some(path) = &checkpoint {
+            render_checkpointed(&mut camera, &scene, &settings, Path::new(path))
+        } else if let Some(prefix) = &args.noise_report {
+            let (image, report) = camera.render_with_noise_report(&scene, &settings);
+            std::fs::write(format!("{}.csv", prefix), report.to_csv()).unwrap();
+            std::fs::write(format!("{}.json", prefix), report.to_json()).unwrap();
+            let plot = report.plot(480, 320);
+            write_image(
+                &format!("{}_plot.ppm", prefix),
+                &plot,
+                ToneMapping::default(),
+                None,
+            );
+            image
+        } else if args.stats {
+            let (image, stats) = camera.render_with_stats(&scene, &settings);
+            print!("\r{}", stats.to_text());
+            image
+        } else {
+            camera.render_observed(&scene, &settings, progress(&camera, &settings))
+        };
+        let image = if args.denoise && !args.compare {
+            let guides = Guides::render(&mut camera, &scene, &settings);
+            Denoiser::default().denoise(&image, &guides)
+        } else {
+            image
+        };
+        write_image(&output, &image, args.tone_mapping, args.thumbnail);
+        if let (Some(manifest), Some(path), Some(checkpoint)) =
+            (&mut manifest, &manifest_path, &checkpoint)
+        {
+            manifest.finish(frame, &output);
+            manifest.save(path).unwrap();
+            let _ = std::fs::remove_file(checkpoint);
+        }
+        if let Some(columns) = args.terminal {
+            println!();
+            let mut terminal = ToneMap::new(args.tone_mapping, Terminal::new(columns, stdout()));
+            terminal.write_image(&image).unwrap();
+        }
+    }
+
+    // Cleanup.
+    println!("\rDone.       ");
+}
+
+/// Command line options.
+struct Args {
+    /// Resolution override.
+    resolution: Option<(u32, u32)>,
+
+    /// Framing policy of resolution override.
+    aspect: AspectPolicy,
+
+    /// View height of orthographic projection replacing perspective, if any.
+    orthographic: Option<f32>,
+
+    /// Shape of lens aperture.
+    aperture: Aperture,
+
+    /// Horizontal squeeze of aperture, one without.
+    anamorphic_squeeze: f32,
+
+    /// Whether to render integrator and sampler comparison sheet instead of final image.
+    compare: bool,
+
+    /// Path prefix of noise report files (CSV, JSON and convergence plot).
+    noise_report: Option<String>,
+
+    /// Whether to print path statistics per material after rendering.
+    stats: bool,
+
+    /// Post-processing of rendered image.
+    tone_mapping: ToneMapping,
+
+    /// Preset applied to render settings.
+    preset: Option<Configuration>,
+
+    /// Angle in degrees of polarizing filter in front of camera, rendering with polarized path tracer if any.
+    polarizer: Option<f32>,
+
+    /// Max luminance of each camera sample, if clamped.
+    clamp: Option<f32>,
+
+    /// Factor of mean luminance of other samples of a pixel above which samples are rejected, if any.
+    reject_outliers: Option<f32>,
+
+    /// Whether to denoise image guided by albedo and normals at first hits.
+    denoise: bool,
+
+    /// Whether to show image in window while rendering.
+    preview: bool,
+
+    /// Whether to explore scene interactively in window, moving camera by keyboard and mouse.
+    explore: bool,
+
+    /// Whether to render on the GPU.
+    gpu: bool,
+
+    /// Addresses of render workers to distribute tiles to, rendering locally if empty.
+    workers: Vec<String>,
+
+    /// Address to serve as render worker at instead of rendering.
+    listen: Option<String>,
+
+    /// Path of checkpoint file which sample accumulation is saved to periodically and continued from. Numbered per
+    /// frame if animated, with a manifest of finished frames next to it (extension `manifest`).
+    checkpoint: Option<String>,
+
+    /// Image regions rendered with more samples per pixel, with their sample count.
+    priority_regions: Vec<(Tile, u32)>,
+
+    /// Path of rendered image file, numbered per frame if animated. Format by extension, PPM or PFM.
+    output: String,
+
+    /// Max width and height of thumbnail written next to each image, if any.
+    thumbnail: Option<u32>,
+
+    /// Width in text columns of preview printed to terminal after each image, if any.
+    terminal: Option<u32>,
+
+    /// Path of animation script.
+    script: Option<String>,
+
+    /// Number of frames to render.
+    frames: u32,
+
+    /// Whether camera orbits the point it looks at once over all frames.
+    turntable: bool,
+
+    /// Path of MagicaVoxel model rendered instead of default scene.
+    vox: Option<String>,
+
+    /// Whether to render objects in studio (shadow catcher ground, light rig, gradient backdrop) instead of on
+    /// ground.
+    studio: bool,
+}
+
+/// Parses command line `[--resolution WIDTHxHEIGHT] [--aspect fit|fill] [--orthographic HEIGHT]
+/// [--aperture BLADES[,ROTATION]] [--anamorphic SQUEEZE] [--compare] [--noise-report PREFIX] [--stats]
+/// [--exposure STOPS] [--ev EV100] [--tonemap linear|reinhard|aces] [--preset caustics|lights|photons|precision]
+/// [--polarizer DEGREES] [--clamp LUMINANCE] [--reject-outliers FACTOR] [--denoise] [--preview] [--explore] [--gpu]
+/// [--workers ADDR,ADDR...] [--listen ADDR] [--checkpoint PATH] [--priority X,Y,W,H,SPP]... [--output PATH]
+/// [--thumbnail SIZE] [--terminal COLUMNS] [--script PATH] [--frames COUNT] [--turntable] [--vox PATH] [--studio]`.
+fn parse_args(mut args: impl Iterator<Item = String>) -> Args {
+    let usage = "usage: eden [--resolution WIDTHxHEIGHT] [--aspect fit|fill] [--orthographic HEIGHT] \
+                 [--aperture BLADES[,ROTATION]] [--anamorphic SQUEEZE] [--compare] [--noise-report PREFIX] [--stats] \
+                 [--exposure STOPS] [--ev EV100] [--tonemap linear|reinhard|aces] \
+                 [--preset caustics|lights|photons|precision] [--polarizer DEGREES] [--clamp LUMINANCE] \
+                 [--reject-outliers FACTOR] [--denoise] [--preview] [--explore] [--gpu] [--workers ADDR,ADDR...] \
+                 [--listen ADDR] [--checkpoint PATH] [--priority X,Y,W,H,SPP]... [--output PATH] [--thumbnail SIZE] \
+                 [--terminal COLUMNS] [--script PATH] [--frames COUNT] [--turntable] [--vox PATH] [--studio]";
+    let mut parsed = Args {
+        resolution: None,
+        aspect: AspectPolicy::default(),
+        orthographic: None,
+        aperture: Aperture::Circular,
+        anamorphic_squeeze: 1.0,
+        compare: false,
+        noise_report: None,
+        stats: false,
+        tone_mapping: ToneMapping::default(),
+        preset: None,
+        polarizer: None,
+        clamp: None,
+        reject_outliers: None,
+        denoise: false,
+        preview: false,
+        explore: false,
+        gpu: false,
+        workers: Vec::new(),
+        listen: None,
+        checkpoint: None,
+        priority_regions: Vec::new(),
+        output: "image.ppm".to_string(),
+        thumbnail: None,
+        terminal: None,
+        script: None,
+        frames: 1,
+        turntable: false,
+        vox: None,
+        studio: false,
     };
-    for a in -11..11 {
-        for b in -11..11 {
-            let choose_mat = rnd_f32();
-            let center = Point3f::new(a as f32 + 0.9 * rnd_f32(), 0.2, b as f32 + 0.9 * rnd_f32());
-
-            if (center - Point3f::new(4.0, 0.2, 0.0)).length() > 0.9 {
-                if choose_mat < 0.8 {
-                    // Diffuse.
-                    let albedo = rnd_color() * rnd_color();
-                    let l = Lambert::new(albedo);
-                    scene.add(Shape::Sphere(Sphere::new(
-                        center,
-                        0.2,
-                        Material::Lambert(l),
-                    )));
-                } else if choose_mat < 0.95 {
-                    // Metal.
-                    let albedo = (rnd_color() / 2.0) + 0.5;
-                    let fuzz = rnd_f32() * 0.5;
-                    let m = Metal::new(albedo, fuzz);
-                    scene.add(Shape::Sphere(Sphere::new(center, 0.2, Material::Metal(m))));
-                } else {
-                    // Glass.
-                    let d = Dielectric::new(1.5);
-                    scene.add(Shape::Sphere(Sphere::new(
-                        center,
-                        0.2,
-                        Material::Dielectric(d),
-                    )));
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--resolution" => {
+                let value = args.next().expect(usage);
+                let (width, height) = value.split_once('x').expect(usage);
+                let size = (width.parse().expect(usage), height.parse().expect(usage));
+                parsed.resolution = Some(size);
+            }
+            "--aspect" => {
+                parsed.aspect = match args.next().expect(usage).as_str() {
+                    "fit" => AspectPolicy::Fit,
+                    "fill" => AspectPolicy::Fill,
+                    _ => panic!("{}", usage),
                 }
             }
+            "--orthographic" => {
+                let view_height = args.next().expect(usage).parse().expect(usage);
+                assert!(view_height > 0.0, "{}", usage);
+                parsed.orthographic = Some(view_height);
+            }
+            "--aperture" => {
+                let value = args.next().expect(usage);
+                let (blades, rotation) = value.split_once(',').unwrap_or((&value, "0"));
+                let blades = blades.parse().expect(usage);
+                assert!(blades >= 3, "{}", usage);
+                let rotation = rotation.parse().expect(usage);
+                parsed.aperture = Aperture::Polygonal { blades, rotation };
+            }
+            "--anamorphic" => {
+                parsed.anamorphic_squeeze = args.next().expect(usage).parse().expect(usage);
+                assert!(parsed.anamorphic_squeeze > 0.0, "{}", usage);
+            }
+            "--compare" => parsed.compare = true,
+            "--denoise" => parsed.denoise = true,
+            "--preview" => parsed.preview = true,
+            "--explore" => parsed.explore = true,
+            "--gpu" => parsed.gpu = true,
+            "--workers" => {
+                let value = args.next().expect(usage);
+                parsed.workers = value.split(',').map(str::to_string).collect();
+            }
+            "--listen" => parsed.listen = Some(args.next().expect(usage)),
+            "--checkpoint" => parsed.checkpoint = Some(args.next().expect(usage)),
+            "--studio" => parsed.studio = true,
+            "--turntable" => parsed.turntable = true,
+            "--stats" => parsed.stats = true,
+            "--output" => parsed.output = args.next().expect(usage),
+            "--thumbnail" => {
+                let size = args.next().expect(usage).parse().expect(usage);
+                assert!(size > 0, "{}", usage);
+                parsed.thumbnail = Some(size);
+            }
+            "--terminal" => {
+                let columns = args.next().expect(usage).parse().expect(usage);
+                assert!(columns > 0, "{}", usage);
+                parsed.terminal = Some(columns);
+            }
+            "--script" => parsed.script = Some(args.next().expect(usage)),
+            "--vox" => parsed.vox = Some(args.next().expect(usage)),
+            "--frames" => {
+                parsed.frames = args.next().expect(usage).parse().expect(usage);
+                assert!(parsed.frames > 0, "{}", usage);
+            }
+            "--priority" => {
+                let value = args.next().expect(usage);
+                let numbers: Vec<u32> = value.split(',').map(|n| n.parse().expect(usage)).collect();
+                let [x, y, width, height, samples_per_pixel] = numbers[..] else {
+                    panic!("{}", usage);
+                };
+                let region = Tile {
+                    x,
+                    y,
+                    width,
+                    height,
+                };
+                parsed.priority_regions.push((region, samples_per_pixel));
+            }
+            "--noise-report" => parsed.noise_report = Some(args.next().expect(usage)),
+            "--exposure" => {
+                let exposure = args.next().expect(usage).parse().expect(usage);
+                parsed.tone_mapping = ToneMapping::new(exposure, parsed.tone_mapping.mapper());
+            }
+            "--ev" => {
+                let exposure_value = args.next().expect(usage).parse().expect(usage);
+                let mapper = parsed.tone_mapping.mapper();
+                parsed.tone_mapping = ToneMapping::from_exposure_value(exposure_value, mapper);
+            }
+            "--tonemap" => {
+                let mapper = match args.next().expect(usage).as_str() {
+                    "linear" => ToneMapper::Linear,
+                    "reinhard" => ToneMapper::Reinhard,
+                    "aces" => ToneMapper::Aces,
+                    _ => panic!("{}", usage),
+                };
+                parsed.tone_mapping = ToneMapping::new(parsed.tone_mapping.exposure(), mapper);
+            }
+            "--preset" => {
+                let preset = Configuration::preset(&args.next().expect(usage));
+                parsed.preset = Some(preset.expect(usage));
+            }
+            "--polarizer" => {
+                parsed.polarizer = Some(args.next().expect(usage).parse().expect(usage));
+            }
+            "--clamp" => {
+                let max_sample_luminance = args.next().expect(usage).parse().expect(usage);
+                assert!(max_sample_luminance > 0.0, "{}", usage);
+                parsed.clamp = Some(max_sample_luminance);
+            }
+            "--reject-outliers" => {
+                let factor = args.next().expect(usage).parse().expect(usage);
+                assert!(factor > 1.0, "{}", usage);
+                parsed.reject_outliers = Some(factor);
+            }
+            _ => panic!("{}", usage),
+        }
+    }
+    parsed
+}
+
+/// Runs job queue command `add [OPTIONS]... | list | pause ID | resume ID | promote ID | remove ID | run`. Jobs are
+/// render options, `run` renders pending jobs front to back in child processes until none is left. Jobs without
+/// `--output` are written to `job_ID.ppm`.
+fn queue_command(args: Vec<String>) {
+    let usage = "usage: eden queue add [OPTIONS]... | list | pause ID | resume ID | promote ID | remove ID | run";
+    let path = Path::new(QUEUE_PATH);
+    let mut queue = Queue::load(path).unwrap_or_else(|e| panic!("{}: {}", QUEUE_PATH, e));
+    let id = || -> u32 { args.get(1).and_then(|id| id.parse().ok()).expect(usage) };
+    let result = match args.first().map(String::as_str) {
+        Some("add") => {
+            let options = args[1..].to_vec();
+            parse_args(options.iter().cloned()); // Validate options.
+            let id = queue.add(options);
+            println!("Added job {}.", id);
+            Ok(())
+        }
+        Some("list") => {
+            for job in queue.jobs() {
+                println!(
+                    "{}\t{}\t{}",
+                    job.id(),
+                    job.state().name(),
+                    job.args().join(" ")
+                );
+            }
+            return;
+        }
+        Some("pause") => queue.pause(id()),
+        Some("resume") => queue.resume(id()),
+        Some("promote") => queue.promote(id()),
+        Some("remove") => queue.remove(id()),
+        Some("run") => {
+            run_queue(path);
+            return;
+        }
+        _ => panic!("{}", usage),
+    };
+    result.unwrap_or_else(|e| panic!("{}", e));
+    queue.save(path).unwrap();
+}
+
+/// Runs lint command `SCENE.usda [--fix]`, printing common problems of the stage's scene and camera with the prims
+/// concerned, fixable ones marked. With `--fix`, fixable problems are repaired in the imported scene, which is linted
+/// again, printing the problems fixed and those left. The stage file is not written.
+/// Exits with status 1 if any problem is found, or left after fixing, and with status 2 on usage errors.
+fn lint_command(args: Vec<String>) {
+    let usage = "usage: eden lint SCENE.usda [--fix]";
+    let (path, fix) = match args.as_slice() {
+        [path] if path != "--fix" => (path, false),
+        [path, flag] | [flag, path] if flag == "--fix" && path != "--fix" => (path, true),
+        _ => {
+            eprintln!("{}", usage);
+            std::process::exit(2);
+        }
+    };
+    let stage = usda::import(Path::new(path), Orientation::default()).unwrap_or_else(|e| {
+        eprintln!("{}: {}", path, e);
+        std::process::exit(1);
+    });
+    let camera = stage.camera().map(|c| {
+        let mut camera = Camera::new(1, 1);
+        c.apply(&mut camera);
+        camera
+    });
+    let paths: Vec<String> = (0..stage.scene().objects().len())
+        .map(|object| stage.object_path(object).to_string())
+        .collect();
+    let report = |warning: &lint::Warning, note: &str| {
+        let objects: Vec<&str> = warning
+            .objects()
+            .into_iter()
+            .map(|object| paths[object].as_str())
+            .collect();
+        println!("{}: {} ({}){}", path, warning, objects.join(", "), note);
+    };
+    let mut scene = stage.into_scene();
+    if fix {
+        for warning in lint::fix(&mut scene) {
+            report(&warning, " (fixed)");
         }
     }
+    let warnings = lint::lint(&scene, camera.as_ref());
+    for warning in &warnings {
+        report(
+            warning,
+            if warning.is_fixable() {
+                " (fixable)"
+            } else {
+                ""
+            },
+        );
+    }
+    if !warnings.is_empty() {
+        std::process::exit(1);
+    }
+}
 
-    let material1 = Dielectric::new(1.5);
-    scene.add(Shape::Sphere(Sphere::new(
-        Point3f::new(0.0, 1.0, 0.0),
-        1.0,
-        Material::Dielectric(material1),
-    )));
+/// Runs contact sheet command `DIR [--columns N] [--size PIXELS] [--output PATH]`, composing PPM and PFM images of
+/// directory, sorted by name and downscaled to size (default 256), into a labeled grid of given count of columns
+/// (default square-ish). Thumbnails are skipped. Writes `contactsheet.ppm` unless given another path.
+fn contact_sheet_command(args: Vec<String>) {
+    let usage = "usage: eden contactsheet DIR [--columns N] [--size PIXELS] [--output PATH]";
+    let mut args = args.into_iter();
+    let dir = args.next().expect(usage);
+    let (mut columns, mut size, mut output) = (None, 256, "contactsheet.ppm".to_string());
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--columns" => columns = Some(args.next().expect(usage).parse().expect(usage)),
+            "--size" => size = args.next().expect(usage).parse().expect(usage),
+            "--output" => output = args.next().expect(usage),
+            _ => panic!("{}", usage),
+        }
+    }
+    assert!(size > 0 && columns != Some(0), "{}", usage);
 
-    let material2 = Lambert::new(Color3f::new(0.4, 0.2, 0.1));
-    scene.add(Shape::Sphere(Sphere::new(
-        Point3f::new(-4.0, 1.0, 0.0),
-        1.0,
-        Material::Lambert(material2),
-    )));
+    // Images of directory, except thumbnails and sheet itself.
+    let mut paths: Vec<_> = std::fs::read_dir(&dir)
+        .unwrap_or_else(|e| panic!("{}: {}", dir, e))
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| {
+            let name = path.to_string_lossy();
+            let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+            ImageFormat::from_path(&name).is_some()
+                && !stem.ends_with("_thumb")
+                && path.canonicalize().ok() != Path::new(&output).canonicalize().ok()
+        })
+        .collect();
+    paths.sort();
+    assert!(!paths.is_empty(), "{}: no images", dir);
 
-    let material3 = Metal::new(Color3f::new(0.7, 0.6, 0.5), 0.0);
-    scene.add(Shape::Sphere(Sphere::new(
-        Point3f::new(4.0, 1.0, 0.0),
-        1.0,
-        Material::Metal(material3),
-    )));
+    let cells: Vec<(String, Film)> = paths
+        .iter()
+        .map(|path| {
+            let name = path.to_string_lossy();
+            let image = output::read_image(&name).unwrap_or_else(|e| panic!("{}: {}", name, e));
+            let label = path.file_name().unwrap().to_string_lossy().into_owned();
+            (label, output::downscale(&image, size))
+        })
+        .collect();
+    let columns = columns.unwrap_or((cells.len() as f32).sqrt().ceil() as u32);
+    let sheet = contact_sheet(&cells, columns);
+    write_image(&output, &sheet, ToneMapping::default(), None);
+    println!("Wrote {} images to {}.", cells.len(), output);
+}
 
-    // Render.
-    let image = camera.render(&scene);
+/// Runs material catalog command `SCENE.usda [--columns N] [--size PIXELS] [--spp N] [--output PATH]`, rendering
+/// every material defined in the stage, then every other material used by its objects, on a shader ball of given
+/// size (default 128) with samples per pixel (default 64) into a labeled grid of given count of columns (default
+/// square-ish). Defined materials are labeled by their prim path, used ones by the first prim using them. Writes
+/// `materials.ppm` unless given another path.
+fn materials_command(args: Vec<String>) {
+    let usage =
+        "usage: eden materials SCENE.usda [--columns N] [--size PIXELS] [--spp N] [--output PATH]";
+    let mut args = args.into_iter();
+    let path = args.next().expect(usage);
+    let (mut columns, mut size, mut spp, mut output) = (None, 128, 64, "materials.ppm".to_string());
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--columns" => columns = Some(args.next().expect(usage).parse().expect(usage)),
+            "--size" => size = args.next().expect(usage).parse().expect(usage),
+            "--spp" => spp = args.next().expect(usage).parse().expect(usage),
+            "--output" => output = args.next().expect(usage),
+            _ => panic!("{}", usage),
+        }
+    }
+    assert!(size > 0 && spp > 0 && columns != Some(0), "{}", usage);
 
-    // Open file.
-    let file = File::create("image.ppm").unwrap();
-    let mut writer = BufWriter::new(file);
+    let stage = usda::import(Path::new(&path), Orientation::default())
+        .unwrap_or_else(|e| panic!("{}: {}", path, e));
+    let mut materials = stage.materials().to_vec();
+    for (object, material) in catalog::used_materials(stage.scene()) {
+        if !materials.iter().any(|(_, m)| *m == material) {
+            materials.push((stage.object_path(object).to_string(), material));
+        }
+    }
+    assert!(!materials.is_empty(), "{}: no materials", path);
 
-    // File header.
-    writeln!(&mut writer, "P3").unwrap();
-    writeln!(&mut writer, "{} {}", image_width, image_height).unwrap();
-    writeln!(&mut writer, "255").unwrap();
+    let mut settings = RenderSettings::new(spp);
+    settings.set_integrator(Integrator::PathTracer(PathTracer::new(16)));
+    let columns = columns.unwrap_or((materials.len() as f32).sqrt().ceil() as u32);
+    let sheet = catalog::render_catalog(&materials, size, &settings, columns);
+    write_image(&output, &sheet, ToneMapping::default(), None);
+    println!("Wrote {} materials to {}.", materials.len(), output);
+}
 
-    // Write pixel values.
-    for color in image {
-        // Gamma correction (gamma 2).
-        let r = (color.r().sqrt() * 255.0).round() as u32;
-        let g = (color.g().sqrt() * 255.0).round() as u32;
-        let b = (color.b().sqrt() * 255.0).round() as u32;
-        writeln!(&mut writer, "{} {} {}", r, g, b).unwrap();
+/// Runs benchmark command `[--scene-preset cover|studio] [--spp LIST] [--reference SPP] [--resolution
+/// WIDTHxHEIGHT] [--output PREFIX] [--scalar] [--throughput [--baseline CSV] [--tolerance FRACTION]]`, rendering
+/// the scene preset (default cover) at each comma separated sample count (default 8,32,128,512) and a reference
+/// sample count (default 4 times the highest). Prints render times and errors vs. the reference, and writes them to
+/// `PREFIX.csv` and `PREFIX.json` (default `benchmark`). Scalar disables packet intersection, to compare render
+/// times with it.
+///
+/// Throughput renders all standard scenes at the first sample count instead, printing rays per second and writing
+/// them to `PREFIX.csv`. Given a baseline CSV written by an earlier version, scenes whose rays per second dropped by
+/// more than tolerance (default 0.1) are reported and the command exits with status 1, e.g. to fail CI jobs.
+fn benchmark_command(args: Vec<String>) {
+    let usage =
+        "usage: eden benchmark [--scene-preset cover|studio] [--spp LIST] [--reference SPP] \
+                 [--resolution WIDTHxHEIGHT] [--output PREFIX] [--scalar] \
+                 [--throughput [--baseline CSV] [--tolerance FRACTION]]";
+    let mut args = args.into_iter();
+    let (mut preset, mut sample_counts, mut reference) =
+        ("cover".to_string(), vec![8, 32, 128, 512], None);
+    let (mut resolution, mut output) = ((400, 225), "benchmark".to_string());
+    let mut scalar = false;
+    let (mut throughput, mut baseline, mut tolerance) = (false, None, 0.1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--scene-preset" => preset = args.next().expect(usage),
+            "--spp" => {
+                let value = args.next().expect(usage);
+                sample_counts = value.split(',').map(|n| n.parse().expect(usage)).collect();
+            }
+            "--reference" => reference = Some(args.next().expect(usage).parse().expect(usage)),
+            "--resolution" => {
+                let value = args.next().expect(usage);
+                let (width, height) = value.split_once('x').expect(usage);
+                resolution = (width.parse().expect(usage), height.parse().expect(usage));
+            }
+            "--output" => output = args.next().expect(usage),
+            "--scalar" => scalar = true,
+            "--throughput" => throughput = true,
+            "--baseline" => baseline = Some(args.next().expect(usage)),
+            "--tolerance" => tolerance = args.next().expect(usage).parse().expect(usage),
+            _ => panic!("{}", usage),
+        }
     }
+    assert!(sample_counts.iter().all(|&s| s > 0), "{}", usage);
+    let reference = reference.unwrap_or(4 * sample_counts.iter().max().expect(usage));
+    assert!(
+        reference > 0 && resolution.0 > 0 && resolution.1 > 0 && tolerance >= 0.0,
+        "{}",
+        usage
+    );
 
-    // Cleanup.
-    println!("\rDone.       ");
+    // Throughput of standard scenes, compared to baseline.
+    if throughput {
+        let report = ThroughputReport::measure(&benchmark::SCENES, resolution, sample_counts[0]);
+        std::fs::write(format!("{}.csv", output), report.to_csv()).unwrap();
+        print!("\r{}", report.to_text());
+        if let Some(path) = baseline {
+            let csv = std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("{}: {}", path, e));
+            let baseline = ThroughputReport::from_csv(&csv)
+                .unwrap_or_else(|| panic!("{}: invalid throughput report", path));
+            let regressions = report.regressions(&baseline, tolerance);
+            for regression in regressions.iter() {
+                println!("regression: {}", regression);
+            }
+            if !regressions.is_empty() {
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    // Scene preset.
+    let (mut scene, mut camera) = benchmark::standard_scene(&preset).expect(usage);
+    camera.set_resolution_override(Some(resolution), AspectPolicy::default());
+    scene.set_packet_intersection(!scalar);
+
+    let settings = cover_settings();
+    let report = BenchmarkReport::measure(&camera, &scene, &settings, &sample_counts, reference);
+    std::fs::write(format!("{}.csv", output), report.to_csv()).unwrap();
+    std::fs::write(format!("{}.json", output), report.to_json()).unwrap();
+    print!("\r{}", report.to_text());
+}
+
+/// Renders pending jobs of queue file front to back. Queue is reloaded between jobs, so it can be edited while
+/// running.
+fn run_queue(path: &Path) {
+    let executable = std::env::current_exe().unwrap();
+    let mut queue = Queue::load(path).unwrap();
+    queue.reset_running();
+    queue.save(path).unwrap();
+    loop {
+        // Claim next pending job.
+        let mut queue = Queue::load(path).unwrap();
+        let Some(job) = queue.next_pending().cloned() else {
+            break;
+        };
+        queue.set_state(job.id(), JobState::Running).unwrap();
+        queue.save(path).unwrap();
+
+        // Render in child process.
+        let mut options = job.args().to_vec();
+        if !options.iter().any(|o| o == "--output") {
+            options.extend(["--output".to_string(), format!("job_{}.ppm", job.id())]);
+        }
+        println!("Job {}: eden {}", job.id(), options.join(" "));
+        let success = Command::new(&executable)
+            .args(&options)
+            .status()
+            .is_ok_and(|status| status.success());
+
+        // Record result.
+        let mut queue = Queue::load(path).unwrap();
+        let state = if success {
+            JobState::Done
+        } else {
+            JobState::Failed
+        };
+        if queue.set_state(job.id(), state).is_ok() {
+            queue.save(path).unwrap();
+        }
+    }
+}
+
+/// Renders scene while showing preview window.
+#[cfg(feature = "preview")]
+fn render_preview(
+    camera: &mut Camera,
+    scene: &Scene,
+    settings: &RenderSettings,
+    tone_mapping: ToneMapping,
+) -> Film {
+    eden::preview::render_with_preview(camera, scene, settings, tone_mapping)
+}
+
+/// Explores scene in window until it is closed, moving camera by keyboard and mouse.
+#[cfg(feature = "preview")]
+fn render_explore(
+    camera: &mut Camera,
+    scene: &Scene,
+    settings: &RenderSettings,
+    tone_mapping: ToneMapping,
+) -> Film {
+    eden::preview::explore(camera, scene, settings, tone_mapping)
+}
+
+/// Renders scene on the GPU, exiting with error if no GPU is available or the scene is not supported there.
+#[cfg(feature = "gpu")]
+fn render_gpu(camera: &mut Camera, scene: &Scene, settings: &RenderSettings) -> Film {
+    let film = eden::gpu::GpuRenderer::new().and_then(|renderer| {
+        println!("Rendering on {}", renderer.adapter_name());
+        renderer.render(camera, scene, settings)
+    });
+    film.unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    })
+}
+
+/// Tile observer printing percentage of finished tiles of render with camera and settings.
+fn progress(
+    camera: &Camera,
+    settings: &RenderSettings,
+) -> impl Fn(&Tile, &[Color3f]) -> bool + Sync {
+    let (width, height) = camera.resolution();
+    let tiles = settings.tiles(width, height).len();
+    let finished = AtomicUsize::new(0);
+    move |_, _| {
+        let finished = finished.fetch_add(1, Ordering::Relaxed) + 1;
+        print!("\r{:.2}%", finished as f32 / tiles as f32 * 100.0);
+        stdout().flush().unwrap();
+        true
+    }
+}
+
+/// Renders scene on workers, printing those which failed, exiting with error once all of them failed.
+fn render_distributed(
+    camera: &mut Camera,
+    scene: &Scene,
+    settings: &RenderSettings,
+    workers: &[String],
+) -> Film {
+    let (film, failed) =
+        distributed::render_distributed(camera, scene, settings, workers, TILES_PER_RANGE)
+            .unwrap_or_else(|e| {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            });
+    for (address, e) in failed {
+        eprintln!("Worker {} failed: {}", address, e);
+    }
+    film
+}
+
+/// Renders scene saving checkpoints, continuing from checkpoint file if present. Exits with error if it belongs to
+/// another render.
+fn render_checkpointed(
+    camera: &mut Camera,
+    scene: &Scene,
+    settings: &RenderSettings,
+    path: &Path,
+) -> Film {
+    let samples_per_pixel = settings.samples_per_pixel();
+    let film =
+        checkpoint::render_checkpointed(camera, scene, settings, path, CHECKPOINT_SAMPLES, |a| {
+            let samples = a.pixels().iter().map(|p| p.samples).min().unwrap_or(0);
+            print!("\rCheckpoint at {}/{} samples", samples, samples_per_pixel);
+            stdout().flush().unwrap();
+        });
+    film.unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    })
+}
+
+/// Serves coordinators connecting to address as render worker, one at a time.
+fn serve_workers(address: &str, camera: &mut Camera, scene: &Scene, settings: &RenderSettings) {
+    let listener = TcpListener::bind(address).unwrap();
+    println!("Listening on {}", listener.local_addr().unwrap());
+    for stream in listener.incoming() {
+        let result = stream
+            .map_err(DistributedError::from)
+            .and_then(|mut stream| {
+                distributed::serve_connection(&mut stream, camera, scene, settings)
+            });
+        if let Err(e) = result {
+            eprintln!("\r{}", e);
+        }
+    }
+}
+
+/// GPU rendering is unavailable without `gpu` feature.
+#[cfg(not(feature = "gpu"))]
+fn render_gpu(_camera: &mut Camera, _scene: &Scene, _settings: &RenderSettings) -> Film {
+    panic!("GPU rendering requires building eden with `--features gpu`");
+}
+
+/// Preview is unavailable without `preview` feature.
+#[cfg(not(feature = "preview"))]
+fn render_preview(
+    _camera: &mut Camera,
+    _scene: &Scene,
+    _settings: &RenderSettings,
+    _tone_mapping: ToneMapping,
+) -> Film {
+    panic!("preview requires building eden with `--features preview`");
+}
+
+/// Exploring is unavailable without `preview` feature.
+#[cfg(not(feature = "preview"))]
+fn render_explore(
+    _camera: &mut Camera,
+    _scene: &Scene,
+    _settings: &RenderSettings,
+    _tone_mapping: ToneMapping,
+) -> Film {
+    panic!("exploring requires building eden with `--features preview`");
+}
+
+/// Loads animation script.
+#[cfg(feature = "scripting")]
+fn load_script(path: &str) -> eden::script::Script {
+    eden::script::Script::load(Path::new(path)).unwrap_or_else(|e| panic!("{}: {}", path, e))
+}
+
+/// Animates scene objects by script for frame time in seconds.
+#[cfg(feature = "scripting")]
+fn run_script(script: &eden::script::Script, scene: &mut Scene, t: f32) {
+    script
+        .on_frame(scene, t)
+        .unwrap_or_else(|e| panic!("{}", e));
+}
+
+/// Scripts are unavailable without `scripting` feature.
+#[cfg(not(feature = "scripting"))]
+fn load_script(_path: &str) {
+    panic!("scripts require building eden with `--features scripting`");
+}
+
+/// Scripts are unavailable without `scripting` feature.
+#[cfg(not(feature = "scripting"))]
+fn run_script(_script: &(), _scene: &mut Scene, _t: f32) {}
+
+/// Path of image file of frame, numbered if animation has several frames (`image.ppm` to `image_0001.ppm`).
+fn frame_path(output: &str, frame: u32, frames: u32) -> String {
+    if frames == 1 {
+        return output.to_string();
+    }
+    let path = Path::new(output);
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(extension) => format!("{}_{:04}.{}", stem, frame, extension.to_string_lossy()),
+        None => format!("{}_{:04}", stem, frame),
+    };
+    path.with_file_name(name).to_string_lossy().into_owned()
+}
+
+/// Configurations of comparison sheet.
+fn comparison_configurations() -> Vec<Configuration> {
+    let mut configurations = Vec::new();
+    for (label, sampler) in [
+        ("path 16", Sampler::Independent),
+        ("halton 16", Sampler::Halton),
+        ("blue noise 16", Sampler::BlueNoise),
+    ] {
+        let mut c = Configuration::new(label);
+        c.set_sampler(sampler);
+        c.set_samples_per_pixel(16);
+        configurations.push(c);
+    }
+    let mut no_nee = PathTracer::new(50);
+    no_nee.set_light_sampling(false);
+    let integrators = [
+        ("no nee 16", Integrator::PathTracer(no_nee)),
+        (
+            "direct 16",
+            Integrator::DirectLighting(DirectLighting::new(50)),
+        ),
+        (
+            "ao 16",
+            Integrator::AmbientOcclusion(AmbientOcclusion::default()),
+        ),
+        ("normals", Integrator::NormalDebug(NormalDebug)),
+        ("depth", Integrator::DepthDebug(DepthDebug::default())),
+        ("materials", Integrator::MaterialDebug(MaterialDebug)),
+        ("uvs", Integrator::UvDebug(UvDebug)),
+        ("lights 16", Integrator::LightVisibility(LightVisibility)),
+    ];
+    for (label, integrator) in integrators {
+        let mut c = Configuration::new(label);
+        c.set_integrator(integrator);
+        c.set_samples_per_pixel(16);
+        configurations.push(c);
+    }
+    configurations
+}
+
+/// Writes film as image file through default output pipeline, in format given by extension of path, and thumbnail
+/// with max size next to it, if given.
+fn write_image(path: &str, image: &Film, tone_mapping: ToneMapping, thumbnail: Option<u32>) {
+    let mut sink = Tee::new();
+    sink.add(output::file_pipeline(path, tone_mapping).unwrap());
+    if let Some(size) = thumbnail {
+        let thumbnail_path = output::thumbnail_path(path);
+        let pipeline = output::file_pipeline(&thumbnail_path, tone_mapping).unwrap();
+        sink.add(Thumbnail::new(size, pipeline));
+    }
+    sink.write_image(image).unwrap();
+}
+
+/// Final quality render settings of the cover scene.
+fn cover_settings() -> RenderSettings {
+    let mut settings = RenderSettings::new(500);
+    settings.set_integrator(Integrator::PathTracer(PathTracer::new(50)));
+    settings
+}
+
+/// Loads MagicaVoxel model, scaled to 2 units along its longest side and resting on the ground at the origin.
+fn load_vox(path: &str) -> Voxels {
+    let mut voxels = vox::import(Path::new(path), 1.0, Orientation::Y_UP)
+        .unwrap_or_else(|e| panic!("{}: {}", path, e));
+    let size = voxels.size();
+    let voxel_size = 2.0 / *size.iter().max().unwrap() as f32;
+    voxels.set_voxel_size(voxel_size);
+    voxels.set_center(Point3f::new(0.0, 0.5 * voxel_size * size[1] as f32, 0.0));
+    voxels
 }
@@ -0,0 +1,73 @@
+use crate::base::material::Material;
+
+/// Handle of named material in a `MaterialLibrary`, valid for the library it was returned by.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct MaterialHandle(usize);
+
+/// Named materials shared by objects of a scene, see `Scene::define_material`. Bound objects refer to a material by
+/// handle, which is looked up at shading time, so all of them share one material and follow its edits. Scene files
+/// refer to materials by name.
+#[derive(Clone, Debug, Default, Hash, PartialEq)]
+pub struct MaterialLibrary {
+    /// Names of materials, by handle.
+    names: Vec<String>,
+
+    /// Materials, by handle.
+    materials: Vec<Material>,
+}
+
+impl MaterialLibrary {
+    /// Creates empty library.
+    pub fn new() -> Self {
+        MaterialLibrary::default()
+    }
+
+    /// Defines material with name, replacing any previous one of the name, which keeps its handle. Returns handle of
+    /// material.
+    pub fn insert(&mut self, name: &str, material: Material) -> MaterialHandle {
+        match self.handle(name) {
+            Some(handle) => {
+                self.materials[handle.0] = material;
+                handle
+            }
+            None => {
+                self.names.push(name.to_string());
+                self.materials.push(material);
+                MaterialHandle(self.materials.len() - 1)
+            }
+        }
+    }
+
+    /// Handle of material with name, or none if undefined.
+    pub fn handle(&self, name: &str) -> Option<MaterialHandle> {
+        self.names
+            .iter()
+            .position(|n| n == name)
+            .map(MaterialHandle)
+    }
+
+    /// Material of handle.
+    pub fn get(&self, handle: MaterialHandle) -> &Material {
+        &self.materials[handle.0]
+    }
+
+    /// Name of material of handle.
+    pub fn name(&self, handle: MaterialHandle) -> &str {
+        &self.names[handle.0]
+    }
+
+    /// Count of materials.
+    pub fn len(&self) -> usize {
+        self.materials.len()
+    }
+
+    /// Whether library has no materials.
+    pub fn is_empty(&self) -> bool {
+        self.materials.is_empty()
+    }
+
+    /// Names and materials in order of definition.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Material)> {
+        self.names.iter().map(String::as_str).zip(&self.materials)
+    }
+}
@@ -1,3 +1,10 @@
+pub mod custom;
 pub mod dielectric;
+pub mod diffuse_light;
 pub mod lambert;
 pub mod metal;
+pub mod microfacet;
+pub mod mix;
+pub mod normal_map;
+pub mod principled;
+pub mod shadow_catcher;
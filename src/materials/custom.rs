@@ -0,0 +1,179 @@
+use crate::base::{
+    color::Color3f,
+    material::{Interactable, Interaction},
+    ray::Ray,
+    shape::Intersection,
+    vector::Vector3f,
+};
+use rand::{Rng, RngCore};
+use std::{
+    fmt::Debug,
+    hash::{Hash, Hasher},
+    sync::Arc,
+};
+
+/// Shading model of user-defined materials, implemented outside of this crate for materials the `Material` enum
+/// lacks, e.g. car paint or toon shading. Wrapped into a `Custom` material, shaders render with all integrators like
+/// built-in materials, see `Interactable` for the contract of each method.
+pub trait Shader: Debug + Send + Sync {
+    /// Evaluates shader at a given intersection point, drawing random numbers from rng. Returns interaction struct
+    /// if not absorbed. Scattered rays are offset off the surface afterwards, so they may start at the point.
+    fn interact(
+        &self,
+        incident_ray: Ray,
+        intersection: Intersection,
+        rng: &mut dyn RngCore,
+    ) -> Option<Interaction>;
+
+    /// Radiance emitted by shader.
+    fn emitted(&self) -> Color3f {
+        Color3f::black()
+    }
+
+    /// Probability density w.r.t. solid angle of scattering into direction, or none for specular shaders.
+    fn scattering_pdf(&self, _intersection: Intersection, _direction: Vector3f) -> Option<f32> {
+        None
+    }
+
+    /// Feeds parameters of shader into hasher, so scene hashes change with them. Shaders with equal parameters
+    /// should hash alike, see `base::hash::hash_float` for floats.
+    fn hash_shader(&self, state: &mut dyn Hasher);
+}
+
+/// Material of user-defined shader. Shaders are shared, so clones of custom materials are cheap and equal as long as
+/// they share the shader.
+#[derive(Clone, Debug)]
+pub struct Custom {
+    /// Shared shading model.
+    shader: Arc<dyn Shader>,
+}
+
+impl Custom {
+    /// Creates custom material of shader.
+    pub fn new(shader: Arc<dyn Shader>) -> Self {
+        Custom { shader }
+    }
+
+    /// Shared shading model.
+    pub fn shader(&self) -> &Arc<dyn Shader> {
+        &self.shader
+    }
+}
+
+impl PartialEq for Custom {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.shader, &other.shader)
+    }
+}
+
+impl Hash for Custom {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.shader.hash_shader(state);
+    }
+}
+
+impl Interactable for Custom {
+    fn interact(
+        &self,
+        incident_ray: Ray,
+        intersection: Intersection,
+        rng: &mut impl Rng,
+    ) -> Option<Interaction> {
+        self.shader.interact(incident_ray, intersection, rng)
+    }
+
+    fn emitted(&self) -> Color3f {
+        self.shader.emitted()
+    }
+
+    fn scattering_pdf(&self, intersection: Intersection, direction: Vector3f) -> Option<f32> {
+        self.shader.scattering_pdf(intersection, direction)
+    }
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        background::Background,
+        base::{
+            hash::{hash_float, stable_hash},
+            material::Material,
+            point::Point3f,
+            shape::Shape,
+        },
+        integrator::{Estimator, Integrator},
+        integrators::path_tracer::PathTracer,
+        scene::Scene,
+    };
+    use rand::{rngs::StdRng, SeedableRng};
+
+    /// Toon shader reflecting light in two bands by angle to the surface, as an example of a shading model the crate
+    /// lacks. Scatters along the normal with the band color, blind to lighting.
+    #[derive(Debug)]
+    struct Toon {
+        /// Color facing the viewer.
+        lit: Color3f,
+
+        /// Color at grazing angles.
+        shade: Color3f,
+    }
+
+    impl Shader for Toon {
+        fn interact(
+            &self,
+            incident_ray: Ray,
+            intersection: Intersection,
+            _rng: &mut dyn RngCore,
+        ) -> Option<Interaction> {
+            let cos = -incident_ray
+                .direction()
+                .normalize()
+                .dot(&intersection.normal);
+            Some(Interaction {
+                attenuation: if cos > 0.5 { self.lit } else { self.shade },
+                scattered_ray: Ray::new(intersection.point, intersection.normal),
+            })
+        }
+
+        fn hash_shader(&self, mut state: &mut dyn Hasher) {
+            for c in [self.lit, self.shade] {
+                hash_float(c.r(), &mut state);
+                hash_float(c.g(), &mut state);
+                hash_float(c.b(), &mut state);
+            }
+        }
+    }
+
+    #[test]
+    fn custom() {
+        // Ball under white sky shows lit band in its middle, shaded band at its rim.
+        let mut scene = Scene::new();
+        scene.set_background(Background::Uniform(Color3f::white()));
+        let toon = Custom::new(Arc::new(Toon {
+            lit: Color3f::new(1.0, 0.5, 0.0),
+            shade: Color3f::new(0.2, 0.1, 0.0),
+        }));
+        scene.add(Shape::preset("ball", Material::Custom(toon.clone())).unwrap());
+        let tracer = Integrator::PathTracer(PathTracer::new(4));
+        let mut rng = StdRng::seed_from_u64(5);
+        let middle = Ray::new(Point3f::new(0.0, 1.0, 5.0), Vector3f::new(0.0, 0.0, -1.0));
+        assert_eq!(
+            tracer.radiance(middle, &scene, &mut rng),
+            Color3f::new(1.0, 0.5, 0.0)
+        );
+        let rim = Ray::new(Point3f::new(0.95, 1.0, 5.0), Vector3f::new(0.0, 0.0, -1.0));
+        assert_eq!(
+            tracer.radiance(rim, &scene, &mut rng),
+            Color3f::new(0.2, 0.1, 0.0)
+        );
+
+        // Clones are equal and hash alike.
+        assert_eq!(
+            Material::Custom(toon.clone()),
+            Material::Custom(toon.clone())
+        );
+        assert_eq!(stable_hash(&toon), stable_hash(&toon.clone()));
+    }
+}
@@ -1,39 +1,165 @@
 use crate::base::{
     color::Color3f,
+    hash::hash_float,
     material::{Interactable, Interaction},
     ray::Ray,
     shape::Intersection,
     vector::Vector3f,
 };
-use rand::{thread_rng, Rng};
+use rand::Rng;
+use std::{
+    hash::{Hash, Hasher},
+    sync::OnceLock,
+};
+
+/// Range of wavelengths in nanometers sampled by dispersive dielectrics.
+const VISIBLE_WAVELENGTHS: (f32, f32) = (380.0, 780.0);
+
+/// Wavelength in nanometers of the sodium D line, which indices of refraction are commonly given at.
+const REFERENCE_WAVELENGTH: f32 = 589.3;
+
+/// Dependence of index of refraction on wavelength λ in micrometers, which splits white light into its colors.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Dispersion {
+    /// Same index for all wavelengths.
+    #[default]
+    None,
+
+    /// Cauchy's equation n = a + b/λ², e.g. a=1.5046, b=0.0042 for BK7 glass.
+    Cauchy { a: f32, b: f32 },
+
+    /// Sellmeier equation n² = 1 + Σ bᵢλ²/(λ² - cᵢ), with cᵢ in µm², as listed in glass catalogs.
+    Sellmeier { b: [f32; 3], c: [f32; 3] },
+}
+
+impl Dispersion {
+    /// Index of refraction at wavelength in nanometers, none without dispersion.
+    pub fn ior(&self, wavelength: f32) -> Option<f32> {
+        let l2 = (wavelength / 1000.0).powi(2);
+        match *self {
+            Dispersion::None => None,
+            Dispersion::Cauchy { a, b } => Some(a + b / l2),
+            Dispersion::Sellmeier { b, c } => {
+                let sum: f32 = b.iter().zip(c).map(|(b, c)| b * l2 / (l2 - c)).sum();
+                Some((1.0 + sum).sqrt())
+            }
+        }
+    }
+}
+
+impl Hash for Dispersion {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            Dispersion::None => {}
+            Dispersion::Cauchy { a, b } => {
+                hash_float(*a, state);
+                hash_float(*b, state);
+            }
+            Dispersion::Sellmeier { b, c } => {
+                for x in b.iter().chain(c) {
+                    hash_float(*x, state);
+                }
+            }
+        }
+    }
+}
 
 /// Dielectric material model.
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Dielectric {
-    /// Index of refraction.
+    /// Index of refraction, at the reference wavelength if dispersive.
     ior: f32,
+
+    /// Absorption coefficient of interior medium per unit distance.
+    absorption: Color3f,
+
+    /// Dependence of index of refraction on wavelength.
+    dispersion: Dispersion,
 }
 
 impl Dielectric {
-    /// Creates dielectric material with given index of refraction.
+    /// Creates clear dielectric material with given index of refraction.
     pub fn new(index_of_refraction: f32) -> Self {
         Dielectric {
             ior: index_of_refraction,
+            absorption: Color3f::black(),
+            dispersion: Dispersion::None,
         }
     }
 
+    /// Creates clear dispersive dielectric material, with index of refraction at wavelength given by dispersion.
+    pub fn dispersive(dispersion: Dispersion) -> Self {
+        let mut dielectric = Dielectric::new(1.0);
+        dielectric.set_dispersion(dispersion);
+        dielectric
+    }
+
+    /// Index of refraction, at the sodium D line (589.3 nm) if dispersive.
+    pub fn ior(&self) -> f32 {
+        self.ior
+    }
+
+    /// Index of refraction at wavelength in nanometers.
+    pub fn ior_at(&self, wavelength: f32) -> f32 {
+        self.dispersion.ior(wavelength).unwrap_or(self.ior)
+    }
+
+    /// Dependence of index of refraction on wavelength.
+    pub fn dispersion(&self) -> Dispersion {
+        self.dispersion
+    }
+
+    /// Sets dependence of index of refraction on wavelength, e.g. for prisms and gemstones splitting light into
+    /// rainbow colors. Dispersive dielectrics refract rays carrying all visible light at a random wavelength, tinting
+    /// them with its color, and rays carrying a wavelength at that. The index of refraction becomes that at the sodium
+    /// D line, used where no wavelength is sampled, e.g. on the GPU.
+    pub fn set_dispersion(&mut self, dispersion: Dispersion) {
+        self.dispersion = dispersion;
+        self.ior = self.ior_at(REFERENCE_WAVELENGTH);
+    }
+
+    /// Absorption coefficient of interior medium per unit distance.
+    pub fn absorption(&self) -> Color3f {
+        self.absorption
+    }
+
+    /// Sets absorption coefficient of interior medium per unit distance. Rays travelling distance d inside are
+    /// attenuated by exp(-absorption * d) (Beer-Lambert law).
+    pub fn set_absorption(&mut self, absorption: Color3f) {
+        self.absorption = absorption;
+    }
+
+    /// Whether ray along direction hitting surface with outward normal from inside is totally reflected, i.e. too
+    /// flat to refract out of the medium.
+    pub fn total_internal_reflection(&self, direction: Vector3f, normal: Vector3f) -> bool {
+        let cos_i = direction.normalize().dot(&normal);
+        cos_i > 0.0 && self.ior * self.ior * (1.0 - cos_i * cos_i) > 1.0
+    }
+
     /// Schlick's approximation for reflectance.
-    fn schlick(&self, incident: Vector3f, normal: Vector3f, eta: f32) -> f32 {
+    pub(crate) fn schlick(&self, incident: Vector3f, normal: Vector3f, eta: f32) -> f32 {
         let cos_i = (-incident.dot(&normal)).min(1.0);
         let r0 = ((1.0 - eta) / (1.0 + eta)).powi(2);
         r0 + (1.0 - r0) * (1.0 - cos_i).powi(5)
     }
 }
 
-impl Interactable for Dielectric {
-    fn interact(&self, incident_ray: Ray, intersection: Intersection) -> Option<Interaction> {
-        let mut rng = thread_rng();
+impl Hash for Dielectric {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        hash_float(self.ior, state);
+        self.absorption.hash(state);
+        self.dispersion.hash(state);
+    }
+}
 
+impl Interactable for Dielectric {
+    fn interact(
+        &self,
+        incident_ray: Ray,
+        intersection: Intersection,
+        rng: &mut impl Rng,
+    ) -> Option<Interaction> {
         // Determine whether ray is inside or outside object, flip outward normal.
         let front_face = incident_ray.direction().dot(&intersection.normal) <= 0.0;
         let normal = if front_face {
@@ -42,8 +168,23 @@ impl Interactable for Dielectric {
             -intersection.normal
         };
 
+        // Index of refraction at wavelength of ray, sampling one if dispersive and ray carries all light.
+        let (ior, tint, wavelength) = match (self.dispersion, incident_ray.wavelength()) {
+            (Dispersion::None, _) => (self.ior, Color3f::white(), None),
+            (_, Some(wavelength)) => (self.ior_at(wavelength), Color3f::white(), None),
+            (_, None) => {
+                let (min, max) = VISIBLE_WAVELENGTHS;
+                let wavelength = min + (max - min) * rng.gen::<f32>();
+                (
+                    self.ior_at(wavelength),
+                    spectral_tint(wavelength),
+                    Some(wavelength),
+                )
+            }
+        };
+
         // Refract at intersection normal.
-        let etai_over_etat = if front_face { 1.0 / self.ior } else { self.ior };
+        let etai_over_etat = if front_face { 1.0 / ior } else { ior };
         let incident = incident_ray.direction().normalize();
         let scattered = if self.schlick(incident, normal, etai_over_etat) > rng.gen() {
             incident.reflect(normal) // Schlick's approximation.
@@ -54,20 +195,52 @@ impl Interactable for Dielectric {
             }
         };
 
+        // Absorption along path inside medium, which ends at back face.
+        let attenuation = if front_face {
+            Color3f::white()
+        } else {
+            let distance = (intersection.point - incident_ray.origin()).length();
+            let transmittance = |a: f32| (-a * distance).exp();
+            let a = self.absorption;
+            Color3f::new(
+                transmittance(a.r()),
+                transmittance(a.g()),
+                transmittance(a.b()),
+            )
+        };
+
         // Return interaction struct.
+        let mut scattered_ray = Ray::new(intersection.point, scattered);
+        scattered_ray.set_wavelength(wavelength);
         let interaction = Interaction {
-            attenuation: Color3f::white(),
-            scattered_ray: Ray::new(intersection.point, scattered),
+            attenuation: attenuation * tint,
+            scattered_ray,
         };
         Some(interaction)
     }
 }
 
+/// Color of light at wavelength in nanometers relative to all visible light, such that the mean over uniformly
+/// sampled visible wavelengths is white.
+fn spectral_tint(wavelength: f32) -> Color3f {
+    static MEAN: OnceLock<Color3f> = OnceLock::new();
+    let mean = MEAN.get_or_init(|| {
+        let (min, max) = VISIBLE_WAVELENGTHS;
+        let steps = (max - min) as u32;
+        let sum = (0..steps).fold(Color3f::black(), |sum, i| {
+            sum + Color3f::from_wavelength(min + i as f32 + 0.5)
+        });
+        sum / steps as f32
+    });
+    Color3f::from_wavelength(wavelength) / *mean
+}
+
 /// Unit tests.
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::base::{material::Material, point::Point3f};
+    use rand::{rngs::StdRng, SeedableRng};
 
     #[test]
     fn interact() {
@@ -77,13 +250,17 @@ mod tests {
         let isect = Intersection {
             point: Point3f::new(1.0, 1.0, 1.0),
             normal: Vector3f::new(0.0, 1.0, 0.0),
+            uv: (0.0, 0.0),
             material: &mat,
             t: 1.0,
+            error: Vector3f::default(),
         };
-        let iact = mat.interact(r, isect).unwrap();
+        let iact = mat
+            .interact(r, isect, &mut StdRng::seed_from_u64(42))
+            .unwrap();
         assert_eq!(iact.attenuation, Color3f::white());
-        assert_eq!(iact.scattered_ray.origin(), isect.point);
-        // Schlick's approximation is currently nondeterministic.
+        assert!((iact.scattered_ray.origin() - isect.point).length() < 1e-6);
+        // Schlick's approximation randomly picks reflection or refraction.
         let refracted = r
             .direction()
             .normalize()
@@ -95,4 +272,102 @@ mod tests {
                 || iact.scattered_ray.direction() == reflected
         );
     }
+
+    #[test]
+    fn dispersion() {
+        // BK7 crown glass bends blue light more than red.
+        let bk7 = Dispersion::Sellmeier {
+            b: [1.039_612, 0.231_792_3, 1.010_469_4],
+            c: [0.006_000_699, 0.020_017_914, 103.560_65],
+        };
+        let d = Dielectric::dispersive(bk7);
+        assert!((d.ior() - 1.5168).abs() < 1e-3);
+        assert!(d.ior_at(450.0) > d.ior_at(650.0));
+        let cauchy = Dispersion::Cauchy {
+            a: 1.5046,
+            b: 0.0042,
+        };
+        assert!((cauchy.ior(500.0).unwrap() - 1.5214).abs() < 1e-4);
+        assert_eq!(Dielectric::new(1.5).ior_at(450.0), 1.5);
+
+        // Rays carrying all light pick a wavelength and its tint, averaging to white.
+        let mat = Material::Dielectric(d);
+        let isect = Intersection {
+            point: Point3f::default(),
+            normal: Vector3f::new(0.0, 1.0, 0.0),
+            uv: (0.0, 0.0),
+            material: &mat,
+            t: 1.0,
+            error: Vector3f::default(),
+        };
+        let mut rng = StdRng::seed_from_u64(42);
+        let white = Ray::new(Point3f::new(-1.0, 1.0, 0.0), Vector3f::new(1.0, -1.0, 0.0));
+        let n = 20000;
+        let mut sum = Color3f::black();
+        for _ in 0..n {
+            let iact = mat.interact(white, isect, &mut rng).unwrap();
+            let wavelength = iact.scattered_ray.wavelength().unwrap();
+            assert!((380.0..780.0).contains(&wavelength));
+            sum += iact.attenuation;
+        }
+        let mean = sum / n as f32;
+        for channel in [mean.r(), mean.g(), mean.b()] {
+            assert!((channel - 1.0).abs() < 0.05, "{:?}", mean);
+        }
+
+        // Rays carrying a wavelength keep it, untinted, and refract by its index.
+        let mut red = white;
+        red.set_wavelength(Some(650.0));
+        let refracted = (0..16)
+            .map(|_| mat.interact(red, isect, &mut rng).unwrap())
+            .find(|iact| iact.scattered_ray.direction().y() < 0.0)
+            .unwrap();
+        assert_eq!(refracted.attenuation, Color3f::white());
+        assert_eq!(refracted.scattered_ray.wavelength(), Some(650.0));
+        let expected = red
+            .direction()
+            .normalize()
+            .refract(isect.normal, 1.0 / d.ior_at(650.0))
+            .unwrap();
+        assert_eq!(refracted.scattered_ray.direction(), expected);
+    }
+
+    #[test]
+    fn total_internal_reflection() {
+        // Critical angle of glass is about 41.8 degrees.
+        let d = Dielectric::new(1.5);
+        let normal = Vector3f::new(0.0, 1.0, 0.0);
+        let steep = Vector3f::new(0.5, 1.0, 0.0);
+        let flat = Vector3f::new(1.0, 0.5, 0.0);
+        assert!(!d.total_internal_reflection(steep, normal));
+        assert!(d.total_internal_reflection(flat, normal));
+        assert!(!d.total_internal_reflection(-flat, normal));
+    }
+
+    #[test]
+    fn absorption() {
+        let mut d = Dielectric::new(1.33);
+        d.set_absorption(Color3f::new(1.0, 0.5, 0.0));
+        let mat = Material::Dielectric(d);
+        let mut rng = StdRng::seed_from_u64(42);
+        let isect = Intersection {
+            point: Point3f::new(0.0, 2.0, 0.0),
+            normal: Vector3f::new(0.0, 1.0, 0.0),
+            uv: (0.0, 0.0),
+            material: &mat,
+            t: 2.0,
+            error: Vector3f::default(),
+        };
+
+        // Rays entering are not attenuated, rays leaving after distance 2 are.
+        let entering = Ray::new(Point3f::new(0.0, 4.0, 0.0), Vector3f::new(0.0, -1.0, 0.0));
+        let iact = mat.interact(entering, isect, &mut rng).unwrap();
+        assert_eq!(iact.attenuation, Color3f::white());
+        let leaving = Ray::new(Point3f::default(), Vector3f::new(0.0, 1.0, 0.0));
+        let iact = mat.interact(leaving, isect, &mut rng).unwrap();
+        assert_eq!(
+            iact.attenuation,
+            Color3f::new((-2.0f32).exp(), (-1.0f32).exp(), 1.0)
+        );
+    }
 }
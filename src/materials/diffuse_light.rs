@@ -0,0 +1,71 @@
+use crate::base::{
+    color::Color3f,
+    material::{Interactable, Interaction},
+    ray::Ray,
+    shape::Intersection,
+};
+use rand::Rng;
+
+/// Diffuse area light material, emitting uniformly in all directions.
+#[derive(Clone, Copy, Debug, Hash, PartialEq)]
+pub struct DiffuseLight {
+    /// Emitted radiance.
+    emission: Color3f,
+}
+
+impl DiffuseLight {
+    /// Creates diffuse light with given emitted radiance.
+    pub fn new(emission: Color3f) -> Self {
+        DiffuseLight { emission }
+    }
+
+    /// Emitted radiance.
+    pub fn emission(&self) -> Color3f {
+        self.emission
+    }
+}
+
+impl Interactable for DiffuseLight {
+    fn interact(
+        &self,
+        _incident_ray: Ray,
+        _intersection: Intersection,
+        _rng: &mut impl Rng,
+    ) -> Option<Interaction> {
+        // Lights do not scatter.
+        None
+    }
+
+    fn emitted(&self) -> Color3f {
+        self.emission
+    }
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::{material::Material, point::Point3f, vector::Vector3f};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn interact() {
+        let emission = Color3f::new(4.0, 2.0, 1.0);
+        let mat = Material::DiffuseLight(DiffuseLight::new(emission));
+        let r = Ray::new(Point3f::default(), Vector3f::new(0.0, -1.0, 0.0));
+        let isect = Intersection {
+            point: Point3f::new(0.0, -1.0, 0.0),
+            normal: Vector3f::new(0.0, 1.0, 0.0),
+            uv: (0.0, 0.0),
+            material: &mat,
+            t: 1.0,
+            error: Vector3f::default(),
+        };
+        assert_eq!(mat.interact(r, isect, &mut StdRng::seed_from_u64(42)), None);
+        assert_eq!(mat.emitted(), emission);
+        assert_eq!(
+            mat.scattering_pdf(isect, Vector3f::new(0.0, 1.0, 0.0)),
+            None
+        );
+    }
+}
@@ -1,14 +1,16 @@
 use crate::base::{
     color::Color3f,
     material::{Interactable, Interaction},
+    onb::Onb,
     ray::Ray,
+    sampling::{cosine_hemisphere, cosine_hemisphere_pdf},
     shape::Intersection,
     vector::Vector3f,
 };
-use rand::thread_rng;
+use rand::Rng;
 
 /// Lambertian material model.
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, Hash, PartialEq)]
 pub struct Lambert {
     /// Fraction of light that the object reflects.
     albedo: Color3f,
@@ -19,19 +21,22 @@ impl Lambert {
     pub fn new(albedo: Color3f) -> Self {
         Lambert { albedo }
     }
+
+    /// Fraction of light that the object reflects.
+    pub fn albedo(&self) -> Color3f {
+        self.albedo
+    }
 }
 
 impl Interactable for Lambert {
-    fn interact(&self, _incident_ray: Ray, intersection: Intersection) -> Option<Interaction> {
-        let mut rng = thread_rng();
-
+    fn interact(
+        &self,
+        _incident_ray: Ray,
+        intersection: Intersection,
+        rng: &mut impl Rng,
+    ) -> Option<Interaction> {
         // Lambertian distribution.
-        let mut scattered = intersection.normal + Vector3f::random_unit_vector(&mut rng);
-
-        // Catch degenerate scatter direction.
-        if scattered.near_zero() {
-            scattered = intersection.normal;
-        }
+        let scattered = Onb::new(intersection.normal).to_world(cosine_hemisphere(rng));
 
         // Return interaction struct.
         let interaction = Interaction {
@@ -40,6 +45,12 @@ impl Interactable for Lambert {
         };
         Some(interaction)
     }
+
+    fn scattering_pdf(&self, intersection: Intersection, direction: Vector3f) -> Option<f32> {
+        // Cosine-weighted hemisphere.
+        let cos_theta = intersection.normal.dot(&direction.normalize());
+        Some(cosine_hemisphere_pdf(cos_theta))
+    }
 }
 
 /// Unit tests.
@@ -47,6 +58,8 @@ impl Interactable for Lambert {
 mod tests {
     use super::*;
     use crate::base::{material::Material, point::Point3f};
+    use rand::{rngs::StdRng, SeedableRng};
+    use std::f32::consts::PI;
 
     #[test]
     fn interact() {
@@ -57,12 +70,41 @@ mod tests {
         let isect = Intersection {
             point: Point3f::new(1.0, 1.0, 1.0),
             normal: Vector3f::new(0.0, 1.0, 0.0),
+            uv: (0.0, 0.0),
             material: &mat,
             t: 1.0,
+            error: Vector3f::default(),
         };
-        let iact = mat.interact(r, isect).unwrap();
+        let iact = mat
+            .interact(r, isect, &mut StdRng::seed_from_u64(42))
+            .unwrap();
         assert_eq!(iact.attenuation, albedo);
-        assert_eq!(iact.scattered_ray.origin(), isect.point);
+        assert!((iact.scattered_ray.origin() - isect.point).length() < 1e-6);
         assert!(iact.scattered_ray.direction().dot(&isect.normal) >= 0.0);
     }
+
+    #[test]
+    fn scattering_pdf() {
+        let mat = Material::Lambert(Lambert::new(Color3f::white()));
+        let isect = Intersection {
+            point: Point3f::default(),
+            normal: Vector3f::new(0.0, 1.0, 0.0),
+            uv: (0.0, 0.0),
+            material: &mat,
+            t: 1.0,
+            error: Vector3f::default(),
+        };
+        let pdf = |d| mat.scattering_pdf(isect, d).unwrap();
+        assert!((pdf(Vector3f::new(0.0, 2.0, 0.0)) - 1.0 / PI).abs() < 1e-6);
+        assert!((pdf(Vector3f::new(1.0, 1.0, 0.0)) - 0.5f32.sqrt() / PI).abs() < 1e-6);
+        assert_eq!(pdf(Vector3f::new(0.0, -1.0, 0.0)), 0.0);
+
+        // Density integrates to one over hemisphere.
+        let mut rng = StdRng::seed_from_u64(7);
+        let n = 100000;
+        let sum: f32 = (0..n)
+            .map(|_| pdf(Vector3f::random_unit_vector(&mut rng)) * 4.0 * PI)
+            .sum();
+        assert!((sum / n as f32 - 1.0).abs() < 0.02);
+    }
 }
@@ -1,11 +1,13 @@
 use crate::base::{
     color::Color3f,
+    hash::hash_float,
     material::{Interactable, Interaction},
     ray::Ray,
     shape::Intersection,
     vector::Vector3f,
 };
-use rand::thread_rng;
+use rand::Rng;
+use std::hash::{Hash, Hasher};
 
 /// Metal material model.
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -25,28 +27,50 @@ impl Metal {
             fuzz: fuzz.clamp(0.0, 1.0),
         }
     }
+
+    /// Fraction of light that the object reflects.
+    pub fn albedo(&self) -> Color3f {
+        self.albedo
+    }
+
+    /// Reflection fuzz factor.
+    pub fn fuzz(&self) -> f32 {
+        self.fuzz
+    }
 }
 
-impl Interactable for Metal {
-    fn interact(&self, incident_ray: Ray, intersection: Intersection) -> Option<Interaction> {
-        let mut rng = thread_rng();
+impl Hash for Metal {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.albedo.hash(state);
+        hash_float(self.fuzz, state);
+    }
+}
 
+impl Interactable for Metal {
+    fn interact(
+        &self,
+        incident_ray: Ray,
+        intersection: Intersection,
+        rng: &mut impl Rng,
+    ) -> Option<Interaction> {
         // Reflect at intersection normal.
-        let reflected = incident_ray.direction().reflect(intersection.normal);
+        let reflected = incident_ray
+            .direction()
+            .reflect(intersection.normal)
+            .normalize();
 
-        // Apply fuzz.
-        // TODO: Debug difference from https://raytracing.github.io/images/img-1.14-metal-fuzz.png.
-        let mut scattered =
-            reflected.normalize() + self.fuzz * Vector3f::random_unit_vector(&mut rng);
+        // Apply fuzz, offsetting reflection by a random vector of fuzz length.
+        let mut scattered = reflected + self.fuzz * Vector3f::random_unit_vector(rng);
 
         // Catch degenerate scatter direction.
         if scattered.near_zero() {
             scattered = reflected;
         }
 
-        // Absorb scatters below surface.
+        // Reflect scatters below surface about the normal instead of absorbing them, so fuzz does not darken metal at
+        // grazing angles, without the bias of resampling.
         if scattered.dot(&intersection.normal) < 0.0 {
-            return None;
+            scattered = scattered.reflect(intersection.normal);
         }
 
         // Return interaction struct.
@@ -62,7 +86,15 @@ impl Interactable for Metal {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::base::{material::Material, point::Point3f};
+    use crate::{
+        background::Background,
+        base::{material::Material, point::Point3f, shape::Shape},
+        camera::Camera,
+        scene::Scene,
+        settings::RenderSettings,
+        shapes::sphere::Sphere,
+    };
+    use rand::{rngs::StdRng, SeedableRng};
 
     #[test]
     fn interact() {
@@ -73,15 +105,91 @@ mod tests {
         let isect = Intersection {
             point: Point3f::new(1.0, 1.0, 1.0),
             normal: Vector3f::new(0.0, 1.0, 0.0),
+            uv: (0.0, 0.0),
             material: &mat,
             t: 1.0,
+            error: Vector3f::default(),
         };
-        let iact = mat.interact(r, isect).unwrap();
+        let iact = mat
+            .interact(r, isect, &mut StdRng::seed_from_u64(42))
+            .unwrap();
         assert_eq!(iact.attenuation, albedo);
-        assert_eq!(iact.scattered_ray.origin(), isect.point);
+        assert!((iact.scattered_ray.origin() - isect.point).length() < 1e-6);
         assert_eq!(
             iact.scattered_ray.direction(),
             r.direction().reflect(isect.normal).normalize()
         );
     }
+
+    #[test]
+    fn fuzz() {
+        // Reflection offset uniformly over sphere of fuzz radius, so each component of the offset is uniform in
+        // [-fuzz, fuzz]. Normalized cube samples would favor diagonals.
+        let fuzz = 0.5;
+        let mat = Material::Metal(Metal::new(Color3f::white(), fuzz));
+        let normal = Vector3f::new(0.0, 1.0, 0.0);
+        let r = Ray::new(Point3f::new(0.0, 1.0, 0.0), Vector3f::new(0.0, -1.0, 0.0));
+        let isect = Intersection {
+            point: Point3f::default(),
+            normal,
+            uv: (0.0, 0.0),
+            material: &mat,
+            t: 1.0,
+            error: Vector3f::default(),
+        };
+        let mut rng = StdRng::seed_from_u64(42);
+        let n = 10000;
+        let mut outer = 0;
+        for _ in 0..n {
+            let offset = mat
+                .interact(r, isect, &mut rng)
+                .unwrap()
+                .scattered_ray
+                .direction()
+                - normal;
+            assert!((offset.length() - fuzz).abs() < 1e-5);
+            outer += (offset.x().abs() > 0.9 * fuzz) as u32;
+        }
+        assert!((outer as f32 / n as f32 - 0.1).abs() < 0.01);
+
+        // Scatters below surface, about half of them at grazing angles, are reflected about the normal, keeping their
+        // tangential direction.
+        let mat = Material::Metal(Metal::new(Color3f::white(), 1.0));
+        let r = Ray::new(
+            Point3f::new(-20.0, 1.0, 0.0),
+            Vector3f::new(1.0, -0.05, 0.0),
+        );
+        let isect = Intersection {
+            material: &mat,
+            ..isect
+        };
+        let mut forward = 0;
+        for _ in 0..n {
+            let direction = mat
+                .interact(r, isect, &mut rng)
+                .unwrap()
+                .scattered_ray
+                .direction();
+            assert!(direction.dot(&normal) >= 0.0);
+            forward += (direction.x() > 0.0) as u32;
+        }
+        assert!(forward as f32 / n as f32 > 0.8);
+    }
+
+    #[test]
+    fn furnace() {
+        // Convex fuzzy metal in uniform white environment reflects exactly its albedo at every pixel, no light is
+        // lost at silhouettes.
+        let grey = Color3f::new(0.5, 0.5, 0.5);
+        let mut scene = Scene::new();
+        scene.set_background(Background::Uniform(Color3f::white()));
+        scene.add(Shape::Sphere(Sphere::new(
+            Point3f::new(0.0, 0.0, 1.0),
+            1.0,
+            Material::Metal(Metal::new(grey, 1.0)),
+        )));
+        let film = Camera::new(24, 24).render(&scene, &RenderSettings::new(8));
+        assert!(film.pixels().all(|c| c.g() >= 0.5 && c.g() <= 1.0));
+        assert!(film.pixels().filter(|&c| c == grey).count() > 100);
+    }
 }
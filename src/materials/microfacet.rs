@@ -0,0 +1,235 @@
+use crate::base::{
+    color::Color3f,
+    hash::hash_float,
+    material::{Interactable, Interaction},
+    onb::Onb,
+    ray::Ray,
+    shape::Intersection,
+    vector::Vector3f,
+};
+use rand::Rng;
+use std::{
+    f32::consts::PI,
+    hash::{Hash, Hasher},
+};
+
+/// Min GGX width, keeping smooth surfaces numerically stable.
+const MIN_ALPHA: f32 = 1e-3;
+
+/// Microfacet material model of rough surfaces, made of tiny mirror facets oriented by the GGX normal distribution,
+/// with Smith shadowing-masking and Fresnel reflectance (Walter et al. 2007). Either a metal reflecting light, or a
+/// dielectric reflecting and refracting light, like frosted glass.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Microfacet {
+    /// Reflectance at normal incidence of metal, white for dielectrics.
+    albedo: Color3f,
+
+    /// Perceptual roughness in [0,1], from mirror to fully rough. GGX width is its square.
+    roughness: f32,
+
+    /// Index of refraction of dielectric, none for metal.
+    ior: Option<f32>,
+}
+
+impl Microfacet {
+    /// Creates rough metal material with reflectance at normal incidence (Schlick's approximation) and roughness.
+    pub fn metal(albedo: Color3f, roughness: f32) -> Self {
+        Microfacet {
+            albedo,
+            roughness: roughness.clamp(0.0, 1.0),
+            ior: None,
+        }
+    }
+
+    /// Creates rough dielectric material with index of refraction and roughness.
+    pub fn dielectric(index_of_refraction: f32, roughness: f32) -> Self {
+        assert!(index_of_refraction > 0.0);
+        Microfacet {
+            albedo: Color3f::white(),
+            roughness: roughness.clamp(0.0, 1.0),
+            ior: Some(index_of_refraction),
+        }
+    }
+
+    /// Reflectance at normal incidence of metal, white for dielectrics.
+    pub fn albedo(&self) -> Color3f {
+        self.albedo
+    }
+
+    /// Perceptual roughness in [0,1].
+    pub fn roughness(&self) -> f32 {
+        self.roughness
+    }
+
+    /// Index of refraction of dielectric, none for metal.
+    pub fn ior(&self) -> Option<f32> {
+        self.ior
+    }
+
+    /// GGX width.
+    pub(crate) fn alpha(&self) -> f32 {
+        (self.roughness * self.roughness).max(MIN_ALPHA)
+    }
+
+    /// Random microfacet normal in local coordinates around the z-axis, distributed by GGX density times cosine.
+    fn sample_normal(&self, rng: &mut impl Rng) -> Vector3f {
+        let u: f32 = rng.gen();
+        let tan2_theta = self.alpha() * self.alpha() * u / (1.0 - u).max(f32::MIN_POSITIVE);
+        let cos_theta = 1.0 / (1.0 + tan2_theta).sqrt();
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * PI * rng.gen::<f32>();
+        Vector3f::new(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta)
+    }
+
+    /// Smith shadowing-masking of GGX for direction with given cosine to the macro normal.
+    fn smith_g1(&self, cos: f32) -> f32 {
+        let cos2 = (cos * cos).max(f32::MIN_POSITIVE);
+        let tan2 = (1.0 - cos2) / cos2;
+        2.0 / (1.0 + (1.0 + self.alpha() * self.alpha() * tan2).sqrt())
+    }
+}
+
+impl Hash for Microfacet {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.albedo.hash(state);
+        hash_float(self.roughness, state);
+        if let Some(ior) = self.ior {
+            hash_float(ior, state);
+        }
+    }
+}
+
+impl Interactable for Microfacet {
+    /// Samples microfacet normal by GGX density times cosine and reflects or refracts at it. Attenuation is the
+    /// sample weight, so depends on scattered direction, and `scattering_pdf` stays none.
+    fn interact(
+        &self,
+        incident_ray: Ray,
+        intersection: Intersection,
+        rng: &mut impl Rng,
+    ) -> Option<Interaction> {
+        // Determine whether ray is inside or outside object, flip outward normal.
+        let front_face = incident_ray.direction().dot(&intersection.normal) <= 0.0;
+        let normal = if front_face {
+            intersection.normal
+        } else {
+            -intersection.normal
+        }
+        .normalize();
+
+        // Sample microfacet normal facing incident ray.
+        let incident = incident_ray.direction().normalize();
+        let facet = Onb::new(normal).to_world(self.sample_normal(rng));
+        let (cos_o, cos_h, cos_oh) = (
+            -incident.dot(&normal),
+            facet.dot(&normal),
+            -incident.dot(&facet),
+        );
+        if cos_o <= 0.0 || cos_oh <= 0.0 {
+            return None;
+        }
+
+        // Reflect or refract at microfacet, choosing by Fresnel reflectance of dielectrics.
+        let (scattered, fresnel) = match self.ior {
+            None => {
+                let f0 = self.albedo;
+                let fresnel = f0 + (Color3f::white() - f0) * (1.0 - cos_oh).powi(5);
+                (incident.reflect(facet), fresnel)
+            }
+            Some(ior) => {
+                let eta = if front_face { 1.0 / ior } else { ior };
+                let r0 = ((1.0 - eta) / (1.0 + eta)).powi(2);
+                let reflectance = r0 + (1.0 - r0) * (1.0 - cos_oh).powi(5);
+                let scattered = match incident.refract(facet, eta) {
+                    Some(refracted) if reflectance <= rng.gen() => refracted,
+                    _ => incident.reflect(facet),
+                };
+                (scattered, Color3f::white())
+            }
+        };
+
+        // Absorb reflections below and refractions above surface.
+        let cos_i = scattered.dot(&normal);
+        let reflected = scattered.dot(&facet) > 0.0;
+        if reflected != (cos_i > 0.0) || cos_i == 0.0 {
+            return None;
+        }
+
+        // Sample weight, Fresnel and density of microfacet normals cancel partially.
+        let shadowing = self.smith_g1(cos_o) * self.smith_g1(cos_i);
+        let weight = shadowing * cos_oh / (cos_o * cos_h);
+        let interaction = Interaction {
+            attenuation: fresnel * weight,
+            scattered_ray: Ray::new(intersection.point, scattered),
+        };
+        Some(interaction)
+    }
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::{material::Material, point::Point3f};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    /// Mean attenuation and fractions of reflected and refracted samples of ray hitting ground at 45 degrees.
+    fn scatter(material: Microfacet) -> (f32, f32, f32) {
+        let mat = Material::Microfacet(material);
+        let r = Ray::new(Point3f::default(), Vector3f::new(1.0, -1.0, 0.0));
+        let isect = Intersection {
+            point: Point3f::new(1.0, -1.0, 0.0),
+            normal: Vector3f::new(0.0, 1.0, 0.0),
+            uv: (0.0, 0.0),
+            material: &mat,
+            t: 1.0,
+            error: Vector3f::default(),
+        };
+        let mut rng = StdRng::seed_from_u64(42);
+        let n = 10000;
+        let (mut mean, mut reflected, mut refracted) = (0.0, 0.0, 0.0);
+        for _ in 0..n {
+            if let Some(iact) = mat.interact(r, isect, &mut rng) {
+                assert!((iact.scattered_ray.origin() - isect.point).length() < 1e-6);
+                mean += iact.attenuation.g() / n as f32;
+                if iact.scattered_ray.direction().y() > 0.0 {
+                    reflected += 1.0 / n as f32;
+                } else {
+                    refracted += 1.0 / n as f32;
+                }
+            }
+        }
+        (mean, reflected, refracted)
+    }
+
+    #[test]
+    fn interact() {
+        // Smooth metal is a mirror.
+        let mat = Material::Microfacet(Microfacet::metal(Color3f::white(), 0.0));
+        let r = Ray::new(Point3f::default(), Vector3f::new(1.0, -1.0, 0.0));
+        let isect = Intersection {
+            point: Point3f::new(1.0, -1.0, 0.0),
+            normal: Vector3f::new(0.0, 1.0, 0.0),
+            uv: (0.0, 0.0),
+            material: &mat,
+            t: 1.0,
+            error: Vector3f::default(),
+        };
+        let iact = mat
+            .interact(r, isect, &mut StdRng::seed_from_u64(42))
+            .unwrap();
+        let mirrored = r.direction().normalize().reflect(isect.normal);
+        assert!((iact.scattered_ray.direction() - mirrored).length() < 0.01);
+        assert!((iact.attenuation.g() - 1.0).abs() < 0.01);
+
+        // White rough metal reflects most light, losing some to shadowing.
+        let (mean, reflected, _) = scatter(Microfacet::metal(Color3f::white(), 0.5));
+        assert!(mean > 0.85 && mean < 1.0);
+        assert!(reflected > 0.9);
+
+        // Rough glass mostly refracts, preserving most light.
+        let (mean, reflected, refracted) = scatter(Microfacet::dielectric(1.5, 0.3));
+        assert!(mean > 0.9 && mean < 1.05);
+        assert!(refracted > 0.85 && reflected > 0.02);
+    }
+}
@@ -0,0 +1,269 @@
+use crate::{
+    base::{
+        color::Color3f,
+        hash::hash_float,
+        material::{Interactable, Interaction, Material},
+        ray::Ray,
+        shape::Intersection,
+        vector::Vector3f,
+    },
+    image::RgbaImage,
+};
+use rand::Rng;
+use std::{
+    hash::{Hash, Hasher},
+    sync::Arc,
+};
+
+/// Weight of second material of a mix, in [0,1].
+#[derive(Clone, Debug, PartialEq)]
+pub enum MixFactor {
+    /// Same weight everywhere.
+    Constant(f32),
+
+    /// Luminance of image at texture coordinates, e.g. a pattern of metal on diffuse.
+    Texture(Arc<RgbaImage>),
+
+    /// Fresnel reflectance of a coating with index of refraction (Schlick's approximation), rising from normal to
+    /// grazing incidence.
+    Fresnel(f32),
+}
+
+impl MixFactor {
+    /// Weight at intersection of incident ray.
+    fn eval(&self, incident_ray: Ray, intersection: &Intersection) -> f32 {
+        match self {
+            MixFactor::Constant(f) => *f,
+            MixFactor::Texture(image) => {
+                let (u, v) = intersection.uv;
+                image.sample(u, v).0.luminance().clamp(0.0, 1.0)
+            }
+            MixFactor::Fresnel(ior) => {
+                let r0 = ((1.0 - ior) / (1.0 + ior)).powi(2);
+                let cos = incident_ray
+                    .direction()
+                    .normalize()
+                    .dot(&intersection.normal)
+                    .abs();
+                r0 + (1.0 - r0) * (1.0 - cos).powi(5)
+            }
+        }
+    }
+
+    /// Mean weight over surface, or over directions of incidence of Fresnel weights (cosine weighted).
+    fn mean(&self) -> f32 {
+        match self {
+            MixFactor::Constant(f) => *f,
+            MixFactor::Texture(image) => {
+                let (width, height) = (image.width(), image.height());
+                let sum: f32 = (0..height)
+                    .flat_map(|y| (0..width).map(move |x| (x, y)))
+                    .map(|(x, y)| {
+                        let (u, v) = (
+                            (x as f32 + 0.5) / width as f32,
+                            (y as f32 + 0.5) / height as f32,
+                        );
+                        image.sample(u, v).0.luminance().clamp(0.0, 1.0)
+                    })
+                    .sum();
+                sum / (width * height) as f32
+            }
+            MixFactor::Fresnel(ior) => {
+                let r0 = ((1.0 - ior) / (1.0 + ior)).powi(2);
+                r0 + (1.0 - r0) / 21.0
+            }
+        }
+    }
+}
+
+impl Hash for MixFactor {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            MixFactor::Constant(f) | MixFactor::Fresnel(f) => hash_float(*f, state),
+            MixFactor::Texture(image) => image.hash(state),
+        }
+    }
+}
+
+/// Blend of two materials, e.g. patterned metal and diffuse, or a clearcoat layered over a base. Each interaction
+/// picks the second material with probability of the mix factor, the first one otherwise.
+///
+/// The scattering density is known only where both materials agree on it, e.g. two lambertian ones. Other mixes
+/// continue paths like specular surfaces.
+#[derive(Clone, Debug, Hash, PartialEq)]
+pub struct Mix {
+    /// Material picked with probability one minus factor.
+    first: Box<Material>,
+
+    /// Material picked with probability of factor.
+    second: Box<Material>,
+
+    /// Weight of second material.
+    factor: MixFactor,
+}
+
+impl Mix {
+    /// Creates mix of first and second material, weighting the second one by factor. Constant factors are clamped
+    /// to [0,1].
+    pub fn new(first: Material, second: Material, factor: MixFactor) -> Self {
+        let factor = match factor {
+            MixFactor::Constant(f) => MixFactor::Constant(f.clamp(0.0, 1.0)),
+            MixFactor::Fresnel(ior) => {
+                assert!(ior > 0.0);
+                MixFactor::Fresnel(ior)
+            }
+            factor => factor,
+        };
+        Mix {
+            first: Box::new(first),
+            second: Box::new(second),
+            factor,
+        }
+    }
+
+    /// Creates coating over base material, e.g. a smooth metal of white albedo for clearcoat. The coating shows by
+    /// its Fresnel reflectance with index of refraction, so mostly at grazing angles.
+    pub fn layer(base: Material, coating: Material, ior: f32) -> Self {
+        Mix::new(base, coating, MixFactor::Fresnel(ior))
+    }
+
+    /// Material picked with probability one minus factor, base of layers.
+    pub fn first(&self) -> &Material {
+        &self.first
+    }
+
+    /// Material picked with probability of factor, coating of layers.
+    pub fn second(&self) -> &Material {
+        &self.second
+    }
+
+    /// Weight of second material.
+    pub fn factor(&self) -> &MixFactor {
+        &self.factor
+    }
+}
+
+impl Interactable for Mix {
+    fn interact(
+        &self,
+        incident_ray: Ray,
+        intersection: Intersection,
+        rng: &mut impl Rng,
+    ) -> Option<Interaction> {
+        // Pick material by weight, so weights cancel with selection probabilities.
+        if self.factor.eval(incident_ray, &intersection) > rng.gen() {
+            self.second.interact(incident_ray, intersection, rng)
+        } else {
+            self.first.interact(incident_ray, intersection, rng)
+        }
+    }
+
+    /// Emission blended by mean factor, as emission does not depend on surface point or direction.
+    fn emitted(&self) -> Color3f {
+        let f = self.factor.mean();
+        self.first.emitted().lerp(self.second.emitted(), f)
+    }
+
+    fn scattering_pdf(&self, intersection: Intersection, direction: Vector3f) -> Option<f32> {
+        let first = self.first.scattering_pdf(intersection, direction)?;
+        let second = self.second.scattering_pdf(intersection, direction)?;
+        (first == second).then_some(first)
+    }
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        base::point::Point3f,
+        materials::{diffuse_light::DiffuseLight, lambert::Lambert, metal::Metal},
+    };
+    use rand::{rngs::StdRng, SeedableRng};
+
+    /// Mean attenuation and fraction of mirrored samples of ray hitting ground at angle to normal (degrees).
+    fn scatter(mix: Mix, uv: (f32, f32), angle: f32) -> (Color3f, f32) {
+        let mat = Material::Mix(mix);
+        let (sin, cos) = angle.to_radians().sin_cos();
+        let r = Ray::new(Point3f::new(-sin, cos, 0.0), Vector3f::new(sin, -cos, 0.0));
+        let isect = Intersection {
+            point: Point3f::default(),
+            normal: Vector3f::new(0.0, 1.0, 0.0),
+            uv,
+            material: &mat,
+            t: 1.0,
+            error: Vector3f::default(),
+        };
+        let mirrored = r.direction().reflect(isect.normal);
+        let mut rng = StdRng::seed_from_u64(42);
+        let n = 10000;
+        let (mut mean, mut mirror) = (Color3f::black(), 0.0);
+        for _ in 0..n {
+            if let Some(iact) = mat.interact(r, isect, &mut rng) {
+                mean += iact.attenuation / n as f32;
+                if (iact.scattered_ray.direction().normalize() - mirrored).length() < 1e-4 {
+                    mirror += 1.0 / n as f32;
+                }
+            }
+        }
+        (mean, mirror)
+    }
+
+    #[test]
+    fn interact() {
+        let red = Material::Lambert(Lambert::new(Color3f::new(1.0, 0.0, 0.0)));
+        let mirror = Material::Metal(Metal::new(Color3f::white(), 0.0));
+
+        // Constant factor blends attenuation and picks mirror by weight.
+        let mix = Mix::new(red.clone(), mirror.clone(), MixFactor::Constant(0.25));
+        let (mean, mirrored) = scatter(mix, (0.0, 0.0), 45.0);
+        assert!((mirrored - 0.25).abs() < 0.02);
+        assert!((mean.g() - 0.25).abs() < 0.02 && mean.r() > 0.99);
+
+        // Texture picks material by luminance at texture coordinates.
+        let pattern = RgbaImage::new(2, 1, vec![Color3f::black(), Color3f::white()], vec![1.0; 2]);
+        let mix = Mix::new(
+            red.clone(),
+            mirror.clone(),
+            MixFactor::Texture(Arc::new(pattern)),
+        );
+        assert_eq!(scatter(mix.clone(), (0.25, 0.5), 45.0).1, 0.0);
+        assert!(scatter(mix, (0.75, 0.5), 45.0).1 > 0.999);
+
+        // Coating reflects little at normal, much at grazing incidence.
+        let coated = Mix::layer(red, mirror, 1.5);
+        let (_, normal) = scatter(coated.clone(), (0.0, 0.0), 0.0);
+        let (_, grazing) = scatter(coated, (0.0, 0.0), 85.0);
+        assert!((normal - 0.04).abs() < 0.01);
+        assert!(grazing > 0.5);
+    }
+
+    #[test]
+    fn emitted_and_scattering_pdf() {
+        let white = Material::Lambert(Lambert::new(Color3f::white()));
+        let red = Material::Lambert(Lambert::new(Color3f::new(1.0, 0.0, 0.0)));
+        let light = Material::DiffuseLight(DiffuseLight::new(Color3f::new(4.0, 4.0, 4.0)));
+        let mix = Mix::new(white.clone(), light, MixFactor::Constant(0.25));
+        assert_eq!(mix.emitted(), Color3f::white());
+
+        // Density is known for lambertian mixes only.
+        let isect = Intersection {
+            point: Point3f::default(),
+            normal: Vector3f::new(0.0, 1.0, 0.0),
+            uv: (0.0, 0.0),
+            material: &white,
+            t: 1.0,
+            error: Vector3f::default(),
+        };
+        let up = Vector3f::new(0.0, 1.0, 0.0);
+        let diffuse = Mix::new(white.clone(), red, MixFactor::Constant(0.5));
+        assert_eq!(
+            diffuse.scattering_pdf(isect, up),
+            white.scattering_pdf(isect, up)
+        );
+        let mirror = Material::Metal(Metal::new(Color3f::white(), 0.0));
+        let glossy = Mix::new(white.clone(), mirror, MixFactor::Constant(0.5));
+        assert_eq!(glossy.scattering_pdf(isect, up), None);
+    }
+}
@@ -0,0 +1,230 @@
+use crate::{
+    base::{
+        color::Color3f,
+        hash::hash_float,
+        material::{Interactable, Interaction, Material},
+        ray::Ray,
+        shape::Intersection,
+        vector::Vector3f,
+    },
+    image::RgbaImage,
+};
+use rand::Rng;
+use std::{
+    hash::{Hash, Hasher},
+    sync::Arc,
+};
+
+/// Texture perturbing the shading normal in the tangent frame of a surface point, see `tangent_frame`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum NormalMap {
+    /// Tangent-space normals encoded as colors, each channel mapping [0,1] to [-1,1]. Red points along u, green
+    /// along v and blue along the surface normal, so flat maps are light blue (0.5, 0.5, 1.0).
+    Normal(Arc<RgbaImage>),
+
+    /// Height as luminance of image, with strength. Normals tilt away from rising height, by strength times the
+    /// height difference between neighbouring pixels.
+    Bump(Arc<RgbaImage>, f32),
+}
+
+impl NormalMap {
+    /// Perturbed shading normal at intersection, of unit length and on the side of the geometric normal.
+    pub fn normal(&self, intersection: &Intersection) -> Vector3f {
+        let normal = intersection.normal.normalize();
+        let (tangent, bitangent) = tangent_frame(normal);
+        let (u, v) = intersection.uv;
+        let perturbed = match self {
+            NormalMap::Normal(image) => {
+                let c = image.sample(u, v).0;
+                let (x, y, z) = (2.0 * c.r() - 1.0, 2.0 * c.g() - 1.0, 2.0 * c.b() - 1.0);
+                x * tangent + y * bitangent + z * normal
+            }
+            NormalMap::Bump(image, strength) => {
+                let height = |u: f32, v: f32| image.sample(u, v).0.luminance();
+                let (du, dv) = (1.0 / image.width() as f32, 1.0 / image.height() as f32);
+                let slope_u = (height(u + du, v) - height(u - du, v)) / 2.0;
+                let slope_v = (height(u, v + dv) - height(u, v - dv)) / 2.0;
+                normal - *strength * (slope_u * tangent + slope_v * bitangent)
+            }
+        };
+
+        // Degenerate or flipped normals would let light pass through the surface.
+        if perturbed.dot(&normal) > 1e-4 {
+            perturbed.normalize()
+        } else {
+            normal
+        }
+    }
+}
+
+impl Hash for NormalMap {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            NormalMap::Normal(image) => image.hash(state),
+            NormalMap::Bump(image, strength) => {
+                image.hash(state);
+                hash_float(*strength, state);
+            }
+        }
+    }
+}
+
+/// Tangents along increasing u and v around unit normal, for texture coordinates wrapping around the y-axis as on
+/// spheres and upright cylinders. Tangents of normals along the y-axis are the x-axis and the normal cross it.
+pub fn tangent_frame(normal: Vector3f) -> (Vector3f, Vector3f) {
+    let tangent = Vector3f::new(0.0, 1.0, 0.0).cross(&normal);
+    let tangent = if tangent.length() > 1e-4 {
+        tangent.normalize()
+    } else {
+        Vector3f::new(1.0, 0.0, 0.0)
+    };
+    (tangent, normal.cross(&tangent))
+}
+
+/// Material with shading normal perturbed by normal or bump map, adding surface detail without geometry, e.g.
+/// to low-poly meshes. Interactions of the inner material see the perturbed normal, while rays still hit the
+/// unperturbed surface.
+#[derive(Clone, Debug, Hash, PartialEq)]
+pub struct NormalMapped {
+    /// Material interacting at perturbed normal.
+    material: Box<Material>,
+
+    /// Texture perturbing normal.
+    map: NormalMap,
+}
+
+impl NormalMapped {
+    /// Creates material perturbing normal of inner material by map. Bump strengths must not be negative.
+    pub fn new(material: Material, map: NormalMap) -> Self {
+        if let NormalMap::Bump(_, strength) = map {
+            assert!(strength >= 0.0);
+        }
+        NormalMapped {
+            material: Box::new(material),
+            map,
+        }
+    }
+
+    /// Material interacting at perturbed normal.
+    pub fn material(&self) -> &Material {
+        &self.material
+    }
+
+    /// Texture perturbing normal.
+    pub fn map(&self) -> &NormalMap {
+        &self.map
+    }
+
+    /// Intersection with perturbed shading normal.
+    fn shading<'a>(&self, intersection: Intersection<'a>) -> Intersection<'a> {
+        Intersection {
+            normal: self.map.normal(&intersection),
+            ..intersection
+        }
+    }
+}
+
+impl Interactable for NormalMapped {
+    fn interact(
+        &self,
+        incident_ray: Ray,
+        intersection: Intersection,
+        rng: &mut impl Rng,
+    ) -> Option<Interaction> {
+        self.material
+            .interact(incident_ray, self.shading(intersection), rng)
+    }
+
+    fn emitted(&self) -> Color3f {
+        self.material.emitted()
+    }
+
+    fn scattering_pdf(&self, intersection: Intersection, direction: Vector3f) -> Option<f32> {
+        self.material
+            .scattering_pdf(self.shading(intersection), direction)
+    }
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{base::point::Point3f, materials::lambert::Lambert};
+    use std::f32::consts::{FRAC_1_SQRT_2, PI};
+
+    fn ground(material: &Material, uv: (f32, f32)) -> Intersection<'_> {
+        Intersection {
+            point: Point3f::default(),
+            normal: Vector3f::new(0.0, 1.0, 0.0),
+            uv,
+            material,
+            t: 1.0,
+            error: Vector3f::default(),
+        }
+    }
+
+    #[test]
+    fn tangent_frame() {
+        // Along increasing sphere texture coordinates.
+        let (t, b) = super::tangent_frame(Vector3f::new(1.0, 0.0, 0.0));
+        assert_eq!(t, Vector3f::new(0.0, 0.0, -1.0));
+        assert_eq!(b, Vector3f::new(0.0, 1.0, 0.0));
+        let n = Vector3f::new(1.0, 2.0, 3.0).normalize();
+        let (t, b) = super::tangent_frame(n);
+        assert!(t.dot(&b).abs() < 1e-6 && t.dot(&n).abs() < 1e-6 && b.dot(&n).abs() < 1e-6);
+        assert!((b.length() - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn normal() {
+        let white = Material::Lambert(Lambert::new(Color3f::white()));
+        let pixel = |c| Arc::new(RgbaImage::new(1, 1, vec![c], vec![1.0]));
+
+        // Flat map keeps normal, tilted one turns it along u (x-axis on ground).
+        let flat = NormalMap::Normal(pixel(Color3f::new(0.5, 0.5, 1.0)));
+        let up = Vector3f::new(0.0, 1.0, 0.0);
+        assert_eq!(flat.normal(&ground(&white, (0.5, 0.5))), up);
+        let h = 0.5 + 0.5 * FRAC_1_SQRT_2;
+        let tilted = NormalMap::Normal(pixel(Color3f::new(h, 0.5, h)));
+        let n = tilted.normal(&ground(&white, (0.5, 0.5)));
+        assert!((n - Vector3f::new(1.0, 1.0, 0.0).normalize()).length() < 1e-3);
+
+        // Normals pointing below surface are ignored.
+        let below = NormalMap::Normal(pixel(Color3f::new(1.0, 0.5, 0.0)));
+        assert_eq!(below.normal(&ground(&white, (0.5, 0.5))), up);
+
+        // Bump tilts normal away from rising height, more with strength.
+        let ramp = Arc::new(RgbaImage::new(
+            3,
+            1,
+            vec![
+                Color3f::black(),
+                Color3f::new(0.5, 0.5, 0.5),
+                Color3f::white(),
+            ],
+            vec![1.0; 3],
+        ));
+        let weak = NormalMap::Bump(ramp.clone(), 1.0).normal(&ground(&white, (0.5, 0.5)));
+        let strong = NormalMap::Bump(ramp, 2.0).normal(&ground(&white, (0.5, 0.5)));
+        assert!(weak.x() < 0.0 && strong.x() < weak.x());
+        assert!(weak.z().abs() < 1e-6 && (weak.length() - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn scattering_pdf() {
+        let white = Material::Lambert(Lambert::new(Color3f::white()));
+        let h = 0.5 + 0.5 * FRAC_1_SQRT_2;
+        let image = RgbaImage::new(1, 1, vec![Color3f::new(h, 0.5, h)], vec![1.0]);
+        let mapped = NormalMapped::new(white.clone(), NormalMap::Normal(Arc::new(image)));
+
+        // Lambertian density follows perturbed normal.
+        let isect = ground(&white, (0.5, 0.5));
+        let tilted = Vector3f::new(1.0, 1.0, 0.0).normalize();
+        let pdf = mapped.scattering_pdf(isect, tilted).unwrap();
+        assert!((pdf - 1.0 / PI).abs() < 1e-3);
+        let pdf = white.scattering_pdf(isect, tilted).unwrap();
+        assert!((pdf - FRAC_1_SQRT_2 / PI).abs() < 1e-3);
+        assert_eq!(mapped.emitted(), Color3f::black());
+    }
+}
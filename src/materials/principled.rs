@@ -0,0 +1,232 @@
+use crate::{
+    base::{
+        color::Color3f,
+        hash::hash_float,
+        material::{Interactable, Interaction},
+        ray::Ray,
+        shape::Intersection,
+    },
+    materials::{lambert::Lambert, microfacet::Microfacet},
+};
+use rand::Rng;
+use std::hash::{Hash, Hasher};
+
+/// Principled material model in the style of the Disney BSDF, combining metal, rough glass and diffuse base under a
+/// specular coating, weighted by a few artist-friendly parameters. Matches the PBR parameters of imported assets,
+/// e.g. UsdPreviewSurface. Each interaction picks one lobe at random by its weight.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Principled {
+    /// Diffuse albedo of dielectrics, reflectance at normal incidence of metals, tint of transmitted light.
+    base_color: Color3f,
+
+    /// Blend from dielectric to metal, in [0,1].
+    metallic: f32,
+
+    /// Perceptual roughness of specular lobes, in [0,1].
+    roughness: f32,
+
+    /// Specular coating strength of dielectrics, in [0,1], where 0.5 is reflectance 4% at normal incidence.
+    specular: f32,
+
+    /// Blend of dielectrics from opaque diffuse base to rough glass, in [0,1].
+    transmission: f32,
+
+    /// Index of refraction of transmitting dielectrics.
+    ior: f32,
+}
+
+impl Principled {
+    /// Creates opaque dielectric principled material with base color, roughness 0.5, specular 0.5 and ior 1.5.
+    pub fn new(base_color: Color3f) -> Self {
+        Principled {
+            base_color,
+            metallic: 0.0,
+            roughness: 0.5,
+            specular: 0.5,
+            transmission: 0.0,
+            ior: 1.5,
+        }
+    }
+
+    /// Diffuse albedo of dielectrics, reflectance at normal incidence of metals, tint of transmitted light.
+    pub fn base_color(&self) -> Color3f {
+        self.base_color
+    }
+
+    /// Sets base color.
+    pub fn set_base_color(&mut self, base_color: Color3f) {
+        self.base_color = base_color;
+    }
+
+    /// Blend from dielectric to metal.
+    pub fn metallic(&self) -> f32 {
+        self.metallic
+    }
+
+    /// Sets blend from dielectric to metal, clamped to [0,1].
+    pub fn set_metallic(&mut self, metallic: f32) {
+        self.metallic = metallic.clamp(0.0, 1.0);
+    }
+
+    /// Perceptual roughness of specular lobes.
+    pub fn roughness(&self) -> f32 {
+        self.roughness
+    }
+
+    /// Sets perceptual roughness of specular lobes, clamped to [0,1].
+    pub fn set_roughness(&mut self, roughness: f32) {
+        self.roughness = roughness.clamp(0.0, 1.0);
+    }
+
+    /// Specular coating strength of dielectrics.
+    pub fn specular(&self) -> f32 {
+        self.specular
+    }
+
+    /// Sets specular coating strength of dielectrics, clamped to [0,1].
+    pub fn set_specular(&mut self, specular: f32) {
+        self.specular = specular.clamp(0.0, 1.0);
+    }
+
+    /// Blend of dielectrics from opaque diffuse base to rough glass.
+    pub fn transmission(&self) -> f32 {
+        self.transmission
+    }
+
+    /// Sets blend of dielectrics from opaque diffuse base to rough glass, clamped to [0,1].
+    pub fn set_transmission(&mut self, transmission: f32) {
+        self.transmission = transmission.clamp(0.0, 1.0);
+    }
+
+    /// Index of refraction of transmitting dielectrics.
+    pub fn ior(&self) -> f32 {
+        self.ior
+    }
+
+    /// Sets index of refraction of transmitting dielectrics.
+    pub fn set_ior(&mut self, ior: f32) {
+        assert!(ior > 0.0);
+        self.ior = ior;
+    }
+}
+
+impl Hash for Principled {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.base_color.hash(state);
+        for x in [
+            self.metallic,
+            self.roughness,
+            self.specular,
+            self.transmission,
+            self.ior,
+        ] {
+            hash_float(x, state);
+        }
+    }
+}
+
+impl Interactable for Principled {
+    fn interact(
+        &self,
+        incident_ray: Ray,
+        intersection: Intersection,
+        rng: &mut impl Rng,
+    ) -> Option<Interaction> {
+        // Pick lobe by weight, so weights cancel with selection probabilities.
+        let u: f32 = rng.gen();
+        let glass = self.metallic + (1.0 - self.metallic) * self.transmission;
+        if u < self.metallic {
+            // Metal.
+            Microfacet::metal(self.base_color, self.roughness).interact(
+                incident_ray,
+                intersection,
+                rng,
+            )
+        } else if u < glass {
+            // Rough glass, tinting transmitted light.
+            let glass = Microfacet::dielectric(self.ior, self.roughness);
+            let mut interaction = glass.interact(incident_ray, intersection, rng)?;
+            let normal = intersection.normal;
+            let incident = incident_ray.direction().dot(&normal);
+            if interaction.scattered_ray.direction().dot(&normal) * incident > 0.0 {
+                interaction.attenuation *= self.base_color;
+            }
+            Some(interaction)
+        } else {
+            // Diffuse base under specular coating, reflecting by Schlick's approximation.
+            let cos = incident_ray
+                .direction()
+                .normalize()
+                .dot(&intersection.normal)
+                .abs();
+            let r0 = 0.08 * self.specular;
+            let reflectance = r0 + (1.0 - r0) * (1.0 - cos).powi(5);
+            if reflectance > rng.gen() {
+                Microfacet::metal(Color3f::white(), self.roughness).interact(
+                    incident_ray,
+                    intersection,
+                    rng,
+                )
+            } else {
+                Lambert::new(self.base_color).interact(incident_ray, intersection, rng)
+            }
+        }
+    }
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::{material::Material, point::Point3f, vector::Vector3f};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    /// Mean attenuation and fraction of transmitted samples of ray hitting ground at 45 degrees.
+    fn scatter(material: Principled) -> (Color3f, f32) {
+        let mat = Material::Principled(material);
+        let r = Ray::new(Point3f::default(), Vector3f::new(1.0, -1.0, 0.0));
+        let isect = Intersection {
+            point: Point3f::new(1.0, -1.0, 0.0),
+            normal: Vector3f::new(0.0, 1.0, 0.0),
+            uv: (0.0, 0.0),
+            material: &mat,
+            t: 1.0,
+            error: Vector3f::default(),
+        };
+        let mut rng = StdRng::seed_from_u64(42);
+        let n = 10000;
+        let (mut mean, mut transmitted) = (Color3f::black(), 0.0);
+        for _ in 0..n {
+            if let Some(iact) = mat.interact(r, isect, &mut rng) {
+                mean += iact.attenuation / n as f32;
+                if iact.scattered_ray.direction().y() < 0.0 {
+                    transmitted += 1.0 / n as f32;
+                }
+            }
+        }
+        (mean, transmitted)
+    }
+
+    #[test]
+    fn interact() {
+        // Diffuse base shows base color, coating reflects some white light.
+        let red = Color3f::new(1.0, 0.0, 0.0);
+        let (mean, transmitted) = scatter(Principled::new(red));
+        assert!(mean.r() > 0.9 && mean.g() > 0.0 && mean.g() < 0.1);
+        assert_eq!(transmitted, 0.0);
+
+        // Metal reflects base color, whitened only at grazing microfacets by Fresnel.
+        let mut metal = Principled::new(red);
+        metal.set_metallic(1.0);
+        let (mean, _) = scatter(metal);
+        assert!(mean.r() > 0.8 && mean.g() < 0.01);
+
+        // Glass transmits most light, tinted by base color.
+        let mut glass = Principled::new(Color3f::new(0.5, 0.5, 0.5));
+        glass.set_transmission(1.0);
+        glass.set_roughness(0.1);
+        let (mean, transmitted) = scatter(glass);
+        assert!(transmitted > 0.9);
+        assert!(mean.g() > 0.45 && mean.g() < 0.6);
+    }
+}
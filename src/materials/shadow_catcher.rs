@@ -0,0 +1,92 @@
+use crate::base::{
+    color::Color3f,
+    hash::hash_float,
+    material::{Interactable, Interaction},
+    ray::Ray,
+    shape::Intersection,
+};
+use rand::Rng;
+use std::hash::{Hash, Hasher};
+
+/// Shadow catcher material, e.g. for invisible ground planes in product shots. Rays pass through unchanged, so the
+/// background shows, darkened where objects occlude the surface (ambient occlusion within a distance). Integrators
+/// apply the darkening, see `integrator::catcher_shadow`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ShadowCatcher {
+    /// Max distance of occluders.
+    distance: f32,
+
+    /// Darkening of fully occluded surface, in [0,1].
+    strength: f32,
+}
+
+impl ShadowCatcher {
+    /// Creates shadow catcher with max occluder distance and darkening strength.
+    pub fn new(distance: f32, strength: f32) -> Self {
+        assert!(distance > 0.0 && (0.0..=1.0).contains(&strength));
+        ShadowCatcher { distance, strength }
+    }
+
+    /// Max distance of occluders.
+    pub fn distance(&self) -> f32 {
+        self.distance
+    }
+
+    /// Darkening of fully occluded surface, in [0,1].
+    pub fn strength(&self) -> f32 {
+        self.strength
+    }
+}
+
+impl Hash for ShadowCatcher {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        hash_float(self.distance, state);
+        hash_float(self.strength, state);
+    }
+}
+
+impl Interactable for ShadowCatcher {
+    fn interact(
+        &self,
+        incident_ray: Ray,
+        intersection: Intersection,
+        _rng: &mut impl Rng,
+    ) -> Option<Interaction> {
+        // Continue ray behind surface.
+        let interaction = Interaction {
+            attenuation: Color3f::white(),
+            scattered_ray: Ray::new(intersection.point, incident_ray.direction()),
+        };
+        Some(interaction)
+    }
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::{material::Material, point::Point3f, vector::Vector3f};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn interact() {
+        let mat = Material::ShadowCatcher(ShadowCatcher::new(1.0, 0.5));
+        let r = Ray::new(Point3f::default(), Vector3f::new(1.0, -1.0, 0.0));
+        let isect = Intersection {
+            point: Point3f::new(1.0, -1.0, 0.0),
+            normal: Vector3f::new(0.0, 1.0, 0.0),
+            uv: (0.0, 0.0),
+            material: &mat,
+            t: 1.0,
+            error: Vector3f::default(),
+        };
+        let iact = mat
+            .interact(r, isect, &mut StdRng::seed_from_u64(42))
+            .unwrap();
+        assert_eq!(iact.attenuation, Color3f::white());
+        assert!((iact.scattered_ray.origin() - isect.point).length() < 1e-6);
+        assert_eq!(iact.scattered_ray.direction(), r.direction());
+        assert_eq!(mat.emitted(), Color3f::black());
+        assert_eq!(mat.scattering_pdf(isect, r.direction()), None);
+    }
+}
@@ -0,0 +1,321 @@
+use crate::{
+    base::color::Color3f,
+    film::{Film, FilmPrecision},
+    font::{draw_text, text_width, GLYPH_HEIGHT},
+};
+use std::sync::Mutex;
+
+/// Estimated image error at sample count checkpoint.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Checkpoint {
+    /// Samples per pixel taken so far.
+    pub samples: u32,
+
+    /// Root mean square error of pixel luminance.
+    pub rmse: f64,
+
+    /// Root mean square error relative to mean pixel luminance.
+    pub relative_rmse: f64,
+}
+
+/// Estimated image error vs. sample count. Errors are estimated per pixel from sample variance of luminance, thus
+/// no reference image is required.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NoiseReport {
+    /// Error estimates by increasing sample count.
+    checkpoints: Vec<Checkpoint>,
+}
+
+impl NoiseReport {
+    /// Error estimates by increasing sample count.
+    pub fn checkpoints(&self) -> &[Checkpoint] {
+        &self.checkpoints
+    }
+
+    /// Report as CSV table with header.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("samples,rmse,relative_rmse\n");
+        for c in self.checkpoints.iter() {
+            csv += &format!("{},{},{}\n", c.samples, c.rmse, c.relative_rmse);
+        }
+        csv
+    }
+
+    /// Report as JSON object.
+    pub fn to_json(&self) -> String {
+        let checkpoints: Vec<String> = self
+            .checkpoints
+            .iter()
+            .map(|c| {
+                format!(
+                    "{{\"samples\":{},\"rmse\":{},\"relative_rmse\":{}}}",
+                    c.samples, c.rmse, c.relative_rmse
+                )
+            })
+            .collect();
+        format!("{{\"checkpoints\":[{}]}}", checkpoints.join(","))
+    }
+
+    /// Log-log plot of error vs. sample count. Gray line shows ideal Monte Carlo convergence (error ~ 1/sqrt(n))
+    /// from first checkpoint.
+    pub fn plot(&self, width: u32, height: u32) -> Film {
+        let mut film = Film::new(width, height, FilmPrecision::Full);
+        let margin = GLYPH_HEIGHT + 4;
+        let (Some(first), Some(last)) = (self.checkpoints.first(), self.checkpoints.last()) else {
+            return film;
+        };
+        assert!(width > 2 * margin && height > 2 * margin);
+
+        // Axis ranges, error range padded to include ideal convergence.
+        let ideal = |samples: u32| first.rmse * (first.samples as f64 / samples as f64).sqrt();
+        let x_range = (
+            (first.samples as f64).log2(),
+            (last.samples as f64)
+                .log2()
+                .max((first.samples as f64).log2() + 1.0),
+        );
+        let errors = self
+            .checkpoints
+            .iter()
+            .map(|c| c.rmse)
+            .chain([ideal(last.samples)]);
+        let positive = errors.filter(|&e| e > 0.0);
+        let y_min = positive.clone().fold(f64::INFINITY, f64::min).log10();
+        let y_max = positive.fold(f64::NEG_INFINITY, f64::max).log10();
+        let y_range = (y_min.min(y_max - 1.0), y_max);
+
+        // Map checkpoint to pixel position.
+        let (plot_width, plot_height) = ((width - 2 * margin) as f64, (height - 2 * margin) as f64);
+        let position = |samples: u32, error: f64| {
+            let u = ((samples as f64).log2() - x_range.0) / (x_range.1 - x_range.0);
+            let v = (error.max(1e-30).log10() - y_range.0) / (y_range.1 - y_range.0);
+            (
+                margin as f64 + u * plot_width,
+                (height - margin) as f64 - v * plot_height,
+            )
+        };
+
+        // Axes and labels.
+        let gray = Color3f::new(0.5, 0.5, 0.5);
+        let (left, bottom) = (margin as f64, (height - margin) as f64);
+        draw_line(
+            &mut film,
+            (left, bottom),
+            (width as f64 - 1.0, bottom),
+            gray,
+        );
+        draw_line(&mut film, (left, bottom), (left, 0.0), gray);
+        let white = Color3f::white();
+        draw_text(
+            &mut film,
+            margin + 2,
+            2,
+            &format!("RMSE {:.1e}", 10f64.powf(y_range.1)),
+            white,
+        );
+        draw_text(
+            &mut film,
+            margin + 2,
+            height - margin - GLYPH_HEIGHT - 2,
+            &format!("{:.1e}", 10f64.powf(y_range.0)),
+            white,
+        );
+        draw_text(
+            &mut film,
+            margin,
+            height - GLYPH_HEIGHT - 2,
+            &first.samples.to_string(),
+            white,
+        );
+        let spp = format!("{} SPP", last.samples);
+        draw_text(
+            &mut film,
+            width.saturating_sub(text_width(&spp) + 2),
+            height - GLYPH_HEIGHT - 2,
+            &spp,
+            white,
+        );
+
+        // Ideal convergence and measured error.
+        draw_line(
+            &mut film,
+            position(first.samples, first.rmse),
+            position(last.samples, ideal(last.samples)),
+            gray,
+        );
+        for pair in self.checkpoints.windows(2) {
+            let a = position(pair[0].samples, pair[0].rmse);
+            let b = position(pair[1].samples, pair[1].rmse);
+            draw_line(&mut film, a, b, white);
+        }
+        film
+    }
+}
+
+/// Running luminance moments of single pixel.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PixelMoments {
+    /// Count of samples.
+    count: u32,
+
+    /// Sum of sample luminances.
+    sum: f64,
+
+    /// Sum of squared sample luminances.
+    sum_squared: f64,
+}
+
+/// Collects pixel sample statistics during rendering at sample count checkpoints (powers of two and final count).
+#[derive(Debug)]
+pub struct NoiseAccumulator {
+    /// Sample counts at which errors are recorded.
+    checkpoints: Vec<u32>,
+
+    /// Sums of variance of pixel mean and of pixel mean per checkpoint, and count of pixels.
+    totals: Mutex<(Vec<(f64, f64)>, u64)>,
+}
+
+impl NoiseAccumulator {
+    /// Creates accumulator for render with samples per pixel.
+    pub fn new(samples_per_pixel: u32) -> Self {
+        let mut checkpoints: Vec<u32> = (1..32)
+            .map(|i| 1 << i)
+            .take_while(|&n| n < samples_per_pixel)
+            .collect();
+        if samples_per_pixel >= 2 {
+            checkpoints.push(samples_per_pixel);
+        }
+        NoiseAccumulator {
+            totals: Mutex::new((vec![(0.0, 0.0); checkpoints.len()], 0)),
+            checkpoints,
+        }
+    }
+
+    /// Empty totals for accumulating a batch of pixels.
+    pub fn batch(&self) -> Vec<(f64, f64)> {
+        vec![(0.0, 0.0); self.checkpoints.len()]
+    }
+
+    /// Adds sample color to pixel moments, recording into batch totals at checkpoints.
+    pub fn add_sample(&self, batch: &mut [(f64, f64)], pixel: &mut PixelMoments, color: Color3f) {
+        let luminance = color.luminance() as f64;
+        pixel.count += 1;
+        pixel.sum += luminance;
+        pixel.sum_squared += luminance * luminance;
+        if let Ok(k) = self.checkpoints.binary_search(&pixel.count) {
+            let n = pixel.count as f64;
+            let mean = pixel.sum / n;
+            let variance = ((pixel.sum_squared - n * mean * mean) / (n - 1.0)).max(0.0);
+            batch[k].0 += variance / n;
+            batch[k].1 += mean;
+        }
+    }
+
+    /// Merges batch totals of given count of pixels.
+    pub fn merge(&self, batch: &[(f64, f64)], pixel_count: u64) {
+        let mut totals = self.totals.lock().unwrap();
+        for (total, b) in totals.0.iter_mut().zip(batch) {
+            total.0 += b.0;
+            total.1 += b.1;
+        }
+        totals.1 += pixel_count;
+    }
+
+    /// Report over all merged pixels.
+    pub fn report(self) -> NoiseReport {
+        let (totals, pixel_count) = self.totals.into_inner().unwrap();
+        let pixel_count = pixel_count.max(1) as f64;
+        let checkpoints = self
+            .checkpoints
+            .iter()
+            .zip(totals)
+            .map(|(&samples, (variance, mean))| {
+                let rmse = (variance / pixel_count).sqrt();
+                let mean = mean / pixel_count;
+                Checkpoint {
+                    samples,
+                    rmse,
+                    relative_rmse: if mean > 0.0 { rmse / mean } else { 0.0 },
+                }
+            })
+            .collect();
+        NoiseReport { checkpoints }
+    }
+}
+
+/// Draws line between pixel positions, clipped to film.
+fn draw_line(film: &mut Film, from: (f64, f64), to: (f64, f64), color: Color3f) {
+    let steps = (to.0 - from.0)
+        .abs()
+        .max((to.1 - from.1).abs())
+        .ceil()
+        .max(1.0) as u32;
+    for i in 0..=steps {
+        let t = i as f64 / steps as f64;
+        let x = (from.0 + t * (to.0 - from.0)).round();
+        let y = (from.1 + t * (to.1 - from.1)).round();
+        if x >= 0.0 && y >= 0.0 && x < film.width() as f64 && y < film.height() as f64 {
+            film.set_pixel(x as u32, y as u32, color);
+        }
+    }
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    /// Report of uniform random pixel samples in [0,1).
+    fn uniform_report(pixels: u32, samples_per_pixel: u32) -> NoiseReport {
+        let accumulator = NoiseAccumulator::new(samples_per_pixel);
+        let mut rng = StdRng::seed_from_u64(42);
+        let mut batch = accumulator.batch();
+        for _ in 0..pixels {
+            let mut moments = PixelMoments::default();
+            for _ in 0..samples_per_pixel {
+                let v = rng.gen::<f32>();
+                accumulator.add_sample(&mut batch, &mut moments, Color3f::new(v, v, v));
+            }
+        }
+        accumulator.merge(&batch, pixels as u64);
+        accumulator.report()
+    }
+
+    #[test]
+    fn checkpoints() {
+        let samples = |spp| NoiseAccumulator::new(spp).checkpoints;
+        assert_eq!(samples(1), Vec::<u32>::new());
+        assert_eq!(samples(2), [2]);
+        assert_eq!(samples(16), [2, 4, 8, 16]);
+        assert_eq!(samples(20), [2, 4, 8, 16, 20]);
+    }
+
+    #[test]
+    fn report() {
+        // Uniform samples have variance 1/12, error of mean sqrt(1/(12n)).
+        let report = uniform_report(1000, 64);
+        assert_eq!(report.checkpoints().len(), 6);
+        for c in report.checkpoints() {
+            let expected = (1.0 / (12.0 * c.samples as f64)).sqrt();
+            assert!((c.rmse - expected).abs() / expected < 0.05);
+            assert!((c.relative_rmse - 2.0 * expected).abs() / expected < 0.1);
+        }
+
+        let csv = report.to_csv();
+        assert_eq!(csv.lines().count(), 7);
+        assert!(csv.starts_with("samples,rmse,relative_rmse\n2,"));
+        let json = report.to_json();
+        assert!(json.starts_with("{\"checkpoints\":[{\"samples\":2,\"rmse\":"));
+        assert_eq!(json.matches("samples").count(), 6);
+    }
+
+    #[test]
+    fn plot() {
+        let film = uniform_report(100, 64).plot(160, 120);
+        assert_eq!((film.width(), film.height()), (160, 120));
+        assert!(film.pixels().any(|p| p == Color3f::white()));
+        assert!(film.pixels().any(|p| p == Color3f::new(0.5, 0.5, 0.5)));
+        assert_eq!(NoiseReport::default().plot(10, 10).pixels().count(), 100);
+    }
+}
@@ -0,0 +1,558 @@
+use crate::{
+    base::color::Color3f,
+    film::{Film, FilmPrecision},
+    tonemap::{srgb_decode, srgb_encode, ToneMapping},
+};
+use std::{
+    fs::File,
+    io::{self, BufWriter, Read, Write},
+    path::Path,
+};
+
+/// An image sink consumes rendered images. Sinks are chained into output pipelines, e.g. tone mapping, dithering
+/// and encoding into a file, where each stage passes its transformed image on to the next sink.
+pub trait ImageSink {
+    /// Consumes image, passing it on or writing it out.
+    fn write_image(&mut self, image: &Film) -> io::Result<()>;
+}
+
+impl<S: ImageSink + ?Sized> ImageSink for Box<S> {
+    fn write_image(&mut self, image: &Film) -> io::Result<()> {
+        (**self).write_image(image)
+    }
+}
+
+/// Stage applying exposure and tone mapping, passing display-referred linear colors in [0,1] on.
+#[derive(Clone, Debug)]
+pub struct ToneMap<S: ImageSink> {
+    /// Post-processing applied to scene radiance.
+    tone_mapping: ToneMapping,
+
+    /// Sink receiving tone mapped image.
+    next: S,
+}
+
+impl<S: ImageSink> ToneMap<S> {
+    /// Creates tone mapping stage passing images on to next sink.
+    pub fn new(tone_mapping: ToneMapping, next: S) -> Self {
+        ToneMap { tone_mapping, next }
+    }
+}
+
+impl<S: ImageSink> ImageSink for ToneMap<S> {
+    fn write_image(&mut self, image: &Film) -> io::Result<()> {
+        let mapped = map_pixels(image, |_, _, color| self.tone_mapping.apply(color));
+        self.next.write_image(&mapped)
+    }
+}
+
+/// Stage dithering display-referred colors before 8-bit quantization, trading banding in smooth gradients for fine
+/// noise. Offsets sRGB encoded values by up to half a quantization step following a 4x4 Bayer pattern.
+#[derive(Clone, Debug)]
+pub struct Dither<S: ImageSink> {
+    /// Sink receiving dithered image.
+    next: S,
+}
+
+impl<S: ImageSink> Dither<S> {
+    /// Creates dithering stage passing images on to next sink.
+    pub fn new(next: S) -> Self {
+        Dither { next }
+    }
+}
+
+impl<S: ImageSink> ImageSink for Dither<S> {
+    fn write_image(&mut self, image: &Film) -> io::Result<()> {
+        const BAYER: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+        let dithered = map_pixels(image, |x, y, color| {
+            let offset = (BAYER[(y % 4) as usize][(x % 4) as usize] as f32 + 0.5) / 16.0 - 0.5;
+            let dither = |c: f32| srgb_decode((srgb_encode(c) + offset / 255.0).clamp(0.0, 1.0));
+            Color3f::new(dither(color.r()), dither(color.g()), dither(color.b()))
+        });
+        self.next.write_image(&dithered)
+    }
+}
+
+/// Stage downscaling images to thumbnails, so their longer side fits a max size. Smaller images pass unchanged.
+#[derive(Clone, Debug)]
+pub struct Thumbnail<S: ImageSink> {
+    /// Max width and height in pixels.
+    max_size: u32,
+
+    /// Sink receiving thumbnail.
+    next: S,
+}
+
+impl<S: ImageSink> Thumbnail<S> {
+    /// Creates thumbnail stage with max width and height, passing images on to next sink.
+    pub fn new(max_size: u32, next: S) -> Self {
+        assert!(max_size > 0);
+        Thumbnail { max_size, next }
+    }
+}
+
+impl<S: ImageSink> ImageSink for Thumbnail<S> {
+    fn write_image(&mut self, image: &Film) -> io::Result<()> {
+        self.next.write_image(&downscale(image, self.max_size))
+    }
+}
+
+/// Stage passing images on to several sinks, e.g. to write multiple formats from one render.
+#[derive(Default)]
+pub struct Tee {
+    /// Sinks receiving images, in order.
+    sinks: Vec<Box<dyn ImageSink>>,
+}
+
+impl Tee {
+    /// Creates stage without sinks.
+    pub fn new() -> Self {
+        Tee::default()
+    }
+
+    /// Adds sink receiving images.
+    pub fn add(&mut self, sink: impl ImageSink + 'static) {
+        self.sinks.push(Box::new(sink));
+    }
+}
+
+impl ImageSink for Tee {
+    fn write_image(&mut self, image: &Film) -> io::Result<()> {
+        self.sinks
+            .iter_mut()
+            .try_for_each(|sink| sink.write_image(image))
+    }
+}
+
+/// Stage printing display-referred images to a terminal as unicode upper half blocks in 24-bit ANSI colors, each
+/// text cell showing two pixels atop each other. Images are downscaled to fit a width in columns, e.g. to check
+/// renders over SSH without transferring files.
+#[derive(Clone, Debug)]
+pub struct Terminal<W: Write> {
+    /// Max width in text columns.
+    columns: u32,
+
+    /// Writer receiving escape sequences, e.g. standard output.
+    writer: W,
+}
+
+impl<W: Write> Terminal<W> {
+    /// Creates terminal stage with max width in text columns, writing to writer.
+    pub fn new(columns: u32, writer: W) -> Self {
+        assert!(columns > 0);
+        Terminal { columns, writer }
+    }
+
+    /// Returns writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> ImageSink for Terminal<W> {
+    fn write_image(&mut self, image: &Film) -> io::Result<()> {
+        let (width, height) = (image.width() as u64, image.height() as u64);
+        let max_size = (self.columns as u64 * width.max(height)).div_ceil(width) as u32;
+        let image = downscale(image, max_size);
+        let rgb = |c: Color3f| {
+            format!(
+                "{};{};{}",
+                quantize(c.r()),
+                quantize(c.g()),
+                quantize(c.b())
+            )
+        };
+        let w = &mut self.writer;
+        for y in (0..image.height()).step_by(2) {
+            for x in 0..image.width() {
+                write!(w, "\x1b[38;2;{}m", rgb(image.pixel(x, y)))?;
+                if y + 1 < image.height() {
+                    write!(w, "\x1b[48;2;{}m\u{2580}", rgb(image.pixel(x, y + 1)))?;
+                } else {
+                    write!(w, "\x1b[49m\u{2580}")?;
+                }
+            }
+            writeln!(w, "\x1b[0m")?;
+        }
+        w.flush()
+    }
+}
+
+/// Image file format.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ImageFormat {
+    /// Plain text portable pixmap with 8-bit sRGB encoded components, expecting display-referred colors.
+    #[default]
+    Ppm,
+
+    /// Portable float map with linear single precision components, keeping the full dynamic range.
+    Pfm,
+}
+
+impl ImageFormat {
+    /// Format of file path by its extension, none if unknown.
+    pub fn from_path(path: &str) -> Option<ImageFormat> {
+        match Path::new(path).extension()?.to_str()? {
+            "ppm" => Some(ImageFormat::Ppm),
+            "pfm" => Some(ImageFormat::Pfm),
+            _ => None,
+        }
+    }
+
+    /// Decodes image from reader. PPM colors are sRGB decoded into display-referred linear colors, PFM colors are
+    /// taken as is. Supports binary and plain text PPM with up to 8 bits per component, and color and grayscale PFM.
+    pub fn decode(&self, mut reader: impl Read) -> io::Result<Film> {
+        let invalid =
+            |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        let (tokens, data) = header(&bytes);
+        let number = |i: usize| -> io::Result<f32> {
+            let token = tokens.get(i).ok_or_else(|| invalid("truncated header"))?;
+            token.parse().map_err(|_| invalid("invalid header"))
+        };
+        let (width, height) = (number(1)? as u32, number(2)? as u32);
+        let mut image = Film::new(width, height, FilmPrecision::Full);
+        let size = (width * height) as usize;
+        let components: Vec<f32> = match (self, tokens.first().map(String::as_str)) {
+            (ImageFormat::Ppm, Some(magic @ ("P3" | "P6"))) => {
+                let max = number(3)?;
+                if !(1.0..256.0).contains(&max) {
+                    return Err(invalid("unsupported max value"));
+                }
+                let values: Vec<f32> = if magic == "P6" {
+                    bytes[data..].iter().map(|&b| b as f32).collect()
+                } else {
+                    String::from_utf8_lossy(&bytes[data..])
+                        .split_ascii_whitespace()
+                        .map(|v| v.parse().map_err(|_| invalid("invalid pixel value")))
+                        .collect::<io::Result<_>>()?
+                };
+                values.iter().map(|v| srgb_decode(v / max)).collect()
+            }
+            (ImageFormat::Pfm, Some(magic @ ("PF" | "Pf"))) => {
+                let little_endian = number(3)? < 0.0;
+                let floats = bytes[data..].chunks_exact(4).map(|chunk| {
+                    let chunk = chunk.try_into().unwrap();
+                    if little_endian {
+                        f32::from_le_bytes(chunk)
+                    } else {
+                        f32::from_be_bytes(chunk)
+                    }
+                });
+                if magic == "Pf" {
+                    floats.flat_map(|v| [v, v, v]).collect()
+                } else {
+                    floats.collect()
+                }
+            }
+            _ => return Err(invalid("unsupported format")),
+        };
+        if components.len() < 3 * size {
+            return Err(invalid("truncated pixel data"));
+        }
+        for (i, c) in components.chunks_exact(3).take(size).enumerate() {
+            let (x, y) = (i as u32 % width, i as u32 / width);
+            // PFM rows run bottom to top.
+            let y = if *self == ImageFormat::Pfm {
+                height - 1 - y
+            } else {
+                y
+            };
+            image.set_pixel(x, y, Color3f::new(c[0], c[1], c[2]));
+        }
+        Ok(image)
+    }
+
+    /// Whether format stores scene radiance without tone mapping.
+    pub fn is_hdr(&self) -> bool {
+        *self == ImageFormat::Pfm
+    }
+}
+
+/// Final stage encoding images in file format into writer, e.g. a file or standard output.
+#[derive(Debug)]
+pub struct Encode<W: Write> {
+    /// File format.
+    format: ImageFormat,
+
+    /// Destination of encoded images.
+    writer: W,
+}
+
+impl<W: Write> Encode<W> {
+    /// Creates encoding stage writing format into writer.
+    pub fn new(format: ImageFormat, writer: W) -> Self {
+        Encode { format, writer }
+    }
+
+    /// Destination of encoded images.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl Encode<BufWriter<File>> {
+    /// Creates encoding stage writing format into file at path, which is created or truncated.
+    pub fn file(path: &str, format: ImageFormat) -> io::Result<Self> {
+        Ok(Encode::new(format, BufWriter::new(File::create(path)?)))
+    }
+}
+
+impl<W: Write> ImageSink for Encode<W> {
+    fn write_image(&mut self, image: &Film) -> io::Result<()> {
+        let w = &mut self.writer;
+        match self.format {
+            ImageFormat::Ppm => {
+                writeln!(w, "P3")?;
+                writeln!(w, "{} {}", image.width(), image.height())?;
+                writeln!(w, "255")?;
+                for c in image.pixels() {
+                    writeln!(
+                        w,
+                        "{} {} {}",
+                        quantize(c.r()),
+                        quantize(c.g()),
+                        quantize(c.b())
+                    )?;
+                }
+            }
+            ImageFormat::Pfm => {
+                // Negative scale marks little endian, rows run bottom to top.
+                write!(w, "PF\n{} {}\n-1.0\n", image.width(), image.height())?;
+                for y in (0..image.height()).rev() {
+                    for x in 0..image.width() {
+                        let c = image.pixel(x, y);
+                        for component in [c.r(), c.g(), c.b()] {
+                            w.write_all(&component.to_le_bytes())?;
+                        }
+                    }
+                }
+            }
+        }
+        w.flush()
+    }
+}
+
+/// Default pipeline writing images to file at path, in format given by its extension (PPM if unknown). Low dynamic
+/// range formats are tone mapped and dithered, high dynamic range formats store radiance as is.
+pub fn file_pipeline(path: &str, tone_mapping: ToneMapping) -> io::Result<Box<dyn ImageSink>> {
+    let format = ImageFormat::from_path(path).unwrap_or_default();
+    let encode = Encode::file(path, format)?;
+    if format.is_hdr() {
+        Ok(Box::new(encode))
+    } else {
+        Ok(Box::new(ToneMap::new(tone_mapping, Dither::new(encode))))
+    }
+}
+
+/// Reads image file at path, in format given by its extension.
+pub fn read_image(path: &str) -> io::Result<Film> {
+    let format = ImageFormat::from_path(path)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "unknown image format"))?;
+    format.decode(File::open(path)?)
+}
+
+/// Path of thumbnail next to image file at path, e.g. `image_thumb.ppm` for `image.ppm`.
+pub fn thumbnail_path(path: &str) -> String {
+    let path = Path::new(path);
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(extension) => format!("{}_thumb.{}", stem, extension.to_string_lossy()),
+        None => format!("{}_thumb", stem),
+    };
+    path.with_file_name(name).to_string_lossy().into_owned()
+}
+
+/// Display-referred linear color component as 8-bit sRGB encoded value.
+fn quantize(x: f32) -> u8 {
+    (srgb_encode(x.clamp(0.0, 1.0)) * 255.0).round() as u8
+}
+
+/// Image downscaled by box filter, so its longer side fits max size, keeping aspect ratio. Smaller images are
+/// returned unchanged.
+pub fn downscale(image: &Film, max_size: u32) -> Film {
+    let (width, height) = (image.width(), image.height());
+    if width <= max_size && height <= max_size {
+        return image.clone();
+    }
+    let scale = max_size as f32 / width.max(height) as f32;
+    let target_width = ((width as f32 * scale).round() as u32).max(1);
+    let target_height = ((height as f32 * scale).round() as u32).max(1);
+    let mut scaled = Film::new(target_width, target_height, image.precision());
+    let span = |i: u32, target: u32, source: u32| {
+        let start = (i as u64 * source as u64 / target as u64) as u32;
+        let end = ((i as u64 + 1) * source as u64 / target as u64) as u32;
+        start..end.max(start + 1)
+    };
+    for y in 0..target_height {
+        for x in 0..target_width {
+            let (xs, ys) = (span(x, target_width, width), span(y, target_height, height));
+            let count = (xs.len() * ys.len()) as f32;
+            let sum = ys
+                .flat_map(|sy| xs.clone().map(move |sx| (sx, sy)))
+                .fold(Color3f::black(), |acc, (sx, sy)| acc + image.pixel(sx, sy));
+            scaled.set_pixel(x, y, sum / count);
+        }
+    }
+    scaled
+}
+
+/// Whitespace-separated header tokens of PPM or PFM file (magic, width, height, max value or scale), skipping
+/// comments, and offset of pixel data after the single whitespace ending the header.
+fn header(bytes: &[u8]) -> (Vec<String>, usize) {
+    let (mut tokens, mut i) = (Vec::new(), 0);
+    while tokens.len() < 4 && i < bytes.len() {
+        if bytes[i] == b'#' {
+            while i < bytes.len() && bytes[i] != b'\n' {
+                i += 1;
+            }
+        } else if bytes[i].is_ascii_whitespace() {
+            i += 1;
+        } else {
+            let start = i;
+            while i < bytes.len() && !bytes[i].is_ascii_whitespace() {
+                i += 1;
+            }
+            tokens.push(String::from_utf8_lossy(&bytes[start..i]).into_owned());
+        }
+    }
+    (tokens, (i + 1).min(bytes.len()))
+}
+
+/// Full precision image of same size with colors mapped by function of pixel position and color.
+fn map_pixels(image: &Film, f: impl Fn(u32, u32, Color3f) -> Color3f) -> Film {
+    let mut mapped = Film::new(image.width(), image.height(), FilmPrecision::Full);
+    for y in 0..image.height() {
+        for x in 0..image.width() {
+            mapped.set_pixel(x, y, f(x, y, image.pixel(x, y)));
+        }
+    }
+    mapped
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tonemap::ToneMapper;
+
+    /// Sink collecting images in memory.
+    #[derive(Default)]
+    struct Collect(Vec<Film>);
+
+    impl ImageSink for &mut Collect {
+        fn write_image(&mut self, image: &Film) -> io::Result<()> {
+            self.0.push(image.clone());
+            Ok(())
+        }
+    }
+
+    /// 4x4 film of uniform color.
+    fn uniform(color: Color3f) -> Film {
+        let mut film = Film::new(4, 4, FilmPrecision::Half);
+        (0..16).for_each(|i| film.set_pixel(i % 4, i / 4, color));
+        film
+    }
+
+    #[test]
+    fn stages() {
+        // Tone mapping scales by exposure and clips.
+        let mut collect = Collect::default();
+        let tone_mapping = ToneMapping::new(1.0, ToneMapper::Linear);
+        let mut sink = ToneMap::new(tone_mapping, &mut collect);
+        sink.write_image(&uniform(Color3f::new(0.25, 1.0, -1.0)))
+            .unwrap();
+        assert_eq!(collect.0[0].pixel(3, 3), Color3f::new(0.5, 1.0, 0.0));
+
+        // Dithering keeps quantized mean of uniform gray between neighboring levels.
+        let mut collect = Collect::default();
+        let gray = srgb_decode(100.4 / 255.0);
+        Dither::new(&mut collect)
+            .write_image(&uniform(Color3f::new(gray, gray, gray)))
+            .unwrap();
+        let levels: Vec<f32> = collect.0[0]
+            .pixels()
+            .map(|c| (srgb_encode(c.g()) * 255.0).round())
+            .collect();
+        let mean = levels.iter().sum::<f32>() / levels.len() as f32;
+        assert!(levels.iter().all(|&l| l == 100.0 || l == 101.0));
+        assert!((mean - 100.4).abs() < 0.1);
+    }
+
+    #[test]
+    fn thumbnail() {
+        // Downscaled by box filter, keeping aspect ratio.
+        let mut film = Film::new(8, 4, FilmPrecision::Full);
+        film.set_pixel(0, 0, Color3f::white());
+        let mut collect = Collect::default();
+        Thumbnail::new(4, &mut collect).write_image(&film).unwrap();
+        let thumbnail = &collect.0[0];
+        assert_eq!((thumbnail.width(), thumbnail.height()), (4, 2));
+        assert_eq!(thumbnail.pixel(0, 0), Color3f::white() / 4.0);
+        assert_eq!(thumbnail.pixel(1, 0), Color3f::black());
+        assert_eq!(downscale(&film, 16), film);
+        assert_eq!(thumbnail_path("out/image.ppm"), "out/image_thumb.ppm");
+    }
+
+    #[test]
+    fn terminal() {
+        // Two pixel rows per line, bottom half of odd last row left to terminal background.
+        let mut film = Film::new(2, 3, FilmPrecision::Full);
+        film.set_pixel(0, 0, Color3f::white());
+        let mut terminal = Terminal::new(4, Vec::new());
+        terminal.write_image(&film).unwrap();
+        let text = String::from_utf8(terminal.into_inner()).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("\x1b[38;2;255;255;255m\x1b[48;2;0;0;0m\u{2580}"));
+        assert_eq!(
+            lines[1],
+            "\x1b[38;2;0;0;0m\x1b[49m\u{2580}".repeat(2) + "\x1b[0m"
+        );
+
+        // Downscaled to fit columns.
+        let mut terminal = Terminal::new(2, Vec::new());
+        terminal
+            .write_image(&Film::new(8, 4, FilmPrecision::Full))
+            .unwrap();
+        let text = String::from_utf8(terminal.into_inner()).unwrap();
+        assert_eq!(text.lines().count(), 1);
+        assert_eq!(text.matches('\u{2580}').count(), 2);
+    }
+
+    #[test]
+    fn encode() {
+        let film = uniform(Color3f::new(0.0, 1.0, 2.0));
+        let mut ppm = Encode::new(ImageFormat::Ppm, Vec::new());
+        ppm.write_image(&film).unwrap();
+        let text = String::from_utf8(ppm.into_inner()).unwrap();
+        assert!(text.starts_with("P3\n4 4\n255\n0 255 255\n"));
+        let mut pfm = Encode::new(ImageFormat::Pfm, Vec::new());
+        pfm.write_image(&film).unwrap();
+        let bytes = pfm.into_inner();
+        assert_eq!(bytes.len(), "PF\n4 4\n-1.0\n".len() + 16 * 12);
+        assert_eq!(bytes[bytes.len() - 4..], 2.0f32.to_le_bytes());
+
+        // Decoding restores colors, PPM up to quantization, and skips comments.
+        let decoded = ImageFormat::Pfm.decode(&bytes[..]).unwrap();
+        assert!(decoded.pixels().eq(film.pixels()));
+        let ppm = ImageFormat::Ppm.decode("P3 # comment\n1 1 255\n0 255 188\n".as_bytes());
+        let color = ppm.unwrap().pixel(0, 0);
+        assert!(color.r() == 0.0 && color.g() == 1.0 && (color.b() - 0.5).abs() < 0.01);
+        let binary = ImageFormat::Ppm.decode(&b"P6\n2 1\n255\n\xff\x00\x00\x00\x00\xff"[..]);
+        assert_eq!(binary.unwrap().pixel(1, 0), Color3f::new(0.0, 0.0, 1.0));
+        assert!(ImageFormat::Ppm
+            .decode("P3 2 2 255\n0 0 0\n".as_bytes())
+            .is_err());
+        assert!(ImageFormat::Pfm
+            .decode("P3 1 1 255\n0 0 0\n".as_bytes())
+            .is_err());
+
+        assert_eq!(
+            ImageFormat::from_path("out/image.pfm"),
+            Some(ImageFormat::Pfm)
+        );
+        assert_eq!(ImageFormat::from_path("image"), None);
+    }
+}
@@ -0,0 +1,408 @@
+use crate::{
+    base::{
+        color::Color3f,
+        interval::Interval,
+        light::{Illuminating, Light},
+        material::{Interactable, Material},
+        onb::Onb,
+        orientation::Orientation,
+        point::Point3f,
+        ray::{gamma, Ray},
+        sampling::{cosine_hemisphere, uniform_disk, uniform_sphere, uniform_sphere_pdf},
+        shape::{Bounded, Intersectable, Sampleable},
+        vector::Vector3f,
+    },
+    scene::Scene,
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::{collections::HashMap, f32::consts::PI};
+
+/// Seed of random numbers drawn while emitting photons, so maps of the same scene are equal.
+const SEED: u64 = 0x9e37_79b9;
+
+/// Caustic photon map: photons emitted by lights and stored where they hit a diffuse surface after one or more
+/// specular bounces (glass, mirrors, glossy metals), i.e. light paths focused into caustics. Paths of path tracers
+/// rarely find these, as lights cannot be sampled through specular surfaces.
+///
+/// Emissive objects emit photons from random points into random directions on both sides, point and spot lights
+/// into all directions. Directional lights and the sun of a physical sky emit parallel photons aimed at the
+/// bounding spheres of objects with specular materials, so none are wasted on the unbounded ground.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PhotonMap {
+    /// Count of emitted photons.
+    emitted: u32,
+
+    /// Max number of bounces of photon paths.
+    max_depth: u32,
+
+    /// Radius of photon queries, also edge length of grid cells.
+    radius: f32,
+
+    /// Stored photons, binned by grid cell.
+    cells: HashMap<[i32; 3], Vec<Photon>>,
+
+    /// Count of stored photons.
+    len: usize,
+}
+
+impl PhotonMap {
+    /// Builds caustic photon map of scene by emitting photons along paths of at most max depth bounces, binned for
+    /// queries within radius.
+    pub fn build(scene: &Scene, emitted: u32, max_depth: u32, radius: f32) -> Self {
+        assert!(radius > 0.0);
+        let mut map = PhotonMap {
+            emitted,
+            max_depth,
+            radius,
+            cells: HashMap::new(),
+            len: 0,
+        };
+        let emitters = Emitters::new(scene);
+        if emitters.count() == 0 || emitted == 0 {
+            return map;
+        }
+
+        // Trace photons, each carrying its share of the power of all lights.
+        let mut rng = StdRng::seed_from_u64(SEED);
+        let share = emitters.count() as f32 / emitted as f32;
+        for _ in 0..emitted {
+            let Some((ray, power)) = emitters.emit(scene, &mut rng) else {
+                continue;
+            };
+            if let Some(photon) = trace(ray, power * share, scene, max_depth, &mut rng) {
+                map.cells
+                    .entry(map.cell(photon.point))
+                    .or_default()
+                    .push(photon);
+                map.len += 1;
+            }
+        }
+        map
+    }
+
+    /// Count of emitted photons.
+    pub fn emitted(&self) -> u32 {
+        self.emitted
+    }
+
+    /// Max number of bounces of photon paths.
+    pub fn max_depth(&self) -> u32 {
+        self.max_depth
+    }
+
+    /// Radius of photon queries.
+    pub fn radius(&self) -> f32 {
+        self.radius
+    }
+
+    /// Count of stored photons.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether no photons are stored.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Stored photons within radius of point.
+    pub fn gather(&self, point: Point3f) -> impl Iterator<Item = &Photon> + '_ {
+        let [x, y, z] = self.cell(point);
+        (-1..=1)
+            .flat_map(move |dx| (-1..=1).flat_map(move |dy| (-1..=1).map(move |dz| [dx, dy, dz])))
+            .filter_map(move |[dx, dy, dz]| self.cells.get(&[x + dx, y + dy, z + dz]))
+            .flatten()
+            .filter(move |photon| {
+                (photon.point - point).length_squared() <= self.radius * self.radius
+            })
+    }
+
+    /// Grid cell containing point.
+    fn cell(&self, point: Point3f) -> [i32; 3] {
+        let index = |x: f32| (x / self.radius).floor() as i32;
+        [index(point.x()), index(point.y()), index(point.z())]
+    }
+}
+
+/// Photon stored at a diffuse surface.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Photon {
+    /// Point photon hit.
+    pub point: Point3f,
+
+    /// Unit direction photon arrived from.
+    pub direction: Vector3f,
+
+    /// Power (flux) carried by photon.
+    pub power: Color3f,
+}
+
+/// Lights of scene photons are emitted from.
+struct Emitters {
+    /// Indices of emissive objects.
+    objects: Vec<usize>,
+
+    /// Analytic lights.
+    lights: Vec<Light>,
+
+    /// Whether the sun of a physical sky emits photons.
+    sun: bool,
+
+    /// Bounding spheres (center, radius) of objects with specular materials, which parallel photons aim at.
+    targets: Vec<(Point3f, f32)>,
+}
+
+impl Emitters {
+    /// Lights of scene. Parallel lights are left out if no object could focus their light.
+    fn new(scene: &Scene) -> Self {
+        let targets: Vec<(Point3f, f32)> = (0..scene.objects().len())
+            .filter(|&index| {
+                !matches!(
+                    scene.material(index),
+                    Material::Lambert(_) | Material::DiffuseLight(_) | Material::ShadowCatcher(_)
+                )
+            })
+            .map(|index| {
+                let bounds = scene.objects()[index].bounding_box();
+                let center = bounds.min() + 0.5 * bounds.size();
+                (center, 0.5 * bounds.size().length())
+            })
+            .filter(|(_, radius)| radius.is_finite())
+            .collect();
+        let parallel = !targets.is_empty();
+        Emitters {
+            objects: scene.lights().to_vec(),
+            lights: scene
+                .analytic_lights()
+                .iter()
+                .copied()
+                .filter(|light| parallel || !matches!(light, Light::Directional(_)))
+                .collect(),
+            sun: parallel && scene.sun().is_some(),
+            targets,
+        }
+    }
+
+    /// Count of lights.
+    fn count(&self) -> usize {
+        self.objects.len() + self.lights.len() + usize::from(self.sun)
+    }
+
+    /// Emits photon from light chosen uniformly. Returns its ray and power divided by the density of the ray among
+    /// those of the light, or none if the photon is lost.
+    fn emit(&self, scene: &Scene, rng: &mut impl Rng) -> Option<(Ray, Color3f)> {
+        let index = rng.gen_range(0..self.count());
+        if let Some(&object) = self.objects.get(index) {
+            // Random point and cosine-weighted direction on random side of surface.
+            let emission = scene.material(object).emitted();
+            let sample = scene.objects()[object].sample_point(rng);
+            let normal = if rng.gen::<bool>() {
+                sample.normal
+            } else {
+                -sample.normal
+            };
+            let direction = Onb::new(normal).to_world(cosine_hemisphere(rng));
+            let error = gamma(7) * (sample.point - Point3f::default()).abs();
+            let ray = Ray::spawn(sample.point, error, normal, direction);
+            let power = emission * (2.0 * PI / sample.pdf);
+            return Some((ray, power));
+        }
+        match self.lights.get(index - self.objects.len()) {
+            Some(Light::Point(p)) => self.emit_point(&Light::Point(*p), p.position(), rng),
+            Some(Light::Spot(s)) => self.emit_point(&Light::Spot(*s), s.position(), rng),
+            Some(light) => {
+                let illumination = light.illuminate(Point3f::default())?;
+                self.emit_parallel(scene, -illumination.direction, illumination.irradiance, rng)
+            }
+            None => {
+                let sky = scene.sun()?;
+                let (direction, pdf) = sky.sample_sun(rng)?;
+                let direction = Orientation::Y_UP
+                    .conversion(scene.orientation())
+                    .vector(direction);
+                self.emit_parallel(scene, -direction, sky.sun_radiance() / pdf, rng)
+            }
+        }
+    }
+
+    /// Emits photon from point light at position into uniformly random direction.
+    fn emit_point(
+        &self,
+        light: &Light,
+        position: Point3f,
+        rng: &mut impl Rng,
+    ) -> Option<(Ray, Color3f)> {
+        let direction = uniform_sphere(rng);
+        let intensity = light.illuminate(position + direction)?.irradiance;
+        Some((
+            Ray::new(position, direction),
+            intensity / uniform_sphere_pdf(),
+        ))
+    }
+
+    /// Emits photon along direction from parallel light of irradiance, through a random point of the disk of a target
+    /// picked by area. The photon starts in front of all targets along its line, and is lost if anything shadows it.
+    fn emit_parallel(
+        &self,
+        scene: &Scene,
+        direction: Vector3f,
+        irradiance: Color3f,
+        rng: &mut impl Rng,
+    ) -> Option<(Ray, Color3f)> {
+        let area: f32 = self.targets.iter().map(|(_, r)| r * r).sum();
+        let mut pick = rng.gen::<f32>() * area;
+        let &(center, radius) = self
+            .targets
+            .iter()
+            .find(|(_, r)| {
+                pick -= r * r;
+                pick <= 0.0
+            })
+            .unwrap_or(self.targets.last()?);
+        let (u, v) = uniform_disk(rng);
+        let (e1, e2) = direction.orthonormal_basis();
+        let point = center + radius * (u * e1 + v * e2);
+
+        // Count disks covering line, the line's density being their count over the total disk area.
+        let (mut covering, mut front) = (0, f32::INFINITY);
+        for &(center, radius) in &self.targets {
+            let to_center = center - point;
+            let along = to_center.dot(&direction);
+            if to_center.length_squared() - along * along <= radius * radius {
+                covering += 1;
+                front = front.min(along - radius);
+            }
+        }
+        if covering == 0 {
+            return None;
+        }
+        let origin = point + front * direction;
+        if scene
+            .intersect(
+                Ray::new(origin, -direction),
+                Interval::new(0.0, f32::INFINITY),
+            )
+            .is_some()
+        {
+            return None;
+        }
+        let power = irradiance * (PI * area / covering as f32);
+        Some((Ray::new(origin, direction), power))
+    }
+}
+
+/// Traces photon along ray with power through specular bounces. Returns it stored at the diffuse surface it hits
+/// after at least one specular bounce, none if it hits one directly, escapes or is absorbed.
+fn trace(
+    mut ray: Ray,
+    mut power: Color3f,
+    scene: &Scene,
+    max_depth: u32,
+    rng: &mut impl Rng,
+) -> Option<Photon> {
+    let mut specular = false;
+    for _ in 0..max_depth {
+        let (object, isect) = scene.intersect_object(ray, Interval::new(0.0, f32::INFINITY))?;
+        let material = scene.surface_material(object, &isect, rng);
+        let iact = material.interact(ray, isect, rng)?;
+        if let Material::ShadowCatcher(_) = *material {
+            // Ray passes through unchanged.
+            ray = iact.scattered_ray;
+            continue;
+        }
+        if material
+            .scattering_pdf(isect, iact.scattered_ray.direction())
+            .is_some()
+        {
+            return specular.then_some(Photon {
+                point: isect.point,
+                direction: -ray.direction().normalize(),
+                power,
+            });
+        }
+        specular = true;
+        power *= iact.attenuation;
+        ray = iact.scattered_ray;
+    }
+    None
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        base::shape::Shape,
+        lights::{directional::DirectionalLight, point::PointLight},
+        materials::lambert::Lambert,
+        shapes::sphere::Sphere,
+    };
+
+    /// Scene with white ground and glass ball above, lit by light.
+    fn scene(light: Light) -> Scene {
+        let mut scene = Scene::new();
+        let white = Material::Lambert(Lambert::new(Color3f::white()));
+        scene.add(Shape::preset("ground", white).unwrap());
+        let glass = Material::preset("glass").unwrap();
+        scene.add(Shape::Sphere(Sphere::new(
+            Point3f::new(0.0, 1.0, 0.0),
+            0.5,
+            glass,
+        )));
+        scene.add_light(light);
+        scene
+    }
+
+    #[test]
+    fn build() {
+        // Photons gather below glass ball, arriving from above.
+        let point = PointLight::new(Point3f::new(0.0, 4.0, 0.0), Color3f::white() * 10.0);
+        let map = PhotonMap::build(&scene(Light::Point(point)), 20000, 8, 0.1);
+        assert_eq!(
+            (map.emitted(), map.max_depth(), map.radius()),
+            (20000, 8, 0.1)
+        );
+        assert!(map.len() > 100);
+        let below: Vec<&Photon> = map.gather(Point3f::new(0.0, 0.0, 0.0)).collect();
+        assert!(!below.is_empty());
+        for photon in &below {
+            assert!(
+                photon.point.y().abs() < 1e-3
+                    && (photon.point - Point3f::default()).length() <= 0.1
+            );
+            assert!(photon.direction.y() > 0.0);
+        }
+
+        // Stored power is at most the power passing the ball, less reflections off the glass.
+        let total = map
+            .cells
+            .values()
+            .flatten()
+            .fold(Color3f::black(), |acc, p| acc + p.power);
+        let solid_angle = 2.0 * PI * (1.0 - (1.0 - (0.5f32 / 3.0).powi(2)).sqrt());
+        assert!(total.g() < 10.0 * solid_angle && total.g() > 0.8 * 10.0 * solid_angle);
+
+        // Without specular objects nothing is stored.
+        let mut diffuse = Scene::new();
+        let white = Material::Lambert(Lambert::new(Color3f::white()));
+        diffuse.add(Shape::preset("ground", white).unwrap());
+        diffuse.add_light(Light::Point(point));
+        assert!(PhotonMap::build(&diffuse, 1000, 8, 0.1).is_empty());
+    }
+
+    #[test]
+    fn parallel() {
+        // Irradiance focused below ball exceeds that of the sun, light reflected off the ball is spread thin.
+        let sun = DirectionalLight::new(Vector3f::new(0.0, -1.0, 0.0), Color3f::white());
+        let map = PhotonMap::build(&scene(Light::Directional(sun)), 20000, 8, 0.05);
+        let irradiance =
+            |point| map.gather(point).map(|p| p.power.g()).sum::<f32>() / (PI * 0.05 * 0.05);
+        assert!(irradiance(Point3f::new(0.0, 0.0, 0.0)) > 2.0);
+        assert!(irradiance(Point3f::new(2.0, 0.0, 0.0)) < 0.1);
+
+        // Total power is the sun's through the ball's cross section, less reflections off the glass.
+        let total: f32 = map.cells.values().flatten().map(|p| p.power.g()).sum();
+        let cross_section = PI * 0.25;
+        assert!(total < cross_section && total > 0.8 * cross_section);
+    }
+}
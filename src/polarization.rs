@@ -0,0 +1,463 @@
+use crate::{
+    base::{color::Color3f, material::Material, ray::Ray, shape::Intersection, vector::Vector3f},
+    materials::dielectric::Dielectric,
+};
+use std::ops::{Add, Div, Mul, Sub};
+
+/// Max reflectance at normal incidence of conductors, keeping their derived complex index of refraction finite.
+const MAX_CONDUCTOR_REFLECTANCE: f32 = 0.999;
+
+/// Stokes vector of light per color channel, relative to a reference direction perpendicular to propagation: total
+/// intensity, excess of linear polarization along the reference direction over perpendicular to it, excess of linear
+/// polarization at +45 degrees over -45 degrees, and excess of right over left circular polarization.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Stokes {
+    /// Components s0 to s3.
+    pub components: [Color3f; 4],
+}
+
+impl Stokes {
+    /// Unpolarized light of given intensity, like that of all emitters and backgrounds.
+    pub fn unpolarized(intensity: Color3f) -> Self {
+        let black = Color3f::black();
+        Stokes {
+            components: [intensity, black, black, black],
+        }
+    }
+
+    /// Total intensity.
+    pub fn intensity(&self) -> Color3f {
+        self.components[0]
+    }
+
+    /// Fraction of intensity that is polarized per channel, zero for unpolarized and one for fully polarized light.
+    pub fn degree_of_polarization(&self) -> Color3f {
+        let [s0, s1, s2, s3] = self.components;
+        let polarized = s1 * s1 + s2 * s2 + s3 * s3;
+        let degree = |p: f32, i: f32| if i > 0.0 { p.sqrt() / i } else { 0.0 };
+        Color3f::new(
+            degree(polarized.r(), s0.r()),
+            degree(polarized.g(), s0.g()),
+            degree(polarized.b(), s0.b()),
+        )
+    }
+}
+
+/// Mueller matrix per color channel, transforming Stokes vectors of light passing an optical element: a filter,
+/// reflection or refraction. Stokes vectors on both sides of Fresnel interactions refer to the direction
+/// perpendicular to the plane of incidence (s-polarization).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Mueller {
+    /// Rows of matrix.
+    rows: [[Color3f; 4]; 4],
+}
+
+impl Mueller {
+    /// Matrix of element leaving light unchanged.
+    pub fn identity() -> Self {
+        Mueller::uniform([
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    /// Matrix of element scattering light into unpolarized light, attenuated per channel, e.g. a diffuse surface.
+    pub fn depolarizer(attenuation: Color3f) -> Self {
+        let mut depolarizer = Mueller::uniform([[0.0; 4]; 4]);
+        depolarizer.rows[0][0] = attenuation;
+        depolarizer
+    }
+
+    /// Matrix of ideal linear polarizing filter with transmission axis at angle in radians from reference direction,
+    /// counterclockwise as seen facing the incoming light.
+    pub fn linear_polarizer(angle: f32) -> Self {
+        let (s, c) = (2.0 * angle).sin_cos();
+        Mueller::uniform([
+            [0.5, 0.5 * c, 0.5 * s, 0.0],
+            [0.5 * c, 0.5 * c * c, 0.5 * c * s, 0.0],
+            [0.5 * s, 0.5 * c * s, 0.5 * s * s, 0.0],
+            [0.0, 0.0, 0.0, 0.0],
+        ])
+    }
+
+    /// Matrix expressing Stokes vectors relative to reference direction rotated by angle in radians, counterclockwise
+    /// as seen facing the incoming light.
+    pub fn rotation(angle: f32) -> Self {
+        let (s, c) = (2.0 * angle).sin_cos();
+        Mueller::uniform([
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, c, s, 0.0],
+            [0.0, -s, c, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    /// Matrix of Fresnel reflection off dielectric interface with relative index of refraction eta (transmitted over
+    /// incident side), at cosine of angle of incidence. Includes phase shifts of total internal reflection.
+    pub fn dielectric_reflection(cos_i: f32, eta: f32) -> Self {
+        let eta = Complex::new(eta, 0.0);
+        Mueller::reflection([fresnel(cos_i, eta); 3].map(|(rs, rp, _)| (rs, rp)))
+    }
+
+    /// Matrix of Fresnel refraction through dielectric interface with relative index of refraction eta (transmitted
+    /// over incident side), at cosine of angle of incidence. Zero beyond the critical angle.
+    pub fn dielectric_transmission(cos_i: f32, eta: f32) -> Self {
+        let (_, _, cos_t) = fresnel(cos_i, Complex::new(eta, 0.0));
+        if cos_t.im != 0.0 || cos_i <= 0.0 {
+            return Mueller::uniform([[0.0; 4]; 4]);
+        }
+
+        // Transmitted power per polarization, ratio of beam cross sections included.
+        let (cos_t, factor) = (cos_t.re, eta * cos_t.re / cos_i);
+        let ts = 2.0 * cos_i / (cos_i + eta * cos_t);
+        let tp = 2.0 * cos_i / (eta * cos_i + cos_t);
+        let (ts2, tp2) = (factor * ts * ts, factor * tp * tp);
+        let cross = (ts2 * tp2).sqrt();
+        Mueller::uniform([
+            [0.5 * (ts2 + tp2), 0.5 * (ts2 - tp2), 0.0, 0.0],
+            [0.5 * (ts2 - tp2), 0.5 * (ts2 + tp2), 0.0, 0.0],
+            [0.0, 0.0, cross, 0.0],
+            [0.0, 0.0, 0.0, cross],
+        ])
+    }
+
+    /// Matrix of Fresnel reflection off conductor with given reflectance at normal incidence per channel, at cosine of
+    /// angle of incidence. The complex index of refraction is derived from reflectance with edge tint equal to it
+    /// (Gulbrandsen's artist friendly metallic Fresnel).
+    pub fn conductor_reflection(cos_i: f32, reflectance: Color3f) -> Self {
+        let ior = |r: f32| {
+            let r = r.clamp(0.0, MAX_CONDUCTOR_REFLECTANCE);
+            let n = r * (1.0 - r) / (1.0 + r) + (1.0 - r) * (1.0 + r.sqrt()) / (1.0 - r.sqrt());
+            let k = ((r * (n + 1.0).powi(2) - (n - 1.0).powi(2)) / (1.0 - r))
+                .max(0.0)
+                .sqrt();
+            Complex::new(n, k)
+        };
+        let channels = [reflectance.r(), reflectance.g(), reflectance.b()];
+        Mueller::reflection(channels.map(|r| {
+            let (rs, rp, _) = fresnel(cos_i, ior(r));
+            (rs, rp)
+        }))
+    }
+
+    /// Matrix of element followed by other, i.e. light passes other first.
+    pub fn compose(&self, other: &Mueller) -> Self {
+        let mut rows = [[Color3f::black(); 4]; 4];
+        for (i, row) in rows.iter_mut().enumerate() {
+            for (j, entry) in row.iter_mut().enumerate() {
+                for k in 0..4 {
+                    *entry += self.rows[i][k] * other.rows[k][j];
+                }
+            }
+        }
+        Mueller { rows }
+    }
+
+    /// Stokes vector of light after element.
+    pub fn apply(&self, stokes: &Stokes) -> Stokes {
+        let mut components = [Color3f::black(); 4];
+        for (component, row) in components.iter_mut().zip(&self.rows) {
+            for (entry, s) in row.iter().zip(&stokes.components) {
+                *component += *entry * *s;
+            }
+        }
+        Stokes { components }
+    }
+
+    /// Intensity of unpolarized light with given intensity after element.
+    pub fn apply_unpolarized(&self, intensity: Color3f) -> Color3f {
+        self.rows[0][0] * intensity
+    }
+
+    /// Matrix scaled by attenuation per channel.
+    pub fn attenuate(&self, attenuation: Color3f) -> Self {
+        Mueller {
+            rows: self.rows.map(|row| row.map(|entry| entry * attenuation)),
+        }
+    }
+
+    /// Matrix with same entries for all channels.
+    fn uniform(rows: [[f32; 4]; 4]) -> Self {
+        Mueller {
+            rows: rows.map(|row| row.map(|x| Color3f::new(x, x, x))),
+        }
+    }
+
+    /// Matrix of reflection with complex amplitude coefficients of s- and p-polarized light per channel.
+    fn reflection(coefficients: [(Complex, Complex); 3]) -> Self {
+        let entries = coefficients.map(|(rs, rp)| {
+            let (rs2, rp2) = (rs.norm_sqr(), rp.norm_sqr());
+            let cross = rs * rp.conj();
+            [0.5 * (rs2 + rp2), 0.5 * (rs2 - rp2), cross.re, cross.im]
+        });
+        let entry = |i: usize| Color3f::new(entries[0][i], entries[1][i], entries[2][i]);
+        let zero = Color3f::black();
+        Mueller {
+            rows: [
+                [entry(0), entry(1), zero, zero],
+                [entry(1), entry(0), zero, zero],
+                [zero, zero, entry(2), entry(3)],
+                [zero, zero, -entry(3), entry(2)],
+            ],
+        }
+    }
+}
+
+/// Mueller matrix of ray scattered by polarizing material at intersection, weighted by the inverse probability of
+/// the scattering event chosen by `Material::interact`, and the reference direction of Stokes vectors on both sides:
+/// perpendicular to the plane of incidence. Dielectrics reflect and refract with exact Fresnel equations, metals
+/// reflect as conductors, about the mirror direction even if fuzzy. None for other materials, which depolarize.
+pub fn scattering(
+    material: &Material,
+    ray: Ray,
+    isect: &Intersection,
+    scattered: Ray,
+    attenuation: Color3f,
+) -> Option<(Mueller, Vector3f)> {
+    let incident = ray.direction().normalize();
+    let front_face = incident.dot(&isect.normal) <= 0.0;
+    let normal = if front_face {
+        isect.normal
+    } else {
+        -isect.normal
+    };
+    let cos_i = (-incident.dot(&normal)).clamp(0.0, 1.0);
+    let mueller = match material {
+        Material::Dielectric(dielectric) => {
+            let ior = scattered
+                .wavelength()
+                .map_or(dielectric.ior(), |wavelength| dielectric.ior_at(wavelength));
+            let eta = if front_face { ior } else { 1.0 / ior };
+            dielectric_scattering(dielectric, incident, normal, eta, scattered.direction())
+                .attenuate(attenuation)
+        }
+        Material::Metal(metal) => Mueller::conductor_reflection(cos_i, metal.albedo()),
+        _ => return None,
+    };
+
+    // Perpendicular to plane of incidence, any direction perpendicular to ray at normal incidence.
+    let perpendicular = incident.cross(&normal);
+    let reference = if perpendicular.length_squared() > 1e-12 {
+        perpendicular.normalize()
+    } else {
+        incident.orthonormal_basis().0
+    };
+    Some((mueller, reference))
+}
+
+/// Angle in radians from reference direction to other, both perpendicular to ray along direction, counterclockwise
+/// as seen facing the ray.
+pub fn reference_angle(direction: Vector3f, reference: Vector3f, other: Vector3f) -> f32 {
+    let facing = -direction.normalize();
+    reference
+        .cross(&other)
+        .dot(&facing)
+        .atan2(reference.dot(&other))
+}
+
+/// Mueller matrix of reflection or refraction at interface with relative index of refraction eta, whichever
+/// scattered direction took, over its probability.
+fn dielectric_scattering(
+    dielectric: &Dielectric,
+    incident: Vector3f,
+    normal: Vector3f,
+    eta: f32,
+    scattered: Vector3f,
+) -> Mueller {
+    let cos_i = (-incident.dot(&normal)).clamp(0.0, 1.0);
+    let reflection = Mueller::dielectric_reflection(cos_i, eta);
+    if incident.refract(normal, 1.0 / eta).is_none() {
+        return reflection;
+    }
+    let probability = dielectric.schlick(incident, normal, 1.0 / eta);
+    if scattered.dot(&normal) > 0.0 {
+        reflection.attenuate(Color3f::white() / probability)
+    } else {
+        Mueller::dielectric_transmission(cos_i, eta)
+            .attenuate(Color3f::white() / (1.0 - probability))
+    }
+}
+
+/// Fresnel amplitude coefficients of reflection of s- and p-polarized light at interface with relative complex index
+/// of refraction eta, at cosine of angle of incidence, and the complex cosine of the angle of refraction.
+fn fresnel(cos_i: f32, eta: Complex) -> (Complex, Complex, Complex) {
+    let cos_i = Complex::new(cos_i, 0.0);
+    let sin2_i = Complex::new(1.0, 0.0) - cos_i * cos_i;
+    let cos_t = (Complex::new(1.0, 0.0) - sin2_i / (eta * eta)).sqrt();
+    let rs = (cos_i - eta * cos_t) / (cos_i + eta * cos_t);
+    let rp = (eta * cos_i - cos_t) / (eta * cos_i + cos_t);
+    (rs, rp, cos_t)
+}
+
+/// Complex number, for Fresnel coefficients of conductors and of total internal reflection.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Complex {
+    /// Real part.
+    re: f32,
+
+    /// Imaginary part.
+    im: f32,
+}
+
+impl Complex {
+    /// Creates complex number from real and imaginary part.
+    fn new(re: f32, im: f32) -> Self {
+        Complex { re, im }
+    }
+
+    /// Complex conjugate.
+    fn conj(self) -> Self {
+        Complex::new(self.re, -self.im)
+    }
+
+    /// Squared magnitude.
+    fn norm_sqr(self) -> f32 {
+        self.re * self.re + self.im * self.im
+    }
+
+    /// Principal square root, with non-negative real part.
+    fn sqrt(self) -> Self {
+        let magnitude = self.norm_sqr().sqrt();
+        let re = (0.5 * (magnitude + self.re)).max(0.0).sqrt();
+        let im = (0.5 * (magnitude - self.re)).max(0.0).sqrt();
+        Complex::new(re, if self.im < 0.0 { -im } else { im })
+    }
+}
+
+impl Add for Complex {
+    type Output = Complex;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Complex::new(self.re + rhs.re, self.im + rhs.im)
+    }
+}
+
+impl Sub for Complex {
+    type Output = Complex;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Complex::new(self.re - rhs.re, self.im - rhs.im)
+    }
+}
+
+impl Mul for Complex {
+    type Output = Complex;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        Complex::new(
+            self.re * rhs.re - self.im * rhs.im,
+            self.re * rhs.im + self.im * rhs.re,
+        )
+    }
+}
+
+impl Div for Complex {
+    type Output = Complex;
+
+    fn div(self, rhs: Self) -> Self::Output {
+        let numerator = self * rhs.conj();
+        let denominator = rhs.norm_sqr();
+        Complex::new(numerator.re / denominator, numerator.im / denominator)
+    }
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::FRAC_PI_2;
+
+    /// Whether all channels of colors are within tolerance.
+    fn near(a: Color3f, b: Color3f) -> bool {
+        let d = a - b;
+        d.r().abs().max(d.g().abs()).max(d.b().abs()) < 1e-5
+    }
+
+    #[test]
+    fn filters() {
+        // Polarizers pass half of unpolarized light, crossed ones none.
+        let light = Stokes::unpolarized(Color3f::white());
+        let horizontal = Mueller::linear_polarizer(0.0);
+        let passed = horizontal.apply(&light);
+        assert!(near(passed.intensity(), Color3f::new(0.5, 0.5, 0.5)));
+        assert!(near(passed.degree_of_polarization(), Color3f::white()));
+        let crossed = Mueller::linear_polarizer(FRAC_PI_2).compose(&horizontal);
+        assert!(near(crossed.apply(&light).intensity(), Color3f::black()));
+
+        // Malus's law, and rotated filters are filters in rotated frames.
+        let tilted = Mueller::linear_polarizer(0.5).compose(&horizontal);
+        let expected = 0.5 * 0.5f32.cos().powi(2);
+        assert!(near(
+            tilted.apply_unpolarized(Color3f::white()),
+            Color3f::new(expected, expected, expected)
+        ));
+        let rotated = Mueller::rotation(-0.5)
+            .compose(&horizontal)
+            .compose(&Mueller::rotation(0.5));
+        for (a, b) in rotated
+            .rows
+            .iter()
+            .zip(&Mueller::linear_polarizer(0.5).rows)
+        {
+            assert!(a.iter().zip(b).all(|(a, b)| near(*a, *b)));
+        }
+        assert_eq!(Mueller::identity().apply(&passed), passed);
+        assert!(near(
+            Mueller::depolarizer(Color3f::new(0.5, 0.5, 0.5))
+                .apply(&passed)
+                .degree_of_polarization(),
+            Color3f::black()
+        ));
+    }
+
+    #[test]
+    fn fresnel() {
+        // Reflection at Brewster's angle is fully s-polarized.
+        let eta: f32 = 1.5;
+        let brewster = eta.atan().cos();
+        let light = Stokes::unpolarized(Color3f::white());
+        let reflected = Mueller::dielectric_reflection(brewster, eta).apply(&light);
+        assert!(near(reflected.degree_of_polarization(), Color3f::white()));
+        assert!(reflected.components[1].r() > 0.0);
+
+        // Energy is conserved, total internal reflection reflects all.
+        for cos_i in [1.0, 0.8, 0.3, 0.05] {
+            let total = Mueller::dielectric_reflection(cos_i, eta).rows[0][0]
+                + Mueller::dielectric_transmission(cos_i, eta).rows[0][0];
+            assert!(near(total, Color3f::white()), "{}", cos_i);
+        }
+        let normal = Mueller::dielectric_reflection(1.0, eta).rows[0][0].g();
+        assert!((normal - 0.04).abs() < 1e-5);
+        assert!(near(
+            Mueller::dielectric_reflection(0.3, 1.0 / eta).rows[0][0],
+            Color3f::white()
+        ));
+        assert_eq!(
+            Mueller::dielectric_transmission(0.3, 1.0 / eta),
+            Mueller::uniform([[0.0; 4]; 4])
+        );
+
+        // Conductors reflect given reflectance head-on and all at grazing angles.
+        let gold = Color3f::new(1.0, 0.78, 0.34);
+        let head_on = Mueller::conductor_reflection(1.0, gold).rows[0][0];
+        assert!(
+            (head_on - gold.clamp(0.0, MAX_CONDUCTOR_REFLECTANCE))
+                .luminance()
+                .abs()
+                < 1e-3
+        );
+        let grazing = Mueller::conductor_reflection(1e-3, gold).rows[0][0];
+        assert!(grazing.b() > 0.95);
+    }
+
+    #[test]
+    fn reference_angle() {
+        let direction = Vector3f::new(0.0, 0.0, -1.0);
+        let right = Vector3f::new(1.0, 0.0, 0.0);
+        let up = Vector3f::new(0.0, 1.0, 0.0);
+        assert!((super::reference_angle(direction, right, up) - FRAC_PI_2).abs() < 1e-6);
+        assert!((super::reference_angle(direction, up, right) + FRAC_PI_2).abs() < 1e-6);
+    }
+}
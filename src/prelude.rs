@@ -0,0 +1,33 @@
+//! Commonly used types, for glob import with `use eden::prelude::*`. Covers building a scene of shapes with
+//! materials and rendering it with a camera.
+
+pub use crate::{
+    background::Background,
+    base::{
+        color::Color3f,
+        light::Light,
+        material::{Interactable, Material},
+        point::Point3f,
+        shape::{Intersectable, Shape},
+        vector::Vector3f,
+    },
+    camera::Camera,
+    film::Film,
+    integrator::Integrator,
+    lights::{directional::DirectionalLight, point::PointLight, spot::SpotLight},
+    materials::{
+        dielectric::Dielectric,
+        diffuse_light::DiffuseLight,
+        lambert::Lambert,
+        metal::Metal,
+        microfacet::Microfacet,
+        mix::{Mix, MixFactor},
+        normal_map::{NormalMap, NormalMapped},
+        principled::Principled,
+        shadow_catcher::ShadowCatcher,
+    },
+    scene::Scene,
+    settings::RenderSettings,
+    shapes::sphere::Sphere,
+    sky::PhysicalSky,
+};
@@ -0,0 +1,222 @@
+use crate::{
+    base::vector::Vector3f, camera::Camera, checkpoint::Accumulation, film::Film, scene::Scene,
+    settings::RenderSettings, tonemap::ToneMapping,
+};
+use minifb::{Key, MouseButton, MouseMode, Window, WindowOptions};
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    thread,
+    time::Instant,
+};
+
+/// Degrees the view turns per pixel of mouse movement.
+const LOOK_SENSITIVITY: f32 = 0.2;
+
+/// Max angle of view above or below the horizon in degrees, keeping the view off the 'up' direction.
+const MAX_PITCH: f32 = 89.0;
+
+/// Movement keys with their direction in camera-local axes: x right, y up, z forward.
+const MOVEMENT_KEYS: [(Key, [f32; 3]); 8] = [
+    (Key::W, [0.0, 0.0, 1.0]),
+    (Key::S, [0.0, 0.0, -1.0]),
+    (Key::A, [-1.0, 0.0, 0.0]),
+    (Key::D, [1.0, 0.0, 0.0]),
+    (Key::E, [0.0, 1.0, 0.0]),
+    (Key::Space, [0.0, 1.0, 0.0]),
+    (Key::Q, [0.0, -1.0, 0.0]),
+    (Key::LeftShift, [0.0, -1.0, 0.0]),
+];
+
+/// Renders scene with settings while showing the image in a window, updated whenever a tile is finished. Closing the
+/// window or pressing escape stops rendering early, tiles not rendered by then stay black.
+pub fn render_with_preview(
+    camera: &mut Camera,
+    scene: &Scene,
+    settings: &RenderSettings,
+    tone_mapping: ToneMapping,
+) -> Film {
+    let (width, height) = camera.resolution();
+    let mut window = Window::new(
+        "eden",
+        width as usize,
+        height as usize,
+        WindowOptions::default(),
+    )
+    .expect("cannot open preview window");
+    window.set_target_fps(30);
+
+    // Window pixels (0RGB), written by render threads.
+    let pixels = Mutex::new(vec![0u32; (width * height) as usize]);
+    let open = AtomicBool::new(true);
+
+    thread::scope(|s| {
+        let render = s.spawn(|| {
+            camera.render_observed(scene, settings, |tile, buffer| {
+                let mut pixels = pixels.lock().unwrap();
+                for (i, &color) in buffer.iter().enumerate() {
+                    let x = tile.x + i as u32 % tile.width;
+                    let y = tile.y + i as u32 / tile.width;
+                    pixels[(y * width + x) as usize] = pack(tone_mapping.encode(color));
+                }
+                open.load(Ordering::Relaxed)
+            })
+        });
+
+        // Window event loop on calling thread until rendering finishes.
+        while !render.is_finished() {
+            if !window.is_open() || window.is_key_down(Key::Escape) {
+                open.store(false, Ordering::Relaxed);
+                thread::sleep(std::time::Duration::from_millis(10));
+                continue;
+            }
+            let frame = pixels.lock().unwrap().clone();
+            window
+                .update_with_buffer(&frame, width as usize, height as usize)
+                .unwrap();
+        }
+        render.join().unwrap()
+    })
+}
+
+/// Explores scene interactively in a window, refining the image progressively with one sample per pixel per pass
+/// up to the sample count of settings. WASD walks, E or space rises and Q or shift sinks, at the distance from
+/// camera to the point it looks at per second; dragging with the left mouse button looks around. Any movement
+/// restarts accumulation. Closing the window or pressing escape returns the image accumulated so far, with the
+/// camera left where it was moved to.
+pub fn explore(
+    camera: &mut Camera,
+    scene: &Scene,
+    settings: &RenderSettings,
+    tone_mapping: ToneMapping,
+) -> Film {
+    let (width, height) = camera.resolution();
+    let mut window = Window::new(
+        "eden",
+        width as usize,
+        height as usize,
+        WindowOptions::default(),
+    )
+    .expect("cannot open preview window");
+    window.set_target_fps(30);
+
+    let mut accumulation = Accumulation::new(camera, scene, settings);
+    let mut done = false;
+    let mut last_frame = Instant::now();
+    let mut last_mouse = window.get_mouse_pos(MouseMode::Pass);
+    while window.is_open() && !window.is_key_down(Key::Escape) {
+        // Movement scaled by frame time, look by mouse drag.
+        let elapsed = last_frame.elapsed().as_secs_f32();
+        last_frame = Instant::now();
+        let speed = (camera.look_at() - camera.look_from()).length() * elapsed;
+        let movement = MOVEMENT_KEYS
+            .iter()
+            .filter(|(key, _)| window.is_key_down(*key))
+            .fold(Vector3f::default(), |sum, &(_, [x, y, z])| {
+                sum + speed * Vector3f::new(x, y, z)
+            });
+        let mouse = window.get_mouse_pos(MouseMode::Pass);
+        let (yaw, pitch) = match (last_mouse, mouse) {
+            (Some((x0, y0)), Some((x1, y1))) if window.get_mouse_down(MouseButton::Left) => {
+                (-(x1 - x0) * LOOK_SENSITIVITY, -(y1 - y0) * LOOK_SENSITIVITY)
+            }
+            _ => (0.0, 0.0),
+        };
+        last_mouse = mouse;
+        if movement != Vector3f::default() || yaw != 0.0 || pitch != 0.0 {
+            steer(camera, movement, yaw, pitch);
+            accumulation = Accumulation::new(camera, scene, settings);
+            done = false;
+        }
+
+        // Next pass, or just window events once all samples are taken.
+        if done {
+            window.update();
+            continue;
+        }
+        done = camera.render_accumulated(scene, settings, &mut accumulation, 1);
+        let frame: Vec<u32> = accumulation
+            .to_film(settings.film_precision())
+            .pixels()
+            .map(|color| pack(tone_mapping.encode(color)))
+            .collect();
+        window
+            .update_with_buffer(&frame, width as usize, height as usize)
+            .unwrap();
+    }
+    accumulation.to_film(settings.film_precision())
+}
+
+/// Moves camera like a first-person view: by movement in camera-local axes (x right, y along 'up' direction, z
+/// forward along the horizon), then turns it by yaw degrees to the left around 'up' direction and pitch degrees
+/// upward, keeping the distance to the point it looks at.
+pub fn steer(camera: &mut Camera, movement: Vector3f, yaw: f32, pitch: f32) {
+    let up = camera.view_up().normalize();
+    let offset = camera.look_at() - camera.look_from();
+    let distance = offset.length();
+    let forward = offset / distance;
+
+    // Heading along horizon and pitch above it.
+    let heading = (forward - forward.dot(&up) * up).normalize();
+    let current_pitch = forward.dot(&up).clamp(-1.0, 1.0).asin().to_degrees();
+    let right = heading.cross(&up);
+    let look_from =
+        camera.look_from() + movement.x() * right + movement.y() * up + movement.z() * heading;
+
+    // Turn heading around 'up' direction, then tilt.
+    let (sin, cos) = yaw.to_radians().sin_cos();
+    let heading = cos * heading + sin * up.cross(&heading);
+    let (sin, cos) = (current_pitch + pitch)
+        .clamp(-MAX_PITCH, MAX_PITCH)
+        .to_radians()
+        .sin_cos();
+    let forward = cos * heading + sin * up;
+    camera.set_look_from(look_from);
+    camera.set_look_at(look_from + distance * forward);
+}
+
+/// Packs 8-bit RGB components into window pixel (0RGB).
+fn pack([r, g, b]: [u8; 3]) -> u32 {
+    (r as u32) << 16 | (g as u32) << 8 | b as u32
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::point::Point3f;
+
+    #[test]
+    fn steer() {
+        let mut camera = Camera::new(4, 4);
+        camera.set_look_from(Point3f::new(0.0, 1.0, 0.0));
+        camera.set_look_at(Point3f::new(0.0, 0.0, -1.0));
+        let near = |a: Point3f, b: Point3f| (a - b).length() < 1e-5;
+
+        // Walking keeps height while looking down, strafing moves sideways.
+        super::steer(&mut camera, Vector3f::new(1.0, 0.0, 2.0), 0.0, 0.0);
+        assert!(near(camera.look_from(), Point3f::new(1.0, 1.0, -2.0)));
+        assert!(near(camera.look_at(), Point3f::new(1.0, 0.0, -3.0)));
+
+        // Turning left by 90 degrees faces -x, looking level keeps distance to look-at point.
+        super::steer(&mut camera, Vector3f::default(), 90.0, 45.0);
+        let distance = 2f32.sqrt();
+        assert!(near(
+            camera.look_at(),
+            Point3f::new(1.0 - distance, 1.0, -2.0)
+        ));
+
+        // Looking up stops short of 'up' direction.
+        super::steer(&mut camera, Vector3f::default(), 0.0, 180.0);
+        let forward = (camera.look_at() - camera.look_from()).normalize();
+        assert!((forward.y() - MAX_PITCH.to_radians().sin()).abs() < 1e-5);
+    }
+
+    #[test]
+    fn pack() {
+        assert_eq!(super::pack([0x12, 0x34, 0x56]), 0x123456);
+        assert_eq!(super::pack([255, 0, 0]), 0xff0000);
+    }
+}
@@ -0,0 +1,358 @@
+use std::{error::Error, fmt, fs, io, path::Path};
+
+/// Processing state of render job.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum JobState {
+    /// Waiting to be rendered.
+    Pending,
+
+    /// Currently rendering.
+    Running,
+
+    /// Skipped until resumed.
+    Paused,
+
+    /// Rendered successfully.
+    Done,
+
+    /// Rendering failed.
+    Failed,
+}
+
+impl JobState {
+    /// All states.
+    const ALL: [JobState; 5] = [
+        JobState::Pending,
+        JobState::Running,
+        JobState::Paused,
+        JobState::Done,
+        JobState::Failed,
+    ];
+
+    /// Lowercase name of state.
+    pub fn name(&self) -> &'static str {
+        match self {
+            JobState::Pending => "pending",
+            JobState::Running => "running",
+            JobState::Paused => "paused",
+            JobState::Done => "done",
+            JobState::Failed => "failed",
+        }
+    }
+}
+
+/// Render job, given by command line options of a single render.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Job {
+    /// Unique job number.
+    id: u32,
+
+    /// Processing state.
+    state: JobState,
+
+    /// Command line options of render.
+    args: Vec<String>,
+}
+
+impl Job {
+    /// Unique job number.
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Processing state.
+    pub fn state(&self) -> JobState {
+        self.state
+    }
+
+    /// Command line options of render.
+    pub fn args(&self) -> &[String] {
+        &self.args
+    }
+}
+
+/// Error of job queue operation.
+#[derive(Debug)]
+pub enum QueueError {
+    /// Queue file could not be read or written.
+    Io(io::Error),
+
+    /// Queue file is malformed.
+    Syntax { line: usize, message: String },
+
+    /// No job with given id exists.
+    UnknownJob(u32),
+
+    /// Operation is not possible in current job state.
+    InvalidState { id: u32, state: JobState },
+}
+
+impl fmt::Display for QueueError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QueueError::Io(e) => write!(f, "{}", e),
+            QueueError::Syntax { line, message } => write!(f, "line {}: {}", line, message),
+            QueueError::UnknownJob(id) => write!(f, "no job {}", id),
+            QueueError::InvalidState { id, state } => {
+                write!(f, "job {} is {}", id, state.name())
+            }
+        }
+    }
+}
+
+impl Error for QueueError {}
+
+impl From<io::Error> for QueueError {
+    fn from(e: io::Error) -> Self {
+        QueueError::Io(e)
+    }
+}
+
+/// Queue of render jobs, processed front to back. Persisted as text with one job per line: id, state and render
+/// options, separated by tabs.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Queue {
+    /// Jobs in processing order.
+    jobs: Vec<Job>,
+}
+
+impl Queue {
+    /// Creates empty queue.
+    pub fn new() -> Self {
+        Queue { jobs: Vec::new() }
+    }
+
+    /// Loads queue from file. A missing file is an empty queue.
+    pub fn load(path: &Path) -> Result<Queue, QueueError> {
+        match fs::read_to_string(path) {
+            Ok(text) => Queue::parse(&text),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Queue::new()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Saves queue to file, replacing it atomically so concurrent readers never see partial queues.
+    pub fn save(&self, path: &Path) -> Result<(), QueueError> {
+        let temporary = path.with_extension("tmp");
+        fs::write(&temporary, self.to_text())?;
+        fs::rename(&temporary, path)?;
+        Ok(())
+    }
+
+    /// Parses queue from text.
+    pub fn parse(text: &str) -> Result<Queue, QueueError> {
+        let mut queue = Queue::new();
+        for (i, line) in text.lines().enumerate() {
+            if line.is_empty() {
+                continue;
+            }
+            let syntax = |message: &str| QueueError::Syntax {
+                line: i + 1,
+                message: message.to_string(),
+            };
+            let mut fields = line.split('\t');
+            let id = fields
+                .next()
+                .and_then(|id| id.parse().ok())
+                .ok_or_else(|| syntax("invalid job id"))?;
+            let state = fields
+                .next()
+                .and_then(|name| JobState::ALL.into_iter().find(|s| s.name() == name))
+                .ok_or_else(|| syntax("invalid job state"))?;
+            if queue.jobs.iter().any(|job| job.id == id) {
+                return Err(syntax("duplicate job id"));
+            }
+            let args = fields.map(str::to_string).collect();
+            queue.jobs.push(Job { id, state, args });
+        }
+        Ok(queue)
+    }
+
+    /// Queue as text, see `parse`.
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for job in self.jobs.iter() {
+            text += &job.id.to_string();
+            text += "\t";
+            text += job.state.name();
+            for arg in job.args.iter() {
+                text += "\t";
+                text += arg;
+            }
+            text += "\n";
+        }
+        text
+    }
+
+    /// Jobs in processing order.
+    pub fn jobs(&self) -> &[Job] {
+        &self.jobs
+    }
+
+    /// Appends pending job with render options, which must not contain tabs or line breaks. Returns job id.
+    pub fn add(&mut self, args: Vec<String>) -> u32 {
+        assert!(args.iter().all(|a| !a.contains(['\t', '\n', '\r'])));
+        let id = self.jobs.iter().map(|job| job.id + 1).max().unwrap_or(1);
+        self.jobs.push(Job {
+            id,
+            state: JobState::Pending,
+            args,
+        });
+        id
+    }
+
+    /// First pending job.
+    pub fn next_pending(&self) -> Option<&Job> {
+        self.jobs.iter().find(|job| job.state == JobState::Pending)
+    }
+
+    /// Sets state of job.
+    pub fn set_state(&mut self, id: u32, state: JobState) -> Result<(), QueueError> {
+        self.job_mut(id)?.state = state;
+        Ok(())
+    }
+
+    /// Pauses pending job.
+    pub fn pause(&mut self, id: u32) -> Result<(), QueueError> {
+        self.transition(id, &[JobState::Pending], JobState::Paused)
+    }
+
+    /// Makes paused or failed job pending again.
+    pub fn resume(&mut self, id: u32) -> Result<(), QueueError> {
+        self.transition(id, &[JobState::Paused, JobState::Failed], JobState::Pending)
+    }
+
+    /// Moves job to front of queue.
+    pub fn promote(&mut self, id: u32) -> Result<(), QueueError> {
+        let index = self.index(id)?;
+        let job = self.jobs.remove(index);
+        self.jobs.insert(0, job);
+        Ok(())
+    }
+
+    /// Removes job which is not running.
+    pub fn remove(&mut self, id: u32) -> Result<(), QueueError> {
+        let index = self.index(id)?;
+        let state = self.jobs[index].state;
+        if state == JobState::Running {
+            return Err(QueueError::InvalidState { id, state });
+        }
+        self.jobs.remove(index);
+        Ok(())
+    }
+
+    /// Makes jobs left running (e.g. by an interrupted queue runner) pending again.
+    pub fn reset_running(&mut self) {
+        for job in self.jobs.iter_mut() {
+            if job.state == JobState::Running {
+                job.state = JobState::Pending;
+            }
+        }
+    }
+
+    /// Changes state of job if it is in one of given states.
+    fn transition(&mut self, id: u32, from: &[JobState], to: JobState) -> Result<(), QueueError> {
+        let job = self.job_mut(id)?;
+        if !from.contains(&job.state) {
+            return Err(QueueError::InvalidState {
+                id,
+                state: job.state,
+            });
+        }
+        job.state = to;
+        Ok(())
+    }
+
+    /// Position of job in queue.
+    fn index(&self, id: u32) -> Result<usize, QueueError> {
+        self.jobs
+            .iter()
+            .position(|job| job.id == id)
+            .ok_or(QueueError::UnknownJob(id))
+    }
+
+    /// Job with id.
+    fn job_mut(&mut self, id: u32) -> Result<&mut Job, QueueError> {
+        let index = self.index(id)?;
+        Ok(&mut self.jobs[index])
+    }
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Options of test job.
+    fn args(options: &[&str]) -> Vec<String> {
+        options.iter().map(|o| o.to_string()).collect()
+    }
+
+    #[test]
+    fn operations() {
+        let mut queue = Queue::new();
+        let a = queue.add(args(&["--preset", "caustics"]));
+        let b = queue.add(args(&[]));
+        let c = queue.add(args(&["--compare"]));
+        assert_eq!((a, b, c), (1, 2, 3));
+        assert_eq!(queue.next_pending().unwrap().id(), 1);
+
+        // Paused jobs are skipped, promoted jobs go first.
+        queue.pause(a).unwrap();
+        assert_eq!(queue.next_pending().unwrap().id(), 2);
+        queue.promote(c).unwrap();
+        let order: Vec<u32> = queue.jobs().iter().map(Job::id).collect();
+        assert_eq!(order, [3, 1, 2]);
+        assert_eq!(queue.next_pending().unwrap().id(), 3);
+        queue.resume(a).unwrap();
+
+        // Invalid transitions and unknown jobs.
+        queue.set_state(c, JobState::Running).unwrap();
+        assert!(matches!(
+            queue.pause(c),
+            Err(QueueError::InvalidState {
+                id: 3,
+                state: JobState::Running
+            })
+        ));
+        assert!(queue.remove(c).is_err());
+        assert!(matches!(queue.promote(7), Err(QueueError::UnknownJob(7))));
+        queue.reset_running();
+        assert_eq!(queue.jobs()[0].state(), JobState::Pending);
+        queue.remove(c).unwrap();
+        assert_eq!(queue.add(args(&[])), 3);
+    }
+
+    #[test]
+    fn persistence() {
+        let mut queue = Queue::new();
+        queue.add(args(&["--resolution", "64x32", "--output", "my image.ppm"]));
+        let id = queue.add(args(&[]));
+        queue.set_state(id, JobState::Failed).unwrap();
+        let text = queue.to_text();
+        assert_eq!(
+            text,
+            "1\tpending\t--resolution\t64x32\t--output\tmy image.ppm\n2\tfailed\n"
+        );
+        assert_eq!(Queue::parse(&text).unwrap(), queue);
+
+        // File round trip, missing file is empty queue.
+        let path = std::env::temp_dir().join(format!("eden-queue-{}.tsv", std::process::id()));
+        assert_eq!(Queue::load(&path).unwrap(), Queue::new());
+        queue.save(&path).unwrap();
+        assert_eq!(Queue::load(&path).unwrap(), queue);
+        fs::remove_file(&path).unwrap();
+
+        // Malformed queues.
+        for (text, line) in [
+            ("x\tpending\n", 1),
+            ("1\tpending\n\n1\tdone\n", 3),
+            ("1\tlost", 1),
+        ] {
+            assert!(matches!(
+                Queue::parse(text),
+                Err(QueueError::Syntax { line: l, .. }) if l == line
+            ));
+        }
+    }
+}
@@ -0,0 +1,394 @@
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, RngCore, SeedableRng};
+use std::sync::OnceLock;
+
+/// First primes used as Halton bases, one per sample dimension.
+const PRIMES: [u32; 32] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+    101, 103, 107, 109, 113, 127, 131,
+];
+
+/// Edge length of the tiled blue noise mask in pixels.
+const MASK_SIZE: usize = 64;
+
+/// Strategy for generating sample values of a pixel.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Sampler {
+    /// Independent uniform random values.
+    #[default]
+    Independent,
+
+    /// Halton low-discrepancy sequence, randomized per pixel by Cranley-Patterson rotation. Dimensions beyond the
+    /// prime table fall back to independent values.
+    Halton,
+
+    /// Halton sequence like `Halton`, but rotated per pixel by values of a tiled blue noise mask instead of random
+    /// ones, shifted per dimension. Errors of neighboring pixels differ as much as possible, so the remaining noise
+    /// lacks low frequencies and low sample counts look less blotchy.
+    BlueNoise,
+}
+
+/// Random number source for a single pixel. Each call draws the next dimension of the current sample, thus it can
+/// be handed to any code sampling through `Rng`, e.g. to materials as per-path random number stream. Values depend
+/// only on seed, sample index and dimension, so renders are deterministic regardless of thread scheduling.
+#[derive(Clone, Debug)]
+pub struct PixelSampler {
+    /// Sampling strategy.
+    sampler: Sampler,
+
+    /// Index of current sample.
+    index: u32,
+
+    /// Next dimension to draw from current sample.
+    dimension: usize,
+
+    /// Per-pixel random offsets of quasi-random dimensions.
+    offsets: [f64; PRIMES.len()],
+
+    /// Source of independent values.
+    rng: StdRng,
+}
+
+impl PixelSampler {
+    /// Creates pixel sampler from strategy and seed.
+    pub fn new(sampler: Sampler, seed: u64) -> Self {
+        PixelSampler::continuing(sampler, seed, 0)
+    }
+
+    /// Creates pixel sampler for samples from given index on, e.g. to add samples to a checkpointed render.
+    /// Quasi-random dimensions continue their sequence, while independent values come from a separate stream, as
+    /// the stream of earlier samples cannot be skipped.
+    pub fn continuing(sampler: Sampler, seed: u64, first_sample: u32) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let offsets = std::array::from_fn(|_| rng.gen::<f64>());
+        if first_sample > 0 {
+            rng = StdRng::seed_from_u64(rng.gen::<u64>() ^ first_sample as u64);
+        }
+        PixelSampler {
+            sampler,
+            index: 0,
+            dimension: 0,
+            offsets,
+            rng,
+        }
+    }
+
+    /// Creates pixel sampler for pixel of image from seed of render, for samples from given index on, see
+    /// `continuing`. Pixels get distinct streams, and blue noise offsets by their position.
+    pub fn for_pixel(sampler: Sampler, seed: u64, (x, y): (u32, u32), first_sample: u32) -> Self {
+        let pixel = ((y as u64) << 32) | x as u64;
+        let mut result = PixelSampler::continuing(
+            sampler,
+            seed ^ pixel.wrapping_mul(0x9E37_79B9_7F4A_7C15),
+            first_sample,
+        );
+        if sampler == Sampler::BlueNoise {
+            // Shift mask by seed, and per dimension along the R2 sequence, decorrelating dimensions.
+            let mask = blue_noise_mask();
+            let shift = StdRng::seed_from_u64(seed).gen::<u64>();
+            let (sx, sy) = (
+                shift as usize % MASK_SIZE,
+                (shift >> 32) as usize % MASK_SIZE,
+            );
+            for (dimension, offset) in result.offsets.iter_mut().enumerate() {
+                let d = dimension as f64 + 1.0;
+                let dx = sx + ((d * 0.754_877_666_2).fract() * MASK_SIZE as f64) as usize;
+                let dy = sy + ((d * 0.569_840_291_0).fract() * MASK_SIZE as f64) as usize;
+                let (u, v) = ((x as usize + dx) % MASK_SIZE, (y as usize + dy) % MASK_SIZE);
+                *offset = mask[v * MASK_SIZE + u];
+            }
+        }
+        result
+    }
+
+    /// Starts sample with given index, restarting at first dimension.
+    pub fn start_sample(&mut self, index: u32) {
+        self.index = index;
+        self.dimension = 0;
+    }
+
+    /// Index of current sample.
+    pub fn sample_index(&self) -> u32 {
+        self.index
+    }
+
+    /// Dimension drawn by the next value of current sample, i.e. the count of values drawn since the sample started.
+    /// Dimensions up to the size of the prime table are stratified by quasi-random strategies.
+    pub fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    /// Next sample value in [0,1).
+    fn next_value(&mut self) -> Option<f64> {
+        let dimension = self.dimension;
+        self.dimension += 1;
+        match self.sampler {
+            Sampler::Independent => None,
+            Sampler::Halton | Sampler::BlueNoise if dimension < PRIMES.len() => {
+                let value =
+                    radical_inverse(PRIMES[dimension], self.index) + self.offsets[dimension];
+                Some(value.fract())
+            }
+            Sampler::Halton | Sampler::BlueNoise => None,
+        }
+    }
+}
+
+impl RngCore for PixelSampler {
+    fn next_u32(&mut self) -> u32 {
+        match self.next_value() {
+            Some(value) => (value * (u32::MAX as f64 + 1.0)) as u32,
+            None => self.rng.next_u32(),
+        }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        match self.next_value() {
+            Some(value) => (value * (u64::MAX as f64 + 1.0)) as u64,
+            None => self.rng.next_u64(),
+        }
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest);
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.rng.try_fill_bytes(dest)
+    }
+}
+
+/// Mirrors digits of index in given base around the decimal point.
+fn radical_inverse(base: u32, mut index: u32) -> f64 {
+    let inverse_base = 1.0 / base as f64;
+    let mut factor = inverse_base;
+    let mut result = 0.0;
+    while index > 0 {
+        result += (index % base) as f64 * factor;
+        index /= base;
+        factor *= inverse_base;
+    }
+    result
+}
+
+/// Blue noise mask of `MASK_SIZE`² values in [0,1), row by row, tileable. Each value occurs once, and pixels of
+/// similar values lie far apart. Generated once by void-and-cluster (Ulichney 1993): starting from a binary pattern
+/// of evenly spread pixels, pixels are ranked by removing the tightest clusters and filling the largest voids, the
+/// rank becoming the value.
+fn blue_noise_mask() -> &'static [f64] {
+    static MASK: OnceLock<Vec<f64>> = OnceLock::new();
+    MASK.get_or_init(|| {
+        let n = MASK_SIZE * MASK_SIZE;
+
+        // Gaussian filter weights by toroidal offset, energy of a pixel sums weights of set pixels around it.
+        let weight: Vec<f64> = (0..n)
+            .map(|i| {
+                let (dx, dy) = (i % MASK_SIZE, i / MASK_SIZE);
+                let dx = dx.min(MASK_SIZE - dx) as f64;
+                let dy = dy.min(MASK_SIZE - dy) as f64;
+                (-(dx * dx + dy * dy) / (2.0 * 1.5 * 1.5)).exp()
+            })
+            .collect();
+        let update = |energy: &mut [f64], i: usize, sign: f64| {
+            let (x, y) = (i % MASK_SIZE, i / MASK_SIZE);
+            for (j, e) in energy.iter_mut().enumerate() {
+                let dx = (j % MASK_SIZE + MASK_SIZE - x) % MASK_SIZE;
+                let dy = (j / MASK_SIZE + MASK_SIZE - y) % MASK_SIZE;
+                *e += sign * weight[dy * MASK_SIZE + dx];
+            }
+        };
+        let extreme = |energy: &[f64], set: &[bool], value: bool, cluster: bool| {
+            (0..n)
+                .filter(|&i| set[i] == value)
+                .max_by(|&a, &b| {
+                    let order = energy[a].total_cmp(&energy[b]);
+                    if cluster {
+                        order
+                    } else {
+                        order.reverse()
+                    }
+                })
+                .unwrap()
+        };
+
+        // Initial pattern of a tenth of pixels at random, spread evenly by moving tightest clusters to largest voids.
+        let mut set = vec![false; n];
+        let mut energy = vec![0.0; n];
+        let mut indices: Vec<usize> = (0..n).collect();
+        indices.shuffle(&mut StdRng::seed_from_u64(0));
+        let ones = n / 10;
+        for &i in &indices[..ones] {
+            set[i] = true;
+            update(&mut energy, i, 1.0);
+        }
+        loop {
+            let cluster = extreme(&energy, &set, true, true);
+            set[cluster] = false;
+            update(&mut energy, cluster, -1.0);
+            let void = extreme(&energy, &set, false, false);
+            set[void] = true;
+            update(&mut energy, void, 1.0);
+            if void == cluster {
+                break;
+            }
+        }
+
+        // Ranks below the initial pattern by removing tightest clusters, above it by filling largest voids.
+        let mut rank = vec![0; n];
+        let (mut removed, mut removed_energy) = (set.clone(), energy.clone());
+        for r in (0..ones).rev() {
+            let cluster = extreme(&removed_energy, &removed, true, true);
+            removed[cluster] = false;
+            update(&mut removed_energy, cluster, -1.0);
+            rank[cluster] = r;
+        }
+        for r in ones..n {
+            let void = extreme(&energy, &set, false, false);
+            set[void] = true;
+            update(&mut energy, void, 1.0);
+            rank[void] = r;
+        }
+        rank.iter().map(|&r| (r as f64 + 0.5) / n as f64).collect()
+    })
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn radical_inverse() {
+        assert_eq!(super::radical_inverse(2, 0), 0.0);
+        assert_eq!(super::radical_inverse(2, 1), 0.5);
+        assert_eq!(super::radical_inverse(2, 2), 0.25);
+        assert_eq!(super::radical_inverse(2, 3), 0.75);
+        assert_eq!(super::radical_inverse(2, 6), 0.375);
+        assert!((super::radical_inverse(3, 5) - 7.0 / 9.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn stratification() {
+        // First n samples of each dimension fall into distinct strata of size 1/n.
+        let mut s = PixelSampler::new(Sampler::Halton, 42);
+        let mut stratified = |dimension: usize, n: u32| {
+            let mut hit = vec![false; n as usize];
+            for i in 0..n {
+                s.start_sample(i);
+                let x: f32 = (0..=dimension).map(|_| s.gen()).last().unwrap();
+                hit[(x * n as f32) as usize] = true;
+            }
+            hit.iter().all(|&h| h)
+        };
+        assert!(stratified(0, 8));
+        assert!(stratified(1, 9));
+        assert!(stratified(2, 25));
+    }
+
+    #[test]
+    fn convergence() {
+        // Integrate x*y over unit square (exact value 1/4), mean absolute error over many pixels.
+        let error = |sampler| {
+            let mut total = 0.0;
+            for seed in 0..32 {
+                let mut s = PixelSampler::new(sampler, seed);
+                let mut sum = 0.0;
+                for i in 0..256 {
+                    s.start_sample(i);
+                    sum += s.gen::<f64>() * s.gen::<f64>();
+                }
+                total += (sum / 256.0 - 0.25f64).abs();
+            }
+            total / 32.0
+        };
+        assert!(error(Sampler::Halton) < 0.002);
+        assert!(error(Sampler::Halton) < error(Sampler::Independent) / 4.0);
+    }
+
+    #[test]
+    fn fallback() {
+        let mut s = PixelSampler::new(Sampler::Halton, 1);
+        s.start_sample(3);
+        for _ in 0..100 {
+            let x: f32 = s.gen();
+            assert!((0.0..1.0).contains(&x));
+        }
+    }
+
+    #[test]
+    fn continuing() {
+        // Quasi-random values continue, independent values differ from those of earlier samples.
+        let mut a = PixelSampler::new(Sampler::Halton, 3);
+        let mut b = PixelSampler::continuing(Sampler::Halton, 3, 4);
+        a.start_sample(4);
+        b.start_sample(4);
+        assert_eq!(a.gen::<f64>(), b.gen::<f64>());
+        let mut a = PixelSampler::new(Sampler::Independent, 3);
+        let mut b = PixelSampler::continuing(Sampler::Independent, 3, 4);
+        assert_ne!(a.gen::<f64>(), b.gen::<f64>());
+    }
+
+    #[test]
+    fn dimension() {
+        // Each drawn value advances one dimension, streams repeat per seed and sample.
+        let mut s = PixelSampler::new(Sampler::Halton, 7);
+        s.start_sample(5);
+        let a: (f32, f64, bool) = (s.gen(), s.gen(), s.gen());
+        assert_eq!((s.sample_index(), s.dimension()), (5, 3));
+        s.start_sample(5);
+        assert_eq!(s.dimension(), 0);
+        assert_eq!(a, (s.gen(), s.gen(), s.gen()));
+    }
+
+    #[test]
+    fn blue_noise_mask() {
+        // Each value once, neighboring values far apart, compared to white noise with mean difference 1/3.
+        let mask = super::blue_noise_mask();
+        let mut ranks: Vec<usize> = mask
+            .iter()
+            .map(|v| (v * mask.len() as f64) as usize)
+            .collect();
+        ranks.sort();
+        assert!(ranks.iter().enumerate().all(|(i, &r)| i == r));
+        let difference = (0..mask.len())
+            .map(|i| (mask[i] - mask[(i + 1) % mask.len()]).abs())
+            .sum::<f64>()
+            / mask.len() as f64;
+        assert!(difference > 0.4);
+    }
+
+    #[test]
+    fn blue_noise() {
+        // Image of 1 sample per pixel of step function, blurred 4x4 like seen from afar: errors of blue noise cancel
+        // out among neighbors, unlike those of random offsets.
+        let blurred_error = |sampler| {
+            let size = 64;
+            let image: Vec<f64> = (0..size * size)
+                .map(|i| {
+                    let mut s = PixelSampler::for_pixel(sampler, 9, (i % size, i / size), 0);
+                    s.start_sample(0);
+                    let _: f64 = s.gen();
+                    f64::from(u8::from(s.gen::<f64>() < 0.3))
+                })
+                .collect();
+            let mut error = 0.0;
+            for by in (0..size).step_by(4) {
+                for bx in (0..size).step_by(4) {
+                    let sum: f64 = (0..16)
+                        .map(|j| image[((by + j / 4) * size + bx + j % 4) as usize])
+                        .sum();
+                    error += (sum / 16.0 - 0.3).abs();
+                }
+            }
+            error / (size * size / 16) as f64
+        };
+        assert!(blurred_error(Sampler::BlueNoise) < blurred_error(Sampler::Halton) / 2.0);
+        assert!(blurred_error(Sampler::BlueNoise) < blurred_error(Sampler::Independent) / 2.0);
+
+        // Different seeds shift the pattern.
+        let value = |seed| {
+            let mut s = PixelSampler::for_pixel(Sampler::BlueNoise, seed, (3, 5), 0);
+            s.gen::<f64>()
+        };
+        assert_ne!(value(1), value(2));
+    }
+}
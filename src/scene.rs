@@ -1,13 +1,84 @@
-use crate::base::{
-    interval::Interval,
-    ray::Ray,
-    shape::{Intersectable, Intersection, Shape},
+use crate::{
+    background::Background,
+    base::{
+        color::Color3f,
+        hash::{hash_float, stable_hash},
+        interval::Interval,
+        light::{Illuminating, Illumination, Light},
+        material::{Interactable, Material},
+        orientation::Orientation,
+        point::Point3f,
+        ray::{gamma, Ray},
+        shape::{Intersectable, Intersection, Sampleable, Shape},
+        uv_transform::UvTransform,
+        vector::Vector3f,
+    },
+    bvh::{Bvh, BvhStats},
+    decal::Decal,
+    material_library::{MaterialHandle, MaterialLibrary},
+    photon_map::PhotonMap,
+    sky::PhysicalSky,
 };
+use rand::Rng;
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    f32::consts::PI,
+    hash::{Hash, Hasher},
+    sync::{Arc, OnceLock, RwLock},
+};
+
+/// Background directions sampled for the luminance median of `set_environment_luminance`.
+const ENVIRONMENT_SAMPLES: usize = 1024;
+
+/// Fraction of distance to sampled light points left unchecked by shadow rays, keeping them off the light itself.
+const SHADOW_EPSILON: f32 = 1e-3;
+
+/// Factor by which refits may raise the expected intersection cost of the bounding volume hierarchy before it is
+/// rebuilt instead.
+const MAX_BVH_DEGRADATION: f32 = 2.0;
 
 /// 3-dim scene holding shape objects.
+#[derive(Hash)]
 pub struct Scene {
     /// Objects in scene.
     objects: Vec<Shape>,
+
+    /// Texture coordinate transforms of objects, by index.
+    uv_transforms: Vec<UvTransform>,
+
+    /// Identifiers of objects.
+    ids: ObjectIds,
+
+    /// Named materials shared by objects.
+    library: MaterialLibrary,
+
+    /// Named materials objects are bound to, by index.
+    bindings: Vec<Option<MaterialHandle>>,
+
+    /// Indices of emissive objects.
+    lights: Vec<usize>,
+
+    /// Radiance of rays escaping scene.
+    background: Background,
+
+    /// Factor on background radiance.
+    environment_intensity: EnvironmentIntensity,
+
+    /// Decals layered over object materials with the objects they are attached to, topmost last.
+    decals: Vec<(ObjectId, Decal)>,
+
+    /// Analytic lights without surface.
+    analytic_lights: Vec<Light>,
+
+    /// Convention of world coordinate axes.
+    orientation: Orientation,
+
+    /// Acceleration structure over objects.
+    bvh: SceneBvh,
+
+    /// Caustic photon map, if built since lights or objects last changed.
+    photons: PhotonCache,
 }
 
 impl Scene {
@@ -15,29 +86,748 @@ impl Scene {
     pub fn new() -> Self {
         Scene {
             objects: Vec::new(),
+            uv_transforms: Vec::new(),
+            ids: ObjectIds::default(),
+            library: MaterialLibrary::new(),
+            bindings: Vec::new(),
+            lights: Vec::new(),
+            background: Background::default(),
+            environment_intensity: EnvironmentIntensity(1.0),
+            decals: Vec::new(),
+            analytic_lights: Vec::new(),
+            orientation: Orientation::default(),
+            bvh: SceneBvh {
+                max_leaf_size: 4,
+                packets: true,
+                bvh: OnceLock::new(),
+            },
+            photons: PhotonCache::default(),
         }
     }
 
-    /// Adds object to scene.
-    pub fn add(&mut self, object: Shape) {
+    /// Adds object to scene. Returns its identifier.
+    pub fn add(&mut self, object: Shape) -> ObjectId {
+        if object.material().emitted() != Color3f::black() {
+            self.lights.push(self.objects.len());
+        }
         self.objects.push(object);
+        self.uv_transforms.push(UvTransform::IDENTITY);
+        self.bindings.push(None);
+        self.bvh.bvh.take();
+        self.photons.clear();
+        let id = ObjectId(self.ids.next);
+        self.ids.next += 1;
+        self.ids.ids.push(id);
+        id
     }
-}
 
-impl Intersectable for Scene {
-    fn intersect(&self, ray: Ray, ray_t: Interval) -> Option<Intersection> {
-        let mut intersection = None;
-        let mut closest_t = ray_t.end();
-
-        // Find closest object to ray.
-        for object in self.objects.iter() {
-            if let Some(i) = object.intersect(ray, Interval::new(ray_t.start(), closest_t)) {
-                intersection = Some(i);
-                closest_t = i.t;
+    /// Replaces object at index, e.g. to animate it. The object keeps its identifier and any material binding.
+    pub fn set_object(&mut self, index: usize, object: Shape) {
+        self.objects[index] = object;
+        self.object_changed(index);
+    }
+
+    /// Removes object with identifier from scene. Objects after it move down one index, keeping their identifiers.
+    /// Returns the object, or none if not in scene.
+    pub fn remove(&mut self, id: ObjectId) -> Option<Shape> {
+        let index = self.object_index(id)?;
+        let object = self.objects.remove(index);
+        self.uv_transforms.remove(index);
+        self.bindings.remove(index);
+        self.decals.retain(|(object, _)| *object != id);
+        self.ids.ids.remove(index);
+        self.lights.retain(|&light| light != index);
+        for light in &mut self.lights {
+            if *light > index {
+                *light -= 1;
+            }
+        }
+        self.bvh.bvh.take();
+        self.photons.clear();
+        Some(object)
+    }
+
+    /// Replaces object with identifier, which it keeps. Returns the previous object, or none if not in scene.
+    pub fn replace(&mut self, id: ObjectId, object: Shape) -> Option<Shape> {
+        let index = self.object_index(id)?;
+        let previous = std::mem::replace(&mut self.objects[index], object);
+        self.object_changed(index);
+        Some(previous)
+    }
+
+    /// Edits object with identifier in place, e.g. moving it or changing its transform. Returns whether the object
+    /// is in scene.
+    pub fn update(&mut self, id: ObjectId, edit: impl FnOnce(&mut Shape)) -> bool {
+        let Some(index) = self.object_index(id) else {
+            return false;
+        };
+        edit(&mut self.objects[index]);
+        self.object_changed(index);
+        true
+    }
+
+    /// Sets material of object with identifier, leaving the bounding volume hierarchy untouched. Unbinds the object
+    /// from any named material. Returns whether the object is in scene.
+    pub fn set_material(&mut self, id: ObjectId, material: Material) -> bool {
+        let Some(index) = self.object_index(id) else {
+            return false;
+        };
+        self.objects[index].set_material(material);
+        self.bindings[index] = None;
+        self.update_light(index);
+        self.photons.clear();
+        true
+    }
+
+    /// Named materials shared by objects.
+    pub fn library(&self) -> &MaterialLibrary {
+        &self.library
+    }
+
+    /// Defines named material in library, replacing any previous one of the name, which objects bound to the name
+    /// show from now on. Returns its handle.
+    pub fn define_material(&mut self, name: &str, material: Material) -> MaterialHandle {
+        let handle = self.library.insert(name, material);
+        for index in 0..self.objects.len() {
+            if self.bindings[index] == Some(handle) {
+                self.update_light(index);
+            }
+        }
+        self.photons.clear();
+        handle
+    }
+
+    /// Binds object with identifier to named material of handle, which it shows instead of its own material, also
+    /// once redefined or the object replaced, until `set_material` gives it a material of its own. Returns whether
+    /// the object is in scene.
+    pub fn bind_material(&mut self, id: ObjectId, handle: MaterialHandle) -> bool {
+        let Some(index) = self.object_index(id) else {
+            return false;
+        };
+        self.bindings[index] = Some(handle);
+        self.update_light(index);
+        self.photons.clear();
+        true
+    }
+
+    /// Handle of named material object at index is bound to, if any.
+    pub fn material_binding(&self, index: usize) -> Option<MaterialHandle> {
+        self.bindings[index]
+    }
+
+    /// Material of object at index, the named material it is bound to if any, else its own.
+    pub fn material(&self, index: usize) -> &Material {
+        match self.bindings[index] {
+            Some(handle) => self.library.get(handle),
+            None => self.objects[index].material(),
+        }
+    }
+
+    /// Updates lights, bounding volume hierarchy and photon map after object at index changed. The hierarchy is
+    /// refitted if built, or rebuilt on next use once refits degraded it too far.
+    fn object_changed(&mut self, index: usize) {
+        self.update_light(index);
+        if let Some(bvh) = self.bvh.bvh.get_mut() {
+            bvh.refit(&self.objects);
+            if bvh.degradation() > MAX_BVH_DEGRADATION {
+                self.bvh.bvh.take();
+            }
+        }
+        self.photons.clear();
+    }
+
+    /// Lists object at index among lights if emissive, else removes it.
+    fn update_light(&mut self, index: usize) {
+        self.lights.retain(|&light| light != index);
+        if self.material(index).emitted() != Color3f::black() {
+            let position = self.lights.partition_point(|&light| light < index);
+            self.lights.insert(position, index);
+        }
+    }
+
+    /// Sets maximum count of objects per leaf of the bounding volume hierarchy, four by default. Smaller leaves
+    /// speed up intersection of heavy scenes at the cost of build time and memory.
+    pub fn set_bvh_leaf_size(&mut self, max_leaf_size: usize) {
+        assert!(max_leaf_size > 0);
+        self.bvh.max_leaf_size = max_leaf_size;
+        self.bvh.bvh.take();
+    }
+
+    /// Sets whether leaves of the bounding volume hierarchy test their objects in packets of four, enabled by default.
+    /// Disabled e.g. to benchmark the speedup.
+    pub fn set_packet_intersection(&mut self, packets: bool) {
+        self.bvh.packets = packets;
+        self.bvh.bvh.take();
+    }
+
+    /// Statistics of the bounding volume hierarchy over objects, building it if not done yet.
+    pub fn bvh_stats(&self) -> BvhStats {
+        self.bvh().stats()
+    }
+
+    /// Bounding volume hierarchy over objects, built on first use after objects changed.
+    fn bvh(&self) -> &Bvh {
+        self.bvh
+            .bvh
+            .get_or_init(|| Bvh::build(&self.objects, self.bvh.max_leaf_size, self.bvh.packets))
+    }
+
+    /// Caustic photon map of scene with given count of emitted photons, max depth of photon paths and query radius.
+    /// Built on first use after lights or objects changed, or when asked for other parameters than last time.
+    pub fn photon_map(&self, emitted: u32, max_depth: u32, radius: f32) -> Arc<PhotonMap> {
+        let matches = |map: &&Arc<PhotonMap>| {
+            (map.emitted(), map.max_depth(), map.radius()) == (emitted, max_depth, radius)
+        };
+        if let Some(map) = self.photons.0.read().unwrap().as_ref().filter(matches) {
+            return map.clone();
+        }
+
+        // Build sequentially while holding the lock, as threads waiting for parallel work could pick up other
+        // rendering work asking for the map again.
+        let mut cache = self.photons.0.write().unwrap();
+        if let Some(map) = cache.as_ref().filter(matches) {
+            return map.clone();
+        }
+        let map = Arc::new(PhotonMap::build(self, emitted, max_depth, radius));
+        *cache = Some(map.clone());
+        map
+    }
+
+    /// Texture coordinate transform of object at index.
+    pub fn uv_transform(&self, index: usize) -> UvTransform {
+        self.uv_transforms[index]
+    }
+
+    /// Sets texture coordinate transform of object at index, applied to its intersections.
+    pub fn set_uv_transform(&mut self, index: usize, uv_transform: UvTransform) {
+        self.uv_transforms[index] = uv_transform;
+        self.photons.clear();
+    }
+
+    /// Attaches decal to object with identifier, on top of previously added decals. The decal is placed in the local
+    /// space of the object, see `Shape::frame`, and removed along with it. Returns whether the object is in scene.
+    pub fn add_decal(&mut self, id: ObjectId, decal: Decal) -> bool {
+        if self.object_index(id).is_none() {
+            return false;
+        }
+        self.decals.push((id, decal));
+        self.photons.clear();
+        true
+    }
+
+    /// Decals in scene with the objects they are attached to.
+    pub fn decals(&self) -> &[(ObjectId, Decal)] {
+        &self.decals
+    }
+
+    /// Stochastically selects material of topmost decal of object at index covering point in world space. Returns
+    /// none if object material shows through.
+    pub fn decal_material(
+        &self,
+        index: usize,
+        point: Point3f,
+        rng: &mut impl Rng,
+    ) -> Option<Material> {
+        let id = self.ids.ids[index];
+        let mut decals = self
+            .decals
+            .iter()
+            .rev()
+            .filter(|(object, _)| *object == id)
+            .peekable();
+        decals.peek()?;
+        let local = self.objects[index].frame().inverse()?.point(point);
+        decals.find_map(|(_, decal)| decal.material_at(local, rng))
+    }
+
+    /// Material at intersection of object at index, either of decal covering intersection point or of object.
+    pub fn surface_material<'a>(
+        &self,
+        index: usize,
+        isect: &Intersection<'a>,
+        rng: &mut impl Rng,
+    ) -> Cow<'a, Material> {
+        match self.decal_material(index, isect.point, rng) {
+            Some(material) => Cow::Owned(material),
+            None => Cow::Borrowed(isect.material),
+        }
+    }
+
+    /// Objects in scene.
+    pub fn objects(&self) -> &[Shape] {
+        &self.objects
+    }
+
+    /// Identifier of object at index.
+    pub fn object_id(&self, index: usize) -> ObjectId {
+        self.ids.ids[index]
+    }
+
+    /// Index of object with identifier, if in scene.
+    pub fn object_index(&self, id: ObjectId) -> Option<usize> {
+        // Identifiers ascend with indices, as objects are only appended.
+        self.ids.ids.binary_search(&id).ok()
+    }
+
+    /// Indices of emissive objects.
+    pub fn lights(&self) -> &[usize] {
+        &self.lights
+    }
+
+    /// Adds analytic light to scene, lighting surfaces in integrators that sample lights explicitly.
+    pub fn add_light(&mut self, light: Light) {
+        self.analytic_lights.push(light);
+        self.photons.clear();
+    }
+
+    /// Analytic lights in scene.
+    pub fn analytic_lights(&self) -> &[Light] {
+        &self.analytic_lights
+    }
+
+    /// Unoccluded light of all analytic lights arriving at intersection, one shadow ray per light.
+    pub fn illumination(&self, isect: &Intersection) -> Vec<Illumination> {
+        self.analytic_lights
+            .iter()
+            .filter_map(|light| light.illuminate(isect.point))
+            .filter(|i| {
+                let shadow_ray = isect.spawn_ray(i.direction);
+                let end = i.distance * (1.0 - SHADOW_EPSILON);
+                self.intersect(shadow_ray, Interval::new(0.0, end))
+                    .is_none()
+            })
+            .collect()
+    }
+
+    /// Probability density w.r.t. solid angle at reference point of sampling given point on object by picking a
+    /// light uniformly and sampling it. Zero if object is no light.
+    pub fn light_pdf(
+        &self,
+        object: usize,
+        reference: Point3f,
+        point: Point3f,
+        normal: Vector3f,
+    ) -> f32 {
+        if !self.lights.contains(&object) {
+            return 0.0;
+        }
+        self.objects[object].pdf_from(reference, point, normal) / self.light_count() as f32
+    }
+
+    /// Samples point on light chosen uniformly, as seen from intersection. The sun of a physical sky counts as one
+    /// more light. Returns none if there are no lights or the sample is occluded.
+    pub fn sample_light(&self, isect: &Intersection, rng: &mut impl Rng) -> Option<LightSample> {
+        let count = self.light_count();
+        if count == 0 {
+            return None;
+        }
+        let index = rng.gen_range(0..count);
+        if index == self.lights.len() {
+            return self.sample_sun(isect, rng);
+        }
+
+        // Sample point on light.
+        let light = &self.objects[self.lights[index]];
+        let emission = self.material(self.lights[index]).emitted();
+        let sample = light.sample_from(isect.point, rng)?;
+        let direction = (sample.point - isect.point).normalize();
+
+        // Shadow ray.
+        let shadow_ray = isect.spawn_ray(direction);
+        let end = (sample.point - shadow_ray.origin()).length() * (1.0 - SHADOW_EPSILON);
+        if self
+            .intersect(shadow_ray, Interval::new(0.0, end))
+            .is_some()
+        {
+            return None;
+        }
+
+        // Return light sample struct.
+        let light_sample = LightSample {
+            direction,
+            emission,
+            pdf: sample.pdf / count as f32,
+        };
+        Some(light_sample)
+    }
+
+    /// Count of lights picked from by `sample_light`, i.e. emissive objects plus the sun of a physical sky.
+    fn light_count(&self) -> usize {
+        self.lights.len() + usize::from(self.sun().is_some())
+    }
+
+    /// Physical sky of background with sun above the horizon, if any, with sun intensity scaled by environment
+    /// intensity.
+    pub fn sun(&self) -> Option<PhysicalSky> {
+        let Background::PhysicalSky(mut sky) = self.background else {
+            return None;
+        };
+        sky.set_sun_intensity(sky.sun_intensity() * self.environment_intensity.0);
+        Some(sky).filter(|sky| sky.sun_radiance() != Color3f::black())
+    }
+
+    /// Probability density w.r.t. solid angle of `sample_light` sampling direction within the sun disk, zero outside
+    /// or without sun.
+    pub fn sun_pdf(&self, direction: Vector3f) -> f32 {
+        let Some(sky) = self.sun() else {
+            return 0.0;
+        };
+        let direction = self
+            .orientation
+            .conversion(Orientation::Y_UP)
+            .vector(direction);
+        sky.sun_pdf(direction) / self.light_count() as f32
+    }
+
+    /// Samples direction within sun disk from intersection, see `sample_light`.
+    fn sample_sun(&self, isect: &Intersection, rng: &mut impl Rng) -> Option<LightSample> {
+        let sky = self.sun()?;
+        let (direction, pdf) = sky.sample_sun(rng)?;
+        let direction = Orientation::Y_UP
+            .conversion(self.orientation)
+            .vector(direction);
+        let shadow_ray = isect.spawn_ray(direction);
+        if self
+            .intersect(shadow_ray, Interval::new(0.0, f32::INFINITY))
+            .is_some()
+        {
+            return None;
+        }
+        Some(LightSample {
+            direction,
+            emission: sky.sun_radiance(),
+            pdf: pdf / self.light_count() as f32,
+        })
+    }
+
+    /// Intersects ray with closest object in given t-interval. Returns object index and intersection if exists, with
+    /// texture coordinates transformed by the object's UV transform and the named material it is bound to, if any.
+    pub fn intersect_object(&self, ray: Ray, ray_t: Interval) -> Option<(usize, Intersection<'_>)> {
+        let intersection = self.bvh().intersect(&self.objects, ray, ray_t);
+        intersection.map(|(index, mut i)| {
+            i.uv = self.uv_transforms[index].apply(i.uv);
+            if let Some(handle) = self.bindings[index] {
+                i.material = self.library.get(handle);
+            }
+            (index, i)
+        })
+    }
+
+    /// Casts ray from origin along direction, which need not be unit length, up to max distance. Returns nearest
+    /// hit if exists, for queries without rendering, e.g. collision checks. Origins on surfaces may hit them.
+    pub fn raycast(
+        &self,
+        origin: Point3f,
+        direction: Vector3f,
+        max_distance: f32,
+    ) -> Option<RayHit> {
+        let ray = Ray::new(origin, direction.normalize());
+        let (object, i) = self.intersect_object(ray, Interval::new(0.0, max_distance))?;
+        Some(RayHit {
+            object,
+            distance: i.t,
+            point: i.point,
+            normal: i.normal,
+        })
+    }
+
+    /// Nearest object hit by ray, with its identifier and surface at the hit, e.g. to select objects clicked in a
+    /// viewport. See `Camera::pick_ray` for rays through image points.
+    pub fn pick(&self, ray: Ray) -> Option<Pick<'_>> {
+        let (object, i) = self.intersect_object(ray, Interval::new(0.0, f32::INFINITY))?;
+        Some(Pick {
+            id: self.ids.ids[object],
+            object,
+            distance: i.t * ray.direction().length(),
+            point: i.point,
+            normal: i.normal,
+            uv: i.uv,
+            material: i.material,
+        })
+    }
+
+    /// Whether no object lies between points a and b, e.g. for visibility or occlusion checks. Objects at the points
+    /// themselves do not block, so points may lie on surfaces.
+    pub fn line_of_sight(&self, a: Point3f, b: Point3f) -> bool {
+        // Spawn off both points along the line, beyond the rounding error of surface points of their magnitude.
+        let error = |p: Point3f| gamma(6) * (p - Point3f::default()).abs();
+        let direction = (b - a).normalize();
+        let ray = Ray::spawn(a, error(a), direction, direction);
+        let end = Ray::spawn(b, error(b), -direction, -direction).origin();
+        let distance = (end - ray.origin()).dot(&direction);
+        distance <= 0.0 || self.intersect(ray, Interval::new(0.0, distance)).is_none()
+    }
+
+    /// Scene background.
+    pub fn background(&self) -> Background {
+        self.background
+    }
+
+    /// Sets scene background.
+    pub fn set_background(&mut self, background: Background) {
+        self.background = background;
+        self.photons.clear();
+    }
+
+    /// Background radiance seen along ray times environment intensity, with the background's up direction along the
+    /// up axis of the scene.
+    pub fn background_color(&self, ray: Ray) -> Color3f {
+        let conversion = self.orientation.conversion(Orientation::Y_UP);
+        let direction = conversion.vector(ray.direction());
+        self.background.color(Ray::new(ray.origin(), direction)) * self.environment_intensity.0
+    }
+
+    /// Factor on background radiance, including the sun of a physical sky, one by default.
+    pub fn environment_intensity(&self) -> f32 {
+        self.environment_intensity.0
+    }
+
+    /// Sets factor on background radiance, including the sun of a physical sky. Emissive objects and analytic
+    /// lights keep their radiance, so environment and artificial lights can be balanced numerically.
+    pub fn set_environment_intensity(&mut self, environment_intensity: f32) {
+        assert!(environment_intensity >= 0.0 && environment_intensity.is_finite());
+        self.environment_intensity = EnvironmentIntensity(environment_intensity);
+        self.photons.clear();
+    }
+
+    /// Sets environment intensity so that the median luminance of the background over all directions becomes the
+    /// given luminance, e.g. that of the scene's emissive objects. The median ignores small bright regions like the
+    /// sun disk. Backgrounds which are black in most directions are left unchanged.
+    pub fn set_environment_luminance(&mut self, luminance: f32) {
+        // Luminance histogram over directions evenly spread on the sphere (Fibonacci lattice).
+        let golden_angle = PI * (3.0 - 5f32.sqrt());
+        let mut luminances: Vec<f32> = (0..ENVIRONMENT_SAMPLES)
+            .map(|i| {
+                let y = 1.0 - (2 * i + 1) as f32 / ENVIRONMENT_SAMPLES as f32;
+                let r = (1.0 - y * y).sqrt();
+                let phi = golden_angle * i as f32;
+                let direction = Vector3f::new(r * phi.cos(), y, r * phi.sin());
+                let ray = Ray::new(Point3f::default(), direction);
+                self.background.color(ray).luminance()
+            })
+            .collect();
+        luminances.sort_by(f32::total_cmp);
+        let median = luminances[ENVIRONMENT_SAMPLES / 2];
+        if median > 0.0 {
+            self.set_environment_intensity(luminance / median);
+        }
+    }
+
+    /// Convention of world coordinate axes.
+    pub fn orientation(&self) -> Orientation {
+        self.orientation
+    }
+
+    /// Sets convention of world coordinate axes, which backgrounds and importers follow. Existing objects keep
+    /// their coordinates, and shape presets stay y-up.
+    pub fn set_orientation(&mut self, orientation: Orientation) {
+        self.orientation = orientation;
+        self.photons.clear();
+    }
+
+    /// Stable content hash of scene. Changes whenever any object or the background changes.
+    pub fn content_hash(&self) -> u64 {
+        stable_hash(self)
+    }
+
+    /// Compares scene content with other scene. Objects are matched by content hash including their UV transform,
+    /// thus a modified object is reported as removed and added.
+    pub fn diff(&self, other: &Scene) -> SceneDiff {
+        // Count unmatched objects of other scene by hash.
+        let mut unmatched: HashMap<u64, Vec<usize>> = HashMap::new();
+        for (i, object) in other.objects.iter().enumerate().rev() {
+            let key = stable_hash(&(object, other.uv_transforms[i]));
+            unmatched.entry(key).or_default().push(i);
+        }
+
+        // Match objects of this scene.
+        let mut removed = Vec::new();
+        for (i, object) in self.objects.iter().enumerate() {
+            let matched = unmatched
+                .get_mut(&stable_hash(&(object, self.uv_transforms[i])))
+                .and_then(|v| v.pop());
+            if matched.is_none() {
+                removed.push(i);
             }
         }
+        let mut added: Vec<usize> = unmatched.into_values().flatten().collect();
+        added.sort_unstable();
+
+        SceneDiff {
+            removed,
+            added,
+            background_changed: self.background != other.background
+                || self.environment_intensity != other.environment_intensity
+                || self.orientation != other.orientation,
+            decals_changed: self.decals != other.decals,
+            lights_changed: self.analytic_lights != other.analytic_lights,
+        }
+    }
+}
+
+impl Default for Scene {
+    /// Default empty scene.
+    fn default() -> Self {
+        Scene::new()
+    }
+}
+
+impl Intersectable for Scene {
+    fn intersect(&self, ray: Ray, ray_t: Interval) -> Option<Intersection<'_>> {
+        self.intersect_object(ray, ray_t).map(|(_, i)| i)
+    }
+}
+
+/// Factor on background radiance, hashed by value.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct EnvironmentIntensity(f32);
+
+impl Hash for EnvironmentIntensity {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        hash_float(self.0, state);
+    }
+}
+
+/// Lazily built bounding volume hierarchy of scene. Derived from objects, thus not part of scene content.
+struct SceneBvh {
+    /// Maximum count of objects per leaf.
+    max_leaf_size: usize,
+
+    /// Whether leaves test objects in packets.
+    packets: bool,
+
+    /// Hierarchy, if built since objects last changed.
+    bvh: OnceLock<Bvh>,
+}
+
+impl Hash for SceneBvh {
+    /// Hashes nothing, as scene content is hashed by objects.
+    fn hash<H: Hasher>(&self, _state: &mut H) {}
+}
+
+/// Caustic photon map built for the scene, if any.
+#[derive(Debug, Default)]
+struct PhotonCache(RwLock<Option<Arc<PhotonMap>>>);
+
+impl PhotonCache {
+    /// Drops photon map, after scene content changed.
+    fn clear(&mut self) {
+        *self.0.get_mut().unwrap() = None;
+    }
+}
+
+impl Hash for PhotonCache {
+    /// Hashes nothing, as photon maps follow from scene content.
+    fn hash<H: Hasher>(&self, _state: &mut H) {}
+}
+
+/// Struct holding unoccluded light sample properties.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct LightSample {
+    /// Unit direction from reference point towards light.
+    pub direction: Vector3f,
+
+    /// Radiance emitted towards reference point.
+    pub emission: Color3f,
+
+    /// Probability density of sample w.r.t. solid angle, including choice of light.
+    pub pdf: f32,
+}
+
+/// Nearest hit of a ray cast into scene.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct RayHit {
+    /// Index of hit object.
+    pub object: usize,
+
+    /// Distance from ray origin to hit point.
+    pub distance: f32,
+
+    /// Hit point.
+    pub point: Point3f,
+
+    /// Unit surface normal at hit point, pointing outwards.
+    pub normal: Vector3f,
+}
+
+/// Stable identifier of object in scene, kept while the object is replaced, e.g. by animation.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct ObjectId(u64);
+
+impl ObjectId {
+    /// Identifier as number, unique within its scene.
+    pub fn value(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Identifiers of objects by index, and the next one to assign.
+#[derive(Debug, Default)]
+struct ObjectIds {
+    /// Identifiers by object index.
+    ids: Vec<ObjectId>,
+
+    /// Next identifier assigned.
+    next: u64,
+}
 
-        intersection
+impl Hash for ObjectIds {
+    /// Hashes nothing, as identifiers are no scene content.
+    fn hash<H: Hasher>(&self, _state: &mut H) {}
+}
+
+/// Object hit by a pick ray.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Pick<'a> {
+    /// Identifier of hit object.
+    pub id: ObjectId,
+
+    /// Index of hit object.
+    pub object: usize,
+
+    /// Distance from ray origin to hit point.
+    pub distance: f32,
+
+    /// Hit point.
+    pub point: Point3f,
+
+    /// Unit surface normal at hit point, pointing outwards.
+    pub normal: Vector3f,
+
+    /// Texture coordinates at hit point, transformed by the object's UV transform.
+    pub uv: (f32, f32),
+
+    /// Material of hit object, not of decals over it.
+    pub material: &'a Material,
+}
+
+/// Content differences between two scenes.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SceneDiff {
+    /// Indices of objects only present in old scene.
+    pub removed: Vec<usize>,
+
+    /// Indices of objects only present in new scene.
+    pub added: Vec<usize>,
+
+    /// Whether background, its intensity or orientation, which turns the background, differs.
+    pub background_changed: bool,
+
+    /// Whether decals differ.
+    pub decals_changed: bool,
+
+    /// Whether analytic lights differ.
+    pub lights_changed: bool,
+}
+
+impl SceneDiff {
+    /// Whether scenes have identical content.
+    pub fn is_empty(&self) -> bool {
+        self.removed.is_empty()
+            && self.added.is_empty()
+            && !self.background_changed
+            && !self.decals_changed
+            && !self.lights_changed
     }
 }
 
@@ -46,9 +836,13 @@ impl Intersectable for Scene {
 mod tests {
     use super::*;
     use crate::{
-        base::{material::Material, point::Point3f, vector::Vector3f},
+        base::{color::Color3f, material::Material, point::Point3f, vector::Vector3f},
+        image::RgbaImage,
+        materials::{diffuse_light::DiffuseLight, lambert::Lambert},
         shapes::sphere::Sphere,
     };
+    use rand::{rngs::StdRng, SeedableRng};
+    use std::sync::Arc;
 
     #[test]
     fn intersect() {
@@ -56,9 +850,9 @@ mod tests {
         let s1 = Sphere::new(Point3f::new(2.0, 0.0, 0.0), 1.0, Material::None);
         let s2 = Sphere::new(Point3f::new(8.0, 0.0, 0.0), 1.0, Material::None);
         let s3 = Sphere::new(Point3f::new(5.0, 0.0, 0.0), 1.0, Material::None);
-        scene.add(Shape::Sphere(s1));
-        scene.add(Shape::Sphere(s2));
-        scene.add(Shape::Sphere(s3));
+        scene.add(Shape::Sphere(s1.clone()));
+        scene.add(Shape::Sphere(s2.clone()));
+        scene.add(Shape::Sphere(s3.clone()));
 
         let r1 = Ray::new(Point3f::default(), Vector3f::new(1.0, 0.0, 0.0));
         let i1 = Interval::new(0.0, 10.0);
@@ -73,7 +867,407 @@ mod tests {
         assert_eq!(scene.intersect(r1, i5), None);
 
         let s4 = Sphere::new(Point3f::new(7.9, 0.0, 0.0), 1.0, Material::None);
-        scene.add(Shape::Sphere(s4));
+        scene.add(Shape::Sphere(s4.clone()));
         assert_eq!(scene.intersect(r1, i4), s4.intersect(r1, i4));
     }
+
+    #[test]
+    fn raycast() {
+        let mut scene = Scene::new();
+        scene.add(Shape::Sphere(Sphere::new(
+            Point3f::new(2.0, 0.0, 0.0),
+            1.0,
+            Material::None,
+        )));
+        scene.add(Shape::Sphere(Sphere::new(
+            Point3f::new(5.0, 0.0, 0.0),
+            1.0,
+            Material::None,
+        )));
+
+        // Nearest hit within distance, along direction of any length.
+        let origin = Point3f::new(8.0, 0.0, 0.0);
+        let hit = scene
+            .raycast(origin, Vector3f::new(-2.0, 0.0, 0.0), 10.0)
+            .unwrap();
+        assert_eq!(hit.object, 1);
+        assert!((hit.distance - 2.0).abs() < 1e-5);
+        assert_eq!(hit.point, Point3f::new(6.0, 0.0, 0.0));
+        assert_eq!(hit.normal, Vector3f::new(1.0, 0.0, 0.0));
+        assert_eq!(
+            scene.raycast(origin, Vector3f::new(-1.0, 0.0, 0.0), 1.5),
+            None
+        );
+        assert_eq!(
+            scene.raycast(origin, Vector3f::new(0.0, 1.0, 0.0), 10.0),
+            None
+        );
+
+        // Spheres block sight between points on either side, not between points on their surfaces.
+        assert!(!scene.line_of_sight(Point3f::default(), origin));
+        assert!(scene.line_of_sight(Point3f::new(3.0, 0.0, 0.0), Point3f::new(4.0, 0.0, 0.0)));
+        assert!(scene.line_of_sight(Point3f::new(0.0, 2.0, 0.0), Point3f::new(8.0, 2.0, 0.0)));
+
+        // Error bounds scale with the scene, so tiny objects block and points on huge ones see off their surfaces.
+        let mut scene = Scene::new();
+        scene.add(Shape::Sphere(Sphere::new(
+            Point3f::default(),
+            1e-4,
+            Material::None,
+        )));
+        assert!(!scene.line_of_sight(Point3f::new(-2e-4, 0.0, 0.0), Point3f::new(2e-4, 0.0, 0.0)));
+        let huge = Sphere::new(Point3f::new(0.0, -1e5, 0.0), 1e5, Material::None);
+        let mut scene = Scene::new();
+        scene.add(Shape::Sphere(huge.clone()));
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..100 {
+            let sample = huge.sample_point(&mut rng);
+            assert!(scene.line_of_sight(sample.point, sample.point + sample.normal));
+        }
+    }
+
+    #[test]
+    fn pick() {
+        let mut scene = Scene::new();
+        let white = Material::Lambert(Lambert::new(Color3f::white()));
+        let a = scene.add(Shape::Sphere(Sphere::new(
+            Point3f::new(2.0, 0.0, 0.0),
+            1.0,
+            white.clone(),
+        )));
+        let b = scene.add(Shape::Sphere(Sphere::new(
+            Point3f::new(5.0, 0.0, 0.0),
+            1.0,
+            Material::None,
+        )));
+        assert_ne!(a, b);
+        assert_eq!((scene.object_id(0), scene.object_id(1)), (a, b));
+        assert_eq!(scene.object_index(b), Some(1));
+
+        // Nearest hit with surface data, distance along direction of any length.
+        let ray = Ray::new(Point3f::default(), Vector3f::new(2.0, 0.0, 0.0));
+        let pick = scene.pick(ray).unwrap();
+        assert_eq!((pick.id, pick.object), (a, 0));
+        assert!((pick.distance - 1.0).abs() < 1e-5);
+        assert_eq!(pick.point, Point3f::new(1.0, 0.0, 0.0));
+        assert_eq!(pick.normal, Vector3f::new(-1.0, 0.0, 0.0));
+        assert_eq!(pick.material, &white);
+        assert_eq!(
+            scene.pick(Ray::new(Point3f::default(), Vector3f::new(0.0, 1.0, 0.0))),
+            None
+        );
+
+        // Replaced objects keep their identifier, identifiers of other scenes are not found.
+        scene.set_object(
+            0,
+            Shape::Sphere(Sphere::new(Point3f::new(3.0, 0.0, 0.0), 1.0, white)),
+        );
+        assert_eq!(scene.pick(ray).unwrap().id, a);
+        let mut other = Scene::new();
+        other.add(Shape::Sphere(Sphere::new(
+            Point3f::default(),
+            1.0,
+            Material::None,
+        )));
+        assert_eq!(other.object_index(b), None);
+    }
+
+    #[test]
+    fn uv_transform() {
+        let mut scene = Scene::new();
+        let sphere = Sphere::new(Point3f::default(), 1.0, Material::None);
+        scene.add(Shape::Sphere(sphere.clone()));
+        let ray = Ray::new(Point3f::new(0.3, 0.2, 5.0), Vector3f::new(0.0, 0.0, -1.0));
+        let ray_t = Interval::new(0.0, 10.0);
+        let uv = sphere.intersect(ray, ray_t).unwrap().uv;
+        assert_eq!(scene.intersect(ray, ray_t).unwrap().uv, uv);
+
+        // Hits carry transformed coordinates, transformed objects differ.
+        let mut before = Scene::new();
+        before.add(Shape::Sphere(sphere));
+        let transform = UvTransform::new((1.0, 1.0), (0.25, 0.0), 0.0);
+        scene.set_uv_transform(0, transform);
+        assert_eq!(scene.uv_transform(0), transform);
+        assert_eq!(scene.intersect(ray, ray_t).unwrap().uv, transform.apply(uv));
+        assert_eq!(scene.diff(&before).removed, vec![0]);
+    }
+
+    #[test]
+    fn lights() {
+        let mut scene = Scene::new();
+        let light = Material::DiffuseLight(DiffuseLight::new(Color3f::white()));
+        scene.add(Shape::Sphere(Sphere::new(
+            Point3f::new(0.0, 4.0, 0.0),
+            1.0,
+            light.clone(),
+        )));
+        scene.add(Shape::Sphere(Sphere::new(
+            Point3f::default(),
+            1.0,
+            Material::None,
+        )));
+        scene.add(Shape::Sphere(Sphere::new(
+            Point3f::new(0.0, -4.0, 0.0),
+            1.0,
+            light.clone(),
+        )));
+        assert_eq!(scene.lights(), [0, 2]);
+
+        // Density is halved by choice among two lights.
+        let reference = Point3f::default();
+        let (point, normal) = (Point3f::new(0.0, 3.0, 0.0), Vector3f::new(0.0, -1.0, 0.0));
+        let pdf = scene.objects()[0].pdf_from(reference, point, normal);
+        assert_eq!(scene.light_pdf(0, reference, point, normal), pdf / 2.0);
+        assert_eq!(scene.light_pdf(1, reference, point, normal), 0.0);
+
+        // Replaced objects update lights.
+        let mut object = scene.objects()[1].clone();
+        object.set_material(light);
+        scene.set_object(1, object.clone());
+        assert_eq!(scene.lights(), [0, 1, 2]);
+        object.set_material(Material::None);
+        scene.set_object(0, object);
+        assert_eq!(scene.lights(), [1, 2]);
+    }
+
+    #[test]
+    fn edit() {
+        let mut scene = Scene::new();
+        let light = Material::DiffuseLight(DiffuseLight::new(Color3f::white()));
+        let sphere = |x: f32, material: &Material| {
+            Shape::Sphere(Sphere::new(
+                Point3f::new(x, 0.0, 0.0),
+                1.0,
+                material.clone(),
+            ))
+        };
+        let a = scene.add(sphere(2.0, &Material::None));
+        let b = scene.add(sphere(5.0, &light));
+        let c = scene.add(sphere(8.0, &light));
+        let ray = Ray::new(Point3f::default(), Vector3f::new(1.0, 0.0, 0.0));
+        assert_eq!(scene.pick(ray).unwrap().id, a);
+
+        // Moved objects are found where they are now.
+        assert!(scene.update(a, |object| object.set_center(Point3f::new(0.0, 5.0, 0.0))));
+        assert_eq!(scene.pick(ray).unwrap().id, b);
+        assert!(scene.update(c, |object| object.set_center(Point3f::new(3.0, 0.0, 0.0))));
+        assert_eq!(scene.pick(ray).unwrap().id, c);
+
+        // Removal moves later objects and lights down, keeping identifiers.
+        let removed = scene.remove(a).unwrap();
+        assert_eq!(removed.center(), Point3f::new(0.0, 5.0, 0.0));
+        assert_eq!(scene.remove(a), None);
+        assert!(!scene.update(a, |_| {}));
+        assert_eq!(
+            (scene.object_index(b), scene.object_index(c)),
+            (Some(0), Some(1))
+        );
+        assert_eq!(scene.lights(), [0, 1]);
+        let pick = scene.pick(ray).unwrap();
+        assert_eq!((pick.id, pick.object), (c, 1));
+
+        // Materials and replacements update lights.
+        assert!(scene.set_material(c, Material::None));
+        assert_eq!(scene.lights(), [0]);
+        assert_eq!(
+            scene.replace(b, sphere(5.0, &Material::None)),
+            Some(sphere(5.0, &light))
+        );
+        assert!(scene.lights().is_empty());
+        assert_eq!(scene.objects().len(), 2);
+    }
+
+    #[test]
+    fn library() {
+        let mut scene = Scene::new();
+        let sphere =
+            |x: f32| Shape::Sphere(Sphere::new(Point3f::new(x, 0.0, 0.0), 1.0, Material::None));
+        let (a, b, c) = (
+            scene.add(sphere(0.0)),
+            scene.add(sphere(3.0)),
+            scene.add(sphere(6.0)),
+        );
+        let gold = scene.define_material("gold", Material::preset("gold").unwrap());
+        assert!(scene.bind_material(a, gold) && scene.bind_material(c, gold));
+        assert_eq!(scene.library().handle("gold"), Some(gold));
+        assert_eq!(scene.library().name(gold), "gold");
+
+        // Redefinition updates all bound objects, and lights if it emits, without touching their own materials.
+        let light = Material::DiffuseLight(DiffuseLight::new(Color3f::white()));
+        assert_eq!(scene.define_material("gold", light.clone()), gold);
+        assert_eq!(scene.library().len(), 1);
+        assert_eq!(*scene.material(0), light);
+        assert_eq!(*scene.material(1), Material::None);
+        assert_eq!(*scene.material(2), light);
+        assert_eq!(*scene.objects()[0].material(), Material::None);
+        assert_eq!(scene.lights(), [0, 2]);
+        let ray = Ray::new(Point3f::new(6.0, 0.0, 5.0), Vector3f::new(0.0, 0.0, -1.0));
+        let (index, i) = scene
+            .intersect_object(ray, Interval::new(0.0, f32::INFINITY))
+            .unwrap();
+        assert_eq!((index, i.material), (2, &light));
+        assert!(std::ptr::eq(i.material, scene.library().get(gold)));
+
+        // Replacements keep bindings, own materials and removal drop them.
+        scene.set_object(0, sphere(1.0));
+        assert_eq!(*scene.material(0), light);
+        assert!(scene.set_material(a, Material::None));
+        assert_eq!(*scene.material(0), Material::None);
+        assert_eq!(scene.material_binding(0), None);
+        assert!(scene.remove(b).is_some());
+        assert_eq!(
+            scene.material_binding(scene.object_index(c).unwrap()),
+            Some(gold)
+        );
+        scene.define_material("gold", Material::None);
+        assert!(scene.lights().is_empty());
+    }
+
+    #[test]
+    fn diff() {
+        let s1 = Sphere::new(Point3f::new(2.0, 0.0, 0.0), 1.0, Material::None);
+        let s2 = Sphere::new(Point3f::new(8.0, 0.0, 0.0), 1.0, Material::None);
+        let s3 = Sphere::new(Point3f::new(5.0, 0.0, 0.0), 1.0, Material::None);
+
+        let mut a = Scene::new();
+        a.add(Shape::Sphere(s1.clone()));
+        a.add(Shape::Sphere(s2.clone()));
+        let mut b = Scene::new();
+        b.add(Shape::Sphere(s2));
+        b.add(Shape::Sphere(s1.clone()));
+        assert!(a.diff(&b).is_empty());
+        assert_ne!(a.content_hash(), b.content_hash());
+
+        b.add(Shape::Sphere(s3));
+        b.add(Shape::Sphere(s1));
+        let d = a.diff(&b);
+        assert_eq!(d.removed, Vec::<usize>::new());
+        assert_eq!(d.added, vec![2, 3]);
+        assert_eq!(b.diff(&a).removed, vec![2, 3]);
+
+        b.set_background(Background::Uniform(Color3f::white()));
+        assert!(b.diff(&a).background_changed);
+    }
+
+    #[test]
+    fn orientation() {
+        // Sky is above along up axis of scene.
+        let mut scene = Scene::new();
+        let up = Ray::new(Point3f::default(), Vector3f::new(0.0, 0.0, 1.0));
+        let sky = Background::Sky.color(Ray::new(Point3f::default(), Vector3f::new(0.0, 1.0, 0.0)));
+        assert_ne!(scene.background_color(up), sky);
+        scene.set_orientation(Orientation::Z_UP);
+        assert_eq!(scene.background_color(up), sky);
+        assert!(scene.diff(&Scene::new()).background_changed);
+    }
+
+    #[test]
+    fn environment_intensity() {
+        // Background and sun scale, emitters keep their radiance.
+        let mut scene = Scene::new();
+        let sky = PhysicalSky::new(Vector3f::new(1.0, 1.0, 0.0), 3.0);
+        scene.set_background(Background::PhysicalSky(sky));
+        let light = Material::DiffuseLight(DiffuseLight::new(Color3f::new(4.0, 4.0, 4.0)));
+        scene.add(Shape::Sphere(Sphere::new(
+            Point3f::new(0.0, -2.0, 0.0),
+            1.0,
+            light,
+        )));
+        let up = Ray::new(Point3f::default(), Vector3f::new(0.0, 1.0, 0.0));
+        let color = scene.background_color(up);
+        scene.set_environment_intensity(0.25);
+        assert_eq!(scene.background_color(up), color * 0.25);
+        assert_eq!(
+            scene.sun().unwrap().sun_radiance(),
+            sky.sun_radiance() * 0.25
+        );
+        assert!(scene.diff(&Scene::new()).background_changed);
+        assert_ne!(scene.content_hash(), {
+            let mut other = Scene::new();
+            other.set_background(Background::PhysicalSky(sky));
+            other.add(scene.objects()[0].clone());
+            other.content_hash()
+        });
+        scene.set_environment_intensity(0.0);
+        assert!(scene.sun().is_none());
+
+        // Median luminance of uniform and gradient backgrounds.
+        scene.set_background(Background::Uniform(Color3f::new(2.0, 2.0, 2.0)));
+        scene.set_environment_luminance(0.5);
+        assert!((scene.environment_intensity() - 0.25).abs() < 1e-6);
+        scene.set_background(Background::Gradient {
+            bottom: Color3f::black(),
+            top: Color3f::white(),
+        });
+        scene.set_environment_luminance(1.0);
+        assert!((scene.environment_intensity() - 2.0).abs() < 0.01);
+        scene.set_background(Background::Uniform(Color3f::black()));
+        scene.set_environment_luminance(1.0);
+        assert!((scene.environment_intensity() - 2.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn content_hash() {
+        let build = |radius: f32| {
+            let mut scene = Scene::new();
+            let material = Material::Lambert(Lambert::new(Color3f::new(0.5, 0.5, 0.5)));
+            scene.add(Shape::Sphere(Sphere::new(
+                Point3f::default(),
+                radius,
+                material,
+            )));
+            scene
+        };
+        assert_eq!(build(1.0).content_hash(), build(1.0).content_hash());
+        assert_ne!(build(1.0).content_hash(), build(1.1).content_hash());
+    }
+
+    #[test]
+    fn decal_material() {
+        let decal = |color: Color3f, alpha: f32| {
+            let image = RgbaImage::new(1, 1, vec![color], vec![alpha]);
+            let (u, v) = (Vector3f::new(1.0, 0.0, 0.0), Vector3f::new(0.0, 1.0, 0.0));
+            Decal::new(Point3f::new(0.0, 0.0, 1.0), u, v, 1.0, Arc::new(image))
+        };
+        let mut rng = StdRng::seed_from_u64(42);
+        let mut scene = Scene::new();
+        let sphere =
+            |x: f32| Shape::Sphere(Sphere::new(Point3f::new(x, 0.0, 0.0), 1.0, Material::None));
+        let (a, b) = (scene.add(sphere(0.0)), scene.add(sphere(0.0)));
+        let front = Point3f::new(0.0, 0.0, 1.0);
+        assert_eq!(scene.decal_material(0, front, &mut rng), None);
+
+        // Topmost opaque decal of object wins, others' decals do not show.
+        let red = Color3f::new(1.0, 0.0, 0.0);
+        let blue = Color3f::new(0.0, 0.0, 1.0);
+        assert!(scene.add_decal(a, decal(red, 1.0)));
+        assert!(scene.add_decal(a, decal(blue, 1.0)));
+        let expected = Material::Lambert(Lambert::new(blue));
+        assert_eq!(
+            scene.decal_material(0, front, &mut rng),
+            Some(expected.clone())
+        );
+        assert_eq!(
+            scene.decal_material(0, Point3f::new(0.0, 0.0, -1.0), &mut rng),
+            None
+        );
+        assert_eq!(scene.decal_material(1, front, &mut rng), None);
+
+        // Transparent decal shows decal below.
+        scene.add_decal(a, decal(Color3f::white(), 0.0));
+        assert_eq!(
+            scene.decal_material(0, front, &mut rng),
+            Some(expected.clone())
+        );
+
+        // Decals follow their object as it moves, and are removed with it.
+        assert!(scene.update(a, |object| object.set_center(Point3f::new(5.0, 0.0, 0.0))));
+        assert_eq!(scene.decal_material(0, front, &mut rng), None);
+        let moved = Point3f::new(5.0, 0.0, 1.0);
+        assert_eq!(scene.decal_material(0, moved, &mut rng), Some(expected));
+        assert!(scene.remove(a).is_some());
+        assert!(scene.decals().is_empty());
+        assert!(!scene.add_decal(a, decal(red, 1.0)));
+        assert!(scene.add_decal(b, decal(red, 1.0)));
+    }
 }
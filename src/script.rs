@@ -0,0 +1,358 @@
+use crate::{
+    base::{
+        color::Color3f, material::Material, point::Point3f, shape::Shape, uv_transform::UvTransform,
+    },
+    materials::{
+        diffuse_light::DiffuseLight, lambert::Lambert, metal::Metal, microfacet::Microfacet,
+    },
+    scene::Scene,
+};
+use rhai::{Array, CallFnOptions, Dynamic, Engine, EvalAltResult, Scope, AST, FLOAT, INT};
+use std::{error::Error, fmt, fs, io, path::Path};
+
+/// Result of script function, failing with script error.
+type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
+
+/// Name of script function called per frame.
+const ON_FRAME: &str = "on_frame";
+
+/// Error of loading or running scene script.
+#[derive(Debug)]
+pub enum ScriptError {
+    /// Script file could not be read.
+    Io(io::Error),
+
+    /// Script is malformed.
+    Syntax(String),
+
+    /// Script failed while running.
+    Runtime(String),
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScriptError::Io(e) => write!(f, "{}", e),
+            ScriptError::Syntax(message) => write!(f, "syntax error: {}", message),
+            ScriptError::Runtime(message) => write!(f, "runtime error: {}", message),
+        }
+    }
+}
+
+impl Error for ScriptError {}
+
+impl From<io::Error> for ScriptError {
+    fn from(e: io::Error) -> Self {
+        ScriptError::Io(e)
+    }
+}
+
+/// Rhai script animating scene objects. The script defines `fn on_frame(t)`, called with the frame time in seconds
+/// before each frame is rendered, with the scene objects bound to `this`:
+///
+/// - `this.count()`: number of objects,
+/// - `this.center(i)`: center position of object i as array `[x, y, z]`,
+/// - `this.set_center(i, x, y, z)`: moves object i,
+/// - `this.set_color(i, r, g, b)`: sets albedo (lambert, metal) or emission (light) of material of object i,
+/// - `this.set_temperature(i, kelvin, luminance)`: sets emission of light object i to blackbody color of
+///   temperature with luminance,
+/// - `this.set_material(i, name)`: sets material preset of object i,
+/// - `this.set_phase(i, phase)`: sets wave phase of water surface i,
+/// - `this.set_uv_transform(i, scale_u, scale_v, offset_u, offset_v, degrees)`: sets texture coordinate transform
+///   of object i.
+///
+/// Objects are indexed in order of addition to scene. Changes persist into following frames.
+pub struct Script {
+    /// Engine with scene functions registered.
+    engine: Engine,
+
+    /// Compiled script.
+    ast: AST,
+}
+
+impl Script {
+    /// Compiles script from source text.
+    pub fn compile(source: &str) -> Result<Script, ScriptError> {
+        let engine = engine();
+        let ast = engine
+            .compile(source)
+            .map_err(|e| ScriptError::Syntax(e.to_string()))?;
+        Ok(Script { engine, ast })
+    }
+
+    /// Loads and compiles script file.
+    pub fn load(path: &Path) -> Result<Script, ScriptError> {
+        Script::compile(&fs::read_to_string(path)?)
+    }
+
+    /// Calls `on_frame` of script with frame time in seconds, applying its changes to scene objects. Does nothing if
+    /// script defines no `on_frame`.
+    pub fn on_frame(&self, scene: &mut Scene, t: f32) -> Result<(), ScriptError> {
+        if !self.ast.iter_functions().any(|f| f.name == ON_FRAME) {
+            return Ok(());
+        }
+
+        // Run script on copy of objects, showing the named materials bound ones share, ignoring return value.
+        let count = scene.objects().len();
+        let shapes = (0..count).map(|i| {
+            let mut shape = scene.objects()[i].clone();
+            if scene.material_binding(i).is_some() {
+                shape.set_material(scene.material(i).clone());
+            }
+            shape
+        });
+        let mut objects = Dynamic::from(Objects {
+            shapes: shapes.collect(),
+            uv_transforms: (0..count).map(|i| scene.uv_transform(i)).collect(),
+        });
+        let options = CallFnOptions::new().bind_this_ptr(&mut objects);
+        let _ = self
+            .engine
+            .call_fn_with_options::<Dynamic>(options, &mut Scope::new(), &self.ast, ON_FRAME, (t,))
+            .map_err(|e| ScriptError::Runtime(e.to_string()))?;
+
+        // Replace changed objects. Bound objects given other materials get materials of their own.
+        let objects: Objects = objects.cast();
+        for (index, mut object) in objects.shapes.into_iter().enumerate() {
+            if scene.material_binding(index).is_some() {
+                if object.material() != scene.material(index) {
+                    scene.set_material(scene.object_id(index), object.material().clone());
+                }
+                object.set_material(scene.objects()[index].material().clone());
+            }
+            if scene.objects()[index] != object {
+                scene.set_object(index, object);
+            }
+        }
+        for (index, uv_transform) in objects.uv_transforms.into_iter().enumerate() {
+            scene.set_uv_transform(index, uv_transform);
+        }
+        Ok(())
+    }
+}
+
+/// Scene objects as seen by script.
+#[derive(Clone)]
+struct Objects {
+    /// Shapes, in order of addition to scene.
+    shapes: Vec<Shape>,
+
+    /// Texture coordinate transforms of shapes.
+    uv_transforms: Vec<UvTransform>,
+}
+
+impl Objects {
+    /// Object at script index.
+    fn get(&mut self, index: INT) -> ScriptResult<&mut Shape> {
+        let i = self.index(index)?;
+        Ok(&mut self.shapes[i])
+    }
+
+    /// Vector index of script index.
+    fn index(&self, index: INT) -> ScriptResult<usize> {
+        let count = self.shapes.len();
+        usize::try_from(index)
+            .ok()
+            .filter(|&i| i < count)
+            .ok_or_else(|| format!("no object {} (of {})", index, count).into())
+    }
+}
+
+/// Script engine with scene object functions registered.
+fn engine() -> Engine {
+    let mut engine = Engine::new();
+    engine
+        .register_type_with_name::<Objects>("Objects")
+        .register_fn("count", |objects: &mut Objects| objects.shapes.len() as INT)
+        .register_fn(
+            "center",
+            |objects: &mut Objects, i: INT| -> ScriptResult<Array> {
+                let c = objects.get(i)?.center();
+                Ok(vec![c.x().into(), c.y().into(), c.z().into()])
+            },
+        )
+        .register_fn(
+            "set_center",
+            |objects: &mut Objects, i: INT, x: FLOAT, y: FLOAT, z: FLOAT| -> ScriptResult<()> {
+                objects.get(i)?.set_center(Point3f::new(x, y, z));
+                Ok(())
+            },
+        )
+        .register_fn(
+            "set_color",
+            |objects: &mut Objects, i: INT, r: FLOAT, g: FLOAT, b: FLOAT| -> ScriptResult<()> {
+                let object = objects.get(i)?;
+                let color = Color3f::new(r, g, b);
+                let material = match object.material() {
+                    Material::Lambert(_) => Material::Lambert(Lambert::new(color)),
+                    Material::Metal(m) => Material::Metal(Metal::new(color, m.fuzz())),
+                    Material::Principled(mut p) => {
+                        p.set_base_color(color);
+                        Material::Principled(p)
+                    }
+                    Material::Microfacet(m) if m.ior().is_none() => {
+                        Material::Microfacet(Microfacet::metal(color, m.roughness()))
+                    }
+                    Material::DiffuseLight(_) => Material::DiffuseLight(DiffuseLight::new(color)),
+                    _ => return Err(format!("material of object {} has no color", i).into()),
+                };
+                object.set_material(material);
+                Ok(())
+            },
+        )
+        .register_fn(
+            "set_temperature",
+            |objects: &mut Objects, i: INT, kelvin: FLOAT, luminance: FLOAT| -> ScriptResult<()> {
+                let object = objects.get(i)?;
+                if !matches!(object.material(), Material::DiffuseLight(_)) {
+                    return Err(format!("object {} is no light", i).into());
+                }
+                if kelvin <= 0.0 {
+                    return Err(format!("invalid temperature {}", kelvin).into());
+                }
+                let emission = Color3f::blackbody(kelvin) * luminance;
+                object.set_material(Material::DiffuseLight(DiffuseLight::new(emission)));
+                Ok(())
+            },
+        )
+        .register_fn(
+            "set_material",
+            |objects: &mut Objects, i: INT, name: &str| -> ScriptResult<()> {
+                let material =
+                    Material::preset(name).ok_or(format!("no material preset {}", name))?;
+                objects.get(i)?.set_material(material);
+                Ok(())
+            },
+        )
+        .register_fn(
+            "set_phase",
+            |objects: &mut Objects, i: INT, phase: FLOAT| -> ScriptResult<()> {
+                match objects.get(i)? {
+                    Shape::Water(w) => w.set_phase(phase),
+                    _ => return Err(format!("object {} is no water surface", i).into()),
+                }
+                Ok(())
+            },
+        )
+        .register_fn(
+            "set_uv_transform",
+            |objects: &mut Objects,
+             i: INT,
+             scale_u: FLOAT,
+             scale_v: FLOAT,
+             offset_u: FLOAT,
+             offset_v: FLOAT,
+             degrees: FLOAT|
+             -> ScriptResult<()> {
+                let i = objects.index(i)?;
+                let (scale, offset) = ((scale_u, scale_v), (offset_u, offset_v));
+                objects.uv_transforms[i] = UvTransform::new(scale, offset, degrees);
+                Ok(())
+            },
+        );
+    engine
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{base::material::Interactable, shapes::sphere::Sphere};
+
+    /// Scene with lambert sphere at origin and light sphere above.
+    fn scene() -> Scene {
+        let mut scene = Scene::new();
+        let red = Material::Lambert(Lambert::new(Color3f::new(1.0, 0.0, 0.0)));
+        scene.add(Shape::Sphere(Sphere::new(Point3f::default(), 1.0, red)));
+        let light = Material::DiffuseLight(DiffuseLight::new(Color3f::white()));
+        let above = Point3f::new(0.0, 5.0, 0.0);
+        scene.add(Shape::Sphere(Sphere::new(above, 1.0, light)));
+        scene
+    }
+
+    #[test]
+    fn on_frame() {
+        let script = Script::compile(
+            r#"
+            fn on_frame(t) {
+                let c = this.center(0);
+                this.set_center(0, c[0] + t, 1.0, 0.0);
+                this.set_color(1, 0.0, 0.0, 0.0);
+                if this.count() == 2 {
+                    this.set_material(0, "gold");
+                }
+                this.set_uv_transform(1, 2.0, 2.0, 0.0, 0.5, 90.0);
+            }
+            "#,
+        )
+        .unwrap();
+        let mut scene = scene();
+        script.on_frame(&mut scene, 0.5).unwrap();
+        script.on_frame(&mut scene, 0.5).unwrap();
+        assert_eq!(scene.objects()[0].center(), Point3f::new(1.0, 1.0, 0.0));
+        assert_eq!(
+            *scene.objects()[0].material(),
+            Material::preset("gold").unwrap()
+        );
+        assert!(scene.lights().is_empty());
+        let uv_transform = UvTransform::new((2.0, 2.0), (0.0, 0.5), 90.0);
+        assert_eq!(scene.uv_transform(1), uv_transform);
+
+        // Lights glow by temperature.
+        let script =
+            Script::compile("fn on_frame(t) { this.set_temperature(1, 2700.0, 5.0); }").unwrap();
+        let mut scene = self::scene();
+        script.on_frame(&mut scene, 0.0).unwrap();
+        assert_eq!(
+            scene.objects()[1].material().emitted(),
+            Color3f::blackbody(2700.0) * 5.0
+        );
+
+        // Bound objects keep their binding when moved, and get materials of their own when recolored.
+        let mut scene = self::scene();
+        let gold = scene.define_material("gold", Material::preset("gold").unwrap());
+        scene.bind_material(scene.object_id(0), gold);
+        let script =
+            Script::compile("fn on_frame(t) { this.set_center(0, t, 0.0, 0.0); }").unwrap();
+        script.on_frame(&mut scene, 1.0).unwrap();
+        assert_eq!(scene.material_binding(0), Some(gold));
+        assert_eq!(scene.objects()[0].center(), Point3f::new(1.0, 0.0, 0.0));
+        let script =
+            Script::compile("fn on_frame(t) { this.set_color(0, 0.0, 0.0, 1.0); }").unwrap();
+        script.on_frame(&mut scene, 0.0).unwrap();
+        assert_eq!(scene.material_binding(0), None);
+        assert_ne!(*scene.material(0), Material::preset("gold").unwrap());
+
+        // Scripts without callback leave scene untouched.
+        let script = Script::compile("let x = 1;").unwrap();
+        let mut unchanged = self::scene();
+        script.on_frame(&mut unchanged, 1.0).unwrap();
+        assert_eq!(unchanged.content_hash(), self::scene().content_hash());
+    }
+
+    #[test]
+    fn errors() {
+        assert!(matches!(
+            Script::compile("fn on_frame(t) {"),
+            Err(ScriptError::Syntax(_))
+        ));
+        assert!(matches!(
+            Script::load(Path::new("missing.rhai")),
+            Err(ScriptError::Io(_))
+        ));
+        for source in [
+            "fn on_frame(t) { this.set_center(2, 0.0, 0.0, 0.0); }",
+            "fn on_frame(t) { this.set_material(0, \"unobtainium\"); }",
+            "fn on_frame(t) { this.set_phase(0, t); }",
+            "fn on_frame(t) { this.set_temperature(0, 3200.0, 1.0); }",
+            "fn on_frame(t) { this.set_temperature(1, 0.0, 1.0); }",
+            "fn on_frame(t) { this.set_uv_transform(-1, 1.0, 1.0, 0.0, 0.0, 0.0); }",
+        ] {
+            let script = Script::compile(source).unwrap();
+            assert!(matches!(
+                script.on_frame(&mut scene(), 0.0),
+                Err(ScriptError::Runtime(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,294 @@
+use crate::{
+    base::color::Color3f,
+    film::FilmPrecision,
+    integrator::Integrator,
+    sampler::{PixelSampler, Sampler},
+    tile::{tiles, Tile, TileOrder},
+};
+
+/// Settings of how an image is rendered, independent of what is seen: sample counts, light transport (including its
+/// max path depth and clamping), sampling and scheduling. The same camera renders drafts and final images with
+/// different settings.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RenderSettings {
+    /// Count of random samples per pixel.
+    samples_per_pixel: u32,
+
+    /// Image regions rendered with more samples per pixel, with their sample count.
+    priority_regions: Vec<(Tile, u32)>,
+
+    /// Light transport algorithm.
+    integrator: Integrator,
+
+    /// Storage precision of rendered film.
+    film_precision: FilmPrecision,
+
+    /// Seed of random number generators.
+    seed: u64,
+
+    /// Strategy for generating pixel samples.
+    sampler: Sampler,
+
+    /// Edge length of square render tiles in pixels.
+    tile_size: u32,
+
+    /// Scheduling order of render tiles.
+    tile_order: TileOrder,
+
+    /// Max luminance of each camera sample.
+    max_sample_luminance: f32,
+
+    /// Factor of the mean luminance of the other samples of a pixel above which samples are rejected, if any.
+    outlier_rejection: Option<f32>,
+}
+
+impl RenderSettings {
+    /// Creates settings rendering given samples per pixel with default path tracer.
+    pub fn new(samples_per_pixel: u32) -> Self {
+        RenderSettings {
+            samples_per_pixel,
+            priority_regions: Vec::new(),
+            integrator: Integrator::default(),
+            film_precision: FilmPrecision::Full,
+            seed: 0,
+            sampler: Sampler::Independent,
+            tile_size: 32,
+            tile_order: TileOrder::Scanline,
+            max_sample_luminance: f32::INFINITY,
+            outlier_rejection: None,
+        }
+    }
+
+    /// Samples per pixel outside of priority regions.
+    pub fn samples_per_pixel(&self) -> u32 {
+        self.samples_per_pixel
+    }
+
+    /// Sets samples per pixel.
+    pub fn set_samples_per_pixel(&mut self, samples_per_pixel: u32) {
+        self.samples_per_pixel = samples_per_pixel;
+    }
+
+    /// Adds image region (in pixels of rendered image) which is rendered with higher count of samples per pixel,
+    /// e.g. around the subject of the image. Overlapping regions use the highest count. Tiles touching priority
+    /// regions are rendered first.
+    pub fn add_priority_region(&mut self, region: Tile, samples_per_pixel: u32) {
+        self.priority_regions.push((region, samples_per_pixel));
+    }
+
+    /// Removes all priority regions.
+    pub fn clear_priority_regions(&mut self) {
+        self.priority_regions.clear();
+    }
+
+    /// Whether any priority regions are set.
+    #[cfg(feature = "gpu")]
+    pub(crate) fn has_priority_regions(&self) -> bool {
+        !self.priority_regions.is_empty()
+    }
+
+    /// Count of samples of pixel x,y.
+    pub(crate) fn samples_at(&self, x: u32, y: u32) -> u32 {
+        self.priority_regions
+            .iter()
+            .filter(|(region, _)| region.contains(x, y))
+            .map(|&(_, samples)| samples)
+            .fold(self.samples_per_pixel, u32::max)
+    }
+
+    /// Light transport algorithm.
+    pub fn integrator(&self) -> Integrator {
+        self.integrator
+    }
+
+    /// Sets light transport algorithm.
+    pub fn set_integrator(&mut self, integrator: Integrator) {
+        self.integrator = integrator;
+    }
+
+    /// Storage precision of rendered film.
+    pub fn film_precision(&self) -> FilmPrecision {
+        self.film_precision
+    }
+
+    /// Sets storage precision of rendered film.
+    pub fn set_film_precision(&mut self, film_precision: FilmPrecision) {
+        self.film_precision = film_precision;
+    }
+
+    /// Seed of random number generators.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Sets seed of random number generators. Same seed yields identical image.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+    }
+
+    /// Strategy for generating pixel samples.
+    pub fn sampler(&self) -> Sampler {
+        self.sampler
+    }
+
+    /// Sets strategy for generating pixel samples.
+    pub fn set_sampler(&mut self, sampler: Sampler) {
+        self.sampler = sampler;
+    }
+
+    /// Edge length of square render tiles.
+    pub fn tile_size(&self) -> u32 {
+        self.tile_size
+    }
+
+    /// Sets edge length of square render tiles.
+    pub fn set_tile_size(&mut self, tile_size: u32) {
+        assert!(tile_size > 0);
+        self.tile_size = tile_size;
+    }
+
+    /// Scheduling order of render tiles.
+    pub fn tile_order(&self) -> TileOrder {
+        self.tile_order
+    }
+
+    /// Sets scheduling order of render tiles.
+    pub fn set_tile_order(&mut self, tile_order: TileOrder) {
+        self.tile_order = tile_order;
+    }
+
+    /// Max luminance of each camera sample.
+    pub fn max_sample_luminance(&self) -> f32 {
+        self.max_sample_luminance
+    }
+
+    /// Sets max luminance of each camera sample, scaling brighter samples down before they are accumulated. Unlike
+    /// the path tracer's clamping at diffuse bounces, this bounds the whole path including light seen directly, so
+    /// it should stay well above the brightest light seen by the camera.
+    pub fn set_max_sample_luminance(&mut self, max_sample_luminance: f32) {
+        assert!(max_sample_luminance > 0.0);
+        self.max_sample_luminance = max_sample_luminance;
+    }
+
+    /// Factor of the mean luminance of the other samples of a pixel above which samples are rejected, if any.
+    pub fn outlier_rejection(&self) -> Option<f32> {
+        self.outlier_rejection
+    }
+
+    /// Sets factor of the mean luminance of the other samples of a pixel above which samples are rejected as
+    /// fireflies, averaging pixels over the remaining samples. Rejection darkens pixels lit by rare paths only, e.g.
+    /// caustics at low sample counts, so factors should be large (say 20). Applies to renders taking all samples of
+    /// a pixel at once, progressive accumulation only clamps samples.
+    pub fn set_outlier_rejection(&mut self, factor: Option<f32>) {
+        if let Some(factor) = factor {
+            assert!(factor > 1.0);
+        }
+        self.outlier_rejection = factor;
+    }
+
+    /// Sample scaled down to max sample luminance.
+    pub(crate) fn clamp_sample(&self, sample: Color3f) -> Color3f {
+        let luminance = sample.luminance();
+        if luminance > self.max_sample_luminance {
+            sample * (self.max_sample_luminance / luminance)
+        } else {
+            sample
+        }
+    }
+
+    /// Mean of samples of a pixel, without outliers if rejection is set.
+    pub(crate) fn pixel_mean(&self, samples: &[Color3f]) -> Color3f {
+        let sum = samples.iter().fold(Color3f::black(), |acc, &s| acc + s);
+        let (Some(factor), 2..) = (self.outlier_rejection, samples.len()) else {
+            return sum / samples.len() as f32;
+        };
+        let total = sum.luminance();
+        let others = (samples.len() - 1) as f32;
+        let (sum, count) = samples
+            .iter()
+            .filter(|s| s.luminance() <= factor * (total - s.luminance()) / others)
+            .fold((Color3f::black(), 0), |(sum, count), &s| {
+                (sum + s, count + 1)
+            });
+        if count == 0 {
+            return Color3f::black();
+        }
+        sum / count as f32
+    }
+
+    /// Render tiles of image with given resolution in configured order, tiles touching priority regions first.
+    pub fn tiles(&self, width: u32, height: u32) -> Vec<Tile> {
+        let mut tiles = tiles(width, height, self.tile_size, self.tile_order);
+        tiles.sort_by_key(|tile| {
+            !self
+                .priority_regions
+                .iter()
+                .any(|(r, _)| r.intersects(tile))
+        });
+        tiles
+    }
+
+    /// Random number source of pixel x,y for samples from given index on, independent of render order.
+    pub(crate) fn pixel_sampler(&self, x: u32, y: u32, first_sample: u32) -> PixelSampler {
+        PixelSampler::for_pixel(self.sampler, self.seed, (x, y), first_sample)
+    }
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        RenderSettings::new(10)
+    }
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn priority_regions() {
+        let mut settings = RenderSettings::new(4);
+        settings.set_tile_size(8);
+        let region = Tile {
+            x: 20,
+            y: 4,
+            width: 4,
+            height: 4,
+        };
+        settings.add_priority_region(region, 16);
+        assert_eq!(
+            (settings.samples_at(21, 5), settings.samples_at(0, 0)),
+            (16, 4)
+        );
+
+        // Tiles touching region come first.
+        let tiles = settings.tiles(32, 16);
+        assert_eq!(tiles.len(), 8);
+        assert!(tiles[0].intersects(&region));
+        settings.clear_priority_regions();
+        assert_eq!(settings.tiles(32, 16)[0].x, 0);
+    }
+
+    #[test]
+    fn fireflies() {
+        let gray = Color3f::new(0.5, 0.5, 0.5);
+        let firefly = Color3f::new(100.0, 0.0, 100.0);
+        let samples = [gray, gray, gray, firefly];
+
+        // Clamping keeps hue.
+        let mut settings = RenderSettings::new(4);
+        assert_eq!(settings.clamp_sample(firefly), firefly);
+        settings.set_max_sample_luminance(2.0);
+        let clamped = settings.clamp_sample(firefly);
+        assert!((clamped.luminance() - 2.0).abs() < 1e-5);
+        assert_eq!(clamped.r(), clamped.b());
+        assert_eq!(settings.clamp_sample(gray), gray);
+
+        // Rejection drops samples far brighter than the others only.
+        assert!(settings.pixel_mean(&samples).r() > 20.0);
+        settings.set_outlier_rejection(Some(20.0));
+        assert_eq!(settings.pixel_mean(&samples), gray);
+        assert_eq!(settings.pixel_mean(&[gray, 2.0 * gray]), 1.5 * gray);
+        assert_eq!(settings.pixel_mean(&[firefly]), firefly);
+    }
+}
@@ -1 +1,11 @@
+pub mod capsule;
+pub mod cone;
+pub mod csg;
+pub mod custom;
+pub mod cylinder;
+pub mod instance;
+pub mod mesh;
 pub mod sphere;
+pub mod torus;
+pub mod voxels;
+pub mod water;
@@ -0,0 +1,331 @@
+use crate::base::{
+    aabb::Aabb,
+    hash::hash_float,
+    interval::Interval,
+    material::Material,
+    point::Point3f,
+    ray::Ray,
+    shape::{Bounded, Intersectable, Intersection, Sampleable, SurfaceSample},
+    vector::Vector3f,
+};
+use rand::Rng;
+use std::{
+    f32::consts::PI,
+    hash::{Hash, Hasher},
+};
+
+/// Capsule in 3-dim space: all points within radius of an axis segment, defined by center position, axis direction,
+/// radius and segment length. Ends are hemispheres.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Capsule {
+    /// Center position (midpoint of axis segment).
+    center: Point3f,
+
+    /// Unit axis direction.
+    axis: Vector3f,
+
+    /// Capsule radius.
+    radius: f32,
+
+    /// Length of axis segment, excluding hemispherical ends.
+    height: f32,
+
+    /// Surface material.
+    material: Material,
+}
+
+impl Capsule {
+    /// Creates capsule with center position, axis direction, radius and length of axis segment.
+    pub fn new(
+        center: Point3f,
+        axis: Vector3f,
+        radius: f32,
+        height: f32,
+        material: Material,
+    ) -> Self {
+        assert!(radius > 0.0 && height >= 0.0 && !axis.near_zero());
+        Capsule {
+            center,
+            axis: axis.normalize(),
+            radius,
+            height,
+            material,
+        }
+    }
+
+    /// Center position (midpoint of axis segment).
+    pub fn center(&self) -> Point3f {
+        self.center
+    }
+
+    /// Sets center position (midpoint of axis segment).
+    pub fn set_center(&mut self, center: Point3f) {
+        self.center = center;
+    }
+
+    /// Unit axis direction.
+    pub fn axis(&self) -> Vector3f {
+        self.axis
+    }
+
+    /// Capsule radius.
+    pub fn radius(&self) -> f32 {
+        self.radius
+    }
+
+    /// Length of axis segment, excluding hemispherical ends.
+    pub fn height(&self) -> f32 {
+        self.height
+    }
+
+    /// Surface material.
+    pub fn material(&self) -> &Material {
+        &self.material
+    }
+
+    /// Sets surface material.
+    pub fn set_material(&mut self, material: Material) {
+        self.material = material;
+    }
+
+    /// Orthonormal basis with axis as third vector.
+    pub fn basis(&self) -> (Vector3f, Vector3f, Vector3f) {
+        let (u, v) = self.axis.orthonormal_basis();
+        (u, v, self.axis)
+    }
+
+    /// Area of cylindrical side.
+    fn side_area(&self) -> f32 {
+        2.0 * PI * self.radius * self.height
+    }
+}
+
+impl Hash for Capsule {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.center.hash(state);
+        self.axis.hash(state);
+        hash_float(self.radius, state);
+        hash_float(self.height, state);
+        self.material.hash(state);
+    }
+}
+
+impl Intersectable for Capsule {
+    fn intersect(&self, ray: Ray, ray_t: Interval) -> Option<Intersection<'_>> {
+        // Ray in local frame with axis along z.
+        let (u, v, w) = self.basis();
+        let oc = ray.origin() - self.center;
+        let d = ray.direction();
+        let (ox, oy, oz) = (oc.dot(&u), oc.dot(&v), oc.dot(&w));
+        let (dx, dy, dz) = (d.dot(&u), d.dot(&v), d.dot(&w));
+        let half_height = 0.5 * self.height;
+        let radius_squared = self.radius * self.radius;
+
+        // Nearest hit in interval, as ray parameter and local normal.
+        let mut nearest: Option<(f32, Vector3f)> = None;
+        let mut closest_t = ray_t.end();
+
+        // Side: solve quadratic equation of infinite cylinder, keep roots along segment.
+        let a = dx * dx + dy * dy;
+        let half_b = ox * dx + oy * dy;
+        let c = ox * ox + oy * oy - radius_squared;
+        let discriminant = half_b * half_b - a * c;
+        if a > 0.0 && discriminant >= 0.0 {
+            let discriminant_sqrt = discriminant.sqrt();
+            for root in [
+                (-half_b - discriminant_sqrt) / a,
+                (-half_b + discriminant_sqrt) / a,
+            ] {
+                let z = oz + root * dz;
+                if Interval::new(ray_t.start(), closest_t).contains(root) && z.abs() <= half_height
+                {
+                    let normal = Vector3f::new(ox + root * dx, oy + root * dy, 0.0).normalize();
+                    nearest = Some((root, normal));
+                    closest_t = root;
+                    break;
+                }
+            }
+        }
+
+        // Ends: solve quadratic equations of spheres at segment ends, keep roots beyond segment.
+        let a = a + dz * dz;
+        for side in [-1.0, 1.0] {
+            let oz = oz - side * half_height;
+            let half_b = half_b + oz * dz;
+            let c = c + oz * oz;
+            let discriminant = half_b * half_b - a * c;
+            if discriminant < 0.0 {
+                continue;
+            }
+            let discriminant_sqrt = discriminant.sqrt();
+            for root in [
+                (-half_b - discriminant_sqrt) / a,
+                (-half_b + discriminant_sqrt) / a,
+            ] {
+                let z = oz + root * dz;
+                if Interval::new(ray_t.start(), closest_t).contains(root) && side * z >= 0.0 {
+                    let normal = Vector3f::new(ox + root * dx, oy + root * dy, z).normalize();
+                    nearest = Some((root, normal));
+                    closest_t = root;
+                    break;
+                }
+            }
+        }
+
+        // Surface coordinates from angle around and position along axis, including ends.
+        let (t, n) = nearest?;
+        let z = oz + t * dz;
+        let uv = (
+            (n.y().atan2(n.x()) / (2.0 * PI)).rem_euclid(1.0),
+            ((z + half_height + self.radius) / (self.height + 2.0 * self.radius)).clamp(0.0, 1.0),
+        );
+
+        // Return intersection struct with normal in world frame.
+        let intersection = Intersection {
+            point: ray.at(t),
+            material: &self.material,
+            normal: n.x() * u + n.y() * v + n.z() * w,
+            uv,
+            t,
+            error: ray.error_at(t),
+        };
+        Some(intersection)
+    }
+}
+
+impl Bounded for Capsule {
+    fn bounding_box(&self) -> Aabb {
+        // Per axis, half extent of axis segment plus radius of end spheres.
+        let extent = |a: f32| 0.5 * self.height * a.abs() + self.radius;
+        let a = self.axis;
+        let e = Vector3f::new(extent(a.x()), extent(a.y()), extent(a.z()));
+        Aabb::new(self.center - e, self.center + e)
+    }
+}
+
+impl Sampleable for Capsule {
+    fn area(&self) -> f32 {
+        self.side_area() + 4.0 * PI * self.radius * self.radius
+    }
+
+    fn sample_point(&self, rng: &mut impl Rng) -> SurfaceSample {
+        let (u, v, w) = self.basis();
+
+        // Pick side or ends proportional to area. Ends together form a sphere, split at the segment.
+        let (point, normal) = if rng.gen::<f32>() * self.area() < self.side_area() {
+            let phi = 2.0 * PI * rng.gen::<f32>();
+            let radial = phi.cos() * u + phi.sin() * v;
+            let z = self.height * (rng.gen::<f32>() - 0.5);
+            (self.center + self.radius * radial + z * w, radial)
+        } else {
+            let normal = Vector3f::random_unit_vector(rng);
+            let side = if normal.dot(&w) < 0.0 { -1.0 } else { 1.0 };
+            let point = self.center + (side * 0.5 * self.height) * w + self.radius * normal;
+            (point, normal)
+        };
+
+        // Return surface sample struct.
+        SurfaceSample {
+            point,
+            normal,
+            pdf: 1.0 / self.area(),
+        }
+    }
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    /// Capsule along y-axis with segment from y=1 to y=3 and radius 1.
+    fn capsule() -> Capsule {
+        Capsule::new(
+            Point3f::new(0.0, 2.0, 0.0),
+            Vector3f::new(0.0, 1.0, 0.0),
+            1.0,
+            2.0,
+            Material::None,
+        )
+    }
+
+    /// Distance of point to axis segment of capsule.
+    fn segment_distance(c: &Capsule, p: Point3f) -> f32 {
+        let local = p - c.center();
+        let along = local
+            .dot(&c.axis())
+            .clamp(-0.5 * c.height(), 0.5 * c.height());
+        (local - along * c.axis()).length()
+    }
+
+    #[test]
+    fn intersect() {
+        let c = capsule();
+        let all = Interval::new(0.0, f32::INFINITY);
+
+        // Side hit, from outside and inside.
+        let r1 = Ray::new(Point3f::new(-3.0, 1.5, 0.0), Vector3f::new(1.0, 0.0, 0.0));
+        let i1 = c.intersect(r1, all).unwrap();
+        assert!((i1.t - 2.0).abs() < 1e-6);
+        assert!((i1.normal - Vector3f::new(-1.0, 0.0, 0.0)).length() < 1e-6);
+        assert!((i1.uv.1 - 0.375).abs() < 1e-6);
+        let i2 = c.intersect(r1, Interval::new(2.5, f32::INFINITY)).unwrap();
+        assert!((i2.t - 4.0).abs() < 1e-6);
+
+        // Axial hits on end poles.
+        let r2 = Ray::new(Point3f::new(0.0, 10.0, 0.0), Vector3f::new(0.0, -1.0, 0.0));
+        let i3 = c.intersect(r2, all).unwrap();
+        assert!((i3.t - 6.0).abs() < 1e-6);
+        assert!((i3.normal - Vector3f::new(0.0, 1.0, 0.0)).length() < 1e-6);
+        assert!((i3.uv.1 - 1.0).abs() < 1e-6);
+        let i4 = c.intersect(r2, Interval::new(6.5, f32::INFINITY)).unwrap();
+        assert!((i4.t - 10.0).abs() < 1e-6);
+        assert!((i4.normal - Vector3f::new(0.0, -1.0, 0.0)).length() < 1e-6);
+
+        // Hit on end sphere beside segment, misses beyond.
+        let r3 = Ray::new(Point3f::new(-3.0, 3.5, 0.0), Vector3f::new(1.0, 0.0, 0.0));
+        let i5 = c.intersect(r3, all).unwrap();
+        assert!((i5.t - (3.0 - 0.75f32.sqrt())).abs() < 1e-6);
+        let r4 = Ray::new(Point3f::new(-3.0, 4.5, 0.0), Vector3f::new(1.0, 0.0, 0.0));
+        assert_eq!(c.intersect(r4, all), None);
+    }
+
+    #[test]
+    fn tilted() {
+        // Points hit on tilted capsule lie on its surface, normals point outwards.
+        let axis = Vector3f::new(1.0, 2.0, -1.0).normalize();
+        let c = Capsule::new(Point3f::new(1.0, 2.0, 3.0), axis, 0.5, 3.0, Material::None);
+        let mut rng = StdRng::seed_from_u64(42);
+        for _ in 0..100 {
+            let target = c.center() + Vector3f::random_unit_vector(&mut rng) * 0.4;
+            let origin = target + 10.0 * Vector3f::random_unit_vector(&mut rng);
+            let ray = Ray::new(origin, target - origin);
+            let isect = c.intersect(ray, Interval::new(0.0, f32::INFINITY)).unwrap();
+            assert!((segment_distance(&c, isect.point) - 0.5).abs() < 1e-4);
+            assert!(isect.normal.dot(&(origin - isect.point)) > 0.0);
+            assert!((isect.normal.length() - 1.0).abs() < 1e-5);
+            assert!((0.0..=1.0).contains(&isect.uv.0) && (0.0..=1.0).contains(&isect.uv.1));
+        }
+    }
+
+    #[test]
+    fn sample_point() {
+        let c = capsule();
+        assert!((c.area() - 8.0 * PI).abs() < 1e-5);
+        let mut rng = StdRng::seed_from_u64(42);
+        for _ in 0..100 {
+            let sample = c.sample_point(&mut rng);
+            assert!((segment_distance(&c, sample.point) - 1.0).abs() < 1e-5);
+            assert!((sample.normal.length() - 1.0).abs() < 1e-5);
+            assert_eq!(sample.pdf, 1.0 / c.area());
+        }
+    }
+
+    #[test]
+    fn bounding_box() {
+        let b = capsule().bounding_box();
+        assert_eq!(b.min(), Point3f::new(-1.0, 0.0, -1.0));
+        assert_eq!(b.max(), Point3f::new(1.0, 4.0, 1.0));
+    }
+}
@@ -0,0 +1,379 @@
+use crate::base::{
+    aabb::Aabb,
+    hash::hash_float,
+    interval::Interval,
+    material::Material,
+    point::Point3f,
+    ray::Ray,
+    shape::{Bounded, Intersectable, Intersection, Sampleable, SurfaceSample},
+    vector::Vector3f,
+};
+use rand::Rng;
+use std::{
+    f32::consts::PI,
+    hash::{Hash, Hasher},
+};
+
+/// Finite cone in 3-dim space defined by center position, axis direction (pointing from base to apex), base radius
+/// and height, optionally closed by a flat base.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cone {
+    /// Center position (midpoint of axis).
+    center: Point3f,
+
+    /// Unit axis direction, from base to apex.
+    axis: Vector3f,
+
+    /// Radius of base.
+    radius: f32,
+
+    /// Length along axis.
+    height: f32,
+
+    /// Whether base is closed by a disk.
+    base: bool,
+
+    /// Surface material.
+    material: Material,
+}
+
+impl Cone {
+    /// Creates closed cone with center position, axis direction, base radius and height.
+    pub fn new(
+        center: Point3f,
+        axis: Vector3f,
+        radius: f32,
+        height: f32,
+        material: Material,
+    ) -> Self {
+        assert!(radius > 0.0 && height > 0.0 && !axis.near_zero());
+        Cone {
+            center,
+            axis: axis.normalize(),
+            radius,
+            height,
+            base: true,
+            material,
+        }
+    }
+
+    /// Center position (midpoint of axis).
+    pub fn center(&self) -> Point3f {
+        self.center
+    }
+
+    /// Sets center position (midpoint of axis).
+    pub fn set_center(&mut self, center: Point3f) {
+        self.center = center;
+    }
+
+    /// Unit axis direction, from base to apex.
+    pub fn axis(&self) -> Vector3f {
+        self.axis
+    }
+
+    /// Radius of base.
+    pub fn radius(&self) -> f32 {
+        self.radius
+    }
+
+    /// Length along axis.
+    pub fn height(&self) -> f32 {
+        self.height
+    }
+
+    /// Whether base is closed by a disk.
+    pub fn base(&self) -> bool {
+        self.base
+    }
+
+    /// Sets whether base is closed by a disk.
+    pub fn set_base(&mut self, base: bool) {
+        self.base = base;
+    }
+
+    /// Surface material.
+    pub fn material(&self) -> &Material {
+        &self.material
+    }
+
+    /// Sets surface material.
+    pub fn set_material(&mut self, material: Material) {
+        self.material = material;
+    }
+
+    /// Tip of cone.
+    pub fn apex(&self) -> Point3f {
+        self.center + 0.5 * self.height * self.axis
+    }
+
+    /// Orthonormal basis with axis as third vector.
+    pub fn basis(&self) -> (Vector3f, Vector3f, Vector3f) {
+        let (u, v) = self.axis.orthonormal_basis();
+        (u, v, self.axis)
+    }
+
+    /// Distance from apex to base rim.
+    fn slant_height(&self) -> f32 {
+        (self.radius * self.radius + self.height * self.height).sqrt()
+    }
+
+    /// Area of side surface.
+    fn side_area(&self) -> f32 {
+        PI * self.radius * self.slant_height()
+    }
+
+    /// Area of base, zero if open.
+    fn base_area(&self) -> f32 {
+        if self.base {
+            PI * self.radius * self.radius
+        } else {
+            0.0
+        }
+    }
+}
+
+impl Hash for Cone {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.center.hash(state);
+        self.axis.hash(state);
+        hash_float(self.radius, state);
+        hash_float(self.height, state);
+        self.base.hash(state);
+        self.material.hash(state);
+    }
+}
+
+impl Intersectable for Cone {
+    fn intersect(&self, ray: Ray, ray_t: Interval) -> Option<Intersection<'_>> {
+        // Ray in local frame with axis along z, apex at +z.
+        let (u, v, w) = self.basis();
+        let oc = ray.origin() - self.center;
+        let d = ray.direction();
+        let (ox, oy, oz) = (oc.dot(&u), oc.dot(&v), oc.dot(&w));
+        let (dx, dy, dz) = (d.dot(&u), d.dot(&v), d.dot(&w));
+        let half_height = 0.5 * self.height;
+        let slope = self.radius / self.height;
+
+        // Nearest hit in interval, as ray parameter, local normal and surface coordinates.
+        let mut nearest: Option<(f32, Vector3f, (f32, f32))> = None;
+        let mut closest_t = ray_t.end();
+
+        // Side: solve quadratic equation of infinite double cone x²+y² = (slope·(h/2-z))², keep roots between base
+        // and apex. Numerically stable form, since rays almost parallel to the surface make the quadratic term vanish.
+        let q = half_height - oz;
+        let a = dx * dx + dy * dy - slope * slope * dz * dz;
+        let half_b = ox * dx + oy * dy + slope * slope * q * dz;
+        let c = ox * ox + oy * oy - slope * slope * q * q;
+        let discriminant = half_b * half_b - a * c;
+        let k = -(half_b + half_b.signum() * discriminant.max(0.0).sqrt());
+        let (r1, r2) = (k / a, c / k);
+        let roots = if discriminant >= 0.0 {
+            [r1.min(r2), r1.max(r2)]
+        } else {
+            [f32::NAN; 2]
+        };
+        for root in roots {
+            let z = oz + root * dz;
+            if Interval::new(ray_t.start(), closest_t).contains(root) && z.abs() <= half_height {
+                let (x, y) = (ox + root * dx, oy + root * dy);
+                let rho = slope * (half_height - z);
+                let uv = (
+                    (y.atan2(x) / (2.0 * PI)).rem_euclid(1.0),
+                    (z + half_height) / self.height,
+                );
+                let normal = Vector3f::new(x, y, slope * rho);
+                if normal.near_zero() {
+                    // Exactly at apex.
+                    nearest = Some((root, Vector3f::new(0.0, 0.0, 1.0), uv));
+                } else {
+                    nearest = Some((root, normal.normalize(), uv));
+                }
+                closest_t = root;
+                break;
+            }
+        }
+
+        // Base: intersect base plane, keep hit within radius.
+        if self.base && dz != 0.0 {
+            let root = (-half_height - oz) / dz;
+            let (x, y) = (ox + root * dx, oy + root * dy);
+            if Interval::new(ray_t.start(), closest_t).contains(root)
+                && x * x + y * y <= self.radius * self.radius
+            {
+                let uv = (0.5 + 0.5 * x / self.radius, 0.5 + 0.5 * y / self.radius);
+                nearest = Some((root, Vector3f::new(0.0, 0.0, -1.0), uv));
+            }
+        }
+
+        // Return intersection struct with normal in world frame.
+        let (t, n, uv) = nearest?;
+        let intersection = Intersection {
+            point: ray.at(t),
+            material: &self.material,
+            normal: n.x() * u + n.y() * v + n.z() * w,
+            uv,
+            t,
+            error: ray.error_at(t),
+        };
+        Some(intersection)
+    }
+}
+
+impl Bounded for Cone {
+    fn bounding_box(&self) -> Aabb {
+        // Apex joined with box of base disk.
+        let extent = |a: f32| self.radius * (1.0 - a * a).max(0.0).sqrt();
+        let a = self.axis;
+        let e = Vector3f::new(extent(a.x()), extent(a.y()), extent(a.z()));
+        let base = self.center - 0.5 * self.height * a;
+        Aabb::new(base - e, base + e).union(&Aabb::new(self.apex(), self.apex()))
+    }
+}
+
+impl Sampleable for Cone {
+    fn area(&self) -> f32 {
+        self.side_area() + self.base_area()
+    }
+
+    fn sample_point(&self, rng: &mut impl Rng) -> SurfaceSample {
+        let (u, v, w) = self.basis();
+        let phi = 2.0 * PI * rng.gen::<f32>();
+        let radial = phi.cos() * u + phi.sin() * v;
+
+        // Pick side or base proportional to area. Side area grows linearly with distance from apex.
+        let (point, normal) = if rng.gen::<f32>() * self.area() < self.side_area() {
+            let s = rng.gen::<f32>().sqrt();
+            let point = self.apex() + s * (self.radius * radial - self.height * w);
+            let normal = (self.height * radial + self.radius * w) / self.slant_height();
+            (point, normal)
+        } else {
+            let r = self.radius * rng.gen::<f32>().sqrt();
+            let point = self.center + r * radial - (0.5 * self.height) * w;
+            (point, -w)
+        };
+
+        // Return surface sample struct.
+        SurfaceSample {
+            point,
+            normal,
+            pdf: 1.0 / self.area(),
+        }
+    }
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    /// Closed cone along y-axis with base at y=0, apex at y=2 and radius 1.
+    fn cone() -> Cone {
+        Cone::new(
+            Point3f::new(0.0, 1.0, 0.0),
+            Vector3f::new(0.0, 1.0, 0.0),
+            1.0,
+            2.0,
+            Material::None,
+        )
+    }
+
+    /// Whether point lies on surface of cone, within tolerance.
+    fn on_surface(c: &Cone, p: Point3f) -> bool {
+        let local = p - c.center();
+        let along = local.dot(&c.axis());
+        let distance = (local - along * c.axis()).length();
+        let half_height = 0.5 * c.height();
+        let on_side = (distance - c.radius() * (half_height - along) / c.height()).abs() < 1e-4
+            && along.abs() <= half_height + 1e-4;
+        let on_base = (along + half_height).abs() < 1e-4 && distance <= c.radius() + 1e-4;
+        on_side || on_base
+    }
+
+    #[test]
+    fn intersect() {
+        let c = cone();
+        let all = Interval::new(0.0, f32::INFINITY);
+
+        // Side hit halfway up, from outside and inside.
+        let r1 = Ray::new(Point3f::new(-3.0, 1.0, 0.0), Vector3f::new(1.0, 0.0, 0.0));
+        let i1 = c.intersect(r1, all).unwrap();
+        assert!((i1.t - 2.5).abs() < 1e-6);
+        let expected = Vector3f::new(-2.0, 1.0, 0.0).normalize();
+        assert!((i1.normal - expected).length() < 1e-6);
+        assert!((i1.uv.1 - 0.5).abs() < 1e-6);
+        let i2 = c.intersect(r1, Interval::new(3.0, f32::INFINITY)).unwrap();
+        assert!((i2.t - 3.5).abs() < 1e-6);
+
+        // Axial ray hits apex first, then base from inside.
+        let r2 = Ray::new(Point3f::new(0.0, 5.0, 0.0), Vector3f::new(0.0, -1.0, 0.0));
+        assert!((c.intersect(r2, all).unwrap().t - 3.0).abs() < 1e-5);
+        let i3 = c.intersect(r2, Interval::new(3.5, f32::INFINITY)).unwrap();
+        assert!((i3.t - 5.0).abs() < 1e-6);
+        assert_eq!(i3.normal, Vector3f::new(0.0, -1.0, 0.0));
+
+        // Ray parallel to side surface.
+        let r3 = Ray::new(Point3f::new(-1.0, 3.0, 0.0), Vector3f::new(1.0, -2.0, 0.0));
+        let i4 = c.intersect(r3, all).unwrap();
+        assert!((i4.point - Point3f::new(-0.25, 1.5, 0.0)).length() < 1e-5);
+
+        // Open cone lets rays through base, misses mirrored cone above apex and beside base.
+        let mut open = c.clone();
+        open.set_base(false);
+        let below = Ray::new(Point3f::new(0.0, -1.0, 0.0), Vector3f::new(0.0, 1.0, 0.0));
+        assert!((open.intersect(below, all).unwrap().t - 3.0).abs() < 1e-5);
+        let above = Ray::new(Point3f::new(-3.0, 3.0, 0.0), Vector3f::new(1.0, 0.0, 0.0));
+        assert_eq!(c.intersect(above, all), None);
+        let beside = Ray::new(Point3f::new(1.5, 5.0, 0.0), Vector3f::new(0.0, -1.0, 0.0));
+        assert_eq!(c.intersect(beside, all), None);
+    }
+
+    #[test]
+    fn tilted() {
+        // Points hit on tilted cone lie on its surface, normals point outwards.
+        let axis = Vector3f::new(1.0, 2.0, -1.0).normalize();
+        let c = Cone::new(Point3f::new(1.0, 2.0, 3.0), axis, 0.8, 3.0, Material::None);
+        let mut rng = StdRng::seed_from_u64(42);
+        for _ in 0..100 {
+            let target = c.center() - 0.5 * axis + Vector3f::random_unit_vector(&mut rng) * 0.2;
+            let origin = target + 10.0 * Vector3f::random_unit_vector(&mut rng);
+            let ray = Ray::new(origin, target - origin);
+            let isect = c.intersect(ray, Interval::new(0.0, f32::INFINITY)).unwrap();
+            assert!(on_surface(&c, isect.point));
+            assert!(isect.normal.dot(&(origin - isect.point)) > 0.0);
+            assert!((isect.normal.length() - 1.0).abs() < 1e-5);
+            assert!((0.0..=1.0).contains(&isect.uv.0) && (0.0..=1.0).contains(&isect.uv.1));
+        }
+    }
+
+    #[test]
+    fn sample_point() {
+        let c = cone();
+        assert!((c.area() - PI * (5.0f32.sqrt() + 1.0)).abs() < 1e-5);
+        let mut rng = StdRng::seed_from_u64(42);
+        for _ in 0..100 {
+            let sample = c.sample_point(&mut rng);
+            assert!(on_surface(&c, sample.point));
+            assert!((sample.normal.length() - 1.0).abs() < 1e-5);
+            assert_eq!(sample.pdf, 1.0 / c.area());
+        }
+    }
+
+    #[test]
+    fn bounding_box() {
+        let b = cone().bounding_box();
+        assert_eq!(b.min(), Point3f::new(-1.0, 0.0, -1.0));
+        assert_eq!(b.max(), Point3f::new(1.0, 2.0, 1.0));
+        let sideways = Cone::new(
+            Point3f::default(),
+            Vector3f::new(-1.0, 0.0, 0.0),
+            1.0,
+            4.0,
+            Material::None,
+        );
+        let b = sideways.bounding_box();
+        assert!((b.min() - Point3f::new(-2.0, -1.0, -1.0)).length() < 1e-6);
+        assert!((b.max() - Point3f::new(2.0, 1.0, 1.0)).length() < 1e-6);
+    }
+}
@@ -0,0 +1,342 @@
+use crate::base::{
+    aabb::Aabb,
+    interval::Interval,
+    material::Material,
+    point::Point3f,
+    ray::Ray,
+    shape::{Bounded, Intersectable, Intersection, Sampleable, Shape, SurfaceSample},
+    vector::Vector3f,
+};
+use rand::Rng;
+
+/// Offset of ray parameter past a child hit before searching for the next one, relative to the hit distance.
+const EPSILON: f32 = 1e-5;
+
+/// Boolean operation combining two solids.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum Operation {
+    /// Points inside either solid.
+    Union,
+
+    /// Points inside both solids.
+    Intersection,
+
+    /// Points inside first but not second solid.
+    Difference,
+}
+
+impl Operation {
+    /// Whether point is inside combined solid, given whether it is inside left and right solid.
+    pub fn contains(self, left: bool, right: bool) -> bool {
+        match self {
+            Operation::Union => left || right,
+            Operation::Intersection => left && right,
+            Operation::Difference => left && !right,
+        }
+    }
+}
+
+/// Constructive solid geometry node combining two child shapes by boolean operation. Children are treated as closed
+/// solids with outward normals, so rays passing through them alternately enter and exit. Surfaces of the right child
+/// cut out of the left one by difference face into the right child.
+///
+/// Area and point samples cover the complete child surfaces, including parts hidden by the operation. Samples from
+/// a reference point on hidden parts are rejected, which keeps light sampling unbiased.
+#[derive(Clone, Debug, Hash, PartialEq)]
+pub struct Csg {
+    /// Boolean operation.
+    operation: Operation,
+
+    /// Left operand.
+    left: Box<Shape>,
+
+    /// Right operand.
+    right: Box<Shape>,
+}
+
+impl Csg {
+    /// Creates node combining left and right shape by operation.
+    pub fn new(operation: Operation, left: Shape, right: Shape) -> Self {
+        Csg {
+            operation,
+            left: Box::new(left),
+            right: Box::new(right),
+        }
+    }
+
+    /// Boolean operation.
+    pub fn operation(&self) -> Operation {
+        self.operation
+    }
+
+    /// Left operand.
+    pub fn left(&self) -> &Shape {
+        &self.left
+    }
+
+    /// Right operand.
+    pub fn right(&self) -> &Shape {
+        &self.right
+    }
+
+    /// Center position of left operand.
+    pub fn center(&self) -> Point3f {
+        self.left.center()
+    }
+
+    /// Moves both operands such that center of left operand is at given position.
+    pub fn set_center(&mut self, center: Point3f) {
+        let offset = center - self.center();
+        let right = self.right.center() + offset;
+        self.left.set_center(center);
+        self.right.set_center(right);
+    }
+
+    /// Surface material of left operand.
+    pub fn material(&self) -> &Material {
+        self.left.material()
+    }
+
+    /// Sets surface material of both operands.
+    pub fn set_material(&mut self, material: Material) {
+        self.left.set_material(material.clone());
+        self.right.set_material(material);
+    }
+
+    /// Whether point on surface of left or right child lies on surface of combined solid, i.e. crossing the child
+    /// surface there changes whether the point is inside the combined solid.
+    fn on_surface(&self, point: Point3f, right: bool) -> bool {
+        if right {
+            let left = contains(&self.left, point);
+            self.operation.contains(left, false) != self.operation.contains(left, true)
+        } else {
+            let right = contains(&self.right, point);
+            self.operation.contains(false, right) != self.operation.contains(true, right)
+        }
+    }
+
+    /// Samples point uniformly by area on surfaces of both children. Returns sample and whether it lies on right
+    /// child.
+    fn sample_child(&self, rng: &mut impl Rng) -> (SurfaceSample, bool) {
+        // Pick child proportional to area.
+        let (left, right) = self.areas();
+        let on_right = rng.gen::<f32>() * (left + right) >= left;
+        let mut sample = if on_right {
+            self.right.sample_point(rng)
+        } else {
+            self.left.sample_point(rng)
+        };
+        if on_right && self.operation == Operation::Difference {
+            sample.normal = -sample.normal;
+        }
+        sample.pdf = 1.0 / (left + right);
+        (sample, on_right)
+    }
+
+    /// Area of left and right operand.
+    fn areas(&self) -> (f32, f32) {
+        (self.left.area(), self.right.area())
+    }
+}
+
+/// Whether point lies inside closed shape, determined by the orientation of the first surface hit along a fixed
+/// direction.
+pub(crate) fn contains(shape: &Shape, point: Point3f) -> bool {
+    let direction = Vector3f::new(0.267, 0.535, 0.802);
+    let ray = Ray::new(point, direction);
+    shape
+        .intersect(ray, Interval::new(EPSILON, f32::INFINITY))
+        .is_some_and(|isect| isect.normal.dot(&direction) > 0.0)
+}
+
+impl Intersectable for Csg {
+    fn intersect(&self, ray: Ray, ray_t: Interval) -> Option<Intersection<'_>> {
+        let children = [&self.left, &self.right];
+        let exits = |isect: &Intersection| isect.normal.dot(&ray.direction()) > 0.0;
+
+        // Next hit on each child, ray starts inside child if that hit exits it.
+        let mut next = children.map(|child| child.intersect(ray, ray_t));
+        let mut inside = next.map(|isect| isect.as_ref().is_some_and(exits));
+
+        // Walk along child hits until one enters or exits combined solid.
+        loop {
+            let side = match (&next[0], &next[1]) {
+                (Some(l), Some(r)) => usize::from(r.t < l.t),
+                (Some(_), None) => 0,
+                (None, Some(_)) => 1,
+                (None, None) => return None,
+            };
+            let mut isect = next[side]?;
+            let before = self.operation.contains(inside[0], inside[1]);
+            inside[side] = !exits(&isect);
+            if self.operation.contains(inside[0], inside[1]) != before {
+                // Cut out surface faces into right child.
+                if side == 1 && self.operation == Operation::Difference {
+                    isect.normal = -isect.normal;
+                }
+                return Some(isect);
+            }
+            let start = isect.t + EPSILON * isect.t.abs().max(1.0);
+            next[side] = children[side].intersect(ray, Interval::new(start, ray_t.end()));
+        }
+    }
+}
+
+impl Bounded for Csg {
+    fn bounding_box(&self) -> Aabb {
+        let (left, right) = (self.left.bounding_box(), self.right.bounding_box());
+        match self.operation {
+            Operation::Union => left.union(&right),
+            Operation::Intersection => left.intersection(&right).unwrap_or(left),
+            Operation::Difference => left,
+        }
+    }
+}
+
+impl Sampleable for Csg {
+    fn area(&self) -> f32 {
+        let (left, right) = self.areas();
+        left + right
+    }
+
+    fn sample_point(&self, rng: &mut impl Rng) -> SurfaceSample {
+        self.sample_child(rng).0
+    }
+
+    fn sample_from(&self, reference: Point3f, rng: &mut impl Rng) -> Option<SurfaceSample> {
+        // Reject samples hidden by operation.
+        let (sample, right) = self.sample_child(rng);
+        if !self.on_surface(sample.point, right) {
+            return None;
+        }
+        sample.to_solid_angle(reference)
+    }
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shapes::sphere::Sphere;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    /// Unit spheres at x=-0.5 (left) and x=0.5 (right) combined by operation.
+    fn spheres(operation: Operation) -> Csg {
+        let sphere =
+            |x: f32| Shape::Sphere(Sphere::new(Point3f::new(x, 0.0, 0.0), 1.0, Material::None));
+        Csg::new(operation, sphere(-0.5), sphere(0.5))
+    }
+
+    /// Ray parameters and normal x-coordinates of all hits of ray along x-axis from x=-5.
+    fn hits(csg: &Csg) -> Vec<(f32, f32)> {
+        let ray = Ray::new(Point3f::new(-5.0, 0.0, 0.0), Vector3f::new(1.0, 0.0, 0.0));
+        let mut hits = Vec::new();
+        let mut start = 0.0;
+        while let Some(isect) = csg.intersect(ray, Interval::new(start, f32::INFINITY)) {
+            hits.push((isect.t, isect.normal.x()));
+            start = isect.t + 1e-3;
+        }
+        hits
+    }
+
+    /// Asserts hits match expected ray parameters and normal x-coordinates.
+    fn assert_hits(actual: Vec<(f32, f32)>, expected: &[(f32, f32)]) {
+        assert_eq!(actual.len(), expected.len(), "{:?}", actual);
+        for ((t, n), (et, en)) in actual.iter().zip(expected) {
+            assert!(
+                (t - et).abs() < 1e-4 && (n - en).abs() < 1e-4,
+                "{:?}",
+                actual
+            );
+        }
+    }
+
+    #[test]
+    fn intersect() {
+        // Entry and exit of overlapping spheres along x-axis, inner surfaces only where they bound the result.
+        assert_hits(hits(&spheres(Operation::Union)), &[(3.5, -1.0), (6.5, 1.0)]);
+        assert_hits(
+            hits(&spheres(Operation::Intersection)),
+            &[(4.5, -1.0), (5.5, 1.0)],
+        );
+        assert_hits(
+            hits(&spheres(Operation::Difference)),
+            &[(3.5, -1.0), (4.5, 1.0)],
+        );
+
+        // Ray starting inside, and rays missing the result.
+        let difference = spheres(Operation::Difference);
+        let inside = Ray::new(Point3f::new(-1.0, 0.0, 0.0), Vector3f::new(1.0, 0.0, 0.0));
+        let isect = difference.intersect(inside, Interval::new(0.0, f32::INFINITY));
+        assert!((isect.unwrap().t - 0.5).abs() < 1e-5);
+        let beside = Ray::new(Point3f::new(1.2, 5.0, 0.0), Vector3f::new(0.0, -1.0, 0.0));
+        assert_eq!(
+            difference.intersect(beside, Interval::new(0.0, f32::INFINITY)),
+            None
+        );
+        let intersection = spheres(Operation::Intersection);
+        assert!(intersection
+            .intersect(beside, Interval::new(0.0, f32::INFINITY))
+            .is_none());
+    }
+
+    #[test]
+    fn nested() {
+        // Lens (intersection) with spherical hole in its middle (difference).
+        let hole = Shape::Sphere(Sphere::new(Point3f::default(), 0.2, Material::None));
+        let lens = Shape::Csg(spheres(Operation::Intersection));
+        let csg = Csg::new(Operation::Difference, lens, hole);
+        assert_hits(
+            hits(&csg),
+            &[(4.5, -1.0), (4.8, 1.0), (5.2, -1.0), (5.5, 1.0)],
+        );
+    }
+
+    #[test]
+    fn bounding_box() {
+        let union = spheres(Operation::Union).bounding_box();
+        assert_eq!(union.min(), Point3f::new(-1.5, -1.0, -1.0));
+        assert_eq!(union.max(), Point3f::new(1.5, 1.0, 1.0));
+        let intersection = spheres(Operation::Intersection).bounding_box();
+        assert_eq!(intersection.min(), Point3f::new(-0.5, -1.0, -1.0));
+        assert_eq!(intersection.max(), Point3f::new(0.5, 1.0, 1.0));
+        let difference = spheres(Operation::Difference).bounding_box();
+        assert_eq!(difference.max(), Point3f::new(0.5, 1.0, 1.0));
+    }
+
+    #[test]
+    fn sample_from() {
+        // Accepted samples lie on result surface with outward normals.
+        let difference = spheres(Operation::Difference);
+        let reference = Point3f::new(-5.0, 0.0, 0.0);
+        let mut rng = StdRng::seed_from_u64(42);
+        let mut accepted = 0;
+        for _ in 0..1000 {
+            let Some(sample) = difference.sample_from(reference, &mut rng) else {
+                continue;
+            };
+            accepted += 1;
+            let left = (sample.point - Point3f::new(-0.5, 0.0, 0.0)).length();
+            let right = (sample.point - Point3f::new(0.5, 0.0, 0.0)).length();
+            if (left - 1.0).abs() < 1e-4 {
+                assert!(right >= 1.0 - 1e-4);
+            } else {
+                assert!((right - 1.0).abs() < 1e-4 && left <= 1.0 + 1e-4);
+                assert!(sample.normal.x() > 0.0);
+            }
+            let pdf = difference.pdf_from(reference, sample.point, sample.normal);
+            assert!((sample.pdf - pdf).abs() < 1e-3 * pdf);
+        }
+
+        // Left sphere keeps 3/4 of its area, right sphere contributes 1/4 of its area.
+        assert!((accepted as f32 / 1000.0 - 0.5).abs() < 0.05);
+    }
+
+    #[test]
+    fn set_center() {
+        // Moving node moves both children.
+        let mut csg = spheres(Operation::Union);
+        csg.set_center(Point3f::new(0.5, 1.0, 0.0));
+        assert_eq!(csg.right().center(), Point3f::new(1.5, 1.0, 0.0));
+    }
+}
@@ -0,0 +1,258 @@
+use crate::base::{
+    aabb::Aabb,
+    interval::Interval,
+    material::Material,
+    point::Point3f,
+    ray::Ray,
+    shape::{Bounded, Intersectable, Intersection, Sampleable, SurfaceSample},
+    vector::Vector3f,
+};
+use rand::{Rng, RngCore};
+use std::{
+    fmt::Debug,
+    hash::{Hash, Hasher},
+    sync::Arc,
+};
+
+/// Geometry of user-defined primitives, implemented outside of this crate for shapes the `Shape` enum lacks. Wrapped
+/// into a `Custom` shape, which adds material and placement, custom geometry takes part in bounding volume
+/// hierarchies, rendering, light sampling and picking like built-in shapes.
+pub trait Geometry: Debug + Send + Sync {
+    /// Nearest hit of ray in given t-interval, if any.
+    fn hit(&self, ray: Ray, ray_t: Interval) -> Option<Hit>;
+
+    /// Axis-aligned box enclosing geometry.
+    fn bounding_box(&self) -> Aabb;
+
+    /// Surface area.
+    fn area(&self) -> f32;
+
+    /// Samples point uniformly by area. Returns surface sample with pdf w.r.t. surface area.
+    fn sample_point(&self, rng: &mut dyn RngCore) -> SurfaceSample;
+
+    /// Feeds parameters of geometry into hasher, so scene hashes change with them. Geometry with equal parameters
+    /// should hash alike, see `base::hash::hash_float` for floats.
+    fn hash_geometry(&self, state: &mut dyn Hasher);
+}
+
+/// Struct holding hit properties of custom geometry.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Hit {
+    /// Value of ray parameter t.
+    pub t: f32,
+
+    /// Unit normal at hit, pointing outwards.
+    pub normal: Vector3f,
+
+    /// Surface coordinates at hit, in [0,1] each.
+    pub uv: (f32, f32),
+}
+
+/// Shape of user-defined geometry with surface material, moved by an offset. Geometry is shared, so clones of
+/// custom shapes are cheap and equal as long as they share geometry, material and offset.
+#[derive(Clone, Debug)]
+pub struct Custom {
+    /// Shared geometry in local space.
+    geometry: Arc<dyn Geometry>,
+
+    /// Offset of geometry from local to world space.
+    offset: Vector3f,
+
+    /// Surface material.
+    material: Material,
+}
+
+impl Custom {
+    /// Creates custom shape of geometry with material, in place.
+    pub fn new(geometry: Arc<dyn Geometry>, material: Material) -> Self {
+        Custom {
+            geometry,
+            offset: Vector3f::default(),
+            material,
+        }
+    }
+
+    /// Shared geometry in local space.
+    pub fn geometry(&self) -> &Arc<dyn Geometry> {
+        &self.geometry
+    }
+
+    /// Offset of geometry from local to world space.
+    pub fn offset(&self) -> Vector3f {
+        self.offset
+    }
+
+    /// Center of geometry bounds in world space.
+    pub fn center(&self) -> Point3f {
+        let bounds = self.geometry.bounding_box();
+        bounds.min() + 0.5 * bounds.size() + self.offset
+    }
+
+    /// Moves shape such that center of geometry bounds is at given position.
+    pub fn set_center(&mut self, center: Point3f) {
+        self.offset += center - self.center();
+    }
+
+    /// Surface material.
+    pub fn material(&self) -> &Material {
+        &self.material
+    }
+
+    /// Sets surface material.
+    pub fn set_material(&mut self, material: Material) {
+        self.material = material;
+    }
+}
+
+impl PartialEq for Custom {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.geometry, &other.geometry)
+            && self.offset == other.offset
+            && self.material == other.material
+    }
+}
+
+impl Hash for Custom {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.geometry.hash_geometry(state);
+        self.offset.hash(state);
+        self.material.hash(state);
+    }
+}
+
+impl Intersectable for Custom {
+    fn intersect(&self, ray: Ray, ray_t: Interval) -> Option<Intersection<'_>> {
+        // Ray in local space, ray parameters stay the same.
+        let local = Ray::with_time(ray.origin() - self.offset, ray.direction(), ray.time());
+        let hit = self.geometry.hit(local, ray_t)?;
+        Some(Intersection {
+            point: ray.at(hit.t),
+            normal: hit.normal,
+            uv: hit.uv,
+            material: &self.material,
+            t: hit.t,
+            error: ray.error_at(hit.t),
+        })
+    }
+}
+
+impl Bounded for Custom {
+    fn bounding_box(&self) -> Aabb {
+        let bounds = self.geometry.bounding_box();
+        Aabb::new(bounds.min() + self.offset, bounds.max() + self.offset)
+    }
+}
+
+impl Sampleable for Custom {
+    fn area(&self) -> f32 {
+        self.geometry.area()
+    }
+
+    fn sample_point(&self, rng: &mut impl Rng) -> SurfaceSample {
+        let sample = self.geometry.sample_point(rng);
+        SurfaceSample {
+            point: sample.point + self.offset,
+            ..sample
+        }
+    }
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        base::{
+            color::Color3f,
+            hash::{hash_float, stable_hash},
+            shape::Shape,
+        },
+        materials::lambert::Lambert,
+        scene::Scene,
+        shapes::sphere::Sphere,
+    };
+    use rand::{rngs::StdRng, SeedableRng};
+    use std::f32::consts::PI;
+
+    /// Disk in y=0 plane around origin facing +y, as an example of a primitive the crate lacks.
+    #[derive(Debug)]
+    struct Disk {
+        /// Radius.
+        radius: f32,
+    }
+
+    impl Geometry for Disk {
+        fn hit(&self, ray: Ray, ray_t: Interval) -> Option<Hit> {
+            let t = -ray.origin().y() / ray.direction().y();
+            let p = ray.at(t);
+            if !ray_t.contains(t) || p.x() * p.x() + p.z() * p.z() > self.radius * self.radius {
+                return None;
+            }
+            Some(Hit {
+                t,
+                normal: Vector3f::new(0.0, 1.0, 0.0),
+                uv: (
+                    0.5 + 0.5 * p.x() / self.radius,
+                    0.5 + 0.5 * p.z() / self.radius,
+                ),
+            })
+        }
+
+        fn bounding_box(&self) -> Aabb {
+            let r = self.radius;
+            Aabb::new(Point3f::new(-r, -1e-4, -r), Point3f::new(r, 1e-4, r))
+        }
+
+        fn area(&self) -> f32 {
+            PI * self.radius * self.radius
+        }
+
+        fn sample_point(&self, rng: &mut dyn RngCore) -> SurfaceSample {
+            let r = self.radius * rng.gen::<f32>().sqrt();
+            let phi = 2.0 * PI * rng.gen::<f32>();
+            SurfaceSample {
+                point: Point3f::new(r * phi.cos(), 0.0, r * phi.sin()),
+                normal: Vector3f::new(0.0, 1.0, 0.0),
+                pdf: 1.0 / self.area(),
+            }
+        }
+
+        fn hash_geometry(&self, mut state: &mut dyn Hasher) {
+            hash_float(self.radius, &mut state);
+        }
+    }
+
+    #[test]
+    fn custom() {
+        // Disk among spheres, found through the bounding volume hierarchy.
+        let mut scene = Scene::new();
+        let gray = Material::Lambert(Lambert::new(Color3f::new(0.5, 0.5, 0.5)));
+        for i in 0..8 {
+            let center = Point3f::new(4.0 * i as f32, 1.0, -10.0);
+            scene.add(Shape::Sphere(Sphere::new(center, 1.0, gray.clone())));
+        }
+        let mut disk = Custom::new(Arc::new(Disk { radius: 1.0 }), gray);
+        disk.set_center(Point3f::new(5.0, 2.0, 0.0));
+        assert_eq!(disk.offset(), Vector3f::new(5.0, 2.0, 0.0));
+        let id = scene.add(Shape::Custom(disk.clone()));
+
+        // Picking hits disk at its offset, misses beside it.
+        let ray = Ray::new(Point3f::new(5.5, 4.0, 0.0), Vector3f::new(0.0, -1.0, 0.0));
+        let pick = scene.pick(ray).unwrap();
+        assert_eq!(pick.id, id);
+        assert!((pick.distance - 2.0).abs() < 1e-5);
+        assert_eq!(pick.normal, Vector3f::new(0.0, 1.0, 0.0));
+        let ray = Ray::new(Point3f::new(6.5, 4.0, 0.0), Vector3f::new(0.0, -1.0, 0.0));
+        assert!(scene.pick(ray).is_none());
+
+        // Samples lie on disk, clones are equal and hash alike.
+        let mut rng = StdRng::seed_from_u64(1);
+        let sample = disk.sample_point(&mut rng);
+        assert_eq!(sample.point.y(), 2.0);
+        assert!((sample.point - Point3f::new(5.0, 2.0, 0.0)).length() <= 1.0);
+        let shape = Shape::Custom(disk.clone());
+        assert_eq!(shape, Shape::Custom(disk.clone()));
+        let hash = stable_hash(&shape);
+        assert_eq!(hash, stable_hash(&Shape::Custom(disk)));
+    }
+}
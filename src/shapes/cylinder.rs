@@ -0,0 +1,356 @@
+use crate::base::{
+    aabb::Aabb,
+    hash::hash_float,
+    interval::Interval,
+    material::Material,
+    point::Point3f,
+    ray::Ray,
+    shape::{Bounded, Intersectable, Intersection, Sampleable, SurfaceSample},
+    vector::Vector3f,
+};
+use rand::Rng;
+use std::{
+    f32::consts::PI,
+    hash::{Hash, Hasher},
+};
+
+/// Finite cylinder in 3-dim space defined by center position, axis direction, radius and height, optionally closed
+/// by flat caps.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cylinder {
+    /// Center position (midpoint of axis).
+    center: Point3f,
+
+    /// Unit axis direction.
+    axis: Vector3f,
+
+    /// Cylinder radius.
+    radius: f32,
+
+    /// Length along axis.
+    height: f32,
+
+    /// Whether ends are closed by caps.
+    caps: bool,
+
+    /// Surface material.
+    material: Material,
+}
+
+impl Cylinder {
+    /// Creates closed cylinder with center position, axis direction, radius and height.
+    pub fn new(
+        center: Point3f,
+        axis: Vector3f,
+        radius: f32,
+        height: f32,
+        material: Material,
+    ) -> Self {
+        assert!(radius > 0.0 && height > 0.0 && !axis.near_zero());
+        Cylinder {
+            center,
+            axis: axis.normalize(),
+            radius,
+            height,
+            caps: true,
+            material,
+        }
+    }
+
+    /// Center position (midpoint of axis).
+    pub fn center(&self) -> Point3f {
+        self.center
+    }
+
+    /// Sets center position (midpoint of axis).
+    pub fn set_center(&mut self, center: Point3f) {
+        self.center = center;
+    }
+
+    /// Unit axis direction.
+    pub fn axis(&self) -> Vector3f {
+        self.axis
+    }
+
+    /// Cylinder radius.
+    pub fn radius(&self) -> f32 {
+        self.radius
+    }
+
+    /// Length along axis.
+    pub fn height(&self) -> f32 {
+        self.height
+    }
+
+    /// Whether ends are closed by caps.
+    pub fn caps(&self) -> bool {
+        self.caps
+    }
+
+    /// Sets whether ends are closed by caps. Open cylinders are tubes.
+    pub fn set_caps(&mut self, caps: bool) {
+        self.caps = caps;
+    }
+
+    /// Surface material.
+    pub fn material(&self) -> &Material {
+        &self.material
+    }
+
+    /// Sets surface material.
+    pub fn set_material(&mut self, material: Material) {
+        self.material = material;
+    }
+
+    /// Orthonormal basis with axis as third vector.
+    pub fn basis(&self) -> (Vector3f, Vector3f, Vector3f) {
+        let (u, v) = self.axis.orthonormal_basis();
+        (u, v, self.axis)
+    }
+
+    /// Area of side surface.
+    fn side_area(&self) -> f32 {
+        2.0 * PI * self.radius * self.height
+    }
+
+    /// Area of both caps, zero if open.
+    fn caps_area(&self) -> f32 {
+        if self.caps {
+            2.0 * PI * self.radius * self.radius
+        } else {
+            0.0
+        }
+    }
+}
+
+impl Hash for Cylinder {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.center.hash(state);
+        self.axis.hash(state);
+        hash_float(self.radius, state);
+        hash_float(self.height, state);
+        self.caps.hash(state);
+        self.material.hash(state);
+    }
+}
+
+impl Intersectable for Cylinder {
+    fn intersect(&self, ray: Ray, ray_t: Interval) -> Option<Intersection<'_>> {
+        // Ray in local frame with axis along z.
+        let (u, v, w) = self.basis();
+        let oc = ray.origin() - self.center;
+        let d = ray.direction();
+        let (ox, oy, oz) = (oc.dot(&u), oc.dot(&v), oc.dot(&w));
+        let (dx, dy, dz) = (d.dot(&u), d.dot(&v), d.dot(&w));
+        let half_height = 0.5 * self.height;
+
+        // Nearest hit in interval, as ray parameter, local normal and surface coordinates.
+        let mut nearest: Option<(f32, Vector3f, (f32, f32))> = None;
+        let mut closest_t = ray_t.end();
+
+        // Side: solve quadratic equation of infinite cylinder, keep roots within height.
+        let a = dx * dx + dy * dy;
+        let half_b = ox * dx + oy * dy;
+        let c = ox * ox + oy * oy - self.radius * self.radius;
+        let discriminant = half_b * half_b - a * c;
+        if a > 0.0 && discriminant >= 0.0 {
+            let discriminant_sqrt = discriminant.sqrt();
+            for root in [
+                (-half_b - discriminant_sqrt) / a,
+                (-half_b + discriminant_sqrt) / a,
+            ] {
+                let z = oz + root * dz;
+                if Interval::new(ray_t.start(), closest_t).contains(root) && z.abs() <= half_height
+                {
+                    let (x, y) = (ox + root * dx, oy + root * dy);
+                    let phi = y.atan2(x);
+                    let uv = (
+                        (phi / (2.0 * PI)).rem_euclid(1.0),
+                        (z + half_height) / self.height,
+                    );
+                    let normal = Vector3f::new(x, y, 0.0) / self.radius;
+                    nearest = Some((root, normal, uv));
+                    closest_t = root;
+                    break;
+                }
+            }
+        }
+
+        // Caps: intersect end planes, keep hits within radius.
+        if self.caps && dz != 0.0 {
+            for side in [-1.0, 1.0] {
+                let root = (side * half_height - oz) / dz;
+                let (x, y) = (ox + root * dx, oy + root * dy);
+                if Interval::new(ray_t.start(), closest_t).contains(root)
+                    && x * x + y * y <= self.radius * self.radius
+                {
+                    let uv = (0.5 + 0.5 * x / self.radius, 0.5 + 0.5 * y / self.radius);
+                    nearest = Some((root, Vector3f::new(0.0, 0.0, side), uv));
+                    closest_t = root;
+                }
+            }
+        }
+
+        // Return intersection struct with normal in world frame.
+        let (t, n, uv) = nearest?;
+        let intersection = Intersection {
+            point: ray.at(t),
+            material: &self.material,
+            normal: n.x() * u + n.y() * v + n.z() * w,
+            uv,
+            t,
+            error: ray.error_at(t),
+        };
+        Some(intersection)
+    }
+}
+
+impl Bounded for Cylinder {
+    fn bounding_box(&self) -> Aabb {
+        // Per axis, half extent of axis segment plus extent of cap disk.
+        let extent =
+            |a: f32| 0.5 * self.height * a.abs() + self.radius * (1.0 - a * a).max(0.0).sqrt();
+        let a = self.axis;
+        let e = Vector3f::new(extent(a.x()), extent(a.y()), extent(a.z()));
+        Aabb::new(self.center - e, self.center + e)
+    }
+}
+
+impl Sampleable for Cylinder {
+    fn area(&self) -> f32 {
+        self.side_area() + self.caps_area()
+    }
+
+    fn sample_point(&self, rng: &mut impl Rng) -> SurfaceSample {
+        let (u, v, w) = self.basis();
+        let phi = 2.0 * PI * rng.gen::<f32>();
+        let radial = phi.cos() * u + phi.sin() * v;
+
+        // Pick side or cap proportional to area.
+        let (point, normal) = if rng.gen::<f32>() * self.area() < self.side_area() {
+            let z = self.height * (rng.gen::<f32>() - 0.5);
+            (self.center + self.radius * radial + z * w, radial)
+        } else {
+            let side = if rng.gen::<bool>() { 1.0 } else { -1.0 };
+            let r = self.radius * rng.gen::<f32>().sqrt();
+            let point = self.center + r * radial + (side * 0.5 * self.height) * w;
+            (point, side * w)
+        };
+
+        // Return surface sample struct.
+        SurfaceSample {
+            point,
+            normal,
+            pdf: 1.0 / self.area(),
+        }
+    }
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    /// Closed cylinder along y-axis from y=0 to y=2 with radius 1.
+    fn cylinder() -> Cylinder {
+        Cylinder::new(
+            Point3f::new(0.0, 1.0, 0.0),
+            Vector3f::new(0.0, 1.0, 0.0),
+            1.0,
+            2.0,
+            Material::None,
+        )
+    }
+
+    #[test]
+    fn intersect() {
+        let c = cylinder();
+        let all = Interval::new(0.0, f32::INFINITY);
+
+        // Side hit, from outside and inside.
+        let r1 = Ray::new(Point3f::new(-3.0, 0.5, 0.0), Vector3f::new(1.0, 0.0, 0.0));
+        let i1 = c.intersect(r1, all).unwrap();
+        assert!((i1.t - 2.0).abs() < 1e-6);
+        assert!((i1.normal - Vector3f::new(-1.0, 0.0, 0.0)).length() < 1e-6);
+        assert!((i1.uv.1 - 0.25).abs() < 1e-6);
+        let i2 = c.intersect(r1, Interval::new(2.5, f32::INFINITY)).unwrap();
+        assert!((i2.t - 4.0).abs() < 1e-6);
+        assert!((i2.normal - Vector3f::new(1.0, 0.0, 0.0)).length() < 1e-6);
+
+        // Cap hits from above and below.
+        let r2 = Ray::new(Point3f::new(0.5, 5.0, 0.0), Vector3f::new(0.0, -1.0, 0.0));
+        let i3 = c.intersect(r2, all).unwrap();
+        assert!((i3.t - 3.0).abs() < 1e-6);
+        assert_eq!(i3.normal, Vector3f::new(0.0, 1.0, 0.0));
+        let (cu, cv) = (i3.uv.0 - 0.5, i3.uv.1 - 0.5);
+        assert!(((cu * cu + cv * cv).sqrt() - 0.25).abs() < 1e-6);
+        let i4 = c.intersect(r2, Interval::new(3.5, f32::INFINITY)).unwrap();
+        assert!((i4.t - 5.0).abs() < 1e-6);
+        assert_eq!(i4.normal, Vector3f::new(0.0, -1.0, 0.0));
+
+        // Open tube lets axial rays pass, misses beyond ends or radius.
+        let mut tube = c.clone();
+        tube.set_caps(false);
+        assert_eq!(tube.intersect(r2, all), None);
+        let above = Ray::new(Point3f::new(-3.0, 2.5, 0.0), Vector3f::new(1.0, 0.0, 0.0));
+        assert_eq!(c.intersect(above, all), None);
+        let beside = Ray::new(Point3f::new(1.5, 5.0, 0.0), Vector3f::new(0.0, -1.0, 0.0));
+        assert_eq!(c.intersect(beside, all), None);
+    }
+
+    #[test]
+    fn tilted() {
+        // Points hit on tilted cylinder lie on its surface, normals point outwards.
+        let axis = Vector3f::new(1.0, 2.0, -1.0).normalize();
+        let c = Cylinder::new(Point3f::new(1.0, 2.0, 3.0), axis, 0.5, 3.0, Material::None);
+        let mut rng = StdRng::seed_from_u64(42);
+        for _ in 0..100 {
+            let target = c.center() + Vector3f::random_unit_vector(&mut rng) * 0.4;
+            let origin = target + 10.0 * Vector3f::random_unit_vector(&mut rng);
+            let ray = Ray::new(origin, target - origin);
+            let isect = c.intersect(ray, Interval::new(0.0, f32::INFINITY)).unwrap();
+            let local = isect.point - c.center();
+            let along = local.dot(&axis);
+            let distance = (local - along * axis).length();
+            let on_side = (distance - 0.5).abs() < 1e-4 && along.abs() <= 1.5 + 1e-4;
+            let on_cap = (along.abs() - 1.5).abs() < 1e-4 && distance <= 0.5 + 1e-4;
+            assert!(on_side || on_cap);
+            assert!(isect.normal.dot(&(origin - isect.point)) > 0.0);
+            assert!((0.0..=1.0).contains(&isect.uv.0) && (0.0..=1.0).contains(&isect.uv.1));
+        }
+    }
+
+    #[test]
+    fn sample_point() {
+        let c = cylinder();
+        assert!((c.area() - 6.0 * PI).abs() < 1e-5);
+        let mut rng = StdRng::seed_from_u64(42);
+        for _ in 0..100 {
+            let sample = c.sample_point(&mut rng);
+            let p = sample.point;
+            let distance = (p.x() * p.x() + p.z() * p.z()).sqrt();
+            let on_side = (distance - 1.0).abs() < 1e-5 && (0.0..=2.0).contains(&p.y());
+            let on_cap = (p.y() == 0.0 || p.y() == 2.0) && distance <= 1.0 + 1e-5;
+            assert!(on_side || on_cap);
+            assert!((sample.normal.length() - 1.0).abs() < 1e-5);
+            assert_eq!(sample.pdf, 1.0 / c.area());
+        }
+    }
+
+    #[test]
+    fn bounding_box() {
+        let b = cylinder().bounding_box();
+        assert_eq!(b.min(), Point3f::new(-1.0, 0.0, -1.0));
+        assert_eq!(b.max(), Point3f::new(1.0, 2.0, 1.0));
+        let tilted = Cylinder::new(
+            Point3f::default(),
+            Vector3f::new(1.0, 1.0, 0.0),
+            1.0,
+            2.0,
+            Material::None,
+        );
+        let e = 2.0f32.sqrt();
+        assert!((tilted.bounding_box().max() - Point3f::new(e, e, 1.0)).length() < 1e-5);
+    }
+}
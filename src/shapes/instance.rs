@@ -0,0 +1,305 @@
+use crate::{
+    base::{
+        aabb::Aabb,
+        interval::Interval,
+        material::Material,
+        point::Point3f,
+        ray::Ray,
+        shape::{Bounded, Intersectable, Intersection, Sampleable, Shape, SurfaceSample},
+        transform::Transform,
+    },
+    bvh::Bvh,
+    scene::Scene,
+};
+use rand::Rng;
+use std::{
+    hash::{Hash, Hasher},
+    sync::Arc,
+};
+
+/// Maximum count of objects per leaf of prototype hierarchies.
+const MAX_LEAF_SIZE: usize = 4;
+
+/// Group of objects in local space, e.g. a loaded asset, built once with its own bounding volume hierarchy and
+/// shared by any number of instances.
+#[derive(Clone, Debug)]
+pub struct Prototype {
+    /// Objects in local space.
+    objects: Vec<Shape>,
+
+    /// Acceleration structure over objects.
+    bvh: Bvh,
+
+    /// Bounds of all objects.
+    bounds: Aabb,
+
+    /// Total surface area of objects.
+    area: f32,
+}
+
+impl Prototype {
+    /// Creates prototype of objects, which must not be empty.
+    pub fn new(objects: Vec<Shape>) -> Self {
+        assert!(!objects.is_empty(), "prototype must have objects");
+        let bounds = objects
+            .iter()
+            .map(Bounded::bounding_box)
+            .reduce(|a, b| a.union(&b))
+            .unwrap();
+        Prototype {
+            bvh: Bvh::build(&objects, MAX_LEAF_SIZE, true),
+            area: objects.iter().map(Sampleable::area).sum(),
+            objects,
+            bounds,
+        }
+    }
+
+    /// Creates prototype of all objects of scene, e.g. of an imported stage. Lights and background are not part of
+    /// it.
+    pub fn from_scene(scene: &Scene) -> Self {
+        Prototype::new(scene.objects().to_vec())
+    }
+
+    /// Objects in local space.
+    pub fn objects(&self) -> &[Shape] {
+        &self.objects
+    }
+
+    /// Bounds of all objects in local space.
+    pub fn bounds(&self) -> Aabb {
+        self.bounds
+    }
+}
+
+impl PartialEq for Prototype {
+    fn eq(&self, other: &Self) -> bool {
+        self.objects == other.objects
+    }
+}
+
+impl Hash for Prototype {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.objects.hash(state);
+    }
+}
+
+/// Placement of shared prototype in scene by affine transform, optionally overriding the materials of all its
+/// objects. Rays are transformed into prototype space rather than objects into world space, so instances of large
+/// prototypes cost no more memory than a single shape.
+///
+/// Area and point samples scale areas by the mean axis scale squared, thus they are exact for transforms of uniform
+/// scale only.
+#[derive(Clone, Debug, Hash, PartialEq)]
+pub struct Instance {
+    /// Shared objects in local space.
+    prototype: Arc<Prototype>,
+
+    /// Transform from local to world space.
+    transform: Transform,
+
+    /// Transform from world to local space.
+    inverse: Transform,
+
+    /// Material replacing those of prototype objects, if any.
+    material: Option<Material>,
+}
+
+impl Instance {
+    /// Creates instance of prototype placed by transform, which must be invertible.
+    pub fn new(prototype: Arc<Prototype>, transform: Transform) -> Self {
+        let inverse = transform
+            .inverse()
+            .expect("instance transform must be invertible");
+        Instance {
+            prototype,
+            transform,
+            inverse,
+            material: None,
+        }
+    }
+
+    /// Shared prototype.
+    pub fn prototype(&self) -> &Arc<Prototype> {
+        &self.prototype
+    }
+
+    /// Transform from local to world space.
+    pub fn transform(&self) -> Transform {
+        self.transform
+    }
+
+    /// Material override, if any.
+    pub fn material_override(&self) -> Option<&Material> {
+        self.material.as_ref()
+    }
+
+    /// Center of prototype bounds in world space.
+    pub fn center(&self) -> Point3f {
+        let bounds = self.prototype.bounds;
+        self.transform.point(bounds.min() + 0.5 * bounds.size())
+    }
+
+    /// Moves instance such that center of prototype bounds is at given position.
+    pub fn set_center(&mut self, center: Point3f) {
+        let offset = center - self.center();
+        self.transform = Transform::translation(offset).compose(&self.transform);
+        self.inverse = self.transform.inverse().unwrap();
+    }
+
+    /// Material override, or material of first prototype object without one.
+    pub fn material(&self) -> &Material {
+        self.material
+            .as_ref()
+            .unwrap_or_else(|| self.prototype.objects[0].material())
+    }
+
+    /// Overrides materials of all prototype objects.
+    pub fn set_material(&mut self, material: Material) {
+        self.material = Some(material);
+    }
+
+    /// Factor of local to world surface areas.
+    fn area_scale(&self) -> f32 {
+        let scale = self.transform.scale();
+        scale * scale
+    }
+}
+
+impl Intersectable for Instance {
+    fn intersect(&self, ray: Ray, ray_t: Interval) -> Option<Intersection<'_>> {
+        // Unit ray in local space, with ray parameters scaled to match.
+        let direction = self.inverse.vector(ray.direction());
+        let scale = direction.length();
+        let local = Ray::with_time(
+            self.inverse.point(ray.origin()),
+            direction / scale,
+            ray.time(),
+        );
+        let local_t = Interval::new(ray_t.start() * scale, ray_t.end() * scale);
+        let (_, isect) = self
+            .prototype
+            .bvh
+            .intersect(&self.prototype.objects, local, local_t)?;
+        Some(Intersection {
+            point: self.transform.point(isect.point),
+            normal: self.transform.normal(isect.normal),
+            uv: isect.uv,
+            material: self.material.as_ref().unwrap_or(isect.material),
+            t: isect.t / scale,
+            error: self.transform.point_error(isect.point, isect.error),
+        })
+    }
+}
+
+impl Bounded for Instance {
+    fn bounding_box(&self) -> Aabb {
+        let corners = self
+            .prototype
+            .bounds
+            .corners()
+            .map(|c| self.transform.point(c));
+        corners[1..]
+            .iter()
+            .fold(Aabb::new(corners[0], corners[0]), |bounds, &c| {
+                bounds.union(&Aabb::new(c, c))
+            })
+    }
+}
+
+impl Sampleable for Instance {
+    fn area(&self) -> f32 {
+        self.prototype.area * self.area_scale()
+    }
+
+    fn sample_point(&self, rng: &mut impl Rng) -> SurfaceSample {
+        // Pick object proportional to area.
+        let objects = &self.prototype.objects;
+        let mut target = rng.gen::<f32>() * self.prototype.area;
+        let object = objects
+            .iter()
+            .find(|o| {
+                target -= o.area();
+                target < 0.0
+            })
+            .unwrap_or(&objects[objects.len() - 1]);
+        let sample = object.sample_point(rng);
+        SurfaceSample {
+            point: self.transform.point(sample.point),
+            normal: self.transform.normal(sample.normal),
+            pdf: 1.0 / self.area(),
+        }
+    }
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        base::{color::Color3f, vector::Vector3f},
+        materials::lambert::Lambert,
+        shapes::sphere::Sphere,
+    };
+    use rand::{rngs::StdRng, SeedableRng};
+
+    /// Prototype of two unit spheres at x=-1 and x=1.
+    fn prototype() -> Arc<Prototype> {
+        let sphere =
+            |x: f32| Shape::Sphere(Sphere::new(Point3f::new(x, 0.0, 0.0), 1.0, Material::None));
+        Arc::new(Prototype::new(vec![sphere(-1.0), sphere(1.0)]))
+    }
+
+    #[test]
+    fn intersect() {
+        // Prototype scaled by two, rotated to stand along y and moved up by ten.
+        let transform = Transform::translation(Vector3f::new(0.0, 10.0, 0.0))
+            .compose(&Transform::rotation(2, 90.0))
+            .compose(&Transform::scaling(2.0, 2.0, 2.0));
+        let mut instance = Instance::new(prototype(), transform);
+        let ray = Ray::new(Point3f::new(0.0, 12.0, -10.0), Vector3f::new(0.0, 0.0, 2.0));
+        let isect = instance
+            .intersect(ray, Interval::new(0.0, f32::INFINITY))
+            .unwrap();
+        assert!((isect.t - 4.0).abs() < 1e-4);
+        assert!((isect.point - Point3f::new(0.0, 12.0, -2.0)).length() < 1e-4);
+        assert!((isect.normal - Vector3f::new(0.0, 0.0, -1.0)).length() < 1e-4);
+        assert_eq!(isect.material, &Material::None);
+        assert!(instance.intersect(ray, Interval::new(0.0, 3.9)).is_none());
+
+        // Material override applies to all objects.
+        let red = Material::Lambert(Lambert::new(Color3f::new(1.0, 0.0, 0.0)));
+        instance.set_material(red.clone());
+        let isect = instance
+            .intersect(ray, Interval::new(0.0, f32::INFINITY))
+            .unwrap();
+        assert_eq!(isect.material, &red);
+    }
+
+    #[test]
+    fn bounding_box() {
+        let transform = Transform::translation(Vector3f::new(0.0, 10.0, 0.0))
+            .compose(&Transform::rotation(2, 90.0));
+        let mut instance = Instance::new(prototype(), transform);
+        let bounds = instance.bounding_box();
+        assert!((bounds.min() - Point3f::new(-1.0, 8.0, -1.0)).length() < 1e-5);
+        assert!((bounds.max() - Point3f::new(1.0, 12.0, 1.0)).length() < 1e-5);
+        instance.set_center(Point3f::default());
+        assert!((instance.center() - Point3f::default()).length() < 1e-5);
+    }
+
+    #[test]
+    fn sample_point() {
+        // Samples lie on transformed spheres with outward normals.
+        let transform = Transform::translation(Vector3f::new(0.0, 0.0, 5.0))
+            .compose(&Transform::scaling(3.0, 3.0, 3.0));
+        let instance = Instance::new(prototype(), transform);
+        assert!((instance.area() - 2.0 * 4.0 * std::f32::consts::PI * 9.0).abs() < 1e-3);
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..100 {
+            let sample = instance.sample_point(&mut rng);
+            let center = Point3f::new(3.0 * sample.point.x().signum(), 0.0, 5.0);
+            assert!(((sample.point - center).length() - 3.0).abs() < 1e-4);
+            assert!((sample.normal - (sample.point - center) / 3.0).length() < 1e-4);
+        }
+    }
+}
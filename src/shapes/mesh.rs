@@ -0,0 +1,608 @@
+use crate::base::{
+    aabb::Aabb,
+    interval::Interval,
+    material::Material,
+    point::{Point3f, Uv},
+    ray::{gamma, Ray},
+    sampling::uniform_triangle,
+    shape::{Bounded, Intersectable, Intersection, Sampleable, SurfaceSample},
+    vector::Vector3f,
+    winding,
+};
+use rand::Rng;
+use std::hash::{Hash, Hasher};
+
+/// Max count of triangles in a leaf of the triangle hierarchy.
+const LEAF_SIZE: usize = 4;
+
+/// Triangle mesh in 3-dim space, with optional shading normals and surface coordinates per triangle corner. Front
+/// faces wind counter-clockwise, their geometric normals point outwards. Triangles are intersected through a
+/// bounding volume hierarchy built along with the mesh.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mesh {
+    /// Vertex positions.
+    points: Vec<Point3f>,
+
+    /// Point indices of triangle corners.
+    triangles: Vec<[usize; 3]>,
+
+    /// Shading normals with their indices per triangle corner, if any.
+    normals: Option<(Vec<Vector3f>, Vec<[usize; 3]>)>,
+
+    /// Surface coordinates with their indices per triangle corner, if any.
+    uvs: Option<(Vec<Uv>, Vec<[usize; 3]>)>,
+
+    /// Surface material.
+    material: Material,
+
+    /// Nodes of triangle hierarchy in depth-first order, root first.
+    nodes: Vec<MeshNode>,
+
+    /// Triangle indices, grouped by leaf.
+    order: Vec<usize>,
+
+    /// Cumulative triangle areas, for sampling triangles by area.
+    cumulative_area: Vec<f32>,
+}
+
+/// Node of triangle hierarchy.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct MeshNode {
+    /// Bounds of triangles below node.
+    bounds: Aabb,
+
+    /// Start of leaf triangles in order, or index of second child of interior node (the first follows the node).
+    start: usize,
+
+    /// Count of leaf triangles, zero for interior nodes.
+    count: usize,
+}
+
+impl Mesh {
+    /// Creates mesh of points and triangles given by point indices of their corners.
+    pub fn new(points: Vec<Point3f>, triangles: Vec<[usize; 3]>, material: Material) -> Self {
+        assert!(!triangles.is_empty());
+        assert!(triangles.iter().flatten().all(|&i| i < points.len()));
+        let mut mesh = Mesh {
+            points,
+            triangles,
+            normals: None,
+            uvs: None,
+            material,
+            nodes: Vec::new(),
+            order: Vec::new(),
+            cumulative_area: Vec::new(),
+        };
+        mesh.build();
+        mesh
+    }
+
+    /// Creates mesh of a single triangle.
+    pub fn triangle(a: Point3f, b: Point3f, c: Point3f, material: Material) -> Self {
+        Mesh::new(vec![a, b, c], vec![[0, 1, 2]], material)
+    }
+
+    /// Creates parallelogram quad of two triangles spanned by edges u and v from corner, facing towards u×v.
+    /// Surface coordinates run from 0 to 1 along the edges.
+    pub fn quad(corner: Point3f, u: Vector3f, v: Vector3f, material: Material) -> Self {
+        let points = vec![corner, corner + u, corner + u + v, corner + v];
+        let triangles = vec![[0, 1, 2], [0, 2, 3]];
+        let mut mesh = Mesh::new(points, triangles.clone(), material);
+        let uvs = vec![
+            Uv::new(0.0, 0.0),
+            Uv::new(1.0, 0.0),
+            Uv::new(1.0, 1.0),
+            Uv::new(0.0, 1.0),
+        ];
+        mesh.set_uvs(uvs, triangles);
+        mesh
+    }
+
+    /// Vertex positions.
+    pub fn points(&self) -> &[Point3f] {
+        &self.points
+    }
+
+    /// Point indices of triangle corners.
+    pub fn triangles(&self) -> &[[usize; 3]] {
+        &self.triangles
+    }
+
+    /// Shading normals with their indices per triangle corner, if any.
+    pub fn normals(&self) -> Option<(&[Vector3f], &[[usize; 3]])> {
+        self.normals
+            .as_ref()
+            .map(|(n, i)| (n.as_slice(), i.as_slice()))
+    }
+
+    /// Sets shading normals, interpolated over triangles from normals at corners given by indices per triangle,
+    /// e.g. the point indices for normals per vertex.
+    pub fn set_normals(&mut self, normals: Vec<Vector3f>, indices: Vec<[usize; 3]>) {
+        assert_eq!(indices.len(), self.triangles.len());
+        assert!(indices.iter().flatten().all(|&i| i < normals.len()));
+        let normals = normals.into_iter().map(Vector3f::normalize).collect();
+        self.normals = Some((normals, indices));
+    }
+
+    /// Surface coordinates with their indices per triangle corner, if any.
+    pub fn uvs(&self) -> Option<(&[Uv], &[[usize; 3]])> {
+        self.uvs
+            .as_ref()
+            .map(|(uv, i)| (uv.as_slice(), i.as_slice()))
+    }
+
+    /// Sets surface coordinates, interpolated over triangles from coordinates at corners given by indices per
+    /// triangle. Without, triangles are parametrized by barycentric coordinates.
+    pub fn set_uvs(&mut self, uvs: Vec<Uv>, indices: Vec<[usize; 3]>) {
+        assert_eq!(indices.len(), self.triangles.len());
+        assert!(indices.iter().flatten().all(|&i| i < uvs.len()));
+        self.uvs = Some((uvs, indices));
+    }
+
+    /// Orients triangles of closed parts consistently outwards, see [`winding::orient`], flipping shading normals and
+    /// surface coordinates along. Returns count of flipped triangles.
+    pub fn orient(&mut self) -> usize {
+        let flipped = winding::orient(&self.points, &mut self.triangles);
+        for &t in &flipped {
+            if let Some((_, indices)) = &mut self.normals {
+                indices[t].swap(1, 2);
+            }
+            if let Some((_, indices)) = &mut self.uvs {
+                indices[t].swap(1, 2);
+            }
+        }
+        flipped.len()
+    }
+
+    /// Center of bounding box.
+    pub fn center(&self) -> Point3f {
+        let b = self.bounding_box();
+        b.min() + 0.5 * b.size()
+    }
+
+    /// Moves mesh to center position of bounding box.
+    pub fn set_center(&mut self, center: Point3f) {
+        let offset = center - self.center();
+        for p in self.points.iter_mut() {
+            *p = *p + offset;
+        }
+        for node in self.nodes.iter_mut() {
+            node.bounds = Aabb::new(node.bounds.min() + offset, node.bounds.max() + offset);
+        }
+    }
+
+    /// Surface material.
+    pub fn material(&self) -> &Material {
+        &self.material
+    }
+
+    /// Sets surface material.
+    pub fn set_material(&mut self, material: Material) {
+        self.material = material;
+    }
+
+    /// Corner points of triangle.
+    pub fn corners(&self, triangle: usize) -> [Point3f; 3] {
+        self.triangles[triangle].map(|i| self.points[i])
+    }
+
+    /// Unnormalized geometric normal of triangle, with length of twice its area.
+    fn cross(&self, triangle: usize) -> Vector3f {
+        let [a, b, c] = self.corners(triangle);
+        (b - a).cross(&(c - a))
+    }
+
+    /// Builds triangle hierarchy and cumulative areas.
+    fn build(&mut self) {
+        let mut total = 0.0;
+        self.cumulative_area = (0..self.triangles.len())
+            .map(|i| {
+                total += 0.5 * self.cross(i).length();
+                total
+            })
+            .collect();
+
+        let bounds: Vec<Aabb> = (0..self.triangles.len())
+            .map(|i| {
+                let [a, b, c] = self.corners(i);
+                Aabb::new(a, b).union(&Aabb::new(c, c))
+            })
+            .collect();
+        self.order = (0..self.triangles.len()).collect();
+        self.nodes.clear();
+        let mut order = std::mem::take(&mut self.order);
+        self.build_node(&bounds, &mut order, 0);
+        self.order = order;
+    }
+
+    /// Builds node over triangles of order from start, splitting at the median along the longest axis of their
+    /// centroids. Returns index of node.
+    fn build_node(&mut self, bounds: &[Aabb], order: &mut [usize], start: usize) -> usize {
+        let index = self.nodes.len();
+        let node_bounds = order
+            .iter()
+            .map(|&i| bounds[i])
+            .reduce(|a, b| a.union(&b))
+            .unwrap();
+        self.nodes.push(MeshNode {
+            bounds: node_bounds,
+            start,
+            count: order.len(),
+        });
+        if order.len() <= LEAF_SIZE {
+            return index;
+        }
+
+        let centroid = |i: usize| bounds[i].min() + 0.5 * bounds[i].size();
+        let centroids = order
+            .iter()
+            .map(|&i| Aabb::new(centroid(i), centroid(i)))
+            .reduce(|a, b| a.union(&b))
+            .unwrap();
+        let axis = centroids.size().max_dimension();
+        let middle = order.len() / 2;
+        let coordinate = |i: usize| (centroid(i) - Point3f::default())[axis];
+        order.select_nth_unstable_by(middle, |&a, &b| coordinate(a).total_cmp(&coordinate(b)));
+
+        let (first, second) = order.split_at_mut(middle);
+        self.build_node(bounds, first, start);
+        let second = self.build_node(bounds, second, start + middle);
+        self.nodes[index].start = second;
+        self.nodes[index].count = 0;
+        index
+    }
+
+    /// Intersects ray with triangle (Möller-Trumbore). Returns ray parameter and barycentric coordinates of the
+    /// second and third corner.
+    fn intersect_triangle(
+        &self,
+        triangle: usize,
+        ray: &Ray,
+        ray_t: Interval,
+    ) -> Option<(f32, f32, f32)> {
+        let [a, b, c] = self.corners(triangle);
+        let (e1, e2) = (b - a, c - a);
+        let p = ray.direction().cross(&e2);
+        let determinant = e1.dot(&p);
+        if determinant == 0.0 || !determinant.is_finite() {
+            return None;
+        }
+        let inverse = 1.0 / determinant;
+        let s = ray.origin() - a;
+        let u = s.dot(&p) * inverse;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+        let q = s.cross(&e1);
+        let v = ray.direction().dot(&q) * inverse;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+        let t = e2.dot(&q) * inverse;
+        ray_t.contains(t).then_some((t, u, v))
+    }
+}
+
+impl Hash for Mesh {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.points.hash(state);
+        self.triangles.hash(state);
+        if let Some((normals, indices)) = &self.normals {
+            normals.hash(state);
+            indices.hash(state);
+        }
+        if let Some((uvs, indices)) = &self.uvs {
+            uvs.hash(state);
+            indices.hash(state);
+        }
+        self.material.hash(state);
+    }
+}
+
+impl Intersectable for Mesh {
+    fn intersect(&self, ray: Ray, ray_t: Interval) -> Option<Intersection<'_>> {
+        // Depth-first traversal, nearest hit shrinking the interval.
+        let mut nearest = None;
+        let mut closest_t = ray_t.end();
+        let mut stack = vec![0];
+        while let Some(index) = stack.pop() {
+            let node = self.nodes[index];
+            if node
+                .bounds
+                .hit(ray, Interval::new(ray_t.start(), closest_t))
+                .is_none()
+            {
+                continue;
+            }
+            if node.count == 0 {
+                stack.push(node.start);
+                stack.push(index + 1);
+                continue;
+            }
+            for &triangle in &self.order[node.start..node.start + node.count] {
+                let interval = Interval::new(ray_t.start(), closest_t);
+                if let Some((t, u, v)) = self.intersect_triangle(triangle, &ray, interval) {
+                    nearest = Some((triangle, t, u, v));
+                    closest_t = t;
+                }
+            }
+        }
+
+        // Interpolate attributes at hit, shading normal on side of geometric normal.
+        let (triangle, t, u, v) = nearest?;
+        let weights = [1.0 - u - v, u, v];
+        let [a, b, c] = self.corners(triangle);
+        let point = Point3f::default()
+            + weights[0] * (a - Point3f::default())
+            + weights[1] * (b - Point3f::default())
+            + weights[2] * (c - Point3f::default());
+        let error = gamma(7)
+            * ((weights[0] * (a - Point3f::default())).abs()
+                + (weights[1] * (b - Point3f::default())).abs()
+                + (weights[2] * (c - Point3f::default())).abs());
+        let geometric = self.cross(triangle).normalize();
+        let normal = match &self.normals {
+            Some((normals, indices)) => {
+                let [na, nb, nc] = indices[triangle].map(|i| normals[i]);
+                let n = (weights[0] * na + weights[1] * nb + weights[2] * nc).normalize();
+                match n.dot(&geometric) {
+                    d if d.is_nan() => geometric,
+                    d if d < 0.0 => -n,
+                    _ => n,
+                }
+            }
+            None => geometric,
+        };
+        let uv = match &self.uvs {
+            Some((uvs, indices)) => {
+                let corners = indices[triangle].map(|i| uvs[i]);
+                let interpolate =
+                    |f: fn(&Uv) -> f32| (0..3).map(|i| weights[i] * f(&corners[i])).sum();
+                (interpolate(Uv::u), interpolate(Uv::v))
+            }
+            None => (u, v),
+        };
+
+        // Return intersection struct.
+        let intersection = Intersection {
+            point,
+            normal,
+            uv,
+            material: &self.material,
+            t,
+            error,
+        };
+        Some(intersection)
+    }
+}
+
+impl Bounded for Mesh {
+    fn bounding_box(&self) -> Aabb {
+        self.nodes[0].bounds
+    }
+}
+
+impl Sampleable for Mesh {
+    fn area(&self) -> f32 {
+        *self.cumulative_area.last().unwrap()
+    }
+
+    fn sample_point(&self, rng: &mut impl Rng) -> SurfaceSample {
+        // Pick triangle proportional to area, then uniform point on it.
+        let target = rng.gen::<f32>() * self.area();
+        let triangle = self
+            .cumulative_area
+            .partition_point(|&a| a <= target)
+            .min(self.triangles.len() - 1);
+        let (b0, b1) = uniform_triangle(rng);
+        let [a, b, c] = self.corners(triangle);
+        SurfaceSample {
+            point: c + b0 * (a - c) + b1 * (b - c),
+            normal: self.cross(triangle).normalize(),
+            pdf: 1.0 / self.area(),
+        }
+    }
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    /// Unit cube from origin to (1,1,1), two triangles per face wound counter-clockwise seen from outside.
+    fn cube() -> Mesh {
+        let points = (0..8)
+            .map(|i| Point3f::new((i & 1) as f32, (i >> 1 & 1) as f32, (i >> 2 & 1) as f32))
+            .collect();
+        let quads = [
+            [0, 2, 3, 1],
+            [4, 5, 7, 6],
+            [0, 1, 5, 4],
+            [2, 6, 7, 3],
+            [0, 4, 6, 2],
+            [1, 3, 7, 5],
+        ];
+        let triangles = quads
+            .iter()
+            .flat_map(|&[a, b, c, d]| [[a, b, c], [a, c, d]])
+            .collect();
+        Mesh::new(points, triangles, Material::None)
+    }
+
+    #[test]
+    fn intersect() {
+        let m = cube();
+        let all = Interval::new(0.0, f32::INFINITY);
+
+        // Hits from outside and inside, normals pointing outwards.
+        let r1 = Ray::new(Point3f::new(-2.0, 0.25, 0.5), Vector3f::new(1.0, 0.0, 0.0));
+        let i1 = m.intersect(r1, all).unwrap();
+        assert!((i1.t - 2.0).abs() < 1e-6);
+        assert_eq!(i1.normal, Vector3f::new(-1.0, 0.0, 0.0));
+        assert!((i1.point - Point3f::new(0.0, 0.25, 0.5)).length() < 1e-6);
+        let i2 = m.intersect(r1, Interval::new(2.5, f32::INFINITY)).unwrap();
+        assert!((i2.t - 3.0).abs() < 1e-6);
+        assert_eq!(i2.normal, Vector3f::new(1.0, 0.0, 0.0));
+
+        // Misses beside and behind.
+        let beside = Ray::new(Point3f::new(-2.0, 1.5, 0.5), Vector3f::new(1.0, 0.0, 0.0));
+        assert_eq!(m.intersect(beside, all), None);
+        assert_eq!(m.intersect(r1, Interval::new(0.0, 1.0)), None);
+
+        // Random rays towards the inside hit the surface.
+        let mut rng = StdRng::seed_from_u64(42);
+        for _ in 0..100 {
+            let target = Point3f::new(0.5, 0.5, 0.5) + 0.4 * Vector3f::random_unit_vector(&mut rng);
+            let origin = target + 10.0 * Vector3f::random_unit_vector(&mut rng);
+            let isect = m.intersect(Ray::new(origin, target - origin), all).unwrap();
+            let p = isect.point;
+            let on_face = [p.x(), p.y(), p.z()]
+                .iter()
+                .any(|&x| x.abs() < 1e-5 || (x - 1.0).abs() < 1e-5);
+            assert!(on_face);
+            assert!(isect.normal.dot(&(origin - p)) > 0.0);
+        }
+    }
+
+    #[test]
+    fn attributes() {
+        // Shading normals and surface coordinates interpolated from corners.
+        let mut m = Mesh::triangle(
+            Point3f::new(0.0, 0.0, 0.0),
+            Point3f::new(1.0, 0.0, 0.0),
+            Point3f::new(0.0, 1.0, 0.0),
+            Material::None,
+        );
+        let ray = Ray::new(Point3f::new(0.25, 0.25, 1.0), Vector3f::new(0.0, 0.0, -1.0));
+        let all = Interval::new(0.0, f32::INFINITY);
+        let i = m.intersect(ray, all).unwrap();
+        assert_eq!(i.normal, Vector3f::new(0.0, 0.0, 1.0));
+        assert_eq!(i.uv, (0.25, 0.25));
+
+        m.set_uvs(
+            vec![Uv::new(0.0, 0.0), Uv::new(2.0, 0.0), Uv::new(0.0, 4.0)],
+            vec![[0, 1, 2]],
+        );
+        let up = Vector3f::new(0.0, 0.0, 1.0);
+        let tilted = Vector3f::new(1.0, 0.0, 1.0);
+        m.set_normals(vec![up, tilted, up], vec![[0, 1, 2]]);
+        let i = m.intersect(ray, all).unwrap();
+        assert_eq!(i.uv, (0.5, 1.0));
+        assert!(i.normal.x() > 0.0 && (i.normal.length() - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn orient() {
+        let mut m = cube();
+        assert_eq!(m.orient(), 0);
+
+        // Inverted triangle flipped with its attributes.
+        let normals = vec![Vector3f::new(0.0, 0.0, -1.0)];
+        let uvs = vec![Uv::new(0.0, 0.0), Uv::new(1.0, 0.0), Uv::new(0.0, 1.0)];
+        m.set_normals(normals, vec![[0, 0, 0]; 12]);
+        m.set_uvs(uvs, vec![[0, 1, 2]; 12]);
+        m.triangles[3].swap(1, 2);
+        assert_eq!(m.orient(), 1);
+        assert_eq!(m.triangles(), cube().triangles());
+        assert_eq!(m.uvs().unwrap().1[3], [0, 2, 1]);
+        assert_eq!(m.uvs().unwrap().1[2], [0, 1, 2]);
+    }
+
+    #[test]
+    fn sample_point() {
+        let m = cube();
+        assert!((m.area() - 6.0).abs() < 1e-6);
+        let mut rng = StdRng::seed_from_u64(42);
+        for _ in 0..100 {
+            let sample = m.sample_point(&mut rng);
+            let p = sample.point;
+            let coordinates = [p.x(), p.y(), p.z()];
+            assert!(coordinates
+                .iter()
+                .all(|&x| (-1e-6..=1.0 + 1e-6).contains(&x)));
+            assert!(coordinates
+                .iter()
+                .any(|&x| x.abs() < 1e-6 || (x - 1.0).abs() < 1e-6));
+            assert_eq!(sample.pdf, 1.0 / 6.0);
+        }
+    }
+
+    #[test]
+    fn quad() {
+        let u = Vector3f::new(2.0, 0.0, 0.0);
+        let v = Vector3f::new(0.0, 0.0, -3.0);
+        let m = Mesh::quad(Point3f::new(-1.0, 1.0, 1.0), u, v, Material::None);
+        assert_eq!(m.area(), 6.0);
+
+        // Facing up, surface coordinates along edges.
+        let ray = Ray::new(Point3f::new(0.5, 2.0, 0.0), Vector3f::new(0.0, -1.0, 0.0));
+        let i = m.intersect(ray, Interval::new(0.0, f32::INFINITY)).unwrap();
+        assert_eq!(i.normal, Vector3f::new(0.0, 1.0, 0.0));
+        assert!((i.uv.0 - 0.75).abs() < 1e-6 && (i.uv.1 - 1.0 / 3.0).abs() < 1e-6);
+
+        // Uniform samples: quarter of them in each quarter of the quad.
+        let mut rng = StdRng::seed_from_u64(42);
+        let mut counts = [0; 4];
+        let n = 10000;
+        for _ in 0..n {
+            let sample = m.sample_point(&mut rng);
+            assert!((sample.point.y() - 1.0).abs() < 1e-6);
+            assert_eq!(sample.normal, Vector3f::new(0.0, 1.0, 0.0));
+            assert_eq!(sample.pdf, 1.0 / 6.0);
+            let quarter =
+                (sample.point.x() > 0.0) as usize + 2 * (sample.point.z() < -0.5) as usize;
+            counts[quarter] += 1;
+        }
+        for count in counts {
+            assert!((count as f32 / n as f32 - 0.25).abs() < 0.02);
+        }
+    }
+
+    #[test]
+    fn sample_from() {
+        // Triangles of unequal area, picked proportional to area.
+        let points = vec![
+            Point3f::new(-1.0, 0.0, 0.0),
+            Point3f::new(1.0, 0.0, 0.0),
+            Point3f::new(0.0, 2.0, 0.0),
+            Point3f::new(2.0, 0.0, 0.0),
+            Point3f::new(3.0, 0.0, 0.0),
+            Point3f::new(2.0, 1.0, 0.0),
+        ];
+        let m = Mesh::new(points, vec![[0, 1, 2], [3, 4, 5]], Material::None);
+        assert_eq!(m.area(), 2.5);
+
+        // Solid angle density from area density, reciprocal for reference on either side.
+        let mut rng = StdRng::seed_from_u64(42);
+        let mut large = 0;
+        for reference in [Point3f::new(0.5, 0.5, 2.0), Point3f::new(0.5, 0.5, -2.0)] {
+            for _ in 0..1000 {
+                let sample = m.sample_from(reference, &mut rng).unwrap();
+                let to_sample = sample.point - reference;
+                let cos_theta = to_sample.normalize().dot(&sample.normal).abs();
+                let pdf = to_sample.length_squared() / (cos_theta * m.area());
+                assert!((sample.pdf - pdf).abs() / pdf < 1e-4);
+                let pdf_from = m.pdf_from(reference, sample.point, sample.normal);
+                assert!((pdf_from - pdf).abs() / pdf < 1e-4);
+                large += (sample.point.x() < 1.0) as u32;
+            }
+        }
+        assert!((large as f32 / 2000.0 - 0.8).abs() < 0.03);
+
+        // No samples seen edge-on.
+        assert_eq!(m.sample_from(Point3f::new(5.0, 0.5, 0.0), &mut rng), None);
+    }
+
+    #[test]
+    fn bounding_box() {
+        let mut m = cube();
+        assert_eq!(m.bounding_box().min(), Point3f::default());
+        assert_eq!(m.bounding_box().max(), Point3f::new(1.0, 1.0, 1.0));
+        m.set_center(Point3f::new(0.0, 2.0, 0.0));
+        assert_eq!(m.bounding_box().min(), Point3f::new(-0.5, 1.5, -0.5));
+        let ray = Ray::new(Point3f::new(0.0, 5.0, 0.0), Vector3f::new(0.0, -1.0, 0.0));
+        let i = m.intersect(ray, Interval::new(0.0, f32::INFINITY)).unwrap();
+        assert!((i.t - 2.5).abs() < 1e-6);
+    }
+}
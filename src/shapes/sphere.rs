@@ -1,13 +1,22 @@
 use crate::base::{
+    aabb::Aabb,
+    hash::hash_float,
     interval::Interval,
     material::Material,
-    point::Point3f,
-    ray::Ray,
-    shape::{Intersectable, Intersection},
+    onb::Onb,
+    point::{Point, Point3f},
+    ray::{gamma, Ray},
+    shape::{Bounded, Intersectable, Intersection, Sampleable, SurfaceSample},
+    vector::{Vector, Vector3f},
+};
+use rand::Rng;
+use std::{
+    f32::consts::PI,
+    hash::{Hash, Hasher},
 };
 
 /// Sphere in 3-dim space defined by center position and radius.
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Sphere {
     /// Center position.
     center: Point3f,
@@ -15,6 +24,9 @@ pub struct Sphere {
     /// Sphere radius.
     radius: f32,
 
+    /// Displacement of center over one frame duration, for motion blur.
+    motion: Vector3f,
+
     /// Surface material.
     material: Material,
 }
@@ -25,15 +37,130 @@ impl Sphere {
         Sphere {
             center,
             radius,
+            motion: Vector3f::default(),
             material,
         }
     }
+
+    /// Center position.
+    pub fn center(&self) -> Point3f {
+        self.center
+    }
+
+    /// Sets center position.
+    pub fn set_center(&mut self, center: Point3f) {
+        self.center = center;
+    }
+
+    /// Sphere radius.
+    pub fn radius(&self) -> f32 {
+        self.radius
+    }
+
+    /// Displacement of center over one frame duration.
+    pub fn motion(&self) -> Vector3f {
+        self.motion
+    }
+
+    /// Sets displacement of center over one frame duration, blurring the sphere along it while the camera shutter
+    /// is open. The center is where the sphere is at start of frame. Light sampling sees the sphere there.
+    pub fn set_motion(&mut self, motion: Vector3f) {
+        self.motion = motion;
+    }
+
+    /// Whether sphere moves within the frame.
+    pub fn is_moving(&self) -> bool {
+        self.motion != Vector3f::default()
+    }
+
+    /// Center position at time in frame durations since start of frame.
+    fn center_at(&self, time: f32) -> Point3f {
+        self.center + time * self.motion
+    }
+
+    /// Surface material.
+    pub fn material(&self) -> &Material {
+        &self.material
+    }
+
+    /// Sets surface material.
+    pub fn set_material(&mut self, material: Material) {
+        self.material = material;
+    }
+
+    /// Ray parameter of nearest intersection computed in double precision, for checking the single precision
+    /// intersection. Solves the quadratic by the numerically stable form avoiding cancellation.
+    pub fn intersect_precise(
+        &self,
+        origin: Point<f64, 3>,
+        direction: Vector<f64, 3>,
+        ray_t: Interval<f64>,
+    ) -> Option<f64> {
+        let oc = origin - self.center.cast();
+        let radius = self.radius as f64;
+        let a = direction.length_squared();
+        let half_b = direction.dot(&oc);
+        let c = oc.length_squared() - radius * radius;
+        let discriminant = half_b * half_b - a * c;
+        if discriminant < 0.0 {
+            return None;
+        }
+
+        // Roots q / a and c / q, nearest first.
+        let q = -half_b - half_b.signum() * discriminant.sqrt();
+        let (mut t0, mut t1) = (q / a, c / q);
+        if t0 > t1 {
+            std::mem::swap(&mut t0, &mut t1);
+        }
+        [t0, t1].into_iter().find(|&t| ray_t.contains(t))
+    }
+
+    /// Intersection of ray hitting sphere at ray parameter t, e.g. found by a packet test.
+    pub(crate) fn intersection_at(&self, ray: Ray, t: f32) -> Intersection<'_> {
+        // Reproject hit onto surface, bounding the error of the point by its magnitude rather than by the precision
+        // of t, which degrades far from huge spheres.
+        let center = self.center_at(ray.time());
+        let local = ray.at(t) - center;
+        let local = local * (self.radius / local.length());
+        let normal = local / self.radius;
+        Intersection {
+            point: center + local,
+            material: &self.material,
+            normal,
+            uv: Sphere::uv(normal),
+            t,
+            error: gamma(6) * (local.abs() + (center - Point3f::default()).abs()),
+        }
+    }
+
+    /// Surface coordinates of point with outward unit normal: u is the angle around the y-axis starting at -x, v the
+    /// angle from -y to +y.
+    fn uv(normal: Vector3f) -> (f32, f32) {
+        let theta = (-normal.y()).clamp(-1.0, 1.0).acos();
+        let phi = (-normal.z()).atan2(normal.x()) + PI;
+        (phi / (2.0 * PI), theta / PI)
+    }
+
+    /// Cosine of half opening angle of cone subtended by sphere, seen from outside point at squared distance.
+    fn cos_theta_max(&self, distance_squared: f32) -> f32 {
+        let sin2_theta_max = self.radius * self.radius / distance_squared;
+        (1.0 - sin2_theta_max).max(0.0).sqrt()
+    }
+}
+
+impl Hash for Sphere {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.center.hash(state);
+        hash_float(self.radius, state);
+        self.motion.hash(state);
+        self.material.hash(state);
+    }
 }
 
 impl Intersectable for Sphere {
-    fn intersect(&self, ray: Ray, ray_t: Interval) -> Option<Intersection> {
+    fn intersect(&self, ray: Ray, ray_t: Interval) -> Option<Intersection<'_>> {
         // Solve quadratic equation.
-        let oc = ray.origin() - self.center;
+        let oc = ray.origin() - self.center_at(ray.time());
         let a = ray.direction().length_squared();
         let half_b = ray.direction().dot(&oc);
         let c = oc.length_squared() - self.radius * self.radius;
@@ -52,19 +179,88 @@ impl Intersectable for Sphere {
                 return None; // Outside interval.
             }
         }
-        let point = ray.at(root);
+        Some(self.intersection_at(ray, root))
+    }
+}
 
-        // Calculate normal.
-        let normal = (point - self.center) / self.radius;
+impl Bounded for Sphere {
+    fn bounding_box(&self) -> Aabb {
+        // Box swept over the frame.
+        let r = Vector3f::new(self.radius, self.radius, self.radius);
+        let end = self.center_at(1.0);
+        Aabb::new(self.center - r, self.center + r).union(&Aabb::new(end - r, end + r))
+    }
+}
 
-        // Return intersection struct.
-        let intersection = Intersection {
-            point,
-            material: &self.material,
+impl Sampleable for Sphere {
+    fn area(&self) -> f32 {
+        4.0 * PI * self.radius * self.radius
+    }
+
+    fn sample_point(&self, rng: &mut impl Rng) -> SurfaceSample {
+        // Uniform direction on unit sphere.
+        let z = 1.0 - 2.0 * rng.gen::<f32>();
+        let r = (1.0 - z * z).max(0.0).sqrt();
+        let phi = 2.0 * PI * rng.gen::<f32>();
+        let normal = Vector3f::new(r * phi.cos(), r * phi.sin(), z);
+
+        // Return surface sample struct.
+        SurfaceSample {
+            point: self.center + self.radius * normal,
             normal,
-            t: root,
+            pdf: 1.0 / self.area(),
+        }
+    }
+
+    fn sample_from(&self, reference: Point3f, rng: &mut impl Rng) -> Option<SurfaceSample> {
+        let to_center = self.center - reference;
+        let distance_squared = to_center.length_squared();
+        let radius_squared = self.radius * self.radius;
+
+        // Fall back to area sampling for reference points inside sphere.
+        if distance_squared <= radius_squared {
+            return self.sample_point(rng).to_solid_angle(reference);
+        }
+
+        // Sample direction uniformly in cone subtended by sphere.
+        let cos_theta_max = self.cos_theta_max(distance_squared);
+        let cos_theta = 1.0 - rng.gen::<f32>() * (1.0 - cos_theta_max);
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * PI * rng.gen::<f32>();
+
+        // Direction in orthonormal basis around cone axis.
+        let local = Vector3f::new(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta);
+        let direction = Onb::new(to_center).to_world(local);
+
+        // Nearest intersection of sampled direction with sphere.
+        let distance = distance_squared.sqrt();
+        let t = distance * cos_theta
+            - (radius_squared - distance_squared * sin_theta * sin_theta)
+                .max(0.0)
+                .sqrt();
+        let point = reference + t * direction;
+
+        // Return surface sample struct.
+        let sample = SurfaceSample {
+            point,
+            normal: (point - self.center) / self.radius,
+            pdf: 1.0 / (2.0 * PI * (1.0 - cos_theta_max)),
         };
-        Some(intersection)
+        Some(sample)
+    }
+
+    fn pdf_from(&self, reference: Point3f, point: Point3f, normal: Vector3f) -> f32 {
+        let distance_squared = (self.center - reference).length_squared();
+        if distance_squared <= self.radius * self.radius {
+            // Area sampling inside sphere.
+            let sample = SurfaceSample {
+                point,
+                normal,
+                pdf: 1.0 / self.area(),
+            };
+            return sample.to_solid_angle(reference).map_or(0.0, |s| s.pdf);
+        }
+        1.0 / (2.0 * PI * (1.0 - self.cos_theta_max(distance_squared)))
     }
 }
 
@@ -72,7 +268,7 @@ impl Intersectable for Sphere {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::base::vector::Vector3f;
+    use rand::{rngs::StdRng, SeedableRng};
 
     #[test]
     fn intersect() {
@@ -84,13 +280,17 @@ mod tests {
             point: Point3f::new(0.0, 1.0, 0.0),
             material: &Material::None,
             normal: Vector3f::new(0.0, -1.0, 0.0),
+            uv: (0.5, 0.0),
             t: 1.0,
+            error: gamma(6) * Vector3f::new(0.0, 5.0, 0.0),
         };
         let i2 = Intersection {
             point: Point3f::new(0.0, 5.0, 0.0),
             material: &Material::None,
             normal: Vector3f::new(0.0, 1.0, 0.0),
+            uv: (0.5, 1.0),
             t: 5.0,
+            error: gamma(6) * Vector3f::new(0.0, 5.0, 0.0),
         };
         assert_eq!(s.intersect(r1, Interval::new(0.0, f32::INFINITY)), Some(i1));
         assert_eq!(s.intersect(r1, Interval::new(1.0, f32::INFINITY)), Some(i2));
@@ -104,13 +304,17 @@ mod tests {
             point: Point3f::new(0.0, 5.0, 0.0),
             material: &Material::None,
             normal: Vector3f::new(0.0, 1.0, 0.0),
+            uv: (0.5, 1.0),
             t: 2.0,
+            error: gamma(6) * Vector3f::new(0.0, 5.0, 0.0),
         };
         let i4 = Intersection {
             point: Point3f::new(0.0, 1.0, 0.0),
             material: &Material::None,
             normal: Vector3f::new(0.0, -1.0, 0.0),
+            uv: (0.5, 0.0),
             t: -2.0,
+            error: gamma(6) * Vector3f::new(0.0, 5.0, 0.0),
         };
         assert_eq!(s.intersect(r2, Interval::new(0.0, f32::INFINITY)), Some(i3));
         assert_eq!(
@@ -126,4 +330,152 @@ mod tests {
             None
         );
     }
+
+    #[test]
+    fn intersect_precise() {
+        // Agrees with single precision, but stays exact far from huge sphere.
+        let s = Sphere::new(Point3f::new(0.0, 3.0, 0.0), 2.0, Material::None);
+        let origin = Point::<f64, 3>::new(0.0, 0.0, 0.0);
+        let up = Vector::<f64, 3>::new(0.0, 1.0, 0.0);
+        assert_eq!(
+            s.intersect_precise(origin, up, Interval::new(0.0, f64::INFINITY)),
+            Some(1.0)
+        );
+        assert_eq!(
+            s.intersect_precise(origin, up, Interval::new(2.0, f64::INFINITY)),
+            Some(5.0)
+        );
+        assert_eq!(
+            s.intersect_precise(origin, -up, Interval::new(0.0, 10.0)),
+            None
+        );
+        let ground = Sphere::new(Point3f::new(0.0, -1e5, 0.0), 1e5, Material::None);
+        let t = ground.intersect_precise(
+            Point::<f64, 3>::new(0.0, 1e-3, 0.0),
+            -up,
+            Interval::new(0.0, f64::INFINITY),
+        );
+        assert!((t.unwrap() - 1e-3).abs() < 1e-9);
+    }
+
+    #[test]
+    fn uv() {
+        let uv = |x, y, z| Sphere::uv(Vector3f::new(x, y, z));
+        assert_eq!(uv(-1.0, 0.0, 0.0), (0.0, 0.5));
+        assert_eq!(uv(0.0, 0.0, 1.0), (0.25, 0.5));
+        assert_eq!(uv(1.0, 0.0, 0.0), (0.5, 0.5));
+        assert_eq!(uv(0.0, 0.0, -1.0), (0.75, 0.5));
+    }
+
+    #[test]
+    fn sample_point() {
+        let s = Sphere::new(Point3f::new(1.0, 2.0, 3.0), 2.0, Material::None);
+        assert_eq!(s.area(), 16.0 * PI);
+
+        let mut rng = StdRng::seed_from_u64(42);
+        for _ in 0..100 {
+            let sample = s.sample_point(&mut rng);
+            assert!(((sample.point - s.center).length() - 2.0).abs() < 1e-5);
+            assert!((sample.normal.length() - 1.0).abs() < 1e-5);
+            assert_eq!(sample.pdf, 1.0 / s.area());
+        }
+    }
+
+    #[test]
+    fn sample_from() {
+        let s = Sphere::new(Point3f::default(), 1.0, Material::None);
+        let mut rng = StdRng::seed_from_u64(42);
+
+        // Outside reference, cone sampling.
+        let reference = Point3f::new(0.0, 0.0, 2.0);
+        let cos_theta_max = f32::sqrt(3.0) / 2.0;
+        let pdf = 1.0 / (2.0 * PI * (1.0 - cos_theta_max));
+        for _ in 0..100 {
+            let sample = s.sample_from(reference, &mut rng).unwrap();
+            assert!(((sample.point - s.center).length() - 1.0).abs() < 1e-4);
+            assert!(sample.normal.dot(&(reference - sample.point)) >= -1e-4);
+            assert!((sample.pdf - pdf).abs() / pdf < 1e-4);
+            let pdf_from = s.pdf_from(reference, sample.point, sample.normal);
+            assert!((pdf_from - sample.pdf).abs() / pdf < 1e-4);
+        }
+
+        // Inside reference, area sampling.
+        let reference = Point3f::new(0.0, 0.0, 0.5);
+        for _ in 0..100 {
+            if let Some(sample) = s.sample_from(reference, &mut rng) {
+                let to_sample = sample.point - reference;
+                let cos_theta = to_sample.normalize().dot(&sample.normal).abs();
+                let pdf = to_sample.length_squared() / (cos_theta * s.area());
+                assert!((sample.pdf - pdf).abs() / pdf < 1e-4);
+                let pdf_from = s.pdf_from(reference, sample.point, sample.normal);
+                assert!((pdf_from - pdf).abs() / pdf < 1e-4);
+            }
+        }
+    }
+
+    #[test]
+    fn spawn_ray() {
+        // Rays leaving huge spheres far from the origin miss them when leaving outwards, even at grazing angles, and
+        // cross them when leaving inwards.
+        let mut rng = StdRng::seed_from_u64(1);
+        for (center, radius) in [
+            (Point3f::new(0.0, -1e5, 0.0), 1e5),
+            (Point3f::new(3e4, 0.0, 0.0), 1e3),
+        ] {
+            let s = Sphere::new(center, radius, Material::None);
+            for _ in 0..1000 {
+                let origin = center + 2.0 * radius * Vector3f::random_unit_vector(&mut rng);
+                let target = center + radius * Vector3f::random_unit_vector(&mut rng);
+                let ray = Ray::new(origin, target - origin);
+                let Some(isect) = s.intersect(ray, Interval::new(0.0, f32::INFINITY)) else {
+                    continue;
+                };
+                let tangent = Vector3f::random_unit_vector(&mut rng)
+                    .cross(&isect.normal)
+                    .normalize();
+                for cos in [0.5, 1e-2, 1e-4] {
+                    let outward = tangent + cos * isect.normal;
+                    let out =
+                        s.intersect(isect.spawn_ray(outward), Interval::new(0.0, f32::INFINITY));
+                    assert!(out.is_none());
+                    let inward = tangent - cos * isect.normal;
+                    let chord =
+                        s.intersect(isect.spawn_ray(inward), Interval::new(0.0, f32::INFINITY));
+                    assert!(chord.unwrap().t > radius * cos);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn bounding_box() {
+        let s = Sphere::new(Point3f::new(1.0, 2.0, 3.0), 2.0, Material::None);
+        let b = s.bounding_box();
+        assert_eq!(b.min(), Point3f::new(-1.0, 0.0, 1.0));
+        assert_eq!(b.max(), Point3f::new(3.0, 4.0, 5.0));
+    }
+
+    #[test]
+    fn motion() {
+        // Sphere moving along x, swept box covers whole frame.
+        let mut s = Sphere::new(Point3f::default(), 1.0, Material::None);
+        s.set_motion(Vector3f::new(4.0, 0.0, 0.0));
+        let b = s.bounding_box();
+        assert_eq!(b.min(), Point3f::new(-1.0, -1.0, -1.0));
+        assert_eq!(b.max(), Point3f::new(5.0, 1.0, 1.0));
+
+        // Rays see sphere where it is at their time.
+        let down = |x: f32, time: f32| {
+            let ray = Ray::with_time(
+                Point3f::new(x, 5.0, 0.0),
+                Vector3f::new(0.0, -1.0, 0.0),
+                time,
+            );
+            s.intersect(ray, Interval::new(0.0, f32::INFINITY))
+        };
+        assert!(down(0.0, 0.0).is_some() && down(4.0, 0.0).is_none());
+        let hit = down(2.0, 0.5).unwrap();
+        assert_eq!((hit.t, hit.normal), (4.0, Vector3f::new(0.0, 1.0, 0.0)));
+        assert!(down(0.0, 1.0).is_none());
+    }
 }
@@ -0,0 +1,323 @@
+use crate::base::{
+    aabb::Aabb,
+    hash::hash_float,
+    interval::Interval,
+    material::Material,
+    point::Point3f,
+    polynomial::solve_quartic,
+    ray::Ray,
+    shape::{Bounded, Intersectable, Intersection, Sampleable, SurfaceSample},
+    vector::Vector3f,
+};
+use rand::Rng;
+use std::{
+    f32::consts::PI,
+    hash::{Hash, Hasher},
+};
+
+/// Torus in 3-dim space defined by center position, axis direction, major radius (from center to tube center) and
+/// minor radius (of tube).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Torus {
+    /// Center position.
+    center: Point3f,
+
+    /// Unit axis direction, normal of plane of tube center circle.
+    axis: Vector3f,
+
+    /// Distance from center to tube center.
+    major_radius: f32,
+
+    /// Tube radius.
+    minor_radius: f32,
+
+    /// Surface material.
+    material: Material,
+}
+
+impl Torus {
+    /// Creates torus with center position, axis direction, major and minor radius. Tube must not reach through
+    /// center, i.e. minor radius is at most major radius.
+    pub fn new(
+        center: Point3f,
+        axis: Vector3f,
+        major_radius: f32,
+        minor_radius: f32,
+        material: Material,
+    ) -> Self {
+        assert!(minor_radius > 0.0 && major_radius >= minor_radius && !axis.near_zero());
+        Torus {
+            center,
+            axis: axis.normalize(),
+            major_radius,
+            minor_radius,
+            material,
+        }
+    }
+
+    /// Center position.
+    pub fn center(&self) -> Point3f {
+        self.center
+    }
+
+    /// Sets center position.
+    pub fn set_center(&mut self, center: Point3f) {
+        self.center = center;
+    }
+
+    /// Unit axis direction, normal of plane of tube center circle.
+    pub fn axis(&self) -> Vector3f {
+        self.axis
+    }
+
+    /// Distance from center to tube center.
+    pub fn major_radius(&self) -> f32 {
+        self.major_radius
+    }
+
+    /// Tube radius.
+    pub fn minor_radius(&self) -> f32 {
+        self.minor_radius
+    }
+
+    /// Surface material.
+    pub fn material(&self) -> &Material {
+        &self.material
+    }
+
+    /// Sets surface material.
+    pub fn set_material(&mut self, material: Material) {
+        self.material = material;
+    }
+
+    /// Orthonormal basis with axis as third vector.
+    pub fn basis(&self) -> (Vector3f, Vector3f, Vector3f) {
+        let (u, v) = self.axis.orthonormal_basis();
+        (u, v, self.axis)
+    }
+}
+
+impl Hash for Torus {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.center.hash(state);
+        self.axis.hash(state);
+        hash_float(self.major_radius, state);
+        hash_float(self.minor_radius, state);
+        self.material.hash(state);
+    }
+}
+
+impl Intersectable for Torus {
+    fn intersect(&self, ray: Ray, ray_t: Interval) -> Option<Intersection<'_>> {
+        // Ray in local frame with axis along z, in double precision for the quartic.
+        let (u, v, w) = self.basis();
+        let oc = ray.origin() - self.center;
+        let d = ray.direction();
+        let local = |a: Vector3f| [a.dot(&u) as f64, a.dot(&v) as f64, a.dot(&w) as f64];
+        let (mut o, d) = (local(oc), local(d));
+        let dot = |a: [f64; 3], b: [f64; 3]| a[0] * b[0] + a[1] * b[1] + a[2] * b[2];
+
+        // Start from point on ray closest to center, which keeps coefficients well conditioned for far origins.
+        // Rays passing outside of bounding sphere miss.
+        let m = dot(d, d);
+        let t0 = -dot(o, d) / m;
+        o = [o[0] + t0 * d[0], o[1] + t0 * d[1], o[2] + t0 * d[2]];
+        let (big, small) = (self.major_radius as f64, self.minor_radius as f64);
+        if dot(o, o) > (big + small) * (big + small) {
+            return None;
+        }
+
+        // Solve (|p|² + R² - r²)² = 4R²(x² + y²) for points p on ray.
+        let n = dot(o, d);
+        let k = dot(o, o) + big * big - small * small;
+        let four_r2 = 4.0 * big * big;
+        let roots = solve_quartic(
+            m * m,
+            4.0 * m * n,
+            4.0 * n * n + 2.0 * m * k - four_r2 * (d[0] * d[0] + d[1] * d[1]),
+            4.0 * n * k - 2.0 * four_r2 * (o[0] * d[0] + o[1] * d[1]),
+            k * k - four_r2 * (o[0] * o[0] + o[1] * o[1]),
+        );
+        let root = *roots
+            .iter()
+            .find(|&&root| ray_t.contains((t0 + root) as f32))?;
+
+        // Normal points away from tube center circle.
+        let p = [o[0] + root * d[0], o[1] + root * d[1], o[2] + root * d[2]];
+        let (x, y, z) = (p[0] as f32, p[1] as f32, p[2] as f32);
+        let rho = (x * x + y * y).sqrt();
+        let n = if rho > 0.0 {
+            let scale = 1.0 - self.major_radius / rho;
+            Vector3f::new(x * scale, y * scale, z).normalize()
+        } else {
+            Vector3f::new(0.0, 0.0, z.signum())
+        };
+        let uv = (
+            (y.atan2(x) / (2.0 * PI)).rem_euclid(1.0),
+            (z.atan2(rho - self.major_radius) / (2.0 * PI)).rem_euclid(1.0),
+        );
+
+        // Return intersection struct with normal in world frame.
+        let t = (t0 + root) as f32;
+        let intersection = Intersection {
+            point: ray.at(t),
+            material: &self.material,
+            normal: n.x() * u + n.y() * v + n.z() * w,
+            uv,
+            t,
+            error: ray.error_at(t),
+        };
+        Some(intersection)
+    }
+}
+
+impl Bounded for Torus {
+    fn bounding_box(&self) -> Aabb {
+        // Per axis, extent of tube center circle plus tube radius.
+        let extent = |a: f32| self.major_radius * (1.0 - a * a).max(0.0).sqrt() + self.minor_radius;
+        let a = self.axis;
+        let e = Vector3f::new(extent(a.x()), extent(a.y()), extent(a.z()));
+        Aabb::new(self.center - e, self.center + e)
+    }
+}
+
+impl Sampleable for Torus {
+    fn area(&self) -> f32 {
+        4.0 * PI * PI * self.major_radius * self.minor_radius
+    }
+
+    fn sample_point(&self, rng: &mut impl Rng) -> SurfaceSample {
+        let (u, v, w) = self.basis();
+        let phi = 2.0 * PI * rng.gen::<f32>();
+        let radial = phi.cos() * u + phi.sin() * v;
+
+        // Angle around tube by rejection, outer side has more area than inner side.
+        let theta = loop {
+            let theta = 2.0 * PI * rng.gen::<f32>();
+            let outward = self.major_radius + self.minor_radius * theta.cos();
+            if rng.gen::<f32>() * (self.major_radius + self.minor_radius) <= outward {
+                break theta;
+            }
+        };
+        let normal = theta.cos() * radial + theta.sin() * w;
+
+        // Return surface sample struct.
+        SurfaceSample {
+            point: self.center + self.major_radius * radial + self.minor_radius * normal,
+            normal,
+            pdf: 1.0 / self.area(),
+        }
+    }
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    /// Torus around y-axis at origin with major radius 2 and minor radius 0.5.
+    fn torus() -> Torus {
+        Torus::new(
+            Point3f::default(),
+            Vector3f::new(0.0, 1.0, 0.0),
+            2.0,
+            0.5,
+            Material::None,
+        )
+    }
+
+    /// Distance of point to tube center circle of torus.
+    fn tube_distance(t: &Torus, p: Point3f) -> f32 {
+        let local = p - t.center();
+        let along = local.dot(&t.axis());
+        let rho = (local - along * t.axis()).length();
+        ((rho - t.major_radius()).powi(2) + along * along).sqrt()
+    }
+
+    #[test]
+    fn intersect() {
+        let t = torus();
+        let all = Interval::new(0.0, f32::INFINITY);
+
+        // Ray through center crosses tube twice.
+        let r1 = Ray::new(Point3f::new(-5.0, 0.0, 0.0), Vector3f::new(1.0, 0.0, 0.0));
+        let i1 = t.intersect(r1, all).unwrap();
+        assert!((i1.t - 2.5).abs() < 1e-5);
+        assert!((i1.normal - Vector3f::new(-1.0, 0.0, 0.0)).length() < 1e-5);
+        let ts: Vec<f32> = [0.0, 3.0, 5.0, 7.0]
+            .iter()
+            .map(|&start| {
+                t.intersect(r1, Interval::new(start, f32::INFINITY))
+                    .unwrap()
+                    .t
+            })
+            .collect();
+        for (actual, expected) in ts.iter().zip([2.5, 3.5, 6.5, 7.5]) {
+            assert!((actual - expected).abs() < 1e-4);
+        }
+        assert_eq!(t.intersect(r1, Interval::new(8.0, f32::INFINITY)), None);
+
+        // Axial ray through hole misses, onto tube top hits.
+        let hole = Ray::new(Point3f::new(0.0, 5.0, 0.0), Vector3f::new(0.0, -1.0, 0.0));
+        assert_eq!(t.intersect(hole, all), None);
+        let top = Ray::new(Point3f::new(0.0, 5.0, 2.0), Vector3f::new(0.0, -1.0, 0.0));
+        let i2 = t.intersect(top, all).unwrap();
+        assert!((i2.t - 4.5).abs() < 1e-5);
+        assert!((i2.normal - Vector3f::new(0.0, 1.0, 0.0)).length() < 1e-5);
+
+        // Grazing ray along top of tube, far away ray.
+        let graze = Ray::new(Point3f::new(-5.0, 0.5, 0.0), Vector3f::new(1.0, 0.0, 0.0));
+        assert!(t.intersect(graze, all).is_some());
+        let far = Ray::new(Point3f::new(-1e4, 0.0, 0.1), Vector3f::new(1.0, 0.0, 0.0));
+        assert!((t.intersect(far, all).unwrap().t - (1e4 - 2.5)).abs() < 1e-2);
+    }
+
+    #[test]
+    fn tilted() {
+        // Points hit on tilted torus lie on its surface, normals point outwards.
+        let axis = Vector3f::new(1.0, 2.0, -1.0).normalize();
+        let t = Torus::new(Point3f::new(1.0, 2.0, 3.0), axis, 1.0, 0.3, Material::None);
+        let (u, _, _) = t.basis();
+        let mut rng = StdRng::seed_from_u64(42);
+        for _ in 0..100 {
+            let target = t.center() + u + Vector3f::random_unit_vector(&mut rng) * 0.2;
+            let origin = target + 10.0 * Vector3f::random_unit_vector(&mut rng);
+            let ray = Ray::new(origin, target - origin);
+            let isect = t.intersect(ray, Interval::new(0.0, f32::INFINITY)).unwrap();
+            assert!((tube_distance(&t, isect.point) - 0.3).abs() < 1e-4);
+            assert!(isect.normal.dot(&(origin - isect.point)) > 0.0);
+            assert!((isect.normal.length() - 1.0).abs() < 1e-5);
+            assert!((0.0..=1.0).contains(&isect.uv.0) && (0.0..=1.0).contains(&isect.uv.1));
+        }
+    }
+
+    #[test]
+    fn sample_point() {
+        let t = torus();
+        assert!((t.area() - 4.0 * PI * PI).abs() < 1e-5);
+        let mut rng = StdRng::seed_from_u64(42);
+        let mut outer = 0;
+        for _ in 0..1000 {
+            let sample = t.sample_point(&mut rng);
+            assert!((tube_distance(&t, sample.point) - 0.5).abs() < 1e-5);
+            assert!((sample.normal.length() - 1.0).abs() < 1e-5);
+            assert_eq!(sample.pdf, 1.0 / t.area());
+            let p = sample.point;
+            if p.x() * p.x() + p.z() * p.z() > 4.0 {
+                outer += 1;
+            }
+        }
+
+        // Outer half has (R + 2r/π) / R times the area of the inner half.
+        let ratio = outer as f32 / (1000 - outer) as f32;
+        assert!((ratio - (2.0 + 1.0 / PI) / (2.0 - 1.0 / PI)).abs() < 0.2);
+    }
+
+    #[test]
+    fn bounding_box() {
+        let b = torus().bounding_box();
+        assert_eq!(b.min(), Point3f::new(-2.5, -0.5, -2.5));
+        assert_eq!(b.max(), Point3f::new(2.5, 0.5, 2.5));
+    }
+}
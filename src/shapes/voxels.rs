@@ -0,0 +1,528 @@
+use crate::base::{
+    aabb::Aabb,
+    hash::hash_float,
+    interval::Interval,
+    material::Material,
+    point::Point3f,
+    ray::Ray,
+    shape::{Bounded, Intersectable, Intersection, Sampleable, SurfaceSample},
+    vector::Vector3f,
+};
+use rand::Rng;
+use std::hash::{Hash, Hasher};
+
+/// Node of sparse voxel octree.
+#[derive(Clone, Copy, Debug, Hash, PartialEq)]
+enum Node {
+    /// No voxels inside.
+    Empty,
+
+    /// Single voxel with palette index of its material.
+    Voxel(u8),
+
+    /// Eight children stored from index on, child with offset (x, y, z) at x + 2y + 4z.
+    Branch(u32),
+}
+
+/// Grid of axis-aligned cubic voxels with per-voxel materials, stored as sparse octree. Rays traverse the grid voxel
+/// by voxel (3D DDA), skipping empty octree nodes as a whole. Surfaces lie between filled and empty voxels, so rays
+/// starting inside filled voxels (e.g. refracted into glass) hit where they leave them.
+///
+/// Area and point samples cover all voxel faces, including faces hidden between neighboring voxels. Samples from a
+/// reference point on hidden faces are rejected, which keeps light sampling unbiased.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Voxels {
+    /// Corner of grid with smallest coordinates.
+    origin: Point3f,
+
+    /// Edge length of voxels.
+    voxel_size: f32,
+
+    /// Number of voxels along each axis.
+    size: [u32; 3],
+
+    /// Depth of octree, root spans 2^depth voxels along each axis.
+    depth: u32,
+
+    /// Octree nodes, root first.
+    nodes: Vec<Node>,
+
+    /// Grid positions of filled voxels.
+    positions: Vec<[u32; 3]>,
+
+    /// Materials referenced by voxels.
+    palette: Vec<Material>,
+}
+
+impl Voxels {
+    /// Creates grid with corner position, voxel size, material palette and filled voxels as grid positions with
+    /// palette index. Voxels at the same position replace each other.
+    pub fn new(
+        origin: Point3f,
+        voxel_size: f32,
+        palette: Vec<Material>,
+        voxels: &[([u32; 3], u8)],
+    ) -> Self {
+        assert!(voxel_size > 0.0 && !voxels.is_empty());
+        assert!(voxels.iter().all(|&(_, i)| (i as usize) < palette.len()));
+        let size = [0, 1, 2].map(|a| voxels.iter().map(|(p, _)| p[a] + 1).max().unwrap());
+        let depth = size
+            .iter()
+            .max()
+            .unwrap()
+            .next_power_of_two()
+            .trailing_zeros();
+        let mut grid = Voxels {
+            origin,
+            voxel_size,
+            size,
+            depth,
+            nodes: vec![Node::Empty],
+            positions: Vec::new(),
+            palette,
+        };
+        for &(position, index) in voxels {
+            grid.insert(position, index);
+        }
+        grid
+    }
+
+    /// Inserts voxel at grid position, creating branches on the way down.
+    fn insert(&mut self, position: [u32; 3], index: u8) {
+        let mut node = 0;
+        for level in (0..self.depth).rev() {
+            let first = match self.nodes[node] {
+                Node::Branch(first) => first as usize,
+                _ => {
+                    let first = self.nodes.len();
+                    self.nodes[node] = Node::Branch(first as u32);
+                    self.nodes.extend([Node::Empty; 8]);
+                    first
+                }
+            };
+            let child: usize = (0..3)
+                .map(|a| ((position[a] >> level & 1) as usize) << a)
+                .sum();
+            node = first + child;
+        }
+        if self.nodes[node] == Node::Empty {
+            self.positions.push(position);
+        }
+        self.nodes[node] = Node::Voxel(index);
+    }
+
+    /// Largest node containing grid cell, with its smallest cell and edge length in cells.
+    fn lookup(&self, cell: [i64; 3]) -> (Node, [i64; 3], i64) {
+        let (mut node, mut min, mut side) = (self.nodes[0], [0; 3], 1 << self.depth);
+        while let Node::Branch(first) = node {
+            side /= 2;
+            let mut child = 0;
+            for a in 0..3 {
+                if cell[a] >= min[a] + side {
+                    min[a] += side;
+                    child |= 1 << a;
+                }
+            }
+            node = self.nodes[first as usize + child];
+        }
+        (node, min, side)
+    }
+
+    /// Palette index of voxel at grid cell, none if empty or outside grid.
+    fn index(&self, cell: [i64; 3]) -> Option<u8> {
+        if (0..3).any(|a| cell[a] < 0 || cell[a] >= self.size[a] as i64) {
+            return None;
+        }
+        match self.lookup(cell).0 {
+            Node::Voxel(index) => Some(index),
+            _ => None,
+        }
+    }
+
+    /// Material of voxel at grid position, none if empty.
+    pub fn voxel(&self, position: [u32; 3]) -> Option<&Material> {
+        let index = self.index(position.map(i64::from))?;
+        Some(&self.palette[index as usize])
+    }
+
+    /// Number of filled voxels.
+    pub fn count(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// Grid positions of filled voxels.
+    pub fn positions(&self) -> &[[u32; 3]] {
+        &self.positions
+    }
+
+    /// Number of voxels along each axis.
+    pub fn size(&self) -> [u32; 3] {
+        self.size
+    }
+
+    /// Corner of grid with smallest coordinates.
+    pub fn origin(&self) -> Point3f {
+        self.origin
+    }
+
+    /// Edge length of voxels.
+    pub fn voxel_size(&self) -> f32 {
+        self.voxel_size
+    }
+
+    /// Sets edge length of voxels, scaling grid about its center.
+    pub fn set_voxel_size(&mut self, voxel_size: f32) {
+        assert!(voxel_size > 0.0);
+        let center = self.center();
+        self.voxel_size = voxel_size;
+        self.set_center(center);
+    }
+
+    /// Center position of grid.
+    pub fn center(&self) -> Point3f {
+        self.origin + 0.5 * self.extent()
+    }
+
+    /// Moves grid to center position.
+    pub fn set_center(&mut self, center: Point3f) {
+        self.origin = center - 0.5 * self.extent();
+    }
+
+    /// Material of first voxel, representing the grid as a whole.
+    pub fn material(&self) -> &Material {
+        let index = self.index(self.positions[0].map(i64::from)).unwrap();
+        &self.palette[index as usize]
+    }
+
+    /// Sets material of all voxels.
+    pub fn set_material(&mut self, material: Material) {
+        self.palette.fill(material);
+    }
+
+    /// Intersection at ray parameter on face perpendicular to axis, with normal along axis in given direction.
+    fn intersection(
+        &self,
+        ray: Ray,
+        t: f32,
+        axis: usize,
+        outward: f32,
+        index: u8,
+    ) -> Intersection<'_> {
+        let point = ray.at(t);
+        let local = (point - self.origin) / self.voxel_size;
+        let local = [local.x(), local.y(), local.z()];
+        let mut normal = [0.0; 3];
+        normal[axis] = outward;
+        Intersection {
+            point,
+            normal: Vector3f::new(normal[0], normal[1], normal[2]),
+            uv: (
+                local[(axis + 1) % 3].rem_euclid(1.0),
+                local[(axis + 2) % 3].rem_euclid(1.0),
+            ),
+            material: &self.palette[index as usize],
+            t,
+            error: ray.error_at(t),
+        }
+    }
+
+    /// Samples point uniformly on faces of all voxels. Returns sample and grid cell beyond sampled face.
+    fn sample_face(&self, rng: &mut impl Rng) -> (SurfaceSample, [i64; 3]) {
+        let position = self.positions[rng.gen_range(0..self.positions.len())];
+        let face = rng.gen_range(0..6);
+        let (axis, side) = (face % 3, if face < 3 { -1 } else { 1 });
+
+        // Point on face in grid coordinates.
+        let mut local = [0, 1, 2].map(|a| position[a] as f32 + rng.gen::<f32>());
+        local[axis] = position[axis] as f32 + if side > 0 { 1.0 } else { 0.0 };
+        let mut normal = [0.0; 3];
+        normal[axis] = side as f32;
+        let mut neighbor = position.map(i64::from);
+        neighbor[axis] += side;
+
+        // Return surface sample struct and neighbor cell.
+        let sample = SurfaceSample {
+            point: self.origin + self.voxel_size * Vector3f::new(local[0], local[1], local[2]),
+            normal: Vector3f::new(normal[0], normal[1], normal[2]),
+            pdf: 1.0 / self.area(),
+        };
+        (sample, neighbor)
+    }
+
+    /// Extent of grid along each axis.
+    fn extent(&self) -> Vector3f {
+        let [x, y, z] = self.size.map(|s| s as f32 * self.voxel_size);
+        Vector3f::new(x, y, z)
+    }
+}
+
+impl Hash for Voxels {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.origin.hash(state);
+        hash_float(self.voxel_size, state);
+        self.size.hash(state);
+        self.nodes.hash(state);
+        self.palette.hash(state);
+    }
+}
+
+impl Intersectable for Voxels {
+    fn intersect(&self, ray: Ray, ray_t: Interval) -> Option<Intersection<'_>> {
+        // Ray in grid coordinates, one unit per voxel.
+        let o = (ray.origin() - self.origin) / self.voxel_size;
+        let d = ray.direction() / self.voxel_size;
+        let (o, d) = ([o.x(), o.y(), o.z()], [d.x(), d.y(), d.z()]);
+
+        // Clip ray to grid box, remembering axis of entry face if ray starts outside.
+        let (mut t, mut t_exit) = (ray_t.start(), ray_t.end());
+        let mut axis = None;
+        for a in 0..3 {
+            let (t0, t1) = ((0.0 - o[a]) / d[a], (self.size[a] as f32 - o[a]) / d[a]);
+            let (t0, t1) = if t0 < t1 { (t0, t1) } else { (t1, t0) };
+            if t0.is_nan() {
+                return None; // Parallel to and on grid face.
+            }
+            if t0 > t {
+                t = t0;
+                axis = Some(a);
+            }
+            t_exit = t_exit.min(t1);
+        }
+        if t >= t_exit {
+            return None;
+        }
+
+        // Starting cell, entry face exactly on grid boundary.
+        let last = self.size.map(|s| s as i64 - 1);
+        let mut cell = [0, 1, 2].map(|a| ((o[a] + t * d[a]).floor() as i64).clamp(0, last[a]));
+        if let Some(a) = axis {
+            cell[a] = if d[a] > 0.0 { 0 } else { last[a] };
+        }
+
+        // Walk cells until crossing face between filled and empty voxel.
+        let mut inside = None;
+        loop {
+            let (node, min, side) = self.lookup(cell);
+            let filled = match node {
+                Node::Voxel(index) => Some(index),
+                _ => None,
+            };
+            if let Some(a) = axis {
+                let (index, outward) = match (inside, filled) {
+                    (None, Some(index)) => (index, -d[a].signum()),
+                    (Some(index), None) => (index, d[a].signum()),
+                    _ => (0, 0.0),
+                };
+                if outward != 0.0 {
+                    return Some(self.intersection(ray, t, a, outward, index));
+                }
+            }
+            inside = filled;
+
+            // Exit face of node, crossing into cell beyond it.
+            let mut t_next = f32::INFINITY;
+            for a in 0..3 {
+                let plane = if d[a] > 0.0 { min[a] + side } else { min[a] };
+                let ta = (plane as f32 - o[a]) / d[a];
+                if d[a] != 0.0 && ta < t_next {
+                    t_next = ta;
+                    axis = Some(a);
+                }
+            }
+            let a = axis?;
+            if t_next >= ray_t.end() {
+                return None;
+            }
+            t = t_next.max(t);
+
+            // Other axes from position within node, never stepping backwards.
+            for b in (0..3).filter(|&b| b != a) {
+                let position = ((o[b] + t * d[b]).floor() as i64).clamp(min[b], min[b] + side - 1);
+                cell[b] = if d[b] > 0.0 {
+                    position.max(cell[b])
+                } else if d[b] < 0.0 {
+                    position.min(cell[b])
+                } else {
+                    cell[b]
+                };
+            }
+            cell[a] = if d[a] > 0.0 {
+                min[a] + side
+            } else {
+                min[a] - 1
+            };
+
+            // Leaving grid ends walk, with hit if leaving filled voxel.
+            if cell[a] < 0 || cell[a] > last[a] {
+                let index = inside?;
+                return Some(self.intersection(ray, t, a, d[a].signum(), index));
+            }
+        }
+    }
+}
+
+impl Bounded for Voxels {
+    fn bounding_box(&self) -> Aabb {
+        Aabb::new(self.origin, self.origin + self.extent())
+    }
+}
+
+impl Sampleable for Voxels {
+    fn area(&self) -> f32 {
+        6.0 * self.positions.len() as f32 * self.voxel_size * self.voxel_size
+    }
+
+    fn sample_point(&self, rng: &mut impl Rng) -> SurfaceSample {
+        self.sample_face(rng).0
+    }
+
+    fn sample_from(&self, reference: Point3f, rng: &mut impl Rng) -> Option<SurfaceSample> {
+        // Reject samples on faces between neighboring voxels.
+        let (sample, neighbor) = self.sample_face(rng);
+        if self.index(neighbor).is_some() {
+            return None;
+        }
+        sample.to_solid_angle(reference)
+    }
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    /// L-shape of three unit voxels at (0,0,0), (1,0,0), (0,1,0) with two materials, grid at origin.
+    fn voxels() -> Voxels {
+        let palette = vec![Material::None, Material::preset("glass").unwrap()];
+        let voxels = [([0, 0, 0], 0), ([1, 0, 0], 0), ([0, 1, 0], 1)];
+        Voxels::new(Point3f::default(), 1.0, palette, &voxels)
+    }
+
+    #[test]
+    fn new() {
+        let v = voxels();
+        assert_eq!(v.count(), 3);
+        assert_eq!(v.size(), [2, 2, 1]);
+        assert_eq!(v.voxel([0, 0, 0]), Some(&Material::None));
+        assert_eq!(v.voxel([0, 1, 0]), Material::preset("glass").as_ref());
+        assert_eq!(v.voxel([1, 1, 0]), None);
+        assert_eq!(v.voxel([5, 0, 0]), None);
+        assert_eq!(v.center(), Point3f::new(1.0, 1.0, 0.5));
+        let mut scaled = v.clone();
+        scaled.set_voxel_size(2.0);
+        assert_eq!(scaled.center(), v.center());
+        assert_eq!(scaled.bounding_box().size(), Vector3f::new(4.0, 4.0, 2.0));
+
+        // Sparse: single voxel far from origin needs one branch per level only.
+        let far = Voxels::new(
+            Point3f::default(),
+            1.0,
+            vec![Material::None],
+            &[([100, 3, 60], 0)],
+        );
+        assert_eq!(far.nodes.len(), 1 + 7 * 8);
+        assert!(far.voxel([100, 3, 60]).is_some());
+    }
+
+    #[test]
+    fn intersect() {
+        let v = voxels();
+        let all = Interval::new(0.0, f32::INFINITY);
+
+        // Hit from outside on face, passing through empty cell first.
+        let r1 = Ray::new(Point3f::new(5.0, 1.5, 0.5), Vector3f::new(-1.0, 0.0, 0.0));
+        let i1 = v.intersect(r1, all).unwrap();
+        assert!((i1.t - 4.0).abs() < 1e-6);
+        assert_eq!(i1.normal, Vector3f::new(1.0, 0.0, 0.0));
+        assert_eq!(*i1.material, Material::preset("glass").unwrap());
+
+        // Ray starting inside leaves at far face, through touching voxels of other material.
+        let r2 = Ray::new(Point3f::new(0.5, 1.5, 0.5), Vector3f::new(0.0, -1.0, 0.0));
+        let i2 = v.intersect(r2, all).unwrap();
+        assert!((i2.t - 1.5).abs() < 1e-6);
+        assert_eq!(i2.normal, Vector3f::new(0.0, -1.0, 0.0));
+
+        // Diagonal ray through empty corner cell misses, interval excludes hit.
+        let r3 = Ray::new(Point3f::new(1.5, 3.0, 0.5), Vector3f::new(0.0, -1.0, 0.0));
+        assert!((v.intersect(r3, all).unwrap().t - 2.0).abs() < 1e-6);
+        assert_eq!(v.intersect(r3, Interval::new(0.0, 1.5)), None);
+        let r4 = Ray::new(Point3f::new(3.0, 3.0, 0.5), Vector3f::new(1.0, 1.0, 0.0));
+        assert_eq!(v.intersect(r4, all), None);
+    }
+
+    #[test]
+    fn random_rays() {
+        // Hits agree with brute force test against every voxel box, on random grid.
+        let mut rng = StdRng::seed_from_u64(42);
+        let voxels: Vec<([u32; 3], u8)> = (0..200)
+            .map(|_| ([0, 1, 2].map(|_| rng.gen_range(0..20)), 0))
+            .collect();
+        let v = Voxels::new(
+            Point3f::new(-2.0, 1.0, 0.0),
+            0.5,
+            vec![Material::None],
+            &voxels,
+        );
+        let center = v.center();
+        for _ in 0..500 {
+            let origin = center + 20.0 * Vector3f::random_unit_vector(&mut rng);
+            let target = center + 4.0 * Vector3f::random_unit_vector(&mut rng);
+            let ray = Ray::new(origin, target - origin);
+            let expected = v
+                .positions()
+                .iter()
+                .filter_map(|p| {
+                    let min =
+                        v.origin() + 0.5 * Vector3f::new(p[0] as f32, p[1] as f32, p[2] as f32);
+                    slab(ray, min, min + Vector3f::new(0.5, 0.5, 0.5))
+                })
+                .fold(f32::INFINITY, f32::min);
+            let actual = v
+                .intersect(ray, Interval::new(0.0, f32::INFINITY))
+                .map_or(f32::INFINITY, |i| i.t);
+            assert!(
+                (expected - actual).abs() < 1e-4 || expected == actual,
+                "{} != {}",
+                actual,
+                expected
+            );
+        }
+    }
+
+    /// Entry ray parameter of ray into box, if hit.
+    fn slab(ray: Ray, min: Point3f, max: Point3f) -> Option<f32> {
+        let (o, d) = (ray.origin(), ray.direction());
+        let mut interval = (0.0f32, f32::INFINITY);
+        for (o, d, min, max) in [
+            (o.x(), d.x(), min.x(), max.x()),
+            (o.y(), d.y(), min.y(), max.y()),
+            (o.z(), d.z(), min.z(), max.z()),
+        ] {
+            let (t0, t1) = ((min - o) / d, (max - o) / d);
+            interval = (interval.0.max(t0.min(t1)), interval.1.min(t0.max(t1)));
+        }
+        (interval.0 < interval.1).then_some(interval.0)
+    }
+
+    #[test]
+    fn sample_from() {
+        // Accepted samples lie on outer faces, area counts all faces.
+        let v = voxels();
+        assert_eq!(v.area(), 18.0);
+        let mut rng = StdRng::seed_from_u64(42);
+        let reference = Point3f::new(5.0, 5.0, 5.0);
+        let mut accepted = 0;
+        for _ in 0..1000 {
+            if let Some(sample) = v.sample_from(reference, &mut rng) {
+                accepted += 1;
+                let p = sample.point + 0.01 * sample.normal;
+                let cell = [p.x(), p.y(), p.z()].map(|x| x.floor() as i64);
+                assert_eq!(v.index(cell), None);
+            }
+        }
+
+        // 14 of 18 faces are exposed.
+        assert!((accepted as f32 / 1000.0 - 14.0 / 18.0).abs() < 0.05);
+    }
+}
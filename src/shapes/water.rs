@@ -0,0 +1,345 @@
+use crate::base::{
+    aabb::Aabb,
+    hash::hash_float,
+    interval::Interval,
+    material::Material,
+    point::Point3f,
+    ray::Ray,
+    shape::{Bounded, Intersectable, Intersection, Sampleable, SurfaceSample},
+    vector::Vector3f,
+};
+use rand::Rng;
+use std::{
+    f32::consts::TAU,
+    hash::{Hash, Hasher},
+};
+
+/// Count of summed sine waves.
+const WAVE_COUNT: usize = 4;
+
+/// Max ray marching steps per intersection.
+const MAX_STEPS: u32 = 512;
+
+/// Square horizontal water surface displaced by procedural waves. The surface is a heightfield summing sine waves
+/// of decreasing wavelength and amplitude, travelling in different directions.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Water {
+    /// Center position of undisturbed surface.
+    center: Point3f,
+
+    /// Edge length of square surface.
+    size: f32,
+
+    /// Max displacement above or below undisturbed surface.
+    amplitude: f32,
+
+    /// Wavelength of longest wave.
+    wavelength: f32,
+
+    /// Wave phase offset in radians, advancing it animates the waves.
+    phase: f32,
+
+    /// Surface material.
+    material: Material,
+}
+
+impl Water {
+    /// Creates water surface with center position, edge length, max wave displacement and longest wavelength.
+    pub fn new(
+        center: Point3f,
+        size: f32,
+        amplitude: f32,
+        wavelength: f32,
+        material: Material,
+    ) -> Self {
+        assert!(size > 0.0 && amplitude >= 0.0 && wavelength > 0.0);
+        Water {
+            center,
+            size,
+            amplitude,
+            wavelength,
+            phase: 0.0,
+            material,
+        }
+    }
+
+    /// Center position of undisturbed surface.
+    pub fn center(&self) -> Point3f {
+        self.center
+    }
+
+    /// Sets center position of undisturbed surface.
+    pub fn set_center(&mut self, center: Point3f) {
+        self.center = center;
+    }
+
+    /// Edge length of square surface.
+    pub fn size(&self) -> f32 {
+        self.size
+    }
+
+    /// Max displacement above or below undisturbed surface.
+    pub fn amplitude(&self) -> f32 {
+        self.amplitude
+    }
+
+    /// Wavelength of longest wave.
+    pub fn wavelength(&self) -> f32 {
+        self.wavelength
+    }
+
+    /// Wave phase offset in radians.
+    pub fn phase(&self) -> f32 {
+        self.phase
+    }
+
+    /// Sets wave phase offset in radians.
+    pub fn set_phase(&mut self, phase: f32) {
+        self.phase = phase;
+    }
+
+    /// Surface material.
+    pub fn material(&self) -> &Material {
+        &self.material
+    }
+
+    /// Sets surface material.
+    pub fn set_material(&mut self, material: Material) {
+        self.material = material;
+    }
+
+    /// Surface height and its x,z-derivatives at horizontal position.
+    pub fn height_at(&self, x: f32, z: f32) -> (f32, f32, f32) {
+        let (x, z) = (x - self.center.x(), z - self.center.z());
+        let mut height = self.center.y();
+        let (mut dx, mut dz) = (0.0, 0.0);
+        for (amplitude, k, direction, phase) in self.waves() {
+            let angle = k * (direction.0 * x + direction.1 * z) + phase;
+            height += amplitude * angle.sin();
+            let slope = amplitude * k * angle.cos();
+            dx += slope * direction.0;
+            dz += slope * direction.1;
+        }
+        (height, dx, dz)
+    }
+
+    /// Upward unit normal at horizontal position.
+    pub fn normal_at(&self, x: f32, z: f32) -> Vector3f {
+        let (_, dx, dz) = self.height_at(x, z);
+        Vector3f::new(-dx, 1.0, -dz).normalize()
+    }
+
+    /// Amplitude, wave number, horizontal unit direction and phase of each wave. Each wave has 0.55 times the
+    /// wavelength and half the amplitude of the previous one, amplitudes sum up to max displacement.
+    fn waves(&self) -> impl Iterator<Item = (f32, f32, (f32, f32), f32)> + '_ {
+        let norm: f32 = (0..WAVE_COUNT).map(|i| 0.5f32.powi(i as i32)).sum();
+        (0..WAVE_COUNT).map(move |i| {
+            let amplitude = self.amplitude * 0.5f32.powi(i as i32) / norm;
+            let k = TAU / (self.wavelength * 0.55f32.powi(i as i32));
+            let theta = 2.4 * i as f32; // Golden angle spreads directions.
+            let phase = self.phase * (1.0 + 0.3 * i as f32) + 1.7 * i as f32;
+            (amplitude, k, (theta.cos(), theta.sin()), phase)
+        })
+    }
+
+    /// Upper bound of surface slope.
+    fn max_slope(&self) -> f32 {
+        self.waves().map(|(amplitude, k, _, _)| amplitude * k).sum()
+    }
+}
+
+impl Hash for Water {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.center.hash(state);
+        hash_float(self.size, state);
+        hash_float(self.amplitude, state);
+        hash_float(self.wavelength, state);
+        hash_float(self.phase, state);
+        self.material.hash(state);
+    }
+}
+
+impl Intersectable for Water {
+    fn intersect(&self, ray: Ray, ray_t: Interval) -> Option<Intersection<'_>> {
+        // Clip ray to bounding box of surface.
+        let bounds = self.bounding_box();
+        let t = bounds.hit(ray, ray_t)?;
+        let (t_start, t_end) = (t.start(), t.end());
+
+        // Signed height of ray above surface.
+        let above = |t: f32| {
+            let p = ray.at(t);
+            p.y() - self.height_at(p.x(), p.z()).0
+        };
+
+        // March conservatively: signed height changes at most by rate per unit t.
+        let d = ray.direction();
+        let rate = d.y().abs() + self.max_slope() * (d.x() * d.x() + d.z() * d.z()).sqrt();
+        let min_step = 1e-4 * self.wavelength / d.length();
+        let (mut t, mut f) = (t_start, above(t_start));
+        let mut bracket = None;
+        for _ in 0..MAX_STEPS {
+            let next = (t + (f.abs() / rate).max(min_step)).min(t_end);
+            let f_next = above(next);
+            if f_next == 0.0 || f_next.signum() != f.signum() {
+                bracket = Some((t, f, next));
+                break;
+            }
+            if next >= t_end {
+                return None;
+            }
+            (t, f) = (next, f_next);
+        }
+        let (mut t0, f0, mut t1) = bracket?;
+
+        // Refine crossing by bisection. The remaining bracket bounds the error of the point beyond rounding.
+        for _ in 0..24 {
+            let mid = 0.5 * (t0 + t1);
+            if above(mid).signum() == f0.signum() {
+                t0 = mid;
+            } else {
+                t1 = mid;
+            }
+        }
+        let root = t1;
+        if !ray_t.contains(root) {
+            return None;
+        }
+        let point = ray.at(root);
+
+        // Return intersection struct.
+        let intersection = Intersection {
+            point,
+            material: &self.material,
+            normal: self.normal_at(point.x(), point.z()),
+            uv: (
+                (point.x() - bounds.min().x()) / self.size,
+                (point.z() - bounds.min().z()) / self.size,
+            ),
+            t: root,
+            error: ray.error_at(root) + (t1 - t0) * ray.direction().abs(),
+        };
+        Some(intersection)
+    }
+}
+
+impl Bounded for Water {
+    fn bounding_box(&self) -> Aabb {
+        let half = Vector3f::new(0.5 * self.size, self.amplitude, 0.5 * self.size);
+        Aabb::new(self.center - half, self.center + half)
+    }
+}
+
+impl Sampleable for Water {
+    /// Area of undisturbed surface, which approximates the slightly larger wavy surface.
+    fn area(&self) -> f32 {
+        self.size * self.size
+    }
+
+    fn sample_point(&self, rng: &mut impl Rng) -> SurfaceSample {
+        let x = self.center.x() + self.size * (rng.gen::<f32>() - 0.5);
+        let z = self.center.z() + self.size * (rng.gen::<f32>() - 0.5);
+        SurfaceSample {
+            point: Point3f::new(x, self.height_at(x, z).0, z),
+            normal: self.normal_at(x, z),
+            pdf: 1.0 / self.area(),
+        }
+    }
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    /// Water surface with center at y=1.
+    fn water() -> Water {
+        Water::new(Point3f::new(0.0, 1.0, 0.0), 10.0, 0.2, 2.0, Material::None)
+    }
+
+    #[test]
+    fn height() {
+        let w = water();
+        let mut rng = StdRng::seed_from_u64(42);
+        for _ in 0..100 {
+            let (x, z) = (rng.gen_range(-5.0..5.0), rng.gen_range(-5.0..5.0));
+            let (h, dx, dz) = w.height_at(x, z);
+            assert!((h - 1.0).abs() <= 0.2);
+
+            // Derivatives match finite differences.
+            let e = 1e-2;
+            let fdx = (w.height_at(x + e, z).0 - w.height_at(x - e, z).0) / (2.0 * e);
+            let fdz = (w.height_at(x, z + e).0 - w.height_at(x, z - e).0) / (2.0 * e);
+            assert!((dx - fdx).abs() < 1e-2 && (dz - fdz).abs() < 1e-2);
+            assert!(dx.abs() + dz.abs() <= 2.0 * w.max_slope());
+        }
+
+        // Flat water is a plane.
+        let flat = Water::new(Point3f::default(), 1.0, 0.0, 1.0, Material::None);
+        assert_eq!(flat.height_at(0.3, 0.2), (0.0, 0.0, 0.0));
+        assert_eq!(flat.normal_at(0.3, 0.2), Vector3f::new(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn intersect() {
+        let w = water();
+        let mut rng = StdRng::seed_from_u64(42);
+        for _ in 0..100 {
+            // Rays from above and below through surface point, steep and grazing.
+            let (x, z) = (rng.gen_range(-4.0..4.0), rng.gen_range(-4.0..4.0));
+            let target = Point3f::new(x, w.height_at(x, z).0, z);
+            let direction = Vector3f::new(
+                rng.gen_range(-1.0..1.0),
+                rng.gen_range(-1.0..1.0),
+                rng.gen_range(-1.0..1.0),
+            );
+            let ray = Ray::new(target - 0.5 * direction, direction);
+            let isect = w
+                .intersect(ray, Interval::new(0.001, f32::INFINITY))
+                .unwrap();
+            let (h, _, _) = w.height_at(isect.point.x(), isect.point.z());
+            assert!((isect.point.y() - h).abs() < 1e-4 && isect.t < 0.5 + 1e-4);
+            assert!((isect.normal.length() - 1.0).abs() < 1e-5 && isect.normal.y() > 0.0);
+            assert_eq!(isect.point, ray.at(isect.t));
+            assert!((isect.uv.0 - (isect.point.x() + 5.0) / 10.0).abs() < 1e-6);
+            assert!((isect.uv.1 - (isect.point.z() + 5.0) / 10.0).abs() < 1e-6);
+        }
+
+        // Rays missing the square or ending before the surface.
+        let down = Vector3f::new(0.0, -1.0, 0.0);
+        let outside = Ray::new(Point3f::new(6.0, 3.0, 0.0), down);
+        assert_eq!(
+            w.intersect(outside, Interval::new(0.0, f32::INFINITY)),
+            None
+        );
+        let short = Ray::new(Point3f::new(0.0, 3.0, 0.0), down);
+        assert_eq!(w.intersect(short, Interval::new(0.0, 1.5)), None);
+        let parallel = Ray::new(Point3f::new(-6.0, 2.0, 0.0), Vector3f::new(1.0, 0.0, 0.0));
+        assert_eq!(
+            w.intersect(parallel, Interval::new(0.0, f32::INFINITY)),
+            None
+        );
+    }
+
+    #[test]
+    fn sample_point() {
+        let w = water();
+        assert_eq!(w.area(), 100.0);
+        let mut rng = StdRng::seed_from_u64(42);
+        for _ in 0..100 {
+            let sample = w.sample_point(&mut rng);
+            let p = sample.point;
+            assert!(p.x().abs() <= 5.0 && p.z().abs() <= 5.0);
+            assert_eq!(p.y(), w.height_at(p.x(), p.z()).0);
+            assert_eq!(sample.pdf, 0.01);
+        }
+    }
+
+    #[test]
+    fn bounding_box() {
+        let b = water().bounding_box();
+        assert_eq!(b.min(), Point3f::new(-5.0, 0.8, -5.0));
+        assert_eq!(b.max(), Point3f::new(5.0, 1.2, 5.0));
+    }
+}
@@ -0,0 +1,287 @@
+use crate::{
+    base::{
+        color::Color3f,
+        hash::hash_float,
+        onb::Onb,
+        sampling::{uniform_cone, uniform_cone_pdf},
+        vector::Vector3f,
+    },
+    tonemap::LUMINANCE_PER_RADIANCE,
+};
+use rand::Rng;
+use std::{
+    f32::consts::{FRAC_PI_2, PI},
+    hash::{Hash, Hasher},
+};
+
+/// Radiance per sky luminance in kcd/m², so a clear zenith is about one.
+const SKY_SCALE: f32 = 1000.0 / LUMINANCE_PER_RADIANCE;
+
+/// Radiance of the sun disk outside the atmosphere, from its luminance of 1.6e9 cd/m².
+const SUN_RADIANCE: f32 = 1.6e6 * SKY_SCALE;
+
+/// Cosine of angular radius of the sun disk (0.27 degrees).
+const SUN_COS_RADIUS: f32 = 0.999_989;
+
+/// Clear sky by the Preetham model, lit by a sun disk which integrators sample explicitly. Directions are y-up, as
+/// for all backgrounds. Turbidity describes haze, from 2 for clear to 10 for hazy skies.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PhysicalSky {
+    /// Unit direction towards the sun.
+    sun_direction: Vector3f,
+
+    /// Atmospheric turbidity.
+    turbidity: f32,
+
+    /// Luminance and chromaticity x, y at zenith.
+    zenith: [f32; 3],
+
+    /// Perez distribution coefficients A to E of luminance and chromaticity x, y.
+    perez: [[f32; 5]; 3],
+
+    /// Radiance of sun disk after extinction by atmosphere.
+    sun_radiance: Color3f,
+
+    /// Factor on radiance of sun disk.
+    sun_intensity: f32,
+
+    /// Factor on radiance of sky.
+    sky_brightness: f32,
+}
+
+impl PhysicalSky {
+    /// Creates sky with direction towards the sun, which need not be unit length, and turbidity clamped to the
+    /// valid range [1.7,10] of the model. Suns below the horizon leave a dim twilight sky.
+    pub fn new(sun_direction: Vector3f, turbidity: f32) -> Self {
+        let sun_direction = sun_direction.normalize();
+        let t = turbidity.clamp(1.7, 10.0);
+        let theta_s = sun_direction.y().clamp(0.0, 1.0).acos();
+
+        // Zenith luminance and chromaticity, cubic in sun zenith angle.
+        let chi = (4.0 / 9.0 - t / 120.0) * (PI - 2.0 * theta_s);
+        let cubic = |c: [f32; 4]| ((c[0] * theta_s + c[1]) * theta_s + c[2]) * theta_s + c[3];
+        let chromaticity = |t2: [f32; 4], t1: [f32; 4], t0: [f32; 4]| {
+            t * t * cubic(t2) + t * cubic(t1) + cubic(t0)
+        };
+        let zenith = [
+            (4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192,
+            chromaticity(
+                [0.00166, -0.00375, 0.00209, 0.0],
+                [-0.02903, 0.06377, -0.03202, 0.00394],
+                [0.11693, -0.21196, 0.06052, 0.25886],
+            ),
+            chromaticity(
+                [0.00275, -0.00610, 0.00317, 0.0],
+                [-0.04214, 0.08970, -0.04153, 0.00516],
+                [0.15346, -0.26756, 0.06670, 0.26688],
+            ),
+        ];
+        let perez = [
+            [
+                0.1787 * t - 1.4630,
+                -0.3554 * t + 0.4275,
+                -0.0227 * t + 5.3251,
+                0.1206 * t - 2.5771,
+                -0.0670 * t + 0.3703,
+            ],
+            [
+                -0.0193 * t - 0.2592,
+                -0.0665 * t + 0.0008,
+                -0.0004 * t + 0.2125,
+                -0.0641 * t - 0.8989,
+                -0.0033 * t + 0.0452,
+            ],
+            [
+                -0.0167 * t - 0.2608,
+                -0.0950 * t + 0.0092,
+                -0.0079 * t + 0.2102,
+                -0.0441 * t - 1.6537,
+                -0.0109 * t + 0.0529,
+            ],
+        ];
+
+        // Extinction of sunlight along air mass (Kasten-Young) by Rayleigh and aerosol scattering at 680, 550 and
+        // 440 nm.
+        let sun_radiance = if sun_direction.y() > 0.0 {
+            let elevation = 90.0 - theta_s.to_degrees();
+            let air_mass = 1.0 / (theta_s.cos() + 0.50572 * (elevation + 6.07995).powf(-1.6364));
+            let beta = 0.04608 * t - 0.04586;
+            let extinction = |rayleigh: f32, wavelength: f32| {
+                (-air_mass * (rayleigh + beta * wavelength.powf(-1.3))).exp()
+            };
+            Color3f::new(
+                extinction(0.0417, 0.68),
+                extinction(0.0975, 0.55),
+                extinction(0.2380, 0.44),
+            ) * SUN_RADIANCE
+        } else {
+            Color3f::black()
+        };
+
+        PhysicalSky {
+            sun_direction,
+            turbidity: t,
+            zenith,
+            perez,
+            sun_radiance,
+            sun_intensity: 1.0,
+            sky_brightness: 1.0,
+        }
+    }
+
+    /// Unit direction towards the sun.
+    pub fn sun_direction(&self) -> Vector3f {
+        self.sun_direction
+    }
+
+    /// Atmospheric turbidity.
+    pub fn turbidity(&self) -> f32 {
+        self.turbidity
+    }
+
+    /// Radiance of sun disk after extinction by atmosphere times sun intensity, black for suns below the horizon.
+    pub fn sun_radiance(&self) -> Color3f {
+        self.sun_radiance * self.sun_intensity
+    }
+
+    /// Factor on radiance of sun disk, one by default.
+    pub fn sun_intensity(&self) -> f32 {
+        self.sun_intensity
+    }
+
+    /// Sets factor on radiance of sun disk relative to the physical model, e.g. zero to light by sky only.
+    pub fn set_sun_intensity(&mut self, sun_intensity: f32) {
+        assert!(sun_intensity >= 0.0 && sun_intensity.is_finite());
+        self.sun_intensity = sun_intensity;
+    }
+
+    /// Factor on radiance of sky without sun disk, one by default.
+    pub fn sky_brightness(&self) -> f32 {
+        self.sky_brightness
+    }
+
+    /// Sets factor on radiance of sky without sun disk relative to the physical model, where a clear zenith is
+    /// about one.
+    pub fn set_sky_brightness(&mut self, sky_brightness: f32) {
+        assert!(sky_brightness >= 0.0 && sky_brightness.is_finite());
+        self.sky_brightness = sky_brightness;
+    }
+
+    /// Radiance of sky and sun disk seen along direction.
+    pub fn color(&self, direction: Vector3f) -> Color3f {
+        let direction = direction.normalize();
+        let mut color = self.sky_color(direction);
+        if direction.dot(&self.sun_direction) >= SUN_COS_RADIUS {
+            color += self.sun_radiance();
+        }
+        color
+    }
+
+    /// Radiance of sky without sun disk seen along unit direction. Below the horizon, the horizon continues.
+    pub fn sky_color(&self, direction: Vector3f) -> Color3f {
+        let theta = direction.y().max(0.0).acos().min(FRAC_PI_2 - 1e-3);
+        let gamma = direction.dot(&self.sun_direction).clamp(-1.0, 1.0).acos();
+        let theta_s = self.sun_direction.y().clamp(0.0, 1.0).acos();
+        let [luminance, x, y] = [0, 1, 2].map(|i| {
+            let p = self.perez[i];
+            let f = |theta: f32, gamma: f32| {
+                (1.0 + p[0] * (p[1] / theta.cos()).exp())
+                    * (1.0 + p[2] * (p[3] * gamma).exp() + p[4] * gamma.cos().powi(2))
+            };
+            self.zenith[i] * f(theta, gamma) / f(0.0, theta_s)
+        });
+
+        // CIE xyY to linear sRGB.
+        let luminance = luminance.max(0.0) * SKY_SCALE * self.sky_brightness;
+        let (cx, cz) = (x / y * luminance, (1.0 - x - y) / y * luminance);
+        Color3f::new(
+            (3.2406 * cx - 1.5372 * luminance - 0.4986 * cz).max(0.0),
+            (-0.9689 * cx + 1.8758 * luminance + 0.0415 * cz).max(0.0),
+            (0.0557 * cx - 0.2040 * luminance + 1.0570 * cz).max(0.0),
+        )
+    }
+
+    /// Samples unit direction uniformly within sun disk. Returns direction and its probability density w.r.t. solid
+    /// angle, or none if the sun is below the horizon.
+    pub fn sample_sun(&self, rng: &mut impl Rng) -> Option<(Vector3f, f32)> {
+        if self.sun_radiance() == Color3f::black() {
+            return None;
+        }
+        let direction = Onb::new(self.sun_direction).to_world(uniform_cone(SUN_COS_RADIUS, rng));
+        Some((direction, uniform_cone_pdf(SUN_COS_RADIUS)))
+    }
+
+    /// Probability density w.r.t. solid angle of `sample_sun` sampling direction.
+    pub fn sun_pdf(&self, direction: Vector3f) -> f32 {
+        if self.sun_radiance() == Color3f::black()
+            || direction.normalize().dot(&self.sun_direction) < SUN_COS_RADIUS
+        {
+            return 0.0;
+        }
+        uniform_cone_pdf(SUN_COS_RADIUS)
+    }
+}
+
+impl Hash for PhysicalSky {
+    /// Hashes parameters, all other fields derive from them.
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.sun_direction.hash(state);
+        hash_float(self.turbidity, state);
+        hash_float(self.sun_intensity, state);
+        hash_float(self.sky_brightness, state);
+    }
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn color() {
+        let sky = PhysicalSky::new(Vector3f::new(0.0, 1.0, 1.0), 3.0);
+        let zenith = sky.color(Vector3f::new(0.0, 1.0, 0.0));
+        let horizon = sky.color(Vector3f::new(0.0, 0.0, -1.0));
+
+        // Blue zenith around one, brighter towards horizon and sun, bright yellowish sun disk.
+        assert!(zenith.b() > zenith.r() && zenith.g() > 0.2 && zenith.g() < 2.0);
+        assert!(horizon.luminance() > zenith.luminance());
+        let near_sun = sky.color(Vector3f::new(0.0, 1.0, 1.2));
+        assert!(near_sun.luminance() > 2.0 * zenith.luminance());
+        let sun = sky.color(sky.sun_direction()) - sky.sky_color(sky.sun_direction());
+        assert_eq!(sun, sky.sun_radiance());
+        assert!(sun.r() > sun.b() && sun.g() > 1e4);
+
+        // Haze brightens sky, sets sun.
+        let hazy = PhysicalSky::new(sky.sun_direction(), 8.0);
+        assert!(hazy.color(Vector3f::new(0.0, 1.0, 0.0)).luminance() > zenith.luminance());
+        assert!(hazy.sun_radiance().g() < sun.g());
+        let night = PhysicalSky::new(Vector3f::new(0.0, -1.0, 1.0), 3.0);
+        assert_eq!(night.sun_radiance(), Color3f::black());
+        assert_eq!(night.sample_sun(&mut StdRng::seed_from_u64(42)), None);
+
+        // Sun and sky scale independently, a sun without intensity is not sampled.
+        let mut scaled = sky;
+        scaled.set_sun_intensity(0.5);
+        scaled.set_sky_brightness(2.0);
+        assert_eq!(scaled.sun_radiance(), sky.sun_radiance() * 0.5);
+        let zenith_direction = Vector3f::new(0.0, 1.0, 0.0);
+        assert_eq!(scaled.color(zenith_direction), zenith * 2.0);
+        scaled.set_sun_intensity(0.0);
+        assert_eq!(scaled.sample_sun(&mut StdRng::seed_from_u64(42)), None);
+        assert_eq!(scaled.sun_pdf(sky.sun_direction()), 0.0);
+    }
+
+    #[test]
+    fn sample_sun() {
+        let sky = PhysicalSky::new(Vector3f::new(1.0, 1.0, 0.0), 3.0);
+        let mut rng = StdRng::seed_from_u64(42);
+        for _ in 0..100 {
+            let (direction, pdf) = sky.sample_sun(&mut rng).unwrap();
+            assert_eq!(sky.sun_pdf(direction), pdf);
+            assert!(pdf > 1e4);
+        }
+        assert_eq!(sky.sun_pdf(Vector3f::new(0.0, 1.0, 0.0)), 0.0);
+    }
+}
@@ -0,0 +1,235 @@
+use crate::base::material::Material;
+
+/// Share of a material's bounces being total internal reflections above which light counts as trapped inside it.
+const TRAPPED_SHARE: f64 = 0.5;
+
+/// Share of paths ending at max depth above which hints are given.
+const MAX_DEPTH_SHARE: f64 = 0.01;
+
+/// Path events at surfaces of one material.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MaterialStats {
+    /// Material of surfaces.
+    pub material: Material,
+
+    /// Count of interactions continuing paths.
+    pub bounces: u64,
+
+    /// Count of bounces being total internal reflections inside dielectrics.
+    pub tir_bounces: u64,
+
+    /// Count of paths cut off at max depth right after bouncing off material, i.e. absorbed although light might
+    /// have arrived along them.
+    pub max_depth_paths: u64,
+}
+
+/// Path statistics of render, collected by integrators per surface material. They explain dark dielectrics, where
+/// paths run out of depth while bouncing inside the medium.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RenderStats {
+    /// Count of camera paths.
+    paths: u64,
+
+    /// Events per material, in order of first occurrence.
+    materials: Vec<MaterialStats>,
+}
+
+impl RenderStats {
+    /// Count of camera paths.
+    pub fn paths(&self) -> u64 {
+        self.paths
+    }
+
+    /// Events per material, in order of first occurrence.
+    pub fn materials(&self) -> &[MaterialStats] {
+        &self.materials
+    }
+
+    /// Events of material, if it occurred.
+    pub fn material(&self, material: &Material) -> Option<&MaterialStats> {
+        self.materials.iter().find(|m| m.material == *material)
+    }
+
+    /// Records camera path.
+    pub fn record_path(&mut self) {
+        self.paths += 1;
+    }
+
+    /// Records bounce off material continuing path, whether it was a total internal reflection and whether the path
+    /// is cut off at max depth afterwards.
+    pub fn record_bounce(&mut self, material: &Material, tir: bool, max_depth: bool) {
+        let stats = self.entry(material);
+        stats.bounces += 1;
+        stats.tir_bounces += u64::from(tir);
+        stats.max_depth_paths += u64::from(max_depth);
+    }
+
+    /// Adds counts of other stats, e.g. of another render tile.
+    pub fn merge(&mut self, other: &RenderStats) {
+        self.paths += other.paths;
+        for m in other.materials.iter() {
+            let stats = self.entry(&m.material);
+            stats.bounces += m.bounces;
+            stats.tir_bounces += m.tir_bounces;
+            stats.max_depth_paths += m.max_depth_paths;
+        }
+    }
+
+    /// Stats as text table, one material per line, followed by hints for materials where many paths end at max
+    /// depth.
+    pub fn to_text(&self) -> String {
+        let mut text = format!("paths: {}\n", self.paths);
+        text += "material                        bounces      tir  max depth\n";
+        for m in self.materials.iter() {
+            text += &format!(
+                "{:<30} {:>8} {:>7.1}% {:>9.2}%\n",
+                label(&m.material),
+                m.bounces,
+                100.0 * share(m.tir_bounces, m.bounces),
+                100.0 * share(m.max_depth_paths, self.paths),
+            );
+        }
+        for hint in self.hints() {
+            text += &format!("hint: {}\n", hint);
+        }
+        text
+    }
+
+    /// Hints for materials where many paths end at max depth. Light bouncing mostly by total internal reflection is
+    /// trapped inside the medium, where more depth converges slowly and regularization (e.g. roughening glass after
+    /// the first bounces) helps more.
+    pub fn hints(&self) -> Vec<String> {
+        self.materials
+            .iter()
+            .filter(|m| share(m.max_depth_paths, self.paths) > MAX_DEPTH_SHARE)
+            .map(|m| {
+                let ended = 100.0 * share(m.max_depth_paths, self.paths);
+                if share(m.tir_bounces, m.bounces) > TRAPPED_SHARE {
+                    format!(
+                        "{:.1}% of paths end at max depth in {}, trapped by total internal reflection; \
+                         regularization would help more than depth",
+                        ended,
+                        label(&m.material)
+                    )
+                } else {
+                    format!(
+                        "{:.1}% of paths end at max depth at {}; increasing max depth would brighten it",
+                        ended,
+                        label(&m.material)
+                    )
+                }
+            })
+            .collect()
+    }
+
+    /// Events of material, added if missing.
+    fn entry(&mut self, material: &Material) -> &mut MaterialStats {
+        let index = match self.materials.iter().position(|m| m.material == *material) {
+            Some(index) => index,
+            None => {
+                self.materials.push(MaterialStats {
+                    material: material.clone(),
+                    bounces: 0,
+                    tir_bounces: 0,
+                    max_depth_paths: 0,
+                });
+                self.materials.len() - 1
+            }
+        };
+        &mut self.materials[index]
+    }
+}
+
+/// Share of count in total, zero if total is zero.
+fn share(count: u64, total: u64) -> f64 {
+    if total > 0 {
+        count as f64 / total as f64
+    } else {
+        0.0
+    }
+}
+
+/// Short description of material.
+fn label(material: &Material) -> String {
+    match material {
+        Material::Custom(_) => "custom".to_string(),
+        Material::Dielectric(d) => format!("dielectric (ior {:.2})", d.ior()),
+        Material::DiffuseLight(_) => "diffuse light".to_string(),
+        Material::Lambert(l) => {
+            let a = l.albedo();
+            format!("lambert ({:.2} {:.2} {:.2})", a.r(), a.g(), a.b())
+        }
+        Material::Metal(m) => format!("metal (fuzz {:.2})", m.fuzz()),
+        Material::Microfacet(m) => match m.ior() {
+            Some(ior) => format!(
+                "rough dielectric (ior {:.2}, roughness {:.2})",
+                ior,
+                m.roughness()
+            ),
+            None => format!("rough metal (roughness {:.2})", m.roughness()),
+        },
+        Material::Mix(m) => format!("mix of {} and {}", label(m.first()), label(m.second())),
+        Material::NormalMapped(n) => format!("normal mapped {}", label(n.material())),
+        Material::Principled(p) => {
+            let (m, r, t) = (p.metallic(), p.roughness(), p.transmission());
+            format!("principled (metallic {m:.2}, roughness {r:.2}, transmission {t:.2})")
+        }
+        Material::ShadowCatcher(_) => "shadow catcher".to_string(),
+
+        #[cfg(test)]
+        Material::None => "none".to_string(),
+    }
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        base::color::Color3f,
+        materials::{dielectric::Dielectric, lambert::Lambert},
+    };
+
+    #[test]
+    fn record() {
+        let glass = Material::Dielectric(Dielectric::new(1.5));
+        let mut stats = RenderStats::default();
+        for i in 0..10 {
+            stats.record_path();
+            stats.record_bounce(&glass, i < 8, i < 5);
+            stats.record_bounce(&Material::None, false, false);
+        }
+        let mut merged = stats.clone();
+        merged.merge(&stats);
+        assert_eq!(merged.paths(), 20);
+        assert_eq!(merged.materials().len(), 2);
+        let glass_stats = merged.material(&glass).unwrap();
+        assert_eq!(
+            (
+                glass_stats.bounces,
+                glass_stats.tir_bounces,
+                glass_stats.max_depth_paths
+            ),
+            (20, 16, 10)
+        );
+
+        // Glass traps light, other material needs no hint.
+        let hints = merged.hints();
+        assert_eq!(hints.len(), 1);
+        assert!(hints[0]
+            .starts_with("50.0% of paths end at max depth in dielectric (ior 1.50), trapped"));
+        let text = merged.to_text();
+        assert!(text.starts_with("paths: 20\n"));
+        assert_eq!(text.lines().count(), 5);
+    }
+
+    #[test]
+    fn depth_hint() {
+        let lambert = Material::Lambert(Lambert::new(Color3f::white()));
+        let mut stats = RenderStats::default();
+        stats.record_path();
+        stats.record_bounce(&lambert, false, true);
+        assert!(stats.hints()[0].contains("increasing max depth"));
+        assert!(RenderStats::default().hints().is_empty());
+    }
+}
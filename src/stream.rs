@@ -0,0 +1,424 @@
+use crate::{
+    base::color::Color3f, camera::Camera, film::Film, scene::Scene, settings::RenderSettings,
+    stats::RenderStats, tile::Tile,
+};
+use rayon::ThreadPoolBuilder;
+use std::{
+    collections::VecDeque,
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Condvar, Mutex,
+    },
+    task::{Context, Poll, Waker},
+    thread::{self, JoinHandle},
+};
+
+/// Update of a render running in the background.
+#[derive(Clone, Debug, PartialEq)]
+pub enum RenderUpdate {
+    /// Finished tile with its pixels in row-major order.
+    Tile { tile: Tile, pixels: Vec<Color3f> },
+
+    /// Final image, last update of a render.
+    Done(Film),
+}
+
+/// Updates not yet taken from stream, shared with render thread.
+#[derive(Default)]
+struct Shared {
+    /// Pending updates, oldest first.
+    updates: VecDeque<RenderUpdate>,
+
+    /// Whether render thread has exited.
+    finished: bool,
+
+    /// Waker of task awaiting next update.
+    waker: Option<Waker>,
+}
+
+impl Shared {
+    /// Queues update and wakes awaiting task.
+    fn push(&mut self, update: RenderUpdate) {
+        self.updates.push_back(update);
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// Marks stream finished when render thread exits, also by panic, so awaiting tasks do not hang.
+struct FinishGuard(Arc<Mutex<Shared>>);
+
+impl Drop for FinishGuard {
+    fn drop(&mut self) {
+        let mut shared = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        shared.finished = true;
+        if let Some(waker) = shared.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// Stream of updates of a render on a background thread, see `Camera::render_async`. Awaiting updates never blocks,
+/// so async runtimes like tokio keep serving other tasks. The stream works with any runtime, as it only relies on
+/// wakers. Dropping it cancels the render.
+pub struct RenderStream {
+    /// Updates shared with render thread.
+    shared: Arc<Mutex<Shared>>,
+
+    /// Whether render is cancelled.
+    cancelled: Arc<AtomicBool>,
+}
+
+impl RenderStream {
+    /// Starts rendering scene with camera and settings on a new thread.
+    pub(crate) fn spawn(mut camera: Camera, scene: Arc<Scene>, settings: RenderSettings) -> Self {
+        let shared = Arc::new(Mutex::new(Shared::default()));
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let (thread_shared, thread_cancelled) = (shared.clone(), cancelled.clone());
+        thread::spawn(move || {
+            let _guard = FinishGuard(thread_shared.clone());
+            let film = camera.render_observed(&scene, &settings, |tile, pixels| {
+                thread_shared.lock().unwrap().push(RenderUpdate::Tile {
+                    tile: *tile,
+                    pixels: pixels.to_vec(),
+                });
+                !thread_cancelled.load(Ordering::Relaxed)
+            });
+            if !thread_cancelled.load(Ordering::Relaxed) {
+                thread_shared.lock().unwrap().push(RenderUpdate::Done(film));
+            }
+        });
+        RenderStream { shared, cancelled }
+    }
+
+    /// Next update, or none once the render has finished and all updates were taken.
+    pub fn next_update(&mut self) -> NextUpdate<'_> {
+        NextUpdate { stream: self }
+    }
+
+    /// Final image, skipping tile updates. Returns none if the render was cancelled or failed.
+    pub async fn image(mut self) -> Option<Film> {
+        while let Some(update) = self.next_update().await {
+            if let RenderUpdate::Done(film) = update {
+                return Some(film);
+            }
+        }
+        None
+    }
+
+    /// Cancels render, tiles in flight still finish. The stream ends without final image.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+impl Drop for RenderStream {
+    /// Cancels render, as nobody takes its updates anymore.
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+/// Future of next update of render stream.
+pub struct NextUpdate<'a> {
+    /// Stream taking update from.
+    stream: &'a mut RenderStream,
+}
+
+impl Future for NextUpdate<'_> {
+    type Output = Option<RenderUpdate>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let cancelled = self.stream.cancelled.load(Ordering::Relaxed);
+        let mut shared = self.stream.shared.lock().unwrap();
+        if let Some(update) = shared.updates.pop_front() {
+            // Images finished just before cancelling are dropped too.
+            let image = matches!(update, RenderUpdate::Done(_));
+            Poll::Ready((!(image && cancelled)).then_some(update))
+        } else if shared.finished {
+            Poll::Ready(None)
+        } else {
+            shared.waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+/// State of a render job shared between handle and render thread.
+struct Job {
+    /// Whether render is paused.
+    paused: Mutex<bool>,
+
+    /// Signalled when render is resumed or cancelled.
+    resumed: Condvar,
+
+    /// Whether render is cancelled.
+    cancelled: AtomicBool,
+
+    /// Count of rendered pixels.
+    rendered: AtomicU64,
+
+    /// Path statistics of rendered tiles.
+    stats: Mutex<RenderStats>,
+
+    /// Image with rendered tiles, others black.
+    image: Mutex<Film>,
+}
+
+/// Handle of a render on a background thread, see `Camera::start_render`. The render runs on a thread pool of its
+/// own. Pausing lets tiles in flight finish, then holds the pool's threads until resumed, leaving the global rayon
+/// pool free. Dropping the handle cancels the render.
+pub struct RenderHandle {
+    /// State shared with render thread.
+    job: Arc<Job>,
+
+    /// Count of pixels of image.
+    pixels: u64,
+
+    /// Render thread, returning final image unless cancelled.
+    thread: Option<JoinHandle<Option<Film>>>,
+}
+
+impl RenderHandle {
+    /// Starts rendering scene with camera and settings on a new thread.
+    pub(crate) fn spawn(mut camera: Camera, scene: Arc<Scene>, settings: RenderSettings) -> Self {
+        let (width, height) = camera.resolution();
+        let job = Arc::new(Job {
+            paused: Mutex::new(false),
+            resumed: Condvar::new(),
+            cancelled: AtomicBool::new(false),
+            rendered: AtomicU64::new(0),
+            stats: Mutex::new(RenderStats::default()),
+            image: Mutex::new(Film::new(width, height, settings.film_precision())),
+        });
+        let thread_job = job.clone();
+        let thread = thread::spawn(move || {
+            let job = thread_job;
+
+            // Render on a pool of its own, so workers held while paused do not stall other rayon work.
+            let pool = ThreadPoolBuilder::new()
+                .thread_name(|i| format!("eden-render-{}", i))
+                .build()
+                .ok()?;
+            let film = pool.install(|| {
+                camera.render_observed_with_stats(&scene, &settings, &job.stats, |tile, pixels| {
+                    {
+                        let mut image = job.image.lock().unwrap();
+                        for (i, &color) in pixels.iter().enumerate() {
+                            let (x, y) = (
+                                tile.x + i as u32 % tile.width,
+                                tile.y + i as u32 / tile.width,
+                            );
+                            image.set_pixel(x, y, color);
+                        }
+                    }
+                    job.rendered
+                        .fetch_add(tile.pixel_count() as u64, Ordering::Relaxed);
+
+                    // Hold worker while paused.
+                    let mut paused = job.paused.lock().unwrap();
+                    while *paused && !job.cancelled.load(Ordering::Relaxed) {
+                        paused = job.resumed.wait(paused).unwrap();
+                    }
+                    !job.cancelled.load(Ordering::Relaxed)
+                })
+            });
+            (!job.cancelled.load(Ordering::Relaxed)).then_some(film)
+        });
+        RenderHandle {
+            job,
+            pixels: width as u64 * height as u64,
+            thread: Some(thread),
+        }
+    }
+
+    /// Pauses render after tiles in flight.
+    pub fn pause(&self) {
+        *self.job.paused.lock().unwrap() = true;
+    }
+
+    /// Resumes paused render.
+    pub fn resume(&self) {
+        *self.job.paused.lock().unwrap() = false;
+        self.job.resumed.notify_all();
+    }
+
+    /// Whether render is paused.
+    pub fn is_paused(&self) -> bool {
+        *self.job.paused.lock().unwrap()
+    }
+
+    /// Cancels render, also if paused. Tiles in flight still finish.
+    pub fn cancel(&self) {
+        self.job.cancelled.store(true, Ordering::Relaxed);
+        let _paused = self.job.paused.lock().unwrap();
+        self.job.resumed.notify_all();
+    }
+
+    /// Whether render thread has exited, i.e. render finished or was cancelled.
+    pub fn is_finished(&self) -> bool {
+        self.thread.as_ref().is_none_or(|t| t.is_finished())
+    }
+
+    /// Fraction of rendered pixels in [0,1].
+    pub fn progress(&self) -> f32 {
+        self.job.rendered.load(Ordering::Relaxed) as f32 / self.pixels as f32
+    }
+
+    /// Snapshot of path statistics of rendered tiles.
+    pub fn stats(&self) -> RenderStats {
+        self.job.stats.lock().unwrap().clone()
+    }
+
+    /// Snapshot of image with rendered tiles, tiles not yet rendered are black.
+    pub fn image(&self) -> Film {
+        self.job.image.lock().unwrap().clone()
+    }
+
+    /// Blocks until render finishes. Returns final image, or none if cancelled or failed.
+    pub fn wait(mut self) -> Option<Film> {
+        self.thread.take()?.join().ok().flatten()
+    }
+}
+
+impl Drop for RenderHandle {
+    /// Cancels render, as nobody can wait for it anymore.
+    fn drop(&mut self) {
+        if self.thread.is_some() {
+            self.cancel();
+        }
+    }
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        base::{material::Material, point::Point3f, shape::Shape},
+        materials::lambert::Lambert,
+        shapes::sphere::Sphere,
+    };
+    use rayon::prelude::*;
+    use std::task::Wake;
+
+    /// Waker unparking the thread blocked on a future.
+    struct ThreadWaker(thread::Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    /// Minimal executor polling future on current thread until ready.
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = std::pin::pin!(future);
+        let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(output) => return output,
+                Poll::Pending => thread::park(),
+            }
+        }
+    }
+
+    #[test]
+    fn render_async() {
+        let mut scene = Scene::new();
+        let material = Material::Lambert(Lambert::new(Color3f::new(0.5, 0.5, 0.5)));
+        scene.add(Shape::Sphere(Sphere::new(
+            Point3f::default(),
+            0.5,
+            material,
+        )));
+        let scene = Arc::new(scene);
+        let mut camera = Camera::new(16, 16);
+        camera.set_look_from(Point3f::new(0.0, 0.0, 2.0));
+        let mut settings = RenderSettings::default();
+        settings.set_tile_size(4);
+        let full = camera.render(&scene, &settings);
+
+        // All tiles arrive before final image, then stream ends.
+        let mut stream = camera.render_async(scene.clone(), &settings);
+        let (tiles, film) = block_on(async {
+            let mut tiles = 0;
+            loop {
+                match stream.next_update().await {
+                    Some(RenderUpdate::Tile { pixels, .. }) => {
+                        assert_eq!(pixels.len(), 16);
+                        tiles += 1;
+                    }
+                    Some(RenderUpdate::Done(film)) => {
+                        assert_eq!(stream.next_update().await, None);
+                        return (tiles, film);
+                    }
+                    None => panic!("render ended without image"),
+                }
+            }
+        });
+        assert_eq!(tiles, 16);
+        assert_eq!(film, full);
+        assert_eq!(
+            block_on(camera.render_async(scene.clone(), &settings).image()),
+            Some(full)
+        );
+
+        // Cancelled renders end without image.
+        let stream = camera.render_async(scene, &settings);
+        stream.cancel();
+        assert_eq!(block_on(stream.image()), None);
+    }
+
+    #[test]
+    fn render_handle() {
+        let mut scene = Scene::new();
+        let material = Material::Lambert(Lambert::new(Color3f::new(0.5, 0.5, 0.5)));
+        scene.add(Shape::Sphere(Sphere::new(
+            Point3f::default(),
+            0.5,
+            material,
+        )));
+        let scene = Arc::new(scene);
+        let mut camera = Camera::new(64, 64);
+        camera.set_look_from(Point3f::new(0.0, 0.0, 2.0));
+        let mut settings = RenderSettings::new(64);
+        settings.set_tile_size(4);
+        let full = camera.render(&scene, &settings);
+
+        // Paused render holds still after tiles in flight.
+        let handle = camera.start_render(scene.clone(), &settings);
+        handle.pause();
+        assert!(handle.is_paused());
+        thread::sleep(std::time::Duration::from_millis(100));
+        let progress = handle.progress();
+        thread::sleep(std::time::Duration::from_millis(50));
+        assert!(progress < 1.0 && handle.progress() == progress && !handle.is_finished());
+
+        // Paused render leaves global pool free.
+        let sum: u32 = (0..1000u32).into_par_iter().sum();
+        assert_eq!(sum, 499500);
+
+        // Resumed render finishes with all paths counted.
+        handle.resume();
+        let image = handle.wait().unwrap();
+        assert_eq!(image, full);
+        let handle = camera.start_render(scene.clone(), &settings);
+        while !handle.is_finished() {
+            thread::sleep(std::time::Duration::from_millis(10));
+        }
+        assert_eq!(handle.progress(), 1.0);
+        assert_eq!(handle.stats().paths(), 64 * 64 * 64);
+        assert_eq!(handle.image(), full);
+
+        // Cancelling releases paused render without image.
+        let handle = camera.start_render(scene, &settings);
+        handle.pause();
+        handle.cancel();
+        assert_eq!(handle.wait(), None);
+    }
+}
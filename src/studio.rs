@@ -0,0 +1,126 @@
+use crate::{
+    background::Background,
+    base::{
+        color::Color3f,
+        material::Material,
+        point::Point3f,
+        shape::{Bounded, Shape},
+        vector::Vector3f,
+    },
+    camera::Camera,
+    materials::{diffuse_light::DiffuseLight, shadow_catcher::ShadowCatcher},
+    scene::Scene,
+    shapes::sphere::Sphere,
+};
+
+/// Azimuth of camera around model in degrees, counter-clockwise from the z-axis seen from above.
+const CAMERA_AZIMUTH: f32 = 30.0;
+
+/// Elevation of camera above model center in degrees.
+const CAMERA_ELEVATION: f32 = 15.0;
+
+/// Vertical field of view of camera in degrees.
+const CAMERA_VFOV: f32 = 30.0;
+
+/// Key, fill and rim light as azimuth relative to camera and elevation in degrees, with emitted radiance.
+const LIGHTS: [(f32, f32, f32); 3] = [(45.0, 40.0, 24.0), (-60.0, 15.0, 8.0), (180.0, 50.0, 16.0)];
+
+/// Distance of lights from model center, relative to model radius.
+const LIGHT_DISTANCE: f32 = 4.0;
+
+/// Radius of lights, relative to model radius.
+const LIGHT_RADIUS: f32 = 0.5;
+
+/// Sets up studio for product shots around objects of scene: an invisible ground below the objects catching their
+/// shadows, key, fill and rim light, a neutral gray backdrop, and the camera framing the objects from the front
+/// above. Light positions and sizes scale with the objects' bounding sphere.
+pub fn setup(scene: &mut Scene, camera: &mut Camera) {
+    // Bounding sphere of objects.
+    let bounds = scene
+        .objects()
+        .iter()
+        .map(Shape::bounding_box)
+        .reduce(|a, b| a.union(&b))
+        .expect("studio requires objects");
+    let center = bounds.min() + 0.5 * bounds.size();
+    let radius = 0.5 * bounds.size().length();
+
+    // Shadow catcher ground, huge sphere touching objects from below.
+    let catcher = Material::ShadowCatcher(ShadowCatcher::new(radius, 0.8));
+    let ground = Point3f::new(center.x(), bounds.min().y() - 1000.0 * radius, center.z());
+    scene.add(Shape::Sphere(Sphere::new(ground, 1000.0 * radius, catcher)));
+
+    // Three-point lighting.
+    for (azimuth, elevation, radiance) in LIGHTS {
+        let position =
+            center + LIGHT_DISTANCE * radius * direction(CAMERA_AZIMUTH + azimuth, elevation);
+        let emission = Color3f::white() * radiance;
+        let light = Material::DiffuseLight(DiffuseLight::new(emission));
+        scene.add(Shape::Sphere(Sphere::new(
+            position,
+            LIGHT_RADIUS * radius,
+            light,
+        )));
+    }
+
+    // Backdrop, lighter towards the floor.
+    scene.set_background(Background::Gradient {
+        bottom: Color3f::new(0.8, 0.8, 0.8),
+        top: Color3f::new(0.3, 0.3, 0.3),
+    });
+
+    // Frame bounding sphere.
+    let distance = 1.1 * radius / (0.5 * CAMERA_VFOV).to_radians().sin();
+    camera.set_vfov(CAMERA_VFOV);
+    camera.set_look_from(center + distance * direction(CAMERA_AZIMUTH, CAMERA_ELEVATION));
+    camera.set_look_at(center);
+    camera.set_view_up(Vector3f::new(0.0, 1.0, 0.0));
+    camera.set_defocus_angle(0.0);
+    camera.set_focus_distance(distance);
+}
+
+/// Unit direction with azimuth counter-clockwise from the z-axis seen from above and elevation, both in degrees.
+fn direction(azimuth: f32, elevation: f32) -> Vector3f {
+    let (azimuth, elevation) = (azimuth.to_radians(), elevation.to_radians());
+    Vector3f::new(
+        azimuth.sin() * elevation.cos(),
+        elevation.sin(),
+        azimuth.cos() * elevation.cos(),
+    )
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::materials::lambert::Lambert;
+
+    #[test]
+    fn setup() {
+        let mut scene = Scene::new();
+        let model = Material::Lambert(Lambert::new(Color3f::white()));
+        scene.add(Shape::preset("ball", model).unwrap());
+        let mut camera = Camera::new(16, 9);
+        super::setup(&mut scene, &mut camera);
+
+        // Model, catcher and three lights above catcher.
+        assert_eq!(scene.objects().len(), 5);
+        assert_eq!(scene.lights().len(), 3);
+        let catcher = &scene.objects()[1];
+        assert!(matches!(catcher.material(), Material::ShadowCatcher(_)));
+        assert!(catcher.bounding_box().max().y().abs() < 1e-3);
+        for &light in scene.lights() {
+            assert!(scene.objects()[light].bounding_box().min().y() > 0.0);
+        }
+        assert!(matches!(scene.background(), Background::Gradient { .. }));
+    }
+
+    #[test]
+    fn direction() {
+        let front = super::direction(0.0, 0.0);
+        assert!((front - Vector3f::new(0.0, 0.0, 1.0)).length() < 1e-6);
+        let up = super::direction(90.0, 90.0);
+        assert!((up - Vector3f::new(0.0, 1.0, 0.0)).length() < 1e-6);
+        assert!((super::direction(30.0, 40.0).length() - 1.0).abs() < 1e-6);
+    }
+}
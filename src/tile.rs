@@ -0,0 +1,252 @@
+/// Order in which image tiles are scheduled for rendering.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum TileOrder {
+    /// Row by row, starting at top left.
+    #[default]
+    Scanline,
+
+    /// Outwards from image center.
+    Spiral,
+
+    /// Along Hilbert curve for spatial coherence.
+    Hilbert,
+}
+
+/// Rectangular region of image.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Tile {
+    /// Left pixel column.
+    pub x: u32,
+
+    /// Top pixel row.
+    pub y: u32,
+
+    /// Width in pixels.
+    pub width: u32,
+
+    /// Height in pixels.
+    pub height: u32,
+}
+
+impl Tile {
+    /// Count of pixels in tile.
+    pub fn pixel_count(&self) -> usize {
+        (self.width * self.height) as usize
+    }
+
+    /// Whether tile contains pixel x,y.
+    pub fn contains(&self, x: u32, y: u32) -> bool {
+        self.x <= x && x < self.x + self.width && self.y <= y && y < self.y + self.height
+    }
+
+    /// Whether tile overlaps other tile.
+    pub fn intersects(&self, other: &Tile) -> bool {
+        self.x < other.x + other.width
+            && other.x < self.x + self.width
+            && self.y < other.y + other.height
+            && other.y < self.y + self.height
+    }
+}
+
+/// Splits image into square tiles (clipped at image borders) in given order.
+pub fn tiles(image_width: u32, image_height: u32, tile_size: u32, order: TileOrder) -> Vec<Tile> {
+    assert!(tile_size > 0);
+    let tiles_x = image_width.div_ceil(tile_size);
+    let tiles_y = image_height.div_ceil(tile_size);
+
+    // Tile grid coordinates in scanline order.
+    let mut grid: Vec<(u32, u32)> = (0..tiles_y)
+        .flat_map(|ty| (0..tiles_x).map(move |tx| (tx, ty)))
+        .collect();
+
+    // Reorder grid.
+    match order {
+        TileOrder::Scanline => {}
+        TileOrder::Spiral => {
+            let cx = (tiles_x as f32 - 1.0) / 2.0;
+            let cy = (tiles_y as f32 - 1.0) / 2.0;
+            let key = |&(tx, ty): &(u32, u32)| {
+                let dx = tx as f32 - cx;
+                let dy = ty as f32 - cy;
+                (dx.abs().max(dy.abs()), dy.atan2(dx))
+            };
+            grid.sort_by(|a, b| key(a).partial_cmp(&key(b)).unwrap());
+        }
+        TileOrder::Hilbert => {
+            let n = tiles_x.max(tiles_y).next_power_of_two();
+            grid.sort_by_key(|&(tx, ty)| hilbert_index(n, tx, ty));
+        }
+    }
+
+    // Convert to pixel regions.
+    grid.into_iter()
+        .map(|(tx, ty)| {
+            let x = tx * tile_size;
+            let y = ty * tile_size;
+            Tile {
+                x,
+                y,
+                width: tile_size.min(image_width - x),
+                height: tile_size.min(image_height - y),
+            }
+        })
+        .collect()
+}
+
+/// Distance of cell x,y along Hilbert curve filling n*n grid (n power of two).
+fn hilbert_index(n: u32, mut x: u32, mut y: u32) -> u64 {
+    let mut d = 0;
+    let mut s = n / 2;
+    while s > 0 {
+        let rx = u32::from(x & s > 0);
+        let ry = u32::from(y & s > 0);
+        d += s as u64 * s as u64 * ((3 * rx) ^ ry) as u64;
+
+        // Rotate quadrant.
+        if ry == 0 {
+            if rx == 1 {
+                x = n - 1 - x;
+                y = n - 1 - y;
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        s /= 2;
+    }
+    d
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn coverage() {
+        for order in [TileOrder::Scanline, TileOrder::Spiral, TileOrder::Hilbert] {
+            let t = tiles(100, 70, 32, order);
+            assert_eq!(t.len(), 12);
+
+            // Every pixel covered exactly once.
+            let mut covered = vec![0; 100 * 70];
+            for tile in t.iter() {
+                for y in tile.y..tile.y + tile.height {
+                    for x in tile.x..tile.x + tile.width {
+                        covered[(y * 100 + x) as usize] += 1;
+                    }
+                }
+            }
+            assert!(covered.iter().all(|&c| c == 1));
+        }
+    }
+
+    #[test]
+    fn order() {
+        let scanline = tiles(64, 64, 16, TileOrder::Scanline);
+        assert_eq!(
+            scanline[0],
+            Tile {
+                x: 0,
+                y: 0,
+                width: 16,
+                height: 16
+            }
+        );
+        assert_eq!(
+            scanline[1],
+            Tile {
+                x: 16,
+                y: 0,
+                width: 16,
+                height: 16
+            }
+        );
+        assert_eq!(
+            scanline[4],
+            Tile {
+                x: 0,
+                y: 16,
+                width: 16,
+                height: 16
+            }
+        );
+
+        // Spiral starts at center ring.
+        let spiral = tiles(48, 48, 16, TileOrder::Spiral);
+        assert_eq!(
+            spiral[0],
+            Tile {
+                x: 16,
+                y: 16,
+                width: 16,
+                height: 16
+            }
+        );
+
+        // Consecutive Hilbert tiles are neighbors.
+        let hilbert = tiles(64, 64, 16, TileOrder::Hilbert);
+        assert_eq!(
+            hilbert[0],
+            Tile {
+                x: 0,
+                y: 0,
+                width: 16,
+                height: 16
+            }
+        );
+        for pair in hilbert.windows(2) {
+            let dx = pair[0].x.abs_diff(pair[1].x);
+            let dy = pair[0].y.abs_diff(pair[1].y);
+            assert_eq!(dx + dy, 16);
+        }
+    }
+
+    #[test]
+    fn clipping() {
+        let t = tiles(10, 5, 8, TileOrder::Scanline);
+        assert_eq!(
+            t[0],
+            Tile {
+                x: 0,
+                y: 0,
+                width: 8,
+                height: 5
+            }
+        );
+        assert_eq!(
+            t[1],
+            Tile {
+                x: 8,
+                y: 0,
+                width: 2,
+                height: 5
+            }
+        );
+        assert_eq!(t[1].pixel_count(), 10);
+    }
+
+    #[test]
+    fn intersects() {
+        let a = Tile {
+            x: 0,
+            y: 0,
+            width: 4,
+            height: 4,
+        };
+        let b = Tile {
+            x: 3,
+            y: 3,
+            width: 2,
+            height: 2,
+        };
+        let c = Tile {
+            x: 4,
+            y: 0,
+            width: 2,
+            height: 2,
+        };
+        assert!(a.intersects(&b) && b.intersects(&a));
+        assert!(!a.intersects(&c) && !c.intersects(&a));
+        assert!(!b.intersects(&c));
+        assert!(a.contains(0, 0) && a.contains(3, 3) && !a.contains(4, 0));
+    }
+}
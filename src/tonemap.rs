@@ -0,0 +1,169 @@
+use crate::base::color::Color3f;
+
+/// Luminance in cd/m² of unit radiance. Emitters, backgrounds and the physical sky share this unit, so a rendered
+/// pixel converts to photometric luminance, e.g. for exposure by exposure value.
+pub const LUMINANCE_PER_RADIANCE: f32 = 10_000.0;
+
+/// Ratio of luminance saturating a sensor to 2^EV at ISO 100 (saturation-based speed, ISO 12232).
+const SATURATION_FACTOR: f32 = 1.2;
+
+/// Operator compressing scene radiance into displayable range [0,1].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ToneMapper {
+    /// Radiance is clipped at 1.
+    #[default]
+    Linear,
+
+    /// Luminance L is compressed to L/(1+L), keeping hue.
+    Reinhard,
+
+    /// Filmic curve fitted to ACES reference rendering transform (Narkowicz 2015).
+    Aces,
+}
+
+impl ToneMapper {
+    /// Maps scene radiance to display range [0,1]. Negative components are treated as zero.
+    pub fn map(&self, color: Color3f) -> Color3f {
+        let positive = |x: f32| x.max(0.0);
+        let color = Color3f::new(
+            positive(color.r()),
+            positive(color.g()),
+            positive(color.b()),
+        );
+        let mapped = match self {
+            ToneMapper::Linear => color,
+            ToneMapper::Reinhard => color / (1.0 + color.luminance()),
+            ToneMapper::Aces => {
+                let curve = |x: f32| (x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14);
+                Color3f::new(curve(color.r()), curve(color.g()), curve(color.b()))
+            }
+        };
+        let clamp = |x: f32| x.min(1.0);
+        Color3f::new(clamp(mapped.r()), clamp(mapped.g()), clamp(mapped.b()))
+    }
+}
+
+/// Post-processing from rendered radiance to 8-bit sRGB pixels: exposure, tone mapping and sRGB encoding.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ToneMapping {
+    /// Exposure adjustment in stops (radiance is scaled by 2^exposure).
+    exposure: f32,
+
+    /// Tone mapping operator.
+    mapper: ToneMapper,
+}
+
+impl ToneMapping {
+    /// Creates tone mapping with exposure in stops and operator.
+    pub fn new(exposure: f32, mapper: ToneMapper) -> Self {
+        assert!(exposure.is_finite());
+        ToneMapping { exposure, mapper }
+    }
+
+    /// Creates tone mapping exposing like a camera at exposure value EV at ISO 100, e.g. 15 for sunlit scenes and 7
+    /// for bright interiors: luminance 1.2 * 2^EV cd/m² maps to white. Exposure value combines shutter time t and
+    /// f-number N as log2(N²/t).
+    pub fn from_exposure_value(exposure_value: f32, mapper: ToneMapper) -> Self {
+        let white = SATURATION_FACTOR * exposure_value.exp2();
+        ToneMapping::new((LUMINANCE_PER_RADIANCE / white).log2(), mapper)
+    }
+
+    /// Exposure adjustment in stops.
+    pub fn exposure(&self) -> f32 {
+        self.exposure
+    }
+
+    /// Exposure value at ISO 100 a camera exposes with, see `from_exposure_value`.
+    pub fn exposure_value(&self) -> f32 {
+        (LUMINANCE_PER_RADIANCE / SATURATION_FACTOR).log2() - self.exposure
+    }
+
+    /// Tone mapping operator.
+    pub fn mapper(&self) -> ToneMapper {
+        self.mapper
+    }
+
+    /// Maps scene radiance to display-referred linear color in [0,1].
+    pub fn apply(&self, color: Color3f) -> Color3f {
+        self.mapper.map(color * self.exposure.exp2())
+    }
+
+    /// Maps scene radiance to 8-bit sRGB encoded components.
+    pub fn encode(&self, color: Color3f) -> [u8; 3] {
+        let c = self.apply(color);
+        let quantize = |x: f32| (srgb_encode(x) * 255.0).round() as u8;
+        [quantize(c.r()), quantize(c.g()), quantize(c.b())]
+    }
+}
+
+/// sRGB transfer function (IEC 61966-2-1) of linear value in [0,1].
+pub fn srgb_encode(x: f32) -> f32 {
+    if x <= 0.0031308 {
+        12.92 * x
+    } else {
+        1.055 * x.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Inverse sRGB transfer function of encoded value in [0,1], e.g. of 8-bit palette colors.
+pub fn srgb_decode(x: f32) -> f32 {
+    if x <= 0.04045 {
+        x / 12.92
+    } else {
+        ((x + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mappers() {
+        let gray = |x: f32| Color3f::new(x, x, x);
+        for mapper in [ToneMapper::Linear, ToneMapper::Reinhard, ToneMapper::Aces] {
+            assert_eq!(mapper.map(Color3f::black()), Color3f::black());
+            assert_eq!(mapper.map(gray(-1.0)), Color3f::black());
+            assert!(mapper.map(gray(1e6)).g() <= 1.0);
+        }
+        assert_eq!(ToneMapper::Linear.map(gray(2.0)), Color3f::white());
+        assert!((ToneMapper::Reinhard.map(gray(1.0)).g() - 0.5).abs() < 1e-6);
+        assert!(ToneMapper::Reinhard.map(gray(1e6)).g() < 1.0);
+        assert!((ToneMapper::Aces.map(gray(0.18)).g() - 0.267).abs() < 0.001);
+        assert_eq!(ToneMapper::Aces.map(gray(100.0)), Color3f::white());
+    }
+
+    #[test]
+    fn encode() {
+        assert_eq!(srgb_encode(0.0), 0.0);
+        assert!((srgb_encode(1.0) - 1.0).abs() < 1e-6);
+        assert!((srgb_encode(0.5) - 0.7354).abs() < 1e-4);
+        assert!((srgb_encode(0.002) - 0.02584).abs() < 1e-6);
+        for x in [0.0, 0.002, 0.2, 0.5, 1.0] {
+            assert!((srgb_decode(srgb_encode(x)) - x).abs() < 1e-6);
+        }
+
+        let tone_mapping = ToneMapping::default();
+        assert_eq!(
+            tone_mapping.encode(Color3f::new(0.0, 0.5, 1.0)),
+            [0, 188, 255]
+        );
+        let darker = ToneMapping::new(-1.0, ToneMapper::Linear);
+        assert_eq!(darker.encode(Color3f::new(0.0, 1.0, 2.0)), [0, 188, 255]);
+    }
+
+    #[test]
+    fn exposure_value() {
+        // Saturating luminance maps to white, one EV more halves brightness.
+        let ev = 10.0;
+        let tone_mapping = ToneMapping::from_exposure_value(ev, ToneMapper::Linear);
+        let white = 1.2 * 2f32.powf(ev) / LUMINANCE_PER_RADIANCE;
+        let gray = |x: f32| Color3f::new(x, x, x);
+        assert!((tone_mapping.apply(gray(white)).g() - 1.0).abs() < 1e-5);
+        let brighter = ToneMapping::from_exposure_value(ev + 1.0, ToneMapper::Linear);
+        assert!((brighter.apply(gray(white)).g() - 0.5).abs() < 1e-5);
+        assert!((tone_mapping.exposure_value() - ev).abs() < 1e-5);
+        assert!((ToneMapping::default().exposure_value() - 13.02).abs() < 0.01);
+    }
+}
@@ -0,0 +1,166 @@
+use crate::{base::shape::Sampleable, scene::Scene};
+use rand::{rngs::StdRng, SeedableRng};
+
+/// Pairwise visibility between tagged objects of a scene, as fraction of mutually visible point pairs on their
+/// surfaces. Precomputed for gameplay and AI systems, e.g. line of sight between rooms or cover spots.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VisibilityMatrix {
+    /// Tags of objects, in order of rows and columns.
+    tags: Vec<String>,
+
+    /// Point pairs cast per pair of objects.
+    samples: u32,
+
+    /// Visibility in [0,1] in row-major order, symmetric with ones on the diagonal.
+    visibility: Vec<f32>,
+}
+
+impl VisibilityMatrix {
+    /// Computes visibility between objects given by tag and index into scene, casting rays between point pairs
+    /// sampled uniformly on both surfaces. All scene objects occlude, including the pair itself, so points facing
+    /// away from the other object count as hidden. Random numbers are drawn from seed, so results are reproducible.
+    pub fn compute(scene: &Scene, tagged: &[(&str, usize)], samples: u32, seed: u64) -> Self {
+        assert!(samples > 0);
+        let n = tagged.len();
+        let mut visibility = vec![1.0; n * n];
+        let objects = scene.objects();
+        for i in 0..n {
+            for j in i + 1..n {
+                let mut rng = StdRng::seed_from_u64(seed.wrapping_add((i * n + j) as u64));
+                let (a, b) = (&objects[tagged[i].1], &objects[tagged[j].1]);
+                let visible = (0..samples)
+                    .filter(|_| {
+                        let p = a.sample_point(&mut rng).point;
+                        let q = b.sample_point(&mut rng).point;
+                        scene.line_of_sight(p, q)
+                    })
+                    .count();
+                let v = visible as f32 / samples as f32;
+                visibility[i * n + j] = v;
+                visibility[j * n + i] = v;
+            }
+        }
+        VisibilityMatrix {
+            tags: tagged.iter().map(|(tag, _)| tag.to_string()).collect(),
+            samples,
+            visibility,
+        }
+    }
+
+    /// Tags of objects, in order of rows and columns.
+    pub fn tags(&self) -> &[String] {
+        &self.tags
+    }
+
+    /// Point pairs cast per pair of objects.
+    pub fn samples(&self) -> u32 {
+        self.samples
+    }
+
+    /// Visibility in [0,1] between objects at indices into tags. Objects see themselves fully.
+    pub fn get(&self, i: usize, j: usize) -> f32 {
+        self.visibility[i * self.tags.len() + j]
+    }
+
+    /// Matrix as CSV table, with tags as header row and first column.
+    pub fn to_csv(&self) -> String {
+        let tags: Vec<String> = self.tags.iter().map(|t| csv_field(t)).collect();
+        let mut csv = format!(",{}\n", tags.join(","));
+        for (i, tag) in tags.iter().enumerate() {
+            let row: Vec<String> = (0..tags.len())
+                .map(|j| self.get(i, j).to_string())
+                .collect();
+            csv += &format!("{},{}\n", tag, row.join(","));
+        }
+        csv
+    }
+
+    /// Matrix as JSON object with tags, samples and nested rows.
+    pub fn to_json(&self) -> String {
+        let tags: Vec<String> = self.tags.iter().map(|t| json_string(t)).collect();
+        let rows: Vec<String> = (0..self.tags.len())
+            .map(|i| {
+                let row: Vec<String> = (0..self.tags.len())
+                    .map(|j| self.get(i, j).to_string())
+                    .collect();
+                format!("[{}]", row.join(","))
+            })
+            .collect();
+        format!(
+            "{{\"tags\":[{}],\"samples\":{},\"visibility\":[{}]}}",
+            tags.join(","),
+            self.samples,
+            rows.join(",")
+        )
+    }
+}
+
+/// CSV field, quoted if it contains separators, quotes or line breaks.
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+/// Quoted JSON string with escapes.
+fn json_string(s: &str) -> String {
+    let mut json = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => json += "\\\"",
+            '\\' => json += "\\\\",
+            c if (c as u32) < 0x20 => json += &format!("\\u{:04x}", c as u32),
+            c => json.push(c),
+        }
+    }
+    json + "\""
+}
+
+/// Unit tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        base::{material::Material, point::Point3f, shape::Shape},
+        shapes::sphere::Sphere,
+    };
+
+    #[test]
+    fn compute() {
+        // Wall sphere hides left from right one, top one sees both partially.
+        let mut scene = Scene::new();
+        let sphere =
+            |x, y, r| Shape::Sphere(Sphere::new(Point3f::new(x, y, 0.0), r, Material::None));
+        scene.add(sphere(-4.0, 0.0, 0.5));
+        scene.add(sphere(0.0, 0.0, 2.0));
+        scene.add(sphere(4.0, 0.0, 0.5));
+        scene.add(sphere(0.0, 8.0, 0.5));
+        let tagged = [("left", 0), ("right", 2), ("top", 3)];
+        let matrix = VisibilityMatrix::compute(&scene, &tagged, 1000, 42);
+        assert_eq!(matrix.tags(), ["left", "right", "top"]);
+        assert_eq!(matrix.get(0, 0), 1.0);
+        assert_eq!(matrix.get(0, 1), 0.0);
+        assert_eq!(matrix.get(0, 2), matrix.get(2, 0));
+        assert!(matrix.get(0, 2) > 0.1 && matrix.get(0, 2) < 0.5);
+        assert_eq!(matrix, VisibilityMatrix::compute(&scene, &tagged, 1000, 42));
+    }
+
+    #[test]
+    fn export() {
+        let matrix = VisibilityMatrix {
+            tags: vec!["a,b".to_string(), "\"c\"".to_string()],
+            samples: 4,
+            visibility: vec![1.0, 0.25, 0.25, 1.0],
+        };
+        assert_eq!(
+            matrix.to_csv(),
+            ",\"a,b\",\"\"\"c\"\"\"\n\"a,b\",1,0.25\n\"\"\"c\"\"\",0.25,1\n"
+        );
+        assert_eq!(
+            matrix.to_json(),
+            "{\"tags\":[\"a,b\",\"\\\"c\\\"\"],\"samples\":4,\"visibility\":[[1,0.25],[0.25,1]]}"
+        );
+    }
+}
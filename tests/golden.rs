@@ -0,0 +1,153 @@
+//! Golden image regression tests: small canonical scenes rendered at fixed seeds and compared against reference
+//! images in `tests/golden` by per-channel root mean square error, catching silent changes of rendered images.
+//!
+//! After intended changes of rendered images, write the references anew by running the tests with `EDEN_BLESS=1`,
+//! and review the images before committing them. Missing references are written as well, failing the test.
+
+use eden::{
+    benchmark::{standard_scene, standard_settings},
+    camera::AspectPolicy,
+    integrators::path_tracer::PathTracer,
+    output::{read_image, Encode, ImageFormat, ImageSink},
+    prelude::*,
+};
+use std::{env, fs, path::Path};
+
+/// Directory of reference images.
+const DIRECTORY: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden");
+
+/// Resolution of golden images.
+const RESOLUTION: (u32, u32) = (48, 27);
+
+/// Samples per pixel of golden images.
+const SAMPLES: u32 = 32;
+
+/// Maximum root mean square error per color channel, of colors compressed to [0,1) so single bright pixels do not
+/// dominate. Far above the differences of floating point rounding between platforms, and below those of noise
+/// rendered with another seed.
+const TOLERANCE: f64 = 0.004;
+
+/// Root mean square error per color channel of image vs. reference of same size, of colors compressed to [0,1).
+fn channel_rmse(image: &Film, reference: &Film) -> [f64; 3] {
+    assert_eq!(
+        (image.width(), image.height()),
+        (reference.width(), reference.height())
+    );
+    let compress = |c: f32| c as f64 / (1.0 + c as f64);
+    let mut squared = [0.0; 3];
+    for (a, b) in image.pixels().zip(reference.pixels()) {
+        let (a, b) = ([a.r(), a.g(), a.b()], [b.r(), b.g(), b.b()]);
+        for channel in 0..3 {
+            squared[channel] += (compress(a[channel]) - compress(b[channel])).powi(2);
+        }
+    }
+    let count = (image.width() * image.height()) as f64;
+    squared.map(|s| (s / count).sqrt())
+}
+
+/// Writes image to reference path in full precision.
+fn write_reference(path: &str, image: &Film) {
+    fs::create_dir_all(DIRECTORY).unwrap();
+    let mut encode = Encode::file(path, ImageFormat::Pfm).unwrap();
+    encode.write_image(image).unwrap();
+}
+
+/// Compares image against reference image with name, or writes it as reference if blessing or missing.
+fn check(name: &str, image: &Film) {
+    let path = format!("{}/{}.pfm", DIRECTORY, name);
+    if env::var_os("EDEN_BLESS").is_some() {
+        write_reference(&path, image);
+        return;
+    }
+    if !Path::new(&path).exists() {
+        write_reference(&path, image);
+        panic!("{}: reference image missing, written anew for review", path);
+    }
+    let reference = read_image(&path).unwrap();
+    let rmse = channel_rmse(image, &reference);
+    assert!(
+        rmse.iter().all(|&e| e <= TOLERANCE),
+        "{}: rmse per channel {:?} above tolerance {}, rerun with EDEN_BLESS=1 if intended",
+        name,
+        rmse,
+        TOLERANCE
+    );
+}
+
+/// Renders standard benchmark scene with name at golden resolution and samples.
+fn render_standard(name: &str) -> Film {
+    let (scene, mut camera) = standard_scene(name).unwrap();
+    camera.set_resolution_override(Some(RESOLUTION), AspectPolicy::default());
+    camera.render(&scene, &standard_settings(SAMPLES))
+}
+
+#[test]
+fn cover() {
+    check("cover", &render_standard("cover"));
+}
+
+#[test]
+fn studio() {
+    check("studio", &render_standard("studio"));
+}
+
+#[test]
+fn materials() {
+    // Row of balls of all material presets on gray ground under the sun.
+    let mut scene = Scene::new();
+    let gray = Material::Lambert(Lambert::new(Color3f::new(0.5, 0.5, 0.5)));
+    scene.add(Shape::preset("ground", gray).unwrap());
+    let count = Material::PRESETS.len();
+    for (i, name) in Material::PRESETS.iter().enumerate() {
+        let x = 2.2 * (i as f32 - 0.5 * (count - 1) as f32);
+        let material = Material::preset(name).unwrap();
+        scene.add(Shape::Sphere(Sphere::new(
+            Point3f::new(x, 1.0, 0.0),
+            1.0,
+            material,
+        )));
+    }
+    let sun = DirectionalLight::new(Vector3f::new(-1.0, -2.0, -1.0), Color3f::white() * 3.0);
+    scene.add_light(Light::Directional(sun));
+
+    let mut camera = Camera::new(RESOLUTION.0 * 2, RESOLUTION.1);
+    camera.set_vfov(20.0);
+    camera.set_look_from(Point3f::new(0.0, 6.0, 40.0));
+    camera.set_look_at(Point3f::new(0.0, 1.0, 0.0));
+    let mut settings = RenderSettings::new(SAMPLES);
+    settings.set_integrator(Integrator::PathTracer(PathTracer::new(16)));
+    check("materials", &camera.render(&scene, &settings));
+}
+
+#[test]
+fn metal_fuzz() {
+    // Scene of image 1.14 of Ray Tracing in One Weekend: lambertian ball between slightly and fully fuzzed metal
+    // balls. Unlike in the reference, scatters below the surface are reflected about the normal, not absorbed.
+    let mut scene = Scene::new();
+    let lambert = |r, g, b| Material::Lambert(Lambert::new(Color3f::new(r, g, b)));
+    let metal = |r, g, b, fuzz| Material::Metal(Metal::new(Color3f::new(r, g, b), fuzz));
+    let balls = [
+        (
+            Point3f::new(0.0, -100.5, -1.0),
+            100.0,
+            lambert(0.8, 0.8, 0.0),
+        ),
+        (Point3f::new(0.0, 0.0, -1.2), 0.5, lambert(0.1, 0.2, 0.5)),
+        (
+            Point3f::new(-1.0, 0.0, -1.0),
+            0.5,
+            metal(0.8, 0.8, 0.8, 0.3),
+        ),
+        (Point3f::new(1.0, 0.0, -1.0), 0.5, metal(0.8, 0.6, 0.2, 1.0)),
+    ];
+    for (center, radius, material) in balls {
+        scene.add(Shape::Sphere(Sphere::new(center, radius, material)));
+    }
+
+    let mut camera = Camera::new(RESOLUTION.0, RESOLUTION.1);
+    camera.set_look_from(Point3f::default());
+    camera.set_look_at(Point3f::new(0.0, 0.0, -1.0));
+    let mut settings = RenderSettings::new(SAMPLES);
+    settings.set_integrator(Integrator::PathTracer(PathTracer::new(50)));
+    check("metal_fuzz", &camera.render(&scene, &settings));
+}